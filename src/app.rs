@@ -18,16 +18,90 @@ use gtk::{
 };
 use gtk4_layer_shell::*;
 use owo_colors::OwoColorize;
-use std::{path::*, sync::Once, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs,
+    path::*,
+    sync::{Arc, Mutex, Once},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    cli::CliConfig, drm::*, frame_controller::*, geometry::*, keyboard_controller::*,
-    mouse_controller::*, preset::*, renderer::*, screen_controller::*, *,
+    audio_spectrum::*,
+    cli::{load_preset_from_file_or_json, CliConfig, CliError},
+    desktop_monitor::*,
+    drm::*,
+    frame_controller::*,
+    geometry::*,
+    history::*,
+    hotkeys::*,
+    hud::*,
+    idle_monitor::*,
+    ipc::*,
+    keyboard_controller::*,
+    media_monitor::*,
+    mouse_controller::*,
+    occlusion_monitor::*,
+    output_power_monitor::*,
+    power_monitor::*,
+    preset::*,
+    renderer::*,
+    resolution_controller::*,
+    screen_controller::*,
+    session_lock::*,
+    shadertoy::{complexity::check_complexity, http},
+    sleep_monitor::*,
+    system_stats::*,
+    *,
 };
 
 /// Interval for checking monitor state during standby.
 const STANDBY_CHECK_INTERVAL: Duration = Duration::from_millis(250);
 
+/// Width of the password entry of the lock screen, in logical pixels.
+const UNLOCK_PROMPT_WIDTH: i32 = 280;
+
+/// Delay before recreating windows after the OpenGL context was lost.
+const CONTEXT_RECOVERY_DELAY: Duration = Duration::from_secs(1);
+
+/// Consecutive context recoveries attempted before giving up.
+const MAX_CONTEXT_RECOVERY_ATTEMPTS: u32 = 3;
+
+/// Interval between updates of the night mode brightness.
+const NIGHT_MODE_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Duration of the crossfade from the last frame of a replaced window
+/// into the output of its new renderer.
+const WINDOW_CROSSFADE_DURATION: Duration = Duration::from_millis(500);
+
+/// Time after which replaced windows are destroyed even if the new
+/// windows have not presented a frame yet.
+const RETIRED_WINDOW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Interval between samples of the system statistics.
+const SYSTEM_STATS_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval for checking whether the shaders of a new renderer are compiled.
+const PENDING_RENDERER_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Interval between logs of the GPU time of each pass with `--profile`.
+const PROFILE_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between checks of whether the frames read back by the
+/// `capture` control command are ready.
+const CAPTURE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Interval between updates of the information overlay with the
+/// progress of a preset loading in the background.
+const LOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time the progress of a preset load remains visible after its last
+/// update.
+const LOAD_PROGRESS_DURATION: Duration = Duration::from_secs(5);
+
+/// Animation time skipped by the time jump hotkeys.
+const HOTKEY_JUMP_DURATION: Duration = Duration::from_secs(5);
+
 /// Ensures that GL function pointers are only loaded once.
 static LOAD_GL: Once = Once::new();
 
@@ -40,14 +114,24 @@ pub struct AppData {
     /// Configuration loaded from CLI arguments.
     pub cli_config: CliConfig,
 
+    /// Preset in effect, i.e., the loaded preset with the
+    /// power profile matching the current power source applied.
+    pub preset: Preset,
+
     /// File change monitor.
     pub preset_monitor: Option<gio::FileMonitor>,
 
+    /// Change monitors of the external shader files of the preset.
+    pub shader_file_monitors: Vec<gio::FileMonitor>,
+
     /// Timer driving frame updates when rendering is
     /// throttled  or during crossfade animation.
     /// At most one animation source is active at a time.
     pub animation_timer: Option<glib::SourceId>,
 
+    /// Tick callbacks driving continuous rendering.
+    pub tick_callbacks: Vec<gtk::TickCallbackId>,
+
     /// Controls logical frame production, timing statistics,
     ///  and crossfade animation.
     pub frame_controller: FrameController,
@@ -64,6 +148,133 @@ pub struct AppData {
     /// Indicates whether the compositor supports the
     /// `zwlr_layer_shell_v1` protocol.
     pub layer_shell_supported: bool,
+
+    /// OpenGL API requested for GL areas, chosen at activation
+    /// depending on what the driver supports.
+    pub gl_api: gdk::GLAPI,
+
+    /// Tracks DRM connectors across hotplugs.
+    /// `None` when no DRM card is accessible.
+    pub drm_monitor: Option<DrmMonitor>,
+
+    /// Watches for fullscreen windows covering the wallpaper.
+    /// `None` when disabled or unsupported by the compositor.
+    pub occlusion_monitor: Option<OcclusionMonitor>,
+
+    /// Watches for outputs powered off by DPMS.
+    /// `None` when unsupported by the compositor.
+    pub output_power_monitor: Option<OutputPowerMonitor>,
+
+    /// Watches for the user going idle, to show the screensaver.
+    /// `None` without a screensaver or when unsupported by the
+    /// compositor.
+    pub idle_monitor: Option<IdleMonitor>,
+
+    /// Preset restored when activity resumes.
+    /// `Some` while the screensaver is shown.
+    pub screensaver_return: Option<PathBuf>,
+
+    /// Lock of the session whose lock screen shows the preset.
+    /// `None` unless running with `--session-lock`.
+    pub session_lock: Option<SessionLock>,
+
+    /// Listens for commands from other processes.
+    /// `None` unless running in daemon mode.
+    pub ipc_server: Option<IpcServer>,
+
+    /// Presets applied so far, navigated with the `next` and `previous`
+    /// commands.
+    pub preset_history: PresetHistory,
+
+    /// Number of preset switches started, used to discard presets
+    /// finishing loading after a later switch was requested.
+    pub preset_switch_count: u64,
+
+    /// Watches the power source.
+    /// `None` when UPower is unavailable.
+    pub power_monitor: Option<PowerMonitor>,
+
+    /// Watches for system suspend and resume.
+    /// `None` when logind is unavailable.
+    pub sleep_monitor: Option<SleepMonitor>,
+
+    /// Global hotkeys registered with `--hotkeys`.
+    /// `None` when disabled or the desktop portal is unavailable.
+    pub hotkeys: Option<Hotkeys>,
+
+    /// Indicates whether the performance HUD is shown, initially set
+    /// with `--hud` and toggled with the `hud` control command.
+    pub hud_visible: bool,
+
+    /// Indicates whether the GPU time of each pass is measured for
+    /// `--profile` or the `profile` control command, regardless of the
+    /// HUD.
+    pub profiling: bool,
+
+    /// Adjusts the resolution scale to hold the `target_fps` of the
+    /// preset. `None` when the preset has no target.
+    pub resolution_controller: Option<ResolutionController>,
+
+    /// Indicates whether the machine is running on battery power.
+    pub on_battery: bool,
+
+    /// Timer updating the night mode brightness.
+    /// `None` when the preset has no night mode.
+    pub night_mode_timer: Option<glib::SourceId>,
+
+    /// Current night mode brightness multiplier.
+    pub dimming: f32,
+
+    /// Timing of the last rendered frame, from which the previous preset
+    /// carries on during a preset transition.
+    pub last_frame_stats: Option<FrameStats>,
+
+    /// Samples system statistics for `iSystemStats`.
+    /// `None` when no pass uses the uniform.
+    pub system_stats: Option<SystemStatsSampler>,
+
+    /// Timer sampling the system statistics.
+    pub system_stats_timer: Option<glib::SourceId>,
+
+    /// Captures the output audio for `iAudioBands`.
+    /// `None` when no pass uses the uniform or capture failed.
+    pub audio_spectrum: Option<AudioSpectrum>,
+
+    /// Watches the compositor for `iDesktop`.
+    /// `None` when unused or the compositor is unsupported.
+    pub desktop_monitor: Option<DesktopMonitor>,
+
+    /// Watches media players for `album_art` inputs and `iTrackProgress`.
+    /// `None` when unused or the session bus is unavailable.
+    pub media_monitor: Option<MediaMonitor>,
+
+    /// Windows replaced by the current ones, kept visible until every
+    /// new area has presented its first frame.
+    pub retired_windows: Vec<gtk::Window>,
+
+    /// Last frames of the replaced windows, by monitor connector,
+    /// waiting to be picked up by the new windows.
+    pub frame_snapshots: HashMap<String, image::RgbaImage>,
+
+    /// Contents of the buffer passes of the replaced windows, by monitor
+    /// connector, when the preset preserves buffers.
+    pub buffer_snapshots: HashMap<String, Vec<PassSnapshot>>,
+
+    /// Number of context recoveries since a renderer was last created.
+    pub context_recovery_attempts: u32,
+
+    /// Indicates whether windows are about to be recreated after
+    /// the OpenGL context was lost.
+    pub context_recovery_pending: bool,
+
+    /// Time the GPU is given to finish the frame of an area before the
+    /// preset is aborted. `None` unless enabled with `--gpu-watchdog` or
+    /// `gpu_watchdog`.
+    pub gpu_watchdog_timeout: Option<Duration>,
+
+    /// Presets aborted by the GPU watchdog, each once with the last time
+    /// it was aborted, reported by the `watchdog` control command.
+    pub gpu_hangs: Vec<(String, String)>,
 }
 
 /// Per-window rendering state attached to each `GLArea`.
@@ -73,6 +284,9 @@ pub struct AreaData {
     /// Renderer.
     pub renderer: Option<Renderer>,
 
+    /// Renderer replacing `renderer` once its shaders are compiled.
+    pub pending_renderer: Option<PendingRenderer>,
+
     /// Name of the monitor connector associated to this area.
     pub connector: String,
 
@@ -81,119 +295,1576 @@ pub struct AreaData {
     /// with x-axis pointing right and y-axis pointing down.
     pub bounds: Rectangle,
 
-    /// Monitor rectangle in global screen space
-    /// (origin at top-left, Y increasing downward).
-    pub gl_offset: Offset,
+    /// Monitor rectangle in global screen space
+    /// (origin at top-left, Y increasing downward).
+    pub gl_offset: Offset,
+
+    /// Optional widget for displaying shader info,
+    /// shown when the area is first rendered.
+    pub info_overlay: Option<gtk::Widget>,
+
+    /// Performance HUD drawn over the area.
+    /// `None` unless enabled with `--hud` or running in daemon mode.
+    pub hud: Option<Hud>,
+
+    /// GL framebuffer size reported by the last resize.
+    pub viewport_size: Size,
+
+    /// Last frame of the window this area replaced, or else the preset
+    /// fallback image, presented until the renderer is ready and then
+    /// faded out.
+    pub frame_snapshot: Option<FrameSnapshot>,
+
+    /// Contents of the buffer passes of the window this area replaced,
+    /// restored into its first renderer when the preset preserves buffers.
+    pub buffer_snapshots: Vec<PassSnapshot>,
+
+    /// Start of the fade out of `frame_snapshot`.
+    pub frame_snapshot_fade_start: Option<Instant>,
+
+    /// Indicates whether the area has presented at least one frame.
+    pub presented: bool,
+
+    /// Indicates whether the output of the area is powered off, in which
+    /// case its renderer is skipped.
+    pub powered_off: bool,
+
+    /// Crossfade from the previous preset, while switching presets.
+    pub transition: Option<PresetTransition>,
+
+    /// Aborts the preset if the GPU does not finish a frame of the area
+    /// in time. Created on the first frame when the watchdog is enabled.
+    pub gpu_watchdog: Option<GpuWatchdog>,
+}
+
+/// Renderer of the previous preset, kept rendering while the renderer of
+/// the new preset is revealed over it.
+pub struct PresetTransition {
+    /// Renderer of the previous preset, once the new renderer is ready.
+    /// Until then, the previous renderer is still the area renderer.
+    pub renderer: Option<Renderer>,
+
+    /// Start of the transition, when the new renderer was installed.
+    pub fade_start: Option<Instant>,
+
+    /// Duration of the transition (`transition_duration` of the new
+    /// preset).
+    pub duration: Duration,
+
+    /// Effect composing both presets. `None` for a plain crossfade.
+    pub effect: Option<TransitionEffect>,
+
+    /// Last frame of the previous preset before the switch.
+    pub frame_stats: FrameStats,
+
+    /// Time of the switch.
+    pub switched_at: Instant,
+}
+
+impl PresetTransition {
+    /// Returns the timing of the frame of the previous preset rendered
+    /// along the given frame of the new preset, so that the previous
+    /// preset carries on from its last frame.
+    fn previous_frame_stats(&self, frame_stats: &FrameStats) -> FrameStats {
+        FrameStats {
+            time: self.frame_stats.time + self.switched_at.elapsed(),
+            frame_number: self.frame_stats.frame_number + 1 + frame_stats.frame_number,
+            ..frame_stats.clone()
+        }
+    }
+
+    /// Returns the progress of the transition, from 0 until the new
+    /// renderer is ready to 1.
+    fn progress(&self) -> f32 {
+        self.fade_start.map_or(0.0, |start| {
+            (start.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+        })
+    }
+}
+
+/// Snapshot of input state supplied to the renderer for one frame.
+pub struct InputData {
+    pub mouse: MouseData,
+    pub keyboard: Option<KeyboardData>,
+    pub system_stats: SystemStats,
+    pub audio_bands: AudioBands,
+    pub desktop: DesktopState,
+    pub media: MediaData,
+}
+
+/// Initializes and runs the GTK application.
+///
+/// Creates global `AppData`, installs signal handlers,
+/// and starts the GTK main loop.
+pub fn run(cli_config: CliConfig) -> glib::ExitCode {
+    // A preview or a lock screen runs alongside the instance showing the
+    // wallpaper
+    let flags = if cli_config.preview || cli_config.session_lock {
+        gio::ApplicationFlags::NON_UNIQUE
+    } else {
+        gio::ApplicationFlags::HANDLES_OPEN
+    };
+    let app = gtk::Application::builder()
+        .application_id(APP_ID)
+        .flags(flags)
+        .build();
+
+    // A second instance hands its preset over to the running one
+    if let Err(err) = app.register(None::<&gio::Cancellable>) {
+        log::warn!("Failed to register the application: {err}");
+    }
+    if app.is_remote() {
+        return forward_to_running_instance(&app, cli_config.preset_path.as_deref());
+    }
+
+    set_data!(
+        app,
+        AppData {
+            areas: Vec::default(),
+            preset: cli_config.preset.clone(),
+            gpu_watchdog_timeout: cli_config.gpu_watchdog,
+            cli_config,
+            preset_monitor: None,
+            shader_file_monitors: Vec::default(),
+            animation_timer: None,
+            tick_callbacks: Vec::default(),
+            frame_controller: FrameController::default(),
+            mouse_controller: MouseController::new(app.clone()),
+            keyboard_controller: KeyboardController::new(app.clone()),
+            screen_controller: ScreenController::default(),
+            layer_shell_supported: false,
+            gl_api: gdk::GLAPI::GL,
+            drm_monitor: None,
+            occlusion_monitor: None,
+            output_power_monitor: None,
+            idle_monitor: None,
+            screensaver_return: None,
+            session_lock: None,
+            ipc_server: None,
+            preset_history: PresetHistory::default(),
+            preset_switch_count: 0,
+            power_monitor: None,
+            sleep_monitor: None,
+            hotkeys: None,
+            hud_visible: false,
+            profiling: false,
+            resolution_controller: None,
+            on_battery: false,
+            night_mode_timer: None,
+            dimming: 1.0,
+            last_frame_stats: None,
+            system_stats: None,
+            system_stats_timer: None,
+            audio_spectrum: None,
+            desktop_monitor: None,
+            media_monitor: None,
+            retired_windows: Vec::new(),
+            frame_snapshots: HashMap::new(),
+            buffer_snapshots: HashMap::new(),
+            context_recovery_attempts: 0,
+            context_recovery_pending: false,
+            gpu_hangs: Vec::new(),
+        }
+    );
+
+    let app_data = get_data!(app, AppData, as_mut());
+    app_data.hud_visible = app_data.cli_config.hud;
+
+    if let Some(path) = &app_data.cli_config.preset_path {
+        setup_preset_monitor(&app, path, on_preset_change);
+        app_data.preset_history.push(path);
+    }
+    if app_data.cli_config.daemon {
+        setup_daemon(&app);
+    }
+    setup_shader_file_monitors(
+        &app,
+        &app_data.cli_config.preset.shader_files(),
+        on_shader_file_change,
+    );
+
+    app.connect_activate(activate);
+    app.connect_open(on_open);
+    app.run_with_args(&[""])
+}
+
+/// Asks the running instance to switch to the preset at `path`.
+///
+/// Without a preset, the running instance is left as is, which is not
+/// an error.
+fn forward_to_running_instance(app: &gtk::Application, path: Option<&Path>) -> glib::ExitCode {
+    let Some(path) = path else {
+        log::info!("{APP_NAME} is already running");
+        return glib::ExitCode::SUCCESS;
+    };
+
+    log::info!("Sending {} to the running instance", path.display());
+    app.open(&[gio::File::for_path(path)], "");
+
+    // The request is sent asynchronously
+    if let Some(connection) = app.dbus_connection() {
+        if let Err(err) = connection.flush_sync(None::<&gio::Cancellable>) {
+            log::error!("Failed to reach the running instance: {err}");
+            return glib::ExitCode::FAILURE;
+        }
+    }
+    glib::ExitCode::SUCCESS
+}
+
+/// Switches to the preset sent by another invocation of the program.
+///
+/// While the screensaver is shown, the preset replaces the one restored
+/// when activity resumes instead.
+fn on_open(app: &gtk::Application, files: &[gio::File], _hint: &str) {
+    let Some(path) = files.first().and_then(|file| file.path()) else {
+        return;
+    };
+
+    let app_data = get_data!(app, AppData, as_mut());
+    if let Some(screensaver_return) = &mut app_data.screensaver_return {
+        log::info!("Showing {} after the screensaver", path.display());
+        *screensaver_return = path;
+        return;
+    }
+
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        if let Err(err) = switch_preset(&app, &path).await {
+            log::warn!("{err}");
+        }
+    });
+}
+
+/// Reloads preset from the given file and applies it if it has changed.
+fn on_preset_change(app: &gtk::Application, preset_path: &Path) {
+    match Preset::from_file(preset_path) {
+        Ok(new_preset) => {
+            let app_data = get_data!(app, AppData, as_mut());
+
+            if new_preset != app_data.cli_config.preset {
+                log::info!("Applying updated preset");
+                let shader_changes = app_data.cli_config.preset.shader_changes(&new_preset);
+                apply_preset(app, new_preset);
+
+                // Shader-only edits are applied without recreating windows
+                match shader_changes {
+                    Some(shader_changes) => reload_passes(app, &shader_changes),
+                    None => on_monitor_changed(app.clone()),
+                }
+            } else {
+                log::info!("Preset unchanged after reload");
+            }
+        }
+        Err(err) => log::error!("Error reloading preset: {err}"),
+    }
+}
+
+/// Replaces the loaded preset and restarts the services it configures.
+///
+/// Windows are not recreated.
+fn apply_preset(app: &gtk::Application, preset: Preset) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let shader_files = preset.shader_files();
+    app_data.cli_config.preset = preset;
+    update_active_preset(app);
+    setup_night_mode(app);
+    setup_uniform_sources(app);
+    setup_resolution_controller(app);
+    setup_shader_file_monitors(app, &shader_files, on_shader_file_change);
+}
+
+/// Starts listening for control commands and records the initial
+/// preset as the last applied one.
+fn setup_daemon(app: &gtk::Application) {
+    let weak_app = app.downgrade();
+    let server = IpcServer::new(move |command| on_ipc_command(weak_app.clone(), command));
+
+    let app_data = get_data!(app, AppData, as_mut());
+    match server {
+        Ok(server) => app_data.ipc_server = Some(server),
+        Err(err) => log::warn!("{err}"),
+    }
+
+    if let Some(path) = &app_data.cli_config.preset_path {
+        save_last_preset(path);
+    }
+}
+
+/// Handles a command received over the control socket.
+///
+/// Replies once a requested preset is loaded and applied.
+async fn on_ipc_command(
+    app: glib::WeakRef<gtk::Application>,
+    command: IpcCommand,
+) -> Result<String, String> {
+    let app = app
+        .upgrade()
+        .ok_or_else(|| "Application is shutting down".to_string())?;
+
+    let path = match command {
+        IpcCommand::Switch(path) => path,
+        IpcCommand::Current => {
+            let app_data = get_data!(app, AppData, as_ref());
+            return Ok(app_data
+                .cli_config
+                .preset_path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default());
+        }
+        IpcCommand::Next => next_preset_path(&app)
+            .await
+            .map_err(|err| err.to_string())?,
+        IpcCommand::Previous => previous_preset_path(&app).map_err(|err| err.to_string())?,
+        IpcCommand::Random => random_preset_path(&app)
+            .await
+            .map_err(|err| err.to_string())?,
+        IpcCommand::ToggleHud => {
+            toggle_hud(&app);
+            return Ok(String::new());
+        }
+        IpcCommand::ShowOverlay => {
+            show_info_overlay(&app);
+            return Ok(String::new());
+        }
+        IpcCommand::Profile => return profile_reply(&app),
+        IpcCommand::Watchdog => {
+            let app_data = get_data!(app, AppData, as_ref());
+            let hangs: Vec<_> = app_data
+                .gpu_hangs
+                .iter()
+                .map(|(preset, time)| format!("{time} {preset}"))
+                .collect();
+            return Ok(hangs.join("; "));
+        }
+        IpcCommand::Capture => return capture_frames(&app).await,
+    };
+
+    switch_preset(&app, &path)
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(String::new())
+}
+
+/// Replies to the `capture` control command with the paths of PNG files
+/// holding the current frame of each area, separated by `; `.
+///
+/// Frames are read back without stalling rendering and encoded on a
+/// worker thread. Files are saved to `$XDG_RUNTIME_DIR`, named after the
+/// monitor connector, e.g., `shaderbg-capture-DP-1.png`, and replaced by
+/// later captures.
+async fn capture_frames(app: &gtk::Application) -> Result<String, String> {
+    let app_data = get_data!(app, AppData, as_ref());
+    let mut readbacks: VecDeque<_> = app_data
+        .areas
+        .iter()
+        .filter_map(|area| {
+            let area_data = get_data!(area, AreaData, as_ref());
+            let renderer = area_data.renderer.as_ref().filter(|_| area.is_realized())?;
+            area.make_current();
+            Some((
+                area.clone(),
+                capture_path(&area_data.connector),
+                renderer.begin_read_frame(),
+            ))
+        })
+        .collect();
+    if readbacks.is_empty() {
+        return Err("No frame rendered yet".to_string());
+    }
+
+    let mut paths = Vec::new();
+    while let Some((area, path, readback)) = readbacks.pop_front() {
+        if let Err(err) = save_capture(&area, &path, readback).await {
+            discard_captures(readbacks);
+            return Err(err);
+        }
+        paths.push(path.display().to_string());
+    }
+    Ok(paths.join("; "))
+}
+
+/// Waits for the frame read back from `area` and saves it to `path`.
+async fn save_capture(
+    area: &gtk::GLArea,
+    path: &Path,
+    readback: PixelReadback,
+) -> Result<(), String> {
+    loop {
+        if !area.is_realized() {
+            readback.abandon();
+            return Err("Monitor removed while capturing".to_string());
+        }
+        area.make_current();
+        if readback.is_ready() {
+            break;
+        }
+        glib::timeout_future(CAPTURE_POLL_INTERVAL).await;
+    }
+    let image = readback.finish();
+
+    gio::spawn_blocking({
+        let path = path.to_path_buf();
+        move || image.save_with_format(&path, image::ImageFormat::Png)
+    })
+    .await
+    .map_err(|_| format!("Failed to save {}", path.display()))?
+    .map_err(|err| format!("Failed to save {}: {err}", path.display()))
+}
+
+/// Deletes the readbacks of a failed capture, each with the context of
+/// its area current.
+fn discard_captures(readbacks: VecDeque<(gtk::GLArea, PathBuf, PixelReadback)>) {
+    for (area, _, readback) in readbacks {
+        if area.is_realized() {
+            area.make_current();
+            drop(readback);
+        } else {
+            readback.abandon();
+        }
+    }
+}
+
+/// Returns the path of the file saved by the `capture` control command
+/// for the monitor with the given connector.
+fn capture_path(connector: &str) -> PathBuf {
+    let file_name = if connector.is_empty() {
+        format!("{APP_NAME}-capture.png")
+    } else {
+        format!("{APP_NAME}-capture-{connector}.png")
+    };
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(file_name)
+}
+
+/// Shows or hides the performance HUD of every area, measuring the GPU
+/// time of the passes only while it is shown.
+fn toggle_hud(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    app_data.hud_visible = !app_data.hud_visible;
+
+    for area in &app_data.areas {
+        let area_data = get_data!(area, AreaData, as_ref());
+        if let Some(hud) = &area_data.hud {
+            hud.set_visible(app_data.hud_visible);
+        }
+    }
+    update_profiling(app);
+}
+
+/// Returns `true` if the GPU time of each pass must be measured.
+fn is_profiling(app_data: &AppData) -> bool {
+    app_data.hud_visible || app_data.profiling || app_data.resolution_controller.is_some()
+}
+
+/// Starts or stops measuring the GPU time of each pass in every area.
+fn update_profiling(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_ref());
+    for area in &app_data.areas {
+        let area_data = get_data!(area, AreaData, as_mut());
+        if let Some(renderer) = area_data.renderer.as_mut() {
+            area.make_current();
+            renderer.set_profiling(is_profiling(app_data));
+        }
+    }
+}
+
+/// Replies to the `profile` control command with the GPU time of each
+/// pass, starting to measure it on the first request.
+fn profile_reply(app: &gtk::Application) -> Result<String, String> {
+    let app_data = get_data!(app, AppData, as_mut());
+    if !app_data.profiling {
+        app_data.profiling = true;
+        update_profiling(app);
+    }
+
+    if let Some(area) = app_data.areas.first() {
+        area.make_current();
+        if !supports_pass_timing() {
+            return Err("GPU timer queries are not supported by the OpenGL context".to_string());
+        }
+    }
+
+    let summary = pass_times_summary(app_data);
+    if summary.is_empty() {
+        return Err("No GPU time measured yet, try again in a moment".to_string());
+    }
+    Ok(summary)
+}
+
+/// Returns the GPU time of each pass of every area on a single line,
+/// e.g. `DP-1: Buffer A: 2.310 ms, Image: 0.842 ms`.
+fn pass_times_summary(app_data: &AppData) -> String {
+    app_data
+        .areas
+        .iter()
+        .filter_map(|area| {
+            let area_data = get_data!(area, AreaData, as_ref());
+            let pass_times = area_data.renderer.as_ref()?.pass_times();
+            if pass_times.is_empty() {
+                return None;
+            }
+
+            let pass_times = pass_times
+                .iter()
+                .map(PassTime::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(if area_data.connector.is_empty() {
+                pass_times
+            } else {
+                format!("{}: {pass_times}", area_data.connector)
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Measures the GPU time of each pass and logs it periodically.
+fn setup_profile_logging(app: &gtk::Application) {
+    get_data!(app, AppData, as_mut()).profiling = true;
+
+    glib::timeout_add_local(
+        PROFILE_LOG_INTERVAL,
+        glib::clone!(
+            #[weak]
+            app,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                let summary = pass_times_summary(get_data!(app, AppData, as_ref()));
+                if !summary.is_empty() {
+                    log::info!("GPU time per pass: {summary}");
+                }
+                glib::ControlFlow::Continue
+            }
+        ),
+    );
+}
+
+/// Returns the next preset of the history, or a random preset at its
+/// end.
+async fn next_preset_path(app: &gtk::Application) -> Result<PathBuf, CliError> {
+    let app_data = get_data!(app, AppData, as_ref());
+    match app_data.preset_history.next() {
+        Some(path) => Ok(path.to_path_buf()),
+        None => random_preset_path(app).await,
+    }
+}
+
+/// Returns the previous preset of the history.
+fn previous_preset_path(app: &gtk::Application) -> Result<PathBuf, CliError> {
+    let app_data = get_data!(app, AppData, as_ref());
+    app_data
+        .preset_history
+        .previous()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| CliError::InvalidInput("No previous preset".to_string()))
+}
+
+/// Returns a random preset of the presets directory, honoring the
+/// `--tags` and `--exclude` filters.
+///
+/// The presets directory is scanned on a worker thread, as every preset
+/// is parsed to match the filters.
+async fn random_preset_path(app: &gtk::Application) -> Result<PathBuf, CliError> {
+    let app_data = get_data!(app, AppData, as_ref());
+    let filter = app_data.cli_config.preset_filter.clone();
+    let (_, path) =
+        gio::spawn_blocking(move || load_preset_from_directory(&presets_dir(), &filter))
+            .await
+            .map_err(|_| CliError::InvalidInput("Failed to pick a random preset".to_string()))??;
+    path.ok_or(CliError::Preset(PresetError::NoPresets))
+}
+
+/// Loads the preset at `path`, applies it and recreates the windows.
+///
+/// The preset is loaded on a worker thread, as importing a ShaderToy
+/// export may download its media, so the wallpaper keeps rendering in
+/// the meantime. If another switch starts before loading finishes, the
+/// preset is discarded.
+async fn switch_preset(app: &gtk::Application, path: &Path) -> Result<(), CliError> {
+    let app_data = get_data!(app, AppData, as_mut());
+    app_data.preset_switch_count += 1;
+    let switch_count = app_data.preset_switch_count;
+
+    log::info!("Loading {}", path.display());
+    let path = path.to_path_buf();
+    let progress = Arc::new(Mutex::new(None));
+    let progress_timer = show_load_progress(app, &path, progress.clone());
+    let result = gio::spawn_blocking({
+        let path = path.clone();
+        move || {
+            http::with_progress(
+                move |message| *progress.lock().unwrap() = Some(message.to_string()),
+                || load_preset_from_file_or_json(&path),
+            )
+        }
+    })
+    .await;
+    progress_timer.remove();
+    let (preset, preset_path) = result
+        .map_err(|_| CliError::InvalidInput(format!("Failed to load {}", path.display())))??;
+
+    let app_data = get_data!(app, AppData, as_mut());
+    if app_data.preset_switch_count != switch_count {
+        log::info!(
+            "Discarding {}, superseded by a later switch",
+            path.display()
+        );
+        return Ok(());
+    }
+    check_complexity(&preset, app_data.cli_config.strict)?;
+    if let Some(path) = preset_path.as_ref().filter(|path| {
+        app_data
+            .cli_config
+            .preset_filter
+            .excluded_files
+            .contains(path)
+    }) {
+        return Err(CliError::InvalidInput(format!(
+            "{} is excluded from switches until {APP_NAME} restarts",
+            path.display()
+        )));
+    }
+    log::info!("Switching to {}", path.display());
+
+    app_data.preset_monitor = None;
+    app_data.cli_config.preset_path = preset_path.clone();
+    if let Some(path) = &preset_path {
+        setup_preset_monitor(app, path, on_preset_change);
+        // The screensaver is not one of the presets applied by the user
+        if app_data.screensaver_return.is_none() {
+            app_data.preset_history.push(path);
+            if app_data.cli_config.daemon {
+                save_last_preset(path);
+            }
+        }
+    }
+
+    let previous_preset = app_data.preset.clone();
+    apply_preset(app, preset);
+    if !start_preset_transition(app, &previous_preset) {
+        on_monitor_changed(app.clone());
+    }
+    Ok(())
+}
+
+/// Shows the latest message of `progress`, such as the media being
+/// downloaded by an import, in the information overlay while the preset
+/// at `path` loads. Remove the returned source once it is loaded.
+fn show_load_progress(
+    app: &gtk::Application,
+    path: &Path,
+    progress: Arc<Mutex<Option<String>>>,
+) -> glib::SourceId {
+    let title = format!(
+        "Loading {}",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    glib::timeout_add_local(
+        LOAD_PROGRESS_INTERVAL,
+        glib::clone!(
+            #[weak]
+            app,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                let Some(message) = progress.lock().unwrap().take() else {
+                    return glib::ControlFlow::Continue;
+                };
+                let app_data = get_data!(app, AppData, as_ref());
+                if let Some(area) = focused_area(app_data) {
+                    let style = OverlayStyle {
+                        duration: LOAD_PROGRESS_DURATION,
+                        ..app_data.cli_config.preset.overlay_style()
+                    };
+                    set_info_overlay(area, &[title.clone(), message], &style);
+                }
+                glib::ControlFlow::Continue
+            }
+        ),
+    )
+}
+
+/// Replaces the renderers of the current windows with renderers of the
+/// active preset, which are revealed over the previous preset with its
+/// `transition` effect for its `transition_duration`.
+///
+/// Returns `false` if the windows must be recreated instead, i.e., when
+/// the transition is disabled or the preset changes the monitors, the
+/// layout or the input handling of the windows.
+fn start_preset_transition(app: &gtk::Application, previous: &Preset) -> bool {
+    let app_data = get_data!(app, AppData, as_mut());
+    let preset = &app_data.preset;
+    let Some(frame_stats) = app_data.last_frame_stats.clone() else {
+        return false;
+    };
+
+    if preset.transition_duration.is_zero()
+        || app_data.areas.is_empty()
+        || !app_data
+            .areas
+            .iter()
+            .all(|area| get_data!(area, AreaData, as_ref()).renderer.is_some())
+        || preset.monitor_selection != previous.monitor_selection
+        || preset.screen_bounds_policy != previous.screen_bounds_policy
+        || preset.layout_mode != previous.layout_mode
+        || preset.region != previous.region
+        || preset.input_mode != previous.input_mode
+        || preset.layer != previous.layer
+    {
+        return false;
+    }
+
+    log::debug!("Preset transition for {:?}", preset.transition_duration);
+
+    let monitor_count = app_data.screen_controller.selected_monitors().len();
+    let previous_frame_controller = std::mem::replace(
+        &mut app_data.frame_controller,
+        FrameController::new(preset, monitor_count)
+            .with_time_source(app_data.cli_config.time_source)
+            .with_date(app_data.cli_config.date),
+    );
+    app_data
+        .frame_controller
+        .inherit_pause_reasons(&previous_frame_controller);
+
+    let source = load_transition_source(&app_data.preset);
+    let switched_at = Instant::now();
+    for area in &app_data.areas {
+        get_data!(area, AreaData, as_mut()).transition = Some(PresetTransition {
+            renderer: None,
+            fade_start: None,
+            duration: app_data.preset.transition_duration,
+            effect: source.as_deref().map(TransitionEffect::new),
+            frame_stats: frame_stats.clone(),
+            switched_at,
+        });
+        area.make_current();
+        create_renderer(area);
+    }
+
+    setup_animation_driver(app);
+    true
+}
+
+/// Returns the glsl-transitions source of the transition of `preset`, or
+/// `None` for a plain crossfade.
+fn load_transition_source(preset: &Preset) -> Option<String> {
+    if preset.transition == Transition::Crossfade {
+        return None;
+    }
+    if let Some(source) = builtin_transition_source(&preset.transition) {
+        return Some(source.to_string());
+    }
+
+    let path = preset.transition_path()?;
+    match fs::read_to_string(&path) {
+        Ok(source) => Some(source),
+        Err(err) => {
+            log::warn!("Failed to load transition {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Records `path` as the last applied preset, restored on the next start
+/// in daemon mode.
+fn save_last_preset(path: &Path) {
+    if let Err(err) = daemon::save_last_preset(path) {
+        log::warn!("Failed to save daemon state: {err}");
+    }
+}
+
+/// Reloads an external shader file and recompiles the pass using it.
+fn on_shader_file_change(app: &gtk::Application, pass_name: &str, shader_path: &Path) {
+    let shader = match fs::read_to_string(shader_path) {
+        Ok(shader) => shader,
+        Err(err) => {
+            log::error!("Error reloading shader file: {err}");
+            return;
+        }
+    };
+
+    let app_data = get_data!(app, AppData, as_mut());
+    let Some(pass) = app_data.cli_config.preset.pass_mut(pass_name) else {
+        return;
+    };
+
+    if pass.shader == shader {
+        log::info!("Shader unchanged after reload");
+        return;
+    }
+
+    pass.shader = shader.clone();
+    update_active_preset(app);
+
+    // The new shader may use other optional uniforms
+    setup_uniform_sources(app);
+    reload_passes(app, &[(pass_name, shader)]);
+}
+
+/// Recompiles the given passes in the renderer of every area.
+///
+/// Framebuffers and textures are kept, so new shaders take effect on
+/// the next frame without a black flash. Windows are recreated when a
+/// pass cannot be reloaded in place.
+fn reload_passes<N: AsRef<str>, S: AsRef<str>>(app: &gtk::Application, shaders: &[(N, S)]) {
+    let app_data = get_data!(app, AppData, as_ref());
+
+    let mut needs_rebuild = false;
+    for (pass_name, shader) in shaders {
+        let pass_name = pass_name.as_ref();
+        log::info!("Recompiling '{pass_name}' pass");
+
+        for area in app_data.areas.iter().filter(|area| area.is_realized()) {
+            area.make_current();
+            let area_data = get_data!(area, AreaData, as_mut());
+
+            // A renderer still compiling was built from the old shaders
+            if area_data.pending_renderer.is_some() {
+                needs_rebuild = true;
+                continue;
+            }
+
+            let Some(renderer) = area_data.renderer.as_mut() else {
+                continue;
+            };
+
+            match renderer.reload_pass(pass_name, shader.as_ref()) {
+                Ok(true) => area.queue_render(),
+                Ok(false) => needs_rebuild = true,
+                Err(_) => {
+                    log::warn!("Keeping previous '{pass_name}' shader");
+                    break;
+                }
+            }
+        }
+    }
+
+    if needs_rebuild {
+        on_monitor_changed(app.clone());
+    }
+}
+
+/// Applies the power profile matching the current power source to the
+/// loaded preset.
+///
+/// Returns `true` if the preset in effect has changed.
+fn update_active_preset(app: &gtk::Application) -> bool {
+    let app_data = get_data!(app, AppData, as_mut());
+    let preset = app_data
+        .cli_config
+        .preset
+        .with_power_profile(app_data.on_battery);
+
+    if preset == app_data.preset {
+        return false;
+    }
+
+    app_data.preset = preset;
+    true
+}
+
+/// GTK activation handler.
+///
+/// Detects compositor capabilities, installs monitor listeners,
+/// and triggers initial window creation.
+#[named]
+fn activate(app: &gtk::Application) {
+    log::debug!("{}", function_name!().white().bold());
+
+    log::info!(
+        "GTK Layer Shell version: {}.{}.{}",
+        gtk4_layer_shell::major_version(),
+        gtk4_layer_shell::minor_version(),
+        gtk4_layer_shell::micro_version()
+    );
+
+    let app_data = get_data!(app, AppData, as_mut());
+    app_data.layer_shell_supported =
+        gtk4_layer_shell::is_supported() && !app_data.cli_config.preview;
+
+    if app_data.layer_shell_supported {
+        log::info!(
+            "Layer Shell Protocol (zwlr_layer_shell_v1) version: {}",
+            gtk4_layer_shell::protocol_version()
+        );
+
+        if app_data.cli_config.session_lock {
+            if !setup_session_lock(app) {
+                app.quit();
+                return;
+            }
+        } else {
+            if app_data.cli_config.pause_when_occluded {
+                setup_occlusion_monitor(app);
+            }
+            setup_idle_monitor(app);
+        }
+        setup_output_power_monitor(app);
+    } else if app_data.cli_config.session_lock {
+        log::error!("Session lock requires Layer Shell support");
+        app.quit();
+        return;
+    }
+
+    setup_drm_monitor(app);
+    setup_power_monitor(app);
+    setup_sleep_monitor(app);
+    setup_night_mode(app);
+
+    if app_data.cli_config.hotkeys {
+        setup_hotkeys(app);
+    }
+    if app_data.cli_config.profile {
+        setup_profile_logging(app);
+    }
+    setup_uniform_sources(app);
+    setup_resolution_controller(app);
+
+    if let Some(display) = gdk::Display::default() {
+        match create_gl_context(&display) {
+            Ok(gl_context) => app_data.gl_api = gl_context.api(),
+            Err(err) => log::error!("Failed to create OpenGL context: {err}"),
+        }
+
+        // Sets up the monitor change handler for the display
+        let monitors = display.monitors();
+        monitors.connect_items_changed(glib::clone!(
+            #[weak]
+            app,
+            move |_, _, _, _| {
+                on_monitor_changed(app.clone());
+            }
+        ));
+        on_monitor_changed(app.clone());
+    } else {
+        log::error!("No default GdkDisplay");
+    }
+}
+
+/// Starts tracking DRM connectors, logging displays as they are
+/// plugged and unplugged.
+fn setup_drm_monitor(app: &gtk::Application) {
+    let monitor = DrmMonitor::new(|previous, current| {
+        for connector in current.iter().filter(|connector| connector.connected) {
+            if !previous
+                .iter()
+                .any(|other| other.name == connector.name && other.connected)
+            {
+                log::info!(
+                    "Display connected to {}: {}",
+                    connector.name,
+                    connector.description()
+                );
+            }
+        }
+        for connector in previous.iter().filter(|connector| connector.connected) {
+            if !current
+                .iter()
+                .any(|other| other.name == connector.name && other.connected)
+            {
+                log::info!("Display disconnected from {}", connector.name);
+            }
+        }
+    });
+
+    let app_data = get_data!(app, AppData, as_mut());
+    match monitor {
+        Ok(monitor) => {
+            for connector in monitor.connectors().iter().filter(|c| c.connected) {
+                log::debug!(
+                    "DRM connector {}: {}",
+                    connector.name,
+                    connector.description()
+                );
+            }
+            app_data.drm_monitor = Some(monitor);
+        }
+        Err(err) => log::info!("DRM connectors are unavailable: {err}"),
+    }
+}
+
+/// Starts watching the power source to switch power profiles.
+fn setup_power_monitor(app: &gtk::Application) {
+    let monitor = PowerMonitor::new(glib::clone!(
+        #[weak]
+        app,
+        move |on_battery| on_power_source_changed(&app, on_battery)
+    ));
+
+    let app_data = get_data!(app, AppData, as_mut());
+    match monitor {
+        Ok(monitor) => {
+            app_data.on_battery = monitor.on_battery();
+            app_data.power_monitor = Some(monitor);
+            update_active_preset(app);
+        }
+        Err(err) => log::info!("Power profiles are unavailable: {err}"),
+    }
+}
+
+/// Recreates the windows when switching between battery and AC power
+/// changes the preset in effect.
+fn on_power_source_changed(app: &gtk::Application, on_battery: bool) {
+    let app_data = get_data!(app, AppData, as_mut());
+    if app_data.on_battery == on_battery {
+        return;
+    }
+
+    log::info!(
+        "Running on {} power",
+        if on_battery { "battery" } else { "AC" }
+    );
+    app_data.on_battery = on_battery;
+
+    if update_active_preset(app) {
+        setup_resolution_controller(app);
+        on_monitor_changed(app.clone());
+    }
+}
+
+/// Starts or stops the timer dimming frames according to the night
+/// mode schedule of the preset.
+fn setup_night_mode(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    if let Some(timer) = app_data.night_mode_timer.take() {
+        timer.remove();
+    }
+
+    update_dimming(app);
+
+    if app_data.preset.night_mode.is_some() {
+        app_data.night_mode_timer = Some(glib::timeout_add_local(
+            NIGHT_MODE_UPDATE_INTERVAL,
+            glib::clone!(
+                #[weak]
+                app,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    update_dimming(&app);
+                    glib::ControlFlow::Continue
+                }
+            ),
+        ));
+    }
+}
+
+/// Starts or stops the sources of the optional uniforms and inputs
+/// (`iSystemStats`, `iAudioBands`, `iDesktop`, `iTrackProgress` and album
+/// art) according to the passes of the active preset.
+fn setup_uniform_sources(app: &gtk::Application) {
+    setup_system_stats(app);
+    setup_audio_spectrum(app);
+    setup_desktop_monitor(app);
+    setup_media_monitor(app);
+}
+
+/// Starts sampling system statistics if any pass uses `iSystemStats`,
+/// or stops sampling otherwise.
+fn setup_system_stats(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+
+    if !app_data.preset.references("iSystemStats") {
+        if let Some(timer) = app_data.system_stats_timer.take() {
+            timer.remove();
+        }
+        app_data.system_stats = None;
+        return;
+    }
+
+    if app_data.system_stats.is_some() {
+        return;
+    }
+
+    let mut sampler = SystemStatsSampler::new();
+    sampler.sample();
+    app_data.system_stats = Some(sampler);
+    app_data.system_stats_timer = Some(glib::timeout_add_local(
+        SYSTEM_STATS_UPDATE_INTERVAL,
+        glib::clone!(
+            #[weak]
+            app,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                let app_data = get_data!(app, AppData, as_mut());
+                if let Some(sampler) = app_data.system_stats.as_mut() {
+                    sampler.sample();
+                }
+                glib::ControlFlow::Continue
+            }
+        ),
+    ));
+}
+
+/// Starts capturing the output audio if any pass uses `iAudioBands`, or
+/// stops capturing otherwise. Capture restarts when the band settings of
+/// the preset change.
+fn setup_audio_spectrum(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let preset = &app_data.preset;
+
+    if !preset.references("iAudioBands") {
+        app_data.audio_spectrum = None;
+        return;
+    }
+
+    let (band_count, smoothing) = (preset.audio_bands, preset.audio_smoothing);
+    if app_data
+        .audio_spectrum
+        .as_ref()
+        .is_some_and(|spectrum| spectrum.has_settings(band_count, smoothing))
+    {
+        return;
+    }
+
+    app_data.audio_spectrum = None;
+    match AudioSpectrum::new(band_count, smoothing) {
+        Ok(spectrum) => app_data.audio_spectrum = Some(spectrum),
+        Err(err) => log::warn!("Audio capture is unavailable: {err}"),
+    }
+}
+
+/// Starts watching the compositor if any pass uses `iDesktop`, or stops
+/// watching otherwise.
+fn setup_desktop_monitor(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+
+    if !app_data.preset.references("iDesktop") {
+        app_data.desktop_monitor = None;
+        return;
+    }
+
+    if app_data.desktop_monitor.is_some() {
+        return;
+    }
+
+    match DesktopMonitor::new() {
+        Ok(monitor) => {
+            log::info!("Reading desktop state from {}", monitor.compositor_name());
+            app_data.desktop_monitor = Some(monitor);
+        }
+        Err(err) => log::warn!("{err}"),
+    }
+}
+
+/// Starts watching media players if any pass has an `album_art` input or
+/// uses `iTrackProgress`, or stops watching otherwise.
+fn setup_media_monitor(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let preset = &app_data.preset;
+
+    if !preset.uses_input_type(InputType::AlbumArt) && !preset.references("iTrackProgress") {
+        app_data.media_monitor = None;
+        return;
+    }
+
+    if app_data.media_monitor.is_some() {
+        return;
+    }
+
+    match MediaMonitor::new() {
+        Ok(monitor) => app_data.media_monitor = Some(monitor),
+        Err(err) => log::warn!("Media player information is unavailable: {err}"),
+    }
+}
+
+/// Starts or stops adjusting the resolution scale to hold the
+/// `target_fps` of the preset, keeping the current scale if the
+/// settings have not changed.
+fn setup_resolution_controller(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let controller = ResolutionController::new(&app_data.preset);
+
+    let unchanged = match (&app_data.resolution_controller, &controller) {
+        (Some(current), Some(controller)) => current.has_same_settings(controller),
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return;
+    }
+
+    app_data.resolution_controller = controller;
+    update_profiling(app);
+}
+
+/// Recreates the renderer of every area, e.g., after the dynamic
+/// resolution scale changed.
+fn recreate_renderers(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_ref());
+    for area in &app_data.areas {
+        // Areas not resized yet create their renderer once they are
+        let area_data = get_data!(area, AreaData, as_ref());
+        if area_data.renderer.is_some() || area_data.pending_renderer.is_some() {
+            area.make_current();
+            create_renderer(area);
+        }
+    }
+}
+
+/// Returns the GPU time spent on the passes of every area in a frame,
+/// or `None` if it is not measured.
+fn total_gpu_time(areas: &[gtk::GLArea]) -> Option<Duration> {
+    let pass_times: Vec<PassTime> = areas
+        .iter()
+        .filter_map(|area| get_data!(area, AreaData, as_ref()).renderer.as_ref())
+        .flat_map(|renderer| renderer.pass_times())
+        .collect();
+
+    (!pass_times.is_empty()).then(|| pass_times.iter().map(|pass_time| pass_time.average).sum())
+}
+
+/// Applies the night mode brightness for the current time of day.
+fn update_dimming(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let dimming = app_data
+        .preset
+        .night_mode
+        .as_ref()
+        .map_or(1.0, |night_mode| {
+            night_mode.brightness_at(chrono::Local::now().time())
+        });
+
+    if dimming == app_data.dimming {
+        return;
+    }
+
+    log::debug!("Night mode brightness: {dimming:.2}");
+    app_data.dimming = dimming;
+
+    for area in &app_data.areas {
+        let area_data = get_data!(area, AreaData, as_mut());
+        if let Some(renderer) = area_data.renderer.as_mut() {
+            renderer.set_dimming(dimming);
+            area.queue_render();
+        }
+    }
+}
+
+/// Starts watching for system suspend to pause rendering while asleep.
+fn setup_sleep_monitor(app: &gtk::Application) {
+    let monitor = SleepMonitor::new(glib::clone!(
+        #[weak]
+        app,
+        move |suspending| on_sleep_changed(&app, suspending)
+    ));
+
+    let app_data = get_data!(app, AppData, as_mut());
+    match monitor {
+        Ok(monitor) => app_data.sleep_monitor = Some(monitor),
+        Err(err) => log::info!("Pausing on suspend is unavailable: {err}"),
+    }
+}
+
+/// Pauses rendering before the system suspends and resumes it after
+/// waking up, optionally restarting the animation time.
+fn on_sleep_changed(app: &gtk::Application, suspending: bool) {
+    if suspending {
+        pause_rendering(app, PauseReason::Suspended);
+        return;
+    }
+
+    let app_data = get_data!(app, AppData, as_mut());
+    if app_data.preset.reset_time_on_resume {
+        log::info!("Restarting animation time after resume");
+        app_data.frame_controller.reset_time_base();
+    }
+    resume_rendering(app, PauseReason::Suspended);
+}
+
+/// Registers the global hotkeys controlling the animation.
+fn setup_hotkeys(app: &gtk::Application) {
+    let hotkeys = Hotkeys::new(glib::clone!(
+        #[weak]
+        app,
+        move |hotkey| on_hotkey(&app, hotkey)
+    ));
 
-    /// Optional widget for displaying shader info,
-    /// shown when the area is first rendered.
-    pub info_overlay: Option<gtk::Widget>,
+    let app_data = get_data!(app, AppData, as_mut());
+    match hotkeys {
+        Ok(hotkeys) => app_data.hotkeys = Some(hotkeys),
+        Err(err) => log::warn!("Global hotkeys are unavailable: {err}"),
+    }
 }
 
-/// Snapshot of input state supplied to the renderer for one frame.
-pub struct InputData {
-    pub mouse: MouseData,
-    pub keyboard: Option<KeyboardData>,
+/// Pauses, steps or moves the animation time on a hotkey press.
+fn on_hotkey(app: &gtk::Application, hotkey: Hotkey) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let frame_controller = &mut app_data.frame_controller;
+
+    match hotkey {
+        Hotkey::TogglePause if frame_controller.is_paused_by(PauseReason::User) => {
+            resume_rendering(app, PauseReason::User);
+            return;
+        }
+        Hotkey::TogglePause => {
+            pause_rendering(app, PauseReason::User);
+            return;
+        }
+        Hotkey::StepFrame => {
+            if !frame_controller.step_frame() {
+                return;
+            }
+        }
+        Hotkey::JumpForward => frame_controller.jump_forward(HOTKEY_JUMP_DURATION),
+        Hotkey::JumpBackward => frame_controller.jump_backward(HOTKEY_JUMP_DURATION),
+        Hotkey::ShowOverlay => {
+            show_info_overlay(app);
+            return;
+        }
+    }
+
+    // The animation driver is stopped while paused
+    if frame_controller.is_paused() {
+        areas_queue_render(app);
+    }
 }
 
-pub fn init_logging() -> Result<(), log::SetLoggerError> {
-    let level = if cfg!(debug_assertions) {
-        log::LevelFilter::Debug
-    } else {
-        log::LevelFilter::Warn
-    };
+/// Starts watching for fullscreen windows that hide the wallpaper.
+fn setup_occlusion_monitor(app: &gtk::Application) {
+    let monitor = OcclusionMonitor::new(glib::clone!(
+        #[weak]
+        app,
+        move |occluded_outputs| on_occlusion_changed(&app, occluded_outputs)
+    ));
 
-    simple_logger::SimpleLogger::new().with_level(level).init()
+    let app_data = get_data!(app, AppData, as_mut());
+    match monitor {
+        Ok(monitor) => app_data.occlusion_monitor = Some(monitor),
+        Err(err) => log::info!("Pausing on fullscreen windows is unavailable: {err}"),
+    }
 }
 
-/// Initializes and runs the GTK application.
+/// Pauses rendering while every area is covered by a fullscreen window.
 ///
-/// Creates global `AppData`, installs signal handlers,
-/// and starts the GTK main loop.
-pub fn run(cli_config: CliConfig) -> glib::ExitCode {
-    let app = gtk::Application::builder().application_id(APP_ID).build();
+/// Windows on [`WindowLayer::Overlay`] are drawn over fullscreen windows
+/// and are never paused.
+fn on_occlusion_changed(app: &gtk::Application, occluded_outputs: &HashSet<String>) {
+    let app_data = get_data!(app, AppData, as_ref());
+    let is_occluded = window_layer(app_data) != WindowLayer::Overlay
+        && !app_data.areas.is_empty()
+        && app_data.areas.iter().all(|area| {
+            let area_data = get_data!(area, AreaData, as_ref());
+            occluded_outputs.contains(&area_data.connector)
+        });
 
-    set_data!(
+    if is_occluded {
+        pause_rendering(app, PauseReason::Occluded);
+    } else {
+        resume_rendering(app, PauseReason::Occluded);
+    }
+}
+
+/// Prepares to lock the session on the first creation of windows, which
+/// then become the lock surfaces.
+///
+/// Returns `false` if the session cannot be locked.
+fn setup_session_lock(app: &gtk::Application) -> bool {
+    let session_lock = match SessionLock::new(config::config().session_lock.as_ref()) {
+        Ok(session_lock) => session_lock,
+        Err(err) => {
+            log::error!("{err}");
+            return false;
+        }
+    };
+    session_lock.connect_failed(glib::clone!(
+        #[weak]
         app,
-        AppData {
-            areas: Vec::default(),
-            cli_config,
-            preset_monitor: None,
-            animation_timer: None,
-            frame_controller: FrameController::default(),
-            mouse_controller: MouseController::new(app.clone()),
-            keyboard_controller: KeyboardController::new(app.clone()),
-            screen_controller: ScreenController::default(),
-            layer_shell_supported: false,
+        move || {
+            log::error!("{}", SessionLockError::Refused);
+            app.quit();
         }
-    );
+    ));
 
-    let app_data = get_data!(app, AppData, as_ref());
+    get_data!(app, AppData, as_mut()).session_lock = Some(session_lock);
+    true
+}
 
-    if let Some(path) = &app_data.cli_config.preset_path {
-        setup_preset_monitor(&app, path, on_preset_change);
+/// Adds the password entry of the lock screen to `overlay`.
+fn add_unlock_prompt(app: &gtk::Application, overlay: &gtk::Overlay) {
+    let entry = gtk::PasswordEntry::builder()
+        .placeholder_text("Password")
+        .halign(gtk::Align::Center)
+        .valign(gtk::Align::Center)
+        .width_request(UNLOCK_PROMPT_WIDTH)
+        .build();
+    entry.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        move |entry| {
+            let password = entry.text().to_string();
+            entry.set_text("");
+            entry.set_sensitive(false);
+            let entry = entry.clone();
+            glib::spawn_future_local(async move { unlock(&app, &entry, &password).await });
+        }
+    ));
+    overlay.add_overlay(&entry);
+}
+
+/// Unlocks the session and quits if the `auth_command` accepts
+/// `password`.
+async fn unlock(app: &gtk::Application, entry: &gtk::PasswordEntry, password: &str) {
+    let Some(session_lock_config) = &config::config().session_lock else {
+        return;
+    };
+    match authenticate(&session_lock_config.auth_command, password).await {
+        Ok(true) => {
+            log::info!("Unlocking the session");
+            let app_data = get_data!(app, AppData, as_ref());
+            if let Some(session_lock) = &app_data.session_lock {
+                session_lock.unlock();
+            }
+            if let Some(display) = gdk::Display::default() {
+                display.flush();
+            }
+            app.quit();
+            return;
+        }
+        Ok(false) => entry.set_property("placeholder-text", "Wrong password"),
+        Err(err) => log::error!("Failed to run the auth command: {err}"),
     }
+    entry.set_sensitive(true);
+    entry.grab_focus();
+}
 
-    app.connect_activate(activate);
-    app.run_with_args(&[""])
+/// Starts watching for the user going idle if the configuration file
+/// has a `[screensaver]` table.
+fn setup_idle_monitor(app: &gtk::Application) {
+    let Some(screensaver) = &config::config().screensaver else {
+        return;
+    };
+    let monitor = IdleMonitor::new(
+        screensaver.idle_timeout,
+        glib::clone!(
+            #[weak]
+            app,
+            move |idle| on_idle_changed(&app, idle)
+        ),
+    );
+
+    let app_data = get_data!(app, AppData, as_mut());
+    match monitor {
+        Ok(monitor) => app_data.idle_monitor = Some(monitor),
+        Err(err) => log::warn!("The screensaver is unavailable: {err}"),
+    }
 }
 
-/// Reloads preset from the given file and applies it if it has changed.
-fn on_preset_change(app: &gtk::Application, preset_path: &Path) {
-    match Preset::from_toml_file(preset_path) {
-        Ok(new_preset) => {
-            let app_data = get_data!(app, AppData, as_mut());
+/// Switches to the screensaver preset when the user goes idle, and back
+/// to the previous preset when activity resumes.
+fn on_idle_changed(app: &gtk::Application, idle: bool) {
+    let Some(screensaver) = &config::config().screensaver else {
+        return;
+    };
+    let app_data = get_data!(app, AppData, as_mut());
+    let path = if idle {
+        let Some(path) = app_data.cli_config.preset_path.clone() else {
+            log::warn!("Not showing the screensaver: the preset has no file to return to");
+            return;
+        };
+        log::info!("Idle, showing the screensaver");
+        app_data.screensaver_return = Some(path);
+        screensaver.preset.clone()
+    } else {
+        let Some(path) = app_data.screensaver_return.take() else {
+            return;
+        };
+        log::info!("Activity resumed, hiding the screensaver");
+        path
+    };
 
-            if new_preset != app_data.cli_config.preset {
-                log::info!("Applying updated preset");
-                app_data.cli_config.preset = new_preset;
-                on_monitor_changed(app.clone());
-            } else {
-                log::info!("Preset unchanged after reload");
-            }
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        if let Err(err) = switch_preset(&app, &path).await {
+            log::warn!("{err}");
         }
-        Err(err) => log::error!("Error reloading preset: {err}"),
+    });
+}
+
+/// Starts watching for outputs powered off by DPMS.
+fn setup_output_power_monitor(app: &gtk::Application) {
+    let monitor = OutputPowerMonitor::new(glib::clone!(
+        #[weak]
+        app,
+        move |powered_off_outputs| on_output_power_changed(&app, powered_off_outputs)
+    ));
+
+    let app_data = get_data!(app, AppData, as_mut());
+    match monitor {
+        Ok(monitor) => app_data.output_power_monitor = Some(monitor),
+        Err(err) => log::info!("Pausing on powered-off outputs is unavailable: {err}"),
     }
 }
 
-/// GTK activation handler.
+/// Skips the renderers of areas whose output is powered off, and pauses
+/// rendering while every output is.
 ///
-/// Detects compositor capabilities, installs monitor listeners,
-/// and triggers initial window creation.
-#[named]
-fn activate(app: &gtk::Application) {
-    log::debug!("{}", function_name!().white().bold());
+/// The pause keeps the animation time still, so rendering resumes where
+/// it stopped once an output is powered on.
+fn on_output_power_changed(app: &gtk::Application, powered_off_outputs: &HashSet<String>) {
+    let app_data = get_data!(app, AppData, as_ref());
+    for area in &app_data.areas {
+        let area_data = get_data!(area, AreaData, as_mut());
+        let powered_off = powered_off_outputs.contains(&area_data.connector);
+        if area_data.powered_off != powered_off {
+            log::info!(
+                "Output {} powered {}",
+                area_data.connector,
+                if powered_off { "off" } else { "on" }
+            );
+            area_data.powered_off = powered_off;
+        }
+    }
 
-    log::info!(
-        "GTK Layer Shell version: {}.{}.{}",
-        gtk4_layer_shell::major_version(),
-        gtk4_layer_shell::minor_version(),
-        gtk4_layer_shell::micro_version()
-    );
+    let is_powered_off = !app_data.areas.is_empty()
+        && app_data
+            .areas
+            .iter()
+            .all(|area| get_data!(area, AreaData, as_ref()).powered_off);
+
+    if is_powered_off {
+        pause_rendering(app, PauseReason::PoweredOff);
+    } else {
+        resume_rendering(app, PauseReason::PoweredOff);
+    }
+}
 
+/// Suspends frame production and removes the animation driver.
+pub fn pause_rendering(app: &gtk::Application, reason: PauseReason) {
     let app_data = get_data!(app, AppData, as_mut());
-    app_data.layer_shell_supported = gtk4_layer_shell::is_supported();
+    if app_data.frame_controller.pause(reason) {
+        log::info!("Rendering paused ({reason:?})");
+        stop_animation_driver(app);
+        set_media_paused(app, true);
+    }
+}
 
-    if app_data.layer_shell_supported {
-        log::info!(
-            "Layer Shell Protocol (zwlr_layer_shell_v1) version: {}",
-            gtk4_layer_shell::protocol_version()
-        );
+/// Clears a pause reason, restarting the animation driver once no
+/// other reason remains.
+pub fn resume_rendering(app: &gtk::Application, reason: PauseReason) {
+    let app_data = get_data!(app, AppData, as_mut());
+    if app_data.frame_controller.resume(reason) {
+        log::info!("Rendering resumed ({reason:?})");
+        set_media_paused(app, false);
+        setup_animation_driver(app);
     }
+}
 
-    if let Some(display) = gdk::Display::default() {
-        // Sets up the monitor change handler for the display
-        let monitors = display.monitors();
-        monitors.connect_items_changed(glib::clone!(
-            #[weak]
-            app,
-            move |_, _, _, _| {
-                on_monitor_changed(app.clone());
-            }
-        ));
-        on_monitor_changed(app.clone());
-    } else {
-        log::error!("No default GdkDisplay");
+/// Pauses or resumes video inputs in every area so that their
+/// `iChannelTime` stands still while rendering is paused.
+fn set_media_paused(app: &gtk::Application, paused: bool) {
+    let app_data = get_data!(app, AppData, as_ref());
+    for area in &app_data.areas {
+        let area_data = get_data!(area, AreaData, as_mut());
+        if let Some(renderer) = area_data.renderer.as_mut() {
+            renderer.set_media_paused(paused);
+        }
     }
 }
 
@@ -211,10 +1882,25 @@ fn activate(app: &gtk::Application) {
 pub fn on_monitor_changed(app: gtk::Application) {
     log::debug!("{}", function_name!().white().bold());
 
-    // Destroy existing windows before creating new ones
-    app.windows().iter().for_each(|window| window.destroy());
+    let app_data = get_data!(app, AppData, as_ref());
+
+    // Lock surfaces cannot be replaced while the session is locked
+    if app_data
+        .session_lock
+        .as_ref()
+        .is_some_and(SessionLock::is_locked)
+    {
+        log::warn!("Keeping the lock screen windows after a change of monitors");
+        return;
+    }
 
-    let has_connected_output = has_connected_drm_output().unwrap_or_else(|err| {
+    let has_connected_output = match &app_data.drm_monitor {
+        Some(drm_monitor) => drm_monitor
+            .refresh()
+            .map(|()| drm_monitor.has_active_output()),
+        None => has_connected_drm_output(),
+    }
+    .unwrap_or_else(|err| {
         log::warn!("Could not query DRM: {err}");
         true // Fall back to GDK monitors check
     });
@@ -227,12 +1913,77 @@ pub fn on_monitor_changed(app: gtk::Application) {
             .iter()
             .any(|monitor| !monitor.is_valid() || monitor.connector().is_none())
     {
+        // Destroy existing windows before entering standby
+        let app_data = get_data!(app, AppData, as_mut());
+        app_data.retired_windows.clear();
+        app_data.frame_snapshots.clear();
+        app_data.buffer_snapshots.clear();
+        app.windows().iter().for_each(|window| window.destroy());
         start_standby_mode(&app);
     } else {
+        retire_windows(&app);
         create_windows(&app);
     }
 }
 
+/// Captures the last frame of every area and keeps the existing windows
+/// visible while new ones are created, so that replacing windows does not
+/// flash the compositor background.
+///
+/// Retired windows are destroyed by [`destroy_retired_windows`] once the
+/// new areas have presented a frame, or after [`RETIRED_WINDOW_TIMEOUT`].
+fn retire_windows(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+
+    app_data.frame_snapshots.clear();
+    app_data.buffer_snapshots.clear();
+    for area in &app_data.areas {
+        let area_data = get_data!(area, AreaData, as_mut());
+        if let Some(renderer) = &area_data.renderer {
+            if area.is_realized() && !area_data.connector.is_empty() {
+                area.make_current();
+                app_data
+                    .frame_snapshots
+                    .insert(area_data.connector.clone(), renderer.read_frame());
+
+                // The new windows have OpenGL contexts of their own
+                if app_data.preset.preserve_buffers {
+                    app_data
+                        .buffer_snapshots
+                        .insert(area_data.connector.clone(), renderer.read_buffers());
+                }
+            }
+        }
+        // Also stops the sound of the replaced renderer
+        area_data.renderer = None;
+        area_data.pending_renderer = None;
+        area_data.transition = None;
+    }
+
+    let windows = app.windows();
+    if windows.is_empty() {
+        return;
+    }
+    app_data.retired_windows.extend(windows);
+
+    glib::timeout_add_local_once(
+        RETIRED_WINDOW_TIMEOUT,
+        glib::clone!(
+            #[weak]
+            app,
+            move || destroy_retired_windows(&app)
+        ),
+    );
+}
+
+/// Destroys the windows replaced by [`retire_windows`].
+fn destroy_retired_windows(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    for window in app_data.retired_windows.drain(..) {
+        window.destroy();
+    }
+}
+
 /// Enters standby mode when no usable monitors are available.
 ///
 /// A hidden window keeps the GTK application alive while
@@ -268,15 +2019,32 @@ pub fn create_windows(app: &gtk::Application) {
     app_data.screen_controller = ScreenController::new(app);
 
     let monitor_count = app_data.screen_controller.selected_monitors().len();
-    app_data.frame_controller = FrameController::new(&app_data.cli_config.preset, monitor_count);
+    let previous_frame_controller = std::mem::replace(
+        &mut app_data.frame_controller,
+        FrameController::new(&app_data.preset, monitor_count)
+            .with_time_source(app_data.cli_config.time_source)
+            .with_date(app_data.cli_config.date),
+    );
+    app_data
+        .frame_controller
+        .inherit_pause_reasons(&previous_frame_controller);
 
     if app_data.layer_shell_supported {
         create_layer_windows(app);
     } else {
         create_fallback_window(app);
     }
+    app_data.frame_snapshots.clear();
+    app_data.buffer_snapshots.clear();
 
     drop(old_areas);
+
+    if let Some(occlusion_monitor) = &app_data.occlusion_monitor {
+        on_occlusion_changed(app, &occlusion_monitor.occluded_outputs());
+    }
+    if let Some(output_power_monitor) = &app_data.output_power_monitor {
+        on_output_power_changed(app, &output_power_monitor.powered_off_outputs());
+    }
     setup_animation_driver(app);
 }
 
@@ -285,8 +2053,21 @@ pub fn create_windows(app: &gtk::Application) {
 /// Each render window is paired with a transparent [`create_input_window`]
 /// on [`Layer::Bottom`] that captures mouse and keyboard events without
 /// interfering with the composited wallpaper below.
+///
+/// With `--session-lock`, the session is locked instead, and the windows
+/// are the lock surfaces of the monitors, with a password entry and no
+/// input windows.
 fn create_layer_windows(app: &gtk::Application) {
     let app_data = get_data!(app, AppData, as_mut());
+    let fallback_image = load_fallback_image(&app_data.preset);
+
+    if let Some(session_lock) = &app_data.session_lock {
+        if let Err(err) = session_lock.lock() {
+            log::error!("{err}");
+            app.quit();
+            return;
+        }
+    }
 
     for monitor in app_data.screen_controller.selected_monitors() {
         let window = gtk::ApplicationWindow::builder()
@@ -295,11 +2076,14 @@ fn create_layer_windows(app: &gtk::Application) {
             .title(APP_NAME)
             .build();
 
-        setup_layer_shell(&window);
+        match &app_data.session_lock {
+            Some(session_lock) => session_lock.assign_window_to_monitor(&window, monitor),
+            None => setup_layer_shell(&window, window_layer(app_data)),
+        }
 
         // Input is handled by the companion input window; render areas
         // must not consume keyboard events from the compositor.
-        let area = setup_area(app, false);
+        let area = setup_area(app, InputMode::None);
 
         let connector = monitor
             .connector()
@@ -307,68 +2091,222 @@ fn create_layer_windows(app: &gtk::Application) {
             .unwrap_or_else(|| "Unknown".to_string());
 
         let (bounds, gl_offset) = app_data.screen_controller.bounds_and_gl_offset_of(monitor);
+        let gl_offset = match app_data.preset.layout_mode {
+            LayoutMode::Region => Offset::default(),
+            _ => gl_offset,
+        };
+        let frame_snapshot = app_data
+            .frame_snapshots
+            .remove(&connector)
+            .or_else(|| fallback_image.clone())
+            .map(FrameSnapshot::new);
+        let buffer_snapshots = app_data
+            .buffer_snapshots
+            .remove(&connector)
+            .unwrap_or_default();
 
         set_data!(
             area,
             AreaData {
                 renderer: None,
+                pending_renderer: None,
                 connector,
                 bounds,
                 gl_offset,
                 info_overlay: None,
+                hud: None,
+                viewport_size: Size::default(),
+                frame_snapshot,
+                buffer_snapshots,
+                frame_snapshot_fade_start: None,
+                presented: false,
+                powered_off: false,
+                transition: None,
+                gpu_watchdog: None,
             }
         );
 
-        if app_data.cli_config.show_overlay {
+        if has_info_overlay(app_data) || has_hud(app_data) || app_data.session_lock.is_some() {
             let overlay = gtk::Overlay::new();
             overlay.set_child(Some(&area));
 
-            if gl_offset == Offset::default() {
-                let name = &app_data.cli_config.preset.name;
-                let author = &app_data.cli_config.preset.username;
+            if app_data.cli_config.show_overlay && gl_offset == Offset::default() {
+                let preset = &app_data.cli_config.preset;
+                let style = preset.overlay_style();
                 let area_data = get_data!(area, AreaData, as_mut());
-                area_data.info_overlay = create_info_widget(name, author);
+                area_data.info_overlay = create_info_widget(&style.lines(preset), &style);
                 if let Some(widget) = &area_data.info_overlay {
                     overlay.add_overlay(widget);
                 }
             }
+            add_hud(app_data, &area, &overlay);
+            if app_data.session_lock.is_some() {
+                add_unlock_prompt(app, &overlay);
+            }
             window.set_child(Some(&overlay));
         } else {
             window.set_child(Some(&area));
         }
 
-        window.set_monitor(Some(monitor));
+        if app_data.session_lock.is_none() {
+            window.set_monitor(Some(monitor));
+        }
         app_data.areas.push(area);
         window.present();
 
-        // Create the companion transparent input-capture window for this monitor
-        create_input_window(app, monitor, gl_offset);
+        // Create the companion transparent input-capture window for this
+        // monitor, unless the preset lets input pass through the wallpaper
+        let input_mode = app_data.cli_config.preset.input_mode;
+        if input_mode.captures_mouse() && app_data.session_lock.is_none() {
+            let margins = match app_data.preset.layout_mode {
+                LayoutMode::Region => app_data.preset.region.margins,
+                _ => [0; 4],
+            };
+            create_input_window(app, monitor, gl_offset, input_mode, margins);
+        }
+    }
+}
+
+/// Loads the preset fallback image, presented by new windows until their
+/// first renderer is ready.
+fn load_fallback_image(preset: &Preset) -> Option<image::RgbaImage> {
+    let path = preset.fallback_image_path()?;
+    image::open(&path)
+        .inspect_err(|err| log::warn!("Failed to load fallback image {path:?}: {err}"))
+        .ok()
+        .map(|image| image.into_rgba8())
+}
+
+/// Creates the key bindings of the fallback window that cycle presets.
+///
+/// | Key                | Action                   |
+/// |--------------------|--------------------------|
+/// | `Ctrl+Page Down`   | Next preset              |
+/// | `Ctrl+Page Up`     | Previous preset          |
+/// | `Ctrl+R`           | Random preset            |
+fn create_preset_shortcuts(app: &gtk::Application) -> gtk::ShortcutController {
+    let bindings = [
+        ("<Control>Page_Down", IpcCommand::Next),
+        ("<Control>Page_Up", IpcCommand::Previous),
+        ("<Control>r", IpcCommand::Random),
+    ];
+
+    let controller = gtk::ShortcutController::new();
+    for (trigger, command) in bindings {
+        let action = gtk::CallbackAction::new(glib::clone!(
+            #[weak]
+            app,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |_, _| {
+                // Switching recreates the window handling the key press
+                let app = app.clone();
+                let command = command.clone();
+                glib::spawn_future_local(async move {
+                    let path = match command {
+                        IpcCommand::Next => next_preset_path(&app).await,
+                        IpcCommand::Previous => previous_preset_path(&app),
+                        _ => random_preset_path(&app).await,
+                    };
+                    let result = match path {
+                        Ok(path) => switch_preset(&app, &path).await,
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = result {
+                        log::warn!("{err}");
+                    }
+                });
+                glib::Propagation::Stop
+            }
+        ));
+        controller.add_shortcut(gtk::Shortcut::new(
+            gtk::ShortcutTrigger::parse_string(trigger),
+            Some(action),
+        ));
     }
+    controller
+}
+
+/// Creates the `Ctrl+Enter` key binding of the preview window, which
+/// applies the previewed preset to the running wallpaper and closes the
+/// preview.
+fn create_preview_shortcuts(app: &gtk::Application) -> gtk::ShortcutController {
+    let action = gtk::CallbackAction::new(glib::clone!(
+        #[weak]
+        app,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, _| {
+            apply_preview(&app);
+            glib::Propagation::Stop
+        }
+    ));
+
+    let controller = gtk::ShortcutController::new();
+    controller.add_shortcut(gtk::Shortcut::new(
+        gtk::ShortcutTrigger::parse_string("<Control>Return|<Control>KP_Enter"),
+        Some(action),
+    ));
+    controller
+}
+
+/// Asks the running wallpaper to switch to the previewed preset over
+/// the control socket, and quits once it has.
+fn apply_preview(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_ref());
+    let Some(path) = &app_data.cli_config.preset_path else {
+        log::warn!("The previewed preset has no file to apply");
+        return;
+    };
+
+    // The daemon resolves relative paths against the presets directory,
+    // not the working directory of the preview
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+    let app = app.clone();
+    glib::spawn_future_local(async move {
+        match ipc::send_in_background(IpcCommand::Switch(path.clone())).await {
+            Ok(_) => {
+                log::info!("Applied {} to the wallpaper", path.display());
+                app.quit();
+            }
+            Err(err) => log::error!("Failed to apply preview: {err}"),
+        }
+    });
 }
 
 /// Creates a single top-level window when Layer Shell is unavailable.
 fn create_fallback_window(app: &gtk::Application) {
-    log::warn!("Layer Shell protocol not supported. Using top-level window.");
-
     let app_data = get_data!(app, AppData, as_mut());
 
+    let title = if app_data.cli_config.preview {
+        format!("{} - {APP_NAME} preview", app_data.preset.name)
+    } else {
+        log::warn!("Layer Shell protocol not supported. Using top-level window.");
+        format!("{APP_NAME} {APP_SEMVER}")
+    };
+
     let window = gtk::ApplicationWindow::builder()
         .application(app)
         .name(APP_NAME)
         .icon_name(APP_NAME)
-        .title(format!("{APP_NAME} {APP_SEMVER}"))
+        .title(title)
         .default_width(800)
         .default_height(600)
         .width_request(320)
         .height_request(240)
         .build();
+    window.add_controller(create_preset_shortcuts(app));
+    if app_data.cli_config.preview {
+        window.add_controller(create_preview_shortcuts(app));
+    }
 
-    let area = setup_area(app, true);
+    let area = setup_area(app, app_data.cli_config.preset.input_mode);
 
     set_data!(
         area,
         AreaData {
             renderer: None,
+            pending_renderer: None,
             connector: String::default(),
             bounds: Rectangle::new(
                 Point::default(),
@@ -376,20 +2314,32 @@ fn create_fallback_window(app: &gtk::Application) {
             ),
             gl_offset: Offset::default(),
             info_overlay: None,
+            hud: None,
+            viewport_size: Size::default(),
+            frame_snapshot: load_fallback_image(&app_data.preset).map(FrameSnapshot::new),
+            buffer_snapshots: Vec::new(),
+            frame_snapshot_fade_start: None,
+            presented: false,
+            powered_off: false,
+            transition: None,
+            gpu_watchdog: None,
         }
     );
 
-    if app_data.cli_config.show_overlay {
+    if has_info_overlay(app_data) || has_hud(app_data) {
         let overlay = gtk::Overlay::new();
         overlay.set_child(Some(&area));
 
-        let name = &app_data.cli_config.preset.name;
-        let author = &app_data.cli_config.preset.username;
-        let area_data = get_data!(area, AreaData, as_mut());
-        area_data.info_overlay = create_info_widget(name, author);
-        if let Some(widget) = &area_data.info_overlay {
-            overlay.add_overlay(widget);
+        if app_data.cli_config.show_overlay {
+            let preset = &app_data.cli_config.preset;
+            let style = preset.overlay_style();
+            let area_data = get_data!(area, AreaData, as_mut());
+            area_data.info_overlay = create_info_widget(&style.lines(preset), &style);
+            if let Some(widget) = &area_data.info_overlay {
+                overlay.add_overlay(widget);
+            }
         }
+        add_hud(app_data, &area, &overlay);
 
         window.set_child(Some(&overlay));
     } else {
@@ -399,81 +2349,168 @@ fn create_fallback_window(app: &gtk::Application) {
     window.present();
 }
 
-/// Creates a text widget for displaying shader info.
-fn create_info_widget(name: &str, author: &str) -> Option<gtk::Widget> {
-    const NAME_FONT_SIZE_PT: i32 = 18;
-    const AUTHOR_FONT_SIZE_PT: i32 = 14;
-    const MARGIN: i32 = 25;
+/// Returns `true` if areas need room for the information overlay, i.e.,
+/// if it is shown on start or may be shown again with the `overlay`
+/// control command or hotkey.
+fn has_info_overlay(app_data: &AppData) -> bool {
+    let cli_config = &app_data.cli_config;
+    cli_config.show_overlay || cli_config.daemon || cli_config.hotkeys
+}
+
+/// Shows the information overlay again, along with the frame rate, on
+/// the focused monitor, or on the first monitor if it is unknown.
+fn show_info_overlay(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_ref());
+    let Some(area) = focused_area(app_data) else {
+        return;
+    };
+
+    let preset = &app_data.cli_config.preset;
+    let style = preset.overlay_style();
+    let mut lines = style.lines(preset);
+    if let Some(frame_stats) = &app_data.last_frame_stats {
+        lines.push(format!("{:.0} fps", frame_stats.frame_rate));
+    }
+    set_info_overlay(area, &lines, &style);
+}
+
+/// Returns the area on the focused monitor, or the first area if the
+/// focused monitor is unknown.
+fn focused_area(app_data: &AppData) -> Option<&gtk::GLArea> {
+    let focused_output = focused_output();
+    app_data
+        .areas
+        .iter()
+        .find(|area| {
+            focused_output.as_deref() == Some(&get_data!(area, AreaData, as_ref()).connector)
+        })
+        .or_else(|| app_data.areas.first())
+}
+
+/// Replaces the information overlay of `area` with one showing `lines`,
+/// which fades out after the duration of `style`.
+fn set_info_overlay(area: &gtk::GLArea, lines: &[String], style: &OverlayStyle) {
+    let Some(overlay) = area.parent().and_downcast::<gtk::Overlay>() else {
+        return;
+    };
+
+    let area_data = get_data!(area, AreaData, as_mut());
+    if let Some(widget) = area_data.info_overlay.take() {
+        overlay.remove_overlay(&widget);
+    }
+
+    area_data.info_overlay = create_info_widget(lines, style);
+    if let Some(widget) = &area_data.info_overlay {
+        overlay.add_overlay(widget);
+        setup_fadeout_timer(widget, style.duration);
+    }
+}
+
+/// Returns `true` if areas need a performance HUD, i.e., if it is shown
+/// with `--hud` or may be toggled with the `hud` control command.
+fn has_hud(app_data: &AppData) -> bool {
+    app_data.cli_config.hud || app_data.cli_config.daemon
+}
 
-    let has_name = !name.is_empty();
-    let has_author = !author.is_empty();
+/// Adds a performance HUD over `area`, if needed.
+fn add_hud(app_data: &AppData, area: &gtk::GLArea, overlay: &gtk::Overlay) {
+    if !has_hud(app_data) {
+        return;
+    }
+
+    let hud = Hud::new();
+    hud.set_visible(app_data.hud_visible);
+    overlay.add_overlay(hud.widget());
+    get_data!(area, AreaData, as_mut()).hud = Some(hud);
+}
 
-    if !has_name && !has_author {
+/// Creates a text widget for displaying shader info, laid out according
+/// to `style`. The first line is the title.
+fn create_info_widget(lines: &[String], style: &OverlayStyle) -> Option<gtk::Widget> {
+    if lines.is_empty() {
         return None;
     }
 
     let container = gtk::Box::new(gtk::Orientation::Vertical, 2);
     container.set_opacity(1.0);
 
-    fn create_text_element(text: &str, font_size: i32, is_bold: bool) -> gtk::Widget {
+    let (halign, valign) = match style.corner {
+        OverlayCorner::TopLeft => (gtk::Align::Start, gtk::Align::Start),
+        OverlayCorner::TopRight => (gtk::Align::End, gtk::Align::Start),
+        OverlayCorner::BottomLeft => (gtk::Align::Start, gtk::Align::End),
+        OverlayCorner::BottomRight => (gtk::Align::End, gtk::Align::End),
+    };
+
+    let create_text_element = |text: &str, font_size: f32, is_bold: bool| -> gtk::Widget {
         let fixed = gtk::Fixed::new();
 
-        fn create_label(text: &str, font_size: i32, is_bold: bool, color: &str) -> gtk::Label {
+        let create_label = |color: &str| {
             let label = gtk::Label::new(None);
             let weight = if is_bold { "bold" } else { "normal" };
+            let font = if style.font.is_empty() {
+                format!("{font_size}")
+            } else {
+                format!("{} {font_size}", style.font)
+            };
             label.set_markup(&format!(
                 r#"<span font="{}" font_weight="{}" foreground="{}">{}</span>"#,
-                font_size,
+                glib::markup_escape_text(&font),
                 weight,
-                color,
+                glib::markup_escape_text(color),
                 glib::markup_escape_text(text)
             ));
             label
-        }
+        };
 
         // Shadow layers
         for i in (1..=3).rev() {
-            let shadow = create_label(text, font_size, is_bold, "black");
+            let shadow = create_label(&style.shadow_color);
             shadow.set_opacity(0.3 / i as f64);
             fixed.put(&shadow, i as f64, i as f64);
         }
 
         // Foreground text
-        let foreground = create_label(text, font_size, is_bold, "white");
+        let foreground = create_label(&style.color);
         fixed.put(&foreground, 0.0, 0.0);
 
+        // Lines are aligned to the side of the corner
+        fixed.set_halign(halign);
         fixed.upcast()
-    }
-
-    if has_name {
-        let name_widget = create_text_element(name, NAME_FONT_SIZE_PT, true);
-        container.append(&name_widget);
-    }
+    };
 
-    if has_author {
-        let author_widget =
-            create_text_element(&format!("by {author}"), AUTHOR_FONT_SIZE_PT, false);
-        container.append(&author_widget);
+    for (i, line) in lines.iter().enumerate() {
+        let widget = if i == 0 {
+            create_text_element(line, style.title_size, true)
+        } else {
+            create_text_element(line, style.text_size, false)
+        };
+        container.append(&widget);
     }
 
-    container.set_halign(gtk::Align::Start);
-    container.set_valign(gtk::Align::End);
-    container.set_margin_start(MARGIN);
-    container.set_margin_bottom(MARGIN);
+    container.set_halign(halign);
+    container.set_valign(valign);
+    container.set_margin_start(style.margin_x);
+    container.set_margin_end(style.margin_x);
+    container.set_margin_top(style.margin_y);
+    container.set_margin_bottom(style.margin_y);
     container.set_hexpand(false);
     container.set_vexpand(false);
 
     Some(container.upcast())
 }
 
-/// Sets up a fade-out animation for the given widget.
-fn setup_fadeout_timer(widget: &gtk::Widget) {
-    const AFTER_SECS: u32 = 10;
+/// Sets up a fade-out animation for the given widget, starting after
+/// `duration`. A zero duration keeps the widget visible.
+fn setup_fadeout_timer(widget: &gtk::Widget, duration: Duration) {
     const FADE_SECS: u32 = 2;
     const FADE_FPS: u64 = 60;
 
+    if duration.is_zero() {
+        return;
+    }
+
     let widget_clone = widget.clone();
-    glib::timeout_add_seconds_local(AFTER_SECS, move || {
+    glib::timeout_add_local_once(duration, move || {
         let start_time = std::time::Instant::now();
         let container_clone = widget_clone.clone();
 
@@ -491,31 +2528,38 @@ fn setup_fadeout_timer(widget: &gtk::Widget) {
 
             glib::ControlFlow::Continue
         });
-
-        glib::ControlFlow::Break
     });
 }
 
 /// Creates a GL area and configures its OpenGL settings and signal handlers.
 ///
-/// When `with_input` is `true` (used for the non-layer-shell fallback window)
-/// mouse and keyboard controllers are also attached to the area directly.
-/// In layer-shell mode pass `false` to make the companion transparent
-/// window handle input.
-fn setup_area(app: &gtk::Application, with_input: bool) -> gtk::GLArea {
+/// For the non-layer-shell fallback window, `input_mode` selects the
+/// controllers attached to the area directly. In layer-shell mode pass
+/// [`InputMode::None`] to make the companion transparent window handle
+/// input.
+fn setup_area(app: &gtk::Application, input_mode: InputMode) -> gtk::GLArea {
     let area = gtk::GLArea::new();
 
-    area.set_required_version(GL_VERSION.0, GL_VERSION.1);
+    let gl_api = get_data!(app, AppData, as_ref()).gl_api;
+    let (major, minor) = if gl_api == gdk::GLAPI::GLES {
+        GLES_VERSION
+    } else {
+        GL_VERSION
+    };
+    area.set_allowed_apis(gl_api);
+    area.set_required_version(major, minor);
     area.set_has_depth_buffer(false);
     area.set_has_stencil_buffer(false);
     area.set_auto_render(false);
     area.set_focusable(true);
 
-    if with_input {
-        let app_data = get_data!(app, AppData, as_ref());
+    let app_data = get_data!(app, AppData, as_ref());
+    if input_mode.captures_mouse() {
         app_data
             .mouse_controller
             .setup_widget(&area, Offset::default());
+    }
+    if input_mode.captures_keyboard() {
         app_data.keyboard_controller.setup_widget(&area);
     }
 
@@ -526,16 +2570,38 @@ fn setup_area(app: &gtk::Application, with_input: bool) -> gtk::GLArea {
     area
 }
 
+/// Returns the layer of the render windows, given by the command line
+/// or else by the preset.
+fn window_layer(app_data: &AppData) -> WindowLayer {
+    app_data.cli_config.layer.unwrap_or(app_data.preset.layer)
+}
+
 /// Applies Layer Shell configuration to a render window.
 ///
-/// Render windows sit on [`Layer::Background`], span the full monitor,
-/// claim an exclusive zone so the compositor reserves the entire output,
-/// and intentionally opt out of keyboard focus ([`KeyboardMode::None`]).
-/// Keyboard input is handled instead by the companion transparent input
-/// window created by [`create_input_window`].
-fn setup_layer_shell(window: &gtk::ApplicationWindow) {
+/// Render windows sit on [`Layer::Background`] unless `layer` puts them
+/// above other windows, span the full monitor, claim an exclusive zone so
+/// the compositor reserves the entire output, and intentionally opt out
+/// of keyboard focus ([`KeyboardMode::None`]). Keyboard input is handled
+/// instead by the companion transparent input window created by
+/// [`create_input_window`].
+///
+/// Windows above other windows have an empty input region, so that
+/// clicks go through to the windows below.
+fn setup_layer_shell(window: &gtk::ApplicationWindow, layer: WindowLayer) {
     window.init_layer_shell();
-    window.set_layer(Layer::Background);
+    window.set_layer(match layer {
+        WindowLayer::Background => Layer::Background,
+        WindowLayer::Top => Layer::Top,
+        WindowLayer::Overlay => Layer::Overlay,
+    });
+
+    if layer.is_above_windows() {
+        window.connect_realize(|window| {
+            if let Some(surface) = window.surface() {
+                surface.set_input_region(&cairo::Region::create());
+            }
+        });
+    }
 
     [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom]
         .iter()
@@ -553,18 +2619,34 @@ fn setup_layer_shell(window: &gtk::ApplicationWindow) {
 /// keyboard events only when no regular window is focused over the desktop.
 ///
 /// `exclusive_zone(0)` means the window does not push any panel or dock away.
-/// [`KeyboardMode::OnDemand`] grants keyboard focus when the surface is clicked.
-fn setup_input_layer_shell(window: &gtk::ApplicationWindow) {
+/// [`KeyboardMode::OnDemand`] grants keyboard focus when the surface is
+/// clicked, unless `input_mode` leaves the keyboard uncaptured.
+///
+/// `margins` (top, right, bottom, left) shrink the window to the rendered
+/// region of the monitor.
+fn setup_input_layer_shell(
+    window: &gtk::ApplicationWindow,
+    input_mode: InputMode,
+    margins: [u32; 4],
+) {
     window.init_layer_shell();
     window.set_layer(Layer::Bottom);
 
-    [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom]
-        .iter()
-        .for_each(|&anchor| window.set_anchor(anchor, true));
+    [Edge::Top, Edge::Right, Edge::Bottom, Edge::Left]
+        .into_iter()
+        .zip(margins)
+        .for_each(|(edge, margin)| {
+            window.set_anchor(edge, true);
+            window.set_margin(edge, margin as i32);
+        });
 
     window.set_namespace(Some(APP_NAME));
     window.set_exclusive_zone(0);
-    window.set_keyboard_mode(KeyboardMode::OnDemand);
+    window.set_keyboard_mode(if input_mode.captures_keyboard() {
+        KeyboardMode::OnDemand
+    } else {
+        KeyboardMode::None
+    });
     window.set_decorated(false);
 
     window.connect_is_active_notify(|w| {
@@ -585,14 +2667,20 @@ fn setup_input_layer_shell(window: &gtk::ApplicationWindow) {
 /// The window is registered with the GTK application and is therefore
 /// destroyed automatically by [`on_monitor_changed`] when the monitor
 /// configuration changes.
-fn create_input_window(app: &gtk::Application, monitor: &gdk::Monitor, gl_offset: Offset) {
+fn create_input_window(
+    app: &gtk::Application,
+    monitor: &gdk::Monitor,
+    gl_offset: Offset,
+    input_mode: InputMode,
+    margins: [u32; 4],
+) {
     let window = gtk::ApplicationWindow::builder()
         .application(app)
         .name(APP_NAME)
         .title(format!("{APP_NAME}-input"))
         .build();
 
-    setup_input_layer_shell(&window);
+    setup_input_layer_shell(&window, input_mode, margins);
     window.set_monitor(Some(monitor));
 
     // A DrawingArea that explicitly paints fully transparent.
@@ -605,7 +2693,7 @@ fn create_input_window(app: &gtk::Application, monitor: &gdk::Monitor, gl_offset
     // Note: just setting the window opacity to 0.0 does not work because it
     // also disables the input region.
     let da = gtk::DrawingArea::new();
-    da.set_focusable(true);
+    da.set_focusable(input_mode.captures_keyboard());
     da.set_draw_func(|_, cr, _, _| {
         cr.set_operator(cairo::Operator::Source);
         cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
@@ -620,7 +2708,9 @@ fn create_input_window(app: &gtk::Application, monitor: &gdk::Monitor, gl_offset
     // sibling render GLArea so coordinate spaces match exactly.
     let app_data = get_data!(app, AppData, as_ref());
     app_data.mouse_controller.setup_widget(&da, gl_offset);
-    app_data.keyboard_controller.setup_widget(&da);
+    if input_mode.captures_keyboard() {
+        app_data.keyboard_controller.setup_widget(&da);
+    }
 
     log::debug!(
         "Input window created for monitor {:?} gl_offset={:?}",
@@ -632,7 +2722,8 @@ fn create_input_window(app: &gtk::Application, monitor: &gdk::Monitor, gl_offset
 }
 
 /// Installs a CSS rule that strips the GTK/GSK background from
-/// `.shaderbg-input` windows.
+/// `.shaderbg-input` windows and from the render windows of transparent
+/// presets (`.shaderbg-transparent`).
 ///
 /// In GTK4 the GSK renderer draws widget backgrounds before the Cairo draw
 /// function runs, so the draw function alone cannot suppress the window
@@ -645,7 +2736,8 @@ fn ensure_transparent_css() {
     CSS_APPLIED.call_once(|| {
         let provider = gtk::CssProvider::new();
         provider.load_from_string(
-            ".shaderbg-input, .shaderbg-input * { background: transparent; box-shadow: none; }",
+            ".shaderbg-input, .shaderbg-input *, .shaderbg-transparent { \
+             background: transparent; box-shadow: none; }",
         );
         if let Some(display) = gdk::Display::default() {
             gtk::style_context_add_provider_for_display(
@@ -657,35 +2749,79 @@ fn ensure_transparent_css() {
     });
 }
 
-/// Configures frame scheduling based on preset timing parameters.
-fn setup_animation_driver(app: &gtk::Application) {
+/// Removes every source driving frame production.
+fn stop_animation_driver(app: &gtk::Application) {
     let app_data = get_data!(app, AppData, as_mut());
     if let Some(source_id) = app_data.animation_timer.take() {
         source_id.remove();
     }
+    for tick_callback in app_data.tick_callbacks.drain(..) {
+        tick_callback.remove();
+    }
+}
 
-    if app_data.cli_config.preset.interval_between_frames.is_zero() {
-        // Continuous
-        let areas = &app_data.areas;
-        if app_data.frame_controller.current_monitor() == 0
-            && areas.iter().all(|area| area.is_realized())
-        {
-            for area in areas {
-                area.add_tick_callback(glib::clone!(
-                    #[strong]
-                    area,
-                    move |_, _| {
-                        area.queue_render();
-                        glib::ControlFlow::Continue
+/// Configures frame scheduling based on preset timing parameters.
+///
+/// Does nothing while the [`FrameController`] is paused.
+fn setup_animation_driver(app: &gtk::Application) {
+    stop_animation_driver(app);
+
+    let app_data = get_data!(app, AppData, as_mut());
+    if app_data.frame_controller.is_paused() {
+        return;
+    }
+
+    let areas = &app_data.areas;
+    let areas_ready = app_data.frame_controller.current_monitor() == 0
+        && areas.iter().all(|area| area.is_realized());
+
+    if app_data.frame_controller.is_frame_paced() {
+        // Capped or throttled by a short interval: a single frame clock
+        // paces all areas, so that frames are aligned to the display
+        // refresh and every monitor presents the same logical frame.
+        if !areas_ready {
+            return;
+        }
+
+        if let Some(area) = areas.first() {
+            let tick_callback = area.add_tick_callback(glib::clone!(
+                #[weak]
+                app,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move |_, frame_clock| {
+                    let app_data = get_data!(app, AppData, as_mut());
+                    let frame_time = Duration::from_micros(frame_clock.frame_time() as u64);
+                    if app_data.frame_controller.is_frame_due(frame_time) {
+                        areas_queue_render(&app);
                     }
-                ));
-            }
+                    glib::ControlFlow::Continue
+                }
+            ));
+            app_data.tick_callbacks.push(tick_callback);
+        }
+    } else if app_data.preset.interval_between_frames.is_zero() {
+        // Continuous
+        if !areas_ready {
+            return;
+        }
+
+        for area in areas {
+            let tick_callback = area.add_tick_callback(glib::clone!(
+                #[strong]
+                area,
+                move |_, _| {
+                    area.queue_render();
+                    glib::ControlFlow::Continue
+                }
+            ));
+            app_data.tick_callbacks.push(tick_callback);
         }
-    } else if app_data.cli_config.preset.crossfade_overlap_ratio > 0.0 {
+    } else if app_data.preset.crossfade_overlap_ratio > 0.0 {
         // Continuous during crossfade, throttled otherwise
         cross_fade(app);
     } else {
-        // Throttled
+        // Throttled by a long interval
         let tick_callback = glib::clone!(
             #[weak]
             app,
@@ -695,10 +2831,8 @@ fn setup_animation_driver(app: &gtk::Application) {
                 glib::ControlFlow::Continue
             }
         );
-        let source_id = glib::timeout_add_local(
-            app_data.cli_config.preset.interval_between_frames,
-            tick_callback,
-        );
+        let source_id =
+            glib::timeout_add_local(app_data.preset.interval_between_frames, tick_callback);
         app_data.animation_timer = Some(source_id);
     }
 }
@@ -778,6 +2912,13 @@ fn on_realize(area: &gtk::GLArea) {
 
     if let Some(err) = area.error() {
         log::error!("{err}");
+
+        // A context that worked before is likely being reset
+        if LOAD_GL.is_completed() {
+            recover_from_context_loss(&get_app_from_area(area));
+            return;
+        }
+
         let (minor, major) = area.required_version();
         log::error!("OpenGL {minor}.{major} required");
         std::process::exit(1);
@@ -793,13 +2934,63 @@ fn on_realize(area: &gtk::GLArea) {
             std::process::exit(1);
         }
         log_glsl_version();
+        set_gl_profile(GlProfile::from_context(
+            gl_context.api() == gdk::GLAPI::GLES,
+            gl_context.version(),
+        ));
     });
+
+    // The compositor may announce a fractional scale after the first
+    // resize, or change it when the monitor is reconfigured. The GL
+    // framebuffer size may not change in that case, so rebuild the
+    // renderer at the last known size.
+    if let Some(surface) = area.native().and_then(|native| native.surface()) {
+        surface.connect_scale_notify(glib::clone!(
+            #[weak]
+            area,
+            move |surface| {
+                let viewport_size = get_data!(area, AreaData, as_ref()).viewport_size;
+                if area.is_realized() && viewport_size != Size::default() {
+                    log::debug!("Surface scale changed to {}", surface.scale());
+                    on_resize(
+                        &area,
+                        viewport_size.width() as i32,
+                        viewport_size.height() as i32,
+                    );
+                }
+            }
+        ));
+    }
+}
+
+/// Creates and realizes an OpenGL context for the display.
+///
+/// Desktop OpenGL [`GL_VERSION`] is preferred. When the driver cannot
+/// provide it (e.g., on ARM boards and older integrated GPUs), an
+/// OpenGL ES [`GLES_VERSION`] or later context is created instead.
+pub fn create_gl_context(display: &gdk::Display) -> Result<gdk::GLContext, glib::Error> {
+    let realize = |api: gdk::GLAPI, (major, minor): (i32, i32)| {
+        let gl_context = display.create_gl_context()?;
+        gl_context.set_allowed_apis(api);
+        gl_context.set_required_version(major, minor);
+        gl_context.realize()?;
+        Ok(gl_context)
+    };
+
+    realize(gdk::GLAPI::GL, GL_VERSION).or_else(|err| {
+        log::warn!(
+            "OpenGL {}.{} unavailable ({err}); falling back to OpenGL ES",
+            GL_VERSION.0,
+            GL_VERSION.1
+        );
+        realize(gdk::GLAPI::GLES, GLES_VERSION)
+    })
 }
 
 /// Loads OpenGL function pointers via libepoxy.
 ///
 /// Required because GTK does not expose GL symbol loading.
-fn load_gl_functions() -> Result<(), Box<dyn std::error::Error>> {
+pub fn load_gl_functions() -> Result<(), Box<dyn std::error::Error>> {
     let library = unsafe {
         libloading::os::unix::Library::new("libepoxy.so.0")
             .map_err(|err| format!("Failed to load libepoxy.so.0: {}", err))?
@@ -866,15 +3057,40 @@ fn on_resize(area: &gtk::GLArea, width: i32, height: i32) {
 
     if !app_data.layer_shell_supported {
         let monitor_count = app_data.screen_controller.selected_monitors().len();
-        app_data.frame_controller =
-            FrameController::new(&app_data.cli_config.preset, monitor_count);
+        let previous_frame_controller = std::mem::replace(
+            &mut app_data.frame_controller,
+            FrameController::new(&app_data.preset, monitor_count)
+                .with_time_source(app_data.cli_config.time_source)
+                .with_date(app_data.cli_config.date),
+        );
+        app_data
+            .frame_controller
+            .inherit_pause_reasons(&previous_frame_controller);
     }
 
-    let viewport_size = Size::new(width as u32, height as u32);
+    area_data.viewport_size = Size::new(width as u32, height as u32);
+    create_renderer(area);
+}
+
+/// Starts creating the renderer of the area for its current size,
+/// replacing the current renderer once its shaders are compiled.
+///
+/// The OpenGL context of the area must be current.
+fn create_renderer(area: &gtk::GLArea) {
+    let area_data = get_data!(area, AreaData, as_mut());
+    let app = get_app_from_area(area);
+    let app_data = get_data!(app, AppData, as_mut());
+    let viewport_size = area_data.viewport_size;
+
+    // Physical pixels per logical pixel, fractional on HiDPI monitors
+    let pixel_scale = area
+        .native()
+        .and_then(|native| native.surface())
+        .map_or(1.0, |surface| surface.scale()) as f32;
 
     if !area_data.connector.is_empty() {
         log::debug!(
-            "{:?}, {:?}, {:?}",
+            "{:?}, {:?}, {:?}, scale {pixel_scale}",
             area_data.connector,
             area_data.bounds,
             area_data.gl_offset
@@ -896,17 +3112,384 @@ fn on_resize(area: &gtk::GLArea, width: i32, height: i32) {
         _ => area_size,
     };
 
-    let renderer = Renderer::new(
-        screen_size,
-        viewport_size,
-        area_size,
-        &app_data.cli_config.preset,
+    // Cloned monitors fitting a shared frame all render it at the same
+    // size, and the renderer letterboxes or crops it when blitting
+    let (screen_size, area_size) = match app_data.screen_controller.cloned_frame_size() {
+        Some(frame_size)
+            if app_data.layer_shell_supported && app_data.preset.clone_fit != CloneFit::Stretch =>
+        {
+            (frame_size, frame_size)
+        }
+        _ => (screen_size, area_size),
+    };
+
+    // Regions are rendered independently on each monitor
+    let (screen_size, area_size) = match app_data.preset.layout_mode {
+        LayoutMode::Region => {
+            let region_size = app_data.preset.region.size_within(area_size, 1.0);
+            (region_size, region_size)
+        }
+        _ => (screen_size, area_size),
+    };
+
+    let scaled_preset;
+    let preset = match app_data.resolution_controller.as_mut() {
+        Some(controller) => {
+            // The new renderer needs some time to settle before measuring it
+            controller.restart();
+            scaled_preset = Preset {
+                resolution_scale: controller.scale(),
+                ..app_data.preset.clone()
+            };
+            &scaled_preset
+        }
+        None => &app_data.preset,
+    };
+
+    // GL areas are composited with their alpha, so transparent presets
+    // only need to strip the window background
+    if let Some(window) = area.root() {
+        if preset.transparent {
+            ensure_transparent_css();
+            window.add_css_class("shaderbg-transparent");
+        } else {
+            window.remove_css_class("shaderbg-transparent");
+        }
+    }
+
+    match Renderer::begin(screen_size, viewport_size, area_size, pixel_scale, preset) {
+        Ok(renderer) => area_data.pending_renderer = Some(renderer),
+        Err(err) => {
+            on_renderer_error(area, &err);
+            return;
+        }
+    }
+
+    // The previous renderer, if any, keeps presenting frames meanwhile
+    glib::timeout_add_local(
+        PENDING_RENDERER_POLL_INTERVAL,
+        glib::clone!(
+            #[weak]
+            area,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                area.make_current();
+                let area_data = get_data!(area, AreaData, as_ref());
+                match &area_data.pending_renderer {
+                    Some(renderer) if !renderer.is_ready() => glib::ControlFlow::Continue,
+                    _ => {
+                        area.queue_render();
+                        glib::ControlFlow::Break
+                    }
+                }
+            }
+        ),
     );
-    if let Err(err) = &renderer {
-        log::error!("Failed to create renderer: {err}");
+}
+
+/// Handles a failure to create the renderer of an area.
+///
+/// Renderers are recreated only if the OpenGL context was lost. Other
+/// errors, such as a shader edit that fails to compile, keep the previous
+/// renderer of the area running, and exit only if there is none to keep,
+/// unless the session is locked.
+fn on_renderer_error(area: &gtk::GLArea, err: &shader::ShaderError) {
+    log::error!("Failed to create renderer: {err}");
+
+    let app = get_app_from_area(area);
+    if area.error().is_some() || is_context_lost() {
+        recover_from_context_loss(&app);
+        return;
+    }
+
+    let area_data = get_data!(area, AreaData, as_mut());
+
+    // A transition to the failed preset has nothing to fade in
+    if area_data
+        .transition
+        .as_ref()
+        .is_some_and(|transition| transition.renderer.is_none())
+    {
+        area_data.transition = None;
+    }
+
+    let app_data = get_data!(app, AppData, as_ref());
+    let locked = app_data
+        .session_lock
+        .as_ref()
+        .is_some_and(SessionLock::is_locked);
+    if area_data.renderer.is_none() && !locked {
         std::process::exit(1);
     }
-    area_data.renderer = renderer.ok();
+}
+
+/// Tears down every renderer and recreates the windows, and with them
+/// their OpenGL contexts, after a GPU reset.
+///
+/// While the session is locked, the lock surfaces are kept and only
+/// their areas are replaced. Exits after [`MAX_CONTEXT_RECOVERY_ATTEMPTS`]
+/// recoveries in a row that failed to produce a renderer, except on the
+/// lock screen, which is left blank with its password entry instead.
+fn recover_from_context_loss(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+    if app_data.context_recovery_pending {
+        return;
+    }
+
+    let locked = app_data
+        .session_lock
+        .as_ref()
+        .is_some_and(SessionLock::is_locked);
+
+    if app_data.context_recovery_attempts >= MAX_CONTEXT_RECOVERY_ATTEMPTS {
+        log::error!(
+            "Giving up after {MAX_CONTEXT_RECOVERY_ATTEMPTS} attempts to recreate the renderer"
+        );
+        if !locked {
+            std::process::exit(1);
+        }
+
+        // Exiting would leave the session locked without a client
+        log::warn!("Showing a blank lock screen");
+        app_data.context_recovery_pending = true;
+        for area in &app_data.areas {
+            let area_data = get_data!(area, AreaData, as_mut());
+            area_data.renderer = None;
+            area_data.pending_renderer = None;
+            area_data.transition = None;
+        }
+        return;
+    }
+
+    log::warn!("OpenGL context lost");
+    app_data.context_recovery_attempts += 1;
+    app_data.context_recovery_pending = true;
+    log::warn!(
+        "Recreating renderers (attempt {}/{MAX_CONTEXT_RECOVERY_ATTEMPTS})",
+        app_data.context_recovery_attempts
+    );
+
+    for area in &app_data.areas {
+        area.make_current();
+        let area_data = get_data!(area, AreaData, as_mut());
+        area_data.renderer = None;
+        area_data.pending_renderer = None;
+        area_data.transition = None;
+    }
+
+    glib::timeout_add_local_once(
+        CONTEXT_RECOVERY_DELAY,
+        glib::clone!(
+            #[weak]
+            app,
+            move || {
+                get_data!(app, AppData, as_mut()).context_recovery_pending = false;
+                if locked {
+                    replace_lock_screen_areas(&app);
+                } else {
+                    on_monitor_changed(app);
+                }
+            }
+        ),
+    );
+}
+
+/// Replaces the area of each lock screen window by a new one, with a new
+/// OpenGL context, keeping the windows, since lock surfaces cannot be
+/// recreated while the session is locked.
+fn replace_lock_screen_areas(app: &gtk::Application) {
+    let app_data = get_data!(app, AppData, as_mut());
+
+    for old_area in std::mem::take(&mut app_data.areas) {
+        let old_data = get_data!(old_area, AreaData, as_mut());
+        let area = setup_area(app, InputMode::None);
+        set_data!(
+            area,
+            AreaData {
+                connector: std::mem::take(&mut old_data.connector),
+                bounds: old_data.bounds,
+                gl_offset: old_data.gl_offset,
+                info_overlay: old_data.info_overlay.take(),
+                hud: old_data.hud.take(),
+                ..Default::default()
+            }
+        );
+
+        // Lock screen areas are always the child of an overlay holding
+        // the password entry
+        if let Some(overlay) = old_area
+            .parent()
+            .and_then(|parent| parent.downcast::<gtk::Overlay>().ok())
+        {
+            overlay.set_child(Some(&area));
+        }
+        app_data.areas.push(area);
+    }
+
+    setup_animation_driver(app);
+}
+
+/// Replaces the preset whose frame the GPU watchdog found unfinished by
+/// the default preset, and excludes it from preset switches for the rest
+/// of the session.
+///
+/// `switch_count` is the preset switch count when the hang was found.
+/// Nothing is done if the preset was replaced since, e.g., by an abort
+/// already queued by an earlier frame.
+fn abort_hung_preset(app: &gtk::Application, switch_count: u64) {
+    let app_data = get_data!(app, AppData, as_mut());
+    let Some(timeout) = app_data.gpu_watchdog_timeout else {
+        return;
+    };
+    if app_data.preset_switch_count != switch_count {
+        return;
+    }
+
+    let preset = match &app_data.cli_config.preset_path {
+        Some(path) => path.display().to_string(),
+        None => app_data.cli_config.preset.name.clone(),
+    };
+    log::error!(
+        "GPU watchdog: a frame of {preset} did not finish within {}, switching to the default preset",
+        humantime_serde::re::humantime::format_duration(timeout)
+    );
+    let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    app_data.gpu_hangs.retain(|(hung, _)| *hung != preset);
+    app_data.gpu_hangs.push((preset, time));
+
+    app_data.preset_switch_count += 1;
+    app_data.preset_monitor = None;
+    if let Some(path) = app_data.cli_config.preset_path.take() {
+        app_data.cli_config.preset_filter.excluded_files.push(path);
+    }
+    apply_preset(app, Preset::with_serde_defaults());
+    on_monitor_changed(app.clone());
+}
+
+/// Replaces the renderer of the area with its pending renderer once
+/// shader compilation has finished.
+fn install_pending_renderer(area: &gtk::GLArea) {
+    let area_data = get_data!(area, AreaData, as_mut());
+    if !area_data
+        .pending_renderer
+        .as_ref()
+        .is_some_and(PendingRenderer::is_ready)
+    {
+        return;
+    }
+
+    let Some(renderer) = area_data.pending_renderer.take() else {
+        return;
+    };
+
+    let app = get_app_from_area(area);
+    let mut renderer = match renderer.finish() {
+        Ok(renderer) => renderer,
+        Err(err) => {
+            on_renderer_error(area, &err);
+            return;
+        }
+    };
+
+    let app_data = get_data!(app, AppData, as_mut());
+    let transition = area_data
+        .transition
+        .as_mut()
+        .filter(|transition| transition.renderer.is_none());
+
+    match transition {
+        // The renderer of the previous preset keeps rendering under the
+        // new one until it has faded in, but its sound stops before the
+        // sound of the new one starts
+        Some(transition) => {
+            transition.renderer = area_data.renderer.take();
+            transition.fade_start = Some(Instant::now());
+            if let Some(previous) = transition.renderer.as_mut() {
+                previous.stop_sound();
+            }
+
+            // The windows are kept, so their overlay must describe the new preset
+            if app_data.cli_config.show_overlay && area_data.gl_offset == Offset::default() {
+                let preset = &app_data.cli_config.preset;
+                let style = preset.overlay_style();
+                set_info_overlay(area, &style.lines(preset), &style);
+            }
+        }
+        None => {
+            // Simulations carry on from the state of the previous renderer,
+            // or of the renderer of the window this area replaced
+            if app_data.preset.preserve_buffers {
+                if let Some(previous) = area_data.renderer.as_ref() {
+                    previous.copy_buffers_to(&renderer);
+                } else if !area_data.buffer_snapshots.is_empty() {
+                    renderer.restore_buffers(&area_data.buffer_snapshots);
+                }
+            }
+            area_data.buffer_snapshots.clear();
+
+            // Stop the sound of the previous renderer before starting the new one
+            area_data.renderer = None;
+        }
+    }
+
+    app_data.context_recovery_attempts = 0;
+
+    // Sound is played once, by the renderer of the first area
+    if app_data.areas.first() == Some(area) {
+        renderer.start_sound(&app_data.preset);
+    }
+
+    if app_data.frame_controller.is_paused() {
+        renderer.set_media_paused(true);
+    }
+
+    renderer.set_dimming(app_data.dimming);
+    renderer.set_profiling(is_profiling(app_data));
+    area_data.renderer = Some(renderer);
+
+    if area_data.frame_snapshot.is_some() {
+        area_data.frame_snapshot_fade_start = Some(Instant::now());
+    }
+}
+
+/// Ends the preset transition of `area` once the new preset has been
+/// fully revealed, dropping the renderer of the previous preset.
+///
+/// Returns `true` while the transition is in progress.
+fn update_preset_transition(area: &gtk::GLArea) -> bool {
+    let area_data = get_data!(area, AreaData, as_mut());
+    let Some(transition) = &area_data.transition else {
+        return false;
+    };
+    if transition.progress() < 1.0 {
+        return true;
+    }
+
+    area_data.transition = None;
+    false
+}
+
+/// Draws the last frame of the window replaced by `area` over its
+/// output, fading it out once the new renderer is ready.
+///
+/// Returns `true` while the fade is in progress.
+fn draw_frame_snapshot(area: &gtk::GLArea) -> bool {
+    let area_data = get_data!(area, AreaData, as_mut());
+    let Some(snapshot) = area_data.frame_snapshot.as_mut() else {
+        return false;
+    };
+
+    let opacity = area_data.frame_snapshot_fade_start.map_or(1.0, |start| {
+        1.0 - start.elapsed().as_secs_f32() / WINDOW_CROSSFADE_DURATION.as_secs_f32()
+    });
+    if opacity <= 0.0 {
+        area_data.frame_snapshot = None;
+        return false;
+    }
+
+    snapshot.draw(area_data.viewport_size, opacity);
+    area_data.frame_snapshot_fade_start.is_some()
 }
 
 /// Main render callback executed for each [`gtk::GLArea`].
@@ -917,8 +3500,22 @@ fn on_resize(area: &gtk::GLArea, width: i32, height: i32) {
 fn on_render(area: &gtk::GLArea, gl_context: &gdk::GLContext) -> glib::Propagation {
     gl_context.make_current();
 
+    if area.error().is_some() || is_context_lost() {
+        recover_from_context_loss(&get_app_from_area(area));
+        return glib::Propagation::Stop;
+    }
+
+    let app = get_app_from_area(area);
+    let app_data = get_data!(app, AppData, as_mut());
+
+    // Retired windows keep their last frame until destroyed
+    if !app_data.areas.contains(area) {
+        return glib::Propagation::Stop;
+    }
+
+    install_pending_renderer(area);
+
     let area_data = get_data!(area, AreaData, as_ref());
-    let app_data = get_data!(get_app_from_area(area), AppData, as_mut());
 
     log::trace!(
         "{} {}: frame_hw={}",
@@ -927,41 +3524,202 @@ fn on_render(area: &gtk::GLArea, gl_context: &gdk::GLContext) -> glib::Propagati
         area.frame_clock().unwrap().frame_counter(),
     );
 
+    // The first area is the one paced by the frame clock
+    if app_data.areas.first() == Some(area) {
+        if let Some(frame_clock) = area.frame_clock() {
+            record_presentation_latency(&mut app_data.frame_controller, &frame_clock);
+        }
+    }
+
     app_data.frame_controller.render(
         |frame_stats| {
             let input = InputData {
                 mouse: app_data.mouse_controller.snapshot(),
                 keyboard: app_data.keyboard_controller.snapshot(),
+                system_stats: app_data
+                    .system_stats
+                    .as_ref()
+                    .map(SystemStatsSampler::stats)
+                    .unwrap_or_default(),
+                audio_bands: app_data
+                    .audio_spectrum
+                    .as_ref()
+                    .map_or([0.0; MAX_AUDIO_BANDS], AudioSpectrum::bands),
+                desktop: app_data
+                    .desktop_monitor
+                    .as_ref()
+                    .map(DesktopMonitor::state)
+                    .unwrap_or_default(),
+                media: app_data
+                    .media_monitor
+                    .as_ref()
+                    .map(MediaMonitor::data)
+                    .unwrap_or_default(),
             };
 
             // Render all areas
             for area in &app_data.areas {
                 let area_data = get_data!(area, AreaData, as_mut());
+                if area_data.powered_off {
+                    continue;
+                }
+
+                if let Some(timeout) = app_data.gpu_watchdog_timeout {
+                    let watchdog = area_data
+                        .gpu_watchdog
+                        .get_or_insert_with(|| GpuWatchdog::new(timeout));
+                    if !watchdog.poll() {
+                        // Issuing more commands would only prolong the hang
+                        let switch_count = app_data.preset_switch_count;
+                        glib::idle_add_local_once(glib::clone!(
+                            #[weak]
+                            app,
+                            move || abort_hung_preset(&app, switch_count)
+                        ));
+                        return;
+                    }
+                }
 
                 if app_data.cli_config.show_overlay && frame_stats.frame_number == 0 {
                     if let Some(widget) = &area_data.info_overlay {
-                        setup_fadeout_timer(widget);
+                        let duration = app_data.cli_config.preset.overlay_style().duration;
+                        setup_fadeout_timer(widget, duration);
+                    }
+                }
+
+                // The previous preset carries on from its last frame during
+                // a preset transition. Until the new renderer is ready, the
+                // area renderer still belongs to the previous preset.
+                let mut renderer_frame_stats = frame_stats;
+                let previous_frame_stats;
+                if let Some(transition) = area_data.transition.as_mut() {
+                    previous_frame_stats = transition.previous_frame_stats(frame_stats);
+                    match transition.renderer.as_mut() {
+                        Some(previous) => {
+                            previous.render(area_data.gl_offset, &input, &previous_frame_stats)
+                        }
+                        None => renderer_frame_stats = &previous_frame_stats,
                     }
                 }
 
                 if let Some(renderer) = area_data.renderer.as_mut() {
-                    renderer.render(area_data.gl_offset, &input, frame_stats);
+                    renderer.render(area_data.gl_offset, &input, renderer_frame_stats);
+                    if let Some(watchdog) = area_data.gpu_watchdog.as_mut() {
+                        watchdog.end_frame();
+                    }
+
+                    if let Some(hud) = area_data.hud.as_ref().filter(|_| app_data.hud_visible) {
+                        hud.update(&HudStats {
+                            frame_rate: frame_stats.frame_rate,
+                            latency: frame_stats.latency,
+                            resolution: renderer.resolution(),
+                            pass_times: &renderer.pass_times(),
+                        });
+                    }
+                }
+            }
+
+            if let Some(controller) = app_data.resolution_controller.as_mut() {
+                let gpu_time = total_gpu_time(&app_data.areas);
+                if let Some(scale) =
+                    controller.update(Instant::now(), frame_stats.frame_rate, gpu_time)
+                {
+                    log::info!("Changing resolution scale to {scale:.2}");
+                    glib::idle_add_local_once(glib::clone!(
+                        #[weak]
+                        app,
+                        move || recreate_renderers(&app)
+                    ));
                 }
             }
 
             app_data.keyboard_controller.end_frame();
+            app_data.last_frame_stats = Some(frame_stats.clone());
         },
         |crossfade_t| {
             // Blit current area
-            if let Some(renderer) = area_data.renderer.as_ref() {
-                renderer.blit(crossfade_t);
+            let area_data = get_data!(area, AreaData, as_mut());
+            let Some(renderer) = area_data.renderer.as_ref() else {
+                return;
+            };
+            let progress = area_data
+                .transition
+                .as_ref()
+                .map_or(1.0, PresetTransition::progress);
+            match area_data.transition.as_mut() {
+                Some(PresetTransition {
+                    renderer: Some(previous),
+                    effect: Some(effect),
+                    ..
+                }) => effect.draw(
+                    area_data.viewport_size,
+                    progress,
+                    crossfade_t,
+                    previous,
+                    renderer,
+                ),
+                Some(PresetTransition {
+                    renderer: Some(previous),
+                    ..
+                }) => {
+                    previous.blit(crossfade_t);
+                    renderer.blit_over(crossfade_t, progress);
+                }
+                _ => renderer.blit(crossfade_t),
             }
         },
     );
 
+    let transitioning = update_preset_transition(area);
+    if draw_frame_snapshot(area) || transitioning {
+        areas_queue_render(&app);
+    }
+
+    get_data!(area, AreaData, as_mut()).presented = true;
+    if !app_data.retired_windows.is_empty()
+        && app_data
+            .areas
+            .iter()
+            .all(|area| get_data!(area, AreaData, as_ref()).presented)
+    {
+        // Destroy them once the new frames reach the compositor
+        glib::idle_add_local_once(glib::clone!(
+            #[weak]
+            app,
+            move || destroy_retired_windows(&app)
+        ));
+    }
+
     glib::Propagation::Stop
 }
 
+/// Records the presentation latency of the latest frame of `frame_clock`
+/// whose timings are complete, if the compositor reported when it was
+/// presented.
+fn record_presentation_latency(
+    frame_controller: &mut FrameController,
+    frame_clock: &gdk::FrameClock,
+) {
+    let frame_counter = frame_clock.frame_counter();
+    let Some(timings) = (frame_clock.history_start()..frame_counter)
+        .rev()
+        .filter_map(|counter| frame_clock.timings(counter))
+        .find(gdk::FrameTimings::is_complete)
+    else {
+        return;
+    };
+
+    frame_controller.record_presentation(
+        timings.frame_counter(),
+        timings.frame_time(),
+        timings.presentation_time(),
+    );
+    log::trace!(
+        "Presentation latency: {:?}",
+        frame_controller.presentation_latency()
+    );
+}
+
 /// Gets the application from a [`gtk::GLArea`], assuming
 /// it's contained in a [`gtk::Window`].
 /// Panics if the [`gtk::GLArea`] isn't in a [`gtk::Window`] or
@@ -0,0 +1,239 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Audio spectrum exposed to shaders through `iAudioBands`.
+//!
+//! Captures the audio played by the default output device through a
+//! `pw-cat` child process, in the manner of Cava, and splits its spectrum
+//! into logarithmically spaced bands from 50 Hz to 16 kHz. The spectrum
+//! is computed on a dedicated thread after every block of samples, so
+//! the render loop only copies the latest smoothed bands.
+
+use std::{
+    f32::consts::PI,
+    io::{self, Read},
+    process::{Child, ChildStdout, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+use thiserror::Error;
+
+/// Size of the `iAudioBands` uniform array.
+pub const MAX_AUDIO_BANDS: usize = 64;
+
+/// Values of the `iAudioBands` uniform.
+pub type AudioBands = [f32; MAX_AUDIO_BANDS];
+
+/// Capture sample rate.
+const SAMPLE_RATE: u32 = 44100;
+
+/// Number of samples transformed, giving a resolution of about 21.5 Hz.
+const FFT_SIZE: usize = 2048;
+
+/// Number of new samples between spectrum updates (about 86 per second).
+const HOP_SIZE: usize = 512;
+
+/// Frequency range covered by the bands, in Hz.
+const MIN_FREQUENCY: f32 = 50.0;
+const MAX_FREQUENCY: f32 = 16000.0;
+
+/// Band levels, in decibels relative to a full-scale sine, mapped to 0.
+/// Louder levels map linearly up to 1 at 0 dB.
+const MIN_LEVEL_DB: f32 = -60.0;
+
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("Failed to start pw-cat: {0}")]
+    Spawn(#[from] io::Error),
+}
+
+/// Captures the output audio and keeps its smoothed band levels.
+pub struct AudioSpectrum {
+    /// Number of bands computed.
+    band_count: usize,
+
+    /// Fraction of the previous level kept at each update.
+    smoothing: f32,
+
+    /// Latest band levels, written by the reader thread.
+    bands: Arc<Mutex<AudioBands>>,
+
+    /// `pw-cat` capture process.
+    child: Child,
+
+    /// Thread reading samples from the stdout of `pw-cat`.
+    reader: Option<JoinHandle<()>>,
+}
+
+impl AudioSpectrum {
+    /// Starts capturing the monitor of the default output device.
+    ///
+    /// `band_count` is limited to [`MAX_AUDIO_BANDS`], and `smoothing` in
+    /// `[0, 1]` is the fraction of the previous level of a band kept at
+    /// each update.
+    pub fn new(band_count: usize, smoothing: f32) -> Result<Self, AudioError> {
+        let band_count = band_count.clamp(1, MAX_AUDIO_BANDS);
+        let smoothing = smoothing.clamp(0.0, 1.0);
+
+        let mut child = Command::new("pw-cat")
+            .args([
+                "--record",
+                "--raw",
+                "--format",
+                "f32",
+                "--rate",
+                &SAMPLE_RATE.to_string(),
+                "--channels",
+                "1",
+                "-P",
+                "{ stream.capture.sink = true }",
+                "-",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("pw-cat stdout unavailable"))?;
+
+        let bands = Arc::new(Mutex::new([0.0; MAX_AUDIO_BANDS]));
+        let reader = std::thread::spawn({
+            let bands = Arc::clone(&bands);
+            move || read_samples(stdout, &bands, band_count, smoothing)
+        });
+
+        Ok(Self {
+            band_count,
+            smoothing,
+            bands,
+            child,
+            reader: Some(reader),
+        })
+    }
+
+    /// Returns `true` if the spectrum was created with the given settings.
+    pub fn has_settings(&self, band_count: usize, smoothing: f32) -> bool {
+        self.band_count == band_count.clamp(1, MAX_AUDIO_BANDS)
+            && self.smoothing == smoothing.clamp(0.0, 1.0)
+    }
+
+    /// Returns the latest band levels, in `[0, 1]`. Bands beyond the
+    /// band count are zero.
+    pub fn bands(&self) -> AudioBands {
+        *self.bands.lock().unwrap()
+    }
+}
+
+impl Drop for AudioSpectrum {
+    fn drop(&mut self) {
+        // Closing the pipe ends the reader thread
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Reads samples until the capture ends, updating the bands after every
+/// [`HOP_SIZE`] samples.
+fn read_samples(
+    mut stdout: ChildStdout,
+    bands: &Mutex<AudioBands>,
+    band_count: usize,
+    smoothing: f32,
+) {
+    let edges = band_edges(band_count);
+    let window: Vec<f32> = (0..FFT_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+        .collect();
+    let mut samples = vec![0.0; FFT_SIZE];
+    let mut block = vec![0; HOP_SIZE * size_of::<f32>()];
+    let mut levels = [0.0; MAX_AUDIO_BANDS];
+
+    while stdout.read_exact(&mut block).is_ok() {
+        samples.drain(..HOP_SIZE);
+        samples.extend(
+            block
+                .chunks_exact(size_of::<f32>())
+                .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap())),
+        );
+
+        let mut real: Vec<f32> = samples.iter().zip(&window).map(|(s, w)| s * w).collect();
+        let mut imag = vec![0.0; FFT_SIZE];
+        fft(&mut real, &mut imag);
+
+        for (level, &(first, last)) in levels.iter_mut().zip(&edges) {
+            let power = (first..last)
+                .map(|bin| real[bin] * real[bin] + imag[bin] * imag[bin])
+                .fold(0.0, f32::max);
+            // A full-scale sine peaks at FFT_SIZE / 4 with a Hann window
+            let amplitude = power.sqrt() * 4.0 / FFT_SIZE as f32;
+            let db = 20.0 * amplitude.max(f32::MIN_POSITIVE).log10();
+            let target = (1.0 - db / MIN_LEVEL_DB).clamp(0.0, 1.0);
+            *level = smoothing * *level + (1.0 - smoothing) * target;
+        }
+
+        *bands.lock().unwrap() = levels;
+    }
+}
+
+/// Returns the range of FFT bins of each band, with at least one bin
+/// per band.
+pub fn band_edges(band_count: usize) -> Vec<(usize, usize)> {
+    let bin_width = SAMPLE_RATE as f32 / FFT_SIZE as f32;
+    let ratio = MAX_FREQUENCY / MIN_FREQUENCY;
+    let bin = |band: usize| {
+        let frequency = MIN_FREQUENCY * ratio.powf(band as f32 / band_count as f32);
+        (frequency / bin_width).round() as usize
+    };
+
+    (0..band_count)
+        .map(|band| {
+            let first = bin(band).max(1);
+            (first, bin(band + 1).max(first + 1).min(FFT_SIZE / 2))
+        })
+        .collect()
+}
+
+/// In-place radix-2 FFT of a sequence whose length is a power of two.
+pub fn fft(real: &mut [f32], imag: &mut [f32]) {
+    let n = real.len();
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imag.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = real[b] * cos - imag[b] * sin;
+                let ti = real[b] * sin + imag[b] * cos;
+                real[b] = real[a] - tr;
+                imag[b] = imag[a] - ti;
+                real[a] += tr;
+                imag[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
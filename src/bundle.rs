@@ -0,0 +1,526 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Preset bundles (`.sbgz`).
+//!
+//! A bundle is a gzip-compressed tar archive holding a `preset.toml`
+//! along with the local files it references: GLSL files of passes with
+//! a `shader_file` and of `#include` directives, texture, cubemap,
+//! volume and video inputs, the fallback image, the custom transition
+//! and the presets of tiles. Paths in the bundled preset are relative
+//! to the root of the archive, so a wallpaper can be shared as a single
+//! file.
+//!
+//! Bundles are loaded by extracting them into
+//! `$XDG_CACHE_HOME/shaderbg/bundles`. Extracted bundles are reused
+//! until the bundle file changes.
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{preset::*, shadertoy::included_file};
+
+/// File extension of preset bundles.
+pub const BUNDLE_EXTENSION: &str = "sbgz";
+
+/// Name of the preset file within a bundle.
+const BUNDLED_PRESET: &str = "preset.toml";
+
+/// Directory holding the files of `#include` directives within a bundle.
+const INCLUDE_DIR: &str = "includes";
+
+/// Size of tar headers and data blocks.
+const BLOCK_SIZE: usize = 512;
+
+/// Longest file name stored in the `name` field of a tar header.
+const MAX_NAME_LEN: usize = 99;
+
+/// Largest decompressed size of a bundle, so that a small malicious
+/// bundle cannot fill the memory or the disk when extracted.
+const MAX_EXTRACTED_SIZE: u64 = 1 << 30;
+
+/// Suffixes of the `{face}` placeholder of cubemaps made of six files.
+const CUBEMAP_FACE_SUFFIXES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Placeholder substituted by each of [`CUBEMAP_FACE_SUFFIXES`].
+const CUBEMAP_FACE_PLACEHOLDER: &str = "{face}";
+
+/// Settings of the `pack` subcommand.
+#[derive(Debug, Clone)]
+pub struct PackOptions {
+    /// Preset to bundle.
+    pub file: PathBuf,
+
+    /// Bundle file to write.
+    pub output: PathBuf,
+}
+
+/// Settings of the `unpack` subcommand.
+#[derive(Debug, Clone)]
+pub struct UnpackOptions {
+    /// Bundle to extract.
+    pub file: PathBuf,
+
+    /// Directory receiving the preset and its files.
+    pub output: PathBuf,
+}
+
+/// Returns `true` if `path` has the bundle extension.
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some(BUNDLE_EXTENSION)
+}
+
+/// Loads the preset of a bundle, extracting it into the cache directory
+/// if not yet extracted.
+pub fn load(path: &Path) -> Result<Preset, PresetError> {
+    let dir = cache_dir(path)?;
+    if !dir.join(BUNDLED_PRESET).is_file() {
+        // Extract next to the final directory and rename it, so that a
+        // partially extracted bundle is never loaded
+        let partial_dir = dir.with_extension("part");
+        let _ = fs::remove_dir_all(&partial_dir);
+        extract(path, &partial_dir)?;
+        let _ = fs::remove_dir_all(&dir);
+        fs::rename(&partial_dir, &dir)?;
+    }
+
+    Preset::from_toml_file(&dir.join(BUNDLED_PRESET))
+}
+
+/// Writes a bundle with a preset and the local files it references.
+pub fn pack(options: &PackOptions) -> Result<(), PresetError> {
+    let mut preset = Preset::from_file(&options.file)?;
+    let base_dir = preset.base_dir.clone().unwrap_or_default();
+    let mut entries = Vec::new();
+    let mut includes = HashMap::new();
+
+    add_preset_entries(&mut entries, &mut includes, &mut preset)?;
+
+    // Tile presets are bundled next to the preset, so that paths relative
+    // to the root of the archive resolve the same in both
+    for (index, tile) in preset.tiles.iter_mut().enumerate() {
+        if tile.preset.is_none() {
+            tile.shader =
+                add_include_entries(&mut entries, &mut includes, &tile.shader, &base_dir, false)?;
+            continue;
+        }
+        let Some(mut tile_preset) = tile.loaded.take() else {
+            continue;
+        };
+
+        add_preset_entries(&mut entries, &mut includes, &mut tile_preset)?;
+        let entry_name = format!("tile_{}.toml", index + 1);
+        entries.push((entry_name.clone(), to_toml(&tile_preset)?.into_bytes()));
+        tile.preset = Some(PathBuf::from(entry_name));
+    }
+
+    entries.insert(
+        0,
+        (BUNDLED_PRESET.to_string(), to_toml(&preset)?.into_bytes()),
+    );
+
+    let mut encoder = GzEncoder::new(fs::File::create(&options.output)?, Compression::best());
+    write_tar(&mut encoder, &entries)?;
+    encoder.finish()?;
+
+    Ok(())
+}
+
+/// Extracts a bundle into `options.output`.
+pub fn unpack(options: &UnpackOptions) -> Result<(), PresetError> {
+    extract(&options.file, &options.output)
+}
+
+/// Serializes a preset for a bundle.
+fn to_toml(preset: &Preset) -> Result<String, PresetError> {
+    toml::to_string_pretty(preset).map_err(|e| PresetError::Bundle(e.to_string()))
+}
+
+/// Adds the local files referenced by a preset, except by its tiles, and
+/// makes the preset refer to the bundled files.
+fn add_preset_entries(
+    entries: &mut Vec<(String, Vec<u8>)>,
+    includes: &mut HashMap<PathBuf, String>,
+    preset: &mut Preset,
+) -> Result<(), PresetError> {
+    let base_dir = preset.base_dir.clone().unwrap_or_default();
+
+    let pass_names: Vec<_> = preset.passes().map(|(name, _)| name.to_string()).collect();
+    for name in pass_names {
+        let Some(pass) = preset.pass_mut(&name) else {
+            continue;
+        };
+
+        pass.shader = add_include_entries(entries, includes, &pass.shader, &base_dir, false)?;
+
+        if pass.shader_file.is_some() {
+            let file_stem: String = name
+                .to_lowercase()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            let entry_name = unique_entry_name(entries, &format!("shaders/{file_stem}"), "glsl");
+            entries.push((
+                entry_name.clone(),
+                std::mem::take(&mut pass.shader).into_bytes(),
+            ));
+            pass.shader_file = Some(PathBuf::from(entry_name));
+        }
+
+        for input in [
+            &mut pass.input_0,
+            &mut pass.input_1,
+            &mut pass.input_2,
+            &mut pass.input_3,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|input| {
+            matches!(
+                input._type,
+                InputType::Texture | InputType::Cubemap | InputType::Volume | InputType::Video
+            )
+        }) {
+            if let Some(entry_name) = add_file_entries(entries, &base_dir, &input.name)? {
+                input.name = entry_name;
+            }
+        }
+    }
+
+    if let Some(fallback_image) = preset.fallback_image.clone() {
+        let name = fallback_image.to_string_lossy();
+        if let Some(entry_name) = add_file_entries(entries, &base_dir, &name)? {
+            preset.fallback_image = Some(PathBuf::from(entry_name));
+        }
+    }
+
+    if let Transition::Custom(path) = &preset.transition {
+        let name = path.to_string_lossy().into_owned();
+        if let Some(entry_name) = add_file_entries(entries, &base_dir, &name)? {
+            preset.transition = Transition::Custom(PathBuf::from(entry_name));
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the files of the `#include` directives of `source`, resolved
+/// against `include_dir`, and the files they include in turn.
+///
+/// Returns `source` with its directives referring to the bundled files,
+/// relative to the root of the archive, or to [`INCLUDE_DIR`] if `nested`
+/// (for the contents of an included file). Directives of files that
+/// cannot be read are left unchanged.
+pub fn add_include_entries(
+    entries: &mut Vec<(String, Vec<u8>)>,
+    includes: &mut HashMap<PathBuf, String>,
+    source: &str,
+    include_dir: &Path,
+    nested: bool,
+) -> Result<String, PresetError> {
+    let mut output = String::with_capacity(source.len());
+    for line in source.split_inclusive('\n') {
+        let code = line.split("//").next().unwrap_or(line);
+        let Some(file) = included_file(code) else {
+            output.push_str(line);
+            continue;
+        };
+        let path = include_dir.join(file);
+        let path = fs::canonicalize(&path).unwrap_or(path);
+
+        let entry_name = match includes.get(&path) {
+            Some(entry_name) => entry_name.clone(),
+            None => {
+                let Ok(included) = fs::read_to_string(&path) else {
+                    output.push_str(line);
+                    continue;
+                };
+
+                // Prefix names with an index so that files from different
+                // directories never collide
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let prefix = format!("{INCLUDE_DIR}/{}_", includes.len());
+                let entry_name =
+                    prefix.clone() + &truncate_file_name(&file_name, MAX_NAME_LEN - prefix.len());
+
+                // Registered before its own directives are followed, so that
+                // recursive includes are bundled once
+                includes.insert(path.clone(), entry_name.clone());
+                let parent = path.parent().unwrap_or(include_dir);
+                let included = add_include_entries(entries, includes, &included, parent, true)?;
+                entries.push((entry_name.clone(), included.into_bytes()));
+                entry_name
+            }
+        };
+
+        let target = if nested {
+            entry_name
+                .strip_prefix(&format!("{INCLUDE_DIR}/"))
+                .unwrap_or(&entry_name)
+        } else {
+            &entry_name
+        };
+        output.push_str(&line.replacen(&format!("\"{file}\""), &format!("\"{target}\""), 1));
+    }
+
+    Ok(output)
+}
+
+/// Returns `{stem}.{extension}`, or `{stem}_2.{extension}`,
+/// `{stem}_3.{extension}`, etc., if already taken by one of `entries`.
+pub fn unique_entry_name(entries: &[(String, Vec<u8>)], stem: &str, extension: &str) -> String {
+    let mut entry_name = format!("{stem}.{extension}");
+    let mut count = 1;
+    while entries.iter().any(|(name, _)| *name == entry_name) {
+        count += 1;
+        entry_name = format!("{stem}_{count}.{extension}");
+    }
+    entry_name
+}
+
+/// Adds the file, or the six cubemap face files, that an input named
+/// `name` refers to.
+///
+/// Returns the input name referring to the bundled files, or `None` if
+/// `name` is not a local file (e.g., a bundled asset or a URL).
+fn add_file_entries(
+    entries: &mut Vec<(String, Vec<u8>)>,
+    base_dir: &Path,
+    name: &str,
+) -> Result<Option<String>, PresetError> {
+    let path = base_dir.join(name);
+    let paths: Vec<_> = if name.contains(CUBEMAP_FACE_PLACEHOLDER) {
+        CUBEMAP_FACE_SUFFIXES
+            .iter()
+            .map(|suffix| {
+                PathBuf::from(
+                    path.to_string_lossy()
+                        .replace(CUBEMAP_FACE_PLACEHOLDER, suffix),
+                )
+            })
+            .collect()
+    } else {
+        vec![path.clone()]
+    };
+    if !paths.iter().all(|path| path.is_file()) {
+        return Ok(None);
+    }
+
+    // Prefix names with an index so that files from different directories
+    // never collide
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let prefix = format!("files/{}_", entries.len());
+    let file_name = truncate_file_name(&file_name, MAX_NAME_LEN - prefix.len());
+    let entry_name = prefix + &file_name;
+
+    for (path, suffix) in paths.iter().zip(CUBEMAP_FACE_SUFFIXES) {
+        let entry = if name.contains(CUBEMAP_FACE_PLACEHOLDER) {
+            entry_name.replace(CUBEMAP_FACE_PLACEHOLDER, suffix)
+        } else {
+            entry_name.clone()
+        };
+        entries.push((entry, fs::read(path)?));
+    }
+
+    Ok(Some(entry_name))
+}
+
+/// Shortens a file name to at most `max_len` bytes, keeping its extension.
+pub fn truncate_file_name(file_name: &str, max_len: usize) -> String {
+    if file_name.len() <= max_len {
+        return file_name.to_string();
+    }
+    let (stem, extension) = file_name
+        .rsplit_once('.')
+        .map_or((file_name, String::new()), |(stem, extension)| {
+            (stem, format!(".{extension}"))
+        });
+    let mut end = max_len.saturating_sub(extension.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{extension}", &stem[..end])
+}
+
+/// Returns the directory a bundle is extracted into, named after the
+/// bundle file and its size and modification time.
+fn cache_dir(path: &Path) -> Result<PathBuf, PresetError> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs());
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let dir = crate::config::cache_dir()?.join("bundles");
+    fs::create_dir_all(&dir)?;
+
+    Ok(dir.join(format!("{stem}-{}-{modified}", metadata.len())))
+}
+
+/// Returns the contents of the preset file of a bundle, without
+/// extracting the other files unless the bundle was already loaded.
+pub fn read_preset(path: &Path) -> Result<String, PresetError> {
+    if let Ok(content) = fs::read_to_string(cache_dir(path)?.join(BUNDLED_PRESET)) {
+        return Ok(content);
+    }
+
+    let (_, data) = read_tar(&decompress(path)?)?
+        .into_iter()
+        .find(|(name, _)| name == BUNDLED_PRESET)
+        .ok_or_else(|| PresetError::Bundle(format!("missing {BUNDLED_PRESET}")))?;
+    String::from_utf8(data)
+        .map_err(|_| PresetError::Bundle(format!("{BUNDLED_PRESET} is not valid UTF-8")))
+}
+
+/// Returns the decompressed tar archive of a bundle.
+///
+/// Fails if the bundle decompresses to more than [`MAX_EXTRACTED_SIZE`].
+fn decompress(path: &Path) -> Result<Vec<u8>, PresetError> {
+    let mut archive = Vec::new();
+    GzDecoder::new(fs::File::open(path)?)
+        .take(MAX_EXTRACTED_SIZE + 1)
+        .read_to_end(&mut archive)?;
+    if archive.len() as u64 > MAX_EXTRACTED_SIZE {
+        return Err(PresetError::Bundle(format!(
+            "decompressed size exceeds {} MiB",
+            MAX_EXTRACTED_SIZE >> 20
+        )));
+    }
+    Ok(archive)
+}
+
+/// Extracts the files of a bundle into `dir`.
+fn extract(path: &Path, dir: &Path) -> Result<(), PresetError> {
+    let entries = read_tar(&decompress(path)?)?;
+    if !entries.iter().any(|(name, _)| name == BUNDLED_PRESET) {
+        return Err(PresetError::Bundle(format!("missing {BUNDLED_PRESET}")));
+    }
+
+    for (name, data) in entries {
+        let path = dir.join(&name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)?;
+    }
+
+    Ok(())
+}
+
+/// Writes regular files as a tar archive.
+pub fn write_tar(
+    writer: &mut impl Write,
+    entries: &[(String, Vec<u8>)],
+) -> Result<(), PresetError> {
+    for (name, data) in entries {
+        if name.len() > MAX_NAME_LEN {
+            return Err(PresetError::Bundle(format!("file name too long: {name}")));
+        }
+
+        let mut header = [0u8; BLOCK_SIZE];
+        let mut set_field = |offset: usize, value: &[u8]| {
+            header[offset..offset + value.len()].copy_from_slice(value);
+        };
+        set_field(0, name.as_bytes());
+        set_field(100, b"0000644\0");
+        set_field(108, b"0000000\0");
+        set_field(116, b"0000000\0");
+        set_field(124, format!("{:011o}\0", data.len()).as_bytes());
+        set_field(136, b"00000000000\0");
+        set_field(156, b"0");
+        set_field(257, b"ustar\0");
+        set_field(263, b"00");
+
+        // The checksum is computed with its own field filled with spaces
+        header[148..156].fill(b' ');
+        let checksum: u32 = header.iter().map(|&byte| byte as u32).sum();
+        header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(data)?;
+        let padding = (BLOCK_SIZE - data.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        writer.write_all(&vec![0; padding])?;
+    }
+
+    // End of archive
+    writer.write_all(&[0; 2 * BLOCK_SIZE])?;
+
+    Ok(())
+}
+
+/// Reads the regular files of a tar archive.
+///
+/// Fails on file names that are absolute or refer to parent directories,
+/// which could escape the extraction directory, and on truncated archives.
+pub fn read_tar(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>, PresetError> {
+    let invalid = |reason: &str| PresetError::Bundle(reason.to_string());
+    let field = |header: &[u8], offset: usize, len: usize| {
+        let field = &header[offset..offset + len];
+        let end = field.iter().position(|&byte| byte == 0).unwrap_or(len);
+        String::from_utf8_lossy(&field[..end]).into_owned()
+    };
+
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK_SIZE <= archive.len() {
+        let header = &archive[offset..offset + BLOCK_SIZE];
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        offset += BLOCK_SIZE;
+
+        let size = usize::from_str_radix(field(header, 124, 12).trim(), 8)
+            .map_err(|_| invalid("invalid file size"))?;
+        let data = offset
+            .checked_add(size)
+            .and_then(|end| archive.get(offset..end))
+            .ok_or_else(|| invalid("truncated archive"))?;
+        offset += size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+
+        // Skip directories, links and extended headers
+        if !matches!(header[156], b'0' | 0) {
+            continue;
+        }
+
+        let prefix = field(header, 345, 155);
+        let name = field(header, 0, 100);
+        let name = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let components: Vec<_> = Path::new(&name).components().collect();
+        if !components
+            .iter()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            || !components
+                .iter()
+                .any(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(PresetError::Bundle(format!("unsafe file name: {name}")));
+        }
+
+        entries.push((name, data.to_vec()));
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,197 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Preset listing and inspection (`list` and `info` subcommands).
+
+use gtk::glib::ExitCode;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    cli::load_preset_from_file_or_json, offscreen::OffscreenContext, preset::*,
+    thumbnail::thumbnail,
+};
+
+/// Settings of the `list` subcommand.
+#[derive(Debug, Clone)]
+pub struct ListOptions {
+    /// Renders missing thumbnails and prints their paths.
+    pub thumbnails: bool,
+}
+
+/// Settings of the `info` subcommand.
+#[derive(Debug, Clone)]
+pub struct InfoOptions {
+    /// Preset to describe.
+    pub file: PathBuf,
+}
+
+/// Prints the presets of the presets directory, one per line.
+pub fn list(options: &ListOptions) -> ExitCode {
+    let presets = match presets_in_directory(&presets_dir()) {
+        Ok(presets) => presets,
+        Err(PresetError::NoPresets) => Vec::new(),
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let context = options
+        .thumbnails
+        .then(|| {
+            OffscreenContext::new()
+                .inspect_err(|err| log::warn!("Thumbnails are unavailable: {err}"))
+                .ok()
+        })
+        .flatten();
+
+    let mut rows = vec![vec![
+        "FILE".to_string(),
+        "ID".to_string(),
+        "NAME".to_string(),
+        "AUTHOR".to_string(),
+    ]];
+    if options.thumbnails {
+        rows[0].push("THUMBNAIL".to_string());
+    }
+
+    for (preset, path) in &presets {
+        let mut row = vec![
+            file_name(path),
+            or_dash(&preset.id),
+            or_dash(&preset.name),
+            or_dash(&preset.username),
+        ];
+        if options.thumbnails {
+            let thumbnail_path = context.as_ref().and_then(|context| {
+                thumbnail(context, preset)
+                    .inspect_err(|err| log::warn!("{}: {err}", path.display()))
+                    .ok()
+            });
+            row.push(thumbnail_path.map_or_else(|| "-".to_string(), |p| p.display().to_string()));
+        }
+        rows.push(row);
+    }
+
+    print_table(&rows);
+
+    ExitCode::SUCCESS
+}
+
+/// Prints the passes, inputs and rendering settings of a preset.
+pub fn info(options: &InfoOptions) -> ExitCode {
+    let (preset, path) = match load_preset_from_file_or_json(&options.file) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let path = path.unwrap_or_else(|| options.file.clone());
+    println!("File:             {}", path.display());
+    println!("ID:               {}", or_dash(&preset.id));
+    println!("Name:             {}", or_dash(&preset.name));
+    println!("Author:           {}", or_dash(&preset.username));
+    if !preset.tags.is_empty() {
+        println!("Tags:             {}", preset.tags.join(", "));
+    }
+    println!("Resolution scale: {}", preset.resolution_scale);
+    println!("Supersample:      {}x", preset.supersample.max(1));
+    println!("MSAA samples:     {}", preset.msaa_samples);
+    println!(
+        "Estimated cost:   {:.2} full-screen passes per frame",
+        estimated_cost(&preset)
+    );
+
+    println!("Passes:");
+    for (name, pass) in preset.passes() {
+        println!("  {name}");
+        let inputs = [&pass.input_0, &pass.input_1, &pass.input_2, &pass.input_3];
+        for (channel, input) in inputs.into_iter().enumerate() {
+            if let Some(input) = input {
+                println!("    iChannel{channel}: {}", describe_input(input));
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Returns the fragments shaded per frame relative to a single pass at
+/// the resolution of the monitor.
+///
+/// Every render pass counts once at the framebuffer resolution, scaled
+/// by its `scale`, except "Cube A", which renders six faces. The actual
+/// cost also depends on the shaders themselves.
+fn estimated_cost(preset: &Preset) -> f64 {
+    let scale = f64::from(preset.resolution_scale) * f64::from(preset.supersample.max(1));
+    let passes: f64 = preset
+        .render_passes()
+        .map(|(name, pass)| match name {
+            "Cube A" => 6.0,
+            "Image" => 1.0,
+            _ => f64::from(pass.scale.unwrap_or(1.0)).powi(2),
+        })
+        .sum();
+    passes * scale * scale
+}
+
+/// Returns a one-line description of a pass input.
+fn describe_input(input: &Input) -> String {
+    let kind = serde_json::to_value(input._type)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let mut options = vec![format!("{:?}", input.filter), format!("{:?}", input.wrap)];
+    if input.vflip {
+        options.push("vflip".to_string());
+    }
+    let options = options.join(", ").to_lowercase();
+
+    match input._type {
+        // Buffers are identified by name alone
+        InputType::Misc => format!("{} ({options})", input.name),
+        _ => format!("{kind} {} ({options})", input.name),
+    }
+}
+
+/// Prints rows of cells as left-aligned columns.
+pub fn print_table(rows: &[Vec<String>]) {
+    let column_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..column_count)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn or_dash(value: &str) -> String {
+    if value.trim().is_empty() {
+        "-".to_string()
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,234 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Headless preset validation.
+//!
+//! Loads presets, runs them through the ShaderToy compatibility layer
+//! and compiles every pass in a surfaceless OpenGL context, reporting
+//! errors and warnings instead of falling back to the default shader.
+//!
+//! The process exits with:
+//!
+//! | Code | Meaning                                                     |
+//! |------|-------------------------------------------------------------|
+//! | 0    | Every preset is valid                                       |
+//! | 1    | Errors were found (or warnings, with `--deny-warnings`)     |
+//! | 2    | No OpenGL context could be created, so nothing was checked  |
+
+use gtk::glib::ExitCode;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    cli::load_preset_from_file_or_json,
+    offscreen::OffscreenContext,
+    renderer::{shader::ShaderError, validate_preset},
+    shadertoy::{complexity::preset_warnings, precision::precision_warnings},
+};
+
+/// Exit code when errors were found.
+const EXIT_INVALID: u8 = 1;
+
+/// Exit code when the presets could not be checked.
+const EXIT_UNAVAILABLE: u8 = 2;
+
+/// Format of the diagnostics printed to standard output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckFormat {
+    /// One `FILE: PASS: SEVERITY: MESSAGE` line per diagnostic.
+    Text,
+    /// A JSON array of diagnostic objects.
+    Json,
+}
+
+/// Settings of the `check` subcommand.
+#[derive(Debug, Clone)]
+pub struct CheckOptions {
+    /// Preset files to check.
+    pub files: Vec<PathBuf>,
+
+    pub format: CheckFormat,
+
+    /// Fails the check when any warning is reported.
+    pub deny_warnings: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Error or warning found in a preset.
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+
+    /// Pass the diagnostic refers to, or `None` for the preset itself.
+    pub pass: Option<String>,
+
+    /// Line (1-based) of the pass code, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Checks every preset in `options.files` and prints the diagnostics.
+pub fn check(options: &CheckOptions) -> ExitCode {
+    let _context = match OffscreenContext::new() {
+        Ok(context) => context,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::new(EXIT_UNAVAILABLE);
+        }
+    };
+
+    let diagnostics: Vec<_> = options
+        .files
+        .iter()
+        .flat_map(|file| check_file(file))
+        .collect();
+
+    report(
+        &diagnostics,
+        options.files.len(),
+        options.format,
+        options.deny_warnings,
+    )
+}
+
+/// Prints the diagnostics of `file_count` presets in `format` and
+/// returns the exit code of the check.
+pub fn report(
+    diagnostics: &[Diagnostic],
+    file_count: usize,
+    format: CheckFormat,
+    deny_warnings: bool,
+) -> ExitCode {
+    match format {
+        CheckFormat::Text => {
+            for diagnostic in diagnostics {
+                let severity = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                let line = diagnostic
+                    .line
+                    .map_or_else(String::new, |line| format!(":{line}"));
+                println!(
+                    "{}: {}{line}: {severity}: {}",
+                    diagnostic.file.display(),
+                    diagnostic.pass.as_deref().unwrap_or("preset"),
+                    diagnostic.message
+                );
+            }
+        }
+        CheckFormat::Json => match serde_json::to_string_pretty(diagnostics) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("Failed to serialize diagnostics: {err}");
+                return ExitCode::new(EXIT_UNAVAILABLE);
+            }
+        },
+    }
+
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    eprintln!("Checked {file_count} preset(s): {errors} error(s), {warnings} warning(s)");
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        ExitCode::new(EXIT_INVALID)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Loads and compiles a preset, returning its diagnostics.
+fn check_file(file: &Path) -> Vec<Diagnostic> {
+    let diagnostic = |pass: Option<&str>, severity, message: &str| Diagnostic {
+        file: file.to_path_buf(),
+        pass: pass.map(str::to_string),
+        line: None,
+        severity,
+        message: message.trim().to_string(),
+    };
+
+    let preset = match load_preset_from_file_or_json(file) {
+        Ok((preset, _)) => preset,
+        Err(err) => return vec![diagnostic(None, Severity::Error, &err.to_string())],
+    };
+
+    let mut diagnostics = Vec::new();
+    for pass in validate_preset(&preset) {
+        let (log, severity) = match &pass.result {
+            Ok(warnings) => (warnings.as_str(), Severity::Warning),
+            Err(ShaderError::ShaderCompile(log) | ShaderError::ProgramLink(log)) => {
+                (log.as_str(), Severity::Error)
+            }
+            Err(err) => {
+                diagnostics.push(diagnostic(
+                    Some(&pass.name),
+                    Severity::Error,
+                    &err.to_string(),
+                ));
+                continue;
+            }
+        };
+
+        // Driver logs have one message per line, and failed compilations
+        // may report warnings along with errors
+        let pass_diagnostics: Vec<_> = log
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let severity = if line.to_lowercase().contains("warning") {
+                    Severity::Warning
+                } else {
+                    severity
+                };
+                diagnostic(Some(&pass.name), severity, line)
+            })
+            .collect();
+
+        // Some drivers fail without logging an error
+        if let Err(err) = &pass.result {
+            if pass_diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.severity != Severity::Error)
+            {
+                diagnostics.push(diagnostic(
+                    Some(&pass.name),
+                    Severity::Error,
+                    &err.to_string(),
+                ));
+            }
+        }
+        diagnostics.extend(pass_diagnostics);
+    }
+
+    for (pass, warning) in preset_warnings(&preset) {
+        diagnostics.push(diagnostic(Some(&pass), Severity::Warning, &warning));
+    }
+
+    let common_shader = preset.common.as_ref().map(|pass| ("Common", pass));
+    for (pass, render_pass) in common_shader.into_iter().chain(preset.render_passes()) {
+        for warning in precision_warnings(&render_pass.shader) {
+            diagnostics.push(Diagnostic {
+                line: Some(warning.line),
+                ..diagnostic(Some(pass), Severity::Warning, &warning.message)
+            });
+        }
+    }
+
+    diagnostics
+}
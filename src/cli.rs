@@ -9,17 +9,35 @@
 //! initial [`Preset`].
 //!
 //! Produces a [`CliConfig`] describing the runtime configuration used
-//! to initialize the application.
+//! to initialize the application, and the [`Command`] to run.
 
 use std::{
     env, fs, io,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use clap::{Arg, ArgAction, Command};
+use chrono::NaiveDate;
+use clap::{error::ErrorKind, parser::ValueSource, Arg, ArgAction};
 use thiserror::Error;
 
-use crate::{preset::*, *};
+use crate::{
+    bundle::{PackOptions, UnpackOptions, BUNDLE_EXTENSION},
+    catalog::{InfoOptions, ListOptions},
+    check::{CheckFormat, CheckOptions},
+    config::config,
+    export::ExportOptions,
+    frame_controller::TimeSource,
+    geometry::Size,
+    ipc::IpcCommand,
+    lint::LintOptions,
+    logging::{default_log_file, parse_log_filter, LogFilter},
+    offscreen::ScreenshotOptions,
+    preset::*,
+    renderer::parse_gpu_watchdog_timeout,
+    shadertoy::{complexity::check_complexity, importer::ImportOptions, search::*},
+    *,
+};
 
 /// Errors that may occur during CLI parsing or startup initialization.
 #[derive(Debug, Error)]
@@ -32,6 +50,15 @@ pub enum CliError {
 
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+
+    #[error("{0}")]
+    OfflineRender(String),
+
+    #[error(
+        "Searching requires a ShaderToy API key (--key, ${} or shadertoy_api_key in the config file)",
+        API_KEY_VAR
+    )]
+    MissingApiKey,
 }
 
 /// Result of command-line parsing.
@@ -51,6 +78,66 @@ pub struct CliConfig {
 
     /// Enables the on-screen shader information overlay.
     pub show_overlay: bool,
+
+    /// Levels of the log messages printed, per module, overriding the
+    /// configuration file.
+    pub log_level: Option<LogFilter>,
+
+    /// Also writes log messages to this file, rotated when it grows too
+    /// large.
+    pub log_file: Option<PathBuf>,
+
+    /// Suspends rendering while fullscreen windows cover every wallpaper.
+    pub pause_when_occluded: bool,
+
+    /// Task to run, the wallpaper unless a subcommand is given.
+    pub command: Command,
+
+    /// Persists the applied preset and accepts commands over the
+    /// control socket.
+    pub daemon: bool,
+
+    /// Registers global hotkeys to pause and scrub the animation.
+    pub hotkeys: bool,
+
+    /// Shows the performance HUD. Toggled at runtime with the `hud`
+    /// control command.
+    pub hud: bool,
+
+    /// Periodically logs the GPU time spent on each pass.
+    pub profile: bool,
+
+    /// Shows the preset in a regular window alongside the running
+    /// wallpaper instead of replacing it.
+    pub preview: bool,
+
+    /// Locks the session, showing the preset on the lock screen.
+    pub session_lock: bool,
+
+    /// Refuses presets whose shaders look too heavy, instead of only
+    /// warning about them.
+    pub strict: bool,
+
+    /// GPU to render on, as a DRM card name or PCI address. `None` uses
+    /// the GPU picked by the graphics driver.
+    pub gpu: Option<String>,
+
+    /// Time the GPU is given to finish a frame before the preset is
+    /// aborted. `None` disables the watchdog.
+    pub gpu_watchdog: Option<Duration>,
+
+    /// Layer of the render windows, replacing the `layer` of every
+    /// preset.
+    pub layer: Option<WindowLayer>,
+
+    /// Tag filters applied when picking a random preset.
+    pub preset_filter: PresetFilter,
+
+    /// Source of the animation time of the wallpaper.
+    pub time_source: TimeSource,
+
+    /// Date reported in `iDate` instead of the current date.
+    pub date: Option<NaiveDate>,
 }
 
 impl Default for CliConfig {
@@ -58,11 +145,129 @@ impl Default for CliConfig {
         Self {
             preset: Preset::with_serde_defaults(),
             preset_path: None,
-            show_overlay: true,
+            show_overlay: config().show_overlay.unwrap_or(true),
+            log_level: None,
+            log_file: None,
+            pause_when_occluded: true,
+            command: Command::default(),
+            daemon: false,
+            hotkeys: false,
+            hud: false,
+            profile: false,
+            preview: false,
+            session_lock: false,
+            strict: false,
+            gpu: None,
+            gpu_watchdog: None,
+            layer: None,
+            preset_filter: PresetFilter::default(),
+            time_source: TimeSource::Clock,
+            date: None,
         }
     }
 }
 
+/// Task run by the program, chosen by a subcommand or by an option
+/// replacing the wallpaper.
+#[derive(Debug, Default)]
+pub enum Command {
+    /// Runs the wallpaper.
+    #[default]
+    Run,
+
+    /// Runs the wallpaper in a child process, restarting it with the
+    /// default preset if it crashes.
+    Supervise,
+
+    /// Opens the preset manager.
+    Settings,
+
+    /// Renders a single frame to an image file.
+    Screenshot(ScreenshotOptions),
+
+    /// Renders a video file.
+    Export(ExportOptions),
+
+    /// Validates preset files.
+    Check(CheckOptions),
+
+    /// Validates preset files against the ShaderToy GLSL dialect.
+    Lint(LintOptions),
+
+    /// Converts a ShaderToy JSON export to a TOML preset.
+    Import(ImportOptions),
+
+    /// Searches ShaderToy, then runs the picked result, if any, as the
+    /// wallpaper.
+    Search(SearchOptions),
+
+    /// Writes a preset bundle.
+    Pack(PackOptions),
+
+    /// Extracts a preset bundle.
+    Unpack(UnpackOptions),
+
+    /// Sends a command to a running instance, such as switching to
+    /// another preset.
+    Control(IpcCommand),
+
+    /// Lists the presets directory.
+    List(ListOptions),
+
+    /// Describes a preset.
+    Info(InfoOptions),
+
+    /// Lists the connected monitors.
+    Monitors,
+}
+
+impl Command {
+    /// Returns `true` if the command renders the preset given on the
+    /// command line, or a random preset if none is given. Other commands
+    /// load their presets themselves, if any.
+    fn renders_preset(&self) -> bool {
+        matches!(
+            self,
+            Self::Run | Self::Supervise | Self::Screenshot(_) | Self::Export(_)
+        )
+    }
+}
+
+/// Root options applying to every subcommand.
+const GLOBAL_ARGS: [&str; 3] = ["log-level", "log-file", "date"];
+
+/// Root options of the wallpaper, accepted by the `search` subcommand,
+/// which runs the picked result as the wallpaper.
+const WALLPAPER_ARGS: [&str; 16] = [
+    "overlay",
+    "no-overlay",
+    "no-occlusion-pause",
+    "gpu",
+    "gpu-watchdog",
+    "layer",
+    "tags",
+    "exclude",
+    "daemon",
+    "preview",
+    "session-lock",
+    "strict",
+    "hud",
+    "profile",
+    "hotkeys",
+    "time-source",
+];
+
+/// Returns the root options accepted along with a subcommand, besides
+/// [`GLOBAL_ARGS`].
+fn root_args_of_subcommand(name: &str) -> &'static [&'static str] {
+    match name {
+        "search" => &WALLPAPER_ARGS,
+        // Rendered offscreen
+        "export" | "check" | "list" => &["gpu"],
+        _ => &[],
+    }
+}
+
 /// Parses command-line arguments and resolves the initial preset.
 ///
 /// - No arguments: load a random preset.
@@ -72,7 +277,7 @@ pub fn parse_args() -> Result<CliConfig, CliError> {
 
     let presets_directory = presets_dir();
 
-    let matches = Command::new(APP_NAME)
+    let mut cli = clap::Command::new(APP_NAME)
         .author(APP_AUTHOR)
         .version(APP_SEMVER)
         .about(APP_ABOUT)
@@ -88,35 +293,746 @@ pub fn parse_args() -> Result<CliConfig, CliError> {
                 .help("Disable the shader info overlay")
                 .action(ArgAction::SetTrue),
         )
-        .after_help("Run with no arguments to use a random preset")
-        .get_matches();
+        .arg(
+            Arg::new("overlay")
+                .long("overlay")
+                .help("Enable the shader info overlay disabled by the config file")
+                .conflicts_with("no-overlay")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .value_name("FILTER")
+                .help("Levels of log messages (off, error, warn, info, debug or trace), optionally per module, e.g., warn,frame_controller=trace")
+                .global(true)
+                .value_parser(parse_log_filter),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("PATH")
+                .help("Also write log messages to a rotated file, by default shaderbg.log in the cache directory")
+                .num_args(0..=1)
+                .default_missing_value("")
+                .global(true)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("no-occlusion-pause")
+                .long("no-occlusion-pause")
+                .help("Keep rendering while fullscreen windows cover the wallpaper")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("gpu")
+                .long("gpu")
+                .value_name("CARD")
+                .help("Render on this GPU, e.g., card1 or 0000:01:00.0 (see /sys/class/drm)"),
+        )
+        .arg(
+            Arg::new("gpu-watchdog")
+                .long("gpu-watchdog")
+                .value_name("TIMEOUT")
+                .help("Switch to the default preset if the GPU does not finish a frame in time, e.g., 2s")
+                .value_parser(parse_gpu_watchdog_timeout),
+        )
+        .arg(
+            Arg::new("layer")
+                .long("layer")
+                .value_name("LAYER")
+                .help("Render above windows instead of as a wallpaper: 'background', 'top' or 'overlay'")
+                .value_parser(parse_window_layer),
+        )
+        .arg(
+            Arg::new("tags")
+                .long("tags")
+                .value_name("TAG,...")
+                .help("Pick a random preset only among those with all of these tags")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("TAG,...")
+                .help("Never pick a random preset with any of these tags")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("daemon")
+                .long("daemon")
+                .help("Restore the last applied preset and accept switch commands over the control socket")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("preview")
+                .long("preview")
+                .help("Show the preset in a window alongside the running wallpaper (Ctrl+Enter applies it)")
+                .conflicts_with("daemon")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("session-lock")
+                .long("session-lock")
+                .help("Lock the session, showing the preset until the auth_command of the config file accepts the password")
+                .conflicts_with_all(["daemon", "preview"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .help("Refuse to apply presets whose shaders look too heavy, instead of warning")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("supervise")
+                .long("supervise")
+                .help("Restart with the default preset if the wallpaper crashes")
+                .conflicts_with_all(["preview", "session-lock"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("safe-mode")
+                .long("safe-mode")
+                .help("Run the default preset, as after a crash under --supervise")
+                .hide(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("settings")
+                .long("settings")
+                .help("Open a window to manage, edit and apply the presets")
+                .conflicts_with_all(["supervise", "screenshot"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("hud")
+                .long("hud")
+                .help("Show frame rate, frame times and GPU time per pass")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .help("Log the GPU time spent on each pass every few seconds")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("hotkeys")
+                .long("hotkeys")
+                .help("Register global hotkeys to pause, step and scrub the animation")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("time-source")
+                .long("time-source")
+                .value_name("SOURCE")
+                .help("Source of iTime: 'clock' or 'fixed:<SECONDS>' (e.g., fixed:12.5)")
+                .default_value("clock")
+                .value_parser(parse_time_source),
+        )
+        .arg(
+            Arg::new("date")
+                .long("date")
+                .value_name("YYYY-MM-DD")
+                .help("Date reported in iDate, with the time of day following iTime")
+                .global(true)
+                .value_parser(parse_date),
+        )
+        .arg(
+            Arg::new("screenshot")
+                .long("screenshot")
+                .value_name("OUTPUT")
+                .help("Render a single frame to an image file and exit")
+                .conflicts_with("supervise")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("at")
+                .long("at")
+                .value_name("TIME")
+                .help("Time of the screenshot (e.g., 12.5s)")
+                .requires("screenshot")
+                .default_value("0s")
+                .value_parser(parse_duration),
+        )
+        .arg(
+            Arg::new("size")
+                .long("size")
+                .value_name("WIDTHxHEIGHT")
+                .help("Resolution of the screenshot")
+                .requires("screenshot")
+                .default_value("1920x1080")
+                .value_parser(parse_size),
+        )
+        .subcommand(
+            clap::Command::new("export")
+                .about("Render a preset offscreen and encode it to a video file with ffmpeg")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Path to TOML preset file or ShaderToy JSON export")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("OUTPUT")
+                        .help("Video file to write (e.g., loop.mp4 or loop.webm)")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("duration")
+                        .long("duration")
+                        .value_name("TIME")
+                        .help("Length of the video (e.g., 30s)")
+                        .default_value("10s")
+                        .value_parser(parse_duration),
+                )
+                .arg(
+                    Arg::new("fps")
+                        .long("fps")
+                        .value_name("FPS")
+                        .help("Frames per second")
+                        .default_value("60")
+                        .value_parser(clap::value_parser!(u32).range(1..)),
+                )
+                .arg(
+                    Arg::new("size")
+                        .long("size")
+                        .value_name("WIDTHxHEIGHT")
+                        .help("Resolution of the video")
+                        .default_value("1920x1080")
+                        .value_parser(parse_size),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("check")
+                .about("Compile presets offscreen and report errors and warnings")
+                .arg(
+                    Arg::new("files")
+                        .value_name("FILE")
+                        .help("Paths to TOML preset files or ShaderToy JSON exports")
+                        .required(true)
+                        .num_args(1..)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format: 'text' or 'json'")
+                        .default_value("text")
+                        .value_parser(parse_check_format),
+                )
+                .arg(
+                    Arg::new("deny-warnings")
+                        .long("deny-warnings")
+                        .help("Exit with an error status if any warning is reported")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("lint")
+                .about("Validate presets against the ShaderToy GLSL dialect without a GPU")
+                .arg(
+                    Arg::new("files")
+                        .value_name("FILE")
+                        .help("Paths to TOML preset files or ShaderToy JSON exports")
+                        .required(true)
+                        .num_args(1..)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format: 'text' or 'json'")
+                        .default_value("text")
+                        .value_parser(parse_check_format),
+                )
+                .arg(
+                    Arg::new("deny-warnings")
+                        .long("deny-warnings")
+                        .help("Exit with an error status if any warning is reported")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("import")
+                .about("Convert a ShaderToy JSON export to a TOML preset")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Path to ShaderToy JSON export (API response or website export)")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("OUTPUT")
+                        .help("TOML file to write [default: <shader_id>.toml in the presets directory]")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .help("Refuse shaders that look too heavy to run as a wallpaper")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("search")
+                .about("Search ShaderToy and optionally import and apply a result")
+                .arg(
+                    Arg::new("query")
+                        .value_name("QUERY")
+                        .help("Search string (e.g., fractal)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("sort")
+                        .long("sort")
+                        .value_name("ORDER")
+                        .help("Order of results: 'name', 'love', 'popular', 'newest' or 'hot'")
+                        .default_value("popular")
+                        .value_parser(parse_search_sort),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .help("Maximum number of results")
+                        .default_value("10")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .value_name("API_KEY")
+                        .help(format!(
+                            "ShaderToy API key [default: ${API_KEY_VAR} environment variable \
+                             or shadertoy_api_key of the config file]"
+                        )),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("Prompt for a result to import and run as the wallpaper")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("pack")
+                .about("Bundle a preset and its local files into a single .sbgz file")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Path to TOML preset file")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("OUTPUT")
+                        .help("Bundle file to write [default: <FILE stem>.sbgz]")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("unpack")
+                .about("Extract a .sbgz preset bundle")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Path to .sbgz bundle")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("DIR")
+                        .help("Directory to extract into [default: <FILE stem>]")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("switch")
+                .about("Switch the preset of a running daemon")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Path to TOML preset file, bundle or ShaderToy JSON export")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("next")
+                .about("Switch a running daemon to the next preset of its history"),
+        )
+        .subcommand(
+            clap::Command::new("previous")
+                .about("Switch a running daemon to the previous preset of its history"),
+        )
+        .subcommand(
+            clap::Command::new("random").about("Switch a running daemon to a random preset"),
+        )
+        .subcommand(
+            clap::Command::new("list")
+                .about("List the presets of the presets directory")
+                .arg(
+                    Arg::new("thumbnails")
+                        .long("thumbnails")
+                        .help("Render missing thumbnails and print their paths")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("info")
+                .about("Print the passes, inputs and rendering settings of a preset")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("Path to TOML preset file, bundle or ShaderToy JSON export")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("monitors")
+                .about("List connected monitors and their identifiers for monitor_selection"),
+        )
+        .after_help("Run with no arguments to use a random preset");
+    let matches = cli.get_matches_mut();
+
+    if let Some((name, _)) = matches.subcommand() {
+        let accepted = root_args_of_subcommand(name);
+        let rejected = matches.ids().map(|id| id.as_str()).find(|id| {
+            matches.value_source(id) == Some(ValueSource::CommandLine)
+                && !GLOBAL_ARGS.contains(id)
+                && !accepted.contains(id)
+        });
+        if let Some(id) = rejected {
+            let arg = if id == "file" {
+                "FILE".to_string()
+            } else {
+                format!("--{id}")
+            };
+            cli.error(
+                ErrorKind::ArgumentConflict,
+                format!("{arg} cannot be used with the '{name}' subcommand"),
+            )
+            .exit();
+        }
+    }
+
+    let date = matches
+        .subcommand_matches("export")
+        .unwrap_or(&matches)
+        .get_one::<NaiveDate>("date")
+        .copied();
+    let screenshot = matches
+        .get_one::<PathBuf>("screenshot")
+        .map(|output| ScreenshotOptions {
+            output: output.clone(),
+            time: *matches.get_one::<Duration>("at").unwrap(),
+            size: *matches.get_one::<Size>("size").unwrap(),
+            date,
+        });
+    let command = match matches.subcommand() {
+        Some(("export", matches)) => Command::Export(ExportOptions {
+            output: matches.get_one::<PathBuf>("output").unwrap().clone(),
+            duration: *matches.get_one::<Duration>("duration").unwrap(),
+            fps: *matches.get_one::<u32>("fps").unwrap(),
+            size: *matches.get_one::<Size>("size").unwrap(),
+            date,
+        }),
+        Some(("check", matches)) => Command::Check(CheckOptions {
+            files: matches
+                .get_many::<PathBuf>("files")
+                .unwrap()
+                .cloned()
+                .collect(),
+            format: *matches.get_one::<CheckFormat>("format").unwrap(),
+            deny_warnings: matches.get_flag("deny-warnings"),
+        }),
+        Some(("lint", matches)) => Command::Lint(LintOptions {
+            files: matches
+                .get_many::<PathBuf>("files")
+                .unwrap()
+                .cloned()
+                .collect(),
+            format: *matches.get_one::<CheckFormat>("format").unwrap(),
+            deny_warnings: matches.get_flag("deny-warnings"),
+        }),
+        Some(("import", matches)) => Command::Import(ImportOptions {
+            file: matches.get_one::<PathBuf>("file").unwrap().clone(),
+            output: matches.get_one::<PathBuf>("output").cloned(),
+            strict: matches.get_flag("strict"),
+        }),
+        Some(("search", matches)) => {
+            let key = matches
+                .get_one::<String>("key")
+                .cloned()
+                .or_else(|| env::var(API_KEY_VAR).ok())
+                .or_else(|| config().shadertoy_api_key.clone())
+                .ok_or(CliError::MissingApiKey)?;
+            Command::Search(SearchOptions {
+                query: matches.get_one::<String>("query").unwrap().clone(),
+                sort: *matches.get_one::<SearchSort>("sort").unwrap(),
+                limit: *matches.get_one::<usize>("limit").unwrap(),
+                key,
+                pick: matches.get_flag("pick"),
+            })
+        }
+        Some(("pack", matches)) => {
+            let file = matches.get_one::<PathBuf>("file").unwrap().clone();
+            let output = matches
+                .get_one::<PathBuf>("output")
+                .cloned()
+                .unwrap_or_else(|| file.with_extension(BUNDLE_EXTENSION));
+            Command::Pack(PackOptions { file, output })
+        }
+        Some(("unpack", matches)) => {
+            let file = matches.get_one::<PathBuf>("file").unwrap().clone();
+            let output = matches
+                .get_one::<PathBuf>("output")
+                .cloned()
+                .unwrap_or_else(|| file.with_extension(""));
+            Command::Unpack(UnpackOptions { file, output })
+        }
+        Some(("switch", matches)) => {
+            let file = matches.get_one::<PathBuf>("file").unwrap();
+            // The daemon would resolve a relative path against its own
+            // working directory, so the path is sent absolute
+            Command::Control(IpcCommand::Switch(
+                fs::canonicalize(file)
+                    .or_else(|_| std::path::absolute(file))
+                    .unwrap_or_else(|_| file.clone()),
+            ))
+        }
+        Some(("next", _)) => Command::Control(IpcCommand::Next),
+        Some(("previous", _)) => Command::Control(IpcCommand::Previous),
+        Some(("random", _)) => Command::Control(IpcCommand::Random),
+        Some(("list", matches)) => Command::List(ListOptions {
+            thumbnails: matches.get_flag("thumbnails"),
+        }),
+        Some(("info", matches)) => Command::Info(InfoOptions {
+            file: matches.get_one::<PathBuf>("file").unwrap().clone(),
+        }),
+        Some(("monitors", _)) => Command::Monitors,
+        Some((name, _)) => unreachable!("Unhandled subcommand {name}"),
+        None if matches.get_flag("supervise") => Command::Supervise,
+        None if matches.get_flag("settings") => Command::Settings,
+        None => screenshot.map_or(Command::Run, Command::Screenshot),
+    };
+    let file = match matches.subcommand_matches("export") {
+        Some(export_matches) => export_matches.get_one::<PathBuf>("file"),
+        None => matches.get_one::<PathBuf>("file"),
+    };
 
-    let show_overlay = !matches.get_flag("no-overlay");
+    let daemon = matches.get_flag("daemon");
+    let tags = |id| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    };
+    let filter = PresetFilter {
+        tags: tags("tags"),
+        exclude: tags("exclude"),
+        excluded_files: Vec::new(),
+    };
+    let hotkeys = matches.get_flag("hotkeys");
+    let hud = matches.get_flag("hud");
+    let profile = matches.get_flag("profile");
+    let preview = matches.get_flag("preview");
+    let session_lock = matches.get_flag("session-lock");
+    let strict = matches.get_flag("strict");
+    let safe_mode = matches.get_flag("safe-mode");
+    let show_overlay = if matches.get_flag("overlay") {
+        true
+    } else if matches.get_flag("no-overlay") {
+        false
+    } else {
+        config().show_overlay.unwrap_or(true)
+    };
+    let log_level = matches.get_one::<LogFilter>("log-level").cloned();
+    let log_file = match matches.get_one::<PathBuf>("log-file") {
+        Some(path) if path.as_os_str().is_empty() => Some(
+            default_log_file()
+                .map_err(|err| CliError::InvalidInput(format!("{err}; give --log-file a path")))?,
+        ),
+        path => path.cloned(),
+    };
+    let pause_when_occluded = !matches.get_flag("no-occlusion-pause");
+    let gpu = matches
+        .get_one::<String>("gpu")
+        .cloned()
+        .or_else(|| config().gpu.clone());
+    let gpu_watchdog = matches
+        .get_one::<Duration>("gpu-watchdog")
+        .copied()
+        .or(config().gpu_watchdog);
+    let layer = matches.get_one::<WindowLayer>("layer").copied();
+    let time_source = *matches.get_one::<TimeSource>("time-source").unwrap();
 
-    let (preset, preset_path) = match matches.get_one::<PathBuf>("file") {
+    let (preset, preset_path) = match file {
+        // Checked and imported presets are loaded by the subcommand itself
+        _ if !command.renders_preset() => (Preset::with_serde_defaults(), None),
+        // Recovering from a crash: the given or last preset may be the cause
+        _ if safe_mode => {
+            log::warn!("Running the default preset in safe mode");
+            (Preset::with_serde_defaults(), None)
+        }
+        // No arguments in daemon mode: restore the last applied preset
+        None if daemon => match daemon::load_last_preset() {
+            Some(path) => load_preset_from_file_or_json(&path).or_else(|err| {
+                log::warn!("Failed to restore last preset: {err}");
+                load_preset_from_directory(&presets_directory, &filter)
+            })?,
+            None => load_preset_from_directory(&presets_directory, &filter)?,
+        },
         // No arguments: use a random preset from the presets directory
-        None => load_preset_from_directory(&presets_directory)?,
+        None => load_preset_from_directory(&presets_directory, &filter)?,
         // One argument: treat as a file (TOML or JSON)
-        Some(path) => load_preset_from_file_or_json(path)?,
+        Some(path) => load_preset_from_file_or_json(path).map_err(|err| match err {
+            // Offline rendering must not silently fall back to the default preset
+            CliError::InvalidInput(msg)
+                if matches!(command, Command::Screenshot(_) | Command::Export(_)) =>
+            {
+                CliError::OfflineRender(msg)
+            }
+            err => err,
+        })?,
     };
 
     if let Some(path) = &preset_path {
         log::info!("Loaded {}", path.display());
+        check_complexity(&preset, strict)?;
     }
 
     Ok(CliConfig {
         preset,
         preset_path,
         show_overlay,
+        log_level,
+        log_file,
+        pause_when_occluded,
+        command,
+        daemon,
+        hotkeys,
+        hud,
+        profile,
+        preview,
+        session_lock,
+        strict,
+        gpu,
+        gpu_watchdog,
+        layer,
+        preset_filter: filter,
+        time_source,
+        date,
     })
 }
 
+/// Parses a duration such as `"12.5s"` or `"1m 30s"`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    humantime_serde::re::humantime::parse_duration(value).map_err(|err| err.to_string())
+}
+
+/// Parses a time source such as `"clock"` or `"fixed:12.5"`.
+fn parse_time_source(value: &str) -> Result<TimeSource, String> {
+    let invalid = || format!("Invalid time source '{value}' (expected clock or fixed:<SECONDS>)");
+    match value.split_once(':') {
+        None if value == "clock" => Ok(TimeSource::Clock),
+        Some(("fixed", seconds)) => seconds
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .map(TimeSource::Fixed)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a check output format, `"text"` or `"json"`.
+fn parse_check_format(value: &str) -> Result<CheckFormat, String> {
+    match value {
+        "text" => Ok(CheckFormat::Text),
+        "json" => Ok(CheckFormat::Json),
+        _ => Err(format!("Invalid format '{value}' (expected text or json)")),
+    }
+}
+
+/// Parses a window layer, `"background"`, `"top"` or `"overlay"`.
+fn parse_window_layer(value: &str) -> Result<WindowLayer, String> {
+    match value {
+        "background" => Ok(WindowLayer::Background),
+        "top" => Ok(WindowLayer::Top),
+        "overlay" => Ok(WindowLayer::Overlay),
+        _ => Err(format!(
+            "Invalid layer '{value}' (expected background, top or overlay)"
+        )),
+    }
+}
+
+/// Parses a ShaderToy search order such as `"popular"`.
+fn parse_search_sort(value: &str) -> Result<SearchSort, String> {
+    match value {
+        "name" => Ok(SearchSort::Name),
+        "love" => Ok(SearchSort::Love),
+        "popular" => Ok(SearchSort::Popular),
+        "newest" => Ok(SearchSort::Newest),
+        "hot" => Ok(SearchSort::Hot),
+        _ => Err(format!(
+            "Invalid order '{value}' (expected name, love, popular, newest or hot)"
+        )),
+    }
+}
+
+/// Parses a date such as `"2025-12-24"`.
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{value}' (expected YYYY-MM-DD)"))
+}
+
+/// Parses a resolution such as `"1920x1080"`.
+fn parse_size(value: &str) -> Result<Size, String> {
+    let invalid = || format!("Invalid size '{value}' (expected WIDTHxHEIGHT)");
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width = width.trim().parse::<u32>().map_err(|_| invalid())?;
+    let height = height.trim().parse::<u32>().map_err(|_| invalid())?;
+
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+
+    Ok(Size::new(width, height))
+}
+
 /// Loads a preset from either an explicit filesystem path,
 /// or a filename located inside the presets directory.
 ///
 /// File type is determined by extension when possible,
 /// otherwise TOML and JSON loaders are attempted sequentially.
-fn load_preset_from_file_or_json(file: &Path) -> Result<(Preset, Option<PathBuf>), CliError> {
+pub fn load_preset_from_file_or_json(file: &Path) -> Result<(Preset, Option<PathBuf>), CliError> {
     let resolved = if file.exists() {
         file.to_path_buf()
     } else {
@@ -131,7 +1047,7 @@ fn load_preset_from_file_or_json(file: &Path) -> Result<(Preset, Option<PathBuf>
     };
 
     match resolved.extension().and_then(|s| s.to_str()) {
-        Some("toml") => Ok(load_preset_from_toml_file(&resolved)?),
+        Some("toml" | BUNDLE_EXTENSION) => Ok(load_preset_from_toml_file(&resolved)?),
         Some("json") => Ok(load_preset_from_json_file(&resolved)?),
         _ => load_preset_from_toml_file(&resolved)
             .or_else(|_| load_preset_from_json_file(&resolved))
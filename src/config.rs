@@ -0,0 +1,195 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Global configuration file.
+//!
+//! `$XDG_CONFIG_HOME/shaderbg/config.toml` provides defaults that apply
+//! to every preset and run, such as the overlay visibility or the
+//! ShaderToy API key. Every key is optional, and command-line options
+//! and preset settings take precedence over it.
+//!
+//! ```toml
+//! show_overlay = false
+//! log_level = "info,frame_controller=trace"
+//! monitor_selection = ["DP-1"]
+//! shadertoy_api_key = "..."
+//! gpu = "card1"
+//! gpu_watchdog = "2s"
+//!
+//! [location]
+//! latitude = -23.55
+//! longitude = -46.63
+//!
+//! [battery]
+//! resolution_scale = 0.5
+//!
+//! [overlay]
+//! corner = "top_right"
+//! template = "{name} — {author} ({id})"
+//!
+//! [screensaver]
+//! preset = "rain.toml"
+//! idle_timeout = "5m"
+//!
+//! [session_lock]
+//! auth_command = "pamtester login \"$USER\" authenticate"
+//!
+//! [screen_capture]
+//! allow = true
+//! interval = "1s"
+//! ```
+
+use serde::Deserialize;
+use std::{fs, io, path::PathBuf, sync::OnceLock, time::Duration};
+use thiserror::Error;
+
+use crate::{
+    idle_monitor::Screensaver,
+    logging::{parse_log_filter, LogFilter},
+    preset::{OverlayStyle, PowerProfile},
+    renderer::{parse_gpu_watchdog_timeout, ScreenCaptureConfig},
+    session_lock::SessionLockConfig,
+    sun::Location,
+    APP_NAME,
+};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read {}: {err}", path.display())]
+    Io { path: PathBuf, err: io::Error },
+
+    #[error("Failed to parse {}: {err}", path.display())]
+    Parse {
+        path: PathBuf,
+        err: Box<toml::de::Error>,
+    },
+}
+
+/// Contents of the configuration file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Shows the shader information overlay, unless `--no-overlay` or
+    /// `--overlay` is given.
+    pub show_overlay: Option<bool>,
+
+    /// Levels of the log messages printed, per module, unless
+    /// `--log-level` is given.
+    #[serde(deserialize_with = "deserialize_log_filter")]
+    pub log_level: Option<LogFilter>,
+
+    /// Monitors used by presets selecting all monitors, as by default.
+    pub monitor_selection: Option<Vec<String>>,
+
+    /// Overrides applied on battery power to presets without a
+    /// `battery` profile.
+    pub battery: Option<PowerProfile>,
+
+    /// ShaderToy API key used unless `--key` or `$SHADERTOY_API_KEY` is
+    /// given.
+    pub shadertoy_api_key: Option<String>,
+
+    /// Location used for the sun position in `iDayPhase` and
+    /// `iSunPosition`.
+    pub location: Option<Location>,
+
+    /// GPU to render on, unless `--gpu` is given.
+    pub gpu: Option<String>,
+
+    /// Time the GPU is given to finish a frame before the preset is
+    /// aborted, unless `--gpu-watchdog` is given.
+    #[serde(deserialize_with = "deserialize_gpu_watchdog")]
+    pub gpu_watchdog: Option<Duration>,
+
+    /// Style of the information overlay of presets without an `overlay`
+    /// table.
+    pub overlay: Option<OverlayStyle>,
+
+    /// Preset switched to while the user is idle.
+    pub screensaver: Option<Screensaver>,
+
+    /// Password check of the lock screen of `--session-lock`.
+    pub session_lock: Option<SessionLockConfig>,
+
+    /// Permission and rate of `screen` inputs.
+    pub screen_capture: Option<ScreenCaptureConfig>,
+}
+
+/// Loads the configuration file, if any.
+///
+/// Must be called before [`config`] to have any effect. On error, the
+/// defaults are used.
+pub fn init() -> Result<(), ConfigError> {
+    let (config, result) = match load() {
+        Ok(config) => (config, Ok(())),
+        Err(err) => (Config::default(), Err(err)),
+    };
+    let _ = CONFIG.set(config);
+    result
+}
+
+/// Returns the loaded configuration.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Returns the path of the configuration file.
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Returns the cache directory of the application.
+///
+/// Fails without `$XDG_CACHE_HOME` or `$HOME` rather than falling back
+/// to the working directory, which is `/` for a daemon.
+pub fn cache_dir() -> io::Result<PathBuf> {
+    dirs::cache_dir()
+        .map(|dir| dir.join(APP_NAME))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Could not find $XDG_CACHE_HOME or $HOME/.cache",
+            )
+        })
+}
+
+fn load() -> Result<Config, ConfigError> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => return Err(ConfigError::Io { path, err }),
+    };
+
+    toml::from_str(&content).map_err(|err| ConfigError::Parse {
+        path,
+        err: Box::new(err),
+    })
+}
+
+fn deserialize_gpu_watchdog<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_gpu_watchdog_timeout(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn deserialize_log_filter<'de, D>(deserializer: D) -> Result<Option<LogFilter>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_log_filter(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
@@ -0,0 +1,45 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Daemon mode state.
+//!
+//! With `--daemon`, the path of the last applied preset is stored in
+//! `$XDG_STATE_HOME/shaderbg/last_preset` so that the wallpaper started
+//! at the next login is the one that was last switched to.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::APP_NAME;
+
+/// Name of the file storing the path of the last applied preset.
+const LAST_PRESET_FILE: &str = "last_preset";
+
+/// Returns the directory of the daemon state, `$XDG_STATE_HOME/shaderbg`.
+fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir().map(|dir| dir.join(APP_NAME))
+}
+
+/// Returns the path of the last applied preset, if it still exists.
+pub fn load_last_preset() -> Option<PathBuf> {
+    let contents = fs::read_to_string(state_dir()?.join(LAST_PRESET_FILE)).ok()?;
+    let path = PathBuf::from(contents.trim_end_matches('\n'));
+    path.exists().then_some(path)
+}
+
+/// Stores `preset_path` as the last applied preset.
+pub fn save_last_preset(preset_path: &Path) -> io::Result<()> {
+    let dir =
+        state_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No state directory"))?;
+    fs::create_dir_all(&dir)?;
+
+    let preset_path = fs::canonicalize(preset_path)?;
+    fs::write(
+        dir.join(LAST_PRESET_FILE),
+        format!("{}\n", preset_path.display()),
+    )
+}
@@ -0,0 +1,432 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Desktop activity exposed to shaders through `iDesktop`.
+//!
+//! Watches the IPC event stream of Hyprland or Sway, and queries the
+//! active workspace and its windows whenever workspaces or windows
+//! change, so that wallpapers can react to desktop activity, e.g., by
+//! dimming when many windows are open. The uniform is laid out as:
+//!
+//! | Component | Value                                             |
+//! |-----------|---------------------------------------------------|
+//! | x         | Number of the active workspace                    |
+//! | y         | Number of windows on the active workspace         |
+//! | z         | 1 if the active workspace has a fullscreen window |
+//! | w         | Number of windows on all workspaces               |
+//!
+//! The IPC is also queried for the focused monitor, on which the
+//! information overlay is shown on demand.
+
+use serde_json::Value;
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Values of the `iDesktop` uniform.
+pub type DesktopState = [f32; 4];
+
+/// Timeout of each request. [`focused_output`] is queried from the main
+/// loop, so an unresponsive compositor must not stall rendering for long.
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Magic string starting every Sway IPC message.
+const SWAY_IPC_MAGIC: &[u8; 6] = b"i3-ipc";
+
+/// Sway IPC message types.
+const SWAY_GET_WORKSPACES: u32 = 1;
+const SWAY_SUBSCRIBE: u32 = 2;
+const SWAY_GET_OUTPUTS: u32 = 3;
+const SWAY_GET_TREE: u32 = 4;
+
+/// Sway events after which the desktop state is queried again.
+const SWAY_EVENTS: &str = r#"["workspace","window"]"#;
+
+/// Hyprland events after which the desktop state is queried again.
+const HYPRLAND_EVENTS: &[&str] = &[
+    "workspace",
+    "workspacev2",
+    "focusedmon",
+    "focusedmonv2",
+    "createworkspace",
+    "createworkspacev2",
+    "destroyworkspace",
+    "destroyworkspacev2",
+    "moveworkspace",
+    "moveworkspacev2",
+    "openwindow",
+    "closewindow",
+    "movewindow",
+    "movewindowv2",
+    "fullscreen",
+];
+
+#[derive(Debug, Error)]
+pub enum DesktopError {
+    #[error("iDesktop requires Hyprland or Sway")]
+    Unsupported,
+
+    #[error("Failed to subscribe to compositor events: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Compositor IPC socket.
+#[derive(Clone)]
+enum Compositor {
+    Hyprland(PathBuf),
+    Sway(PathBuf),
+}
+
+impl Compositor {
+    /// Returns the IPC socket of the running compositor, or `None` if it
+    /// is neither Hyprland nor Sway.
+    fn from_env() -> Option<Self> {
+        if let Some(signature) = env::var_os("HYPRLAND_INSTANCE_SIGNATURE") {
+            let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+            let socket = [runtime_dir, Some(PathBuf::from("/tmp"))]
+                .into_iter()
+                .flatten()
+                .map(|dir| dir.join("hypr").join(&signature).join(".socket.sock"))
+                .find(|path| path.exists())?;
+            Some(Self::Hyprland(socket))
+        } else {
+            Some(Self::Sway(PathBuf::from(env::var_os("SWAYSOCK")?)))
+        }
+    }
+
+    /// Returns the name of the compositor, for diagnostics.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Hyprland(_) => "Hyprland",
+            Self::Sway(_) => "Sway",
+        }
+    }
+
+    /// Queries the state of the desktop.
+    fn query(&self) -> io::Result<Option<DesktopState>> {
+        match self {
+            Self::Hyprland(socket) => query_hyprland(socket),
+            Self::Sway(socket) => query_sway(socket),
+        }
+    }
+
+    /// Connects to the event stream of the compositor.
+    fn subscribe(&self) -> io::Result<UnixStream> {
+        match self {
+            // Events are sent on a second socket next to the request one
+            Self::Hyprland(socket) => UnixStream::connect(socket.with_file_name(".socket2.sock")),
+            Self::Sway(socket) => {
+                let mut stream = UnixStream::connect(socket)?;
+                write_sway_message(&mut stream, SWAY_SUBSCRIBE, SWAY_EVENTS.as_bytes())?;
+                let reply = read_sway_message(&mut stream)?;
+                if reply["success"].as_bool() != Some(true) {
+                    return Err(io::Error::other("subscription rejected"));
+                }
+                Ok(stream)
+            }
+        }
+    }
+
+    /// Blocks until the next event after which the desktop state must be
+    /// queried again.
+    ///
+    /// Returns `Ok(false)` when the event stream ends.
+    fn wait_for_change(&self, events: &mut BufReader<UnixStream>) -> io::Result<bool> {
+        loop {
+            match self {
+                Self::Hyprland(_) => {
+                    let mut line = String::new();
+                    if events.read_line(&mut line)? == 0 {
+                        return Ok(false);
+                    }
+                    if is_hyprland_change(&line) {
+                        return Ok(true);
+                    }
+                }
+                Self::Sway(_) => {
+                    let event = read_sway_message(events)?;
+                    if is_sway_change(&event) {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the connector name of the focused monitor, e.g., `DP-1`, or
+/// `None` if the compositor is neither Hyprland nor Sway.
+pub fn focused_output() -> Option<String> {
+    let outputs = match Compositor::from_env()? {
+        Compositor::Hyprland(socket) => hyprland_request(&socket, "j/monitors"),
+        Compositor::Sway(socket) => sway_request(&socket, SWAY_GET_OUTPUTS),
+    };
+    match outputs {
+        Ok(outputs) => focused_output_name(&outputs),
+        Err(err) => {
+            log::debug!("Failed to query the focused output: {err}");
+            None
+        }
+    }
+}
+
+/// Returns the name of the focused output in the reply to a Hyprland
+/// `j/monitors` request or a Sway `GET_OUTPUTS` message.
+pub fn focused_output_name(outputs: &Value) -> Option<String> {
+    outputs
+        .as_array()?
+        .iter()
+        .find(|output| output["focused"].as_bool() == Some(true))
+        .and_then(|output| output["name"].as_str())
+        .map(str::to_string)
+}
+
+/// Watches the compositor for the state of the desktop.
+pub struct DesktopMonitor {
+    compositor_name: &'static str,
+    state: Arc<Mutex<DesktopState>>,
+
+    /// Event stream of the compositor, shut down to end the watcher.
+    events: UnixStream,
+    watcher: Option<JoinHandle<()>>,
+}
+
+impl DesktopMonitor {
+    /// Subscribes to the events of the running compositor and starts
+    /// watching them in the background.
+    pub fn new() -> Result<Self, DesktopError> {
+        let compositor = Compositor::from_env().ok_or(DesktopError::Unsupported)?;
+        let events = compositor.subscribe()?;
+        let state = Arc::new(Mutex::new(DesktopState::default()));
+
+        let watcher = thread::Builder::new()
+            .name("desktop-monitor".to_string())
+            .spawn({
+                let compositor = compositor.clone();
+                let events = BufReader::new(events.try_clone()?);
+                let state = Arc::clone(&state);
+                move || watch(&compositor, events, &state)
+            })?;
+
+        Ok(Self {
+            compositor_name: compositor.name(),
+            state,
+            events,
+            watcher: Some(watcher),
+        })
+    }
+
+    /// Returns the name of the compositor, for diagnostics.
+    pub fn compositor_name(&self) -> &'static str {
+        self.compositor_name
+    }
+
+    /// Returns the state of the desktop as of the last change.
+    pub fn state(&self) -> DesktopState {
+        *self.state.lock().unwrap()
+    }
+}
+
+impl Drop for DesktopMonitor {
+    fn drop(&mut self) {
+        // Unblocks the watcher waiting for events
+        let _ = self.events.shutdown(Shutdown::Both);
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+    }
+}
+
+/// Queries the state of the desktop initially and after every change,
+/// until the event stream ends. The previous state is kept on failure.
+fn watch(compositor: &Compositor, mut events: BufReader<UnixStream>, state: &Mutex<DesktopState>) {
+    loop {
+        match compositor.query() {
+            Ok(Some(new_state)) => *state.lock().unwrap() = new_state,
+            Ok(None) => log::debug!("Unexpected {} IPC reply", compositor.name()),
+            Err(err) => log::debug!("{} IPC request failed: {err}", compositor.name()),
+        }
+
+        match compositor.wait_for_change(&mut events) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                // Also the case when the monitor is dropped
+                log::debug!("{} event stream closed: {err}", compositor.name());
+                return;
+            }
+        }
+    }
+}
+
+fn connect(socket: &Path) -> io::Result<UnixStream> {
+    let stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    Ok(stream)
+}
+
+/// Returns `true` if a line of the Hyprland event stream, such as
+/// `openwindow>>...`, changes the desktop state.
+pub fn is_hyprland_change(line: &str) -> bool {
+    line.split_once(">>")
+        .is_some_and(|(event, _)| HYPRLAND_EVENTS.contains(&event))
+}
+
+/// Sends a Hyprland request, such as `j/workspaces`, and parses the
+/// JSON reply.
+fn hyprland_request(socket: &Path, request: &str) -> io::Result<Value> {
+    let mut stream = connect(socket)?;
+    stream.write_all(request.as_bytes())?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    serde_json::from_slice(&reply).map_err(io::Error::other)
+}
+
+fn query_hyprland(socket: &Path) -> io::Result<Option<DesktopState>> {
+    let active = hyprland_request(socket, "j/activeworkspace")?;
+    let workspaces = hyprland_request(socket, "j/workspaces")?;
+    Ok(hyprland_state(&active, &workspaces))
+}
+
+/// Returns the desktop state from the replies to the Hyprland
+/// `j/activeworkspace` and `j/workspaces` requests.
+pub fn hyprland_state(active: &Value, workspaces: &Value) -> Option<DesktopState> {
+    let windows = |workspace: &Value| workspace["windows"].as_u64().unwrap_or(0);
+    let id = active["id"].as_i64()?;
+    let total_windows: u64 = workspaces.as_array()?.iter().map(windows).sum();
+
+    Some([
+        id as f32,
+        windows(active) as f32,
+        active["hasfullscreen"].as_bool().unwrap_or(false) as u8 as f32,
+        total_windows as f32,
+    ])
+}
+
+/// Returns `true` if a Sway event changes the desktop state, i.e., it
+/// is not a change of the title or marks of a window.
+pub fn is_sway_change(event: &Value) -> bool {
+    !matches!(event["change"].as_str(), Some("title" | "mark" | "urgent"))
+}
+
+/// Writes a Sway IPC message.
+fn write_sway_message(
+    stream: &mut impl Write,
+    message_type: u32,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut message = SWAY_IPC_MAGIC.to_vec();
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(payload);
+    stream.write_all(&message)
+}
+
+/// Reads a Sway IPC message, reply or event, and parses its JSON payload.
+fn read_sway_message(stream: &mut impl Read) -> io::Result<Value> {
+    let mut header = [0; SWAY_IPC_MAGIC.len() + 8];
+    stream.read_exact(&mut header)?;
+    if &header[..SWAY_IPC_MAGIC.len()] != SWAY_IPC_MAGIC {
+        return Err(io::Error::other("invalid reply header"));
+    }
+    let length = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+
+    let mut payload = vec![0; length as usize];
+    stream.read_exact(&mut payload)?;
+    serde_json::from_slice(&payload).map_err(io::Error::other)
+}
+
+/// Sends a Sway IPC message and parses the JSON reply.
+fn sway_request(socket: &Path, message_type: u32) -> io::Result<Value> {
+    let mut stream = connect(socket)?;
+    write_sway_message(&mut stream, message_type, &[])?;
+    read_sway_message(&mut stream)
+}
+
+fn query_sway(socket: &Path) -> io::Result<Option<DesktopState>> {
+    let workspaces = sway_request(socket, SWAY_GET_WORKSPACES)?;
+    let tree = sway_request(socket, SWAY_GET_TREE)?;
+    Ok(sway_state(&workspaces, &tree))
+}
+
+/// Returns the desktop state from the replies to the Sway
+/// `GET_WORKSPACES` and `GET_TREE` messages.
+pub fn sway_state(workspaces: &Value, tree: &Value) -> Option<DesktopState> {
+    let focused = workspaces
+        .as_array()?
+        .iter()
+        .find(|workspace| workspace["focused"].as_bool() == Some(true))?;
+    let focused_name = &focused["name"];
+
+    let mut workspace_nodes = Vec::new();
+    collect_sway_workspaces(tree, &mut workspace_nodes);
+    let active = workspace_nodes
+        .iter()
+        .find(|node| &node["name"] == focused_name)?;
+
+    let mut active_windows = Vec::new();
+    collect_sway_windows(active, &mut active_windows);
+    let fullscreen = active_windows
+        .iter()
+        .any(|window| window["fullscreen_mode"].as_u64().unwrap_or(0) > 0);
+    let total_windows: usize = workspace_nodes
+        .iter()
+        .map(|workspace| {
+            let mut windows = Vec::new();
+            collect_sway_windows(workspace, &mut windows);
+            windows.len()
+        })
+        .sum();
+
+    Some([
+        focused["num"].as_i64().unwrap_or(-1) as f32,
+        active_windows.len() as f32,
+        fullscreen as u8 as f32,
+        total_windows as f32,
+    ])
+}
+
+/// Returns the child nodes of a Sway tree node, tiled and floating.
+fn sway_children(node: &Value) -> impl Iterator<Item = &Value> {
+    ["nodes", "floating_nodes"]
+        .into_iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+}
+
+/// Collects the workspaces of a Sway tree, except the scratchpad.
+fn collect_sway_workspaces<'a>(node: &'a Value, workspaces: &mut Vec<&'a Value>) {
+    if node["type"] == "workspace" {
+        if node["name"] != "__i3_scratch" {
+            workspaces.push(node);
+        }
+        return;
+    }
+    for child in sway_children(node) {
+        collect_sway_workspaces(child, workspaces);
+    }
+}
+
+/// Collects the windows below a Sway tree node, i.e., the leaves with a
+/// client process.
+fn collect_sway_windows<'a>(node: &'a Value, windows: &mut Vec<&'a Value>) {
+    if node["pid"].is_u64() && sway_children(node).next().is_none() {
+        windows.push(node);
+        return;
+    }
+    for child in sway_children(node) {
+        collect_sway_windows(child, windows);
+    }
+}
@@ -3,19 +3,64 @@
 // https://github.com/hbatagelo/shaderbg
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-//! DRM/KMS output detection utilities.
+//! DRM/KMS connector enumeration and hotplug monitoring.
 //!
-//! Provides minimal helpers for querying `/dev/dri/card*` devices to
-//! determine whether a connected display output is available.
+//! Queries `/dev/dri/card*` devices for their connectors, including names,
+//! display modes and EDID identification, which GDK does not expose.
 //!
-//! Used during startup to decide whether DRM-backed rendering can be
-//! initialized or if fallback monitor detection should be used.
+//! [`DrmMonitor`] keeps the connector list up to date by listening to
+//! kernel uevents of the `drm` subsystem, the same events udev receives
+//! on hotplug. It is used to decide whether rendering windows can be
+//! created and to identify monitors for `monitor_selection`.
 
 use drm::{
-    control::{connector::State, Device as ControlDevice},
+    control::{connector, Device as ControlDevice},
     Device,
 };
-use std::{error::Error, fs::*, os::fd::*};
+use gtk::glib::{self, ExitCode};
+use std::{
+    cell::RefCell,
+    fmt,
+    fs::*,
+    io,
+    os::fd::*,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use thiserror::Error;
+
+use crate::catalog::print_table;
+
+/// Maximum number of DRM card devices probed.
+const MAX_DRM_CARDS: u32 = 4;
+
+/// Netlink multicast group of kernel uevents.
+const KERNEL_UEVENT_GROUP: u32 = 1;
+
+/// Size of the buffer receiving a uevent.
+const UEVENT_BUFFER_SIZE: usize = 8192;
+
+/// Directories of the EGL vendor libraries of GLVND, in order of
+/// precedence.
+const EGL_VENDOR_DIRS: [&str; 2] = ["/etc/glvnd/egl_vendor.d", "/usr/share/glvnd/egl_vendor.d"];
+
+#[derive(Debug, Error)]
+pub enum DrmError {
+    #[error("No DRM cards accessible")]
+    NoCards,
+
+    #[error("DRM query failed: {0}")]
+    Query(#[from] io::Error),
+
+    #[error("GPU '{0}' not found")]
+    UnknownGpu(String),
+
+    #[error("GPU '{0}' is not a PCI device")]
+    NotPciGpu(String),
+
+    #[error("EGL library of the NVIDIA driver not found for GPU '{0}'")]
+    NoNvidiaEgl(String),
+}
 
 /// Thin wrapper around a DRM device node (`/dev/dri/card*`).
 ///
@@ -46,60 +91,532 @@ impl Card {
     /// - `/dev/dri/card1`
     ///
     /// Fails if the device does not exist or permission is denied.
-    fn try_open(path: &str) -> Result<Self, Box<dyn Error>> {
+    fn try_open(path: &str) -> io::Result<Self> {
         let mut options = OpenOptions::new();
         options.read(true);
         options.write(false);
         let file = options.open(path)?;
         Ok(Card(file))
     }
+
+    /// Reads the EDID blob of a connector, if any.
+    fn edid(&self, handle: connector::Handle) -> io::Result<Option<Vec<u8>>> {
+        let properties = self.get_properties(handle)?;
+        for (&property, &value) in properties.iter() {
+            if self.get_property(property)?.name().to_bytes() == b"EDID" {
+                return match value {
+                    0 => Ok(None),
+                    blob => self.get_property_blob(blob).map(Some),
+                };
+            }
+        }
+        Ok(None)
+    }
 }
 
-/// Detects whether at least one DRM display output is currently active.
+/// Display mode supported by a connector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayMode {
+    pub width: u16,
+    pub height: u16,
+
+    /// Vertical refresh rate, in Hz.
+    pub refresh_rate: u32,
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}@{}", self.width, self.height, self.refresh_rate)
+    }
+}
+
+/// Monitor identification read from the EDID of a connector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edid {
+    /// Three-letter PNP manufacturer ID, e.g., `DEL`.
+    pub manufacturer: String,
+
+    pub product_code: u16,
+
+    /// Numeric serial number, zero when unset.
+    pub serial_number: u32,
+
+    /// Model name from the display product name descriptor.
+    pub model: Option<String>,
+
+    /// Serial from the display product serial descriptor.
+    pub serial: Option<String>,
+}
+
+impl Edid {
+    /// Parses the base block of an EDID, or returns `None` if `data` is
+    /// not an EDID.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+        const DESCRIPTORS_OFFSET: usize = 54;
+        const DESCRIPTOR_SIZE: usize = 18;
+        const PRODUCT_NAME_TAG: u8 = 0xfc;
+        const PRODUCT_SERIAL_TAG: u8 = 0xff;
+
+        if data.len() < 128 || data[..8] != HEADER {
+            return None;
+        }
+
+        // Three 5-bit letters, where 1 is 'A'
+        let id = u16::from_be_bytes([data[8], data[9]]);
+        let manufacturer = [10, 5, 0]
+            .into_iter()
+            .map(|shift| char::from(b'A' - 1 + ((id >> shift) & 0x1f) as u8))
+            .collect();
+
+        let mut model = None;
+        let mut serial = None;
+        for descriptor in data[DESCRIPTORS_OFFSET..DESCRIPTORS_OFFSET + 4 * DESCRIPTOR_SIZE]
+            .chunks_exact(DESCRIPTOR_SIZE)
+            .filter(|descriptor| descriptor[..3] == [0, 0, 0])
+        {
+            let text = descriptor_text(&descriptor[5..]);
+            match descriptor[3] {
+                PRODUCT_NAME_TAG => model = text,
+                PRODUCT_SERIAL_TAG => serial = text,
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            manufacturer,
+            product_code: u16::from_le_bytes([data[10], data[11]]),
+            serial_number: u32::from_le_bytes([data[12], data[13], data[14], data[15]]),
+            model,
+            serial,
+        })
+    }
+
+    /// Returns the identifier of the monitor for `monitor_selection`,
+    /// `MODEL#SERIAL`, or just `MODEL` for monitors without a serial.
+    ///
+    /// The model is the product name, or else the manufacturer ID and
+    /// product code, e.g., `DEL A0B1`.
+    pub fn identifier(&self) -> String {
+        let model = self
+            .model
+            .clone()
+            .unwrap_or_else(|| format!("{} {:04X}", self.manufacturer, self.product_code));
+        let serial = self
+            .serial
+            .clone()
+            .or_else(|| (self.serial_number != 0).then(|| self.serial_number.to_string()));
+        match serial {
+            Some(serial) => format!("{model}#{serial}"),
+            None => model,
+        }
+    }
+}
+
+impl fmt::Display for Edid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.model {
+            Some(model) => write!(f, "{} {model}", self.manufacturer)?,
+            None => write!(f, "{} {:04X}", self.manufacturer, self.product_code)?,
+        }
+        if let Some(serial) = &self.serial {
+            write!(f, " ({serial})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the text of an EDID display descriptor, which ends with a
+/// line feed and is padded with spaces.
+pub fn descriptor_text(bytes: &[u8]) -> Option<String> {
+    let text = bytes.split(|&byte| byte == b'\n').next().unwrap_or(bytes);
+    let text = String::from_utf8_lossy(text).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Connector of a DRM card.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connector {
+    /// Connector name, as also reported by GDK, e.g., `DP-1`.
+    pub name: String,
+
+    /// Whether a display is physically attached.
+    pub connected: bool,
+
+    /// Whether an encoder is assigned, i.e., the display pipeline is
+    /// configured.
+    pub enabled: bool,
+
+    /// Modes supported by the attached display.
+    pub modes: Vec<DisplayMode>,
+
+    pub edid: Option<Edid>,
+}
+
+impl Connector {
+    /// Returns `true` if a display is attached and ready for output.
+    pub fn is_active(&self) -> bool {
+        self.connected && self.enabled
+    }
+
+    /// Returns `true` if a `monitor_selection` entry selects this
+    /// connector.
+    ///
+    /// An entry matches the connector name, the EDID identifier of the
+    /// display, or the model part of it, which selects every display of
+    /// that model.
+    pub fn is_selected_by(&self, entry: &str) -> bool {
+        if entry == self.name {
+            return true;
+        }
+        let Some(identifier) = self.edid.as_ref().map(Edid::identifier) else {
+            return false;
+        };
+        entry == identifier || (!entry.contains('#') && identifier.split('#').next() == Some(entry))
+    }
+
+    /// Describes the attached display and its preferred mode for logging.
+    pub fn description(&self) -> String {
+        let display = self
+            .edid
+            .as_ref()
+            .map_or_else(|| "unknown display".to_string(), Edid::to_string);
+        match self.modes.first() {
+            Some(mode) => format!("{display}, {mode}"),
+            None => display,
+        }
+    }
+}
+
+/// Enumerates the connectors of all accessible DRM cards.
 ///
-/// A connector is considered usable when:
-/// - it reports `Connected` state (physical display attached), and
-/// - an encoder is assigned (display pipeline configured).
+/// Returns an error if no DRM card could be accessed.
+pub fn enumerate_connectors() -> Result<Vec<Connector>, DrmError> {
+    let mut any_card_accessible = false;
+    let mut connectors = Vec::new();
+
+    for card_num in 0..MAX_DRM_CARDS {
+        let card_path = format!("/dev/dri/card{card_num}");
+
+        // Ignore missing devices or permission failures
+        let Ok(card) = Card::try_open(&card_path) else {
+            continue;
+        };
+        any_card_accessible = true;
+
+        for &handle in card.resource_handles()?.connectors() {
+            let info = card.get_connector(handle, false)?;
+            let connected = info.state() == connector::State::Connected;
+            let edid = if connected {
+                card.edid(handle)?.as_deref().and_then(Edid::parse)
+            } else {
+                None
+            };
+
+            connectors.push(Connector {
+                name: info.to_string(),
+                connected,
+                enabled: info.current_encoder().is_some(),
+                modes: info
+                    .modes()
+                    .iter()
+                    .map(|mode| {
+                        let (width, height) = mode.size();
+                        DisplayMode {
+                            width,
+                            height,
+                            refresh_rate: mode.vrefresh(),
+                        }
+                    })
+                    .collect(),
+                edid,
+            });
+        }
+    }
+
+    if any_card_accessible {
+        Ok(connectors)
+    } else {
+        Err(DrmError::NoCards)
+    }
+}
+
+/// Detects whether at least one DRM display output is currently active.
 ///
 /// Returns:
 /// - `Ok(true)` if at least one active output was detected
 /// - `Ok(false)` if the DRM devices are accessible but with no connected displays
 /// - `Err(_)` if no DRM devices could be accessed
+pub fn has_connected_drm_output() -> Result<bool, DrmError> {
+    Ok(enumerate_connectors()?.iter().any(Connector::is_active))
+}
+
+/// Makes the graphics driver render on a GPU instead of its default one,
+/// e.g., the integrated GPU of a hybrid laptop to save power.
 ///
-/// This function is used to determine whether a DRM/KMS rendering backend can be initialized.
-pub fn has_connected_drm_output() -> Result<bool, Box<dyn Error>> {
-    let mut any_card_accessible = false;
+/// `gpu` is a DRM card name, such as `card1` or `/dev/dri/card1`, or a
+/// PCI address, such as `0000:01:00.0`. GDK does not expose device
+/// selection, so this sets the environment variables of Mesa
+/// (`DRI_PRIME`) or of the NVIDIA driver (PRIME render offload) and must
+/// be called before any GL context is created. GDK renders through EGL,
+/// so the NVIDIA EGL library is selected as the GLVND vendor.
+pub fn select_gpu(gpu: &str) -> Result<(), DrmError> {
+    let card = gpu.strip_prefix("/dev/dri/").unwrap_or(gpu);
+    let device = [
+        Path::new("/sys/class/drm").join(card).join("device"),
+        Path::new("/sys/bus/pci/devices").join(gpu),
+    ]
+    .into_iter()
+    .find_map(|path| canonicalize(path).ok())
+    .ok_or_else(|| DrmError::UnknownGpu(gpu.to_string()))?;
 
-    // Maximum number of DRM card devices probed
-    const MAX_DRM_CARDS: u32 = 4;
+    let pci_address = device
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|_| device.starts_with("/sys/devices/pci"))
+        .ok_or_else(|| DrmError::NotPciGpu(gpu.to_string()))?;
+    let driver = read_link(device.join("driver"))
+        .ok()
+        .and_then(|driver| Some(driver.file_name()?.to_string_lossy().into_owned()));
 
-    for card_num in 0..MAX_DRM_CARDS {
-        let card_path = format!("/dev/dri/card{card_num}");
+    log::info!(
+        "Selecting GPU {pci_address} ({})",
+        driver.as_deref().unwrap_or("unknown driver")
+    );
+    if driver.as_deref() == Some("nvidia") {
+        let vendor_file =
+            nvidia_egl_vendor_file().ok_or_else(|| DrmError::NoNvidiaEgl(gpu.to_string()))?;
+        std::env::set_var("__NV_PRIME_RENDER_OFFLOAD", "1");
+        std::env::set_var("__EGL_VENDOR_LIBRARY_FILENAMES", vendor_file);
+    } else {
+        let dri_prime = format!("pci-{}", pci_address.replace([':', '.'], "_"));
+        std::env::set_var("DRI_PRIME", dri_prime);
+    }
+
+    Ok(())
+}
+
+/// Returns the GLVND vendor file of the NVIDIA EGL library, such as
+/// `/usr/share/glvnd/egl_vendor.d/10_nvidia.json`.
+fn nvidia_egl_vendor_file() -> Option<PathBuf> {
+    EGL_VENDOR_DIRS.iter().find_map(|dir| {
+        let mut files: Vec<_> = read_dir(dir)
+            .ok()?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().contains("nvidia"))
+            })
+            .collect();
+        files.sort();
+        files.into_iter().next()
+    })
+}
+
+/// Keeps the connectors of the DRM cards up to date across hotplugs.
+///
+/// Dropping the monitor stops listening to uevents.
+pub struct DrmMonitor {
+    connectors: Rc<RefCell<Vec<Connector>>>,
+    source_id: Option<glib::SourceId>,
+}
+
+impl DrmMonitor {
+    /// Enumerates the connectors and starts listening to hotplug events.
+    ///
+    /// `on_change` is invoked from the main loop with the previous and
+    /// the current connectors whenever a uevent changes them. If uevents
+    /// are unavailable, the connectors are only updated by
+    /// [`DrmMonitor::refresh`].
+    pub fn new<F>(on_change: F) -> Result<Self, DrmError>
+    where
+        F: Fn(&[Connector], &[Connector]) + 'static,
+    {
+        let connectors = Rc::new(RefCell::new(enumerate_connectors()?));
+
+        let source_id = match open_uevent_socket() {
+            Ok(socket) => Some(Self::watch(socket, connectors.clone(), on_change)),
+            Err(err) => {
+                log::warn!("DRM hotplug events unavailable: {err}");
+                None
+            }
+        };
+
+        Ok(Self {
+            connectors,
+            source_id,
+        })
+    }
+
+    /// Returns the connectors as of the last hotplug event or refresh.
+    pub fn connectors(&self) -> Vec<Connector> {
+        self.connectors.borrow().clone()
+    }
 
-        match Card::try_open(&card_path) {
-            Ok(card) => {
-                any_card_accessible = true;
-                let resource_handles = card.resource_handles()?;
-
-                for conn in resource_handles.connectors() {
-                    let info = card.get_connector(*conn, false)?;
-                    // Check if:
-                    // 1. The connector has something physically connected (cable plugged in)
-                    // 2. The connector has an encoder assigned (ready for display output)
-                    if info.state() == State::Connected && info.current_encoder().is_some() {
-                        return Ok(true);
+    /// Enumerates the connectors again.
+    ///
+    /// Used when GDK reports a monitor change before the uevent arrives.
+    pub fn refresh(&self) -> Result<(), DrmError> {
+        *self.connectors.borrow_mut() = enumerate_connectors()?;
+        Ok(())
+    }
+
+    /// Returns `true` if at least one connector is active.
+    pub fn has_active_output(&self) -> bool {
+        self.connectors.borrow().iter().any(Connector::is_active)
+    }
+
+    /// Enumerates the connectors again whenever a `drm` uevent arrives.
+    fn watch<F>(
+        socket: OwnedFd,
+        connectors: Rc<RefCell<Vec<Connector>>>,
+        on_change: F,
+    ) -> glib::SourceId
+    where
+        F: Fn(&[Connector], &[Connector]) + 'static,
+    {
+        glib::unix_fd_add_local(
+            socket.as_raw_fd(),
+            glib::IOCondition::IN | glib::IOCondition::HUP | glib::IOCondition::ERR,
+            move |_, condition| {
+                if condition.intersects(glib::IOCondition::HUP | glib::IOCondition::ERR) {
+                    log::warn!("DRM monitor: uevent socket closed");
+                    return glib::ControlFlow::Break;
+                }
+
+                if !receive_drm_uevents(&socket) {
+                    return glib::ControlFlow::Continue;
+                }
+
+                let current = match enumerate_connectors() {
+                    Ok(current) => current,
+                    Err(err) => {
+                        log::warn!("DRM monitor: {err}");
+                        return glib::ControlFlow::Continue;
                     }
+                };
+
+                let previous = connectors.replace(current.clone());
+                if previous != current {
+                    on_change(&previous, &current);
                 }
-            }
-            // Ignore missing devices or permission failures
-            Err(_) => continue,
+                glib::ControlFlow::Continue
+            },
+        )
+    }
+}
+
+impl Drop for DrmMonitor {
+    fn drop(&mut self) {
+        if let Some(source_id) = self.source_id.take() {
+            source_id.remove();
         }
     }
+}
 
-    if !any_card_accessible {
-        Err("No DRM cards accessible".into())
-    } else {
-        // Cards were readable, but no connected outputs exist
-        Ok(false)
+/// Opens a non-blocking netlink socket receiving kernel uevents.
+fn open_uevent_socket() -> io::Result<OwnedFd> {
+    // SAFETY: Plain socket creation; the descriptor is owned on success.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            libc::NETLINK_KOBJECT_UEVENT,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` is a valid descriptor not owned elsewhere.
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // SAFETY: `sockaddr_nl` is plain data, valid when zeroed.
+    let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_groups = KERNEL_UEVENT_GROUP;
+
+    // SAFETY: `address` outlives the call and its size is passed along.
+    let result = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            (&address as *const libc::sockaddr_nl).cast(),
+            size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
     }
+
+    Ok(socket)
+}
+
+/// Drains pending uevents, returning `true` if any came from the `drm`
+/// subsystem.
+fn receive_drm_uevents(socket: &OwnedFd) -> bool {
+    let mut buffer = [0u8; UEVENT_BUFFER_SIZE];
+    let mut any_drm_event = false;
+
+    loop {
+        // SAFETY: `buffer` is valid for writes of its length.
+        let length = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                0,
+            )
+        };
+        let Ok(length) = usize::try_from(length) else {
+            break;
+        };
+
+        // Uevents are NUL-separated `KEY=value` entries after a header
+        any_drm_event |= buffer[..length]
+            .split(|&byte| byte == 0)
+            .any(|entry| entry == b"SUBSYSTEM=drm");
+    }
+
+    any_drm_event
+}
+
+/// Prints the connected monitors with the identifiers accepted by
+/// `monitor_selection`.
+pub fn list_monitors() -> ExitCode {
+    let connectors = match enumerate_connectors() {
+        Ok(connectors) => connectors,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut rows = vec![vec![
+        "CONNECTOR".to_string(),
+        "IDENTIFIER".to_string(),
+        "MODE".to_string(),
+        "STATUS".to_string(),
+    ]];
+    for connector in connectors.iter().filter(|connector| connector.connected) {
+        rows.push(vec![
+            connector.name.clone(),
+            connector
+                .edid
+                .as_ref()
+                .map_or_else(|| "-".to_string(), Edid::identifier),
+            connector
+                .modes
+                .first()
+                .map_or_else(|| "-".to_string(), DisplayMode::to_string),
+            if connector.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+            .to_string(),
+        ]);
+    }
+
+    print_table(&rows);
+
+    ExitCode::SUCCESS
 }
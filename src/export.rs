@@ -0,0 +1,120 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Offline video export.
+//!
+//! Renders a preset offscreen at a fixed timestep and pipes raw RGBA
+//! frames to an `ffmpeg` child process, which encodes them into the
+//! container and codec implied by the output file extension
+//! (e.g. H.264 for `.mp4`, VP9 for `.webm`).
+
+use chrono::NaiveDate;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::{Command, ExitStatus, Stdio},
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{frame_controller::*, geometry::Size, offscreen::*, preset::*};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("{0}")]
+    Offscreen(#[from] OffscreenError),
+
+    #[error("Failed to run ffmpeg: {0}")]
+    Encoder(#[from] io::Error),
+
+    #[error("ffmpeg exited with {0}")]
+    EncoderFailed(ExitStatus),
+
+    #[error("Frame rate must be greater than zero")]
+    InvalidFrameRate,
+}
+
+/// Settings of the `export` subcommand.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Video file to write.
+    pub output: PathBuf,
+
+    /// Length of the exported video.
+    pub duration: Duration,
+
+    /// Frames per second of the exported video.
+    pub fps: u32,
+
+    /// Size of the video frames, in pixels.
+    pub size: Size,
+
+    /// Date reported in `iDate` instead of the current date.
+    pub date: Option<NaiveDate>,
+}
+
+/// Renders `options.duration` of the preset and encodes it with ffmpeg.
+///
+/// Time advances by exactly `1 / fps` per frame, independently of how
+/// long frames take to render, so the result matches the live wallpaper
+/// played at that frame rate.
+pub fn export(preset: &Preset, options: &ExportOptions) -> Result<(), ExportError> {
+    if options.fps == 0 {
+        return Err(ExportError::InvalidFrameRate);
+    }
+
+    let context = OffscreenContext::new()?;
+    let mut renderer = context.renderer(preset, options.size)?;
+
+    let timestep = Duration::from_secs(1) / options.fps;
+    let mut frame_controller =
+        FrameController::with_fixed_timestep(preset, timestep).with_date(options.date);
+    let frame_count = frame_controller.steps_until(options.duration);
+
+    let mut encoder = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+        .args([
+            "-video_size",
+            &format!("{}x{}", options.size.width(), options.size.height()),
+        ])
+        .args(["-framerate", &options.fps.to_string()])
+        .args(["-i", "-", "-pix_fmt", "yuv420p"])
+        .arg(&options.output)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = encoder
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::other("ffmpeg stdin unavailable"))?;
+
+    log::info!(
+        "Exporting {frame_count} frames ({:?} at {} FPS)",
+        options.duration,
+        options.fps
+    );
+
+    let write_result = (0..frame_count).try_for_each(|frame_idx| {
+        render_frame(&mut renderer, &frame_controller.step());
+        let frame = read_frame(&renderer, options.size);
+
+        log::debug!("Exported frame {}/{frame_count}", frame_idx + 1);
+        stdin.write_all(frame.as_raw())
+    });
+
+    // Closing stdin signals the end of the stream
+    drop(stdin);
+    let status = encoder.wait()?;
+    write_result?;
+
+    if !status.success() {
+        return Err(ExportError::EncoderFailed(status));
+    }
+
+    log::info!("Saved {}", options.output.display());
+
+    Ok(())
+}
@@ -9,7 +9,11 @@
 //! maintains animation timing statistics, and drives crossfade-based
 //! frame presentation.
 
-use std::{collections::VecDeque, time::*};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::{
+    collections::{HashSet, VecDeque},
+    time::*,
+};
 
 use crate::preset::Preset;
 
@@ -21,6 +25,27 @@ const INITIAL_FRAMES_TO_SKIP: u32 = 2;
 /// Time window used for smoothed FPS calculation.
 const FRAME_RATE_WINDOW: Duration = Duration::from_secs(1);
 
+/// Slack allowed when comparing frame clock timestamps against the next
+/// frame deadline of a capped frame rate.
+///
+/// Frame clock timestamps are aligned to the display refresh but carry
+/// some jitter; without slack, a 30 FPS cap on a 60 Hz display would
+/// occasionally miss its vblank and drop to 20 FPS.
+const FRAME_DEADLINE_TOLERANCE: Duration = Duration::from_millis(1);
+
+/// Longest `interval_between_frames` whose frames are paced by the frame
+/// clock. Longer intervals are throttled with a timer instead, since
+/// being off by one refresh is imperceptible there and a frame clock
+/// wakes up on every refresh.
+const MAX_PACED_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Weight of a new measurement in the smoothed presentation latency.
+const LATENCY_SMOOTHING: f64 = 0.1;
+
+/// Animation time advanced by [`FrameController::step_frame`] when
+/// neither `fixed_timestep` nor `max_fps` sets a frame interval.
+const DEFAULT_FRAME_STEP: Duration = Duration::from_micros(16_667);
+
 /// Coordinates frame production, presentation timing, and crossfade blending.
 pub struct FrameController {
     /// Animation time multiplier.
@@ -59,6 +84,70 @@ pub struct FrameController {
     /// Idle delay between crossfade cycles.
     /// `interval_between_frames - crossfade_duration`
     idle_duration: Duration,
+
+    /// Minimum interval between frames derived from `max_fps`.
+    /// Zero when the frame rate is uncapped.
+    min_frame_interval: Duration,
+
+    /// Interval between frames paced by the frame clock, either
+    /// `min_frame_interval` or a short `interval_between_frames`.
+    /// Zero when frames are not paced.
+    paced_frame_interval: Duration,
+
+    /// Frame clock time at which the next paced frame is due.
+    next_frame_deadline: Option<Duration>,
+
+    /// Last frame clock frame whose presentation was recorded.
+    last_presented_frame: Option<i64>,
+
+    /// Smoothed time from the frame clock time of a frame to its
+    /// presentation.
+    presentation_latency: Option<Duration>,
+
+    /// Active reasons for suspending frame production.
+    pause_reasons: HashSet<PauseReason>,
+
+    /// Timestamp when frame production was suspended.
+    paused_at: Option<Instant>,
+
+    /// Clock period by which time advances on every frame.
+    /// `None` when time follows the wall clock.
+    fixed_timestep: Option<Duration>,
+
+    /// Source of the reported animation time.
+    time_source: TimeSource,
+
+    /// Date reported instead of the current date.
+    date: Option<NaiveDate>,
+}
+
+/// Source of the animation time reported in [`FrameStats::time`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeSource {
+    /// Time elapsed since the animation started.
+    #[default]
+    Clock,
+
+    /// Constant time, making every frame reproducible.
+    Fixed(Duration),
+}
+
+/// Reason for suspending frame production.
+///
+/// Rendering resumes only once every active reason has been cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauseReason {
+    /// Every wallpaper output is covered by a fullscreen window.
+    Occluded,
+
+    /// Every wallpaper output is powered off (DPMS).
+    PoweredOff,
+
+    /// The system is suspended.
+    Suspended,
+
+    /// The user paused the animation, e.g., with a hotkey.
+    User,
 }
 
 #[derive(Debug, Clone)]
@@ -118,6 +207,16 @@ impl Default for FrameController {
             last_frame_render_time: now,
             crossfade: CrossfadeState::new(Duration::ZERO),
             idle_duration: Duration::ZERO,
+            min_frame_interval: Duration::ZERO,
+            paced_frame_interval: Duration::ZERO,
+            next_frame_deadline: None,
+            last_presented_frame: None,
+            presentation_latency: None,
+            pause_reasons: HashSet::new(),
+            paused_at: None,
+            fixed_timestep: None,
+            time_source: TimeSource::Clock,
+            date: None,
         }
     }
 }
@@ -133,11 +232,23 @@ pub struct FrameStats {
     /// Corresponds to ShaderToy's `iTimeDelta` uniform.
     pub time_delta: Duration,
 
+    /// Unscaled clock time since previous logical frame, from which
+    /// tiles advance their own animation time.
+    pub clock_delta: Duration,
+
     /// Smoothed frames-per-second measurement.
     pub frame_rate: f64,
 
     /// Zero-based logical frame index.
     pub frame_number: u32,
+
+    /// Local date and time of the frame.
+    /// Corresponds to ShaderToy's `iDate` uniform.
+    pub date: NaiveDateTime,
+
+    /// Smoothed presentation latency measured by the frame clock.
+    /// `None` until measured, or when rendering offscreen.
+    pub latency: Option<Duration>,
 }
 
 impl FrameController {
@@ -152,6 +263,20 @@ impl FrameController {
         let idle_duration = preset
             .interval_between_frames
             .saturating_sub(crossfade_duration);
+        let min_frame_interval = if preset.max_fps > 0.0 {
+            Duration::from_secs_f64(1.0 / preset.max_fps)
+        } else {
+            Duration::ZERO
+        };
+        let paced_frame_interval = if preset.interval_between_frames.is_zero() {
+            min_frame_interval
+        } else if preset.crossfade_overlap_ratio == 0.0
+            && preset.interval_between_frames <= MAX_PACED_FRAME_INTERVAL
+        {
+            preset.interval_between_frames
+        } else {
+            Duration::ZERO
+        };
 
         Self {
             time_scale: preset.time_scale.max(0.0),
@@ -166,9 +291,87 @@ impl FrameController {
             last_frame_render_time: now,
             crossfade: CrossfadeState::new(crossfade_duration),
             idle_duration,
+            min_frame_interval,
+            paced_frame_interval,
+            next_frame_deadline: None,
+            last_presented_frame: None,
+            presentation_latency: None,
+            pause_reasons: HashSet::new(),
+            paused_at: None,
+            fixed_timestep: (!preset.fixed_timestep.is_zero()).then_some(preset.fixed_timestep),
+            time_source: TimeSource::Clock,
+            date: None,
+        }
+    }
+
+    /// Reports time from `time_source` instead of the wall clock.
+    pub fn with_time_source(self, time_source: TimeSource) -> Self {
+        Self {
+            time_source,
+            ..self
+        }
+    }
+
+    /// Reports `date` instead of the current date. The time of day then
+    /// starts at midnight and follows the animation time.
+    pub fn with_date(self, date: Option<NaiveDate>) -> Self {
+        Self { date, ..self }
+    }
+
+    /// Creates a controller whose clock advances by `timestep` on every
+    /// [`FrameController::step`] instead of following the wall clock.
+    ///
+    /// Used for offscreen rendering, where frames must be reproducible
+    /// regardless of how long each one takes to render.
+    pub fn with_fixed_timestep(preset: &Preset, timestep: Duration) -> Self {
+        Self {
+            fixed_timestep: Some(timestep),
+            ..Self::new(preset, 1)
+        }
+    }
+
+    /// Returns the statistics of the next frame on the fixed timestep
+    /// clock and advances the clock by one step.
+    pub fn step(&mut self) -> FrameStats {
+        let timestep = self.fixed_timestep.unwrap_or_default();
+        let frame_rate = if timestep.is_zero() {
+            0.0
+        } else {
+            1.0 / timestep.as_secs_f64()
+        };
+
+        let time = (timestep * self.frame_number).mul_f64(self.time_scale) + self.time_offset;
+        let stats = FrameStats {
+            time,
+            time_delta: timestep.mul_f64(self.time_scale),
+            clock_delta: timestep,
+            frame_rate,
+            frame_number: self.frame_number,
+            date: self.frame_date(time),
+            latency: None,
+        };
+
+        self.frame_number = self.frame_number.wrapping_add(1);
+
+        stats
+    }
+
+    /// Returns the number of fixed timesteps needed to reach `elapsed`.
+    pub fn steps_until(&self, elapsed: Duration) -> u32 {
+        match self.fixed_timestep {
+            Some(timestep) if !timestep.is_zero() => {
+                (elapsed.as_secs_f64() / timestep.as_secs_f64()).round() as u32
+            }
+            _ => 0,
         }
     }
 
+    /// Moves the fixed timestep clock so that the next
+    /// [`FrameController::step`] happens at `elapsed`.
+    pub fn seek(&mut self, elapsed: Duration) {
+        self.frame_number = self.steps_until(elapsed);
+    }
+
     /// Executes rendering for one monitor.
     ///
     /// A single logical frame may span multiple monitors. Only the first monitor renders new content, while the
@@ -218,6 +421,218 @@ impl FrameController {
         self.crossfade.reset();
     }
 
+    /// Returns `true` if frames are paced by the frame clock at an
+    /// interval, i.e., rendering is limited by `max_fps` or throttled by
+    /// a short `interval_between_frames`.
+    pub fn is_frame_paced(&self) -> bool {
+        !self.paced_frame_interval.is_zero()
+    }
+
+    /// Decides whether a paced frame should be produced at `frame_time`.
+    ///
+    /// `frame_time` is the timestamp reported by the GDK frame clock, so
+    /// frames stay aligned to the display refresh. Deadlines advance by a
+    /// fixed interval to keep the average rate, and are rebased when
+    /// rendering falls behind by more than one interval.
+    pub fn is_frame_due(&mut self, frame_time: Duration) -> bool {
+        if !self.is_frame_paced() {
+            return true;
+        }
+
+        let deadline = self.next_frame_deadline.unwrap_or(frame_time);
+        if frame_time + FRAME_DEADLINE_TOLERANCE < deadline {
+            return false;
+        }
+
+        let next_deadline = deadline + self.paced_frame_interval;
+        self.next_frame_deadline = Some(if next_deadline <= frame_time {
+            frame_time + self.paced_frame_interval
+        } else {
+            next_deadline
+        });
+
+        true
+    }
+
+    /// Records that frame clock frame `frame_counter` with frame time
+    /// `frame_time` was presented at `presentation_time`, both in
+    /// microseconds of the frame clock.
+    ///
+    /// Frames already recorded are ignored, as are unknown presentation
+    /// times, which GDK reports as zero.
+    pub fn record_presentation(
+        &mut self,
+        frame_counter: i64,
+        frame_time: i64,
+        presentation_time: i64,
+    ) {
+        if presentation_time <= 0
+            || self
+                .last_presented_frame
+                .is_some_and(|last| frame_counter <= last)
+        {
+            return;
+        }
+        self.last_presented_frame = Some(frame_counter);
+
+        let latency = Duration::from_micros((presentation_time - frame_time).max(0) as u64);
+        self.presentation_latency = Some(match self.presentation_latency {
+            Some(smoothed) => {
+                smoothed.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => latency,
+        });
+    }
+
+    /// Returns the smoothed presentation latency, or `None` until a
+    /// presentation is recorded.
+    pub fn presentation_latency(&self) -> Option<Duration> {
+        self.presentation_latency
+    }
+
+    /// Suspends frame production for the given reason.
+    ///
+    /// Animation time stops advancing while paused. Returns `true` if
+    /// the controller transitioned from running to paused.
+    pub fn pause(&mut self, reason: PauseReason) -> bool {
+        let was_paused = self.is_paused();
+        self.pause_reasons.insert(reason);
+
+        if was_paused {
+            return false;
+        }
+
+        self.paused_at = Some(Instant::now());
+        true
+    }
+
+    /// Clears a pause reason.
+    ///
+    /// Returns `true` if no reason is left and the controller
+    /// transitioned from paused to running.
+    pub fn resume(&mut self, reason: PauseReason) -> bool {
+        if !self.pause_reasons.remove(&reason) || self.is_paused() {
+            return false;
+        }
+
+        if let Some(paused_at) = self.paused_at.take() {
+            // Shift reference times so the pause is invisible to the animation
+            let paused_duration = paused_at.elapsed();
+            self.start_time += paused_duration;
+            self.previous_frame_time += paused_duration;
+            self.last_frame_render_time += paused_duration;
+            self.frame_times.clear();
+            self.next_frame_deadline = None;
+        }
+        true
+    }
+
+    /// Restarts the wall clock of the animation so that `iTime` starts
+    /// over from the time offset. The frame number is kept.
+    pub fn reset_time_base(&mut self) {
+        let now = Instant::now();
+        self.start_time = now;
+        self.previous_frame_time = now;
+        self.frame_times.clear();
+        self.next_frame_deadline = None;
+
+        // Time spent paused before the reset must not be skipped on resume
+        self.paused_at = self.paused_at.map(|_| now);
+    }
+
+    /// Returns `true` while at least one pause reason is active.
+    pub fn is_paused(&self) -> bool {
+        !self.pause_reasons.is_empty()
+    }
+
+    /// Returns `true` if the given pause reason is active.
+    pub fn is_paused_by(&self, reason: PauseReason) -> bool {
+        self.pause_reasons.contains(&reason)
+    }
+
+    /// Advances the animation time of a paused controller by one frame,
+    /// so that the next rendered frame shows it.
+    ///
+    /// The step is the fixed timestep if set, otherwise the interval of
+    /// `max_fps` or a 60 FPS frame. Returns `false` if not paused.
+    pub fn step_frame(&mut self) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+
+        // The fixed timestep clock advances on every rendered frame
+        if self.fixed_timestep.is_none() {
+            let step = if self.min_frame_interval.is_zero() {
+                DEFAULT_FRAME_STEP
+            } else {
+                self.min_frame_interval
+            };
+            self.shift_clock_back(step);
+
+            // Report the step as the `iTimeDelta` of the next frame
+            let paused_at = self.paused_at.unwrap_or_else(Instant::now);
+            self.previous_frame_time = paused_at.checked_sub(step).unwrap_or(paused_at);
+        }
+        true
+    }
+
+    /// Moves the animation time forward by `delta` of `iTime`.
+    pub fn jump_forward(&mut self, delta: Duration) {
+        let Some(delta) = self.clock_duration(delta) else {
+            return;
+        };
+
+        match self.fixed_timestep {
+            Some(_) => {
+                self.frame_number = self.frame_number.saturating_add(self.steps_until(delta));
+            }
+            None => self.shift_clock_back(delta),
+        }
+    }
+
+    /// Moves the animation time backward by `delta` of `iTime`, down to
+    /// `time_offset`.
+    pub fn jump_backward(&mut self, delta: Duration) {
+        let Some(delta) = self.clock_duration(delta) else {
+            return;
+        };
+
+        match self.fixed_timestep {
+            Some(_) => {
+                self.frame_number = self.frame_number.saturating_sub(self.steps_until(delta));
+            }
+            None => {
+                let now = self.paused_at.unwrap_or_else(Instant::now);
+                self.start_time = (self.start_time + delta).min(now);
+            }
+        }
+    }
+
+    /// Converts a duration of `iTime` into wall clock time, or `None` if
+    /// the animation time does not follow the clock.
+    fn clock_duration(&self, delta: Duration) -> Option<Duration> {
+        if matches!(self.time_source, TimeSource::Fixed(_)) || self.time_scale <= 0.0 {
+            return None;
+        }
+        Some(delta.div_f64(self.time_scale))
+    }
+
+    /// Moves the start of the animation clock back, advancing the
+    /// animation time by `delta`.
+    fn shift_clock_back(&mut self, delta: Duration) {
+        match self.start_time.checked_sub(delta) {
+            Some(start_time) => self.start_time = start_time,
+            None => log::warn!("Cannot move animation time past the system uptime"),
+        }
+    }
+
+    /// Carries over the pause reasons of a controller being replaced.
+    pub fn inherit_pause_reasons(&mut self, previous: &FrameController) {
+        for &reason in &previous.pause_reasons {
+            self.pause(reason);
+        }
+    }
+
     /// Returns `true` when rendering the first monitor of the logical frame.
     ///
     /// Only the first monitor is allowed to generate new frame content.
@@ -312,25 +727,66 @@ impl FrameController {
     /// Updates frame statistics and returns current measurements.
     fn update_frame_stats(&mut self) -> FrameStats {
         let now = Instant::now();
-        let elapsed_time = now.duration_since(self.start_time);
-        let delta_time = now.duration_since(self.previous_frame_time);
+
+        // Animation time stands still while paused
+        let clock_time = self.paused_at.unwrap_or(now);
+        let elapsed_time = clock_time.saturating_duration_since(self.start_time);
+        let delta_time = clock_time.saturating_duration_since(self.previous_frame_time);
 
         self.record_frame_time(now);
         let frame_rate = self.calculate_frame_rate(now);
 
+        self.previous_frame_time = clock_time;
+
+        if let TimeSource::Fixed(time) = self.time_source {
+            let stats = FrameStats {
+                time,
+                time_delta: Duration::ZERO,
+                clock_delta: Duration::ZERO,
+                frame_rate,
+                frame_number: self.frame_number,
+                date: self.frame_date(time),
+                latency: self.presentation_latency,
+            };
+            self.frame_number = self.frame_number.wrapping_add(1);
+            return stats;
+        }
+
+        // A virtual clock keeps the simulation independent of throttling
+        if self.fixed_timestep.is_some() {
+            return FrameStats {
+                frame_rate,
+                latency: self.presentation_latency,
+                ..self.step()
+            };
+        }
+
+        let time = elapsed_time.mul_f64(self.time_scale) + self.time_offset;
         let stats = FrameStats {
-            time: elapsed_time.mul_f64(self.time_scale) + self.time_offset,
+            time,
             time_delta: delta_time.mul_f64(self.time_scale),
+            clock_delta: delta_time,
             frame_rate,
             frame_number: self.frame_number,
+            date: self.frame_date(time),
+            latency: self.presentation_latency,
         };
 
-        self.previous_frame_time = now;
         self.frame_number = self.frame_number.wrapping_add(1);
 
         stats
     }
 
+    /// Returns the date of a frame at the given animation time.
+    fn frame_date(&self, time: Duration) -> NaiveDateTime {
+        match self.date {
+            Some(date) => {
+                date.and_time(NaiveTime::MIN) + chrono::Duration::from_std(time).unwrap_or_default()
+            }
+            None => Local::now().naive_local(),
+        }
+    }
+
     /// Records the timestamp of a rendered frame.
     ///
     /// The timestamp is added to the FPS averaging window and
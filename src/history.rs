@@ -0,0 +1,73 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! History of applied presets.
+//!
+//! Works like the history of a web browser: going back and forth moves
+//! through the presets applied so far, and applying another preset
+//! discards the presets ahead of the current one.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Maximum number of presets remembered.
+const MAX_HISTORY_LEN: usize = 100;
+
+/// Sequence of applied presets and the position of the current one.
+#[derive(Debug, Default)]
+pub struct PresetHistory {
+    /// Paths of the applied presets, oldest first.
+    entries: Vec<PathBuf>,
+
+    /// Index of the current preset in `entries`.
+    position: usize,
+}
+
+impl PresetHistory {
+    /// Records `path` as the current preset.
+    ///
+    /// Does nothing if it already is the current preset. If it is the
+    /// preset right before or after the current one, e.g., after applying
+    /// [`PresetHistory::previous`] or [`PresetHistory::next`], moves to it
+    /// without discarding the presets ahead.
+    pub fn push(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if self.entries.get(self.position) == Some(&path) {
+            return;
+        }
+        if self.entries.get(self.position + 1) == Some(&path) {
+            self.position += 1;
+            return;
+        }
+        if self.position > 0 && self.entries.get(self.position - 1) == Some(&path) {
+            self.position -= 1;
+            return;
+        }
+
+        self.entries.truncate(self.position + 1);
+        self.entries.push(path);
+        if self.entries.len() > MAX_HISTORY_LEN {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len() - 1;
+    }
+
+    /// Returns the path of the preset before the current one.
+    ///
+    /// The history only moves once the preset is applied and pushed, so
+    /// that a preset that fails to load does not become the current one.
+    pub fn previous(&self) -> Option<&Path> {
+        let position = self.position.checked_sub(1)?;
+        self.entries.get(position).map(PathBuf::as_path)
+    }
+
+    /// Returns the path of the preset after the current one, or `None` at
+    /// the end of the history.
+    pub fn next(&self) -> Option<&Path> {
+        self.entries.get(self.position + 1).map(PathBuf::as_path)
+    }
+}
@@ -0,0 +1,256 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Global hotkeys.
+//!
+//! The wallpaper never has keyboard focus, so hotkeys are registered
+//! with the `GlobalShortcuts` interface of the XDG desktop portal. The
+//! portal asks the user to confirm the bindings the first time, and the
+//! suggested key combinations can be changed in the desktop settings.
+//!
+//! | Hotkey           | Default trigger  | Effect                           |
+//! |------------------|------------------|----------------------------------|
+//! | `toggle-pause`   | `Ctrl+Alt+P`     | Pauses or resumes the animation  |
+//! | `step-frame`     | `Ctrl+Alt+.`     | Renders one frame while paused   |
+//! | `jump-forward`   | `Ctrl+Alt+Right` | Moves `iTime` forward            |
+//! | `jump-backward`  | `Ctrl+Alt+Left`  | Moves `iTime` backward           |
+//! | `show-overlay`   | `Ctrl+Alt+I`     | Shows the overlay again          |
+
+use gtk::{
+    gio::{self, prelude::*},
+    glib,
+};
+use thiserror::Error;
+
+use crate::APP_NAME;
+
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const GLOBAL_SHORTCUTS_INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+const ACTIVATED_SIGNAL: &str = "Activated";
+const RESPONSE_SIGNAL: &str = "Response";
+
+#[derive(Debug, Error)]
+pub enum HotkeyError {
+    #[error("Failed to connect to the desktop portal: {0}")]
+    DBus(#[from] glib::Error),
+
+    #[error("The desktop portal is not running")]
+    Unavailable,
+}
+
+/// Action bound to a global hotkey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    /// Pauses or resumes the animation.
+    TogglePause,
+
+    /// Renders a single frame while paused.
+    StepFrame,
+
+    /// Moves the animation time forward.
+    JumpForward,
+
+    /// Moves the animation time backward.
+    JumpBackward,
+
+    /// Shows the information overlay again on the focused monitor.
+    ShowOverlay,
+}
+
+impl Hotkey {
+    const ALL: [Hotkey; 5] = [
+        Hotkey::TogglePause,
+        Hotkey::StepFrame,
+        Hotkey::JumpForward,
+        Hotkey::JumpBackward,
+        Hotkey::ShowOverlay,
+    ];
+
+    /// Returns the shortcut identifier registered with the portal.
+    pub fn id(self) -> &'static str {
+        match self {
+            Hotkey::TogglePause => "toggle-pause",
+            Hotkey::StepFrame => "step-frame",
+            Hotkey::JumpForward => "jump-forward",
+            Hotkey::JumpBackward => "jump-backward",
+            Hotkey::ShowOverlay => "show-overlay",
+        }
+    }
+
+    /// Returns the hotkey with the given shortcut identifier.
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|hotkey| hotkey.id() == id)
+    }
+
+    /// Returns the description shown by the desktop settings.
+    fn description(self) -> &'static str {
+        match self {
+            Hotkey::TogglePause => "Pause or resume the wallpaper",
+            Hotkey::StepFrame => "Render one frame of the paused wallpaper",
+            Hotkey::JumpForward => "Move the wallpaper animation forward",
+            Hotkey::JumpBackward => "Move the wallpaper animation backward",
+            Hotkey::ShowOverlay => "Show the name of the wallpaper shader",
+        }
+    }
+
+    /// Returns the suggested trigger, in the format of the XDG shortcuts
+    /// specification.
+    fn preferred_trigger(self) -> &'static str {
+        match self {
+            Hotkey::TogglePause => "CTRL+ALT+p",
+            Hotkey::StepFrame => "CTRL+ALT+period",
+            Hotkey::JumpForward => "CTRL+ALT+Right",
+            Hotkey::JumpBackward => "CTRL+ALT+Left",
+            Hotkey::ShowOverlay => "CTRL+ALT+i",
+        }
+    }
+}
+
+/// Global hotkeys registered with the desktop portal.
+pub struct Hotkeys {
+    _proxy: gio::DBusProxy,
+    _request: gio::DBusProxy,
+}
+
+impl Hotkeys {
+    /// Creates a global shortcuts session and binds the hotkeys once the
+    /// portal has created it.
+    ///
+    /// `on_activated` is invoked from the main loop every time a hotkey
+    /// is pressed.
+    pub fn new<F>(on_activated: F) -> Result<Self, HotkeyError>
+    where
+        F: Fn(Hotkey) + 'static,
+    {
+        let proxy = portal_proxy(PORTAL_PATH, GLOBAL_SHORTCUTS_INTERFACE)?;
+        if proxy.name_owner().is_none() {
+            return Err(HotkeyError::Unavailable);
+        }
+
+        // The portal derives the object paths of the request and session
+        // from the tokens, so the response can be subscribed to before
+        // the request is made
+        let sender = proxy
+            .connection()
+            .unique_name()
+            .map(|name| name.trim_start_matches(':').replace('.', "_"))
+            .unwrap_or_default();
+        let token = format!("{APP_NAME}_{}", std::process::id());
+        let request = portal_proxy(
+            &format!("{PORTAL_PATH}/request/{sender}/{token}"),
+            REQUEST_INTERFACE,
+        )?;
+        let session_path = format!("{PORTAL_PATH}/session/{sender}/{token}");
+
+        request.connect_local(
+            "g-signal",
+            false,
+            glib::clone!(
+                #[weak]
+                proxy,
+                #[upgrade_or]
+                None,
+                move |values| {
+                    let signal_name = values.get(2)?.get::<String>().ok()?;
+                    if signal_name != RESPONSE_SIGNAL {
+                        return None;
+                    }
+
+                    let parameters = values.get(3)?.get::<glib::Variant>().ok()?;
+                    match parameters.child_value(0).get::<u32>() {
+                        Some(0) => bind_shortcuts(&proxy, &session_path),
+                        _ => log::warn!("The desktop portal refused to create global shortcuts"),
+                    }
+                    None
+                }
+            ),
+        );
+
+        proxy.connect_local("g-signal", false, move |values| {
+            let signal_name = values.get(2)?.get::<String>().ok()?;
+            if signal_name != ACTIVATED_SIGNAL {
+                return None;
+            }
+
+            let parameters = values.get(3)?.get::<glib::Variant>().ok()?;
+            let id = parameters.child_value(1).get::<String>()?;
+            if let Some(hotkey) = Hotkey::from_id(&id) {
+                log::debug!("Hotkey {id} activated");
+                on_activated(hotkey);
+            }
+            None
+        });
+
+        let options = glib::VariantDict::new(None);
+        options.insert("handle_token", &token);
+        options.insert("session_handle_token", &token);
+        proxy.call_sync(
+            "CreateSession",
+            Some(&glib::Variant::tuple_from_iter([options.end()])),
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::Cancellable>,
+        )?;
+
+        Ok(Self {
+            _proxy: proxy,
+            _request: request,
+        })
+    }
+}
+
+/// Creates a proxy for an interface of the desktop portal.
+fn portal_proxy(path: &str, interface: &str) -> Result<gio::DBusProxy, glib::Error> {
+    gio::DBusProxy::for_bus_sync(
+        gio::BusType::Session,
+        gio::DBusProxyFlags::DO_NOT_LOAD_PROPERTIES | gio::DBusProxyFlags::DO_NOT_AUTO_START,
+        None,
+        PORTAL_NAME,
+        path,
+        interface,
+        None::<&gio::Cancellable>,
+    )
+}
+
+/// Binds every [`Hotkey`] to the global shortcuts session.
+fn bind_shortcuts(proxy: &gio::DBusProxy, session_path: &str) {
+    let Ok(session_path) = glib::variant::ObjectPath::try_from(session_path.to_string()) else {
+        log::warn!("Invalid global shortcuts session path: {session_path}");
+        return;
+    };
+
+    let shortcuts = Hotkey::ALL.into_iter().map(|hotkey| {
+        let properties = glib::VariantDict::new(None);
+        properties.insert("description", hotkey.description());
+        properties.insert("preferred_trigger", hotkey.preferred_trigger());
+        glib::Variant::tuple_from_iter([hotkey.id().to_variant(), properties.end()])
+    });
+    let shortcuts = glib::Variant::array_from_iter_with_type(
+        glib::VariantTy::new("(sa{sv})").unwrap(),
+        shortcuts,
+    );
+
+    let parameters = glib::Variant::tuple_from_iter([
+        session_path.to_variant(),
+        shortcuts,
+        "".to_variant(),
+        glib::VariantDict::new(None).end(),
+    ]);
+
+    proxy.call(
+        "BindShortcuts",
+        Some(&parameters),
+        gio::DBusCallFlags::NONE,
+        -1,
+        None::<&gio::Cancellable>,
+        |result| {
+            if let Err(err) = result {
+                log::warn!("Failed to bind global shortcuts: {err}");
+            }
+        },
+    );
+}
@@ -0,0 +1,222 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Performance heads-up display.
+//!
+//! Shows the frame rate, a graph of recent frame times, the presentation
+//! latency, the resolution of the Image pass and the GPU time of each
+//! pass on top of the wallpaper. Enabled with `--hud` and toggled with
+//! the `hud` control command.
+
+use gtk::{glib, prelude::*};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{geometry::Size, renderer::PassTime};
+
+/// Number of frame times shown in the graph.
+const GRAPH_LEN: usize = 120;
+
+/// Size of the frame time graph, in logical pixels.
+const GRAPH_SIZE: (i32, i32) = (240, 60);
+
+/// Frame time at the top of the graph, in milliseconds.
+const GRAPH_MAX_MS: f64 = 50.0;
+
+/// Frame times drawn as reference lines, in milliseconds (60 and 30 FPS).
+const GRAPH_MARKS_MS: [f64; 2] = [1000.0 / 60.0, 1000.0 / 30.0];
+
+/// Minimum time between updates of the text, so that it stays readable.
+const TEXT_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+const MARGIN: i32 = 12;
+
+/// Measurements shown by the [`Hud`] for one frame.
+pub struct HudStats<'a> {
+    pub frame_rate: f64,
+
+    /// Time from the start of a frame to its presentation, if measured.
+    pub latency: Option<Duration>,
+
+    /// Size of the Image pass framebuffer.
+    pub resolution: Size,
+
+    /// GPU time of each pass, in rendering order.
+    pub pass_times: &'a [PassTime],
+}
+
+/// Heads-up display attached to the overlay of a wallpaper area.
+pub struct Hud {
+    container: gtk::Box,
+    label: gtk::Label,
+    graph: gtk::DrawingArea,
+
+    /// Recent frame times, in milliseconds, oldest first.
+    frame_times: Rc<RefCell<VecDeque<f64>>>,
+
+    /// Time of the previous frame.
+    last_frame: Cell<Option<Instant>>,
+
+    /// Time of the last update of the text.
+    last_text_update: Cell<Option<Instant>>,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        let frame_times = Rc::new(RefCell::new(VecDeque::with_capacity(GRAPH_LEN)));
+
+        let graph = gtk::DrawingArea::builder()
+            .content_width(GRAPH_SIZE.0)
+            .content_height(GRAPH_SIZE.1)
+            .build();
+        graph.set_draw_func(glib::clone!(
+            #[strong]
+            frame_times,
+            move |_, cr, width, height| {
+                draw_graph(cr, &frame_times.borrow(), width as f64, height as f64);
+            }
+        ));
+
+        let label = gtk::Label::builder().xalign(0.0).use_markup(true).build();
+
+        let container = gtk::Box::new(gtk::Orientation::Vertical, 4);
+        container.append(&label);
+        container.append(&graph);
+        container.set_halign(gtk::Align::Start);
+        container.set_valign(gtk::Align::Start);
+        container.set_margin_start(MARGIN);
+        container.set_margin_top(MARGIN);
+        container.set_can_target(false);
+
+        Self {
+            container,
+            label,
+            graph,
+            frame_times,
+            last_frame: Cell::new(None),
+            last_text_update: Cell::new(None),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    /// Shows or hides the HUD, clearing the frame times when hidden.
+    pub fn set_visible(&self, visible: bool) {
+        self.container.set_visible(visible);
+        if !visible {
+            self.frame_times.borrow_mut().clear();
+            self.last_frame.set(None);
+            self.last_text_update.set(None);
+        }
+    }
+
+    /// Records the measurements of a frame.
+    pub fn update(&self, stats: &HudStats) {
+        if !self.container.is_visible() {
+            return;
+        }
+
+        let now = Instant::now();
+        let Some(frame_time) = self.last_frame.replace(Some(now)).map(|last| now - last) else {
+            return;
+        };
+
+        {
+            let mut frame_times = self.frame_times.borrow_mut();
+            if frame_times.len() == GRAPH_LEN {
+                frame_times.pop_front();
+            }
+            frame_times.push_back(frame_time.as_secs_f64() * 1000.0);
+        }
+        self.graph.queue_draw();
+
+        if self
+            .last_text_update
+            .get()
+            .is_some_and(|last| now.duration_since(last) < TEXT_UPDATE_INTERVAL)
+        {
+            return;
+        }
+        self.last_text_update.set(Some(now));
+        self.label.set_markup(&hud_markup(stats, frame_time));
+    }
+}
+
+/// Returns the Pango markup of the text of the HUD.
+fn hud_markup(stats: &HudStats, frame_time: Duration) -> String {
+    let mut text = format!(
+        "{:.1} FPS  {:.2} ms\n{}×{}",
+        stats.frame_rate,
+        frame_time.as_secs_f64() * 1000.0,
+        stats.resolution.width(),
+        stats.resolution.height()
+    );
+
+    if let Some(latency) = stats.latency {
+        text.push_str(&format!(
+            "\nLatency: {:.2} ms",
+            latency.as_secs_f64() * 1000.0
+        ));
+    }
+
+    for pass_time in stats.pass_times {
+        text.push_str(&format!(
+            "\n{}: {:.2} ms",
+            pass_time.name,
+            pass_time.average.as_secs_f64() * 1000.0
+        ));
+    }
+
+    format!(
+        r##"<span font_family="monospace" size="small" foreground="white" background="#000000a0">{}</span>"##,
+        glib::markup_escape_text(&text)
+    )
+}
+
+/// Draws the frame times as bars from the bottom of the graph.
+fn draw_graph(cr: &gtk::cairo::Context, frame_times: &VecDeque<f64>, width: f64, height: f64) {
+    let y_of = |ms: f64| height * (1.0 - (ms / GRAPH_MAX_MS).min(1.0));
+
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    cr.rectangle(0.0, 0.0, width, height);
+    let _ = cr.fill();
+
+    let bar_width = width / GRAPH_LEN as f64;
+    let offset = GRAPH_LEN - frame_times.len();
+    for (index, &ms) in frame_times.iter().enumerate() {
+        if ms > GRAPH_MARKS_MS[1] {
+            cr.set_source_rgb(0.9, 0.3, 0.2);
+        } else if ms > GRAPH_MARKS_MS[0] * 1.1 {
+            cr.set_source_rgb(0.9, 0.8, 0.2);
+        } else {
+            cr.set_source_rgb(0.3, 0.8, 0.4);
+        }
+        let x = (offset + index) as f64 * bar_width;
+        let y = y_of(ms);
+        cr.rectangle(x, y, bar_width.max(1.0), height - y);
+        let _ = cr.fill();
+    }
+
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.5);
+    cr.set_line_width(1.0);
+    for ms in GRAPH_MARKS_MS {
+        let y = y_of(ms).round() + 0.5;
+        cr.move_to(0.0, y);
+        cr.line_to(width, y);
+    }
+    let _ = cr.stroke();
+}
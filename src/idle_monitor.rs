@@ -0,0 +1,234 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Detection of user inactivity for the screensaver.
+//!
+//! Asks the compositor to report when the seat has been idle for a given
+//! time through the `ext_idle_notifier_v1` protocol, on a dedicated
+//! Wayland connection dispatched from the GLib main loop. The compositor
+//! also reports when activity resumes. Idle inhibitors, such as those of
+//! video players, are honored by the compositor.
+
+use gtk::glib;
+use serde::Deserialize;
+use std::{
+    os::fd::{AsRawFd, RawFd},
+    path::PathBuf,
+    time::Duration,
+};
+use thiserror::Error;
+use wayland_client::{
+    protocol::{wl_registry, wl_seat},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::{self, ExtIdleNotifierV1},
+};
+
+/// Highest `wl_seat` version used.
+const WL_SEAT_VERSION: u32 = 1;
+
+/// Highest `ext_idle_notifier_v1` version used.
+const IDLE_NOTIFIER_VERSION: u32 = 1;
+
+/// Preset shown while the user is idle, from the `[screensaver]` table
+/// of the configuration file.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Screensaver {
+    /// TOML preset or ShaderToy JSON export, absolute or relative to the
+    /// presets directory.
+    pub preset: PathBuf,
+
+    /// Time without input after which the screensaver is shown.
+    #[serde(default = "default_idle_timeout", with = "humantime_serde")]
+    pub idle_timeout: Duration,
+}
+
+fn default_idle_timeout() -> Duration {
+    Duration::from_secs(600)
+}
+
+#[derive(Debug, Error)]
+pub enum IdleError {
+    #[error("Failed to connect to the Wayland display: {0}")]
+    Connect(#[from] wayland_client::ConnectError),
+
+    #[error("Wayland dispatch failed: {0}")]
+    Dispatch(#[from] wayland_client::DispatchError),
+
+    #[error("Compositor does not support ext_idle_notifier_v1")]
+    Unsupported,
+}
+
+/// Watches for the user going idle and becoming active again.
+///
+/// Dropping the monitor stops watching and closes the connection.
+pub struct IdleMonitor {
+    source_id: Option<glib::SourceId>,
+}
+
+impl IdleMonitor {
+    /// Connects to the compositor and starts watching for `timeout` of
+    /// inactivity.
+    ///
+    /// `on_change` is invoked from the main loop with `true` when the
+    /// user goes idle, and with `false` when activity resumes.
+    pub fn new<F>(timeout: Duration, on_change: F) -> Result<Self, IdleError>
+    where
+        F: Fn(bool) + 'static,
+    {
+        let connection = Connection::connect_to_env()?;
+        let mut queue = connection.new_event_queue::<State>();
+        let queue_handle = queue.handle();
+        connection.display().get_registry(&queue_handle, ());
+
+        let mut state = State::default();
+        queue.roundtrip(&mut state)?;
+        let (Some(notifier), Some(seat)) = (&state.notifier, &state.seat) else {
+            return Err(IdleError::Unsupported);
+        };
+        let timeout_ms = timeout.as_millis().clamp(1, u32::MAX as u128) as u32;
+        // The notification lives as long as the connection
+        notifier.get_idle_notification(timeout_ms, seat, &queue_handle, ());
+        queue.roundtrip(&mut state)?;
+
+        let fd = connection.backend().poll_fd().as_raw_fd();
+        let source_id = Self::watch(fd, connection, queue, state, on_change);
+
+        Ok(Self {
+            source_id: Some(source_id),
+        })
+    }
+
+    /// Dispatches Wayland events whenever the connection becomes readable.
+    fn watch<F>(
+        fd: RawFd,
+        connection: Connection,
+        mut queue: EventQueue<State>,
+        mut state: State,
+        on_change: F,
+    ) -> glib::SourceId
+    where
+        F: Fn(bool) + 'static,
+    {
+        glib::unix_fd_add_local(
+            fd,
+            glib::IOCondition::IN | glib::IOCondition::HUP | glib::IOCondition::ERR,
+            move |_, condition| {
+                if condition.intersects(glib::IOCondition::HUP | glib::IOCondition::ERR) {
+                    log::warn!("Idle monitor: Wayland connection closed");
+                    return glib::ControlFlow::Break;
+                }
+
+                if let Some(guard) = queue.prepare_read() {
+                    if let Err(err) = guard.read() {
+                        log::warn!("Idle monitor: {err}");
+                        return glib::ControlFlow::Break;
+                    }
+                }
+
+                let was_idle = state.idle;
+                if let Err(err) = queue.dispatch_pending(&mut state) {
+                    log::warn!("Idle monitor: {err}");
+                    return glib::ControlFlow::Break;
+                }
+                let _ = connection.flush();
+
+                if state.idle != was_idle {
+                    on_change(state.idle);
+                }
+                glib::ControlFlow::Continue
+            },
+        )
+    }
+}
+
+impl Drop for IdleMonitor {
+    fn drop(&mut self) {
+        if let Some(source_id) = self.source_id.take() {
+            source_id.remove();
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    seat: Option<wl_seat::WlSeat>,
+    notifier: Option<ExtIdleNotifierV1>,
+    idle: bool,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        queue_handle: &QueueHandle<Self>,
+    ) {
+        let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        else {
+            return;
+        };
+
+        // The first seat is the one of the user
+        if interface == wl_seat::WlSeat::interface().name && state.seat.is_none() {
+            state.seat = Some(registry.bind(name, version.min(WL_SEAT_VERSION), queue_handle, ()));
+        } else if interface == ExtIdleNotifierV1::interface().name {
+            state.notifier =
+                Some(registry.bind(name, version.min(IDLE_NOTIFIER_VERSION), queue_handle, ()));
+        }
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Capabilities are irrelevant to idleness
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ExtIdleNotifierV1,
+        _: ext_idle_notifier_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // The notifier has no events
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.idle = true,
+            ext_idle_notification_v1::Event::Resumed => state.idle = false,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Control socket.
+//!
+//! A running instance listens on a Unix socket so that other processes
+//! (including `shaderbg` subcommands) can control it. The protocol is
+//! line-based: a client writes one command terminated by a newline and
+//! reads a single reply line, either `ok` or `ok: <text>` on success, or
+//! `error: <message>` on failure.
+//!
+//! | Command          | Effect                                      |
+//! |------------------|---------------------------------------------|
+//! | `switch <FILE>`  | Loads and applies the preset at `FILE`      |
+//! | `current`        | Replies with the path of the current preset |
+//! | `next`           | Goes forward in the preset history          |
+//! | `previous`       | Goes back in the preset history             |
+//! | `random`         | Applies a random preset                     |
+//! | `hud`            | Shows or hides the performance HUD          |
+//! | `overlay`        | Shows the information overlay again         |
+//! | `profile`        | Replies with the GPU time of each pass      |
+//! | `watchdog`       | Replies with the presets aborted by the GPU |
+//! |                  | watchdog, with the time they were aborted   |
+//! | `capture`        | Saves the current frame of each monitor as  |
+//! |                  | a PNG file and replies with the file paths  |
+
+use gtk::{
+    gio::{self, prelude::*},
+    glib,
+};
+use std::{
+    env, fs,
+    future::Future,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::APP_NAME;
+
+/// Longest time a client waits to write a command.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest time a client waits for a reply. Switching presets replies
+/// once the preset is loaded, which may download its media.
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Error)]
+pub enum IpcError {
+    #[error("Failed to listen on control socket: {0}")]
+    Listen(#[from] glib::Error),
+
+    #[error("Another instance is listening on {}", .0.display())]
+    InUse(PathBuf),
+
+    #[error("No running instance is listening on {}", .0.display())]
+    NotRunning(PathBuf),
+
+    #[error("Control socket error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("The running instance did not reply within {} seconds", .0.as_secs())]
+    TimedOut(Duration),
+
+    #[error("Invalid command '{0}'")]
+    InvalidCommand(String),
+
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// Command received over the control socket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpcCommand {
+    /// Switches to the preset at the given path.
+    Switch(PathBuf),
+
+    /// Queries the path of the current preset.
+    Current,
+
+    /// Switches to the next preset of the history, or to a random
+    /// preset at its end.
+    Next,
+
+    /// Switches to the previous preset of the history.
+    Previous,
+
+    /// Switches to a random preset of the presets directory.
+    Random,
+
+    /// Shows or hides the performance HUD.
+    ToggleHud,
+
+    /// Shows the information overlay again on the focused monitor.
+    ShowOverlay,
+
+    /// Queries the GPU time spent on each pass, starting to measure it
+    /// if needed.
+    Profile,
+
+    /// Queries the presets aborted by the GPU watchdog.
+    Watchdog,
+
+    /// Saves the current frame of each monitor to a PNG file and
+    /// queries the file paths.
+    Capture,
+}
+
+impl IpcCommand {
+    /// Parses a command line, e.g. `switch /path/to/preset.toml`.
+    pub fn parse(line: &str) -> Result<Self, IpcError> {
+        let line = line.trim();
+        let (name, argument) = line
+            .split_once(char::is_whitespace)
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((line, ""));
+
+        match (name, argument) {
+            ("switch", path) if !path.is_empty() => Ok(Self::Switch(PathBuf::from(path))),
+            ("current", "") => Ok(Self::Current),
+            ("next", "") => Ok(Self::Next),
+            ("previous", "") => Ok(Self::Previous),
+            ("random", "") => Ok(Self::Random),
+            ("hud", "") => Ok(Self::ToggleHud),
+            ("overlay", "") => Ok(Self::ShowOverlay),
+            ("profile", "") => Ok(Self::Profile),
+            ("watchdog", "") => Ok(Self::Watchdog),
+            ("capture", "") => Ok(Self::Capture),
+            _ => Err(IpcError::InvalidCommand(line.to_string())),
+        }
+    }
+
+    /// Returns the command line sent over the socket.
+    pub fn to_line(&self) -> String {
+        match self {
+            Self::Switch(path) => format!("switch {}", path.display()),
+            Self::Current => "current".to_string(),
+            Self::Next => "next".to_string(),
+            Self::Previous => "previous".to_string(),
+            Self::Random => "random".to_string(),
+            Self::ToggleHud => "hud".to_string(),
+            Self::ShowOverlay => "overlay".to_string(),
+            Self::Profile => "profile".to_string(),
+            Self::Watchdog => "watchdog".to_string(),
+            Self::Capture => "capture".to_string(),
+        }
+    }
+}
+
+/// Listens for commands on the control socket.
+///
+/// The socket file is removed when the server is dropped.
+pub struct IpcServer {
+    service: gio::SocketService,
+    path: PathBuf,
+}
+
+impl IpcServer {
+    /// Starts listening on [`socket_path`].
+    ///
+    /// `on_command` is invoked from the main loop for every command
+    /// received and resolves to the text of the reply, or an error
+    /// message. Other commands are handled while it is pending.
+    pub fn new<F, Fut>(on_command: F) -> Result<Self, IpcError>
+    where
+        F: Fn(IpcCommand) -> Fut + 'static,
+        Fut: Future<Output = Result<String, String>> + 'static,
+    {
+        let path = socket_path();
+        remove_stale_socket(&path)?;
+
+        let service = gio::SocketService::new();
+        service.add_address(
+            &gio::UnixSocketAddress::new(&path),
+            gio::SocketType::Stream,
+            gio::SocketProtocol::Default,
+            None::<&glib::Object>,
+        )?;
+
+        let on_command = Rc::new(on_command);
+        service.connect_incoming(move |_, connection, _| {
+            let connection = connection.clone();
+            let on_command = on_command.clone();
+            glib::spawn_future_local(async move {
+                let input = gio::DataInputStream::new(&connection.input_stream());
+                let reply = match input.read_line_utf8_future(glib::Priority::DEFAULT).await {
+                    Ok(Some(line)) => match IpcCommand::parse(&line) {
+                        Ok(command) => match on_command(command).await {
+                            Ok(text) if text.is_empty() => "ok".to_string(),
+                            Ok(text) => format!("ok: {text}"),
+                            Err(err) => format!("error: {err}"),
+                        },
+                        Err(err) => format!("error: {err}"),
+                    },
+                    Ok(None) => return,
+                    Err(err) => {
+                        log::warn!("Failed to read control command: {err}");
+                        return;
+                    }
+                };
+
+                if let Err((_, err)) = connection
+                    .output_stream()
+                    .write_all_future(format!("{reply}\n"), glib::Priority::DEFAULT)
+                    .await
+                {
+                    log::warn!("Failed to send control reply: {err}");
+                }
+            });
+            true
+        });
+        service.start();
+
+        log::info!("Listening for commands on {}", path.display());
+        Ok(Self { service, path })
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.service.stop();
+        self.service.close();
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Returns the path of the control socket,
+/// `$XDG_RUNTIME_DIR/shaderbg.sock`.
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(format!("{APP_NAME}.sock"))
+}
+
+/// Removes a socket file left behind by an instance that has exited.
+fn remove_stale_socket(path: &Path) -> Result<(), IpcError> {
+    if !path.exists() {
+        return Ok(());
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(IpcError::InUse(path.to_path_buf()));
+    }
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Sends a command to the running instance and returns the reply text.
+///
+/// Blocks until the reply is received, or fails after [`WRITE_TIMEOUT`]
+/// or [`READ_TIMEOUT`] if the instance stalls.
+pub fn send(command: &IpcCommand) -> Result<String, IpcError> {
+    let path = socket_path();
+    let mut stream = UnixStream::connect(&path).map_err(|_| IpcError::NotRunning(path))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let timed_out = |timeout| {
+        move |err: io::Error| match err.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => IpcError::TimedOut(timeout),
+            _ => IpcError::Io(err),
+        }
+    };
+    writeln!(stream, "{}", command.to_line()).map_err(timed_out(WRITE_TIMEOUT))?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(timed_out(READ_TIMEOUT))?;
+    let reply = reply.trim_end();
+
+    match reply.strip_prefix("error: ") {
+        Some(message) => Err(IpcError::Failed(message.to_string())),
+        None => Ok(reply
+            .strip_prefix("ok")
+            .map(|text| text.trim_start_matches(": "))
+            .unwrap_or(reply)
+            .to_string()),
+    }
+}
+
+/// Sends a command with [`send`] on a worker thread, so that the GTK
+/// main loop keeps running while waiting for the reply.
+pub async fn send_in_background(command: IpcCommand) -> Result<String, IpcError> {
+    gio::spawn_blocking(move || send(&command))
+        .await
+        .unwrap_or_else(|_| {
+            Err(IpcError::Failed(
+                "Control socket client panicked".to_string(),
+            ))
+        })
+}
@@ -0,0 +1,238 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Headless linting of presets against the ShaderToy GLSL dialect.
+//!
+//! Every pass is run through the ShaderToy compatibility layer and
+//! assembled as for rendering, but targeting GLSL ES 3.00, the dialect
+//! of ShaderToy shaders, and validated by `glslangValidator`, the
+//! Khronos reference compiler. Implicit conversions and other code
+//! accepted by desktop drivers but not by WebGL 2 are thus reported
+//! without a display or GPU. Words reserved in GLSL ES 3.00 and WebGL 1
+//! built-ins are also searched in the preset code.
+//!
+//! Diagnostics are printed as with `check`, and the process exits with:
+//!
+//! | Code | Meaning                                                     |
+//! |------|-------------------------------------------------------------|
+//! | 0    | Every preset is valid                                       |
+//! | 1    | Errors were found (or warnings, with `--deny-warnings`)     |
+//! | 2    | `glslangValidator` could not be run, so nothing was checked |
+
+use gtk::glib::ExitCode;
+use regex::Regex;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{
+    check::{report, CheckFormat, Diagnostic, Severity},
+    cli::load_preset_from_file_or_json,
+    renderer::{preset_fragment_sources, GlProfile, SourceMap, SourceSection},
+    shadertoy::dialect::dialect_issues,
+};
+
+/// Validator of the Khronos glslang reference compiler.
+const GLSLANG_VALIDATOR: &str = "glslangValidator";
+
+/// Exit code when the presets could not be linted.
+const EXIT_UNAVAILABLE: u8 = 2;
+
+/// Settings of the `lint` subcommand.
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    /// Preset files to lint.
+    pub files: Vec<PathBuf>,
+
+    pub format: CheckFormat,
+
+    /// Fails the lint when any warning is reported.
+    pub deny_warnings: bool,
+}
+
+/// Lints every preset in `options.files` and prints the diagnostics.
+pub fn lint(options: &LintOptions) -> ExitCode {
+    if let Err(err) = Command::new(GLSLANG_VALIDATOR)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+    {
+        eprintln!("Failed to run {GLSLANG_VALIDATOR} (install glslang): {err}");
+        return ExitCode::new(EXIT_UNAVAILABLE);
+    }
+
+    let mut diagnostics = Vec::new();
+    for file in &options.files {
+        match lint_file(file) {
+            Ok(file_diagnostics) => diagnostics.extend(file_diagnostics),
+            Err(err) => {
+                eprintln!("Failed to run {GLSLANG_VALIDATOR}: {err}");
+                return ExitCode::new(EXIT_UNAVAILABLE);
+            }
+        }
+    }
+
+    report(
+        &diagnostics,
+        options.files.len(),
+        options.format,
+        options.deny_warnings,
+    )
+}
+
+/// Lints a preset, returning its diagnostics.
+fn lint_file(file: &Path) -> io::Result<Vec<Diagnostic>> {
+    let diagnostic = |pass: Option<&str>, line, severity, message: &str| Diagnostic {
+        file: file.to_path_buf(),
+        pass: pass.map(str::to_string),
+        line,
+        severity,
+        message: message.trim().to_string(),
+    };
+
+    let preset = match load_preset_from_file_or_json(file) {
+        Ok((preset, _)) => preset,
+        Err(err) => {
+            return Ok(vec![diagnostic(
+                None,
+                None,
+                Severity::Error,
+                &err.to_string(),
+            )])
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+
+    let common_shader = preset.common.as_ref().map(|pass| pass.shader.as_str());
+    let pass_shaders = preset
+        .render_passes()
+        .chain(preset.sound.as_ref().map(|pass| ("Sound", pass)))
+        .map(|(name, pass)| (name, pass.shader.as_str()));
+    for (name, shader) in common_shader
+        .map(|shader| ("Common", shader))
+        .into_iter()
+        .chain(pass_shaders)
+    {
+        for issue in dialect_issues(shader) {
+            diagnostics.push(diagnostic(
+                Some(name),
+                Some(issue.line),
+                Severity::Error,
+                &issue.message,
+            ));
+        }
+    }
+
+    // Errors of the "Common" code are reported once, for the first pass
+    let mut reported = Vec::new();
+    for pass in preset_fragment_sources(&preset, GlProfile::WEBGL2) {
+        let (source, source_map) = match pass.result {
+            Ok(result) => result,
+            Err(err) => {
+                diagnostics.push(diagnostic(
+                    Some(&pass.name),
+                    None,
+                    Severity::Error,
+                    &err.to_string(),
+                ));
+                continue;
+            }
+        };
+
+        for diagnostic in validate(file, &source, &source_map, &pass.name)? {
+            let key = (
+                diagnostic.pass.clone(),
+                diagnostic.line,
+                diagnostic.message.clone(),
+            );
+            if !reported.contains(&key) {
+                diagnostics.push(diagnostic);
+                reported.push(key);
+            }
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Validates an assembled fragment shader of `file` with
+/// `glslangValidator`, returning its errors and warnings with lines
+/// mapped to the preset code.
+fn validate(
+    file: &Path,
+    source: &str,
+    source_map: &SourceMap,
+    pass_name: &str,
+) -> io::Result<Vec<Diagnostic>> {
+    let diagnostic = |pass: &str, line, severity, message: &str| Diagnostic {
+        file: file.to_path_buf(),
+        pass: Some(pass.to_string()),
+        line,
+        severity,
+        message: message.trim().to_string(),
+    };
+
+    let mut validator = Command::new(GLSLANG_VALIDATOR)
+        .args(["--stdin", "-S", "frag"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    validator
+        .stdin
+        .take()
+        .ok_or_else(|| io::Error::other("glslangValidator stdin unavailable"))?
+        .write_all(source.as_bytes())?;
+    let output = validator.wait_with_output()?;
+
+    // `ERROR: 2:12: 'x' : undeclared identifier`
+    let message_re = Regex::new(r"^(ERROR|WARNING): (\d+):(\d+): (.*)$").unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut messages: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| message_re.captures(line.trim()))
+        .filter(|caps| !caps[4].contains("compilation terminated"))
+        .map(|caps| {
+            let severity = if &caps[1] == "ERROR" {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            let location = source_map.locate(
+                caps[2].parse().unwrap_or_default(),
+                caps[3].parse().unwrap_or_default(),
+            );
+            let (section, line) = match location.section {
+                SourceSection::Injected => (pass_name, None),
+                SourceSection::Common => ("Common", Some(location.line)),
+                SourceSection::Pass => (pass_name, Some(location.line)),
+            };
+            diagnostic(section, line, severity, &caps[4])
+        })
+        .collect();
+
+    // The validator may fail without a located error, e.g., on a
+    // preprocessor error
+    if !output.status.success()
+        && messages
+            .iter()
+            .all(|diagnostic| diagnostic.severity != Severity::Error)
+    {
+        let log = [
+            stdout.trim(),
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ]
+        .join("\n");
+        messages.push(diagnostic(pass_name, None, Severity::Error, &log));
+    }
+
+    Ok(messages)
+}
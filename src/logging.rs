@@ -0,0 +1,245 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Log output to the console and, optionally, to a rotated file.
+//!
+//! Messages are filtered by a level per module, given as in
+//! `warn,frame_controller=trace`: a default level followed by levels of
+//! modules of ShaderBG or of its dependencies. The filter and the file
+//! can be changed after the logger is installed, so that the command
+//! line can override the configuration file.
+
+use chrono::Local;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+use crate::APP_NAME;
+
+/// Size of the log file above which it is rotated.
+const MAX_LOG_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Number of rotated log files kept besides the current one, as
+/// `shaderbg.log.1` (newest) to `shaderbg.log.3` (oldest).
+const ROTATED_LOG_FILE_COUNT: usize = 3;
+
+/// Levels of log messages per module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter {
+    /// Level of modules without a level of their own.
+    default: log::LevelFilter,
+
+    /// Modules, as paths relative to the crate root or paths of other
+    /// crates, and their levels.
+    modules: Vec<(String, log::LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Returns a filter with the same level for every module.
+    pub fn new(level: log::LevelFilter) -> Self {
+        Self {
+            default: level,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Returns the level of messages logged by `target`, the most
+    /// specific module given taking precedence.
+    pub fn level(&self, target: &str) -> log::LevelFilter {
+        let target = target
+            .strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::"))
+            .unwrap_or(target);
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    /// Returns the highest level of any module.
+    pub fn max_level(&self) -> log::LevelFilter {
+        self.modules
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+/// Parses a log filter such as `"warn"` or `"warn,frame_controller=trace"`.
+pub fn parse_log_filter(value: &str) -> Result<LogFilter, String> {
+    let parse_level = |level: &str| {
+        level.trim().parse::<log::LevelFilter>().map_err(|_| {
+            format!("Invalid level '{level}' (expected off, error, warn, info, debug or trace)")
+        })
+    };
+
+    let mut filter = LogFilter::new(log::LevelFilter::Warn);
+    for directive in value.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        match directive.split_once('=') {
+            Some((module, level)) => {
+                let module = module.trim().trim_start_matches("::");
+                if module.is_empty() {
+                    return Err(format!("Missing module name in '{directive}'"));
+                }
+                filter
+                    .modules
+                    .push((module.to_string(), parse_level(level)?));
+            }
+            None => filter.default = parse_level(directive)?,
+        }
+    }
+    Ok(filter)
+}
+
+/// Returns the default log file, in the cache directory.
+pub fn default_log_file() -> io::Result<PathBuf> {
+    Ok(crate::config::cache_dir()?.join(format!("{APP_NAME}.log")))
+}
+
+/// Installs the logger with the level of the configuration file, or
+/// else the default level of the build.
+pub fn init(config_filter: Option<LogFilter>) -> Result<(), log::SetLoggerError> {
+    let filter = config_filter.unwrap_or_else(|| {
+        LogFilter::new(if cfg!(debug_assertions) {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Warn
+        })
+    });
+
+    let logger = LOGGER.get_or_init(|| Logger {
+        console: simple_logger::SimpleLogger::new().with_level(log::LevelFilter::Trace),
+        filter: RwLock::new(LogFilter::new(log::LevelFilter::Trace)),
+        file: Mutex::new(None),
+    });
+    log::set_logger(logger)?;
+    set_filter(filter);
+    Ok(())
+}
+
+/// Replaces the filter of log messages.
+pub fn set_filter(filter: LogFilter) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    log::set_max_level(filter.max_level());
+    if let Ok(mut current) = logger.filter.write() {
+        *current = filter;
+    }
+}
+
+/// Also writes log messages to the file at `path`, which is rotated
+/// when it grows too large.
+pub fn set_file(path: &Path) -> io::Result<()> {
+    let Some(logger) = LOGGER.get() else {
+        return Ok(());
+    };
+    let file = LogFile::open(path)?;
+    if let Ok(mut current) = logger.file.lock() {
+        *current = Some(file);
+    }
+    Ok(())
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+struct Logger {
+    console: simple_logger::SimpleLogger,
+    filter: RwLock<LogFilter>,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.filter
+            .read()
+            .is_ok_and(|filter| metadata.level() <= filter.level(metadata.target()))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.console.log(record);
+
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(file) = file.as_mut() {
+                // Logging must not fail, and there is nowhere to report to
+                let _ = file.write(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(file) = file.as_mut() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+/// Log file with its rotation state.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn write(&mut self, record: &log::Record) -> io::Result<()> {
+        if self.size >= MAX_LOG_FILE_SIZE {
+            self.rotate()?;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Renames the file to `<name>.1`, shifting older files up to
+    /// [`ROTATED_LOG_FILE_COUNT`], and starts a new file.
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = |index: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{index}"));
+            PathBuf::from(name)
+        };
+        for index in (1..ROTATED_LOG_FILE_COUNT).rev() {
+            let _ = fs::rename(rotated(index), rotated(index + 1));
+        }
+        fs::rename(&self.path, rotated(1))?;
+
+        *self = Self::open(&self.path)?;
+        Ok(())
+    }
+}
@@ -3,17 +3,62 @@
 // https://github.com/hbatagelo/shaderbg
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+#[cfg(test)]
+mod tests {
+    mod audio_spectrum;
+    mod bundle;
+    mod desktop_monitor;
+    mod drm;
+    mod frame_controller;
+    mod history;
+    mod ipc;
+    mod logging;
+    mod preset;
+    mod resolution_controller;
+    mod sun;
+    mod system_stats;
+    mod util;
+}
 mod app;
+mod audio_spectrum;
+mod bundle;
+mod catalog;
+mod check;
 mod cli;
+mod config;
+mod daemon;
+mod desktop_monitor;
 mod drm;
+mod export;
 mod frame_controller;
 mod geometry;
+mod history;
+mod hotkeys;
+mod hud;
+mod idle_monitor;
+mod ipc;
 mod keyboard_controller;
+mod lint;
+mod logging;
+mod media_monitor;
 mod mouse_controller;
+mod occlusion_monitor;
+mod offscreen;
+mod output_power_monitor;
+mod power_monitor;
 mod preset;
 mod renderer;
+mod resolution_controller;
 mod screen_controller;
+mod session_lock;
+mod settings;
 mod shadertoy;
+mod sleep_monitor;
+mod sun;
+mod supervisor;
+mod system_stats;
+mod thumbnail;
+mod util;
 
 pub const APP_NAME: &str = "shaderbg";
 pub const APP_ABOUT: &str = "Shader wallpaper utility for Wayland";
@@ -21,13 +66,21 @@ pub const APP_AUTHOR: &str = "Harlen Batagelo, hbatagelo@gmail.com";
 pub const APP_ID: &str = "com.github.hbatagelo.shaderbg";
 pub const APP_SEMVER: &str = "1.2.0";
 pub const GL_VERSION: (i32, i32) = (4, 2);
+pub const GLES_VERSION: (i32, i32) = (3, 1);
 
 fn main() -> gtk::glib::ExitCode {
-    if let Err(err) = app::init_logging() {
+    let config_result = config::init();
+
+    if let Err(err) = logging::init(config::config().log_level.clone()) {
         eprintln!("Failed to initialize logging: {err}");
     }
+    supervisor::install_panic_hook();
+
+    if let Err(err) = config_result {
+        log::warn!("{err}. Using default settings.");
+    }
 
-    let config = match cli::parse_args() {
+    let mut config = match cli::parse_args() {
         Ok(cfg) => cfg,
         Err(cli::CliError::InvalidInput(warn)) => {
             log::warn!("{warn}. Using default settings.");
@@ -39,5 +92,110 @@ fn main() -> gtk::glib::ExitCode {
         }
     };
 
+    if let Some(filter) = config.log_level.take() {
+        logging::set_filter(filter);
+    }
+    if let Some(path) = &config.log_file {
+        if let Err(err) = logging::set_file(path) {
+            log::warn!("Failed to open log file {}: {err}", path.display());
+        }
+    }
+
+    if let cli::Command::Supervise = config.command {
+        return supervisor::run();
+    }
+
+    // Must precede the creation of any GL context
+    if let Some(gpu) = &config.gpu {
+        if let Err(err) = drm::select_gpu(gpu) {
+            log::warn!("{err}. Using the default GPU.");
+        }
+    }
+
+    match &config.command {
+        cli::Command::Run | cli::Command::Supervise => {}
+        cli::Command::Screenshot(options) => {
+            return match offscreen::screenshot(&config.preset, options) {
+                Ok(()) => gtk::glib::ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err}");
+                    gtk::glib::ExitCode::FAILURE
+                }
+            };
+        }
+        cli::Command::Settings => return settings::run(),
+        cli::Command::Control(command) => {
+            return match ipc::send(command) {
+                Ok(_) => gtk::glib::ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err}");
+                    gtk::glib::ExitCode::FAILURE
+                }
+            };
+        }
+        cli::Command::Monitors => return drm::list_monitors(),
+        cli::Command::List(options) => return catalog::list(options),
+        cli::Command::Info(options) => return catalog::info(options),
+        cli::Command::Check(options) => return check::check(options),
+        cli::Command::Lint(options) => return lint::lint(options),
+        cli::Command::Import(options) => {
+            return match shadertoy::importer::import(options) {
+                Ok(path) => {
+                    log::info!("Saved {}", path.display());
+                    gtk::glib::ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{err}");
+                    gtk::glib::ExitCode::FAILURE
+                }
+            };
+        }
+        cli::Command::Pack(options) => {
+            return match bundle::pack(options) {
+                Ok(()) => {
+                    log::info!("Saved {}", options.output.display());
+                    gtk::glib::ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{err}");
+                    gtk::glib::ExitCode::FAILURE
+                }
+            };
+        }
+        cli::Command::Unpack(options) => {
+            return match bundle::unpack(options) {
+                Ok(()) => {
+                    log::info!("Extracted to {}", options.output.display());
+                    gtk::glib::ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{err}");
+                    gtk::glib::ExitCode::FAILURE
+                }
+            };
+        }
+        // Runs the picked result as the wallpaper
+        cli::Command::Search(options) => match shadertoy::search::search(options) {
+            Ok(Some((preset, path))) => {
+                config.preset = preset;
+                config.preset_path = Some(path);
+            }
+            Ok(None) => return gtk::glib::ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{err}");
+                return gtk::glib::ExitCode::FAILURE;
+            }
+        },
+        cli::Command::Export(options) => {
+            return match export::export(&config.preset, options) {
+                Ok(()) => gtk::glib::ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err}");
+                    gtk::glib::ExitCode::FAILURE
+                }
+            };
+        }
+    }
+
     app::run(config)
 }
@@ -0,0 +1,390 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Now-playing information from MPRIS media players.
+//!
+//! Watches the media players on the session D-Bus and exposes the track
+//! of the active player to shaders: its album art through `album_art`
+//! inputs, and its playback progress through `iTrackProgress`. The
+//! active player is the first one playing, or else the first one paused.
+//!
+//! Players are tracked from `NameOwnerChanged` signals, and their state
+//! from the `PropertiesChanged` and `Seeked` signals of their proxies, so
+//! the main loop never waits on a player.
+
+use gtk::{
+    gio::{self, prelude::*},
+    glib,
+};
+use image::RgbaImage;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+const DBUS_NAME: &str = "org.freedesktop.DBus";
+const DBUS_PATH: &str = "/org/freedesktop/DBus";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const MPRIS_NAMESPACE: &str = "org.mpris.MediaPlayer2";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const SEEKED_SIGNAL: &str = "Seeked";
+
+/// Timeout of each D-Bus call.
+const CALL_TIMEOUT_MS: i32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum MediaError {
+    #[error("Failed to connect to the session bus: {0}")]
+    DBus(#[from] glib::Error),
+}
+
+/// Now-playing data supplied to the renderer for one frame.
+#[derive(Clone, Default)]
+pub struct MediaData {
+    /// Fraction of the current track played (`iTrackProgress`), or zero
+    /// when unknown.
+    pub track_progress: f32,
+
+    /// Album art of the current track, if any.
+    pub album_art: Option<Rc<RgbaImage>>,
+}
+
+/// Media player on the session bus.
+struct Player {
+    /// Well-known bus name (e.g., "org.mpris.MediaPlayer2.vlc").
+    name: String,
+
+    /// Proxy to the player interface, caching its properties.
+    proxy: gio::DBusProxy,
+
+    /// Playback position, as last queried or signaled.
+    position: Duration,
+
+    /// Time at which `position` was reported.
+    position_at: Instant,
+}
+
+impl Player {
+    /// Returns the cached `PlaybackStatus` property.
+    fn status(&self) -> Option<String> {
+        self.proxy
+            .cached_property("PlaybackStatus")?
+            .get::<String>()
+    }
+
+    /// Returns the cached `Metadata` property.
+    fn metadata(&self) -> Option<glib::VariantDict> {
+        let metadata = self.proxy.cached_property("Metadata")?;
+        Some(glib::VariantDict::new(Some(&metadata)))
+    }
+
+    /// Returns the art URL of the current track, if any.
+    fn art_url(&self) -> Option<String> {
+        self.metadata()?
+            .lookup_value("mpris:artUrl", Some(glib::VariantTy::STRING))
+            .and_then(|url| url.get::<String>())
+            .filter(|url| !url.is_empty())
+    }
+
+    /// Returns the fraction of the current track played, extrapolated
+    /// from the last reported position while playing.
+    fn progress(&self) -> f32 {
+        let length = self
+            .metadata()
+            .and_then(|metadata| metadata.lookup_value("mpris:length", None))
+            .and_then(|length| microseconds(&length))
+            .filter(|length| !length.is_zero());
+        let Some(length) = length else {
+            return 0.0;
+        };
+        let position = if self.status().as_deref() == Some("Playing") {
+            self.position + self.position_at.elapsed()
+        } else {
+            self.position
+        };
+        (position.as_secs_f64() / length.as_secs_f64()).clamp(0.0, 1.0) as f32
+    }
+}
+
+/// Players on the bus and the album art of the active one.
+#[derive(Default)]
+struct State {
+    /// Players, in order of appearance.
+    players: Vec<Player>,
+
+    /// URL of the album art loaded or being loaded.
+    art_url: Option<String>,
+
+    /// Decoded album art, filled when loading completes.
+    album_art: Rc<RefCell<Option<Rc<RgbaImage>>>>,
+}
+
+impl State {
+    /// Returns the active player, if any.
+    fn active_player(&self) -> Option<&Player> {
+        let with_status = |status: &str| {
+            self.players
+                .iter()
+                .find(|player| player.status().as_deref() == Some(status))
+        };
+        with_status("Playing").or_else(|| with_status("Paused"))
+    }
+
+    /// Starts loading the album art of the active player if it changed.
+    fn update_album_art(&mut self) {
+        let art_url = self.active_player().and_then(Player::art_url);
+        if art_url != self.art_url {
+            // Loads of previous tracks find their target dropped
+            self.album_art = Rc::default();
+            if let Some(url) = &art_url {
+                load_album_art(url, &self.album_art);
+            }
+            self.art_url = art_url;
+        }
+    }
+}
+
+/// Watches MPRIS players for the current track.
+pub struct MediaMonitor {
+    state: Rc<RefCell<State>>,
+
+    /// Subscription to the appearance and disappearance of players.
+    _name_owner_changed: gio::SignalSubscription,
+}
+
+impl MediaMonitor {
+    /// Connects to the session bus and starts watching players.
+    pub fn new() -> Result<Self, MediaError> {
+        let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)?;
+        let state = Rc::new(RefCell::new(State::default()));
+
+        let weak_state = Rc::downgrade(&state);
+        let name_owner_changed = connection.subscribe_to_signal(
+            Some(DBUS_NAME),
+            Some(DBUS_NAME),
+            Some("NameOwnerChanged"),
+            Some(DBUS_PATH),
+            Some(MPRIS_NAMESPACE),
+            gio::DBusSignalFlags::MATCH_ARG0_NAMESPACE,
+            move |signal| {
+                let Some(state) = weak_state.upgrade() else {
+                    return;
+                };
+                let Some((name, _, new_owner)) =
+                    signal.parameters.get::<(String, String, String)>()
+                else {
+                    return;
+                };
+
+                state
+                    .borrow_mut()
+                    .players
+                    .retain(|player| player.name != name);
+                if !new_owner.is_empty() {
+                    add_player(&state, signal.connection, name);
+                }
+                state.borrow_mut().update_album_art();
+            },
+        );
+
+        let weak_state = Rc::downgrade(&state);
+        connection.call(
+            Some(DBUS_NAME),
+            DBUS_PATH,
+            DBUS_NAME,
+            "ListNames",
+            None,
+            glib::VariantTy::new("(as)").ok(),
+            gio::DBusCallFlags::NONE,
+            CALL_TIMEOUT_MS,
+            None::<&gio::Cancellable>,
+            glib::clone!(
+                #[strong]
+                connection,
+                move |result| {
+                    let Some(state) = weak_state.upgrade() else {
+                        return;
+                    };
+                    let names = match result.map(|reply| reply.get::<(Vec<String>,)>()) {
+                        Ok(Some((names,))) => names,
+                        Ok(None) => return,
+                        Err(err) => {
+                            log::warn!("Failed to list media players: {err}");
+                            return;
+                        }
+                    };
+                    let prefix = format!("{MPRIS_NAMESPACE}.");
+                    for name in names.into_iter().filter(|name| name.starts_with(&prefix)) {
+                        add_player(&state, &connection, name);
+                    }
+                }
+            ),
+        );
+
+        Ok(Self {
+            state,
+            _name_owner_changed: name_owner_changed,
+        })
+    }
+
+    /// Returns the now-playing data for the current frame.
+    pub fn data(&self) -> MediaData {
+        let state = self.state.borrow();
+        let album_art = state.album_art.borrow().clone();
+        MediaData {
+            track_progress: state
+                .active_player()
+                .map(Player::progress)
+                .unwrap_or_default(),
+            album_art,
+        }
+    }
+}
+
+/// Creates a proxy to the player `name` in the background, and adds the
+/// player to `state` once it is ready.
+fn add_player(state: &Rc<RefCell<State>>, connection: &gio::DBusConnection, name: String) {
+    let weak_state = Rc::downgrade(state);
+    let bus_name = name.clone();
+    gio::DBusProxy::new(
+        connection,
+        gio::DBusProxyFlags::DO_NOT_AUTO_START | gio::DBusProxyFlags::GET_INVALIDATED_PROPERTIES,
+        None,
+        Some(&bus_name),
+        MPRIS_PATH,
+        PLAYER_INTERFACE,
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(state) = weak_state.upgrade() else {
+                return;
+            };
+            let proxy = match result {
+                Ok(proxy) => proxy,
+                Err(err) => {
+                    log::debug!("Ignoring media player {name}: {err}");
+                    return;
+                }
+            };
+            let position = proxy
+                .cached_property("Position")
+                .and_then(|position| microseconds(&position))
+                .unwrap_or_default();
+
+            watch_player(&state, &proxy, &name);
+
+            let mut state = state.borrow_mut();
+            // The player may have been replaced while the proxy was created
+            state.players.retain(|player| player.name != name);
+            state.players.push(Player {
+                name,
+                proxy,
+                position,
+                position_at: Instant::now(),
+            });
+            state.update_album_art();
+        },
+    );
+}
+
+/// Updates the player `name` in `state` on the signals of its proxy.
+fn watch_player(state: &Rc<RefCell<State>>, proxy: &gio::DBusProxy, name: &str) {
+    // The position is not signaled as it changes, so it is queried again
+    // whenever playback changes
+    let weak_state = Rc::downgrade(state);
+    let player_name = name.to_string();
+    proxy.connect_local("g-properties-changed", false, move |values| {
+        let proxy = values.first()?.get::<gio::DBusProxy>().ok()?;
+        let state = weak_state.upgrade()?;
+        query_position(&state, &proxy, &player_name);
+        state.borrow_mut().update_album_art();
+        None
+    });
+
+    let weak_state = Rc::downgrade(state);
+    let player_name = name.to_string();
+    proxy.connect_local("g-signal", false, move |values| {
+        let signal_name = values.get(2)?.get::<String>().ok()?;
+        if signal_name != SEEKED_SIGNAL {
+            return None;
+        }
+        let parameters = values.get(3)?.get::<glib::Variant>().ok()?;
+        let position = microseconds(&parameters.child_value(0))?;
+        set_position(&weak_state.upgrade()?, &player_name, position);
+        None
+    });
+}
+
+/// Queries the playback position of the player `name` in the background.
+fn query_position(state: &Rc<RefCell<State>>, proxy: &gio::DBusProxy, name: &str) {
+    let weak_state = Rc::downgrade(state);
+    let name = name.to_string();
+    proxy.connection().call(
+        proxy.name().as_deref(),
+        MPRIS_PATH,
+        PROPERTIES_INTERFACE,
+        "Get",
+        Some(&(PLAYER_INTERFACE, "Position").to_variant()),
+        glib::VariantTy::new("(v)").ok(),
+        gio::DBusCallFlags::NO_AUTO_START,
+        CALL_TIMEOUT_MS,
+        None::<&gio::Cancellable>,
+        move |result| {
+            let position = result
+                .ok()
+                .and_then(|reply| reply.child_value(0).as_variant())
+                .and_then(|position| microseconds(&position));
+            if let (Some(state), Some(position)) = (weak_state.upgrade(), position) {
+                set_position(&state, &name, position);
+            }
+        },
+    );
+}
+
+/// Sets the playback position of the player `name`.
+fn set_position(state: &Rc<RefCell<State>>, name: &str, position: Duration) {
+    let mut state = state.borrow_mut();
+    if let Some(player) = state.players.iter_mut().find(|player| player.name == name) {
+        player.position = position;
+        player.position_at = Instant::now();
+    }
+}
+
+/// Converts a duration in microseconds, which players send either as a
+/// signed or an unsigned 64-bit integer.
+fn microseconds(value: &glib::Variant) -> Option<Duration> {
+    let micros = value
+        .get::<i64>()
+        .and_then(|micros| u64::try_from(micros).ok())
+        .or_else(|| value.get::<u64>())?;
+    Some(Duration::from_micros(micros))
+}
+
+/// Loads and decodes album art in the background, storing it in `target`
+/// unless it was dropped in the meantime.
+fn load_album_art(url: &str, target: &Rc<RefCell<Option<Rc<RgbaImage>>>>) {
+    let file = gio::File::for_uri(url);
+    let url = url.to_string();
+    let pending = Rc::downgrade(target);
+
+    file.load_contents_async(None::<&gio::Cancellable>, move |result| {
+        let Some(target) = pending.upgrade() else {
+            return;
+        };
+        let image = match result {
+            Ok((contents, _)) => image::load_from_memory(&contents).map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+
+        match image {
+            Ok(image) => {
+                target.replace(Some(Rc::new(image.to_rgba8())));
+            }
+            Err(err) => log::warn!("Failed to load album art {url}: {err}"),
+        }
+    });
+}
@@ -7,9 +7,11 @@
 //!
 //! Captures GTK pointer motion and button events and converts them into
 //! ShaderToy-compatible `iMouse` uniform data expressed in global
-//! OpenGL screen coordinates.
+//! OpenGL screen coordinates. Scrolling and the state of every button
+//! are also tracked for the ShaderBG-specific `iMouseWheel` and
+//! `iMouseButtons` uniforms.
 
-use gtk::{glib, prelude::*};
+use gtk::{gdk, glib, prelude::*};
 use owo_colors::OwoColorize;
 
 use crate::{app::*, geometry::*, *};
@@ -40,12 +42,24 @@ pub struct MouseController {
     /// Used to guarantee that multi-monitor rendering pipelines observe
     /// at least one frame where the click is reported as "just pressed".
     frames_since_pressed: usize,
+
+    /// Bitmask of the buttons currently held (see [`button_mask`]).
+    buttons: i32,
+
+    /// Scroll offset accumulated since startup.
+    wheel_offset: [f32; 2],
+
+    /// Scroll offset accumulated since the last snapshot.
+    wheel_delta: [f32; 2],
 }
 
-/// Raw mouse data formatted for ShaderToy's `iMouse` uniform.
-#[derive(Clone, Copy, Debug)]
+/// Raw mouse data formatted for ShaderToy's `iMouse` uniform and the
+/// `iMouseWheel` and `iMouseButtons` uniforms.
+#[derive(Clone, Copy, Debug, Default)]
 pub struct MouseData {
     raw: [i32; 4],
+    wheel: [f32; 4],
+    buttons: i32,
 }
 
 impl MouseData {
@@ -53,6 +67,56 @@ impl MouseData {
     pub fn as_shadertoy_uniform(&self) -> &[i32; 4] {
         &self.raw
     }
+
+    /// Returns the `iMouseWheel` uniform: accumulated scroll offset in
+    /// `xy` and scroll delta since the previous frame in `zw`.
+    ///
+    /// Positive `y` values scroll up.
+    pub fn wheel(&self) -> &[f32; 4] {
+        &self.wheel
+    }
+
+    /// Returns the `iMouseButtons` uniform: a bitmask of the buttons
+    /// currently held (1 = left, 2 = middle, 4 = right, 8 = back,
+    /// 16 = forward).
+    pub fn buttons(&self) -> i32 {
+        self.buttons
+    }
+
+    /// Returns the data with `iMouse` positions relative to `origin`,
+    /// keeping the signs that encode the button state.
+    pub fn relative_to(&self, origin: Point) -> Self {
+        let shift = |value: i32, origin: i32| {
+            if value < 0 {
+                value + origin
+            } else {
+                value - origin
+            }
+        };
+        let [x, y, click_x, click_y] = self.raw;
+        Self {
+            raw: [
+                shift(x, origin.x()),
+                shift(y, origin.y()),
+                shift(click_x, origin.x()),
+                shift(click_y, origin.y()),
+            ],
+            ..*self
+        }
+    }
+}
+
+/// Returns the `iMouseButtons` bit of a GDK button number, or `0` for
+/// buttons beyond the fifth.
+fn button_mask(button: u32) -> i32 {
+    match button {
+        gdk::BUTTON_PRIMARY => 1,
+        gdk::BUTTON_MIDDLE => 2,
+        gdk::BUTTON_SECONDARY => 4,
+        8 => 8,
+        9 => 16,
+        _ => 0,
+    }
 }
 
 impl MouseController {
@@ -64,6 +128,9 @@ impl MouseController {
             last_release_position: Point::default(),
             pressed: false,
             frames_since_pressed: 0,
+            buttons: 0,
+            wheel_offset: [0.0; 2],
+            wheel_delta: [0.0; 2],
         }
     }
 
@@ -128,6 +195,58 @@ impl MouseController {
             }
         ));
         widget.add_controller(click_controller);
+
+        // Tracks every button independently of the primary-button gesture
+        // above, which drives the ShaderToy `iMouse` semantics.
+        let buttons_controller = gtk::GestureClick::new();
+        buttons_controller.set_button(0);
+        buttons_controller.connect_pressed(glib::clone!(
+            #[weak(rename_to = app)]
+            self.app,
+            move |gesture, _, _, _| {
+                let app_data = get_data!(app, AppData, as_mut());
+                let mouse = &mut app_data.mouse_controller;
+                mouse.buttons |= button_mask(gesture.current_button());
+
+                log::trace!("{} {:?}", "button pressed".white().bold(), mouse);
+            }
+        ));
+        buttons_controller.connect_released(glib::clone!(
+            #[weak(rename_to = app)]
+            self.app,
+            move |gesture, _, _, _| {
+                let app_data = get_data!(app, AppData, as_mut());
+                let mouse = &mut app_data.mouse_controller;
+                mouse.buttons &= !button_mask(gesture.current_button());
+
+                log::trace!("{} {:?}", "button released".white().bold(), mouse);
+            }
+        ));
+        widget.add_controller(buttons_controller);
+
+        let scroll_controller =
+            gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+        scroll_controller.connect_scroll(glib::clone!(
+            #[weak(rename_to = app)]
+            self.app,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |_, dx, dy| {
+                let app_data = get_data!(app, AppData, as_mut());
+                let mouse = &mut app_data.mouse_controller;
+                // GTK reports positive dy when scrolling down; OpenGL's y
+                // axis points up.
+                let (dx, dy) = (dx as f32, -dy as f32);
+                mouse.wheel_offset[0] += dx;
+                mouse.wheel_offset[1] += dy;
+                mouse.wheel_delta[0] += dx;
+                mouse.wheel_delta[1] += dy;
+
+                log::trace!("{} {:?}", "scroll".white().bold(), mouse);
+                glib::Propagation::Stop
+            }
+        ));
+        widget.add_controller(scroll_controller);
     }
 
     /// Produces ShaderToy-compatible mouse uniform data.
@@ -145,6 +264,9 @@ impl MouseController {
             last_release_position: release,
             pressed,
             frames_since_pressed,
+            buttons,
+            wheel_offset,
+            wheel_delta,
         } = &mut *self;
 
         let app_data = get_data!(app, AppData, as_mut());
@@ -163,6 +285,18 @@ impl MouseController {
             [release.x(), release.y(), -press.x(), -press.y()]
         };
 
-        MouseData { raw }
+        let wheel = [
+            wheel_offset[0],
+            wheel_offset[1],
+            wheel_delta[0],
+            wheel_delta[1],
+        ];
+        *wheel_delta = [0.0; 2];
+
+        MouseData {
+            raw,
+            wheel,
+            buttons: *buttons,
+        }
     }
 }
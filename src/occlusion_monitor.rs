@@ -0,0 +1,365 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Detection of outputs covered by fullscreen windows.
+//!
+//! Tracks toplevel windows through the `zwlr_foreign_toplevel_manager_v1`
+//! protocol on a dedicated Wayland connection, and reports which outputs
+//! are fully hidden by a focused fullscreen window. The connection is
+//! dispatched from the GLib main loop, so change notifications arrive on
+//! the GTK thread.
+
+use gtk::glib;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    os::fd::{AsRawFd, RawFd},
+    rc::Rc,
+};
+use thiserror::Error;
+use wayland_client::{
+    backend::ObjectId,
+    event_created_child,
+    protocol::{wl_output, wl_registry},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+/// Highest `wl_output` version used. Version 4 adds the `name` event,
+/// which carries the connector name.
+const WL_OUTPUT_VERSION: u32 = 4;
+
+/// Highest `zwlr_foreign_toplevel_manager_v1` version used.
+const FOREIGN_TOPLEVEL_VERSION: u32 = 3;
+
+#[derive(Debug, Error)]
+pub enum OcclusionError {
+    #[error("Failed to connect to the Wayland display: {0}")]
+    Connect(#[from] wayland_client::ConnectError),
+
+    #[error("Wayland dispatch failed: {0}")]
+    Dispatch(#[from] wayland_client::DispatchError),
+
+    #[error("Compositor does not support zwlr_foreign_toplevel_manager_v1")]
+    Unsupported,
+}
+
+/// Watches toplevel windows and reports outputs hidden by fullscreen windows.
+///
+/// Dropping the monitor stops watching and closes the connection.
+pub struct OcclusionMonitor {
+    state: Rc<RefCell<State>>,
+    source_id: Option<glib::SourceId>,
+}
+
+impl OcclusionMonitor {
+    /// Connects to the compositor and starts watching toplevels.
+    ///
+    /// `on_change` is invoked from the main loop whenever the set of
+    /// occluded outputs changes.
+    pub fn new<F>(on_change: F) -> Result<Self, OcclusionError>
+    where
+        F: Fn(&HashSet<String>) + 'static,
+    {
+        let connection = Connection::connect_to_env()?;
+        let mut queue = connection.new_event_queue::<State>();
+        let queue_handle = queue.handle();
+        connection.display().get_registry(&queue_handle, ());
+
+        let mut state = State::default();
+
+        // First roundtrip binds the globals, the second receives the
+        // initial output names and toplevel states.
+        queue.roundtrip(&mut state)?;
+        if state.manager.is_none() {
+            return Err(OcclusionError::Unsupported);
+        }
+        queue.roundtrip(&mut state)?;
+        state.changed = false;
+
+        let state = Rc::new(RefCell::new(state));
+        let fd = connection.backend().poll_fd().as_raw_fd();
+        let source_id = Self::watch(fd, connection, queue, state.clone(), on_change);
+
+        Ok(Self {
+            state,
+            source_id: Some(source_id),
+        })
+    }
+
+    /// Returns the connector names of outputs currently covered by a
+    /// focused fullscreen window.
+    pub fn occluded_outputs(&self) -> HashSet<String> {
+        self.state.borrow().occluded_outputs()
+    }
+
+    /// Dispatches Wayland events whenever the connection becomes readable.
+    fn watch<F>(
+        fd: RawFd,
+        connection: Connection,
+        mut queue: EventQueue<State>,
+        state: Rc<RefCell<State>>,
+        on_change: F,
+    ) -> glib::SourceId
+    where
+        F: Fn(&HashSet<String>) + 'static,
+    {
+        glib::unix_fd_add_local(
+            fd,
+            glib::IOCondition::IN | glib::IOCondition::HUP | glib::IOCondition::ERR,
+            move |_, condition| {
+                if condition.intersects(glib::IOCondition::HUP | glib::IOCondition::ERR) {
+                    log::warn!("Occlusion monitor: Wayland connection closed");
+                    return glib::ControlFlow::Break;
+                }
+
+                if let Some(guard) = queue.prepare_read() {
+                    if let Err(err) = guard.read() {
+                        log::warn!("Occlusion monitor: {err}");
+                        return glib::ControlFlow::Break;
+                    }
+                }
+
+                let occluded_outputs = {
+                    let mut state = state.borrow_mut();
+                    if let Err(err) = queue.dispatch_pending(&mut state) {
+                        log::warn!("Occlusion monitor: {err}");
+                        return glib::ControlFlow::Break;
+                    }
+                    let _ = connection.flush();
+
+                    if !std::mem::take(&mut state.changed) {
+                        return glib::ControlFlow::Continue;
+                    }
+                    state.occluded_outputs()
+                };
+
+                on_change(&occluded_outputs);
+                glib::ControlFlow::Continue
+            },
+        )
+    }
+}
+
+impl Drop for OcclusionMonitor {
+    fn drop(&mut self) {
+        if let Some(source_id) = self.source_id.take() {
+            source_id.remove();
+        }
+    }
+}
+
+/// Double-buffered state of a foreign toplevel.
+#[derive(Default)]
+struct Toplevel {
+    /// Outputs the toplevel is visible on.
+    outputs: HashSet<ObjectId>,
+    fullscreen: bool,
+    activated: bool,
+    minimized: bool,
+
+    /// Values received since the last `done` event.
+    pending: Option<ToplevelFlags>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct ToplevelFlags {
+    fullscreen: bool,
+    activated: bool,
+    minimized: bool,
+}
+
+impl Toplevel {
+    /// Returns `true` if the toplevel hides everything beneath it.
+    ///
+    /// Only the focused window is considered, since the protocol does not
+    /// tell whether a fullscreen window belongs to the visible workspace.
+    fn is_occluding(&self) -> bool {
+        self.fullscreen && self.activated && !self.minimized
+    }
+}
+
+#[derive(Default)]
+struct State {
+    manager: Option<ZwlrForeignToplevelManagerV1>,
+
+    /// Bound outputs keyed by registry name, with their connector names.
+    outputs: HashMap<u32, (wl_output::WlOutput, Option<String>)>,
+
+    toplevels: HashMap<ObjectId, Toplevel>,
+
+    /// Set when a change may affect the set of occluded outputs.
+    changed: bool,
+}
+
+impl State {
+    fn occluded_outputs(&self) -> HashSet<String> {
+        self.toplevels
+            .values()
+            .filter(|toplevel| toplevel.is_occluding())
+            .flat_map(|toplevel| toplevel.outputs.iter())
+            .filter_map(|output_id| {
+                self.outputs
+                    .values()
+                    .find(|(output, _)| output.id() == *output_id)
+                    .and_then(|(_, name)| name.clone())
+            })
+            .collect()
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        queue_handle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => {
+                if interface == wl_output::WlOutput::interface().name {
+                    let output = registry.bind::<wl_output::WlOutput, _, _>(
+                        name,
+                        version.min(WL_OUTPUT_VERSION),
+                        queue_handle,
+                        (),
+                    );
+                    state.outputs.insert(name, (output, None));
+                } else if interface == ZwlrForeignToplevelManagerV1::interface().name {
+                    state.manager = Some(registry.bind(
+                        name,
+                        version.min(FOREIGN_TOPLEVEL_VERSION),
+                        queue_handle,
+                        (),
+                    ));
+                }
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some((output, _)) = state.outputs.remove(&name) {
+                    if output.version() >= 3 {
+                        output.release();
+                    }
+                    state.changed = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            if let Some((_, output_name)) = state
+                .outputs
+                .values_mut()
+                .find(|(other, _)| other.id() == output.id())
+            {
+                *output_name = Some(name);
+                state.changed = true;
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state.toplevels.insert(toplevel.id(), Toplevel::default());
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                state.manager = None;
+                state.toplevels.clear();
+                state.changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::{Event, State as ToplevelState};
+
+        let Some(toplevel) = state.toplevels.get_mut(&handle.id()) else {
+            return;
+        };
+
+        match event {
+            Event::State { state: values } => {
+                let mut flags = ToplevelFlags::default();
+                for value in values
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                {
+                    match ToplevelState::try_from(value) {
+                        Ok(ToplevelState::Fullscreen) => flags.fullscreen = true,
+                        Ok(ToplevelState::Activated) => flags.activated = true,
+                        Ok(ToplevelState::Minimized) => flags.minimized = true,
+                        _ => {}
+                    }
+                }
+                toplevel.pending = Some(flags);
+            }
+            Event::OutputEnter { output } => {
+                toplevel.outputs.insert(output.id());
+                state.changed = true;
+            }
+            Event::OutputLeave { output } => {
+                toplevel.outputs.remove(&output.id());
+                state.changed = true;
+            }
+            Event::Done => {
+                if let Some(flags) = toplevel.pending.take() {
+                    toplevel.fullscreen = flags.fullscreen;
+                    toplevel.activated = flags.activated;
+                    toplevel.minimized = flags.minimized;
+                    state.changed = true;
+                }
+            }
+            Event::Closed => {
+                state.toplevels.remove(&handle.id());
+                handle.destroy();
+                state.changed = true;
+            }
+            _ => {}
+        }
+    }
+}
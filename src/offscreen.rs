@@ -0,0 +1,228 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Offscreen rendering without a wallpaper window.
+//!
+//! Creates a surfaceless OpenGL context through GDK and renders presets
+//! into the renderer's own framebuffers, with time driven by a fixed
+//! timestep [`FrameController`] so that output is reproducible.
+
+use chrono::NaiveDate;
+use gtk::{gdk, glib, prelude::*};
+use std::{path::PathBuf, time::Duration};
+use thiserror::Error;
+
+use crate::{
+    app::{create_gl_context, load_gl_functions, InputData},
+    audio_spectrum::MAX_AUDIO_BANDS,
+    desktop_monitor::DesktopState,
+    frame_controller::*,
+    geometry::*,
+    media_monitor::MediaData,
+    mouse_controller::MouseData,
+    preset::*,
+    renderer::{set_gl_profile, shader::ShaderError, GlProfile, Renderer},
+    system_stats::SystemStats,
+};
+
+/// Timestep used to simulate frames leading up to a screenshot.
+const SCREENSHOT_TIMESTEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+#[derive(Debug, Error)]
+pub enum OffscreenError {
+    #[error("Failed to initialize GTK: {0}")]
+    Init(#[from] gtk::glib::BoolError),
+
+    #[error("No default display")]
+    NoDisplay,
+
+    #[error("Failed to create OpenGL context: {0}")]
+    Context(#[from] gtk::glib::Error),
+
+    #[error("Failed to load OpenGL functions: {0}")]
+    GlLoad(String),
+
+    #[error("Failed to create renderer: {0}")]
+    Renderer(#[from] ShaderError),
+
+    #[error("Failed to save image: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+/// Settings of the `--screenshot` mode.
+#[derive(Debug, Clone)]
+pub struct ScreenshotOptions {
+    /// Image file to write. The format is deduced from the extension.
+    pub output: PathBuf,
+
+    /// Elapsed time of the captured frame.
+    pub time: Duration,
+
+    /// Size of the captured image, in pixels.
+    pub size: Size,
+
+    /// Date reported in `iDate` instead of the current date.
+    pub date: Option<NaiveDate>,
+}
+
+/// OpenGL context current on the calling thread and not bound to any surface.
+pub struct OffscreenContext {
+    gl_context: gdk::GLContext,
+}
+
+impl OffscreenContext {
+    /// Creates and makes current a surfaceless OpenGL context.
+    pub fn new() -> Result<Self, OffscreenError> {
+        gtk::init()?;
+
+        let display = gdk::Display::default().ok_or(OffscreenError::NoDisplay)?;
+        let gl_context = create_gl_context(&display)?;
+        gl_context.make_current();
+
+        load_gl_functions().map_err(|err| OffscreenError::GlLoad(err.to_string()))?;
+        set_gl_profile(GlProfile::from_context(
+            gl_context.api() == gdk::GLAPI::GLES,
+            gl_context.version(),
+        ));
+
+        Ok(Self { gl_context })
+    }
+
+    /// Creates a renderer producing frames of the given size.
+    pub fn renderer(&self, preset: &Preset, size: Size) -> Result<Renderer, OffscreenError> {
+        self.make_current();
+        Ok(Renderer::new(size, size, size, 1.0, preset)?)
+    }
+
+    /// Makes the context current again, e.g., after GTK rendered a
+    /// window with a context of its own.
+    pub fn make_current(&self) {
+        self.gl_context.make_current();
+    }
+}
+
+impl Drop for OffscreenContext {
+    fn drop(&mut self) {
+        gdk::GLContext::clear_current();
+    }
+}
+
+/// Renders one frame with neutral mouse and keyboard input.
+///
+/// `iSystemStats`, `iAudioBands`, `iDesktop` and `iTrackProgress` are
+/// zero, and album art inputs are black, so that offscreen output is
+/// reproducible.
+pub fn render_frame(renderer: &mut Renderer, frame_stats: &FrameStats) {
+    let input = InputData {
+        mouse: MouseData::default(),
+        keyboard: None,
+        system_stats: SystemStats::default(),
+        audio_bands: [0.0; MAX_AUDIO_BANDS],
+        desktop: DesktopState::default(),
+        media: MediaData::default(),
+    };
+    renderer.render(Offset::default(), &input, frame_stats);
+}
+
+/// Reads back the last rendered frame, resampled to `size` if
+/// `resolution_scale` made the framebuffer larger or smaller.
+pub fn read_frame(renderer: &Renderer, size: Size) -> image::RgbaImage {
+    let image = renderer.read_frame();
+    if image.width() == size.width() && image.height() == size.height() {
+        image
+    } else {
+        image::imageops::resize(
+            &image,
+            size.width(),
+            size.height(),
+            image::imageops::FilterType::Triangle,
+        )
+    }
+}
+
+/// Renders the preset at the requested time and saves the Image pass output.
+pub fn screenshot(preset: &Preset, options: &ScreenshotOptions) -> Result<(), OffscreenError> {
+    let context = OffscreenContext::new()?;
+    render_still(&context, preset, options.time, options.size, options.date)?
+        .save(&options.output)?;
+
+    log::info!("Saved {}", options.output.display());
+
+    Ok(())
+}
+
+/// Renders the frame of the preset at `time` and returns the Image pass
+/// output.
+///
+/// Presets with buffer passes may depend on previous frames, so every
+/// frame since the start is simulated at 60 FPS. Otherwise, only the
+/// requested frame is rendered.
+pub fn render_still(
+    context: &OffscreenContext,
+    preset: &Preset,
+    time: Duration,
+    size: Size,
+    date: Option<NaiveDate>,
+) -> Result<image::RgbaImage, OffscreenError> {
+    let mut renderer = context.renderer(preset, size)?;
+    let mut frame_controller =
+        FrameController::with_fixed_timestep(preset, SCREENSHOT_TIMESTEP).with_date(date);
+
+    for _ in 0..seek_still(&mut renderer, &mut frame_controller, preset, time) {
+        render_frame(&mut renderer, &frame_controller.step());
+    }
+
+    Ok(read_frame(&renderer, size))
+}
+
+/// Same as [`render_still`], but returns to the main loop after every
+/// frame so that windows stay responsive.
+pub async fn render_still_async(
+    context: &OffscreenContext,
+    preset: &Preset,
+    time: Duration,
+    size: Size,
+    date: Option<NaiveDate>,
+) -> Result<image::RgbaImage, OffscreenError> {
+    let mut renderer = context.renderer(preset, size)?;
+    let mut frame_controller =
+        FrameController::with_fixed_timestep(preset, SCREENSHOT_TIMESTEP).with_date(date);
+
+    for _ in 0..seek_still(&mut renderer, &mut frame_controller, preset, time) {
+        render_frame(&mut renderer, &frame_controller.step());
+        glib::timeout_future(Duration::ZERO).await;
+        context.make_current();
+    }
+
+    Ok(read_frame(&renderer, size))
+}
+
+/// Prepares the rendering of the frame at `time` and returns the number
+/// of frames to render, which is every frame since the start for
+/// presets with feedback passes.
+fn seek_still(
+    renderer: &mut Renderer,
+    frame_controller: &mut FrameController,
+    preset: &Preset,
+    time: Duration,
+) -> u32 {
+    if has_feedback_passes(preset) {
+        frame_controller.steps_until(time) + 1
+    } else {
+        frame_controller.seek(time);
+        renderer.seek_media(time);
+        1
+    }
+}
+
+/// Returns `true` if the output of a frame may depend on earlier frames.
+pub fn has_feedback_passes(preset: &Preset) -> bool {
+    preset.buffer_a.is_some()
+        || preset.buffer_b.is_some()
+        || preset.buffer_c.is_some()
+        || preset.buffer_d.is_some()
+        || !preset.extra_passes.is_empty()
+        || preset.cube_a.is_some()
+}
@@ -0,0 +1,329 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Detection of powered-off outputs.
+//!
+//! Tracks the power mode (DPMS) of every output through the
+//! `zwlr_output_power_manager_v1` protocol on a dedicated Wayland
+//! connection, and reports which outputs are powered off. The connection
+//! is dispatched from the GLib main loop, so change notifications arrive
+//! on the GTK thread.
+
+use gtk::glib;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    os::fd::{AsRawFd, RawFd},
+    rc::Rc,
+};
+use thiserror::Error;
+use wayland_client::{
+    protocol::{wl_output, wl_registry},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1::{self, ZwlrOutputPowerManagerV1},
+    zwlr_output_power_v1::{self, ZwlrOutputPowerV1},
+};
+
+/// Highest `wl_output` version used. Version 4 adds the `name` event,
+/// which carries the connector name.
+const WL_OUTPUT_VERSION: u32 = 4;
+
+/// Highest `zwlr_output_power_manager_v1` version used.
+const OUTPUT_POWER_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum OutputPowerError {
+    #[error("Failed to connect to the Wayland display: {0}")]
+    Connect(#[from] wayland_client::ConnectError),
+
+    #[error("Wayland dispatch failed: {0}")]
+    Dispatch(#[from] wayland_client::DispatchError),
+
+    #[error("Compositor does not support zwlr_output_power_manager_v1")]
+    Unsupported,
+}
+
+/// Watches the power mode of outputs.
+///
+/// Dropping the monitor stops watching and closes the connection.
+pub struct OutputPowerMonitor {
+    state: Rc<RefCell<State>>,
+    source_id: Option<glib::SourceId>,
+}
+
+impl OutputPowerMonitor {
+    /// Connects to the compositor and starts watching output power modes.
+    ///
+    /// `on_change` is invoked from the main loop whenever the set of
+    /// powered-off outputs changes.
+    pub fn new<F>(on_change: F) -> Result<Self, OutputPowerError>
+    where
+        F: Fn(&HashSet<String>) + 'static,
+    {
+        let connection = Connection::connect_to_env()?;
+        let mut queue = connection.new_event_queue::<State>();
+        let queue_handle = queue.handle();
+        connection.display().get_registry(&queue_handle, ());
+
+        let mut state = State::default();
+
+        // First roundtrip binds the globals, the second receives the
+        // initial output names and power modes.
+        queue.roundtrip(&mut state)?;
+        let Some(manager) = state.manager.clone() else {
+            return Err(OutputPowerError::Unsupported);
+        };
+        for (&name, output) in &mut state.outputs {
+            output.watch_power(&manager, name, &queue_handle);
+        }
+        queue.roundtrip(&mut state)?;
+        state.changed = false;
+
+        let state = Rc::new(RefCell::new(state));
+        let fd = connection.backend().poll_fd().as_raw_fd();
+        let source_id = Self::watch(fd, connection, queue, state.clone(), on_change);
+
+        Ok(Self {
+            state,
+            source_id: Some(source_id),
+        })
+    }
+
+    /// Returns the connector names of outputs currently powered off.
+    pub fn powered_off_outputs(&self) -> HashSet<String> {
+        self.state.borrow().powered_off_outputs()
+    }
+
+    /// Dispatches Wayland events whenever the connection becomes readable.
+    fn watch<F>(
+        fd: RawFd,
+        connection: Connection,
+        mut queue: EventQueue<State>,
+        state: Rc<RefCell<State>>,
+        on_change: F,
+    ) -> glib::SourceId
+    where
+        F: Fn(&HashSet<String>) + 'static,
+    {
+        glib::unix_fd_add_local(
+            fd,
+            glib::IOCondition::IN | glib::IOCondition::HUP | glib::IOCondition::ERR,
+            move |_, condition| {
+                if condition.intersects(glib::IOCondition::HUP | glib::IOCondition::ERR) {
+                    log::warn!("Output power monitor: Wayland connection closed");
+                    return glib::ControlFlow::Break;
+                }
+
+                if let Some(guard) = queue.prepare_read() {
+                    if let Err(err) = guard.read() {
+                        log::warn!("Output power monitor: {err}");
+                        return glib::ControlFlow::Break;
+                    }
+                }
+
+                let powered_off_outputs = {
+                    let mut state = state.borrow_mut();
+                    if let Err(err) = queue.dispatch_pending(&mut state) {
+                        log::warn!("Output power monitor: {err}");
+                        return glib::ControlFlow::Break;
+                    }
+                    let _ = connection.flush();
+
+                    if !std::mem::take(&mut state.changed) {
+                        return glib::ControlFlow::Continue;
+                    }
+                    state.powered_off_outputs()
+                };
+
+                on_change(&powered_off_outputs);
+                glib::ControlFlow::Continue
+            },
+        )
+    }
+}
+
+impl Drop for OutputPowerMonitor {
+    fn drop(&mut self) {
+        if let Some(source_id) = self.source_id.take() {
+            source_id.remove();
+        }
+    }
+}
+
+/// Bound output with its connector name and power mode.
+struct Output {
+    output: wl_output::WlOutput,
+    name: Option<String>,
+
+    /// Power mode control, or `None` before the manager is bound or
+    /// after the compositor stopped reporting the mode.
+    power: Option<ZwlrOutputPowerV1>,
+
+    powered_off: bool,
+}
+
+impl Output {
+    /// Starts receiving the power mode of the output, identified by its
+    /// registry name.
+    fn watch_power(
+        &mut self,
+        manager: &ZwlrOutputPowerManagerV1,
+        name: u32,
+        queue_handle: &QueueHandle<State>,
+    ) {
+        if self.power.is_none() {
+            self.power = Some(manager.get_output_power(&self.output, queue_handle, name));
+        }
+    }
+
+    fn release(self) {
+        if let Some(power) = self.power {
+            power.destroy();
+        }
+        if self.output.version() >= 3 {
+            self.output.release();
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    manager: Option<ZwlrOutputPowerManagerV1>,
+
+    /// Bound outputs keyed by registry name.
+    outputs: HashMap<u32, Output>,
+
+    /// Set when a change may affect the set of powered-off outputs.
+    changed: bool,
+}
+
+impl State {
+    fn powered_off_outputs(&self) -> HashSet<String> {
+        self.outputs
+            .values()
+            .filter(|output| output.powered_off)
+            .filter_map(|output| output.name.clone())
+            .collect()
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        queue_handle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => {
+                if interface == wl_output::WlOutput::interface().name {
+                    let mut output = Output {
+                        output: registry.bind(
+                            name,
+                            version.min(WL_OUTPUT_VERSION),
+                            queue_handle,
+                            name,
+                        ),
+                        name: None,
+                        power: None,
+                        powered_off: false,
+                    };
+                    // Outputs announced after startup are watched right away
+                    if let Some(manager) = &state.manager {
+                        output.watch_power(manager, name, queue_handle);
+                    }
+                    state.outputs.insert(name, output);
+                } else if interface == ZwlrOutputPowerManagerV1::interface().name {
+                    state.manager = Some(registry.bind(
+                        name,
+                        version.min(OUTPUT_POWER_VERSION),
+                        queue_handle,
+                        (),
+                    ));
+                }
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(output) = state.outputs.remove(&name) {
+                    output.release();
+                    state.changed = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, u32> for State {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        registry_name: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            if let Some(output) = state.outputs.get_mut(registry_name) {
+                output.name = Some(name);
+                state.changed = true;
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerManagerV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrOutputPowerManagerV1,
+        _: zwlr_output_power_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // The manager has no events
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        power: &ZwlrOutputPowerV1,
+        event: zwlr_output_power_v1::Event,
+        registry_name: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.get_mut(registry_name) else {
+            return;
+        };
+
+        match event {
+            zwlr_output_power_v1::Event::Mode { mode } => {
+                let powered_off = mode == WEnum::Value(zwlr_output_power_v1::Mode::Off);
+                if output.powered_off != powered_off {
+                    output.powered_off = powered_off;
+                    state.changed = true;
+                }
+            }
+            zwlr_output_power_v1::Event::Failed => {
+                // The output is gone or its mode is unavailable
+                power.destroy();
+                output.power = None;
+                if std::mem::take(&mut output.powered_off) {
+                    state.changed = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
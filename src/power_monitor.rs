@@ -0,0 +1,79 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Power source detection.
+//!
+//! Watches the UPower daemon over the system D-Bus and reports whether
+//! the machine is running on battery, so that presets can switch to a
+//! lighter power profile.
+
+use gtk::{
+    gio::{self, prelude::*},
+    glib,
+};
+use thiserror::Error;
+
+const UPOWER_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const UPOWER_INTERFACE: &str = "org.freedesktop.UPower";
+const ON_BATTERY_PROPERTY: &str = "OnBattery";
+
+#[derive(Debug, Error)]
+pub enum PowerError {
+    #[error("Failed to connect to UPower: {0}")]
+    DBus(#[from] glib::Error),
+
+    #[error("UPower daemon is not running")]
+    Unavailable,
+}
+
+/// Watches the `OnBattery` property of UPower.
+pub struct PowerMonitor {
+    proxy: gio::DBusProxy,
+}
+
+impl PowerMonitor {
+    /// Connects to UPower.
+    ///
+    /// `on_change` is invoked from the main loop with the new state
+    /// whenever the machine switches between battery and AC power.
+    pub fn new<F>(on_change: F) -> Result<Self, PowerError>
+    where
+        F: Fn(bool) + 'static,
+    {
+        let proxy = gio::DBusProxy::for_bus_sync(
+            gio::BusType::System,
+            gio::DBusProxyFlags::DO_NOT_AUTO_START,
+            None,
+            UPOWER_NAME,
+            UPOWER_PATH,
+            UPOWER_INTERFACE,
+            None::<&gio::Cancellable>,
+        )?;
+
+        if proxy.name_owner().is_none() {
+            return Err(PowerError::Unavailable);
+        }
+
+        proxy.connect_local("g-properties-changed", false, move |values| {
+            let changed_properties = values.get(1)?.get::<glib::Variant>().ok()?;
+            let on_battery = glib::VariantDict::new(Some(&changed_properties))
+                .lookup_value(ON_BATTERY_PROPERTY, Some(glib::VariantTy::BOOLEAN))?
+                .get::<bool>()?;
+            on_change(on_battery);
+            None
+        });
+
+        Ok(Self { proxy })
+    }
+
+    /// Returns `true` if the machine is currently running on battery.
+    pub fn on_battery(&self) -> bool {
+        self.proxy
+            .cached_property(ON_BATTERY_PROPERTY)
+            .and_then(|value| value.get::<bool>())
+            .unwrap_or(false)
+    }
+}
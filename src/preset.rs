@@ -12,10 +12,11 @@
 //! applying validated defaults during deserialization, and monitoring preset
 //! files for live reloading at runtime.
 
+use chrono::NaiveTime;
 use gtk::{gio, prelude::*};
 use serde::*;
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
     ffi::OsStr,
     fs,
     hash::{Hash, Hasher},
@@ -25,7 +26,27 @@ use std::{
 };
 use thiserror::Error;
 
-use crate::{app::*, *};
+use crate::{
+    app::*,
+    geometry::{Point, Size},
+    *,
+};
+
+/// Highest MSAA sample count accepted in presets.
+pub const MAX_MSAA_SAMPLES: u32 = 16;
+
+/// Highest supersampling factor accepted in presets.
+pub const MAX_SUPERSAMPLE: u32 = 4;
+
+/// Lowest nonzero frame rate accepted for `max_fps` and `target_fps`,
+/// so that the interval between frames fits in a [`Duration`].
+pub const MIN_FPS: f64 = 0.01;
+
+/// Lowest gamma accepted in presets.
+pub const MIN_GAMMA: f32 = 0.1;
+
+/// Number of seconds in a day, used to wrap times of day at midnight.
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
 
 #[derive(Debug, Error)]
 pub enum PresetError {
@@ -37,8 +58,18 @@ pub enum PresetError {
     JsonParse(#[from] serde_json::Error),
     #[error("Failed to import from JSON: {0}")]
     Import(String),
+    #[error("Invalid preset bundle: {0}")]
+    Bundle(String),
+    #[error("Failed to download {url}: {reason}")]
+    Download { url: String, reason: String },
     #[error("No .toml presets found in directory")]
     NoPresets,
+    #[error("No presets in directory match the tag filters")]
+    NoMatchingPresets,
+    #[error("Failed to read shader file {}: {source}", .path.display())]
+    ShaderFile { path: PathBuf, source: io::Error },
+    #[error("Shader looks too heavy to run as a wallpaper ({0})")]
+    TooHeavy(String),
 }
 
 #[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -55,6 +86,16 @@ pub enum InputType {
     Keyboard,
     Webcam,
     Microphone,
+    /// Raw RGBA frames pushed by an external program.
+    Stream,
+    /// Pango markup rasterized by the system, with strftime patterns
+    /// expanded with the local time.
+    Text,
+    /// Album art of the track playing in an MPRIS media player.
+    AlbumArt,
+    /// Output captured periodically, if allowed by the configuration
+    /// file.
+    Screen,
 }
 
 /// Specifies how texture coordinates outside the 0-1 range are handled.
@@ -81,6 +122,43 @@ pub enum FilterMode {
     Mipmap,
 }
 
+/// Specifies the precision of the floating-point framebuffers of
+/// buffer passes.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferPrecision {
+    #[default]
+    /// Half-precision (16-bit) floats, as in ShaderToy.
+    F16,
+    /// Single-precision (32-bit) floats.
+    F32,
+}
+
+/// Specifies the effect revealing a preset over the previous one when
+/// switching presets.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    #[default]
+    /// Blends the presets.
+    Crossfade,
+    /// Sweeps the new preset in from the right edge.
+    WipeLeft,
+    /// Sweeps the new preset in from the left edge.
+    WipeRight,
+    /// Sweeps the new preset in from the bottom edge.
+    WipeUp,
+    /// Sweeps the new preset in from the top edge.
+    WipeDown,
+    /// Reveals the new preset in randomly ordered cells.
+    Dissolve,
+    /// Reveals the new preset in a circle growing from the center.
+    CircularReveal,
+    /// Path to a transition in the format of glsl-transitions.
+    #[serde(untagged)]
+    Custom(PathBuf),
+}
+
 /// Specifies how the virtual screen bounds are calculated.
 #[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -94,6 +172,31 @@ pub enum ScreenBoundsPolicy {
     Cloned,
 }
 
+/// Specifies how the frame shared by cloned monitors is fitted to
+/// monitors of a different aspect ratio.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloneFit {
+    #[default]
+    /// Renders each monitor at its own size.
+    Stretch,
+    /// Scales the shared frame to fit inside the monitor (letterboxing).
+    Contain,
+    /// Scales the shared frame to fill the monitor (cropping).
+    Cover,
+}
+
+/// Specifies the order in which buffer passes are rendered.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassOrder {
+    #[default]
+    /// Renders passes in the fixed order of ShaderToy.
+    Shadertoy,
+    /// Renders each pass after the passes it samples.
+    Dependencies,
+}
+
 /// Specifies how the framebuffer is laid out on screen.
 #[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -107,6 +210,106 @@ pub enum LayoutMode {
     Repeat,
     /// Tiles the framebuffer using mirror-repeat wrapping.
     MirroredRepeat,
+    /// Scales the framebuffer to fill a sub-area of each monitor
+    /// (see [`Region`]).
+    Region,
+}
+
+/// Sub-area of each monitor rendered with [`LayoutMode::Region`].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Region {
+    /// Distances from the top, right, bottom and left monitor edges,
+    /// in logical pixels.
+    #[serde(default)]
+    pub margins: [u32; 4],
+    /// RGB color of the area outside the region.
+    #[serde(default, deserialize_with = "validators::clamp_rgb")]
+    pub color: [f32; 3],
+}
+
+impl Region {
+    /// Returns the region covering `rect`, given as the left, top, width
+    /// and height fractions of an area of the given logical size.
+    pub fn from_rect(rect: [f32; 4], size: Size) -> Self {
+        let [x, y, width, height] = rect;
+        let (area_width, area_height) = (size.width() as f32, size.height() as f32);
+        let margin = |fraction: f32, length: f32| (fraction * length).round().max(0.0) as u32;
+        Self {
+            margins: [
+                margin(y, area_height),
+                margin(1.0 - x - width, area_width),
+                margin(1.0 - y - height, area_height),
+                margin(x, area_width),
+            ],
+            color: [0.0; 3],
+        }
+    }
+
+    /// Returns the size of the region within an area of the given size.
+    ///
+    /// The region is at least one pixel wide and tall.
+    pub fn size_within(&self, size: Size, scale: f32) -> Size {
+        let [top, right, bottom, left] = self.margins.map(|margin| margin as f32 * scale);
+        Size::new(
+            (size.width() as f32 - left - right).max(1.0) as u32,
+            (size.height() as f32 - top - bottom).max(1.0) as u32,
+        )
+    }
+
+    /// Returns the OpenGL-space origin (bottom-left) of the region.
+    pub fn origin(&self, scale: f32) -> Point {
+        let [_, _, bottom, left] = self.margins;
+        Point::new(
+            (left as f32 * scale).round() as i32,
+            (bottom as f32 * scale).round() as i32,
+        )
+    }
+}
+
+/// Specifies which input devices the wallpaper captures.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputMode {
+    /// Captures no input; events reach the layers below the wallpaper.
+    None,
+    /// Captures pointer events only.
+    Mouse,
+    #[default]
+    /// Captures pointer and keyboard events.
+    MouseKeyboard,
+}
+
+impl InputMode {
+    /// Returns `true` if pointer events are captured.
+    pub fn captures_mouse(self) -> bool {
+        self != InputMode::None
+    }
+
+    /// Returns `true` if keyboard events are captured.
+    pub fn captures_keyboard(self) -> bool {
+        self == InputMode::MouseKeyboard
+    }
+}
+
+/// Layer Shell layer of the render windows.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowLayer {
+    #[default]
+    /// Below all windows, as a wallpaper.
+    Background,
+    /// Above regular windows, but below fullscreen windows.
+    Top,
+    /// Above all windows, including fullscreen windows.
+    Overlay,
+}
+
+impl WindowLayer {
+    /// Returns `true` if the render windows are drawn over other
+    /// windows, and must therefore let input through.
+    pub fn is_above_windows(self) -> bool {
+        self != WindowLayer::Background
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -128,11 +331,27 @@ pub struct Input {
     pub vflip: bool,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Pass {
     /// Shader source code.
     #[serde(default)]
     pub shader: String,
+    /// GLSL file providing the shader source code, relative to the preset
+    /// file. When set, its contents replace `shader` and are reloaded
+    /// whenever the file changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shader_file: Option<PathBuf>,
+    /// Replaces the `buffer_precision` of the preset for this pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_precision: Option<BufferPrecision>,
+    /// Resolution of the framebuffers of this buffer pass relative to
+    /// the other passes, e.g., `0.25` for a quarter of their resolution.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "validators::clamp_optional_resolution_scale"
+    )]
+    pub scale: Option<f32>,
     /// iChannel0 input.
     #[serde(default)]
     pub input_0: Option<Input>,
@@ -147,7 +366,308 @@ pub struct Pass {
     pub input_3: Option<Input>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+/// Buffer pass defined in the `[[pass]]` array.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NamedPass {
+    /// Name by which inputs of other passes reference the pass output.
+    pub name: String,
+    #[serde(flatten)]
+    pub pass: Pass,
+}
+
+/// Value of a custom uniform defined in the `[uniforms]` table.
+///
+/// Numbers map to `float`, arrays of 2 to 4 numbers to `vec2`–`vec4`,
+/// booleans to `bool`, and `"#rrggbb"`/`"#rrggbbaa"` colors to
+/// `vec3`/`vec4` with components in `[0, 1]`.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "UniformSpec", into = "UniformSpec")]
+pub enum UniformValue {
+    Bool(bool),
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+}
+
+impl UniformValue {
+    /// Returns the GLSL type of the uniform.
+    pub fn glsl_type(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::Float(_) => "float",
+            Self::Vec2(_) => "vec2",
+            Self::Vec3(_) => "vec3",
+            Self::Vec4(_) => "vec4",
+        }
+    }
+}
+
+/// Representation of [`UniformValue`] in preset files.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum UniformSpec {
+    Bool(bool),
+    Float(f32),
+    Vector(Vec<f32>),
+    Color(String),
+}
+
+impl TryFrom<UniformSpec> for UniformValue {
+    type Error = String;
+
+    fn try_from(spec: UniformSpec) -> Result<Self, Self::Error> {
+        match spec {
+            UniformSpec::Bool(value) => Ok(Self::Bool(value)),
+            UniformSpec::Float(value) => Ok(Self::Float(value)),
+            UniformSpec::Vector(values) => match values[..] {
+                [x, y] => Ok(Self::Vec2([x, y])),
+                [x, y, z] => Ok(Self::Vec3([x, y, z])),
+                [x, y, z, w] => Ok(Self::Vec4([x, y, z, w])),
+                _ => Err(format!(
+                    "uniform vectors must have 2 to 4 components, found {}",
+                    values.len()
+                )),
+            },
+            UniformSpec::Color(color) => {
+                let invalid = || format!("invalid color '{color}', expected \"#rrggbb[aa]\"");
+                let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+                if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+                    return Err(invalid());
+                }
+                let components = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map(|c| c as f32 / 255.0))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid())?;
+                match components[..] {
+                    [r, g, b] => Ok(Self::Vec3([r, g, b])),
+                    [r, g, b, a] => Ok(Self::Vec4([r, g, b, a])),
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+}
+
+impl From<UniformValue> for UniformSpec {
+    fn from(value: UniformValue) -> Self {
+        match value {
+            UniformValue::Bool(value) => Self::Bool(value),
+            UniformValue::Float(value) => Self::Float(value),
+            UniformValue::Vec2(values) => Self::Vector(values.to_vec()),
+            UniformValue::Vec3(values) => Self::Vector(values.to_vec()),
+            UniformValue::Vec4(values) => Self::Vector(values.to_vec()),
+        }
+    }
+}
+
+/// Dimming of presented frames during a daily time window.
+///
+/// Brightness ramps down to `brightness` over `transition` from `start`,
+/// and back up over `transition` from `end`. The window may span midnight.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NightMode {
+    /// Local time of day at which dimming begins.
+    #[serde(with = "time_of_day")]
+    pub start: NaiveTime,
+    /// Local time of day at which brightness is restored.
+    #[serde(with = "time_of_day")]
+    pub end: NaiveTime,
+    /// Brightness multiplier at night.
+    #[serde(
+        default = "defaults::night_brightness",
+        deserialize_with = "validators::clamp_unit"
+    )]
+    pub brightness: f32,
+    /// Duration of the ramps between day and night brightness.
+    #[serde(default, with = "humantime_serde")]
+    pub transition: Duration,
+}
+
+impl NightMode {
+    /// Returns the brightness multiplier at the given local time of day.
+    pub fn brightness_at(&self, time: NaiveTime) -> f32 {
+        let seconds_between = |from: NaiveTime, to: NaiveTime| {
+            to.signed_duration_since(from)
+                .num_seconds()
+                .rem_euclid(SECONDS_PER_DAY)
+        };
+        let progress = |elapsed: i64| {
+            if self.transition.is_zero() {
+                1.0
+            } else {
+                (elapsed as f32 / self.transition.as_secs_f32()).min(1.0)
+            }
+        };
+
+        let since_start = seconds_between(self.start, time);
+        let dimming = if since_start < seconds_between(self.start, self.end) {
+            progress(since_start)
+        } else {
+            1.0 - progress(seconds_between(self.end, time))
+        };
+
+        1.0 + (self.brightness - 1.0) * dimming
+    }
+}
+
+/// Settings overridden while a power profile is active.
+///
+/// Unset fields keep the value of the preset.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PowerProfile {
+    /// Replaces `resolution_scale`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "validators::clamp_optional_resolution_scale"
+    )]
+    pub resolution_scale: Option<f32>,
+    /// Replaces `interval_between_frames`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "humantime_serde"
+    )]
+    pub interval_between_frames: Option<Duration>,
+    /// Replaces `max_fps`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "validators::clamp_optional_max_fps"
+    )]
+    pub max_fps: Option<f64>,
+}
+
+/// Rectangle of each monitor running a preset of its own, defined in the
+/// `[[tile]]` array and drawn over the passes of the preset.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tile {
+    /// Left, top, width and height of the tile, as fractions of the
+    /// monitor size.
+    #[serde(deserialize_with = "validators::clamp_rect")]
+    pub rect: [f32; 4],
+    /// TOML preset or ShaderToy JSON export rendered in the tile,
+    /// relative to the preset file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<PathBuf>,
+    /// Shader of the "Image" pass rendered in the tile, if no `preset`
+    /// is given.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub shader: String,
+    /// Preset rendered in the tile, loaded along with the preset.
+    #[serde(skip)]
+    pub loaded: Option<Box<Preset>>,
+}
+
+impl Tile {
+    /// Returns the name of a pass of the tile at `index`, e.g.,
+    /// "Tile 1/Image", as reported by [`Preset::shader_files`].
+    pub fn pass_name(index: usize, name: &str) -> String {
+        format!("Tile {}/{name}", index + 1)
+    }
+
+    /// Splits a name returned by [`Tile::pass_name`] into the index of
+    /// the tile and the name of its pass.
+    pub fn split_pass_name(name: &str) -> Option<(usize, &str)> {
+        let (tile, name) = name.strip_prefix("Tile ")?.split_once('/')?;
+        let index = tile.parse::<usize>().ok()?.checked_sub(1)?;
+        Some((index, name))
+    }
+}
+
+/// Corner of the screen in which the information overlay is shown.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayCorner {
+    TopLeft,
+    TopRight,
+    #[default]
+    BottomLeft,
+    BottomRight,
+}
+
+/// Placement, appearance and contents of the information overlay.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OverlayStyle {
+    /// Corner of the screen in which the overlay is shown.
+    pub corner: OverlayCorner,
+    /// Horizontal distance to the edge of the screen, in logical pixels.
+    pub margin_x: i32,
+    /// Vertical distance to the edge of the screen, in logical pixels.
+    pub margin_y: i32,
+    /// Font family (empty = default font).
+    pub font: String,
+    /// Font size of the first line, in points.
+    pub title_size: f32,
+    /// Font size of the other lines, in points.
+    pub text_size: f32,
+    /// Text color, as a name or `"#rrggbb"`.
+    pub color: String,
+    /// Drop shadow color, as a name or `"#rrggbb"`.
+    pub shadow_color: String,
+    /// Time the overlay is shown before fading out (zero = always shown).
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+    /// Text of the overlay. `{name}`, `{author}`, `{id}`, `{description}`
+    /// and `{tags}` are replaced by the preset fields, and lines whose
+    /// fields are all empty are left out.
+    pub template: String,
+}
+
+impl Default for OverlayStyle {
+    fn default() -> Self {
+        Self {
+            corner: OverlayCorner::default(),
+            margin_x: 25,
+            margin_y: 25,
+            font: String::new(),
+            title_size: 18.0,
+            text_size: 14.0,
+            color: "white".into(),
+            shadow_color: "black".into(),
+            duration: Duration::from_secs(10),
+            template: "{name}\nby {author}".into(),
+        }
+    }
+}
+
+impl OverlayStyle {
+    /// Returns the lines of the overlay text for `preset`.
+    pub fn lines(&self, preset: &Preset) -> Vec<String> {
+        let tags = preset.tags.join(", ");
+        let fields = [
+            ("{name}", preset.name.as_str()),
+            ("{author}", preset.username.as_str()),
+            ("{id}", preset.id.as_str()),
+            ("{description}", preset.description.as_str()),
+            ("{tags}", tags.as_str()),
+        ];
+
+        self.template
+            .lines()
+            .filter_map(|line| {
+                let mut text = line.to_string();
+                let mut has_fields = false;
+                let mut has_values = false;
+                for (placeholder, value) in fields {
+                    if text.contains(placeholder) {
+                        has_fields = true;
+                        has_values |= !value.is_empty();
+                        text = text.replace(placeholder, value);
+                    }
+                }
+                let shown = !text.trim().is_empty() && (has_values || !has_fields);
+                shown.then_some(text)
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Preset {
     /// Shader ID.
     #[serde(default)]
@@ -161,6 +681,15 @@ pub struct Preset {
     // Shader description.
     #[serde(default)]
     pub description: String,
+    /// Labels used to filter random preset selection (e.g., "calm").
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Relative likelihood of being picked at random (`0.0` = never).
+    #[serde(
+        default = "defaults::weight",
+        deserialize_with = "validators::clamp_weight"
+    )]
+    pub weight: f64,
     /// Scaling factor for the frame resolution.
     #[serde(
         default = "defaults::resolution_scale",
@@ -179,22 +708,155 @@ pub struct Preset {
     /// Minimum time between frames.
     #[serde(default, with = "humantime_serde")]
     pub interval_between_frames: Duration,
+    /// Frame rate cap for continuous rendering (`0.0` = uncapped).
+    #[serde(default, deserialize_with = "validators::clamp_max_fps")]
+    pub max_fps: f64,
+    /// Frame rate held by lowering the resolution scale when rendering
+    /// is too slow (`0.0` = disabled).
+    #[serde(default, deserialize_with = "validators::clamp_max_fps")]
+    pub target_fps: f64,
+    /// Lowest resolution scale used to hold `target_fps`.
+    #[serde(
+        default = "defaults::min_resolution_scale",
+        deserialize_with = "validators::clamp_resolution_scale"
+    )]
+    pub min_resolution_scale: f32,
+    /// Constant `iTimeDelta` used instead of the wall clock (`0s` = disabled).
+    #[serde(default, with = "humantime_serde")]
+    pub fixed_timestep: Duration,
+    /// Restarts `iTime` from `time_offset` after the system resumes
+    /// from suspend.
+    #[serde(default)]
+    pub reset_time_on_resume: bool,
+    /// Copies the contents of buffer passes into the new buffers when
+    /// the renderer is recreated, e.g., after a resize or preset reload.
+    #[serde(default)]
+    pub preserve_buffers: bool,
+    /// Order in which buffer passes are rendered.
+    #[serde(default)]
+    pub pass_order: PassOrder,
     /// How the bounds of the virtual screen are calculated.
     #[serde(default)]
     pub screen_bounds_policy: ScreenBoundsPolicy,
     /// Monitor selection using DRM connector names.
     #[serde(default = "defaults::monitor_selection")]
     pub monitor_selection: Vec<String>,
+    /// How the shared frame is fitted to each monitor in clone mode.
+    #[serde(default)]
+    pub clone_fit: CloneFit,
     /// How the framebuffer is laid out on the screen.
     #[serde(default)]
     pub layout_mode: LayoutMode,
+    /// Rendered area of each monitor when `layout_mode` is `region`.
+    #[serde(default)]
+    pub region: Region,
     /// Filtering mode when scaling the framebuffer.
     #[serde(default)]
     pub filter_mode: FilterMode,
+    /// Whether the alpha of the "Image" pass is kept, showing what is
+    /// below the wallpaper through transparent pixels.
+    #[serde(default)]
+    pub transparent: bool,
+    /// Input devices captured by the wallpaper.
+    #[serde(default)]
+    pub input_mode: InputMode,
+    /// Layer of the render windows.
+    #[serde(default)]
+    pub layer: WindowLayer,
+    /// Precision of the framebuffers of buffer passes.
+    #[serde(default)]
+    pub buffer_precision: BufferPrecision,
+    /// Number of MSAA samples of the "Image" pass (`0` = disabled).
+    #[serde(default, deserialize_with = "validators::msaa_samples")]
+    pub msaa_samples: u32,
+    /// Brightness multiplier applied when presenting frames.
+    #[serde(
+        default = "defaults::color_factor",
+        deserialize_with = "validators::clamp_color_factor"
+    )]
+    pub brightness: f32,
+    /// Contrast around mid-gray applied when presenting frames.
+    #[serde(
+        default = "defaults::color_factor",
+        deserialize_with = "validators::clamp_color_factor"
+    )]
+    pub contrast: f32,
+    /// Saturation applied when presenting frames (`0.0` = grayscale).
+    #[serde(
+        default = "defaults::color_factor",
+        deserialize_with = "validators::clamp_color_factor"
+    )]
+    pub saturation: f32,
+    /// Gamma correction applied when presenting frames.
+    #[serde(
+        default = "defaults::color_factor",
+        deserialize_with = "validators::clamp_gamma"
+    )]
+    pub gamma: f32,
+    /// Factor by which passes are rendered larger than displayed
+    /// (`1` = no supersampling).
+    #[serde(
+        default = "defaults::supersample",
+        deserialize_with = "validators::clamp_supersample"
+    )]
+    pub supersample: u32,
     /// Controls smooth frame transitions through cross fading.
     /// (0.0 = no overlap, 1.0 = always transitioning)
     #[serde(default, deserialize_with = "validators::clamp_crossfade")]
     pub crossfade_overlap_ratio: f64,
+    /// Duration of the crossfade from the previous preset when switching
+    /// to this one (zero = no transition).
+    #[serde(default, with = "humantime_serde")]
+    pub transition_duration: Duration,
+    /// Effect revealing this preset over the previous one.
+    #[serde(default)]
+    pub transition: Transition,
+    /// Plays the "Sound" pass, if any.
+    #[serde(default)]
+    pub enable_sound: bool,
+    /// Playback volume of the "Sound" pass.
+    /// (0.0 = muted, 1.0 = full volume)
+    #[serde(
+        default = "defaults::sound_volume",
+        deserialize_with = "validators::clamp_sound_volume"
+    )]
+    pub sound_volume: f32,
+    /// Number of bands of `iAudioBands`, from 1 to 64.
+    #[serde(
+        default = "defaults::audio_bands",
+        deserialize_with = "validators::clamp_audio_bands"
+    )]
+    pub audio_bands: usize,
+    /// Fraction of the previous level of each audio band kept at each
+    /// update. (0.0 = no smoothing)
+    #[serde(
+        default = "defaults::audio_smoothing",
+        deserialize_with = "validators::clamp_unit"
+    )]
+    pub audio_smoothing: f32,
+    /// Overrides applied while running on battery power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery: Option<PowerProfile>,
+    /// Dimming schedule applied when presenting frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub night_mode: Option<NightMode>,
+    /// Image shown while shaders compile, and in place of an "Image"
+    /// pass that fails to compile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_image: Option<PathBuf>,
+    /// Placement, appearance and contents of the information overlay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay: Option<OverlayStyle>,
+    /// Rectangles of each monitor running presets of their own.
+    #[serde(default, rename = "tile", skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<Tile>,
+    /// Custom uniforms declared in every pass, by name.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "validators::uniforms"
+    )]
+    pub uniforms: BTreeMap<String, UniformValue>,
     /// "Common" pass (shader-only).
     #[serde(default)]
     pub common: Option<Pass>,
@@ -210,19 +872,278 @@ pub struct Preset {
     /// "Buffer D" render pass.
     #[serde(default)]
     pub buffer_d: Option<Pass>,
+    /// Additional buffer passes, rendered in order after "Buffer D".
+    #[serde(
+        default,
+        rename = "pass",
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "validators::named_passes"
+    )]
+    pub extra_passes: Vec<NamedPass>,
     /// "Cube A" render pass.
-    #[serde(default)]
+    #[serde(default, deserialize_with = "validators::unscaled_cube_a")]
     pub cube_a: Option<Pass>,
     /// "Image" render pass.
-    #[serde(default = "defaults::image")]
+    #[serde(
+        default = "defaults::image",
+        deserialize_with = "validators::unscaled_image"
+    )]
     pub image: Pass,
+    /// "Sound" render pass.
+    #[serde(default, deserialize_with = "validators::unscaled_sound")]
+    pub sound: Option<Pass>,
+    /// Directory of the preset file, used to resolve relative input paths.
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
 }
 
 impl Preset {
+    /// Color adjustment that leaves frames unchanged.
+    pub const NEUTRAL_COLOR_ADJUSTMENT: [f32; 4] = [1.0; 4];
+
+    /// Returns brightness, contrast, saturation and gamma, in this order.
+    pub fn color_adjustment(&self) -> [f32; 4] {
+        [self.brightness, self.contrast, self.saturation, self.gamma]
+    }
+
     /// Creates a Preset from a TOML file.
     pub fn from_toml_file(path: &Path) -> Result<Self, PresetError> {
+        let mut preset = Self::from_toml_file_without_tiles(path)?;
+        preset.load_tiles()?;
+        Ok(preset)
+    }
+
+    /// Creates a Preset from a TOML file, leaving its tiles unloaded.
+    fn from_toml_file_without_tiles(path: &Path) -> Result<Self, PresetError> {
         let content = fs::read_to_string(path)?;
-        Ok(toml::from_str(&content)?)
+        let mut preset: Self = toml::from_str(&content)?;
+        preset.base_dir = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .parent()
+            .map(Path::to_path_buf);
+        preset.load_shader_files()?;
+        Ok(preset)
+    }
+
+    /// Loads the preset of every tile.
+    ///
+    /// Tiles of tile presets are ignored.
+    fn load_tiles(&mut self) -> Result<(), PresetError> {
+        for tile in &mut self.tiles {
+            let mut preset = match &tile.preset {
+                Some(path) => {
+                    let path = match &self.base_dir {
+                        Some(base_dir) if path.is_relative() => base_dir.join(path),
+                        _ => path.clone(),
+                    };
+                    if path.extension() == Some(OsStr::new("json")) {
+                        Self::from_json_file(&path)?
+                    } else {
+                        Self::from_toml_file_without_tiles(&path)?
+                    }
+                }
+                None => {
+                    let mut preset = Self::with_serde_defaults();
+                    preset.image.shader = tile.shader.clone();
+                    preset.base_dir = self.base_dir.clone();
+                    preset
+                }
+            };
+            preset.tiles.clear();
+            tile.loaded = Some(Box::new(preset));
+        }
+        Ok(())
+    }
+
+    /// Loads a preset from a TOML file or a `.sbgz` bundle.
+    pub fn from_file(path: &Path) -> Result<Self, PresetError> {
+        if crate::bundle::is_bundle(path) {
+            crate::bundle::load(path)
+        } else {
+            Self::from_toml_file(path)
+        }
+    }
+
+    /// Replaces the shader of every pass having a `shader_file` with the
+    /// contents of that file.
+    fn load_shader_files(&mut self) -> Result<(), PresetError> {
+        for (name, path) in self.shader_files() {
+            let shader = fs::read_to_string(&path)
+                .map_err(|source| PresetError::ShaderFile { path, source })?;
+            if let Some(pass) = self.pass_mut(&name) {
+                pass.shader = shader;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the passes defined in the preset along with their names
+    /// ("Common", "Buffer A", ..., "Image", "Sound").
+    pub fn passes(&self) -> impl Iterator<Item = (&str, &Pass)> {
+        self.common
+            .as_ref()
+            .map(|pass| ("Common", pass))
+            .into_iter()
+            .chain(self.render_passes())
+            .chain(self.sound.as_ref().map(|pass| ("Sound", pass)))
+    }
+
+    /// Returns the passes rendered every frame, in rendering order:
+    /// "Buffer A" to "Buffer D", the `[[pass]]` array, "Cube A" and
+    /// "Image".
+    pub fn render_passes(&self) -> impl Iterator<Item = (&str, &Pass)> {
+        let buffers = [
+            ("Buffer A", self.buffer_a.as_ref()),
+            ("Buffer B", self.buffer_b.as_ref()),
+            ("Buffer C", self.buffer_c.as_ref()),
+            ("Buffer D", self.buffer_d.as_ref()),
+        ];
+        let last = [
+            ("Cube A", self.cube_a.as_ref()),
+            ("Image", Some(&self.image)),
+        ];
+
+        buffers
+            .into_iter()
+            .chain(
+                self.extra_passes
+                    .iter()
+                    .map(|named| (named.name.as_str(), Some(&named.pass))),
+            )
+            .chain(last)
+            .filter_map(|(name, pass)| pass.map(|pass| (name, pass)))
+    }
+
+    /// Returns `true` if any pass, including the passes of tiles,
+    /// references the given identifier, e.g. an optional uniform such as
+    /// `iSystemStats`.
+    pub fn references(&self, identifier: &str) -> bool {
+        self.passes()
+            .any(|(_, pass)| pass.shader.contains(identifier))
+            || self
+                .tile_presets()
+                .any(|preset| preset.references(identifier))
+    }
+
+    /// Returns `true` if any pass, including the passes of tiles, has an
+    /// input of the given type.
+    pub fn uses_input_type(&self, input_type: InputType) -> bool {
+        self.passes().any(|(_, pass)| {
+            [&pass.input_0, &pass.input_1, &pass.input_2, &pass.input_3]
+                .into_iter()
+                .flatten()
+                .any(|input| input._type == input_type)
+        }) || self
+            .tile_presets()
+            .any(|preset| preset.uses_input_type(input_type))
+    }
+
+    /// Returns the loaded presets of the tiles.
+    fn tile_presets(&self) -> impl Iterator<Item = &Preset> {
+        self.tiles.iter().filter_map(|tile| tile.loaded.as_deref())
+    }
+
+    /// Returns the pass with the given name, if defined. Passes of tiles
+    /// are named as by [`Tile::pass_name`].
+    pub fn pass_mut(&mut self, name: &str) -> Option<&mut Pass> {
+        if let Some((index, name)) = Tile::split_pass_name(name) {
+            return self.tiles.get_mut(index)?.loaded.as_mut()?.pass_mut(name);
+        }
+
+        match name {
+            "Common" => self.common.as_mut(),
+            "Buffer A" => self.buffer_a.as_mut(),
+            "Buffer B" => self.buffer_b.as_mut(),
+            "Buffer C" => self.buffer_c.as_mut(),
+            "Buffer D" => self.buffer_d.as_mut(),
+            "Cube A" => self.cube_a.as_mut(),
+            "Image" => Some(&mut self.image),
+            "Sound" => self.sound.as_mut(),
+            _ => self
+                .extra_passes
+                .iter_mut()
+                .find(|named| named.name == name)
+                .map(|named| &mut named.pass),
+        }
+    }
+
+    /// Compares the preset with an updated version of itself.
+    ///
+    /// Returns the name and new code of every pass whose shader changed,
+    /// or `None` if any other setting changed as well, in which case the
+    /// updated preset cannot be applied by recompiling shaders alone.
+    pub fn shader_changes(&self, updated: &Preset) -> Option<Vec<(String, String)>> {
+        let without_shaders = |preset: &Preset| {
+            let mut preset = preset.clone();
+            let names: Vec<_> = preset.passes().map(|(name, _)| name.to_string()).collect();
+            for name in names {
+                if let Some(pass) = preset.pass_mut(&name) {
+                    pass.shader.clear();
+                }
+            }
+            preset
+        };
+
+        if without_shaders(self) != without_shaders(updated) {
+            return None;
+        }
+
+        Some(
+            self.passes()
+                .zip(updated.passes())
+                .filter(|((_, pass), (_, updated_pass))| pass.shader != updated_pass.shader)
+                .map(|(_, (name, updated_pass))| (name.to_string(), updated_pass.shader.clone()))
+                .collect(),
+        )
+    }
+
+    /// Returns the name and resolved path of every external shader file,
+    /// including those of tiles, named as by [`Tile::pass_name`].
+    ///
+    /// Relative paths are resolved against the directory of the preset.
+    pub fn shader_files(&self) -> Vec<(String, PathBuf)> {
+        let tile_files = self.tiles.iter().enumerate().flat_map(|(index, tile)| {
+            tile.loaded
+                .iter()
+                .flat_map(|preset| preset.shader_files())
+                .map(move |(name, path)| (Tile::pass_name(index, &name), path))
+        });
+
+        self.passes()
+            .filter_map(|(name, pass)| {
+                let path = pass.shader_file.as_ref()?;
+                let path = match &self.base_dir {
+                    Some(base_dir) if path.is_relative() => base_dir.join(path),
+                    _ => path.clone(),
+                };
+                Some((name.to_string(), path))
+            })
+            .chain(tile_files)
+            .collect()
+    }
+
+    /// Returns the resolved path of the fallback image, if any.
+    ///
+    /// A relative path is resolved against the directory of the preset.
+    pub fn fallback_image_path(&self) -> Option<PathBuf> {
+        let path = self.fallback_image.as_ref()?;
+        Some(match &self.base_dir {
+            Some(base_dir) if path.is_relative() => base_dir.join(path),
+            _ => path.clone(),
+        })
+    }
+
+    /// Returns the resolved path of a custom transition, if any.
+    ///
+    /// A relative path is resolved against the directory of the preset.
+    pub fn transition_path(&self) -> Option<PathBuf> {
+        let Transition::Custom(path) = &self.transition else {
+            return None;
+        };
+        Some(match &self.base_dir {
+            Some(base_dir) if path.is_relative() => base_dir.join(path),
+            _ => path.clone(),
+        })
     }
 
     /// Creates a Preset from a ShaderToy JSON export file.
@@ -234,6 +1155,54 @@ impl Preset {
     pub fn with_serde_defaults() -> Self {
         toml::from_str("").expect("Failed to create default preset")
     }
+
+    /// Returns the style of the information overlay.
+    ///
+    /// Presets without an overlay style use the one of the configuration
+    /// file, if any.
+    pub fn overlay_style(&self) -> OverlayStyle {
+        self.overlay
+            .as_ref()
+            .or(crate::config::config().overlay.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the monitor selection in effect.
+    ///
+    /// Presets selecting all monitors, as by default, use the selection
+    /// of the configuration file, if any.
+    pub fn selected_monitors(&self) -> &[String] {
+        match &crate::config::config().monitor_selection {
+            Some(selection) if self.monitor_selection == defaults::monitor_selection() => selection,
+            _ => &self.monitor_selection,
+        }
+    }
+
+    /// Returns a copy of the preset with the battery profile applied
+    /// when `on_battery` is `true`.
+    ///
+    /// Presets without a battery profile use the one of the
+    /// configuration file, if any.
+    pub fn with_power_profile(&self, on_battery: bool) -> Self {
+        let mut preset = self.clone();
+        let profile = self
+            .battery
+            .as_ref()
+            .or(crate::config::config().battery.as_ref());
+        if let Some(profile) = profile.filter(|_| on_battery) {
+            if let Some(resolution_scale) = profile.resolution_scale {
+                preset.resolution_scale = resolution_scale;
+            }
+            if let Some(interval_between_frames) = profile.interval_between_frames {
+                preset.interval_between_frames = interval_between_frames;
+            }
+            if let Some(max_fps) = profile.max_fps {
+                preset.max_fps = max_fps;
+            }
+        }
+        preset
+    }
 }
 
 /// Default values for preset fields.
@@ -245,11 +1214,51 @@ pub mod defaults {
         1.0
     }
 
+    /// Default lowest resolution scale of dynamic resolution scaling.
+    pub fn min_resolution_scale() -> f32 {
+        0.25
+    }
+
+    /// Default brightness, contrast, saturation and gamma (`1.0` = unchanged).
+    pub fn color_factor() -> f32 {
+        1.0
+    }
+
+    /// Default brightness multiplier of night mode.
+    pub fn night_brightness() -> f32 {
+        0.4
+    }
+
+    /// Default supersampling factor (`1` = native framebuffer resolution).
+    pub fn supersample() -> u32 {
+        1
+    }
+
     /// Default time progression multiplier (`1.0` = real-time speed).
     pub fn time_scale() -> f64 {
         1.0
     }
 
+    /// Default likelihood of random selection.
+    pub fn weight() -> f64 {
+        1.0
+    }
+
+    /// Default playback volume of the "Sound" pass (`1.0` = full volume).
+    pub fn sound_volume() -> f32 {
+        1.0
+    }
+
+    /// Default number of bands of `iAudioBands`.
+    pub fn audio_bands() -> usize {
+        crate::audio_spectrum::MAX_AUDIO_BANDS
+    }
+
+    /// Default smoothing of the audio bands.
+    pub fn audio_smoothing() -> f32 {
+        0.7
+    }
+
     /// Default monitor selection ( `*` = all available monitors).
     pub fn monitor_selection() -> Vec<String> {
         vec!["*".into()]
@@ -261,6 +1270,9 @@ pub mod defaults {
     pub fn image() -> Pass {
         Pass {
             shader: default_image_shader(),
+            shader_file: None,
+            buffer_precision: None,
+            scale: None,
             input_0: None,
             input_1: None,
             input_2: None,
@@ -285,6 +1297,28 @@ void mainImage(out vec4 fragColor, in vec2 fragCoord)
     }
 }
 
+/// (De)serialization of times of day as `"HH:MM"` or `"HH:MM:SS"`.
+mod time_of_day {
+    use super::*;
+
+    pub fn serialize<S>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&time.format("%H:%M:%S").to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        NaiveTime::parse_from_str(&value, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(&value, "%H:%M:%S"))
+            .map_err(|err| de::Error::custom(format!("invalid time of day '{value}': {err}")))
+    }
+}
+
 /// Validation functions applied during deserialization.
 ///
 /// Invalid values are clamped instead of producing hard errors.
@@ -300,6 +1334,124 @@ mod validators {
         Ok(value.max(0.0))
     }
 
+    /// Ensures an optional `resolution_scale` override is non-negative.
+    pub fn clamp_optional_resolution_scale<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<f32>::deserialize(deserializer)?;
+        Ok(value.map(|value| value.max(0.0)))
+    }
+
+    /// Drops the `scale` of a pass that is not a buffer pass, which
+    /// renders at the resolution of the screen.
+    fn drop_scale(pass: &mut Pass, key: &str) {
+        if pass.scale.take().is_some() {
+            log::warn!("Ignoring 'scale' of '{key}': only buffer passes can be scaled");
+        }
+    }
+
+    /// Drops the `scale` of the "Image" pass.
+    pub fn unscaled_image<'de, D>(deserializer: D) -> Result<Pass, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut pass = Pass::deserialize(deserializer)?;
+        drop_scale(&mut pass, "image");
+        Ok(pass)
+    }
+
+    /// Drops the `scale` of the "Cube A" pass, whose faces have a fixed
+    /// size.
+    pub fn unscaled_cube_a<'de, D>(deserializer: D) -> Result<Option<Pass>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut pass = Option::<Pass>::deserialize(deserializer)?;
+        if let Some(pass) = &mut pass {
+            drop_scale(pass, "cube_a");
+        }
+        Ok(pass)
+    }
+
+    /// Drops the `scale` of the "Sound" pass.
+    pub fn unscaled_sound<'de, D>(deserializer: D) -> Result<Option<Pass>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut pass = Option::<Pass>::deserialize(deserializer)?;
+        if let Some(pass) = &mut pass {
+            drop_scale(pass, "sound");
+        }
+        Ok(pass)
+    }
+
+    /// Rounds the MSAA sample count down to a power of two, up to
+    /// [`MAX_MSAA_SAMPLES`].
+    pub fn msaa_samples<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = u32::deserialize(deserializer)?.min(MAX_MSAA_SAMPLES);
+        Ok(value.checked_ilog2().map_or(0, |exponent| 1 << exponent))
+    }
+
+    /// Restricts a factor to the range `[0.0, 1.0]`.
+    pub fn clamp_unit<'de, D>(deserializer: D) -> Result<f32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f32::deserialize(deserializer)?;
+        Ok(value.clamp(0.0, 1.0))
+    }
+
+    /// Restricts a rectangle given in fractions of the monitor size to
+    /// the monitor.
+    pub fn clamp_rect<'de, D>(deserializer: D) -> Result<[f32; 4], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let [x, y, width, height] = <[f32; 4]>::deserialize(deserializer)?;
+        let (x, y) = (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0));
+        Ok([x, y, width.clamp(0.0, 1.0 - x), height.clamp(0.0, 1.0 - y)])
+    }
+
+    /// Restricts every component of an RGB color to `[0.0, 1.0]`.
+    pub fn clamp_rgb<'de, D>(deserializer: D) -> Result<[f32; 3], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = <[f32; 3]>::deserialize(deserializer)?;
+        Ok(value.map(|component| component.clamp(0.0, 1.0)))
+    }
+
+    /// Ensures a color adjustment factor is non-negative.
+    pub fn clamp_color_factor<'de, D>(deserializer: D) -> Result<f32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f32::deserialize(deserializer)?;
+        Ok(value.max(0.0))
+    }
+
+    /// Ensures `gamma` is at least [`MIN_GAMMA`].
+    pub fn clamp_gamma<'de, D>(deserializer: D) -> Result<f32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f32::deserialize(deserializer)?;
+        Ok(value.max(MIN_GAMMA))
+    }
+
+    /// Restricts the supersampling factor to `[1, MAX_SUPERSAMPLE]`.
+    pub fn clamp_supersample<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = u32::deserialize(deserializer)?;
+        Ok(value.clamp(1, MAX_SUPERSAMPLE))
+    }
+
     /// Ensures `time_scale` is non-negative.
     pub fn clamp_time_scale<'de, D>(deserializer: D) -> Result<f64, D::Error>
     where
@@ -309,6 +1461,66 @@ mod validators {
         Ok(value.max(0.0))
     }
 
+    /// Ensures `weight` is finite and non-negative.
+    pub fn clamp_weight<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f64::deserialize(deserializer)?;
+        Ok(if value.is_finite() {
+            value.max(0.0)
+        } else {
+            0.0
+        })
+    }
+
+    /// Ensures `max_fps` is either `0.0` or at least [`MIN_FPS`].
+    pub fn clamp_max_fps<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f64::deserialize(deserializer)?;
+        Ok(clamp_fps(value))
+    }
+
+    /// Ensures an optional `max_fps` override is either `0.0` or at
+    /// least [`MIN_FPS`].
+    pub fn clamp_optional_max_fps<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<f64>::deserialize(deserializer)?;
+        Ok(value.map(clamp_fps))
+    }
+
+    /// Returns `0.0` (disabled) for non-positive or non-finite frame
+    /// rates, and raises the others to [`MIN_FPS`].
+    pub fn clamp_fps(value: f64) -> f64 {
+        if value.is_finite() && value > 0.0 {
+            value.max(MIN_FPS)
+        } else {
+            0.0
+        }
+    }
+
+    /// Restricts sound volume to the valid range `[0.0, 1.0]`.
+    pub fn clamp_sound_volume<'de, D>(deserializer: D) -> Result<f32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f32::deserialize(deserializer)?;
+        Ok(value.clamp(0.0, 1.0))
+    }
+
+    /// Restricts the number of audio bands to the size of `iAudioBands`.
+    pub fn clamp_audio_bands<'de, D>(deserializer: D) -> Result<usize, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = usize::deserialize(deserializer)?;
+        Ok(value.clamp(1, crate::audio_spectrum::MAX_AUDIO_BANDS))
+    }
+
     /// Restricts crossfade overlap ratio to the valid range `[0.0, 1.0]`.
     pub fn clamp_crossfade<'de, D>(deserializer: D) -> Result<f64, D::Error>
     where
@@ -317,11 +1529,74 @@ mod validators {
         let value = f64::deserialize(deserializer)?;
         Ok(value.clamp(0.0, 1.0))
     }
+
+    /// Rejects `[[pass]]` entries whose names are empty, repeated, or
+    /// those of the fixed passes.
+    pub fn named_passes<'de, D>(deserializer: D) -> Result<Vec<NamedPass>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const RESERVED_NAMES: [&str; 9] = [
+            "Common",
+            "Buffer A",
+            "Buffer B",
+            "Buffer C",
+            "Buffer D",
+            "Cube A",
+            "Cubemap A",
+            "Image",
+            "Sound",
+        ];
+
+        let passes = Vec::<NamedPass>::deserialize(deserializer)?;
+        let mut names = HashSet::new();
+        for named in &passes {
+            if named.name.trim().is_empty() || RESERVED_NAMES.contains(&named.name.as_str()) {
+                return Err(serde::de::Error::custom(format!(
+                    "invalid pass name '{}'",
+                    named.name
+                )));
+            }
+            if !names.insert(named.name.as_str()) {
+                return Err(serde::de::Error::custom(format!(
+                    "duplicate pass name '{}'",
+                    named.name
+                )));
+            }
+        }
+        Ok(passes)
+    }
+
+    /// Drops custom uniforms whose names are not valid GLSL identifiers
+    /// or would clash with uniforms declared by ShaderBG.
+    pub fn uniforms<'de, D>(deserializer: D) -> Result<BTreeMap<String, UniformValue>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut uniforms = BTreeMap::<String, UniformValue>::deserialize(deserializer)?;
+        uniforms.retain(|name, _| {
+            let mut chars = name.chars();
+            let is_identifier = chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            let is_reserved = name.starts_with("gl_")
+                || name.starts_with("sbg_")
+                || (name.starts_with('i')
+                    && name[1..].starts_with(|c: char| c.is_ascii_uppercase()));
+
+            if !is_identifier || is_reserved {
+                log::warn!("Ignoring uniform '{name}': invalid or reserved name");
+            }
+            is_identifier && !is_reserved
+        });
+        Ok(uniforms)
+    }
 }
 
-/// Loads preset from a file.
+/// Loads preset from a TOML file or a `.sbgz` bundle.
 pub fn load_preset_from_toml_file(path: &Path) -> Result<(Preset, Option<PathBuf>), PresetError> {
-    Ok((Preset::from_toml_file(path)?, Some(path.to_path_buf())))
+    Ok((Preset::from_file(path)?, Some(path.to_path_buf())))
 }
 
 /// Loads a preset from a JSON file exported from ShaderToy.
@@ -333,30 +1608,152 @@ pub fn load_preset_from_json_file(path: &Path) -> Result<(Preset, Option<PathBuf
     Ok((preset, Some(saved_path)))
 }
 
-/// Loads a random preset from the given directory.
-pub fn load_preset_from_directory(dir: &Path) -> Result<(Preset, Option<PathBuf>), PresetError> {
-    let toml_files: Vec<_> = fs::read_dir(dir)?
+/// Fields of a preset deciding whether and how often it is picked at
+/// random, parsed without loading the rest of the preset.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PresetSelection {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(
+        default = "defaults::weight",
+        deserialize_with = "validators::clamp_weight"
+    )]
+    pub weight: f64,
+}
+
+impl PresetSelection {
+    /// Reads the selection fields of a TOML preset or bundle.
+    pub fn from_file(path: &Path) -> Result<Self, PresetError> {
+        let content = if crate::bundle::is_bundle(path) {
+            crate::bundle::read_preset(path)?
+        } else {
+            fs::read_to_string(path)?
+        };
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// Filters applied to random preset selection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PresetFilter {
+    /// Tags a preset must all have.
+    pub tags: Vec<String>,
+    /// Tags a preset must not have.
+    pub exclude: Vec<String>,
+    /// Preset files never picked, e.g., those aborted by the GPU
+    /// watchdog.
+    pub excluded_files: Vec<PathBuf>,
+}
+
+impl PresetFilter {
+    /// Returns `true` if a preset with the given tags passes the filter.
+    /// Tags are compared case-insensitively.
+    pub fn matches(&self, tags: &[String]) -> bool {
+        let has_tag = |tag: &String| {
+            tags.iter()
+                .any(|preset_tag| preset_tag.eq_ignore_ascii_case(tag))
+        };
+        self.tags.iter().all(has_tag) && !self.exclude.iter().any(has_tag)
+    }
+}
+
+/// Loads a random preset or bundle from the given directory.
+///
+/// Presets not matching `filter` are skipped, and the others are
+/// picked with a probability proportional to their `weight`. Only the
+/// tags and weight of the presets are read, and only the picked preset
+/// is loaded. If it fails to load, another one is picked.
+pub fn load_preset_from_directory(
+    dir: &Path,
+    filter: &PresetFilter,
+) -> Result<(Preset, Option<PathBuf>), PresetError> {
+    let mut candidates: Vec<_> = preset_files(dir)?
+        .into_iter()
+        .filter(|path| !filter.excluded_files.contains(path))
+        .filter_map(|path| match PresetSelection::from_file(&path) {
+            Ok(selection) => (filter.matches(&selection.tags) && selection.weight > 0.0)
+                .then_some((path, selection.weight)),
+            Err(err) => {
+                log::warn!("Skipping {}: {err}", path.display());
+                None
+            }
+        })
+        .collect();
+
+    loop {
+        let weights: Vec<_> = candidates.iter().map(|(_, weight)| *weight).collect();
+        let index =
+            weighted_index(&weights, random_unit()).ok_or(PresetError::NoMatchingPresets)?;
+        let (path, _) = candidates.swap_remove(index);
+        match Preset::from_file(&path) {
+            Ok(preset) => return Ok((preset, Some(path))),
+            Err(err) => log::warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+}
+
+/// Returns the presets and bundles of the given directory, sorted by
+/// file name.
+fn preset_files(dir: &Path) -> Result<Vec<PathBuf>, PresetError> {
+    let mut files: Vec<_> = fs::read_dir(dir)?
         .filter_map(Result::ok)
         .map(|e| e.path())
-        .filter(|p| p.extension() == Some(OsStr::new("toml")))
+        .filter(|p| p.extension() == Some(OsStr::new("toml")) || crate::bundle::is_bundle(p))
         .collect();
+    if files.is_empty() {
+        return Err(PresetError::NoPresets);
+    }
+    files.sort();
+    Ok(files)
+}
 
-    let chosen = toml_files
-        .get(random_index(toml_files.len()))
-        .ok_or(PresetError::NoPresets)?;
+/// Loads every preset and bundle of the given directory, sorted by file
+/// name.
+///
+/// Presets that fail to load are skipped with a warning.
+pub fn presets_in_directory(dir: &Path) -> Result<Vec<(Preset, PathBuf)>, PresetError> {
+    Ok(preset_files(dir)?
+        .into_iter()
+        .filter_map(|path| match Preset::from_file(&path) {
+            Ok(preset) => Some((preset, path)),
+            Err(err) => {
+                log::warn!("Skipping {}: {err}", path.display());
+                None
+            }
+        })
+        .collect())
+}
 
-    Ok((Preset::from_toml_file(chosen)?, Some(chosen.clone())))
+/// Returns the index of the weight `r` falls into when the weights are
+/// laid end to end and scaled to `[0, 1)`, or `None` if there is no
+/// positive weight.
+pub fn weighted_index(weights: &[f64], r: f64) -> Option<usize> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut target = r * total;
+    for (index, &weight) in weights.iter().enumerate() {
+        if target < weight {
+            return Some(index);
+        }
+        target -= weight;
+    }
+
+    // Rounding errors may leave a small remainder past the last weight
+    weights.iter().rposition(|&weight| weight > 0.0)
 }
 
-/// Returns a random index in the range `[0, len)` using system time as seed.
-fn random_index(len: usize) -> usize {
+/// Returns a random number in the range `[0, 1)` using system time as seed.
+fn random_unit() -> f64 {
     let mut hasher = DefaultHasher::new();
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_nanos()
         .hash(&mut hasher);
-    (hasher.finish() as usize) % len
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
 }
 
 /// Derives the preset filename from its shader ID.
@@ -369,7 +1766,7 @@ fn preset_filename(preset: &Preset) -> Result<String, PresetError> {
 }
 
 /// Saves preset to the presets directory.
-fn save_to_presets_directory(preset: &Preset) -> Result<PathBuf, PresetError> {
+pub fn save_to_presets_directory(preset: &Preset) -> Result<PathBuf, PresetError> {
     let path = presets_dir().join(preset_filename(preset)?);
     save_preset_to_file(preset, &path)?;
     log::debug!("Saved preset '{}'", preset.id);
@@ -377,7 +1774,7 @@ fn save_to_presets_directory(preset: &Preset) -> Result<PathBuf, PresetError> {
 }
 
 /// Saves preset to a TOML file.
-fn save_preset_to_file(preset: &Preset, path: &Path) -> Result<(), PresetError> {
+pub fn save_preset_to_file(preset: &Preset, path: &Path) -> Result<(), PresetError> {
     let toml = toml::to_string_pretty(preset).map_err(|e| PresetError::Import(e.to_string()))?;
     fs::write(path, toml)?;
     Ok(())
@@ -462,3 +1859,55 @@ where
     let app_data = get_data!(app, AppData, as_mut());
     app_data.preset_monitor = Some(monitor);
 }
+
+/// Sets up filesystem monitoring for the external shader files of a preset.
+///
+/// Registers one `gio::FileMonitor` per file in `shader_files` (see
+/// [`Preset::shader_files`]) and invokes `on_change` with the pass name
+/// and file path after a file has finished changing.
+///
+/// Monitors of a previously loaded preset are replaced.
+pub fn setup_shader_file_monitors<F>(
+    app: &gtk::Application,
+    shader_files: &[(String, PathBuf)],
+    on_change: F,
+) where
+    F: Fn(&gtk::Application, &str, &Path) + Clone + 'static,
+{
+    let mut monitors = Vec::new();
+
+    for (pass_name, shader_path) in shader_files {
+        let file = gio::File::for_path(shader_path);
+
+        let monitor = match file.monitor(
+            gio::FileMonitorFlags::NONE,
+            None::<gio::Cancellable>.as_ref(),
+        ) {
+            Ok(monitor) => monitor,
+            Err(err) => {
+                log::error!(
+                    "Failed to create shader file monitor for {}: {err}",
+                    shader_path.display()
+                );
+                continue;
+            }
+        };
+
+        let app_clone = app.clone();
+        let on_change = on_change.clone();
+        let pass_name = pass_name.clone();
+        monitor.connect_changed(move |_, changed_file, _, event_type| {
+            if event_type == gio::FileMonitorEvent::ChangesDoneHint {
+                if let Some(path) = changed_file.path() {
+                    log::info!("Shader file changed: {}", path.display());
+                    on_change(&app_clone, &pass_name, &path);
+                }
+            }
+        });
+
+        monitors.push(monitor);
+    }
+
+    let app_data = get_data!(app, AppData, as_mut());
+    app_data.shader_file_monitors = monitors;
+}
@@ -0,0 +1,243 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Animated GIF, APNG and WebP decoding for `InputType::Texture` channels.
+//!
+//! All frames are decoded upfront into RGBA8 images, and playback loops
+//! over them following the frame delays stored in the file. Files with a
+//! single frame are loaded as still textures instead.
+//!
+//! Decoded frames take up to [`MAX_DECODED_BYTES`], and are shared by
+//! the texture managers of all monitors playing the same file. Like the
+//! renderers, they live on the GTK main thread, so decoded files are
+//! registered per thread.
+
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    imageops, AnimationDecoder, Frames, ImageResult, RgbaImage,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    rc::{Rc, Weak},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Delays shorter than this are replaced by [`DEFAULT_FRAME_DELAY`], as
+/// web browsers do, since many files rely on it.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(10);
+
+/// Delay of frames without a usable delay.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// Maximum number of frames decoded from a file. Later frames are not
+/// played.
+const MAX_FRAMES: usize = 1024;
+
+/// Maximum size of the frames decoded from a file, in bytes. Later
+/// frames are not played.
+const MAX_DECODED_BYTES: usize = 512 * 1024 * 1024;
+
+/// Path, vertical flip and modification time of a decoded file.
+type DecodedKey = (PathBuf, bool, Option<SystemTime>);
+
+thread_local! {
+    /// Decoded files, by key.
+    static DECODED: RefCell<HashMap<DecodedKey, Weak<DecodedFrames>>> = RefCell::default();
+}
+
+/// Frames decoded from a file.
+struct DecodedFrames {
+    /// Decoded frames, flipped if requested.
+    frames: Vec<RgbaImage>,
+
+    /// Time at which each frame ends, from the start of the loop.
+    end_times: Vec<Duration>,
+}
+
+/// Looping animation decoded from an image file.
+pub struct AnimatedImage {
+    /// Frames, shared with other animations of the same file.
+    decoded: Rc<DecodedFrames>,
+
+    /// Instant at which the current loop started playing, shifted by
+    /// pauses and seeks.
+    start: Instant,
+
+    /// Instant at which playback was paused, if paused.
+    paused_at: Option<Instant>,
+}
+
+impl AnimatedImage {
+    /// Decodes an animated GIF, APNG or WebP file, or shares its frames
+    /// if another animation already decoded it.
+    ///
+    /// Returns `None` if the file format does not support animation or
+    /// the file has a single frame.
+    pub fn open(path: &Path, vflip: bool) -> ImageResult<Option<Self>> {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let key = (path.to_path_buf(), vflip, modified);
+        let existing = DECODED.with_borrow(|decoded| decoded.get(&key).and_then(Weak::upgrade));
+
+        let decoded = match existing {
+            Some(decoded) => decoded,
+            None => {
+                let Some(decoded) = decode(path, vflip)? else {
+                    return Ok(None);
+                };
+                let decoded = Rc::new(decoded);
+                DECODED.with_borrow_mut(|cache| {
+                    cache.retain(|_, decoded| decoded.strong_count() > 0);
+                    cache.insert(key, Rc::downgrade(&decoded));
+                });
+                decoded
+            }
+        };
+
+        Ok(Some(Self {
+            decoded,
+            start: Instant::now(),
+            paused_at: None,
+        }))
+    }
+
+    /// Returns the frame shown at the current playback position.
+    pub fn current_frame(&self) -> (usize, &RgbaImage) {
+        let index = frame_index(&self.decoded.end_times, self.position());
+        (index, &self.decoded.frames[index])
+    }
+
+    /// Returns the playback position since the animation started.
+    pub fn position(&self) -> Duration {
+        self.paused_at
+            .unwrap_or_else(Instant::now)
+            .saturating_duration_since(self.start)
+    }
+
+    /// Pauses or resumes playback.
+    pub fn set_paused(&mut self, paused: bool) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(Instant::now()),
+            (false, Some(paused_at)) => {
+                self.start += paused_at.elapsed();
+                self.paused_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves playback to the given position.
+    pub fn seek(&mut self, position: Duration) {
+        let now = Instant::now();
+        self.start = now.checked_sub(position).unwrap_or(now);
+        if self.paused_at.is_some() {
+            self.paused_at = Some(now);
+        }
+    }
+}
+
+/// Returns the index of the frame shown at `position`, looping over the
+/// frames whose end times are given.
+pub fn frame_index(end_times: &[Duration], position: Duration) -> usize {
+    let Some(&duration) = end_times.last() else {
+        return 0;
+    };
+    if duration.is_zero() {
+        return 0;
+    }
+
+    let position = Duration::from_nanos((position.as_nanos() % duration.as_nanos()) as u64);
+    end_times
+        .iter()
+        .position(|&end_time| position < end_time)
+        .unwrap_or(end_times.len() - 1)
+}
+
+/// Decodes the frames of an animated file, up to [`MAX_FRAMES`] and
+/// [`MAX_DECODED_BYTES`].
+///
+/// Returns `None` if the file is not animated.
+fn decode(path: &Path, vflip: bool) -> ImageResult<Option<DecodedFrames>> {
+    let Some(frames) = decode_frames(path)? else {
+        return Ok(None);
+    };
+
+    let mut images = Vec::new();
+    let mut end_times = Vec::new();
+    let mut end_time = Duration::ZERO;
+    let mut decoded_bytes = 0;
+    for frame in frames {
+        let frame = frame?;
+        let (numerator, denominator) = frame.delay().numer_denom_ms();
+        let delay = Duration::from_secs_f64(numerator as f64 / denominator.max(1) as f64 / 1e3);
+
+        let mut image = frame.into_buffer();
+        decoded_bytes += image.as_raw().len();
+        if images.len() == MAX_FRAMES || decoded_bytes > MAX_DECODED_BYTES {
+            log::warn!(
+                "Only the first {} frames of {} are played",
+                images.len(),
+                path.display()
+            );
+            break;
+        }
+
+        if vflip {
+            imageops::flip_vertical_in_place(&mut image);
+        }
+        end_time += if delay < MIN_FRAME_DELAY {
+            DEFAULT_FRAME_DELAY
+        } else {
+            delay
+        };
+        images.push(image);
+        end_times.push(end_time);
+    }
+
+    if images.len() < 2 {
+        return Ok(None);
+    }
+
+    Ok(Some(DecodedFrames {
+        frames: images,
+        end_times,
+    }))
+}
+
+/// Returns the frames of a GIF, APNG or WebP file, or `None` for other
+/// formats and still PNG or WebP files.
+fn decode_frames(path: &Path) -> ImageResult<Option<Frames<'static>>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let open = || -> ImageResult<_> { Ok(BufReader::new(File::open(path)?)) };
+
+    let frames = match extension.as_deref() {
+        Some("gif") => GifDecoder::new(open()?)?.into_frames(),
+        Some("png" | "apng") => {
+            let decoder = PngDecoder::new(open()?)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            decoder.apng()?.into_frames()
+        }
+        Some("webp") => {
+            let decoder = WebPDecoder::new(open()?)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(frames))
+}
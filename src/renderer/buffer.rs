@@ -51,10 +51,25 @@ impl Buffer {
         }
     }
 
+    /// Allocates `size` bytes of uninitialized storage, replacing the
+    /// current contents, e.g., as the destination of a GPU copy.
+    pub fn allocate(&self, size: usize, usage: GLuint) {
+        self.bind();
+        unsafe {
+            gl::BufferData(self.target, size as GLsizeiptr, std::ptr::null(), usage);
+        }
+    }
+
     /// Binds this buffer to its OpenGL target.
     pub fn bind(&self) {
         unsafe { gl::BindBuffer(self.target, self.id) };
     }
+
+    /// Binds this buffer to the binding point `index` of its indexed
+    /// target, e.g., [`gl::UNIFORM_BUFFER`].
+    pub fn bind_base(&self, index: GLuint) {
+        unsafe { gl::BindBufferBase(self.target, index, self.id) };
+    }
 }
 
 impl Drop for Buffer {
@@ -41,23 +41,8 @@ fn supports_debug_extension() -> bool {
     let mut flags = 0;
     unsafe { gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut flags) };
 
-    if flags & gl::CONTEXT_FLAG_DEBUG_BIT as i32 != 0 {
-        return true;
-    }
-
-    let mut num_extensions = 0;
-    unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut num_extensions) };
-
-    for i in 0..num_extensions as u32 {
-        let ptr = unsafe { gl::GetStringi(gl::EXTENSIONS, i) };
-        if !ptr.is_null() {
-            let extension = unsafe { std::ffi::CStr::from_ptr(ptr as *const _) }.to_string_lossy();
-            if extension == "GL_KHR_debug" || extension == "GL_ARB_debug_output" {
-                return true;
-            }
-        }
-    }
-    false
+    flags & gl::CONTEXT_FLAG_DEBUG_BIT as i32 != 0
+        || super::has_gl_extension(&["GL_KHR_debug", "GL_ARB_debug_output"])
 }
 
 /// OpenGL debug message callback.
@@ -0,0 +1,178 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Static frame presented while a window's renderer is being replaced.
+//!
+//! When windows are recreated (e.g., after a monitor is plugged in), the
+//! last frame rendered on each surviving monitor is captured and shown by
+//! the new window until its renderer is ready, and then faded out over
+//! the new renderer's output. The preset fallback image is presented
+//! the same way while the first renderer of a window is being built.
+
+use gl::types::*;
+
+use super::{gl_profile, program::Program, shader::*, vertex_array::VertexArray};
+use crate::geometry::Size;
+
+/// Fullscreen triangle strip generated from the vertex index.
+const SNAPSHOT_VERTEX_SHADER: &str = r#"
+out vec2 fragTexCoord;
+
+uniform vec2 texCoordScale;
+
+void main() {
+    vec2 position = vec2(gl_VertexID & 1, gl_VertexID >> 1);
+    // Image rows start at the top
+    fragTexCoord = (vec2(position.x, 1.0 - position.y) - 0.5) * texCoordScale + 0.5;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const SNAPSHOT_FRAGMENT_SHADER: &str = r#"
+in vec2 fragTexCoord;
+out vec4 fragColor;
+
+uniform sampler2D snapshot;
+uniform float opacity;
+
+void main() {
+    fragColor = vec4(texture(snapshot, fragTexCoord).rgb, opacity);
+}
+"#;
+
+/// OpenGL resources of an uploaded snapshot.
+struct SnapshotResources {
+    program: Program,
+    vao: VertexArray,
+    texture: GLuint,
+    opacity_location: GLint,
+    tex_coord_scale_location: GLint,
+}
+
+impl Drop for SnapshotResources {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.texture) };
+    }
+}
+
+/// Captured frame drawn over a window with a given opacity.
+///
+/// The image is uploaded on the first [`FrameSnapshot::draw`], so a
+/// snapshot can be created before the OpenGL context of the window
+/// that presents it exists.
+pub struct FrameSnapshot {
+    image: image::RgbaImage,
+    resources: Option<SnapshotResources>,
+}
+
+impl FrameSnapshot {
+    pub fn new(image: image::RgbaImage) -> Self {
+        Self {
+            image,
+            resources: None,
+        }
+    }
+
+    /// Draws the snapshot scaled to cover the current framebuffer,
+    /// blended with its contents by `opacity`.
+    ///
+    /// Snapshots with a different aspect ratio than the viewport are
+    /// cropped.
+    pub fn draw(&mut self, viewport_size: Size, opacity: f32) {
+        if self.resources.is_none() {
+            match self.upload() {
+                Ok(resources) => self.resources = Some(resources),
+                Err(err) => {
+                    log::warn!("Failed to create frame snapshot: {err}");
+                    return;
+                }
+            }
+        }
+        let Some(resources) = &self.resources else {
+            return;
+        };
+
+        resources.program.bind();
+        resources.vao.bind();
+        unsafe {
+            gl::Viewport(
+                0,
+                0,
+                viewport_size.width() as i32,
+                viewport_size.height() as i32,
+            );
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, resources.texture);
+            gl::Uniform1f(resources.opacity_location, opacity.clamp(0.0, 1.0));
+            let [scale_x, scale_y] = self.tex_coord_scale(viewport_size);
+            gl::Uniform2f(resources.tex_coord_scale_location, scale_x, scale_y);
+
+            // Keep the destination opaque
+            gl::Enable(gl::BLEND);
+            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::ONE, gl::ONE);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::Disable(gl::BLEND);
+        }
+    }
+
+    /// Returns the scale of texture coordinates that crops the image to
+    /// the aspect ratio of the viewport.
+    fn tex_coord_scale(&self, viewport_size: Size) -> [f32; 2] {
+        let image_aspect = self.image.width().max(1) as f32 / self.image.height().max(1) as f32;
+        let viewport_aspect =
+            viewport_size.width().max(1) as f32 / viewport_size.height().max(1) as f32;
+        if viewport_aspect > image_aspect {
+            [1.0, image_aspect / viewport_aspect]
+        } else {
+            [viewport_aspect / image_aspect, 1.0]
+        }
+    }
+
+    fn upload(&self) -> Result<SnapshotResources, ShaderError> {
+        let prelude = gl_profile().shader_prelude();
+        let program = Program::new(&[
+            Shader::new(
+                &(prelude.clone() + SNAPSHOT_VERTEX_SHADER),
+                gl::VERTEX_SHADER,
+            )?,
+            Shader::new(&(prelude + SNAPSHOT_FRAGMENT_SHADER), gl::FRAGMENT_SHADER)?,
+        ])?;
+
+        program.bind();
+        unsafe { gl::Uniform1i(program.uniform_location("snapshot")?, 0) };
+        let opacity_location = program.uniform_location("opacity")?;
+        let tex_coord_scale_location = program.uniform_location("texCoordScale")?;
+
+        let mut texture = 0;
+        unsafe {
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                self.image.width() as i32,
+                self.image.height() as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                self.image.as_ptr() as *const _,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        }
+
+        Ok(SnapshotResources {
+            program,
+            vao: VertexArray::new(),
+            texture,
+            opacity_location,
+            tex_coord_scale_location,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! ShaderToy uniforms shared by all render passes.
+//!
+//! Uniforms having the same value in every pass of a frame, such as
+//! `iTime` or `iDate`, are members of the `sbg_FrameUniforms` uniform
+//! block of pass shaders. They are uploaded once per frame to a single
+//! uniform buffer, instead of once per pass with a call per uniform.
+
+use chrono::prelude::*;
+use gl::types::*;
+
+use crate::{audio_spectrum::MAX_AUDIO_BANDS, renderer::RenderContext};
+
+use super::{buffer::*, sound_player::SAMPLE_RATE};
+
+/// Name of the uniform block in pass shaders.
+pub const FRAME_UNIFORMS_BLOCK: &str = "sbg_FrameUniforms";
+
+/// Uniform buffer binding point of the block.
+pub const FRAME_UNIFORMS_BINDING: GLuint = 0;
+
+/// Contents of the `sbg_FrameUniforms` block, matching its `std140`
+/// layout.
+#[repr(C)]
+struct FrameUniformData {
+    i_date: [f32; 4],
+    i_mouse_wheel: [f32; 4],
+    i_desktop: [f32; 4],
+    i_system_stats: [[f32; 4]; 4],
+    i_sun_position: [f32; 2],
+    i_time: f32,
+    i_global_time: f32,
+    i_time_delta: f32,
+    i_frame_rate: f32,
+    i_frame: i32,
+    i_mouse_buttons: i32,
+    i_sample_rate: f32,
+    i_track_progress: f32,
+    i_day_phase: f32,
+    _padding: f32,
+
+    /// Audio bands, each padded to 16 bytes as `std140` requires for
+    /// array elements.
+    i_audio_bands: [[f32; 4]; MAX_AUDIO_BANDS],
+}
+
+/// Uniform buffer backing the `sbg_FrameUniforms` block.
+pub struct FrameUniforms {
+    buffer: Buffer,
+}
+
+impl FrameUniforms {
+    /// Creates the uniform buffer.
+    pub fn new() -> Self {
+        Self {
+            buffer: Buffer::new(gl::UNIFORM_BUFFER),
+        }
+    }
+
+    /// Uploads the uniforms of the frame described by `ctx` and binds
+    /// the buffer to [`FRAME_UNIFORMS_BINDING`].
+    pub fn update(&self, ctx: &RenderContext) {
+        let (mouse_data, frame_stats) = (ctx.mouse_data, ctx.frame_stats);
+
+        let now = frame_stats.date;
+        const NANOS_PER_SEC: u32 = 1_000_000_000;
+        let time = now.num_seconds_from_midnight() as f32
+            + (now.nanosecond() as f32) / (NANOS_PER_SEC as f32);
+
+        let data = FrameUniformData {
+            i_date: [
+                now.year() as f32,
+                (now.month() - 1) as f32,
+                now.day() as f32,
+                time,
+            ],
+            i_mouse_wheel: *mouse_data.wheel(),
+            i_desktop: *ctx.desktop,
+            i_system_stats: *ctx.system_stats,
+            i_sun_position: [ctx.sun.elevation, ctx.sun.azimuth],
+            i_time: frame_stats.time.as_secs_f32(),
+            i_global_time: frame_stats.time.as_secs_f32(),
+            i_time_delta: frame_stats.time_delta.as_secs_f32(),
+            i_frame_rate: frame_stats.frame_rate as f32,
+            i_frame: frame_stats.frame_number as i32 % i32::MAX,
+            i_mouse_buttons: mouse_data.buttons(),
+            i_sample_rate: SAMPLE_RATE as f32,
+            i_track_progress: ctx.track_progress,
+            i_day_phase: ctx.sun.day_phase,
+            _padding: 0.,
+            i_audio_bands: ctx.audio_bands.map(|band| [band, 0., 0., 0.]),
+        };
+
+        self.buffer
+            .set_data(std::slice::from_ref(&data), gl::DYNAMIC_DRAW);
+        self.buffer.bind_base(FRAME_UNIFORMS_BINDING);
+    }
+}
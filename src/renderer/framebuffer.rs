@@ -14,18 +14,45 @@
 
 use crate::geometry::*;
 use gl::types::*;
+use std::sync::Once;
+
+use super::{
+    gl_profile, has_gl_extension,
+    pixel_readback::{unorm8, PixelReadback},
+};
 
 /// Type of color attachment stored in the framebuffer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FramebufferFormat {
     /// Standard 8-bit RGB texture.
     Tex2D,
+    /// 8-bit RGBA texture, for frames with transparency.
+    Tex2DAlpha,
     /// Floating-point RGBA texture.
     Tex2DFloat,
+    /// Half-precision floating-point RGBA texture.
+    Tex2DHalfFloat,
     /// Floating-point cubemap texture.
     Cubemap,
 }
 
+/// Floating-point color attachments the current context can render to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FloatRenderable {
+    All,
+    HalfOnly,
+    None,
+}
+
+/// Contents of a floating-point framebuffer read back into memory,
+/// e.g., to restore them in another OpenGL context.
+pub struct FramebufferSnapshot {
+    size: Size,
+
+    /// RGBA pixels of each face (one unless a cubemap), bottom row first.
+    faces: Vec<Vec<f32>>,
+}
+
 /// Offscreen render target backed by an OpenGL framebuffer and its attachments.
 ///
 /// The framebuffer owns its color attachments and manages their lifetime.
@@ -43,6 +70,13 @@ pub struct Framebuffer {
     /// Dimensions of the color attachment.
     size: Size,
 
+    /// Format of the color attachment.
+    format: FramebufferFormat,
+
+    /// Type of the pixels of the color attachment when read back or
+    /// uploaded: `FLOAT`, or `UNSIGNED_BYTE` for 8-bit attachments.
+    pixel_type: GLenum,
+
     /// Indicates whether multisample anti-aliasing is active.
     msaa_enabled: bool,
 
@@ -51,44 +85,91 @@ pub struct Framebuffer {
 
     /// ID of the texture containing the resolved image used for shader sampling.
     msaa_resolve_texture_id: GLuint,
+
+    /// Target of the color attachment texture when it has a mip chain
+    /// to regenerate after rendering. `None` without mipmaps.
+    mipmap_target: Option<GLenum>,
 }
 
 impl Framebuffer {
     /// Creates a framebuffer with a color attachment of the given format.
     ///
+    /// With `mipmaps`, a full mip chain is allocated for the attachment,
+    /// to be regenerated with [`Framebuffer::generate_mipmaps`] after
+    /// every render. Mipmaps are not supported with MSAA.
+    ///
+    /// Floating-point attachments the context cannot render to are
+    /// replaced by half-precision or 8-bit attachments.
+    ///
     /// The previously bound framebuffer is restored before returning.
-    pub fn new(size: Size, msaa_samples: u32, format: FramebufferFormat) -> Self {
+    pub fn new(size: Size, msaa_samples: u32, format: FramebufferFormat, mipmaps: bool) -> Self {
         let previous_fbo = current_framebuffer();
 
-        let msaa_enabled = msaa_samples > 0 && format == FramebufferFormat::Tex2D;
+        let float_renderable = float_renderable();
+        let format = match (format, float_renderable) {
+            (FramebufferFormat::Tex2DFloat, FloatRenderable::HalfOnly) => {
+                FramebufferFormat::Tex2DHalfFloat
+            }
+            (
+                FramebufferFormat::Tex2DFloat | FramebufferFormat::Tex2DHalfFloat,
+                FloatRenderable::None,
+            ) => FramebufferFormat::Tex2DAlpha,
+            _ => format,
+        };
+        let pixel_type = match format {
+            FramebufferFormat::Tex2D | FramebufferFormat::Tex2DAlpha => gl::UNSIGNED_BYTE,
+            FramebufferFormat::Cubemap if float_renderable == FloatRenderable::None => {
+                gl::UNSIGNED_BYTE
+            }
+            _ => gl::FLOAT,
+        };
+
+        let msaa_enabled = msaa_samples > 0
+            && matches!(
+                format,
+                FramebufferFormat::Tex2D | FramebufferFormat::Tex2DAlpha
+            );
+        let mipmaps = mipmaps && !msaa_enabled;
 
         let fbo_id = gen_framebuffer();
         bind_framebuffer(fbo_id);
 
         let texture_id = match format {
-            FramebufferFormat::Tex2D | FramebufferFormat::Tex2DFloat => {
-                create_2d_color_attachment(size, format, msaa_samples, msaa_enabled)
+            FramebufferFormat::Tex2D
+            | FramebufferFormat::Tex2DAlpha
+            | FramebufferFormat::Tex2DFloat
+            | FramebufferFormat::Tex2DHalfFloat => {
+                create_2d_color_attachment(size, format, msaa_samples, msaa_enabled, mipmaps)
             }
-            FramebufferFormat::Cubemap => create_cubemap_attachment(size),
+            FramebufferFormat::Cubemap => create_cubemap_attachment(size, pixel_type == gl::FLOAT),
         };
 
         check_framebuffer_status();
 
         let (resolve_fbo, resolve_texture) = if msaa_enabled {
-            create_msaa_resolve_target(size)
+            create_msaa_resolve_target(size, format)
         } else {
             (0, 0)
         };
 
         bind_framebuffer(previous_fbo);
 
+        let mipmap_target = mipmaps.then_some(if format == FramebufferFormat::Cubemap {
+            gl::TEXTURE_CUBE_MAP
+        } else {
+            gl::TEXTURE_2D
+        });
+
         Self {
             fbo_id,
             texture_id,
             size,
+            format,
+            pixel_type,
             msaa_enabled,
             msaa_resolve_fbo_id: resolve_fbo,
             msaa_resolve_texture_id: resolve_texture,
+            mipmap_target,
         }
     }
 
@@ -137,6 +218,179 @@ impl Framebuffer {
         }
     }
 
+    /// Copies the color attachment of `source` into this framebuffer,
+    /// rescaling it with linear filtering when the sizes differ.
+    ///
+    /// Both framebuffers must have floating-point attachments of the
+    /// same kind (2D or cubemap), and MSAA disabled.
+    /// The previously bound framebuffer is restored before returning.
+    pub fn copy_from(&self, source: &Framebuffer, format: FramebufferFormat) {
+        let previous_fbo = current_framebuffer();
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, source.fbo_id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.fbo_id);
+        }
+
+        for face in cubemap_faces(format) {
+            if let Some(face) = face {
+                unsafe {
+                    gl::FramebufferTexture2D(
+                        gl::READ_FRAMEBUFFER,
+                        gl::COLOR_ATTACHMENT0,
+                        face,
+                        source.texture_id,
+                        0,
+                    );
+                    gl::FramebufferTexture2D(
+                        gl::DRAW_FRAMEBUFFER,
+                        gl::COLOR_ATTACHMENT0,
+                        face,
+                        self.texture_id,
+                        0,
+                    );
+                }
+            }
+
+            unsafe {
+                gl::BlitFramebuffer(
+                    0,
+                    0,
+                    source.size.width() as i32,
+                    source.size.height() as i32,
+                    0,
+                    0,
+                    self.size.width() as i32,
+                    self.size.height() as i32,
+                    gl::COLOR_BUFFER_BIT,
+                    gl::LINEAR,
+                );
+            }
+        }
+
+        bind_framebuffer(previous_fbo);
+    }
+
+    /// Reads back the color attachment of a floating-point framebuffer,
+    /// or of the 8-bit framebuffer replacing it.
+    ///
+    /// The previously bound framebuffer is restored before returning.
+    pub fn snapshot(&self) -> FramebufferSnapshot {
+        let previous_fbo = current_framebuffer();
+        let pixel_count = (self.size.width() * self.size.height() * 4) as usize;
+
+        unsafe { gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo_id) };
+
+        let faces = cubemap_faces(self.format)
+            .into_iter()
+            .map(|face| {
+                if let Some(face) = face {
+                    unsafe {
+                        gl::FramebufferTexture2D(
+                            gl::READ_FRAMEBUFFER,
+                            gl::COLOR_ATTACHMENT0,
+                            face,
+                            self.texture_id,
+                            0,
+                        );
+                    }
+                }
+
+                if self.pixel_type == gl::FLOAT {
+                    let mut pixels = vec![0f32; pixel_count];
+                    self.read_face(gl::FLOAT, pixels.as_mut_ptr() as *mut _);
+                    pixels
+                } else {
+                    let mut pixels = vec![0u8; pixel_count];
+                    self.read_face(gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+                    pixels.iter().map(|&c| c as f32 / 255.0).collect()
+                }
+            })
+            .collect();
+
+        bind_framebuffer(previous_fbo);
+
+        FramebufferSnapshot {
+            size: self.size,
+            faces,
+        }
+    }
+
+    /// Replaces the contents of the color attachment with `snapshot`,
+    /// rescaled with linear filtering when the sizes differ.
+    ///
+    /// The snapshot must be of a framebuffer of the same kind (2D or
+    /// cubemap). The previously bound framebuffer is restored before
+    /// returning.
+    pub fn restore(&self, snapshot: &FramebufferSnapshot) {
+        // Uploaded at its own size, and rescaled by blitting
+        let source = Framebuffer::new(snapshot.size, 0, self.format, false);
+
+        let target = if self.format == FramebufferFormat::Cubemap {
+            gl::TEXTURE_CUBE_MAP
+        } else {
+            gl::TEXTURE_2D
+        };
+        unsafe { gl::BindTexture(target, source.texture_id) };
+
+        for (face, pixels) in cubemap_faces(self.format).into_iter().zip(&snapshot.faces) {
+            let bytes: Vec<u8>;
+            let data = if source.pixel_type == gl::FLOAT {
+                pixels.as_ptr() as *const _
+            } else {
+                bytes = pixels
+                    .iter()
+                    .map(|&c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+                    .collect();
+                bytes.as_ptr() as *const _
+            };
+            unsafe {
+                gl::TexSubImage2D(
+                    face.unwrap_or(gl::TEXTURE_2D),
+                    0,
+                    0,
+                    0,
+                    snapshot.size.width() as i32,
+                    snapshot.size.height() as i32,
+                    gl::RGBA,
+                    source.pixel_type,
+                    data,
+                );
+            }
+        }
+
+        self.copy_from(&source, self.format);
+    }
+
+    /// Reads the RGBA pixels of the color attachment of the bound read
+    /// framebuffer into `pixels`, as values of type `ty`.
+    fn read_face(&self, ty: GLenum, pixels: *mut std::ffi::c_void) {
+        unsafe {
+            gl::ReadPixels(
+                0,
+                0,
+                self.size.width() as i32,
+                self.size.height() as i32,
+                gl::RGBA,
+                ty,
+                pixels,
+            );
+        }
+    }
+
+    /// Regenerates the mip chain from the rendered image, if the
+    /// framebuffer was created with mipmaps.
+    ///
+    /// Leaves the texture bound to the active texture unit.
+    pub fn generate_mipmaps(&self) {
+        if let Some(target) = self.mipmap_target {
+            unsafe {
+                gl::BindTexture(target, self.texture_id);
+                gl::GenerateMipmap(target);
+            }
+        }
+    }
+
     /// Resolves the multisampled render target into a single-sampled texture.
     ///
     /// Must be called before sampling the framebuffer texture when MSAA is enabled.
@@ -151,11 +405,75 @@ impl Framebuffer {
         }
     }
 
+    /// Reads back the color attachment as tightly packed RGBA8 pixels,
+    /// bottom row first.
+    ///
+    /// The read framebuffer and pack alignment are restored before
+    /// returning.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let pixel_count = (self.size.width() * self.size.height() * 4) as usize;
+
+        let mut previous_fbo = 0;
+        let mut previous_alignment = 0;
+        unsafe {
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::PACK_ALIGNMENT, &mut previous_alignment);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.resolved_id());
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        }
+
+        // OpenGL ES only reads floating-point attachments as floats
+        let pixels = if self.pixel_type == gl::FLOAT {
+            let mut pixels = vec![0f32; pixel_count];
+            self.read_face(gl::FLOAT, pixels.as_mut_ptr() as *mut _);
+            pixels.into_iter().map(unorm8).collect()
+        } else {
+            let mut pixels = vec![0u8; pixel_count];
+            self.read_face(gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+            pixels
+        };
+
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, previous_alignment);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous_fbo as GLuint);
+        }
+        pixels
+    }
+
+    /// Starts reading back the color attachment without waiting for the
+    /// GPU.
+    pub fn begin_read_pixels(&self) -> PixelReadback {
+        PixelReadback::new(self.resolved_id(), self.size, self.pixel_type)
+    }
+
+    /// Returns the ID of the framebuffer object.
+    #[inline]
+    pub fn id(&self) -> GLuint {
+        self.fbo_id
+    }
+
+    /// Returns the ID of the framebuffer object holding the single
+    /// sampled color attachment.
+    fn resolved_id(&self) -> GLuint {
+        if self.msaa_enabled {
+            self.msaa_resolve_fbo_id
+        } else {
+            self.fbo_id
+        }
+    }
+
     #[inline]
     pub fn size(&self) -> Size {
         self.size
     }
 
+    /// Returns the format of the color attachment, which may differ from
+    /// the requested one if the context cannot render to it.
+    #[inline]
+    pub fn format(&self) -> FramebufferFormat {
+        self.format
+    }
+
     /// Returns the texture handle that should be used for sampling.
     ///
     /// When MSAA is enabled this returns the resolved texture,
@@ -185,13 +503,14 @@ impl Drop for Framebuffer {
     }
 }
 
-/// Creates and attaches a 2D color texture (optionally multisampled)
-/// to the currently bound framebuffer.
+/// Creates and attaches a 2D color texture (optionally multisampled, or
+/// with a mip chain) to the currently bound framebuffer.
 fn create_2d_color_attachment(
     size: Size,
     format: FramebufferFormat,
     samples: u32,
     msaa: bool,
+    mipmaps: bool,
 ) -> GLuint {
     let texture = gen_texture();
 
@@ -206,7 +525,9 @@ fn create_2d_color_attachment(
     if msaa {
         let internal = match format {
             FramebufferFormat::Tex2D => gl::RGB8,
+            FramebufferFormat::Tex2DAlpha => gl::RGBA8,
             FramebufferFormat::Tex2DFloat => gl::RGBA32F,
+            FramebufferFormat::Tex2DHalfFloat => gl::RGBA16F,
             _ => unreachable!(),
         };
 
@@ -220,10 +541,33 @@ fn create_2d_color_attachment(
                 gl::TRUE,
             )
         };
+    } else if mipmaps {
+        let internal = match format {
+            FramebufferFormat::Tex2D => gl::RGB8,
+            FramebufferFormat::Tex2DAlpha => gl::RGBA8,
+            FramebufferFormat::Tex2DFloat => gl::RGBA32F,
+            FramebufferFormat::Tex2DHalfFloat => gl::RGBA16F,
+            _ => unreachable!(),
+        };
+        let levels = size.width().max(size.height()).max(1).ilog2() as i32 + 1;
+
+        unsafe {
+            gl::TexStorage2D(
+                target,
+                levels,
+                internal,
+                size.width() as i32,
+                size.height() as i32,
+            )
+        };
+
+        set_default_texture_params(gl::TEXTURE_2D);
     } else {
         let (internal, format, ty) = match format {
             FramebufferFormat::Tex2D => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            FramebufferFormat::Tex2DAlpha => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
             FramebufferFormat::Tex2DFloat => (gl::RGBA32F, gl::RGBA, gl::FLOAT),
+            FramebufferFormat::Tex2DHalfFloat => (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT),
             _ => unreachable!(),
         };
 
@@ -249,21 +593,31 @@ fn create_2d_color_attachment(
     texture
 }
 
-/// Creates a floating-point cubemap color attachment.
+/// Creates a floating-point cubemap color attachment, or an 8-bit one
+/// without `float`.
 ///
 /// Each face is attached sequentially to validate framebuffer completeness.
-fn create_cubemap_attachment(size: Size) -> GLuint {
+fn create_cubemap_attachment(size: Size, float: bool) -> GLuint {
     let texture = gen_texture();
 
     unsafe { gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture) };
 
     let levels = (size.width().max(size.height()) as f32).log2().floor() as i32 + 1;
 
+    // RGB16F is not color-renderable in OpenGL ES
+    let internal_format = if !float {
+        gl::RGBA8
+    } else if gl_profile().es {
+        gl::RGBA16F
+    } else {
+        gl::RGB16F
+    };
+
     unsafe {
         gl::TexStorage2D(
             gl::TEXTURE_CUBE_MAP,
             levels,
-            gl::RGB16F,
+            internal_format,
             size.width() as i32,
             size.height() as i32,
         )
@@ -286,8 +640,60 @@ fn create_cubemap_attachment(size: Size) -> GLuint {
     texture
 }
 
+/// Returns the floating-point color attachments the current context can
+/// render to, warning once if some cannot.
+///
+/// Desktop OpenGL and OpenGL ES 3.2 render to all of them. OpenGL ES 3.1
+/// needs `EXT_color_buffer_float`, or `EXT_color_buffer_half_float` for
+/// half-precision attachments only.
+fn float_renderable() -> FloatRenderable {
+    static WARNING: Once = Once::new();
+
+    let profile = gl_profile();
+    let renderable = if !profile.es
+        || profile.version >= (3, 2)
+        || has_gl_extension(&["GL_EXT_color_buffer_float"])
+    {
+        FloatRenderable::All
+    } else if has_gl_extension(&["GL_EXT_color_buffer_half_float"]) {
+        FloatRenderable::HalfOnly
+    } else {
+        FloatRenderable::None
+    };
+
+    match renderable {
+        FloatRenderable::All => {}
+        FloatRenderable::HalfOnly => WARNING.call_once(|| {
+            log::warn!("32-bit floating-point buffers are not supported; using 16-bit buffers")
+        }),
+        FloatRenderable::None => WARNING.call_once(|| {
+            log::warn!("Floating-point buffers are not supported; using 8-bit buffers")
+        }),
+    }
+    renderable
+}
+
+/// Returns the face targets of a cubemap color attachment, or `None`
+/// as the only face of a 2D color attachment.
+fn cubemap_faces(format: FramebufferFormat) -> Vec<Option<GLenum>> {
+    match format {
+        FramebufferFormat::Tex2D
+        | FramebufferFormat::Tex2DAlpha
+        | FramebufferFormat::Tex2DFloat
+        | FramebufferFormat::Tex2DHalfFloat => vec![None],
+        FramebufferFormat::Cubemap => (0..6)
+            .map(|face| Some(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face))
+            .collect(),
+    }
+}
+
 /// Creates a single-sampled framebuffer used to resolve MSAA rendering.
-fn create_msaa_resolve_target(size: Size) -> (GLuint, GLuint) {
+fn create_msaa_resolve_target(size: Size, format: FramebufferFormat) -> (GLuint, GLuint) {
+    let (internal, format) = match format {
+        FramebufferFormat::Tex2DAlpha => (gl::RGBA8, gl::RGBA),
+        _ => (gl::RGB8, gl::RGB),
+    };
+
     let fbo = gen_framebuffer();
     bind_framebuffer(fbo);
 
@@ -299,11 +705,11 @@ fn create_msaa_resolve_target(size: Size) -> (GLuint, GLuint) {
         gl::TexImage2D(
             gl::TEXTURE_2D,
             0,
-            gl::RGB8 as i32,
+            internal as i32,
             size.width() as i32,
             size.height() as i32,
             0,
-            gl::RGB,
+            format,
             gl::UNSIGNED_BYTE,
             std::ptr::null(),
         );
@@ -345,7 +751,7 @@ fn bind_framebuffer(id: GLuint) {
 }
 
 /// Returns the currently bound framebuffer.
-fn current_framebuffer() -> GLuint {
+pub fn current_framebuffer() -> GLuint {
     let mut id = 0;
     unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut id) };
     id as GLuint
@@ -0,0 +1,85 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Cache of OpenGL bindings shared by the render passes of a frame.
+//!
+//! Consecutive passes often use the same vertex array, and sample the
+//! same textures in the same channels, so binding calls matching the
+//! cached state are skipped. Code binding objects without the cache
+//! must [`GlStateCache::invalidate`] it afterwards.
+
+use gl::types::*;
+use std::cell::Cell;
+
+/// Number of texture units tracked, one per input channel.
+const TEXTURE_UNITS: usize = 4;
+
+/// Last bindings made through the cache, `None` when unknown.
+#[derive(Default)]
+pub struct GlStateCache {
+    program: Cell<Option<GLuint>>,
+    vertex_array: Cell<Option<GLuint>>,
+    active_texture_unit: Cell<Option<GLuint>>,
+
+    /// Target and texture last bound to each texture unit.
+    textures: Cell<[Option<(GLenum, GLuint)>; TEXTURE_UNITS]>,
+}
+
+impl GlStateCache {
+    /// Forgets all bindings, e.g., at the start of a frame, after other
+    /// code changed them.
+    pub fn invalidate(&self) {
+        self.program.set(None);
+        self.vertex_array.set(None);
+        self.active_texture_unit.set(None);
+        self.textures.set([None; TEXTURE_UNITS]);
+    }
+
+    /// Forgets the texture bound to the active texture unit, after
+    /// binding another one without the cache.
+    pub fn invalidate_active_texture(&self) {
+        if let Some(unit) = self.active_texture_unit.get() {
+            let mut textures = self.textures.get();
+            if let Some(texture) = textures.get_mut(unit as usize) {
+                *texture = None;
+            }
+            self.textures.set(textures);
+        }
+    }
+
+    /// Makes `program` the current program.
+    pub fn use_program(&self, program: GLuint) {
+        if self.program.replace(Some(program)) != Some(program) {
+            unsafe { gl::UseProgram(program) };
+        }
+    }
+
+    /// Binds `vertex_array` as the current vertex array.
+    pub fn bind_vertex_array(&self, vertex_array: GLuint) {
+        if self.vertex_array.replace(Some(vertex_array)) != Some(vertex_array) {
+            unsafe { gl::BindVertexArray(vertex_array) };
+        }
+    }
+
+    /// Binds `texture` to `target` of texture `unit`, leaving `unit`
+    /// active.
+    pub fn bind_texture(&self, unit: GLuint, target: GLenum, texture: GLuint) {
+        if self.active_texture_unit.replace(Some(unit)) != Some(unit) {
+            unsafe { gl::ActiveTexture(gl::TEXTURE0 + unit) };
+        }
+
+        let mut textures = self.textures.get();
+        match textures.get_mut(unit as usize) {
+            Some(bound) if *bound == Some((target, texture)) => {}
+            bound => {
+                unsafe { gl::BindTexture(target, texture) };
+                if let Some(bound) = bound {
+                    *bound = Some((target, texture));
+                }
+            }
+        }
+        self.textures.set(textures);
+    }
+}
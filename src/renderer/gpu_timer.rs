@@ -0,0 +1,151 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gl::types::*;
+use std::{collections::VecDeque, time::Duration};
+
+use super::gl_profile;
+
+/// Number of queries in flight per timer. Results usually become
+/// available one or two frames after the commands are issued.
+const QUERY_COUNT: usize = 4;
+
+/// Number of measurements averaged by [`GpuTimer::average`], about one
+/// second at 60 FPS.
+const AVERAGE_SAMPLE_COUNT: usize = 60;
+
+/// Measures the GPU time spent on the commands issued between
+/// [`GpuTimer::begin`] and [`GpuTimer::end`], using `GL_TIME_ELAPSED`
+/// queries.
+///
+/// Results are read only once available, so measuring never stalls the
+/// pipeline. When every query is still in flight, the commands are not
+/// measured.
+pub struct GpuTimer {
+    /// Queries not in use.
+    free: Vec<GLuint>,
+
+    /// Queries issued and waiting for their results, oldest first.
+    issued: VecDeque<GLuint>,
+
+    /// Query measuring the current commands.
+    active: Option<GLuint>,
+
+    /// Most recent results.
+    samples: TimeSamples,
+}
+
+impl GpuTimer {
+    /// Returns `true` if the context supports timer queries.
+    ///
+    /// OpenGL ES only supports them through an extension, which is not
+    /// used.
+    pub fn is_supported() -> bool {
+        !gl_profile().es && gl::BeginQuery::is_loaded()
+    }
+
+    /// Creates the queries of the timer.
+    pub fn new() -> Self {
+        let mut free = vec![0; QUERY_COUNT];
+        unsafe { gl::GenQueries(QUERY_COUNT as GLsizei, free.as_mut_ptr()) };
+        Self {
+            free,
+            issued: VecDeque::with_capacity(QUERY_COUNT),
+            active: None,
+            samples: TimeSamples::new(AVERAGE_SAMPLE_COUNT),
+        }
+    }
+
+    /// Starts measuring the commands issued from now on.
+    pub fn begin(&mut self) {
+        self.collect_results();
+        self.active = self.free.pop();
+        if let Some(query) = self.active {
+            unsafe { gl::BeginQuery(gl::TIME_ELAPSED, query) };
+        }
+    }
+
+    /// Stops measuring commands.
+    pub fn end(&mut self) {
+        if let Some(query) = self.active.take() {
+            unsafe { gl::EndQuery(gl::TIME_ELAPSED) };
+            self.issued.push_back(query);
+        }
+    }
+
+    /// Returns the mean of the recent measurements, if any.
+    pub fn average(&self) -> Option<Duration> {
+        self.samples.average()
+    }
+
+    /// Reads the results of the issued queries that became available.
+    fn collect_results(&mut self) {
+        while let Some(&query) = self.issued.front() {
+            let mut available = 0;
+            unsafe { gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available) };
+            if available == 0 {
+                break;
+            }
+
+            let mut nanoseconds = 0;
+            unsafe { gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut nanoseconds) };
+            self.samples.push(Duration::from_nanos(nanoseconds));
+
+            self.issued.pop_front();
+            self.free.push(query);
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        let queries: Vec<GLuint> = self
+            .free
+            .iter()
+            .chain(&self.issued)
+            .chain(&self.active)
+            .copied()
+            .collect();
+        unsafe { gl::DeleteQueries(queries.len() as GLsizei, queries.as_ptr()) };
+    }
+}
+
+/// Most recent durations of a repeated measurement.
+#[derive(Debug, Clone)]
+pub struct TimeSamples {
+    /// Measured durations, oldest first.
+    samples: VecDeque<Duration>,
+
+    /// Number of durations kept.
+    capacity: usize,
+}
+
+impl TimeSamples {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds a duration, discarding the oldest one when full.
+    pub fn push(&mut self, duration: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        if self.capacity > 0 {
+            self.samples.push_back(duration);
+        }
+    }
+
+    /// Returns the mean of the durations.
+    pub fn average(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: Duration = self.samples.iter().sum();
+        Some(total / self.samples.len() as u32)
+    }
+}
@@ -0,0 +1,84 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gl::types::*;
+use std::time::{Duration, Instant};
+
+/// Parses a watchdog timeout such as `"2s"`, which must not be zero.
+pub fn parse_gpu_watchdog_timeout(value: &str) -> Result<Duration, String> {
+    match humantime_serde::re::humantime::parse_duration(value) {
+        Ok(Duration::ZERO) => Err("GPU watchdog timeout must be longer than zero".to_string()),
+        result => result.map_err(|err| err.to_string()),
+    }
+}
+
+/// Detects frames the GPU does not finish in time, e.g., because of a
+/// shader stuck in an endless loop.
+///
+/// A fence is inserted after the commands of a frame and polled at the
+/// start of the following frames without waiting, so that the CPU keeps
+/// queuing frames ahead of the GPU. Once the fence signals, the next
+/// frame gets a fence of its own. Each area has a watchdog of its own,
+/// as fences belong to the context of the area.
+pub struct GpuWatchdog {
+    timeout: Duration,
+
+    /// Fence signaled once the commands of the watched frame complete,
+    /// with the time it was inserted.
+    fence: Option<(GLsync, Instant)>,
+}
+
+impl GpuWatchdog {
+    /// Creates a watchdog allowing each frame `timeout` to complete.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            fence: None,
+        }
+    }
+
+    /// Checks, without waiting, whether the watched frame completed.
+    ///
+    /// Returns `false` if it is still unfinished after the timeout.
+    pub fn poll(&mut self) -> bool {
+        let Some((fence, inserted)) = self.fence else {
+            return true;
+        };
+        let result = unsafe { gl::ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, 0) };
+
+        match result {
+            gl::TIMEOUT_EXPIRED => inserted.elapsed() <= self.timeout,
+            result => {
+                if result == gl::WAIT_FAILED {
+                    log::debug!("Failed to poll the GPU watchdog fence");
+                }
+                self.delete_fence();
+                true
+            }
+        }
+    }
+
+    /// Marks the end of the commands of a frame, which is watched unless
+    /// an earlier frame still is.
+    pub fn end_frame(&mut self) {
+        if self.fence.is_some() {
+            return;
+        }
+        let fence = unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) };
+        self.fence = (!fence.is_null()).then(|| (fence, Instant::now()));
+    }
+
+    fn delete_fence(&mut self) {
+        if let Some((fence, _)) = self.fence.take() {
+            unsafe { gl::DeleteSync(fence) };
+        }
+    }
+}
+
+impl Drop for GpuWatchdog {
+    fn drop(&mut self) {
+        self.delete_fence();
+    }
+}
@@ -12,25 +12,264 @@
 //! 2. Pass outputs become textures for subsequent passes.
 //! 3. The final Image pass produces the composited frame.
 //! 4. A blit shader presents the result, optionally crossfading frames.
-
+//! 5. Tiles, each running the pipeline of a preset of their own, are
+//!    presented over the result in their region of the monitor.
+
+#[cfg(test)]
+mod tests {
+    mod animated_image;
+    mod gpu_timer;
+    mod gpu_watchdog;
+    mod pass_graph;
+    mod screen_capture;
+    mod shared_source;
+    mod source_map;
+    mod text_texture;
+    mod texture_manager;
+    mod texture_stream;
+    mod transition;
+}
 pub mod shader;
 
+mod animated_image;
 mod buffer;
 mod check_gl_error;
+mod frame_snapshot;
+mod frame_uniforms;
 mod framebuffer;
+mod gl_state;
+mod gpu_timer;
+mod gpu_watchdog;
+mod pass_graph;
+mod pixel_readback;
 mod program;
 mod render_pass;
+mod screen_capture;
+mod shared_source;
+mod sound_player;
+mod source_map;
+mod text_texture;
 mod texture_manager;
+mod texture_stream;
+mod transition;
 mod vertex_array;
+#[cfg(feature = "video")]
+mod video_decoder;
 
 use gl::types::*;
-use std::collections::HashMap;
+use std::{fmt, sync::OnceLock, time::Duration};
 
 #[cfg(debug_assertions)]
 use check_gl_error::*;
-use {buffer::*, program::*, render_pass::*, shader::*, texture_manager::*, vertex_array::*};
+pub use frame_snapshot::FrameSnapshot;
+pub use gpu_watchdog::{parse_gpu_watchdog_timeout, GpuWatchdog};
+pub use pixel_readback::PixelReadback;
+pub use render_pass::PassSnapshot;
+pub use screen_capture::ScreenCaptureConfig;
+pub use source_map::{SourceMap, SourceSection};
+pub use transition::{builtin_transition_source, TransitionEffect};
+use {
+    buffer::*, frame_uniforms::*, gl_state::*, gpu_timer::*, pass_graph::*, program::*,
+    render_pass::*, shader::*, sound_player::*, texture_manager::*, vertex_array::*,
+};
+
+use crate::{
+    app::InputData, audio_spectrum::AudioBands, desktop_monitor::DesktopState, frame_controller::*,
+    geometry::*, mouse_controller::*, preset::*, sun::SunPosition, system_stats::SystemStats, *,
+};
+
+/// Highest OpenGL ES version whose shading language is targeted.
+const MAX_GLES_VERSION: (i32, i32) = (3, 2);
+
+/// Profile of the OpenGL contexts used for rendering, set once the
+/// first context is realized.
+static GL_PROFILE: OnceLock<GlProfile> = OnceLock::new();
+
+/// OpenGL API and version targeted by shaders and resource formats.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GlProfile {
+    /// Shading language version, as (major, minor).
+    pub version: (i32, i32),
+
+    /// Whether contexts are OpenGL ES.
+    pub es: bool,
+}
+
+impl GlProfile {
+    /// Desktop OpenGL core profile.
+    pub const DESKTOP: Self = Self {
+        version: GL_VERSION,
+        es: false,
+    };
+
+    /// GLSL ES 3.00 of WebGL 2, the dialect of ShaderToy shaders.
+    pub const WEBGL2: Self = Self {
+        version: (3, 0),
+        es: true,
+    };
+
+    /// Returns the profile matching a realized context.
+    ///
+    /// Desktop contexts always target [`GL_VERSION`]. OpenGL ES contexts
+    /// target their own version, up to [`MAX_GLES_VERSION`].
+    pub fn from_context(es: bool, version: (i32, i32)) -> Self {
+        if es {
+            Self {
+                version: version.clamp(GLES_VERSION, MAX_GLES_VERSION),
+                es,
+            }
+        } else {
+            Self::DESKTOP
+        }
+    }
 
-use crate::{app::InputData, frame_controller::*, geometry::*, mouse_controller::*, preset::*, *};
+    /// Returns the `#version` directive of generated shaders followed,
+    /// for OpenGL ES, by the default precision qualifiers that desktop
+    /// GLSL implies.
+    pub fn shader_prelude(self) -> String {
+        let (major, minor) = self.version;
+        if self.es {
+            format!(
+                "#version {major}{minor}0 es\n\
+                 precision highp float;\n\
+                 precision highp int;\n\
+                 precision highp sampler2D;\n\
+                 precision highp sampler3D;\n\
+                 precision highp samplerCube;\n"
+            )
+        } else {
+            format!("#version {major}{minor}0 core\n")
+        }
+    }
+}
+
+impl std::fmt::Display for GlProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let api = if self.es { "OpenGL ES" } else { "OpenGL" };
+        write!(f, "{api} {}.{}", self.version.0, self.version.1)
+    }
+}
+
+/// Sets the profile used by all renderers. Only the first call has effect.
+pub fn set_gl_profile(profile: GlProfile) {
+    if GL_PROFILE.set(profile).is_ok() {
+        log::info!("Rendering with {profile}");
+    }
+}
+
+/// Returns the profile used by all renderers.
+pub fn gl_profile() -> GlProfile {
+    GL_PROFILE.get().copied().unwrap_or(GlProfile::DESKTOP)
+}
+
+/// Returns `true` if the current OpenGL context was lost, e.g., after
+/// a GPU reset or a driver restart.
+///
+/// Only the reset status is queried, which does not synchronize with the
+/// GPU unlike `glGetError`, so it is cheap enough to check every frame.
+/// Contexts without robust access may not report resets.
+pub fn is_context_lost() -> bool {
+    gl::GetGraphicsResetStatus::is_loaded()
+        && unsafe { gl::GetGraphicsResetStatus() } != gl::NO_ERROR
+}
+
+/// Result of compiling one pass of a preset with [`validate_preset`].
+pub struct PassValidation {
+    pub name: String,
+
+    /// Driver warnings on success, or the first error.
+    pub result: Result<String, ShaderError>,
+}
+
+/// Compiles every pass of a preset, including a disabled "Sound" pass,
+/// without creating framebuffers or loading inputs.
+///
+/// Unlike [`Renderer::begin`], failing passes are reported instead of
+/// replaced by the default shader. Requires a current OpenGL context.
+pub fn validate_preset(preset: &Preset) -> Vec<PassValidation> {
+    map_preset_passes(preset, |name, common_shader, pass, inputs| PassValidation {
+        name: name.to_string(),
+        result: validate_pass(
+            name,
+            common_shader,
+            &pass.shader,
+            inputs,
+            &preset.uniforms,
+            preset.base_dir.as_deref(),
+        ),
+    })
+}
+
+/// Fragment shader source of one pass of a preset, built with
+/// [`preset_fragment_sources`].
+pub struct PassSource {
+    pub name: String,
+
+    /// Assembled source along with the map of its lines to the preset
+    /// code, or the error of the compatibility layer.
+    pub result: Result<(String, SourceMap), ShaderError>,
+}
+
+/// Builds the fragment shader source of every pass of a preset for
+/// `profile`, including a disabled "Sound" pass.
+///
+/// Unlike [`validate_preset`], does not require an OpenGL context.
+pub fn preset_fragment_sources(preset: &Preset, profile: GlProfile) -> Vec<PassSource> {
+    map_preset_passes(preset, |name, common_shader, pass, inputs| PassSource {
+        name: name.to_string(),
+        result: pass_fragment_source(
+            name,
+            common_shader,
+            &pass.shader,
+            inputs,
+            &preset.uniforms,
+            profile,
+            preset.base_dir.as_deref(),
+        ),
+    })
+}
+
+/// Maps every pass of a preset, including a disabled "Sound" pass, along
+/// with its name, the shader of the Common pass and the inputs of the
+/// pass.
+fn map_preset_passes<T>(
+    preset: &Preset,
+    mut f: impl FnMut(&str, &str, &Pass, &[Option<Input>; 4]) -> T,
+) -> Vec<T> {
+    let common_shader = preset
+        .common
+        .as_ref()
+        .map_or("", |common_pass| common_pass.shader.as_str());
+
+    preset
+        .render_passes()
+        .chain(preset.sound.as_ref().map(|pass| ("Sound", pass)))
+        .map(|(name, pass)| {
+            let inputs = [
+                pass.input_0.clone(),
+                pass.input_1.clone(),
+                pass.input_2.clone(),
+                pass.input_3.clone(),
+            ];
+            f(name, common_shader, pass, &inputs)
+        })
+        .collect()
+}
+
+/// Returns `true` if the current OpenGL context exposes any of the
+/// given extensions.
+fn has_gl_extension(names: &[&str]) -> bool {
+    let mut num_extensions = 0;
+    unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut num_extensions) };
+
+    (0..num_extensions as u32).any(|i| {
+        let ptr = unsafe { gl::GetStringi(gl::EXTENSIONS, i) };
+        !ptr.is_null() && {
+            let extension = unsafe { std::ffi::CStr::from_ptr(ptr as *const _) }.to_string_lossy();
+            names.contains(&extension.as_ref())
+        }
+    })
+}
 
 /// Fullscreen vertex shader used by the final blit stage.
 const BLIT_VERTEX_SHADER: &str = r#"
@@ -48,9 +287,18 @@ void main() {
 /// Enables crossfade blending between ping-pong buffers in the blit shader.
 const DEFINE_CROSSFADE: &str = "#define SHADERBG_CROSSFADE\n";
 
+/// Enables brightness, contrast, saturation and gamma adjustment in the
+/// blit shader.
+const DEFINE_COLOR_ADJUST: &str = "#define SHADERBG_COLOR_ADJUST\n";
+
+/// Premultiplies the color by the alpha of the Image pass in the blit
+/// shader, as expected by the compositor for transparent surfaces.
+const DEFINE_PREMULTIPLY_ALPHA: &str = "#define SHADERBG_PREMULTIPLY_ALPHA\n";
+
 /// Fragment shader responsible for presenting the final image.
 ///
-/// Supports optional crossfade between two framebuffer textures.
+/// Supports optional crossfade between two framebuffer textures and
+/// optional color adjustment.
 const BLIT_FRAGMENT_SHADER: &str = r#"
 in vec2 fragTexCoord;
 out vec4 fragColor;
@@ -59,6 +307,17 @@ uniform sampler2D iBlitTexture[2];
 #ifdef SHADERBG_CROSSFADE
 uniform float iCrossfadeT;
 #endif
+#ifdef SHADERBG_COLOR_ADJUST
+uniform vec4 iColorAdjust; // brightness, contrast, saturation, gamma
+
+vec3 adjustColor(vec3 color) {
+    color = pow(max(color, 0.0), vec3(1.0 / iColorAdjust.w));
+    color = (color - 0.5) * iColorAdjust.y + 0.5;
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luma), color, iColorAdjust.z);
+    return clamp(color * iColorAdjust.x, 0.0, 1.0);
+}
+#endif
 
 void main() {
     vec4 color0 = texture(iBlitTexture[0], fragTexCoord);
@@ -68,15 +327,15 @@ void main() {
 #else
     fragColor = color0;
 #endif
+#ifdef SHADERBG_COLOR_ADJUST
+    fragColor.rgb = adjustColor(fragColor.rgb);
+#endif
+#ifdef SHADERBG_PREMULTIPLY_ALPHA
+    fragColor = vec4(clamp(fragColor.rgb, 0.0, 1.0) * clamp(fragColor.a, 0.0, 1.0), fragColor.a);
+#endif
 }
 "#;
 
-/// Number of MSAA samples requested for framebuffer creation.
-///
-/// Currently set to zero because MSAA provides little benefit for
-/// fullscreen shader rendering.
-const MSAA_SAMPLES: u32 = 0;
-
 type Position = [f32; 2];
 type TexCoord = [f32; 2];
 type RayDir = [f32; 3];
@@ -96,6 +355,10 @@ struct BlitUniformLocations {
 
     /// Crossfade interpolation parameter.
     i_crossfade_t: GLint,
+
+    /// Brightness, contrast, saturation and gamma.
+    /// `-1` when color adjustment is disabled.
+    i_color_adjust: GLint,
 }
 
 /// Presentation configuration applied during the final blit.
@@ -108,6 +371,25 @@ struct ViewportSettings {
 
     /// Size of the destination viewport.
     size: Size,
+
+    /// Size of the Image pass output once supersampling is resolved,
+    /// before layout mapping.
+    image_size: Size,
+
+    /// How the image is fitted to the viewport by the stretch layout.
+    fit: CloneFit,
+
+    /// Origin and size of the area drawn by the region layout.
+    region: (Point, Size),
+
+    /// Color of the viewport area not covered by the image. `None` when
+    /// drawn over other content, as tiles are.
+    clear_color: Option<[f32; 3]>,
+
+    /// Whether the alpha of the Image pass is presented, premultiplied,
+    /// instead of an opaque frame. The uncovered area is then cleared to
+    /// transparent.
+    transparent: bool,
 }
 
 /// Immutable per-frame rendering inputs shared across render passes.
@@ -129,6 +411,52 @@ pub struct RenderContext<'a> {
 
     /// Frame timing statistics.
     pub frame_stats: &'a FrameStats,
+
+    /// System statistics (`iSystemStats`).
+    pub system_stats: &'a SystemStats,
+
+    /// Levels of the output audio bands (`iAudioBands`).
+    pub audio_bands: &'a AudioBands,
+
+    /// State of the desktop (`iDesktop`).
+    pub desktop: &'a DesktopState,
+
+    /// Fraction of the playing media track (`iTrackProgress`).
+    pub track_progress: f32,
+
+    /// Sun position at the date of the frame (`iDayPhase` and
+    /// `iSunPosition`).
+    pub sun: SunPosition,
+
+    /// Bindings made by the passes of the frame.
+    pub gl_state: &'a GlStateCache,
+}
+
+/// GPU time spent rendering a pass, measured with timer queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTime {
+    /// Name of the pass (e.g. "Image", "Buffer A").
+    pub name: String,
+
+    /// Mean time of the recently measured frames.
+    pub average: Duration,
+}
+
+impl fmt::Display for PassTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.3} ms",
+            self.name,
+            self.average.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Returns `true` if the GPU time of passes can be measured in the
+/// current context.
+pub fn supports_pass_timing() -> bool {
+    GpuTimer::is_supported()
 }
 
 pub struct Renderer {
@@ -138,6 +466,12 @@ pub struct Renderer {
     /// Cached blit shader uniform locations.
     blit_uniform_locations: BlitUniformLocations,
 
+    /// Brightness, contrast, saturation and gamma applied when blitting.
+    color_adjustment: [f32; 4],
+
+    /// Additional brightness multiplier set by night mode.
+    dimming: f32,
+
     /// Vertex arrays for fullscreen quad and cubemap faces.
     /// vaos[0] is for fullscreen, and vaos[1..7] are for cubemap faces.
     vaos: Vec<VertexArray>,
@@ -145,6 +479,12 @@ pub struct Renderer {
     /// Vertex buffers backing the VAOs.
     _vbos: Vec<Buffer>,
 
+    /// Uniform buffer of the ShaderToy uniforms shared by all passes.
+    frame_uniforms: FrameUniforms,
+
+    /// Bindings made by the passes, reset every frame.
+    gl_state: GlStateCache,
+
     /// Framebuffer bound before renderer initialization.
     /// Restored during final presentation.
     original_fbo_id: GLuint,
@@ -152,10 +492,14 @@ pub struct Renderer {
     /// Ordered list of ShaderToy render passes.
     passes: Vec<RenderPass>,
 
+    /// Code of the "Common" pass, prepended to every pass shader.
+    common_shader: String,
+
     /// Full virtual desktop size spanning all monitors, in logical pixels.
     screen_size: Size,
 
-    /// Resolution scaling factor applied to framebuffers.
+    /// Resolution scaling factor applied to framebuffers, combining
+    /// `resolution_scale`, `supersample` and the monitor pixel scale.
     framebuffer_scale: f32,
 
     /// Resolution scaling factor applied to framebuffers.
@@ -167,27 +511,155 @@ pub struct Renderer {
     /// Manages external textures and pass outputs.
     texture_manager: TextureManager,
 
-    /// Tracks the last frame number each ping-pong buffer slot was written, keyed
-    /// by the buffer name + slot suffix (e.g. "Buffer A1").  Used by
-    /// [`RenderPass::set_channel_uniforms`] to choose the correct double-buffer
-    /// offset when a pass reads from another pass's output.
-    buffer_frame_tracker: HashMap<String, u32>,
+    /// "Sound" pass, present only while sound playback is active.
+    sound_pass: Option<RenderPass>,
+
+    /// Audio output fed by the sound pass.
+    sound_player: Option<SoundPlayer>,
+
+    /// Index of the next audio sample to generate.
+    next_sound_sample: u64,
+
+    /// Number of the most recently rendered frame.
+    last_frame_number: u32,
+
+    /// GPU timer of each pass, in the order of `passes`. Empty unless
+    /// profiling.
+    pass_timers: Vec<GpuTimer>,
+
+    /// Renderers of the `[[tile]]` entries, presented over the Image
+    /// pass.
+    tiles: Vec<TileRenderer<Renderer>>,
+}
+
+/// Renderer of a `[[tile]]` entry, animated by the clock of the monitor
+/// at the pace of the tile preset.
+struct TileRenderer<R> {
+    renderer: R,
+
+    /// OpenGL-space origin (bottom-left) of the tile within the monitor,
+    /// in logical pixels.
+    origin: Point,
+
+    /// `time_scale` of the tile preset.
+    time_scale: f64,
+
+    /// `time_offset` of the tile preset, i.e., the time of its first
+    /// frame.
+    time_offset: Duration,
+
+    /// Animation time of the last frame rendered in the tile.
+    time: Option<Duration>,
+}
+
+impl TileRenderer<Renderer> {
+    /// Renders a frame of the tile.
+    ///
+    /// `iTime` and `iTimeDelta` follow the time settings of the tile
+    /// preset, and `iMouse` is relative to the tile.
+    fn render(
+        &mut self,
+        resolution_offset: Offset,
+        input_data: &InputData,
+        frame_stats: &FrameStats,
+    ) {
+        let time_delta = frame_stats.clock_delta.mul_f64(self.time_scale);
+        let time = self.time.map_or(self.time_offset, |time| time + time_delta);
+        self.time = Some(time);
+        let frame_stats = FrameStats {
+            time,
+            time_delta,
+            ..frame_stats.clone()
+        };
+
+        let mouse_data = input_data
+            .mouse
+            .relative_to(self.origin + resolution_offset);
+
+        // Tiles are independent of the position of the monitor
+        self.renderer
+            .render_frame(Offset::default(), input_data, &mouse_data, &frame_stats);
+    }
+}
+
+/// Renderer whose pass shaders are still being compiled.
+///
+/// Returned by [`Renderer::begin`] so that the previous renderer can
+/// keep presenting frames until [`PendingRenderer::is_ready`].
+pub struct PendingRenderer {
+    renderer: Renderer,
+    passes: Vec<PendingRenderPass>,
+    tiles: Vec<TileRenderer<PendingRenderer>>,
+}
+
+impl PendingRenderer {
+    /// Returns `true` if [`PendingRenderer::finish`] will not block
+    /// on shader compilation.
+    pub fn is_ready(&self) -> bool {
+        self.passes.iter().all(PendingRenderPass::is_ready)
+            && self.tiles.iter().all(|tile| tile.renderer.is_ready())
+    }
+
+    /// Waits for the remaining pass shaders and returns the renderer.
+    pub fn finish(self) -> Result<Renderer, ShaderError> {
+        let mut renderer = self.renderer;
+        renderer.passes = self
+            .passes
+            .into_iter()
+            .map(PendingRenderPass::finish)
+            .collect::<Result<_, _>>()?;
+        renderer.texture_manager.load(&renderer.passes);
+        renderer.tiles = self
+            .tiles
+            .into_iter()
+            .map(|tile| {
+                Ok(TileRenderer {
+                    renderer: tile.renderer.finish()?,
+                    origin: tile.origin,
+                    time_scale: tile.time_scale,
+                    time_offset: tile.time_offset,
+                    time: None,
+                })
+            })
+            .collect::<Result<_, ShaderError>>()?;
+
+        Ok(renderer)
+    }
 }
 
 impl Renderer {
+    /// Initializes the renderer, blocking until every pass shader is
+    /// compiled. See [`Renderer::begin`].
+    pub fn new(
+        screen_size: Size,
+        viewport_size: Size,
+        monitor_size: Size,
+        pixel_scale: f32,
+        preset: &Preset,
+    ) -> Result<Self, ShaderError> {
+        Self::begin(
+            screen_size,
+            viewport_size,
+            monitor_size,
+            pixel_scale,
+            preset,
+        )?
+        .finish()
+    }
+
     /// Initializes the renderer with specified viewport and monitor sizes.
     ///
     /// Creates the necessary OpenGL objects like shaders, buffers, and framebuffers.
-    /// This is called during the setup phase of the renderer.
-    pub fn new(
+    /// Pass shaders are all submitted before any is waited for, so drivers
+    /// supporting `GL_KHR_parallel_shader_compile` build them concurrently.
+    pub fn begin(
         // Full virtual desktop size spanning all monitors, in logical pixels.
         // Sizes the offscreen buffer passes so shaders operate in desktop
         // coordinate space. See [`Renderer::screen_size`] field.
         screen_size: Size,
-        // The GTK4 GL area dimensions, in logical pixels as reported by
-        // `on_resize`. GTK4 renders into a logical-pixel framebuffer and
-        // delegates physical-pixel scaling to the Wayland compositor, so
-        // this should not be multiplied by `scale_factor()`.
+        // The GTK4 GL area framebuffer dimensions, as reported by
+        // `on_resize`. GTK already applies the surface scale to these, so
+        // they should not be multiplied by `pixel_scale`.
         // Used only to compute the blit quad UV coordinates (`max_u`/`max_v`)
         // and the viewport-covering check in `blit()`.
         viewport_size: Size,
@@ -197,18 +669,28 @@ impl Renderer {
         // `screen_size` on multi-monitor setups, and from `viewport_size`
         // when `resolution_scale != 1.0`.
         monitor_size: Size,
+        // Physical pixels per logical pixel of the monitor, which is
+        // fractional on HiDPI monitors (e.g., 1.5). Framebuffers, mouse
+        // coordinates and offsets are scaled by this factor so that frames
+        // match physical pixels instead of being upscaled by the compositor.
+        pixel_scale: f32,
         // Loaded preset describing the rendering pipeline.
         preset: &Preset,
-    ) -> Result<Self, ShaderError> {
+    ) -> Result<PendingRenderer, ShaderError> {
         #[cfg(debug_assertions)]
         setup_opengl_debugging();
 
-        let version_directive = format!("#version {}{}0 core\n", GL_VERSION.0, GL_VERSION.1);
+        enable_parallel_compile();
+
+        let version_directive = gl_profile().shader_prelude();
 
         let blit_vertex_source_code = version_directive.clone() + BLIT_VERTEX_SHADER;
         let blit_vertex_shader = Shader::new(&blit_vertex_source_code, gl::VERTEX_SHADER)?;
 
         let crossfade_enabled = preset.crossfade_overlap_ratio > 0.0;
+        let color_adjustment = preset.color_adjustment();
+        let color_adjust_enabled =
+            color_adjustment != Preset::NEUTRAL_COLOR_ADJUSTMENT || preset.night_mode.is_some();
 
         let blit_fragment_source_code = version_directive
             + if crossfade_enabled {
@@ -216,6 +698,16 @@ impl Renderer {
             } else {
                 ""
             }
+            + if color_adjust_enabled {
+                DEFINE_COLOR_ADJUST
+            } else {
+                ""
+            }
+            + if preset.transparent {
+                DEFINE_PREMULTIPLY_ALPHA
+            } else {
+                ""
+            }
             + BLIT_FRAGMENT_SHADER;
         let blit_fragment_shader = Shader::new(&blit_fragment_source_code, gl::FRAGMENT_SHADER)?;
 
@@ -226,33 +718,56 @@ impl Renderer {
         } else {
             0
         };
+        let i_color_adjust = if color_adjust_enabled {
+            blit_program.uniform_location("iColorAdjust")?
+        } else {
+            -1
+        };
 
         let msaa_samples = {
             let mut max_msaa_samples = 0;
             unsafe { gl::GetIntegerv(gl::MAX_SAMPLES, &mut max_msaa_samples) };
-            std::cmp::min(MSAA_SAMPLES, max_msaa_samples as u32)
+            std::cmp::min(preset.msaa_samples, max_msaa_samples as u32)
         };
 
         // Save currently bound FBO
         let mut original_fbo_id = 0;
         unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut original_fbo_id) };
 
-        let framebuffer_scale = preset.resolution_scale.max(0.0);
-        let framebuffer_size = {
-            let mut size = monitor_size * framebuffer_scale;
+        let resolution_scale = preset.resolution_scale.max(0.0) * pixel_scale.max(0.0);
+        let image_size = {
+            let mut size = monitor_size * resolution_scale;
             size.set_width(size.width().max(1));
             size.set_height(size.height().max(1));
             size
         };
 
+        // Supersampled passes are rendered larger and filtered down when blitting
+        let supersample = preset.supersample.max(1);
+        let framebuffer_scale = resolution_scale * supersample as f32;
+        let framebuffer_size = Size::new(
+            image_size.width() * supersample,
+            image_size.height() * supersample,
+        );
+
+        // Bilinear filtering only averages 2x2 texels, so larger factors need mipmaps
+        let filter = if supersample > 2 && preset.filter_mode == FilterMode::Linear {
+            FilterMode::Mipmap
+        } else {
+            preset.filter_mode
+        };
+
         // Create and bind vertex array and buffers
         let vao = VertexArray::new();
         vao.bind();
 
-        let mut max_u = (viewport_size.width() as f32 / framebuffer_size.width() as f32).max(1.0);
-        let mut max_v = (viewport_size.height() as f32 / framebuffer_size.height() as f32).max(1.0);
+        let mut max_u = (viewport_size.width() as f32 / image_size.width() as f32).max(1.0);
+        let mut max_v = (viewport_size.height() as f32 / image_size.height() as f32).max(1.0);
 
-        if preset.layout_mode == LayoutMode::Stretch || preset.layout_mode == LayoutMode::Center {
+        if matches!(
+            preset.layout_mode,
+            LayoutMode::Stretch | LayoutMode::Center | LayoutMode::Region
+        ) {
             max_u = 1.0;
             max_v = 1.0;
         }
@@ -342,16 +857,7 @@ impl Renderer {
             }
         }
 
-        // Create buffer passes
         let offscreen_size = screen_size * framebuffer_scale;
-        let passes_settings = [
-            ("Buffer A", preset.buffer_a.as_ref(), offscreen_size),
-            ("Buffer B", preset.buffer_b.as_ref(), offscreen_size),
-            ("Buffer C", preset.buffer_c.as_ref(), offscreen_size),
-            ("Buffer D", preset.buffer_d.as_ref(), offscreen_size),
-            ("Cube A", preset.cube_a.as_ref(), offscreen_size),
-            ("Image", Some(&preset.image), framebuffer_size),
-        ];
 
         let common_shader = if let Some(common_pass) = preset.common.as_ref() {
             &common_pass.shader
@@ -359,53 +865,376 @@ impl Renderer {
             ""
         };
 
+        // Create render passes in ShaderToy order, or in dependency order
+        // if the preset opts in, with Image last as it is the one presented
+        let (image_passes, buffer_passes): (Vec<_>, Vec<_>) = preset
+            .render_passes()
+            .partition(|(name, _)| *name == "Image");
+        let nodes: Vec<PassNode> = buffer_passes
+            .iter()
+            .map(|(name, pass_cfg)| PassNode {
+                output: output_name(name),
+                inputs: [
+                    &pass_cfg.input_0,
+                    &pass_cfg.input_1,
+                    &pass_cfg.input_2,
+                    &pass_cfg.input_3,
+                ]
+                .into_iter()
+                .flatten()
+                .map(|input| input.name.as_str())
+                .collect(),
+            })
+            .collect();
+        let order = match preset.pass_order {
+            PassOrder::Shadertoy => (0..nodes.len()).collect(),
+            PassOrder::Dependencies => render_order(&nodes),
+        };
+        let ordered_passes: Vec<_> = order
+            .into_iter()
+            .map(|index| buffer_passes[index])
+            .chain(image_passes)
+            .collect();
+        log::debug!(
+            "Pass order: {}",
+            ordered_passes
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        // Outputs sampled with the mipmap filter need their mip chain
+        // regenerated every frame
+        let mipmapped_outputs: Vec<&str> = ordered_passes
+            .iter()
+            .flat_map(|(_, pass_cfg)| {
+                [
+                    &pass_cfg.input_0,
+                    &pass_cfg.input_1,
+                    &pass_cfg.input_2,
+                    &pass_cfg.input_3,
+                ]
+            })
+            .flatten()
+            .filter(|input| input._type == InputType::Misc && input.filter == FilterMode::Mipmap)
+            .map(|input| input.name.as_str())
+            .collect();
+
         let mut passes = Vec::new();
-        for (name, pass_cfg_opt, size) in passes_settings.iter() {
-            if let Some(pass_cfg) = pass_cfg_opt {
-                let inputs: [Option<Input>; 4] = [
-                    pass_cfg.input_0.clone(),
-                    pass_cfg.input_1.clone(),
-                    pass_cfg.input_2.clone(),
-                    pass_cfg.input_3.clone(),
-                ];
-                let pass = RenderPass::new(
-                    name,
-                    common_shader,
-                    &pass_cfg.shader,
-                    *size,
-                    inputs,
-                    msaa_samples,
-                )?;
-                passes.push(pass);
-            }
+        for (order, (name, pass_cfg)) in ordered_passes.into_iter().enumerate() {
+            let output = output_name(name);
+            let inputs: [Option<Input>; 4] = [
+                pass_cfg.input_0.clone(),
+                pass_cfg.input_1.clone(),
+                pass_cfg.input_2.clone(),
+                pass_cfg.input_3.clone(),
+            ];
+            let size = if name == "Image" {
+                framebuffer_size
+            } else {
+                offscreen_size
+            };
+            let pass = RenderPass::begin(
+                name,
+                common_shader,
+                &pass_cfg.shader,
+                size,
+                inputs,
+                &preset.uniforms,
+                preset.base_dir.as_deref(),
+            )?
+            .with_order(order)
+            .with_msaa_samples(msaa_samples)
+            .with_fallback_image(preset.fallback_image_path())
+            .with_buffer_precision(pass_cfg.buffer_precision.unwrap_or(preset.buffer_precision))
+            .with_scale(pass_cfg.scale.unwrap_or(1.0))
+            .with_mipmaps(mipmapped_outputs.contains(&output))
+            .with_alpha(preset.transparent);
+            passes.push(pass);
         }
 
-        let mut texture_manager = TextureManager::new();
-        texture_manager.load(&passes);
-
-        Ok(Self {
+        let renderer = Self {
             blit_program,
             blit_uniform_locations: BlitUniformLocations {
                 i_blit_texture,
                 i_crossfade_t,
+                i_color_adjust,
             },
+            color_adjustment,
+            dimming: 1.0,
             vaos,
             _vbos: vbos,
+            frame_uniforms: FrameUniforms::new(),
+            gl_state: GlStateCache::default(),
             original_fbo_id: original_fbo_id as GLuint,
-            passes,
+            passes: Vec::new(),
+            common_shader: common_shader.to_string(),
             screen_size,
             framebuffer_scale,
             viewport_settings: ViewportSettings {
-                filter: preset.filter_mode,
+                filter,
                 mapping: preset.layout_mode,
                 size: viewport_size,
+                image_size,
+                fit: if preset.screen_bounds_policy == ScreenBoundsPolicy::Cloned {
+                    preset.clone_fit
+                } else {
+                    CloneFit::Stretch
+                },
+                region: (
+                    preset.region.origin(pixel_scale),
+                    preset.region.size_within(viewport_size, pixel_scale),
+                ),
+                clear_color: Some(match preset.layout_mode {
+                    LayoutMode::Region => preset.region.color,
+                    _ => [0.0; 3],
+                }),
+                transparent: preset.transparent,
             },
             msaa_samples,
-            texture_manager,
-            buffer_frame_tracker: HashMap::new(),
+            texture_manager: TextureManager::new(preset.base_dir.clone()),
+            sound_pass: None,
+            sound_player: None,
+            next_sound_sample: 0,
+            last_frame_number: 0,
+            pass_timers: Vec::new(),
+            tiles: Vec::new(),
+        };
+
+        // Tiles are laid out as regions of the monitor
+        let tiles = preset
+            .tiles
+            .iter()
+            .filter_map(|tile| Some((tile.rect, tile.loaded.as_deref()?)))
+            .map(|(rect, tile_preset)| {
+                let region = Region::from_rect(rect, monitor_size);
+                let tile_size = region.size_within(monitor_size, 1.0);
+                let tile_preset = Preset {
+                    layout_mode: LayoutMode::Region,
+                    region,
+                    tiles: Vec::new(),
+                    ..tile_preset.clone()
+                };
+                let mut renderer = Self::begin(
+                    tile_size,
+                    viewport_size,
+                    tile_size,
+                    pixel_scale,
+                    &tile_preset,
+                )?;
+                renderer.renderer.viewport_settings.clear_color = None;
+                Ok(TileRenderer {
+                    renderer,
+                    origin: tile_preset.region.origin(1.0),
+                    time_scale: tile_preset.time_scale.max(0.0),
+                    time_offset: tile_preset.time_offset,
+                    time: None,
+                })
+            })
+            .collect::<Result<_, ShaderError>>()?;
+
+        Ok(PendingRenderer {
+            renderer,
+            passes,
+            tiles,
         })
     }
 
+    /// Recompiles the shader of a single pass without recreating
+    /// framebuffers or textures, so rendering continues seamlessly.
+    ///
+    /// Passes of tiles are named as by [`Tile::pass_name`].
+    /// `"Common"` rebuilds every pass with the new common code. If
+    /// compilation fails, the error is logged and the previous program
+    /// stays in use. Returns `Ok(false)` if the pass does not exist or
+    /// cannot be reloaded in place, in which case the renderer must be
+    /// recreated. The "Sound" pass is ignored by renderers not playing it.
+    pub fn reload_pass(&mut self, name: &str, source: &str) -> Result<bool, ShaderError> {
+        if let Some((index, name)) = Tile::split_pass_name(name) {
+            return match self.tiles.get_mut(index) {
+                Some(tile) => tile.renderer.reload_pass(name, source),
+                None => Ok(false),
+            };
+        }
+
+        let mut passes = self.passes.iter_mut().chain(self.sound_pass.as_mut());
+
+        if name == "Common" {
+            // Every pass is compiled before any program is replaced, so
+            // that a failure leaves all of them with the previous code
+            let mut recompiled = Vec::new();
+            for pass in passes {
+                match pass.recompile(source, pass.shader())? {
+                    Some(program) => recompiled.push((pass, program)),
+                    None => return Ok(false),
+                }
+            }
+            for (pass, program) in recompiled {
+                pass.replace_program(program);
+            }
+            self.common_shader = source.to_string();
+            return Ok(true);
+        }
+
+        match passes.find(|pass| pass.name() == name) {
+            Some(pass) => pass.reload(&self.common_shader, source),
+            None => Ok(name == "Sound"),
+        }
+    }
+
+    /// Starts playing the preset "Sound" pass, if enabled.
+    ///
+    /// Only one renderer should play sound when several monitors
+    /// are rendered. Failures are logged and leave the renderer silent.
+    pub fn start_sound(&mut self, preset: &Preset) {
+        let Some(sound) = preset.sound.as_ref().filter(|_| preset.enable_sound) else {
+            return;
+        };
+
+        let common_shader = preset
+            .common
+            .as_ref()
+            .map_or("", |common_pass| common_pass.shader.as_str());
+        let inputs = [
+            sound.input_0.clone(),
+            sound.input_1.clone(),
+            sound.input_2.clone(),
+            sound.input_3.clone(),
+        ];
+
+        // Sound blocks are generated after every pass has rendered
+        let sound_pass = match RenderPass::begin(
+            "Sound",
+            common_shader,
+            &sound.shader,
+            Size::default(),
+            inputs,
+            &preset.uniforms,
+            preset.base_dir.as_deref(),
+        )
+        .and_then(|pass| pass.with_order(usize::MAX).finish())
+        {
+            Ok(sound_pass) => sound_pass,
+            Err(err) => {
+                log::error!("Sound disabled: {err}");
+                return;
+            }
+        };
+
+        match SoundPlayer::new(preset.sound_volume) {
+            Ok(sound_player) => {
+                self.texture_manager.load(std::slice::from_ref(&sound_pass));
+                self.sound_pass = Some(sound_pass);
+                self.sound_player = Some(sound_player);
+                self.next_sound_sample = 0;
+            }
+            Err(err) => log::error!("Sound disabled: {err}"),
+        }
+    }
+
+    /// Stops playing the "Sound" pass, if playing.
+    pub fn stop_sound(&mut self) {
+        self.sound_player = None;
+        self.sound_pass = None;
+    }
+
+    /// Pauses or resumes media inputs, freezing their `iChannelTime`.
+    pub fn set_media_paused(&mut self, paused: bool) {
+        self.texture_manager.set_media_paused(paused);
+        for tile in &mut self.tiles {
+            tile.renderer.set_media_paused(paused);
+        }
+    }
+
+    /// Moves media inputs to the given playback position.
+    pub fn seek_media(&mut self, position: Duration) {
+        self.texture_manager.seek_media(position);
+        for tile in &mut self.tiles {
+            tile.renderer.seek_media(position);
+        }
+    }
+
+    /// Multiplies the brightness of presented frames, e.g., for night mode.
+    ///
+    /// Has no effect unless the preset enables night mode or color
+    /// adjustment, as the blit shader is otherwise skipped.
+    pub fn set_dimming(&mut self, factor: f32) {
+        self.dimming = factor;
+        for tile in &mut self.tiles {
+            tile.renderer.set_dimming(factor);
+        }
+    }
+
+    /// Copies the contents of the buffer passes into the passes of the
+    /// same name of `renderer`, rescaled to its buffer size.
+    pub fn copy_buffers_to(&self, renderer: &Renderer) {
+        for pass in &renderer.passes {
+            if let Some(previous) = self.passes.iter().find(|p| p.name() == pass.name()) {
+                pass.copy_framebuffers_from(previous);
+            }
+        }
+    }
+
+    /// Reads back the contents of the buffer passes, to be restored with
+    /// [`Renderer::restore_buffers`] by the renderer of a new window.
+    pub fn read_buffers(&self) -> Vec<PassSnapshot> {
+        self.passes
+            .iter()
+            .filter_map(RenderPass::snapshot_framebuffers)
+            .collect()
+    }
+
+    /// Restores the contents of the buffer passes from the snapshots of
+    /// the passes of the same name, rescaled to their buffer size.
+    pub fn restore_buffers(&self, snapshots: &[PassSnapshot]) {
+        for pass in &self.passes {
+            if let Some(snapshot) = snapshots.iter().find(|s| s.name == pass.name()) {
+                pass.restore_framebuffers(snapshot);
+            }
+        }
+    }
+
+    /// Starts or stops measuring the GPU time of each pass.
+    ///
+    /// Does nothing if the context does not support timer queries.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        let profiling = !self.pass_timers.is_empty();
+        if enabled == profiling {
+            return;
+        }
+        self.pass_timers = if enabled && GpuTimer::is_supported() {
+            self.passes.iter().map(|_| GpuTimer::new()).collect()
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Returns the GPU time measured for each pass, in rendering order.
+    ///
+    /// Passes not measured yet are left out, so the list is empty when
+    /// not profiling.
+    pub fn pass_times(&self) -> Vec<PassTime> {
+        self.passes
+            .iter()
+            .zip(&self.pass_timers)
+            .filter_map(|(pass, timer)| {
+                Some(PassTime {
+                    name: pass.name().to_string(),
+                    average: timer.average()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the size of the Image pass framebuffer.
+    pub fn resolution(&self) -> Size {
+        self.passes
+            .last()
+            .map(|pass| pass.framebuffers()[0].size())
+            .unwrap_or_default()
+    }
+
     /// Executes all render passes for the current frame.
     ///
     /// Each pass renders into its framebuffer and feeds subsequent passes.
@@ -416,6 +1245,23 @@ impl Renderer {
         resolution_offset: Offset,
         input_data: &InputData,
         frame_stats: &FrameStats,
+    ) {
+        self.render_frame(
+            resolution_offset,
+            input_data,
+            &input_data.mouse,
+            frame_stats,
+        );
+    }
+
+    /// Executes all render passes for the current frame, with the given
+    /// `iMouse` data.
+    fn render_frame(
+        &mut self,
+        resolution_offset: Offset,
+        input_data: &InputData,
+        mouse_data: &MouseData,
+        frame_stats: &FrameStats,
     ) {
         log::trace!(
             "Frame {}: t={:.2} s, Δt {:.1} ms, {:.1} FPS",
@@ -425,10 +1271,20 @@ impl Renderer {
             frame_stats.frame_rate
         );
 
+        self.last_frame_number = frame_stats.frame_number;
+
         if let Some(keyboard_data) = &input_data.keyboard {
             self.texture_manager.update_keyboard_texture(keyboard_data);
         }
 
+        self.texture_manager.update_video_textures();
+        self.texture_manager.update_stream_textures();
+        self.texture_manager.update_screen_textures();
+        self.texture_manager.update_animated_textures();
+        self.texture_manager.update_text_textures();
+        self.texture_manager
+            .update_album_art_textures(input_data.media.album_art.as_ref());
+
         let mut scaled_resolution = self.screen_size * self.framebuffer_scale;
         scaled_resolution.set_width(scaled_resolution.width().max(1));
         scaled_resolution.set_height(scaled_resolution.height().max(1));
@@ -436,55 +1292,145 @@ impl Renderer {
         let ctx = RenderContext {
             texture_manager: &self.texture_manager,
             vaos: &self.vaos,
-            mouse_data: &input_data.mouse,
+            mouse_data,
             scaled_resolution,
             framebuffer_scale: self.framebuffer_scale,
             frame_stats,
+            system_stats: &input_data.system_stats,
+            audio_bands: &input_data.audio_bands,
+            desktop: &input_data.desktop,
+            track_progress: input_data.media.track_progress,
+            sun: SunPosition::at_local(frame_stats.date),
+            gl_state: &self.gl_state,
         };
 
         let scaled_resolution_offset = resolution_offset * self.framebuffer_scale;
 
-        for pass in &self.passes {
-            pass.render_pass(
-                &ctx,
-                &mut self.buffer_frame_tracker,
-                scaled_resolution_offset,
-            );
+        // Textures were bound while updating them
+        self.gl_state.invalidate();
+        self.frame_uniforms.update(&ctx);
+
+        if self.pass_timers.is_empty() {
+            for pass in &self.passes {
+                pass.render_pass(&ctx, scaled_resolution_offset);
+            }
+        } else {
+            for (pass, timer) in self.passes.iter().zip(&mut self.pass_timers) {
+                timer.begin();
+                pass.render_pass(&ctx, scaled_resolution_offset);
+                timer.end();
+            }
         }
+
+        // Keep audio playback fed ahead of time
+        if let (Some(sound_pass), Some(sound_player)) =
+            (&self.sound_pass, self.sound_player.as_mut())
+        {
+            while sound_player.wants_block() {
+                let samples = sound_pass.render_sound_block(&ctx, self.next_sound_sample);
+                self.next_sound_sample += samples.len() as u64 / 2;
+                sound_player.queue(&samples);
+            }
+        }
+
+        for tile in &mut self.tiles {
+            tile.render(resolution_offset, input_data, frame_stats);
+        }
+    }
+
+    /// Reads back the most recently rendered Image pass output.
+    ///
+    /// The image has the size of the Image pass framebuffer,
+    /// i.e., the monitor size scaled by `resolution_scale`.
+    pub fn read_frame(&self) -> image::RgbaImage {
+        let framebuffer =
+            &self.passes.last().unwrap().framebuffers()[output_framebuffer(self.last_frame_number)];
+        let size = framebuffer.size();
+
+        let mut image =
+            image::RgbaImage::from_raw(size.width(), size.height(), framebuffer.read_pixels())
+                .expect("Framebuffer size mismatch");
+
+        // OpenGL rows start at the bottom
+        image::imageops::flip_vertical_in_place(&mut image);
+
+        image
+    }
+
+    /// Starts reading back the last rendered frame without waiting for
+    /// the GPU.
+    pub fn begin_read_frame(&self) -> PixelReadback {
+        self.passes.last().unwrap().framebuffers()[output_framebuffer(self.last_frame_number)]
+            .begin_read_pixels()
+    }
+
+    /// Presents the last rendered frame to the current drawing surface.
+    pub fn blit(&self, crossfade_t: f32) {
+        self.blit_over(crossfade_t, 1.0);
     }
 
-    /// Presents the final Image pass to the window framebuffer.
+    /// Presents the last rendered frame blended over the contents of the
+    /// current drawing surface with the given opacity, e.g., to fade in
+    /// a new preset over the previous one.
+    pub fn blit_over(&self, crossfade_t: f32, opacity: f32) {
+        self.present(crossfade_t, opacity, self.original_fbo_id);
+    }
+
+    /// Presents the last rendered frame into the given framebuffer
+    /// instead of the drawing surface, e.g., to compose a transition
+    /// effect.
+    pub fn blit_into(&self, crossfade_t: f32, fbo_id: GLuint) {
+        self.present(crossfade_t, 1.0, fbo_id);
+    }
+
+    /// Presents the final Image pass to the framebuffer `target_fbo`.
     ///
     /// Depending on configuration, this may perform crossfade blending,
     /// apply scaling or layout mapping, generate mipmaps,
-    /// or perform a direct framebuffer blit.
-    pub fn blit(&self, crossfade_t: f32) {
+    /// or perform a direct framebuffer blit. Translucent frames are
+    /// always drawn with the blit shader, since framebuffer blits do not
+    /// blend, as are transparent frames, which must be premultiplied.
+    fn present(&self, crossfade_t: f32, opacity: f32, target_fbo: GLuint) {
+        let blending_enabled = opacity < 1.0;
         let crossfade_enabled = self.blit_uniform_locations.i_crossfade_t > 0;
+        let color_adjust_enabled = self.blit_uniform_locations.i_color_adjust >= 0;
         let mipmapping_enabled = self.viewport_settings.filter == FilterMode::Mipmap;
 
-        let framebuffer_size = self.passes.last().unwrap().framebuffers()[0].size();
-        let origin = match self.viewport_settings.mapping {
-            LayoutMode::Center => {
-                Point::new(
-                    self.viewport_settings.size.width() as i32 - framebuffer_size.width() as i32,
-                    self.viewport_settings.size.height() as i32 - framebuffer_size.height() as i32,
-                ) * 0.5
-            }
-            _ => Point::default(),
-        };
-        let size = match self.viewport_settings.mapping {
-            LayoutMode::Stretch => self.viewport_settings.size,
-            _ => framebuffer_size,
+        let framebuffer_size = self.viewport_settings.image_size;
+        let (origin, size) = match self.viewport_settings.mapping {
+            LayoutMode::Stretch => fit_to_viewport(
+                self.viewport_settings.fit,
+                framebuffer_size,
+                self.viewport_settings.size,
+            ),
+            LayoutMode::Center => (
+                centered_origin(framebuffer_size, self.viewport_settings.size),
+                framebuffer_size,
+            ),
+            LayoutMode::Region => self.viewport_settings.region,
+            _ => (Point::default(), framebuffer_size),
         };
 
         // Clear the full viewport before blitting when the content doesn't cover
         // every pixel (e.g. center layout with framebuffer_scale < 1).
-        let covers_full_viewport = origin == Point::default()
-            && size.width() >= self.viewport_settings.size.width()
-            && size.height() >= self.viewport_settings.size.height();
-        if !covers_full_viewport {
+        let covers_full_viewport = origin.x() <= 0
+            && origin.y() <= 0
+            && origin.x() + size.width() as i32 >= self.viewport_settings.size.width() as i32
+            && origin.y() + size.height() as i32 >= self.viewport_settings.size.height() as i32;
+        // Translucent frames keep the uncovered parts of the frame below
+        let clear_color = self
+            .viewport_settings
+            .clear_color
+            .filter(|_| !covers_full_viewport && !blending_enabled);
+        if let Some([red, green, blue]) = clear_color {
+            let alpha = if self.viewport_settings.transparent {
+                0.0
+            } else {
+                1.0
+            };
             unsafe {
-                gl::BindFramebuffer(gl::FRAMEBUFFER, self.original_fbo_id);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, target_fbo);
+                gl::ClearColor(red * alpha, green * alpha, blue * alpha, alpha);
                 gl::Viewport(
                     0,
                     0,
@@ -497,13 +1443,16 @@ impl Renderer {
 
         if self.msaa_samples > 0
             || self.framebuffer_scale > 1.0
+            || blending_enabled
             || crossfade_enabled
+            || color_adjust_enabled
             || mipmapping_enabled
+            || self.viewport_settings.transparent
             || self.viewport_settings.mapping == LayoutMode::Repeat
             || self.viewport_settings.mapping == LayoutMode::MirroredRepeat
         {
             let viewport_size = match self.viewport_settings.mapping {
-                LayoutMode::Stretch | LayoutMode::Center => size,
+                LayoutMode::Stretch | LayoutMode::Center | LayoutMode::Region => size,
                 _ => self.viewport_settings.size,
             };
 
@@ -561,14 +1510,35 @@ impl Renderer {
                     gl::Uniform1f(self.blit_uniform_locations.i_crossfade_t, crossfade_t);
                 }
 
+                if color_adjust_enabled {
+                    let [brightness, contrast, saturation, gamma] = self.color_adjustment;
+                    gl::Uniform4f(
+                        self.blit_uniform_locations.i_color_adjust,
+                        brightness * self.dimming,
+                        contrast,
+                        saturation,
+                        gamma,
+                    );
+                }
+
                 if self.blit_uniform_locations.i_blit_texture >= 0 {
                     const DATA: [i32; 2] = [0, 1];
                     gl::Uniform1iv(self.blit_uniform_locations.i_blit_texture, 2, DATA.as_ptr());
                 }
 
-                gl::BindFramebuffer(gl::FRAMEBUFFER, self.original_fbo_id);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, target_fbo);
+
+                if blending_enabled {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendColor(0.0, 0.0, 0.0, opacity.max(0.0));
+                    gl::BlendFunc(gl::CONSTANT_ALPHA, gl::ONE_MINUS_CONSTANT_ALPHA);
+                }
 
                 gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+
+                if blending_enabled {
+                    gl::Disable(gl::BLEND);
+                }
             }
         } else {
             let filter = match self.viewport_settings.filter {
@@ -576,12 +1546,39 @@ impl Renderer {
                 _ => gl::LINEAR,
             };
 
-            self.passes.last().unwrap().framebuffers()[0].blit_to(
-                self.original_fbo_id,
-                origin,
-                size,
-                filter,
-            );
+            self.passes.last().unwrap().framebuffers()[0].blit_to(target_fbo, origin, size, filter);
+        }
+
+        for tile in &self.tiles {
+            tile.renderer.present(crossfade_t, opacity, target_fbo);
         }
     }
 }
+
+/// Returns the origin that centers content of `size` in `viewport_size`.
+///
+/// The origin is negative along axes where the content is larger.
+fn centered_origin(size: Size, viewport_size: Size) -> Point {
+    Point::new(
+        viewport_size.width() as i32 - size.width() as i32,
+        viewport_size.height() as i32 - size.height() as i32,
+    ) * 0.5
+}
+
+/// Returns the origin and size at which an image is drawn to fit the
+/// viewport according to `fit`.
+fn fit_to_viewport(fit: CloneFit, image_size: Size, viewport_size: Size) -> (Point, Size) {
+    let scale_x = viewport_size.width() as f32 / image_size.width().max(1) as f32;
+    let scale_y = viewport_size.height() as f32 / image_size.height().max(1) as f32;
+    let scale = match fit {
+        CloneFit::Stretch => return (Point::default(), viewport_size),
+        CloneFit::Contain => scale_x.min(scale_y),
+        CloneFit::Cover => scale_x.max(scale_y),
+    };
+
+    let size = Size::new(
+        (image_size.width() as f32 * scale).round() as u32,
+        (image_size.height() as f32 * scale).round() as u32,
+    );
+    (centered_origin(size, viewport_size), size)
+}
@@ -0,0 +1,112 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Rendering order of the passes of a frame.
+//!
+//! By default, passes are rendered in the fixed order of ShaderToy, and
+//! a pass sampling one rendered later reads its output of the previous
+//! frame. Presets may instead opt in to dependency order: passes form a
+//! graph whose edges go from the pass producing an output to every pass
+//! sampling it, and are rendered in topological order, so a pass reads
+//! the outputs of its dependencies from the current frame. Edges closing
+//! a cycle, including a pass sampling itself, read the output of the
+//! previous frame instead, which is how feedback effects are implemented.
+//!
+//! Ties are broken by the order of the passes in the preset, so presets
+//! without forward references render in ShaderToy order either way.
+//!
+//! Each pass has two framebuffers, alternating every frame between the
+//! one written and the one holding the output of the previous frame.
+//! The framebuffer sampled by a pass is chosen by rendering order alone,
+//! so "Cube A" sampling "Cubemap A" works like any other self-feedback.
+
+/// Node of the pass graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassNode<'a> {
+    /// Name under which other passes sample the output of the pass
+    /// (e.g., "Buffer A", "Cubemap A").
+    pub output: &'a str,
+
+    /// Names of the inputs sampled by the pass.
+    pub inputs: Vec<&'a str>,
+}
+
+/// Returns the name under which other passes sample the output of the
+/// pass `pass_name`.
+pub fn output_name(pass_name: &str) -> &str {
+    if pass_name == "Cube A" {
+        "Cubemap A"
+    } else {
+        pass_name
+    }
+}
+
+/// Returns the index of the framebuffer written by every pass on frame
+/// `frame_number`.
+pub fn output_framebuffer(frame_number: u32) -> usize {
+    (frame_number % 2) as usize ^ 1
+}
+
+/// Returns the index of the framebuffer of the pass rendered at
+/// `input_order` sampled on frame `frame_number` by the pass rendered at
+/// `order`.
+///
+/// Passes rendered earlier in the frame are read from the framebuffer
+/// written this frame, and the others, including the pass itself, from
+/// the one written last frame. A pass thus never reads the framebuffer
+/// it is writing.
+pub fn input_framebuffer(input_order: usize, order: usize, frame_number: u32) -> usize {
+    if input_order < order {
+        output_framebuffer(frame_number)
+    } else {
+        output_framebuffer(frame_number) ^ 1
+    }
+}
+
+/// Returns the indices of `nodes` in rendering order.
+///
+/// When every remaining pass depends on another remaining pass, the
+/// first of them in preset order is rendered next, so that its inputs
+/// from the rest of the cycle read the previous frame.
+pub fn render_order(nodes: &[PassNode]) -> Vec<usize> {
+    // Dependencies of each node on other nodes, ignoring self-feedback
+    let dependencies: Vec<Vec<usize>> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let mut dependencies: Vec<usize> = node
+                .inputs
+                .iter()
+                .filter_map(|input| nodes.iter().position(|other| other.output == *input))
+                .filter(|&dependency| dependency != index)
+                .collect();
+            dependencies.sort_unstable();
+            dependencies.dedup();
+            dependencies
+        })
+        .collect();
+
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut rendered = vec![false; nodes.len()];
+
+    while order.len() < nodes.len() {
+        let is_ready = |index: usize| {
+            !rendered[index]
+                && dependencies[index]
+                    .iter()
+                    .all(|&dependency| rendered[dependency])
+        };
+
+        let next = (0..nodes.len())
+            .find(|&index| is_ready(index))
+            .or_else(|| (0..nodes.len()).find(|&index| !rendered[index]))
+            .expect("Some pass must remain");
+
+        rendered[next] = true;
+        order.push(next);
+    }
+
+    order
+}
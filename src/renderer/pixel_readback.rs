@@ -0,0 +1,159 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Asynchronous readback of framebuffer pixels.
+//!
+//! Pixels are copied into a pixel buffer object by the GPU once the
+//! commands issued before the readback complete, and mapped only after a
+//! fence signals that the copy is done, so that reading back a frame
+//! does not stall rendering.
+//!
+//! Pixels are read in the type of the color attachment, since OpenGL ES
+//! only guarantees reading floating-point attachments as floats, and
+//! converted to RGBA8 once mapped.
+
+use gl::types::*;
+
+use crate::geometry::Size;
+
+use super::buffer::Buffer;
+
+/// Pending copy of the RGBA pixels of a framebuffer.
+///
+/// Must be finished or dropped with the context of the framebuffer
+/// current.
+pub struct PixelReadback {
+    buffer: Buffer,
+    fence: GLsync,
+    size: Size,
+
+    /// Type of the copied pixels: `FLOAT` or `UNSIGNED_BYTE`.
+    pixel_type: GLenum,
+}
+
+impl PixelReadback {
+    /// Starts copying the color attachment of the framebuffer `fbo_id`
+    /// of the given size, as RGBA pixels of type `pixel_type` (`FLOAT`
+    /// or `UNSIGNED_BYTE`).
+    ///
+    /// The read framebuffer and pack alignment are restored before
+    /// returning.
+    pub fn new(fbo_id: GLuint, size: Size, pixel_type: GLenum) -> Self {
+        let buffer = Buffer::new(gl::PIXEL_PACK_BUFFER);
+        buffer.allocate(byte_count(size, pixel_type), gl::STREAM_READ);
+
+        let fence = unsafe {
+            let mut previous_fbo = 0;
+            let mut previous_alignment = 0;
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::PACK_ALIGNMENT, &mut previous_alignment);
+
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, fbo_id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            // Reads into the bound pixel buffer, at offset 0
+            gl::ReadPixels(
+                0,
+                0,
+                size.width() as i32,
+                size.height() as i32,
+                gl::RGBA,
+                pixel_type,
+                std::ptr::null_mut(),
+            );
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+
+            gl::PixelStorei(gl::PACK_ALIGNMENT, previous_alignment);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous_fbo as GLuint);
+
+            let fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            // Ensure the fence is submitted, or it may never signal
+            gl::Flush();
+            fence
+        };
+
+        Self {
+            buffer,
+            fence,
+            size,
+            pixel_type,
+        }
+    }
+
+    /// Returns `true` once the pixels are copied and
+    /// [`PixelReadback::finish`] will not block.
+    pub fn is_ready(&self) -> bool {
+        let status = unsafe { gl::ClientWaitSync(self.fence, 0, 0) };
+        matches!(status, gl::ALREADY_SIGNALED | gl::CONDITION_SATISFIED)
+    }
+
+    /// Returns the pixels as RGBA8, top row first, waiting for the copy
+    /// if needed.
+    pub fn finish(self) -> image::RgbaImage {
+        let len = byte_count(self.size, self.pixel_type);
+        let mut data = vec![0u8; len];
+
+        self.buffer.bind();
+        unsafe {
+            let mapped = gl::MapBufferRange(
+                gl::PIXEL_PACK_BUFFER,
+                0,
+                len as GLsizeiptr,
+                gl::MAP_READ_BIT,
+            );
+            if !mapped.is_null() {
+                std::ptr::copy_nonoverlapping(mapped as *const u8, data.as_mut_ptr(), len);
+                gl::UnmapBuffer(gl::PIXEL_PACK_BUFFER);
+            }
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        }
+
+        let pixels = if self.pixel_type == gl::FLOAT {
+            data.chunks_exact(4)
+                .map(|c| unorm8(f32::from_ne_bytes([c[0], c[1], c[2], c[3]])))
+                .collect()
+        } else {
+            data
+        };
+
+        let mut image = image::RgbaImage::from_raw(self.size.width(), self.size.height(), pixels)
+            .expect("Pixel buffer size mismatch");
+
+        // OpenGL rows start at the bottom
+        image::imageops::flip_vertical_in_place(&mut image);
+
+        image
+    }
+
+    /// Discards the readback without deleting its OpenGL objects.
+    ///
+    /// Used once the context of the framebuffer is destroyed, which
+    /// deleted the objects along with it. Deleting them with another
+    /// context current would delete unrelated objects of that context.
+    pub fn abandon(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for PixelReadback {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteSync(self.fence) };
+    }
+}
+
+/// Converts a color component to 8 bits, clamping it to `[0, 1]`.
+pub fn unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Returns the size in bytes of tightly packed RGBA pixels of type
+/// `pixel_type`.
+fn byte_count(size: Size, pixel_type: GLenum) -> usize {
+    let component_size = if pixel_type == gl::FLOAT {
+        std::mem::size_of::<f32>()
+    } else {
+        1
+    };
+    (size.width() * size.height() * 4) as usize * component_size
+}
@@ -33,6 +33,14 @@ impl Program {
     ///
     /// Shaders do not need to outlive the returned `Program`.
     pub fn new(shaders: &[Shader]) -> Result<Self, ShaderError> {
+        let program = Self::link(shaders);
+        program.check()?;
+        program.detach(shaders);
+        Ok(program)
+    }
+
+    /// Submits the shaders for linking without waiting for the result.
+    fn link(shaders: &[Shader]) -> Self {
         unsafe {
             let id = gl::CreateProgram();
 
@@ -41,25 +49,34 @@ impl Program {
             }
             gl::LinkProgram(id);
 
+            Self { id }
+        }
+    }
+
+    /// Waits for linking to finish and returns the driver log
+    /// as a [`ShaderError::ProgramLink`] if it failed.
+    fn check(&self) -> Result<(), ShaderError> {
+        unsafe {
             let mut success = 0;
-            gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
-            if success == 0 {
-                gl::DeleteProgram(id);
-
-                let mut log_len = 0;
-                gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut log_len);
-
-                let mut log = Vec::with_capacity(log_len as usize);
-                gl::GetProgramInfoLog(id, log_len, &mut log_len, log.as_mut_ptr() as *mut _);
-                log.set_len(log_len as usize);
-
-                Err(ShaderError::ProgramLink(String::from_utf8(log)?))
-            } else {
-                for shader in shaders {
-                    gl::DetachShader(id, shader.id());
-                }
-                Ok(Self { id })
+            gl::GetProgramiv(self.id, gl::LINK_STATUS, &mut success);
+            if success != 0 {
+                return Ok(());
             }
+
+            let mut log_len = 0;
+            gl::GetProgramiv(self.id, gl::INFO_LOG_LENGTH, &mut log_len);
+
+            let mut log = Vec::with_capacity(log_len as usize);
+            gl::GetProgramInfoLog(self.id, log_len, &mut log_len, log.as_mut_ptr() as *mut _);
+            log.set_len(log_len as usize);
+
+            Err(ShaderError::ProgramLink(String::from_utf8(log)?))
+        }
+    }
+
+    fn detach(&self, shaders: &[Shader]) {
+        for shader in shaders {
+            unsafe { gl::DetachShader(self.id, shader.id()) };
         }
     }
 
@@ -68,6 +85,11 @@ impl Program {
         unsafe { gl::UseProgram(self.id) };
     }
 
+    #[inline]
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
     /// Returns the location of a uniform variable.
     ///
     /// Returns `Ok(-1)` if the uniform does not exist or was optimized
@@ -76,6 +98,70 @@ impl Program {
         let name = std::ffi::CString::new(name)?;
         Ok(unsafe { gl::GetUniformLocation(self.id, name.as_ptr()) })
     }
+
+    /// Assigns a uniform block to a uniform buffer binding point.
+    ///
+    /// Does nothing if the block does not exist or was optimized out by
+    /// the shader compiler.
+    pub fn bind_uniform_block(&self, name: &str, binding: GLuint) -> Result<(), ShaderError> {
+        let name = std::ffi::CString::new(name)?;
+        unsafe {
+            let index = gl::GetUniformBlockIndex(self.id, name.as_ptr());
+            if index != gl::INVALID_INDEX {
+                gl::UniformBlockBinding(self.id, index, binding);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Program whose shaders are being compiled and linked, possibly on
+/// driver threads.
+///
+/// Submitting every program of a pipeline before finishing any of them
+/// lets drivers supporting `GL_KHR_parallel_shader_compile` build them
+/// concurrently.
+pub struct PendingProgram {
+    program: Program,
+    shaders: Vec<Shader>,
+}
+
+impl PendingProgram {
+    /// Submits the given shader sources for compilation and linking.
+    pub fn new(sources: &[(&str, GLenum)]) -> Result<Self, ShaderError> {
+        let shaders = sources
+            .iter()
+            .map(|&(source, type_)| Shader::compile(source, type_))
+            .collect::<Result<Vec<_>, _>>()?;
+        let program = Program::link(&shaders);
+
+        Ok(Self { program, shaders })
+    }
+
+    /// Returns `true` if the program can be finished without blocking.
+    ///
+    /// Always `true` when parallel compilation is unavailable.
+    pub fn is_ready(&self) -> bool {
+        if !is_parallel_compile_enabled() {
+            return true;
+        }
+
+        let mut completed = 0;
+        unsafe { gl::GetProgramiv(self.program.id, COMPLETION_STATUS_KHR, &mut completed) };
+        completed != 0
+    }
+
+    /// Waits for the program and returns it, or the first compile or
+    /// link error.
+    pub fn finish(self) -> Result<Program, ShaderError> {
+        for shader in &self.shaders {
+            shader.check()?;
+        }
+        self.program.check()?;
+        self.program.detach(&self.shaders);
+
+        Ok(self.program)
+    }
 }
 
 impl Drop for Program {
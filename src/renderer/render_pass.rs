@@ -9,21 +9,31 @@
 //! Each pass owns two framebuffers so previous-frame data can be sampled
 //! while the next frame is rendered.
 
-use chrono::prelude::*;
 use gl::types::*;
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    frame_controller::*,
     geometry::{Offset, Size},
-    mouse_controller::MouseData,
     preset::*,
     renderer::RenderContext,
     shadertoy::to_glsl_version,
-    APP_NAME, GL_VERSION,
+    APP_NAME,
 };
 
-use super::{framebuffer::*, program::*, shader::*, texture_manager::*};
+use super::{
+    frame_uniforms::*,
+    framebuffer::*,
+    gl_profile,
+    pass_graph::{input_framebuffer, output_framebuffer, output_name},
+    program::*,
+    shader::*,
+    source_map::SourceMap,
+    texture_manager::*,
+    GlProfile,
+};
 
 /// Fullscreen vertex shader used by all render passes.
 const VERTEX_SHADER: &str = r#"
@@ -47,9 +57,23 @@ void main() {
 }
 "#;
 
+/// Image pass shader drawing the preset fallback image from `iChannel0`,
+/// scaled to cover the screen.
+const FALLBACK_IMAGE_SHADER: &str = r#"
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 imageSize = max(iChannelResolution[0].xy, vec2(1.0));
+    float scale = max(iResolution.x / imageSize.x, iResolution.y / imageSize.y);
+    vec2 uv = (fragCoord - 0.5 * iResolution.xy) / (imageSize * scale) + 0.5;
+    fragColor = vec4(texture(iChannel0, uv).rgb, 1.0);
+}
+"#;
+
 /// Enables cubemap-specific shader paths.
 const CUBEMAP_DEFINITION: &str = "#define SHADERBG_CUBEMAP\n";
 
+/// Enables sound-specific shader paths.
+const SOUND_DEFINITION: &str = "#define SHADERBG_SOUND\n";
+
 /// Global define indicating execution inside ShaderBG runtime.
 const SHADERBG_DEFINITION: &str = "#define SHADERBG\n";
 
@@ -72,26 +96,48 @@ void st_assert(bool cond) {
     st_assert(cond, 0);
 }
 
+// Shared by all passes and updated once per frame (see frame_uniforms.rs)
+layout(std140) uniform sbg_FrameUniforms {
+    vec4  iDate;                 // (year, month, day, time in seconds)
+    vec4  iMouseWheel;           // scroll offset. xy: accumulated, zw: since last frame
+    vec4  iDesktop;              // workspace, its windows, fullscreen, all windows (see README)
+    vec4  iSystemStats[4];       // CPU, memory, network, temperature (see README)
+    vec2  iSunPosition;          // sun elevation and azimuth (in radians)
+    float iTime;                 // shader playback time (in seconds)
+    float iGlobalTime;           // same as iTime
+    float iTimeDelta;            // render time (in seconds)
+    float iFrameRate;            // shader frame rate
+    int   iFrame;                // shader playback frame
+    int   iMouseButtons;         // held buttons. 1: left, 2: middle, 4: right, 8: back, 16: forward
+    float iSampleRate;           // sound sample rate (i.e., 44100)
+    float iTrackProgress;        // fraction of the playing media track
+    float iDayPhase;             // fraction of the solar day (0.5 at solar noon)
+    float iAudioBands[64];       // smoothed output audio spectrum (see README)
+};
+
 uniform vec3  iResolution;           // viewport resolution (in pixels)
-uniform float iTime;                 // shader playback time (in seconds)
-uniform float iGlobalTime;           // same as iTime
-uniform float iTimeDelta;            // render time (in seconds)
-uniform float iFrameRate;            // shader frame rate
-uniform int   iFrame;                // shader playback frame
 uniform vec4  iMouse;                // mouse pixel coords. xy: current (if MLB down), zw: click
-uniform vec4  iDate;                 // (year, month, day, time in seconds)
 uniform vec3  iChannelResolution[4]; // channel resolution (in pixels)
-uniform float iChannelTime[4];       // TODO: channel playback time (in seconds)
-uniform float iSampleRate;           // TODO: sound sample rate (i.e., 44100)
+uniform float iChannelTime[4];       // channel playback time (in seconds)
 
 uniform vec2  iResolutionOffset;     // Offset to adjust gl_FragCoord when rendering to multiple monitors
 "#;
 
-/// Wrapper main function dispatching to `mainImage` or `mainCubemap`.
+/// Wrapper main function dispatching to `mainImage`, `mainCubemap` or `mainSound`.
+///
+/// Sound passes write one stereo sample per fragment, in row-major order
+/// starting at `sbg_SoundSampleOffset`.
 const FRAGMENT_SHADER_FOOTER: &str = r#"
+#ifdef SHADERBG_SOUND
+uniform int sbg_SoundSampleOffset;
+#endif
+
 void main() {
     vec4 color;
-#ifdef SHADERBG_CUBEMAP
+#if defined(SHADERBG_SOUND)
+    int samp = sbg_SoundSampleOffset + int(gl_FragCoord.y) * int(iResolution.x) + int(gl_FragCoord.x);
+    color = vec4(mainSound(samp, float(samp) / iSampleRate), 0, 1);
+#elif defined(SHADERBG_CUBEMAP)
     mainCubemap(color, gl_FragCoord.xy, vec3(0), normalize(sbg_FragRayDir));
 #else
     mainImage(color, gl_FragCoord.xy + iResolutionOffset);
@@ -106,29 +152,69 @@ const CUBEMAP_NUM_FACES: usize = 6;
 /// Resolution of each cubemap face generated by cubemap passes.
 const CUBEMAP_FACE_RESOLUTION: u32 = 1024;
 
+/// Resolution of the block of samples generated by sound passes.
+/// Each block holds 512 × 512 samples (about 5.9 s at 44.1 kHz).
+const SOUND_BLOCK_RESOLUTION: u32 = 512;
+
+/// Contents of the framebuffers of a buffer pass read back into memory.
+pub struct PassSnapshot {
+    /// Name of the pass.
+    pub name: String,
+
+    pass_type: PassType,
+    framebuffers: Vec<FramebufferSnapshot>,
+}
+
 /// Type of rendering performed by the pass.
+#[derive(Clone, Copy, PartialEq)]
 enum PassType {
     /// Standard fullscreen quad rendering.
     Buffer2D,
     /// Cubemap rendering performed once per face.
     Cubemap,
+    /// Audio rendering performed one block of samples at a time.
+    Sound,
+}
+
+impl PassType {
+    /// Returns the type of rendering of the pass `name`.
+    fn from_name(name: &str) -> Self {
+        match name {
+            "Cube A" => Self::Cubemap,
+            "Sound" => Self::Sound,
+            _ => Self::Buffer2D,
+        }
+    }
 }
 
-/// Cached uniform locations for ShaderToy-compatible uniforms.
+/// Cached uniform locations for ShaderToy-compatible uniforms whose
+/// values depend on the pass.
 ///
-/// Locations are queried once during construction.
+/// Locations are queried once during construction. Uniforms shared by
+/// all passes are in the `sbg_FrameUniforms` block instead.
 struct UniformLocations {
     i_resolution: GLint,
-    i_time: GLint,
-    i_global_time: GLint,
-    i_time_delta: GLint,
-    i_frame_rate: GLint,
-    i_frame: GLint,
     i_mouse: GLint,
-    i_date: GLint,
     i_channel_resolution: GLint,
+    i_channel_time: GLint,
     i_resolution_offset: GLint,
     i_channel: [GLint; 4],
+    sbg_sound_sample_offset: GLint,
+}
+
+/// User-defined uniform from the preset `[uniforms]` table.
+struct CustomUniform {
+    name: String,
+    value: UniformValue,
+    location: GLint,
+}
+
+/// Program of a pass built by [`RenderPass::recompile`], not yet in use.
+pub struct RecompiledProgram {
+    program: Program,
+    uniform_locations: UniformLocations,
+    custom_uniform_locations: Vec<GLint>,
+    shader: String,
 }
 
 /// One ShaderToy rendering stage.
@@ -140,9 +226,16 @@ pub struct RenderPass {
     /// Logical pass name (e.g. "Image", "Buffer A").
     name: String,
 
+    /// Position of the pass in the rendering order of a frame.
+    order: usize,
+
     /// Compiled shader program used to execute the pass.
     program: Program,
 
+    /// ShaderToy code of the pass, kept to rebuild the program
+    /// when the "Common" code changes.
+    shader: String,
+
     /// Double-buffered render targets used for ping-pong rendering.
     framebuffers: [Framebuffer; 2],
 
@@ -156,127 +249,165 @@ pub struct RenderPass {
     /// Only the Image pass applies resolution offsets and MSAA resolve.
     is_image_pass: bool,
 
+    /// Scale of the framebuffers of a buffer pass relative to the
+    /// resolution of the other passes.
+    scale: f32,
+
     /// Cached uniform locations.
     uniform_locations: UniformLocations,
+
+    /// Custom uniforms declared in the shader and uploaded every frame.
+    custom_uniforms: Vec<CustomUniform>,
+
+    /// Indicates whether the pass draws the fallback image because its
+    /// shader failed to compile.
+    is_fallback_image: bool,
+
+    /// Directory against which `#include` directives are resolved.
+    include_dir: Option<PathBuf>,
 }
 
-impl RenderPass {
-    /// Creates a render pass.
-    ///
-    /// Shader compilation failures fall back to a default shader while
-    /// preserving application execution.
-    pub fn new(
-        name: &str,
-        common_shader: &str,
-        pass_shader: &str,
-        framebuffer_size: Size,
-        inputs: [Option<Input>; 4],
-        msaa_samples: u32,
-    ) -> Result<Self, ShaderError> {
-        let mut is_cubemap_pass = name == "Cube A";
-        let mut channel_uniform_declarations = String::default();
-
-        for (i, input_opt) in inputs.iter().enumerate() {
-            let _type = input_opt.as_ref().map_or("2D", |input| match input._type {
-                InputType::Cubemap => "Cube",
-                InputType::Misc if input.name == "Cubemap A" => "Cube",
-                InputType::Volume => "3D",
-                _ => "2D",
-            });
-            channel_uniform_declarations += &format!("uniform sampler{_type} iChannel{i};\n");
-        }
+/// Render pass whose program is still being compiled.
+///
+/// Created by [`RenderPass::begin`] and turned into a [`RenderPass`] by
+/// [`PendingRenderPass::finish`].
+pub struct PendingRenderPass {
+    name: String,
+    order: usize,
+    shader: String,
+    fragment_shader_source: String,
+    source_map: SourceMap,
+    program: Result<PendingProgram, ShaderError>,
+    framebuffer_size: Size,
+    inputs: [Option<Input>; 4],
+    custom_uniforms: Vec<CustomUniform>,
+    msaa_samples: u32,
+    is_cubemap_pass: bool,
+    is_sound_pass: bool,
+    fallback_image: Option<PathBuf>,
+    buffer_precision: BufferPrecision,
+    scale: f32,
+    mipmaps: bool,
+    alpha: bool,
+    include_dir: Option<PathBuf>,
+}
 
-        let version_directive = || format!("#version {}{}0 core\n", GL_VERSION.0, GL_VERSION.1);
+impl PendingRenderPass {
+    /// Sets the position of the pass in the rendering order of a frame,
+    /// which determines whether other passes sample its output of the
+    /// current or of the previous frame.
+    pub fn with_order(mut self, order: usize) -> Self {
+        self.order = order;
+        self
+    }
 
-        let vertex_shader_source = version_directive()
-            + if is_cubemap_pass {
-                CUBEMAP_DEFINITION
-            } else {
-                ""
-            }
-            + VERTEX_SHADER;
+    /// Sets the image drawn instead of the default shader if this
+    /// "Image" pass fails to compile.
+    pub fn with_fallback_image(mut self, path: Option<PathBuf>) -> Self {
+        self.fallback_image = path;
+        self
+    }
 
-        let fragment_shader_source = &(version_directive()
-            + SHADERBG_DEFINITION
-            + if is_cubemap_pass {
-                CUBEMAP_DEFINITION
-            } else {
-                ""
-            }
-            + FRAGMENT_SHADER_HEADER
-            + &channel_uniform_declarations
-            + "\n"
-            + &to_glsl_version(
-                &(SHADERBG_DEFINITION.to_string() + common_shader + "\n" + pass_shader + "\n"),
-                GL_VERSION,
-                false,
-            )?
-            + "\n"
-            + FRAGMENT_SHADER_FOOTER);
-
-        let vertex_shader = Shader::new(&vertex_shader_source, gl::VERTEX_SHADER)?;
-
-        let default_fragment_shader = || {
-            let default_shader_source = version_directive()
-                + FRAGMENT_SHADER_HEADER
-                + &defaults::default_image_shader()
-                + FRAGMENT_SHADER_FOOTER;
-            Shader::new(&default_shader_source, gl::FRAGMENT_SHADER)
-                .expect("Error compiling default fragment shader")
-        };
+    /// Sets the number of samples of the framebuffers, `0` disabling
+    /// multisampling.
+    pub fn with_msaa_samples(mut self, msaa_samples: u32) -> Self {
+        self.msaa_samples = msaa_samples;
+        self
+    }
 
-        let fragment_shader = {
-            let result = Shader::new(fragment_shader_source, gl::FRAGMENT_SHADER);
-            if let Err(err) = result {
-                let mut err_msg = format!("Error compiling '{name}' pass shader: {err}")
-                    .trim()
-                    .to_string();
-                let log_file = log_dir().join(format!("{}.frag", name.to_lowercase()));
+    /// Sets the precision of the framebuffers if this is a buffer pass.
+    pub fn with_buffer_precision(mut self, precision: BufferPrecision) -> Self {
+        self.buffer_precision = precision;
+        self
+    }
 
-                if std::fs::write(&log_file, fragment_shader_source).is_ok() {
-                    err_msg += &format!(" - Shader saved to {}", log_file.to_str().unwrap());
-                }
+    /// Scales the resolution of the framebuffers by `scale` if this is a
+    /// buffer pass, e.g., `0.5` for half the width and height.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
 
-                log::error!("{}", err_msg);
-                is_cubemap_pass = false;
-                default_fragment_shader()
-            } else {
-                result?
-            }
-        };
+    /// Keeps the alpha channel of the output if this is the "Image"
+    /// pass, which is otherwise opaque.
+    pub fn with_alpha(mut self, alpha: bool) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Allocates mipmaps for the framebuffers and regenerates them after
+    /// every render, for passes sampled with the mipmap filter.
+    pub fn with_mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Returns `true` if [`PendingRenderPass::finish`] will not block
+    /// on shader compilation.
+    pub fn is_ready(&self) -> bool {
+        self.program.as_ref().map_or(true, PendingProgram::is_ready)
+    }
+
+    /// Waits for the pass program and creates the pass framebuffers.
+    pub fn finish(self) -> Result<RenderPass, ShaderError> {
+        let Self {
+            name,
+            order,
+            shader,
+            fragment_shader_source,
+            source_map,
+            program,
+            framebuffer_size,
+            mut inputs,
+            mut custom_uniforms,
+            msaa_samples,
+            mut is_cubemap_pass,
+            is_sound_pass,
+            fallback_image,
+            buffer_precision,
+            scale,
+            mipmaps,
+            alpha,
+            include_dir,
+        } = self;
+
+        let mut is_fallback_image = false;
+        let program = match program
+            .and_then(PendingProgram::finish)
+            .map_err(|err| source_map.map_error(err, &name))
+        {
+            Ok(program) => program,
+            Err(err) => {
+                report_shader_error(&name, &err, &fragment_shader_source);
+
+                // The default shader produces colors, not audio
+                if is_sound_pass {
+                    return Err(err);
+                }
 
-        let program = {
-            let result = Program::new(&[vertex_shader, fragment_shader]);
-            if let Err(err) = result {
-                log::error!("Error linking '{name}' pass program: {err}");
-                let vertex_shader = Shader::new(&vertex_shader_source, gl::VERTEX_SHADER)?;
                 is_cubemap_pass = false;
-                Program::new(&[vertex_shader, default_fragment_shader()])?
-            } else {
-                result?
+                match fallback_image.filter(|_| name == "Image").and_then(|path| {
+                    fallback_image_program(&path)
+                        .inspect_err(|err| log::error!("Error building fallback image: {err}"))
+                        .ok()
+                }) {
+                    Some((program, fallback_inputs)) => {
+                        inputs = fallback_inputs;
+                        is_fallback_image = true;
+                        program
+                    }
+                    None => default_program(),
+                }
             }
         };
 
-        let uniform_locations = UniformLocations {
-            i_resolution: program.uniform_location("iResolution")?,
-            i_time: program.uniform_location("iTime")?,
-            i_global_time: program.uniform_location("iGlobalTime")?,
-            i_time_delta: program.uniform_location("iTimeDelta")?,
-            i_frame_rate: program.uniform_location("iFrameRate")?,
-            i_frame: program.uniform_location("iFrame")?,
-            i_mouse: program.uniform_location("iMouse")?,
-            i_date: program.uniform_location("iDate")?,
-            i_channel_resolution: program.uniform_location("iChannelResolution")?,
-            i_resolution_offset: program.uniform_location("iResolutionOffset")?,
-            i_channel: [
-                program.uniform_location("iChannel0")?,
-                program.uniform_location("iChannel1")?,
-                program.uniform_location("iChannel2")?,
-                program.uniform_location("iChannel3")?,
-            ],
-        };
+        let uniform_locations = UniformLocations::new(&program)?;
+        for uniform in &mut custom_uniforms {
+            uniform.location = program.uniform_location(&uniform.name)?;
+        }
 
         let is_image_pass = name == "Image";
+        let scale = if is_image_pass { 1.0 } else { scale };
 
         let (pass_type, size, framebuffer_kind) = if is_cubemap_pass {
             (
@@ -284,37 +415,205 @@ impl RenderPass {
                 Size::new(CUBEMAP_FACE_RESOLUTION, CUBEMAP_FACE_RESOLUTION),
                 FramebufferFormat::Cubemap,
             )
+        } else if is_sound_pass {
+            (
+                PassType::Sound,
+                Size::new(SOUND_BLOCK_RESOLUTION, SOUND_BLOCK_RESOLUTION),
+                FramebufferFormat::Tex2DFloat,
+            )
         } else {
+            let mut size = framebuffer_size * scale;
+            size.set_width(size.width().max(1));
+            size.set_height(size.height().max(1));
             (
                 PassType::Buffer2D,
-                framebuffer_size,
-                if is_image_pass {
-                    FramebufferFormat::Tex2D
-                } else {
-                    FramebufferFormat::Tex2DFloat
+                size,
+                match (is_image_pass, buffer_precision) {
+                    (true, _) if alpha => FramebufferFormat::Tex2DAlpha,
+                    (true, _) => FramebufferFormat::Tex2D,
+                    (false, BufferPrecision::F16) => FramebufferFormat::Tex2DHalfFloat,
+                    (false, BufferPrecision::F32) => FramebufferFormat::Tex2DFloat,
                 },
             )
         };
 
-        Ok(Self {
-            name: name.to_string(),
+        Ok(RenderPass {
+            name,
+            order,
             program,
+            shader,
             framebuffers: [
-                Framebuffer::new(size, msaa_samples, framebuffer_kind),
-                Framebuffer::new(size, msaa_samples, framebuffer_kind),
+                Framebuffer::new(size, msaa_samples, framebuffer_kind, mipmaps),
+                Framebuffer::new(size, msaa_samples, framebuffer_kind, mipmaps),
             ],
             pass_type,
             inputs,
             is_image_pass,
+            scale,
             uniform_locations,
+            custom_uniforms,
+            is_fallback_image,
+            include_dir,
         })
     }
+}
+
+impl RenderPass {
+    /// Submits the shader of a render pass for compilation and returns
+    /// without waiting for it.
+    ///
+    /// Shader compilation failures are handled by
+    /// [`PendingRenderPass::finish`], which falls back to a default shader
+    /// while preserving application execution. Returns an error only if
+    /// the shader cannot be preprocessed.
+    ///
+    /// `#include` directives are resolved against `include_dir`, the
+    /// directory of the preset.
+    pub fn begin(
+        name: &str,
+        common_shader: &str,
+        pass_shader: &str,
+        framebuffer_size: Size,
+        inputs: [Option<Input>; 4],
+        uniforms: &BTreeMap<String, UniformValue>,
+        include_dir: Option<&Path>,
+    ) -> Result<PendingRenderPass, ShaderError> {
+        let pass_type = PassType::from_name(name);
+        let is_cubemap_pass = pass_type == PassType::Cubemap;
+
+        let custom_uniforms: Vec<_> = uniforms
+            .iter()
+            .map(|(name, value)| CustomUniform {
+                name: name.clone(),
+                value: *value,
+                location: -1,
+            })
+            .collect();
+
+        let (fragment_shader_source, source_map) = fragment_shader_source(
+            common_shader,
+            pass_shader,
+            &inputs,
+            &custom_uniforms,
+            pass_type,
+            gl_profile(),
+            include_dir,
+        )?;
+
+        let program = begin_program(&fragment_shader_source, is_cubemap_pass);
+
+        Ok(PendingRenderPass {
+            name: name.to_string(),
+            order: 0,
+            shader: pass_shader.to_string(),
+            fragment_shader_source,
+            source_map,
+            program,
+            framebuffer_size,
+            inputs,
+            custom_uniforms,
+            msaa_samples: 0,
+            is_cubemap_pass,
+            is_sound_pass: pass_type == PassType::Sound,
+            fallback_image: None,
+            buffer_precision: BufferPrecision::default(),
+            scale: 1.0,
+            mipmaps: false,
+            alpha: false,
+            include_dir: include_dir.map(Path::to_path_buf),
+        })
+    }
+
+    /// Recompiles the pass with new shader sources, keeping its
+    /// framebuffers, and therefore the contents of feedback buffers.
+    ///
+    /// On failure, the error is logged and the previous program stays
+    /// in use. Returns `Ok(false)` if the pass type or inputs cannot
+    /// change in place, i.e., a "Cube A" pass that previously fell back
+    /// to the default shader, or an "Image" pass drawing the fallback
+    /// image; the pass must then be recreated.
+    pub fn reload(&mut self, common_shader: &str, pass_shader: &str) -> Result<bool, ShaderError> {
+        let Some(recompiled) = self.recompile(common_shader, pass_shader)? else {
+            return Ok(false);
+        };
+        self.replace_program(recompiled);
+        Ok(true)
+    }
+
+    /// Builds the program of [`RenderPass::reload`] without putting it
+    /// in use, e.g., to reload several passes only if all of them
+    /// compile.
+    ///
+    /// Returns `Ok(None)` if the pass must be recreated instead.
+    pub fn recompile(
+        &self,
+        common_shader: &str,
+        pass_shader: &str,
+    ) -> Result<Option<RecompiledProgram>, ShaderError> {
+        let is_cubemap_pass = matches!(self.pass_type, PassType::Cubemap);
+        if (self.name == "Cube A" && !is_cubemap_pass) || self.is_fallback_image {
+            return Ok(None);
+        }
+
+        let (fragment_shader_source, source_map) = fragment_shader_source(
+            common_shader,
+            pass_shader,
+            &self.inputs,
+            &self.custom_uniforms,
+            self.pass_type,
+            gl_profile(),
+            self.include_dir.as_deref(),
+        )
+        .inspect_err(|err| log::error!("Error building '{}' pass shader: {err}", self.name))?;
+
+        let program = compile_program(&fragment_shader_source, is_cubemap_pass)
+            .map_err(|err| source_map.map_error(err, &self.name))
+            .inspect_err(|err| report_shader_error(&self.name, err, &fragment_shader_source))?;
+
+        let uniform_locations = UniformLocations::new(&program)?;
+        let custom_uniform_locations = self
+            .custom_uniforms
+            .iter()
+            .map(|uniform| program.uniform_location(&uniform.name))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Some(RecompiledProgram {
+            program,
+            uniform_locations,
+            custom_uniform_locations,
+            shader: pass_shader.to_string(),
+        }))
+    }
+
+    /// Puts in use a program built by [`RenderPass::recompile`].
+    pub fn replace_program(&mut self, recompiled: RecompiledProgram) {
+        self.uniform_locations = recompiled.uniform_locations;
+        for (uniform, location) in self
+            .custom_uniforms
+            .iter_mut()
+            .zip(recompiled.custom_uniform_locations)
+        {
+            uniform.location = location;
+        }
+        self.program = recompiled.program;
+        self.shader = recompiled.shader;
+    }
 
     /// Returns the logical name of the pass (e.g. "Image", "Buffer A").
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Returns the position of the pass in the rendering order.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the ShaderToy code of the pass.
+    pub fn shader(&self) -> &str {
+        &self.shader
+    }
+
     /// Returns configured input channels (`iChannel0..3`).
     pub fn inputs(&self) -> &[Option<Input>; 4] {
         &self.inputs
@@ -325,16 +624,74 @@ impl RenderPass {
         &self.framebuffers
     }
 
+    /// Copies the contents of both framebuffers of `previous` into the
+    /// framebuffers of this pass, rescaling them to the new size.
+    ///
+    /// Used to carry the state of simulations (e.g., fluids) over to a
+    /// recreated renderer. Only buffer and "Cube A" passes are copied,
+    /// and only if `previous` is of the same type.
+    pub fn copy_framebuffers_from(&self, previous: &RenderPass) {
+        let format = match (&self.pass_type, &previous.pass_type) {
+            (PassType::Buffer2D, PassType::Buffer2D) if !self.is_image_pass => {
+                FramebufferFormat::Tex2DFloat
+            }
+            (PassType::Cubemap, PassType::Cubemap) => FramebufferFormat::Cubemap,
+            _ => return,
+        };
+
+        for (framebuffer, previous_framebuffer) in
+            self.framebuffers.iter().zip(&previous.framebuffers)
+        {
+            framebuffer.copy_from(previous_framebuffer, format);
+            framebuffer.generate_mipmaps();
+        }
+    }
+
+    /// Reads back the contents of the framebuffers, e.g., to restore
+    /// them with [`RenderPass::restore_framebuffers`] in the renderer of
+    /// a new window.
+    ///
+    /// Returns `None` unless this is a buffer or "Cube A" pass.
+    pub fn snapshot_framebuffers(&self) -> Option<PassSnapshot> {
+        match self.pass_type {
+            PassType::Buffer2D if !self.is_image_pass => {}
+            PassType::Cubemap => {}
+            _ => return None,
+        }
+
+        Some(PassSnapshot {
+            name: self.name.clone(),
+            pass_type: self.pass_type,
+            framebuffers: self
+                .framebuffers
+                .iter()
+                .map(Framebuffer::snapshot)
+                .collect(),
+        })
+    }
+
+    /// Restores the contents of the framebuffers from `snapshot`,
+    /// rescaled to their size.
+    ///
+    /// Only done if `snapshot` is of a pass of the same type.
+    pub fn restore_framebuffers(&self, snapshot: &PassSnapshot) {
+        if self.pass_type != snapshot.pass_type || self.is_image_pass {
+            return;
+        }
+
+        for (framebuffer, framebuffer_snapshot) in
+            self.framebuffers.iter().zip(&snapshot.framebuffers)
+        {
+            framebuffer.restore(framebuffer_snapshot);
+            framebuffer.generate_mipmaps();
+        }
+    }
+
     /// Executes this render pass.
     ///
     /// Selects the appropriate rendering path depending on pass type
     /// and updates the correct framebuffer for the current frame.
-    pub fn render_pass(
-        &self,
-        ctx: &RenderContext,
-        frame_tracker: &mut HashMap<String, u32>,
-        scaled_resolution_offset: Offset,
-    ) {
+    pub fn render_pass(&self, ctx: &RenderContext, scaled_resolution_offset: Offset) {
         let scaled_resolution_offset = if self.is_image_pass {
             scaled_resolution_offset
         } else {
@@ -342,37 +699,95 @@ impl RenderPass {
         };
 
         match self.pass_type {
-            PassType::Buffer2D => self.render_2d_pass(ctx, frame_tracker, scaled_resolution_offset),
-            PassType::Cubemap => self.render_cubemap_pass(ctx, frame_tracker),
+            PassType::Buffer2D => self.render_2d_pass(ctx, scaled_resolution_offset),
+            PassType::Cubemap => self.render_cubemap_pass(ctx),
+            // Sound blocks are generated on demand by `render_sound_block`
+            PassType::Sound => {}
         }
     }
 
+    /// Renders one block of audio samples starting at `first_sample`.
+    ///
+    /// Returns interleaved stereo samples, as written by `mainSound`.
+    pub fn render_sound_block(&self, ctx: &RenderContext, first_sample: u64) -> Vec<f32> {
+        let framebuffer = &self.framebuffers[0];
+        let size = framebuffer.size();
+
+        // Samples range from -1 to 1 and need 32-bit floats, which the
+        // context may not render to
+        if framebuffer.format() != FramebufferFormat::Tex2DFloat {
+            return vec![0.0; (size.width() * size.height() * 2) as usize];
+        }
+
+        ctx.gl_state.use_program(self.program.id());
+
+        self.set_common_uniforms(ctx, size, 1.);
+        self.set_channel_uniforms(ctx);
+
+        let sample_offset_location = self.uniform_locations.sbg_sound_sample_offset;
+        if sample_offset_location >= 0 {
+            let sample_offset = (first_sample % i32::MAX as u64) as i32;
+            unsafe { gl::Uniform1i(sample_offset_location, sample_offset) };
+        }
+
+        ctx.gl_state.bind_vertex_array(ctx.vaos[0].id());
+        framebuffer.bind();
+
+        // OpenGL ES only guarantees RGBA readback of float framebuffers
+        let (format, num_components) = if gl_profile().es {
+            (gl::RGBA, 4)
+        } else {
+            (gl::RG, 2)
+        };
+
+        let mut samples = vec![0.0f32; (size.width() * size.height() * num_components) as usize];
+        unsafe {
+            gl::Viewport(0, 0, size.width() as i32, size.height() as i32);
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+            gl::ReadPixels(
+                0,
+                0,
+                size.width() as i32,
+                size.height() as i32,
+                format,
+                gl::FLOAT,
+                samples.as_mut_ptr() as *mut _,
+            );
+        }
+
+        if num_components == 4 {
+            samples = samples
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[0], pixel[1]])
+                .collect();
+        }
+
+        samples
+    }
+
     /// Renders a fullscreen 2D pass.
     ///
     /// The framebuffer index alternates every frame to implement
     /// ping-pong rendering.
-    fn render_2d_pass(
-        &self,
-        ctx: &RenderContext,
-        frame_tracker: &mut HashMap<String, u32>,
-        scaled_resolution_offset: Offset,
-    ) {
+    fn render_2d_pass(&self, ctx: &RenderContext, scaled_resolution_offset: Offset) {
         // Use the "next" framebuffer so shaders sample from the previous frame.
-        let framebuffer_idx = ((ctx.frame_stats.frame_number + 1) % 2) as usize;
-        let framebuffer = &self.framebuffers[framebuffer_idx];
+        let framebuffer = &self.framebuffers[output_framebuffer(ctx.frame_stats.frame_number)];
         let framebuffer_size = framebuffer.size();
 
-        self.program.bind();
+        ctx.gl_state.use_program(self.program.id());
 
-        self.set_common_uniforms(
-            ctx.scaled_resolution,
-            ctx.mouse_data,
-            ctx.framebuffer_scale,
-            ctx.frame_stats,
-        );
-        self.set_channel_uniforms(ctx.texture_manager, frame_tracker, ctx.frame_stats);
+        // Buffer passes report the resolution of their own framebuffers,
+        // which may be scaled relative to the other passes
+        if self.is_image_pass {
+            self.set_common_uniforms(ctx, ctx.scaled_resolution, ctx.framebuffer_scale);
+        } else {
+            self.set_common_uniforms(ctx, framebuffer_size, ctx.framebuffer_scale * self.scale);
+        }
+        self.set_channel_uniforms(ctx);
 
+        // Offsets are in the resolution of the other passes as well
         if self.uniform_locations.i_resolution_offset >= 0 {
+            let scaled_resolution_offset = scaled_resolution_offset * self.scale;
             unsafe {
                 gl::Uniform2f(
                     self.uniform_locations.i_resolution_offset,
@@ -382,7 +797,7 @@ impl RenderPass {
             }
         }
 
-        ctx.vaos[0].bind();
+        ctx.gl_state.bind_vertex_array(ctx.vaos[0].id());
 
         framebuffer.bind();
 
@@ -399,13 +814,15 @@ impl RenderPass {
         if self.is_image_pass {
             framebuffer.resolve();
         }
+        framebuffer.generate_mipmaps();
+        ctx.gl_state.invalidate_active_texture();
     }
 
     /// Renders a cubemap pass.
     ///
     /// Each cubemap face is rendered independently using a
     /// dedicated VAO providing the correct ray direction.
-    fn render_cubemap_pass(&self, ctx: &RenderContext, frame_tracker: &mut HashMap<String, u32>) {
+    fn render_cubemap_pass(&self, ctx: &RenderContext) {
         const CUBEMAP_FACES: [GLenum; CUBEMAP_NUM_FACES] = [
             gl::TEXTURE_CUBE_MAP_POSITIVE_X,
             gl::TEXTURE_CUBE_MAP_NEGATIVE_X,
@@ -416,15 +833,15 @@ impl RenderPass {
         ];
 
         let resolution = Size::new(CUBEMAP_FACE_RESOLUTION, CUBEMAP_FACE_RESOLUTION);
-        let framebuffer_idx = ((ctx.frame_stats.frame_number + 1) % 2) as usize;
+        let framebuffer_idx = output_framebuffer(ctx.frame_stats.frame_number);
 
-        self.program.bind();
+        ctx.gl_state.use_program(self.program.id());
 
-        self.set_common_uniforms(resolution, ctx.mouse_data, 1., ctx.frame_stats);
-        self.set_channel_uniforms(ctx.texture_manager, frame_tracker, ctx.frame_stats);
+        self.set_common_uniforms(ctx, resolution, 1.);
+        self.set_channel_uniforms(ctx);
 
         for (face_idx, &face) in CUBEMAP_FACES.iter().enumerate() {
-            ctx.vaos[face_idx + 1].bind();
+            ctx.gl_state.bind_vertex_array(ctx.vaos[face_idx + 1].id());
             self.framebuffers[framebuffer_idx].bind_cubemap_face(face);
 
             unsafe {
@@ -432,19 +849,24 @@ impl RenderPass {
                 gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
             }
         }
+
+        self.framebuffers[framebuffer_idx].generate_mipmaps();
+        ctx.gl_state.invalidate_active_texture();
     }
 
-    /// Uploads ShaderToy-compatible global uniforms.
+    /// Uploads the ShaderToy-compatible uniforms specific to the pass
+    /// (resolution and mouse state) and its custom uniforms.
     ///
-    /// Includes timing, resolution, mouse state, frame counters,
-    /// and date information.
+    /// Uniforms shared by all passes are uploaded once per frame by
+    /// [`FrameUniforms`](super::frame_uniforms::FrameUniforms).
     fn set_common_uniforms(
         &self,
+        ctx: &RenderContext,
         scaled_resolution: Size,
-        mouse_data: &MouseData,
         framebuffer_scale: f32,
-        frame_stats: &FrameStats,
     ) {
+        let mouse_data = ctx.mouse_data;
+
         #[inline]
         fn valid(loc: GLint) -> bool {
             loc >= 0
@@ -462,31 +884,6 @@ impl RenderPass {
             }
         }
 
-        let i_time_location = self.uniform_locations.i_time;
-        if valid(i_time_location) {
-            unsafe { gl::Uniform1f(i_time_location, frame_stats.time.as_secs_f32()) };
-        }
-
-        let i_global_time_location = self.uniform_locations.i_global_time;
-        if valid(i_global_time_location) {
-            unsafe { gl::Uniform1f(i_global_time_location, frame_stats.time.as_secs_f32()) };
-        }
-
-        let i_time_delta_location = self.uniform_locations.i_time_delta;
-        if valid(i_time_delta_location) {
-            unsafe { gl::Uniform1f(i_time_delta_location, frame_stats.time_delta.as_secs_f32()) };
-        }
-
-        let i_frame_rate_location = self.uniform_locations.i_frame_rate;
-        if valid(i_frame_rate_location) {
-            unsafe { gl::Uniform1f(i_frame_rate_location, frame_stats.frame_rate as f32) };
-        }
-
-        let i_frame_location = self.uniform_locations.i_frame;
-        if valid(i_frame_location) {
-            unsafe { gl::Uniform1i(i_frame_location, frame_stats.frame_number as i32 % i32::MAX) };
-        }
-
         let i_mouse_location = self.uniform_locations.i_mouse;
         if valid(i_mouse_location) {
             let data = if mouse_data.as_shadertoy_uniform()[0] >= 0 {
@@ -499,18 +896,17 @@ impl RenderPass {
             unsafe { gl::Uniform4fv(i_mouse_location, 1, data.as_ptr()) };
         }
 
-        let i_date_location = self.uniform_locations.i_date;
-        if valid(i_date_location) {
-            let now = Local::now();
-            let year = now.year() as f32;
-            let month = (now.month() - 1) as f32;
-            let day = now.day() as f32;
-
-            const NANOS_PER_SEC: u32 = 1_000_000_000;
-            let time = now.num_seconds_from_midnight() as f32
-                + (now.nanosecond() as f32) / (NANOS_PER_SEC as f32);
-
-            unsafe { gl::Uniform4f(i_date_location, year, month, day, time) };
+        for uniform in self.custom_uniforms.iter().filter(|u| valid(u.location)) {
+            let location = uniform.location;
+            unsafe {
+                match uniform.value {
+                    UniformValue::Bool(value) => gl::Uniform1i(location, value as GLint),
+                    UniformValue::Float(value) => gl::Uniform1f(location, value),
+                    UniformValue::Vec2(value) => gl::Uniform2fv(location, 1, value.as_ptr()),
+                    UniformValue::Vec3(value) => gl::Uniform3fv(location, 1, value.as_ptr()),
+                    UniformValue::Vec4(value) => gl::Uniform4fv(location, 1, value.as_ptr()),
+                }
+            }
         }
     }
 
@@ -518,14 +914,11 @@ impl RenderPass {
     ///
     /// Handles external textures, ping-pong buffer dependencies between passes,
     /// cubemap/volume targets, filtering and wrapping modes, and
-    /// channel resolution reporting.
-    fn set_channel_uniforms(
-        &self,
-        texture_manager: &TextureManager,
-        frame_tracker: &mut HashMap<String, u32>,
-        frame_stats: &FrameStats,
-    ) {
-        let mut channel_resolutions = Vec::<f32>::default();
+    /// channel resolution and playback time reporting.
+    fn set_channel_uniforms(&self, ctx: &RenderContext) {
+        let (texture_manager, frame_stats) = (ctx.texture_manager, ctx.frame_stats);
+        let mut channel_resolutions = [[0.0f32; 3]; 4];
+        let mut channel_times = [0.0f32; 4];
 
         for (idx, input) in self
             .inputs
@@ -533,41 +926,50 @@ impl RenderPass {
             .enumerate()
             .filter_map(|(idx, opt)| opt.as_ref().map(|input| (idx, input)))
         {
-            let mut texture_name = input.name.clone();
-
-            // Buffer inputs reference outputs from other passes.
-            // We select the correct ping-pong buffer so a pass never
-            // reads from the framebuffer currently being written.
-            if matches!(
-                input.name.as_str(),
-                "Buffer A" | "Buffer B" | "Buffer C" | "Buffer D" | "Cubemap A"
-            ) {
-                let mut offset = 0;
-                if self.name > input.name {
-                    let texture_name_with_suffix =
-                        input.name.clone() + &(frame_stats.frame_number % 2).to_string();
-                    let previous_frame_number = frame_tracker
-                        .insert(texture_name_with_suffix, frame_stats.frame_number)
-                        .unwrap_or(u32::MAX);
-                    if previous_frame_number != frame_stats.frame_number {
-                        offset = 1;
-                    }
-                };
-                texture_name += &((frame_stats.frame_number + offset) % 2).to_string();
-            }
+            let mut texture_name = if input._type == InputType::AlbumArt {
+                ALBUM_ART_TEXTURE_NAME.to_string()
+            } else {
+                input.name.clone()
+            };
 
-            if input._type == InputType::Texture && input.vflip {
+            // Buffer inputs reference outputs from other passes, or from
+            // the pass itself for feedback effects. They are never flipped,
+            // whatever their type.
+            if let Some(input_order) = texture_manager.pass_order(&input.name) {
+                let index = input_framebuffer(input_order, self.order, frame_stats.frame_number);
+                texture_name += &index.to_string();
+            } else if matches!(
+                input._type,
+                InputType::Texture
+                    | InputType::Video
+                    | InputType::Webcam
+                    | InputType::Stream
+                    | InputType::Text
+                    | InputType::AlbumArt
+                    | InputType::Screen
+            ) && input.vflip
+            {
                 texture_name += "vflip";
             }
 
-            let texture_id = if input._type == InputType::Keyboard {
-                texture_manager.keyboard_id()
+            if let Some(time) = texture_manager.channel_time(&texture_name) {
+                channel_times[idx] = time.as_secs_f32();
+            }
+
+            let (texture_id, resolution) = if input._type == InputType::Keyboard {
+                (
+                    texture_manager.keyboard_id(),
+                    texture_manager.keyboard_resolution(),
+                )
             } else {
-                texture_manager.id(&texture_name)
+                (
+                    texture_manager.id(&texture_name),
+                    texture_manager.resolution(&texture_name),
+                )
             };
 
             if let Some(texture_id) = texture_id {
-                let target = if input._type == InputType::Cubemap || input.name == "Cubemap A" {
+                let target = if is_cubemap_input(input) {
                     gl::TEXTURE_CUBE_MAP
                 } else if input._type == InputType::Volume {
                     gl::TEXTURE_3D
@@ -575,10 +977,7 @@ impl RenderPass {
                     gl::TEXTURE_2D
                 };
 
-                unsafe {
-                    gl::ActiveTexture(gl::TEXTURE0 + idx as GLuint);
-                    gl::BindTexture(target, texture_id);
-                }
+                ctx.gl_state.bind_texture(idx as GLuint, target, texture_id);
 
                 let wrap_mode = if input.wrap == WrapMode::Repeat {
                     gl::REPEAT
@@ -603,38 +1002,14 @@ impl RenderPass {
                     gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, mag_filter as i32);
                 }
 
-                if input._type == InputType::Misc && input.filter == FilterMode::Mipmap {
-                    unsafe { gl::GenerateMipmap(target) };
-                }
-
                 let i_channel_location = self.uniform_locations.i_channel[idx];
                 if i_channel_location >= 0 {
                     unsafe { gl::Uniform1i(i_channel_location, idx as i32) };
                 }
 
-                let (mut width, mut height, mut depth): (i32, i32, i32) = (0, 0, 1);
-                if input._type == InputType::Cubemap {
-                    let target = gl::TEXTURE_CUBE_MAP_POSITIVE_X;
-                    unsafe {
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_WIDTH, &mut width);
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_HEIGHT, &mut height);
-                    }
-                } else if input._type == InputType::Volume {
-                    unsafe {
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_WIDTH, &mut width);
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_HEIGHT, &mut height);
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_DEPTH, &mut depth);
-                    }
-                } else {
-                    unsafe {
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_WIDTH, &mut width);
-                        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_HEIGHT, &mut height);
-                    }
-                }
-
-                channel_resolutions.push(width as f32);
-                channel_resolutions.push(height as f32);
-                channel_resolutions.push(depth as f32);
+                // Resolutions are cached by the texture manager, as
+                // querying them stalls some drivers
+                channel_resolutions[idx] = resolution.unwrap_or([0., 0., 1.]);
             }
         }
 
@@ -644,13 +1019,274 @@ impl RenderPass {
                 gl::Uniform3fv(
                     i_channel_resolution_location,
                     4,
-                    channel_resolutions.as_ptr(),
+                    channel_resolutions.as_ptr() as *const GLfloat,
                 )
             };
         }
+
+        let i_channel_time_location = self.uniform_locations.i_channel_time;
+        if i_channel_time_location >= 0 {
+            unsafe { gl::Uniform1fv(i_channel_time_location, 4, channel_times.as_ptr()) };
+        }
     }
 }
 
+impl UniformLocations {
+    /// Queries the locations of the ShaderToy uniforms of a program and
+    /// binds its `sbg_FrameUniforms` block to the shared uniform buffer.
+    fn new(program: &Program) -> Result<Self, ShaderError> {
+        program.bind_uniform_block(FRAME_UNIFORMS_BLOCK, FRAME_UNIFORMS_BINDING)?;
+
+        Ok(Self {
+            i_resolution: program.uniform_location("iResolution")?,
+            i_mouse: program.uniform_location("iMouse")?,
+            i_channel_resolution: program.uniform_location("iChannelResolution")?,
+            i_channel_time: program.uniform_location("iChannelTime")?,
+            i_resolution_offset: program.uniform_location("iResolutionOffset")?,
+            i_channel: [
+                program.uniform_location("iChannel0")?,
+                program.uniform_location("iChannel1")?,
+                program.uniform_location("iChannel2")?,
+                program.uniform_location("iChannel3")?,
+            ],
+            sbg_sound_sample_offset: program.uniform_location("sbg_SoundSampleOffset")?,
+        })
+    }
+}
+
+/// Returns the `#version` directive (and default precisions, for
+/// OpenGL ES) matching the rendering profile.
+fn version_directive() -> String {
+    gl_profile().shader_prelude()
+}
+
+/// Returns `true` if `input` is sampled as a cube map, i.e., if it is a
+/// cube map texture or the output of the "Cube A" pass, whatever its type.
+fn is_cubemap_input(input: &Input) -> bool {
+    input._type == InputType::Cubemap || input.name == output_name("Cube A")
+}
+
+/// Builds the complete fragment shader source of a pass for `profile`,
+/// along with the map of its lines to the preset code.
+///
+/// Wraps the ShaderToy code with the ShaderBG header, custom uniform
+/// declarations, sampler declarations matching the input types, and the
+/// `main` footer. `#include` directives are resolved against
+/// `include_dir`.
+fn fragment_shader_source(
+    common_shader: &str,
+    pass_shader: &str,
+    inputs: &[Option<Input>; 4],
+    custom_uniforms: &[CustomUniform],
+    pass_type: PassType,
+    profile: GlProfile,
+    include_dir: Option<&Path>,
+) -> Result<(String, SourceMap), ShaderError> {
+    let mut uniform_declarations = String::default();
+
+    for uniform in custom_uniforms {
+        uniform_declarations +=
+            &format!("uniform {} {};\n", uniform.value.glsl_type(), uniform.name);
+    }
+
+    for (i, input_opt) in inputs.iter().enumerate() {
+        let _type = input_opt.as_ref().map_or("2D", |input| match input._type {
+            _ if is_cubemap_input(input) => "Cube",
+            InputType::Volume => "3D",
+            _ => "2D",
+        });
+        uniform_declarations += &format!("uniform sampler{_type} iChannel{i};\n");
+    }
+
+    let header = profile.shader_prelude()
+        + SHADERBG_DEFINITION
+        + match pass_type {
+            PassType::Cubemap => CUBEMAP_DEFINITION,
+            PassType::Sound => SOUND_DEFINITION,
+            PassType::Buffer2D => "",
+        }
+        + FRAGMENT_SHADER_HEADER
+        + &uniform_declarations
+        + "\n";
+
+    let unbound_channels: Vec<_> = (0..inputs.len()).filter(|&i| inputs[i].is_none()).collect();
+    let (user_source, line_origins) = to_glsl_version(
+        &(SHADERBG_DEFINITION.to_string() + common_shader + "\n" + pass_shader + "\n"),
+        profile.version,
+        profile.es,
+        include_dir,
+        &unbound_channels,
+    )?;
+
+    let source_map = SourceMap::new(
+        header.matches('\n').count() + 1,
+        &line_origins,
+        SHADERBG_DEFINITION.matches('\n').count(),
+        common_shader,
+        pass_shader,
+    );
+
+    Ok((
+        header + &source_map.insert_line_directives(&user_source) + "\n" + FRAGMENT_SHADER_FOOTER,
+        source_map,
+    ))
+}
+
+/// Builds the fragment shader source of the pass `name` for `profile`
+/// without an OpenGL context, along with the map of its lines to the
+/// preset code.
+pub fn pass_fragment_source(
+    name: &str,
+    common_shader: &str,
+    pass_shader: &str,
+    inputs: &[Option<Input>; 4],
+    uniforms: &BTreeMap<String, UniformValue>,
+    profile: GlProfile,
+    include_dir: Option<&Path>,
+) -> Result<(String, SourceMap), ShaderError> {
+    let custom_uniforms: Vec<_> = uniforms
+        .iter()
+        .map(|(name, value)| CustomUniform {
+            name: name.clone(),
+            value: *value,
+            location: -1,
+        })
+        .collect();
+
+    fragment_shader_source(
+        common_shader,
+        pass_shader,
+        inputs,
+        &custom_uniforms,
+        PassType::from_name(name),
+        profile,
+        include_dir,
+    )
+}
+
+/// Compiles and links the shader of a pass without falling back to the
+/// default shader, for validating presets.
+///
+/// Returns the warnings reported by the driver for the fragment shader,
+/// with lines mapped to the preset code, or the first error.
+pub fn validate_pass(
+    name: &str,
+    common_shader: &str,
+    pass_shader: &str,
+    inputs: &[Option<Input>; 4],
+    uniforms: &BTreeMap<String, UniformValue>,
+    include_dir: Option<&Path>,
+) -> Result<String, ShaderError> {
+    let is_cubemap_pass = name == "Cube A";
+    let (fragment_shader_source, source_map) = pass_fragment_source(
+        name,
+        common_shader,
+        pass_shader,
+        inputs,
+        uniforms,
+        gl_profile(),
+        include_dir,
+    )?;
+
+    let build = || {
+        let vertex_shader = Shader::new(&vertex_shader_source(is_cubemap_pass), gl::VERTEX_SHADER)?;
+        let fragment_shader = Shader::new(&fragment_shader_source, gl::FRAGMENT_SHADER)?;
+        let warnings = fragment_shader.info_log()?;
+        Program::new(&[vertex_shader, fragment_shader])?;
+        Ok(warnings)
+    };
+
+    build()
+        .map(|warnings| source_map.rewrite_log(&warnings, name))
+        .map_err(|err| source_map.map_error(err, name))
+}
+
+/// Returns the vertex shader source of a pass program.
+fn vertex_shader_source(is_cubemap_pass: bool) -> String {
+    version_directive()
+        + if is_cubemap_pass {
+            CUBEMAP_DEFINITION
+        } else {
+            ""
+        }
+        + VERTEX_SHADER
+}
+
+/// Submits a pass program for compilation and linking from its
+/// fragment shader source.
+fn begin_program(
+    fragment_shader_source: &str,
+    is_cubemap_pass: bool,
+) -> Result<PendingProgram, ShaderError> {
+    let vertex_shader_source = vertex_shader_source(is_cubemap_pass);
+
+    PendingProgram::new(&[
+        (&vertex_shader_source, gl::VERTEX_SHADER),
+        (fragment_shader_source, gl::FRAGMENT_SHADER),
+    ])
+}
+
+/// Compiles and links a pass program from its fragment shader source.
+fn compile_program(
+    fragment_shader_source: &str,
+    is_cubemap_pass: bool,
+) -> Result<Program, ShaderError> {
+    begin_program(fragment_shader_source, is_cubemap_pass)?.finish()
+}
+
+/// Compiles the program used in place of passes that failed to compile.
+fn default_program() -> Program {
+    let default_shader_source = version_directive()
+        + FRAGMENT_SHADER_HEADER
+        + &defaults::default_image_shader()
+        + FRAGMENT_SHADER_FOOTER;
+    compile_program(&default_shader_source, false).expect("Error compiling default shader")
+}
+
+/// Compiles the program drawing the image at `path` in place of an
+/// "Image" pass that failed to compile, along with the inputs sampling
+/// the image.
+fn fallback_image_program(path: &Path) -> Result<(Program, [Option<Input>; 4]), ShaderError> {
+    let inputs = [
+        Some(Input {
+            _type: InputType::Texture,
+            name: path.display().to_string(),
+            vflip: true,
+            ..Default::default()
+        }),
+        None,
+        None,
+        None,
+    ];
+    let (fragment_shader_source, _) = fragment_shader_source(
+        "",
+        FALLBACK_IMAGE_SHADER,
+        &inputs,
+        &[],
+        PassType::Buffer2D,
+        gl_profile(),
+        None,
+    )?;
+    Ok((compile_program(&fragment_shader_source, false)?, inputs))
+}
+
+/// Logs a shader error and saves the offending source to the log
+/// directory for inspection.
+///
+/// Lines injected by ShaderBG keep their numbers in the saved source.
+fn report_shader_error(name: &str, err: &ShaderError, fragment_shader_source: &str) {
+    let mut err_msg = format!("Error building '{name}' pass shader: {err}")
+        .trim()
+        .to_string();
+    let log_file = log_dir().join(format!("{}.frag", name.to_lowercase()));
+
+    if std::fs::write(&log_file, fragment_shader_source).is_ok() {
+        err_msg += &format!(" - Shader saved to {}", log_file.to_str().unwrap());
+    }
+
+    log::error!("{}", err_msg);
+}
+
 /// Returns directory used to store shader compilation logs.
 ///
 /// Falls back to the current working directory if the cache
@@ -0,0 +1,563 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Screen capture for `InputType::Screen` channels.
+//!
+//! Outputs are captured through the `zwlr_screencopy_manager_v1`
+//! protocol on a dedicated Wayland connection, owned by a background
+//! thread that copies the output into shared memory at most once per
+//! capture interval. Only the newest frame is kept, and the next capture
+//! waits until it is uploaded, so no screen is captured while rendering
+//! is paused. Captures are shared by the texture managers of all
+//! monitors (see [`super::shared_source`]), so each output is copied
+//! once per interval however many areas show it.
+//!
+//! Since presets are often downloaded from the web, capturing is allowed
+//! only by the `[screen_capture]` table of the configuration file, never
+//! by the preset itself.
+
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io,
+    os::{
+        fd::{AsFd, FromRawFd},
+        unix::fs::FileExt,
+    },
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use wayland_client::{
+    protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+};
+
+use crate::{config::config, geometry::Size};
+
+use super::{shared_source::FrameSource, texture_stream::StreamFrame};
+
+/// Highest `wl_output` version used. Version 4 adds the `name` event,
+/// which carries the connector name.
+const WL_OUTPUT_VERSION: u32 = 4;
+
+/// Highest `zwlr_screencopy_manager_v1` version used. Later versions
+/// only add damage tracking and DMA-BUF buffers.
+const SCREENCOPY_VERSION: u32 = 1;
+
+/// Shortest accepted capture interval, as copying a whole output costs
+/// both the compositor and the renderer a full-size transfer.
+const MIN_CAPTURE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time between checks of the stop flag while waiting for the next
+/// capture.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Screen capture settings, from the `[screen_capture]` table of the
+/// configuration file.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScreenCaptureConfig {
+    /// Allows `screen` inputs to capture outputs.
+    #[serde(default)]
+    pub allow: bool,
+
+    /// Minimum time between captures, raised to 250 ms if shorter.
+    #[serde(default = "default_capture_interval", with = "humantime_serde")]
+    pub interval: Duration,
+}
+
+fn default_capture_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+#[derive(Debug, Error)]
+pub enum ScreenCaptureError {
+    #[error("Screen capture is not enabled in the configuration file")]
+    NotAllowed,
+
+    #[error("Failed to connect to the Wayland display: {0}")]
+    Connect(#[from] wayland_client::ConnectError),
+
+    #[error("Wayland dispatch failed: {0}")]
+    Dispatch(#[from] wayland_client::DispatchError),
+
+    #[error("Compositor does not support zwlr_screencopy_manager_v1")]
+    Unsupported,
+
+    #[error("Output '{0}' not found")]
+    OutputNotFound(String),
+
+    #[error("Unsupported pixel format {0:?}")]
+    Format(WEnum<wl_shm::Format>),
+
+    #[error("Compositor failed to copy the output")]
+    Failed,
+
+    #[error("Shared memory error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Output captured periodically by a background thread.
+///
+/// Dropping the capture stops the thread after its current capture.
+pub struct ScreenCapture {
+    latest: Arc<Mutex<Option<StreamFrame>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ScreenCapture {
+    /// Starts capturing the output with the given connector name, or
+    /// the first output when `output_name` is empty.
+    ///
+    /// Frames are flipped vertically when `vflip` is set. Fails if the
+    /// configuration file does not allow screen capture. Connecting to
+    /// the compositor blocks on roundtrips, so it is done by the capture
+    /// thread, which logs a warning and produces no frames if the output
+    /// cannot be captured.
+    pub fn start(output_name: &str, vflip: bool) -> Result<Self, ScreenCaptureError> {
+        let interval = match &config().screen_capture {
+            Some(settings) if settings.allow => settings.interval.max(MIN_CAPTURE_INTERVAL),
+            _ => return Err(ScreenCaptureError::NotAllowed),
+        };
+
+        let latest = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_name = output_name.to_string();
+        let thread_latest = Arc::clone(&latest);
+        let thread_stop = Arc::clone(&stop);
+        thread::Builder::new()
+            .name("screen-capture".to_string())
+            .spawn(move || {
+                let mut session = match Session::connect(&thread_name) {
+                    Ok(session) => session,
+                    Err(err) => {
+                        log::warn!("Screen input '{thread_name}' ignored: {err}");
+                        return;
+                    }
+                };
+                session.run(interval, vflip, &thread_latest, &thread_stop);
+                log::debug!("Screen capture of '{thread_name}' stopped");
+            })?;
+
+        Ok(Self { latest, stop })
+    }
+
+    /// Returns the newest frame captured since the last call, if any.
+    pub fn poll_frame(&mut self) -> Option<StreamFrame> {
+        self.latest.lock().ok()?.take()
+    }
+}
+
+impl FrameSource for ScreenCapture {
+    type Frame = StreamFrame;
+
+    fn poll_frame(&mut self) -> Option<StreamFrame> {
+        ScreenCapture::poll_frame(self)
+    }
+}
+
+impl Drop for ScreenCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Connection to the compositor and the output being captured.
+struct Session {
+    connection: Connection,
+    queue: EventQueue<State>,
+    state: State,
+    output: wl_output::WlOutput,
+
+    /// Shared memory buffer reused while the output size is unchanged.
+    buffer: Option<ShmBuffer>,
+}
+
+impl Session {
+    fn connect(output_name: &str) -> Result<Self, ScreenCaptureError> {
+        let connection = Connection::connect_to_env()?;
+        let mut queue = connection.new_event_queue::<State>();
+        connection.display().get_registry(&queue.handle(), ());
+
+        let mut state = State::default();
+
+        // First roundtrip binds the globals, the second receives the
+        // output names
+        queue.roundtrip(&mut state)?;
+        if state.manager.is_none() || state.shm.is_none() {
+            return Err(ScreenCaptureError::Unsupported);
+        }
+        queue.roundtrip(&mut state)?;
+
+        let output = state
+            .outputs
+            .iter()
+            .find(|(_, name)| output_name.is_empty() || name.as_deref() == Some(output_name))
+            .map(|(output, _)| output.clone())
+            .ok_or_else(|| ScreenCaptureError::OutputNotFound(output_name.to_string()))?;
+
+        Ok(Self {
+            connection,
+            queue,
+            state,
+            output,
+            buffer: None,
+        })
+    }
+
+    /// Captures the output every `interval` until `stop` is set or the
+    /// connection fails.
+    ///
+    /// A frame is captured only after the previous one was taken from
+    /// `latest`.
+    fn run(
+        &mut self,
+        interval: Duration,
+        vflip: bool,
+        latest: &Mutex<Option<StreamFrame>>,
+        stop: &AtomicBool,
+    ) {
+        let mut last_error = None;
+
+        while !stop.load(Ordering::Relaxed) {
+            let started = Instant::now();
+
+            let pending = latest.lock().map_or(true, |frame| frame.is_some());
+            if !pending {
+                match self.capture(vflip) {
+                    Ok(frame) => {
+                        if let Ok(mut latest) = latest.lock() {
+                            *latest = Some(frame);
+                        }
+                        last_error = None;
+                    }
+                    Err(err @ ScreenCaptureError::Dispatch(_)) => {
+                        log::warn!("Screen capture: {err}");
+                        return;
+                    }
+                    Err(err) => {
+                        // Transient failures, e.g., while the output is
+                        // being reconfigured, are retried silently
+                        let message = err.to_string();
+                        if last_error.as_ref() != Some(&message) {
+                            log::warn!("Screen capture: {message}");
+                        }
+                        last_error = Some(message);
+                    }
+                }
+            }
+
+            while !stop.load(Ordering::Relaxed) && started.elapsed() < interval {
+                thread::sleep(IDLE_POLL_INTERVAL.min(interval.saturating_sub(started.elapsed())));
+            }
+        }
+    }
+
+    /// Copies the output into shared memory and converts it to RGBA.
+    fn capture(&mut self, vflip: bool) -> Result<StreamFrame, ScreenCaptureError> {
+        let (Some(manager), Some(shm)) = (&self.state.manager, &self.state.shm) else {
+            return Err(ScreenCaptureError::Unsupported);
+        };
+        let queue_handle = self.queue.handle();
+        let frame = manager.capture_output(0, &self.output, &queue_handle, ());
+        self.state.frame = FrameState::default();
+
+        let result = self.copy_frame(&frame, shm.clone(), &queue_handle);
+        frame.destroy();
+        let _ = self.connection.flush();
+        let info = result?;
+
+        let buffer = self.buffer.as_ref().ok_or(ScreenCaptureError::Failed)?;
+        let mut data = vec![0; (info.stride * info.height) as usize];
+        buffer.file.read_exact_at(&mut data, 0)?;
+
+        Ok(StreamFrame {
+            size: Size::new(info.width, info.height),
+            data: to_rgba(&data, info, vflip != self.state.frame.y_invert)?,
+        })
+    }
+
+    /// Waits for the buffer parameters of `frame`, then for the copy.
+    fn copy_frame(
+        &mut self,
+        frame: &ZwlrScreencopyFrameV1,
+        shm: wl_shm::WlShm,
+        queue_handle: &QueueHandle<State>,
+    ) -> Result<BufferInfo, ScreenCaptureError> {
+        let info = loop {
+            if let Some(info) = self.state.frame.buffer {
+                break info;
+            }
+            if self.state.frame.status.is_some() {
+                return Err(ScreenCaptureError::Failed);
+            }
+            self.queue.blocking_dispatch(&mut self.state)?;
+        };
+
+        if !matches!(
+            info.format,
+            WEnum::Value(
+                wl_shm::Format::Argb8888
+                    | wl_shm::Format::Xrgb8888
+                    | wl_shm::Format::Abgr8888
+                    | wl_shm::Format::Xbgr8888
+            )
+        ) {
+            return Err(ScreenCaptureError::Format(info.format));
+        }
+
+        if self.buffer.as_ref().map(|buffer| buffer.info) != Some(info) {
+            if let Some(buffer) = self.buffer.take() {
+                buffer.destroy();
+            }
+            self.buffer = Some(ShmBuffer::new(&shm, info, queue_handle)?);
+        }
+        if let Some(buffer) = &self.buffer {
+            frame.copy(&buffer.buffer);
+        }
+
+        loop {
+            match self.state.frame.status {
+                Some(true) => return Ok(info),
+                Some(false) => return Err(ScreenCaptureError::Failed),
+                None => {
+                    self.queue.blocking_dispatch(&mut self.state)?;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            buffer.destroy();
+        }
+        let _ = self.connection.flush();
+    }
+}
+
+/// Layout of the buffer requested by the compositor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BufferInfo {
+    pub format: WEnum<wl_shm::Format>,
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+}
+
+/// `wl_buffer` backed by a memory file.
+struct ShmBuffer {
+    file: File,
+    pool: wl_shm_pool::WlShmPool,
+    buffer: wl_buffer::WlBuffer,
+    info: BufferInfo,
+}
+
+impl ShmBuffer {
+    fn new(
+        shm: &wl_shm::WlShm,
+        info: BufferInfo,
+        queue_handle: &QueueHandle<State>,
+    ) -> io::Result<Self> {
+        let len = info.stride * info.height;
+
+        let fd =
+            unsafe { libc::memfd_create(c"shaderbg-screen-capture".as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = unsafe { File::from_raw_fd(fd) };
+        file.set_len(len as u64)?;
+
+        let pool = shm.create_pool(file.as_fd(), len as i32, queue_handle, ());
+        let buffer = pool.create_buffer(
+            0,
+            info.width as i32,
+            info.height as i32,
+            info.stride as i32,
+            info.format.into_result().map_err(io::Error::other)?,
+            queue_handle,
+            (),
+        );
+
+        Ok(Self {
+            file,
+            pool,
+            buffer,
+            info,
+        })
+    }
+
+    fn destroy(self) {
+        self.buffer.destroy();
+        self.pool.destroy();
+    }
+}
+
+/// Converts the pixels of a buffer to tightly packed RGBA8, top row
+/// first unless `flip` is set.
+///
+/// Fails if the format is not one of the 32-bit RGB formats, whose
+/// little-endian channels are stored as BGRA for `Argb8888` and
+/// `Xrgb8888`, and as RGBA for `Abgr8888` and `Xbgr8888`.
+pub fn to_rgba(data: &[u8], info: BufferInfo, flip: bool) -> Result<Vec<u8>, ScreenCaptureError> {
+    let (bgr, opaque) = match info.format {
+        WEnum::Value(wl_shm::Format::Argb8888) => (true, false),
+        WEnum::Value(wl_shm::Format::Xrgb8888) => (true, true),
+        WEnum::Value(wl_shm::Format::Abgr8888) => (false, false),
+        WEnum::Value(wl_shm::Format::Xbgr8888) => (false, true),
+        format => return Err(ScreenCaptureError::Format(format)),
+    };
+
+    let row_len = info.width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_len * info.height as usize);
+
+    for row in 0..info.height as usize {
+        let row = if flip {
+            info.height as usize - 1 - row
+        } else {
+            row
+        };
+        let start = row * info.stride as usize;
+        for pixel in data[start..start + row_len].chunks_exact(4) {
+            let (r, b) = if bgr {
+                (pixel[2], pixel[0])
+            } else {
+                (pixel[0], pixel[2])
+            };
+            let a = if opaque { 255 } else { pixel[3] };
+            pixels.extend_from_slice(&[r, pixel[1], b, a]);
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Events of the frame being captured.
+#[derive(Default)]
+struct FrameState {
+    buffer: Option<BufferInfo>,
+    y_invert: bool,
+
+    /// `true` when the copy is ready, `false` when it failed.
+    status: Option<bool>,
+}
+
+#[derive(Default)]
+struct State {
+    shm: Option<wl_shm::WlShm>,
+    manager: Option<ZwlrScreencopyManagerV1>,
+
+    /// Bound outputs, with their connector names.
+    outputs: Vec<(wl_output::WlOutput, Option<String>)>,
+
+    frame: FrameState,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        queue_handle: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            if interface == wl_output::WlOutput::interface().name {
+                let output = registry.bind::<wl_output::WlOutput, _, _>(
+                    name,
+                    version.min(WL_OUTPUT_VERSION),
+                    queue_handle,
+                    (),
+                );
+                state.outputs.push((output, None));
+            } else if interface == wl_shm::WlShm::interface().name {
+                state.shm = Some(registry.bind(name, 1, queue_handle, ()));
+            } else if interface == ZwlrScreencopyManagerV1::interface().name {
+                state.manager =
+                    Some(registry.bind(name, version.min(SCREENCOPY_VERSION), queue_handle, ()));
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            if let Some((_, output_name)) = state
+                .outputs
+                .iter_mut()
+                .find(|(other, _)| other.id() == output.id())
+            {
+                *output_name = Some(name);
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_screencopy_frame_v1::{Event, Flags};
+
+        match event {
+            Event::Buffer {
+                format,
+                width,
+                height,
+                stride,
+            } => {
+                state.frame.buffer = Some(BufferInfo {
+                    format,
+                    width,
+                    height,
+                    stride,
+                });
+            }
+            Event::Flags { flags } => {
+                state.frame.y_invert =
+                    matches!(flags, WEnum::Value(flags) if flags.contains(Flags::YInvert));
+            }
+            Event::Ready { .. } => state.frame.status = Some(true),
+            Event::Failed => state.frame.status = Some(false),
+            _ => {}
+        }
+    }
+}
+
+wayland_client::delegate_noop!(State: ignore wl_shm::WlShm);
+wayland_client::delegate_noop!(State: wl_shm_pool::WlShmPool);
+wayland_client::delegate_noop!(State: ignore wl_buffer::WlBuffer);
+wayland_client::delegate_noop!(State: ZwlrScreencopyManagerV1);
@@ -7,8 +7,56 @@
 //!
 //! Provides RAII management for shader objects and structured
 //! error reporting for preprocessing, compilation, and linking.
+//!
+//! Compilation is split into submission and status query so that
+//! several shaders can be compiled concurrently by the driver when
+//! `GL_KHR_parallel_shader_compile` is available.
 
 use gl::types::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `GL_MAX_SHADER_COMPILER_THREADS_KHR` value requesting as many
+/// compiler threads as the driver deems useful.
+const MAX_SHADER_COMPILER_THREADS_DEFAULT: GLuint = 0xFFFF_FFFF;
+
+/// `GL_COMPLETION_STATUS_KHR`, not exposed by the `gl` crate.
+pub const COMPLETION_STATUS_KHR: GLenum = 0x91B1;
+
+/// Whether shaders are compiled on driver threads.
+static PARALLEL_COMPILE: AtomicBool = AtomicBool::new(false);
+
+/// Lets the driver compile and link shaders on background threads.
+///
+/// Must be called with a current OpenGL context. Returns `false` if
+/// `GL_KHR_parallel_shader_compile` (or its ARB variant) is unsupported,
+/// in which case compilation completes when its status is first queried.
+pub fn enable_parallel_compile() -> bool {
+    let entry_point = if super::has_gl_extension(&["GL_KHR_parallel_shader_compile"]) {
+        "glMaxShaderCompilerThreadsKHR"
+    } else if super::has_gl_extension(&["GL_ARB_parallel_shader_compile"]) {
+        "glMaxShaderCompilerThreadsARB"
+    } else {
+        PARALLEL_COMPILE.store(false, Ordering::Relaxed);
+        return false;
+    };
+
+    let address = epoxy::get_proc_addr(entry_point);
+    let supported = !address.is_null();
+    if supported {
+        let max_shader_compiler_threads: extern "system" fn(GLuint) =
+            unsafe { std::mem::transmute(address) };
+        max_shader_compiler_threads(MAX_SHADER_COMPILER_THREADS_DEFAULT);
+    }
+
+    PARALLEL_COMPILE.store(supported, Ordering::Relaxed);
+    supported
+}
+
+/// Returns `true` if completion of background compilation can be polled
+/// with [`COMPLETION_STATUS_KHR`].
+pub fn is_parallel_compile_enabled() -> bool {
+    PARALLEL_COMPILE.load(Ordering::Relaxed)
+}
 
 /// Errors that may occur during shader creation or compilation.
 #[allow(clippy::enum_variant_names)]
@@ -50,30 +98,49 @@ impl Shader {
     ///
     /// Requires a current OpenGL context.
     pub fn new(source: &str, type_: GLenum) -> Result<Self, ShaderError> {
+        let shader = Self::compile(source, type_)?;
+        shader.check()?;
+        Ok(shader)
+    }
+
+    /// Submits a GLSL shader for compilation without waiting for the
+    /// result, which must later be queried with [`Shader::check`].
+    pub fn compile(source: &str, type_: GLenum) -> Result<Self, ShaderError> {
         let source = std::ffi::CString::new(source)?;
         unsafe {
             let id = gl::CreateShader(type_);
             gl::ShaderSource(id, 1, &source.as_ptr(), std::ptr::null());
             gl::CompileShader(id);
+            Ok(Self { id })
+        }
+    }
 
-            // Query compilation result
+    /// Waits for compilation to finish and returns the driver log
+    /// as a [`ShaderError::ShaderCompile`] if it failed.
+    pub fn check(&self) -> Result<(), ShaderError> {
+        unsafe {
             let mut success = 0;
-            gl::GetShaderiv(id, gl::COMPILE_STATUS, &mut success);
-            if success == 0 {
-                gl::DeleteShader(id);
+            gl::GetShaderiv(self.id, gl::COMPILE_STATUS, &mut success);
+            if success != 0 {
+                return Ok(());
+            }
+        }
 
-                // Retrieve driver-provided compilation log
-                let mut log_len = 0;
-                gl::GetShaderiv(id, gl::INFO_LOG_LENGTH, &mut log_len);
+        Err(ShaderError::ShaderCompile(self.info_log()?))
+    }
 
-                let mut log = Vec::with_capacity(log_len as usize);
-                gl::GetShaderInfoLog(id, log_len, &mut log_len, log.as_mut_ptr() as *mut _);
-                log.set_len(log_len as usize);
+    /// Returns the driver-provided compilation log, which may contain
+    /// warnings even if compilation succeeded.
+    pub fn info_log(&self) -> Result<String, ShaderError> {
+        unsafe {
+            let mut log_len = 0;
+            gl::GetShaderiv(self.id, gl::INFO_LOG_LENGTH, &mut log_len);
 
-                Err(ShaderError::ShaderCompile(String::from_utf8(log)?))
-            } else {
-                Ok(Self { id })
-            }
+            let mut log = Vec::with_capacity(log_len as usize);
+            gl::GetShaderInfoLog(self.id, log_len, &mut log_len, log.as_mut_ptr() as *mut _);
+            log.set_len(log_len as usize);
+
+            Ok(String::from_utf8(log)?)
         }
     }
 
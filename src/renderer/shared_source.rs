@@ -0,0 +1,121 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Frame sources shared by the renderers of all monitors.
+//!
+//! Each monitor has a renderer, and with it a texture manager, of its
+//! own. Sources such as pipes and capture devices must nevertheless be
+//! opened once: two readers of a pipe each receive part of its frames,
+//! and a V4L2 device is busy for every reader but the first. Sources are
+//! thus opened by the first texture manager needing them, and shared
+//! with the others for as long as any of them holds a [`SourceHandle`].
+//!
+//! Renderers all run on the GTK main thread, so sources are registered
+//! per thread.
+
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+/// Source producing frames that are polled once per rendered frame.
+pub trait FrameSource {
+    type Frame;
+
+    /// Returns the newest frame produced since the last call, if any.
+    fn poll_frame(&mut self) -> Option<Self::Frame>;
+}
+
+thread_local! {
+    /// Open sources, by key.
+    static SOURCES: RefCell<HashMap<String, Weak<dyn Any>>> = RefCell::default();
+}
+
+/// Source and the newest frame it produced.
+struct SharedSource<S: FrameSource> {
+    source: RefCell<S>,
+    latest: RefCell<Option<Rc<S::Frame>>>,
+
+    /// Number of frames produced so far.
+    generation: Cell<u64>,
+}
+
+/// Handle of a texture manager to a shared source.
+///
+/// The source is closed when its last handle is dropped.
+pub struct SourceHandle<S: FrameSource> {
+    shared: Rc<SharedSource<S>>,
+
+    /// Generation of the last frame returned by this handle.
+    generation: u64,
+}
+
+impl<S> SourceHandle<S>
+where
+    S: FrameSource + 'static,
+    S::Frame: 'static,
+{
+    /// Returns a handle to the source registered under `key`, opening it
+    /// with `open` if no handle to it exists.
+    ///
+    /// Keys must differ for sources of different types.
+    pub fn open<E>(key: &str, open: impl FnOnce() -> Result<S, E>) -> Result<Self, E> {
+        let existing = SOURCES.with_borrow(|sources| {
+            sources
+                .get(key)
+                .and_then(Weak::upgrade)
+                .and_then(|shared| shared.downcast::<SharedSource<S>>().ok())
+        });
+
+        let shared = match existing {
+            Some(shared) => shared,
+            None => {
+                let shared = Rc::new(SharedSource {
+                    source: RefCell::new(open()?),
+                    latest: RefCell::new(None),
+                    generation: Cell::new(0),
+                });
+                let any: Rc<dyn Any> = shared.clone();
+                SOURCES.with_borrow_mut(|sources| {
+                    sources.retain(|_, source| source.strong_count() > 0);
+                    sources.insert(key.to_string(), Rc::downgrade(&any));
+                });
+                shared
+            }
+        };
+
+        Ok(Self {
+            shared,
+            generation: 0,
+        })
+    }
+
+    /// Returns the newest frame of the source not yet returned by this
+    /// handle, if any.
+    ///
+    /// Frames polled from the source through another handle are returned
+    /// as well, so every handle sees the newest frame.
+    pub fn poll_frame(&mut self) -> Option<Rc<S::Frame>> {
+        let shared = &self.shared;
+        if let Some(frame) = shared.source.borrow_mut().poll_frame() {
+            *shared.latest.borrow_mut() = Some(Rc::new(frame));
+            shared.generation.set(shared.generation.get() + 1);
+        }
+
+        if self.generation == shared.generation.get() {
+            return None;
+        }
+        self.generation = shared.generation.get();
+        shared.latest.borrow().clone()
+    }
+
+    /// Returns the shared source, e.g., to control its playback.
+    #[cfg(feature = "video")]
+    pub fn source(&self) -> std::cell::Ref<'_, S> {
+        self.shared.source.borrow()
+    }
+}
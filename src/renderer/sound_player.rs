@@ -0,0 +1,149 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Audio output for the ShaderToy "Sound" pass.
+//!
+//! Samples generated on the GPU are converted to 16-bit PCM and streamed
+//! to PipeWire through a `pw-cat` child process. Writing happens on a
+//! dedicated thread so that a full pipe never blocks the render loop.
+
+use std::{
+    io::{self, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread::JoinHandle,
+};
+use thiserror::Error;
+
+/// Output sample rate, matching ShaderToy's `iSampleRate`.
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// Number of interleaved output channels (stereo).
+const CHANNELS: u32 = 2;
+
+/// Maximum number of blocks waiting to be written to PipeWire.
+///
+/// One block is drained while the next one is ready, so playback
+/// continues without gaps while rendering stays ahead by one block.
+const MAX_PENDING_BLOCKS: usize = 2;
+
+#[derive(Debug, Error)]
+pub enum SoundError {
+    #[error("Failed to start pw-cat: {0}")]
+    Spawn(#[from] io::Error),
+}
+
+/// Streams stereo PCM blocks to PipeWire.
+pub struct SoundPlayer {
+    /// Sends PCM blocks to the writer thread.
+    sender: Option<mpsc::Sender<Vec<u8>>>,
+
+    /// Number of blocks queued but not yet written.
+    pending_blocks: Arc<AtomicUsize>,
+
+    /// `pw-cat` playback process.
+    child: Child,
+
+    /// Thread writing PCM blocks to the stdin of `pw-cat`.
+    writer: Option<JoinHandle<()>>,
+}
+
+impl SoundPlayer {
+    /// Starts a `pw-cat` playback stream with the given volume in `[0, 1]`.
+    pub fn new(volume: f32) -> Result<Self, SoundError> {
+        let mut child = Command::new("pw-cat")
+            .args([
+                "--playback",
+                "--raw",
+                "--format",
+                "s16",
+                "--rate",
+                &SAMPLE_RATE.to_string(),
+                "--channels",
+                &CHANNELS.to_string(),
+                "--volume",
+                &volume.clamp(0.0, 1.0).to_string(),
+                "-",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::other("pw-cat stdin unavailable"))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let pending_blocks = Arc::new(AtomicUsize::new(0));
+        let writer = std::thread::spawn({
+            let pending_blocks = pending_blocks.clone();
+            move || write_blocks(stdin, receiver, pending_blocks)
+        });
+
+        log::debug!("Sound playback started ({SAMPLE_RATE} Hz, volume {volume})");
+
+        Ok(Self {
+            sender: Some(sender),
+            pending_blocks,
+            child,
+            writer: Some(writer),
+        })
+    }
+
+    /// Returns `true` when a new block should be generated to keep
+    /// playback fed.
+    pub fn wants_block(&self) -> bool {
+        self.sender.is_some() && self.pending_blocks.load(Ordering::Acquire) < MAX_PENDING_BLOCKS
+    }
+
+    /// Queues interleaved stereo samples in `[-1, 1]` for playback.
+    pub fn queue(&mut self, samples: &[f32]) {
+        let pcm = samples
+            .iter()
+            .flat_map(|sample| ((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+            .collect();
+
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        self.pending_blocks.fetch_add(1, Ordering::AcqRel);
+        if sender.send(pcm).is_err() {
+            log::warn!("Sound playback stopped");
+            self.sender = None;
+        }
+    }
+}
+
+impl Drop for SoundPlayer {
+    fn drop(&mut self) {
+        self.sender = None;
+        let _ = self.child.kill();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// Writes queued blocks until the channel closes or `pw-cat` exits.
+fn write_blocks(
+    mut stdin: ChildStdin,
+    receiver: mpsc::Receiver<Vec<u8>>,
+    pending_blocks: Arc<AtomicUsize>,
+) {
+    for block in receiver {
+        let result = stdin.write_all(&block);
+        pending_blocks.fetch_sub(1, Ordering::AcqRel);
+        if let Err(err) = result {
+            log::debug!("Sound playback: {err}");
+            break;
+        }
+    }
+}
@@ -0,0 +1,287 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Mapping of assembled pass shaders back to preset code.
+//!
+//! The fragment shader of a pass is the preset code, converted by the
+//! ShaderToy compatibility layer, wrapped by code injected by ShaderBG.
+//! A [`SourceMap`] inserts `#line` directives into the converted code so
+//! that compilers report locations in the "Common" or pass code as
+//! written in the preset, told apart by their source string number:
+//!
+//! | Source string | Section                                     |
+//! |---------------|---------------------------------------------|
+//! | 0             | Code injected by ShaderBG (assembled lines) |
+//! | 1             | "Common" code                               |
+//! | 2             | Pass code                                   |
+//!
+//! The `__LINE__` and `__FILE__` macros left by the preprocessor are
+//! replaced by the same locations. Driver logs are then rewritten to
+//! label locations with the section name, e.g. `Image:12(5)`.
+//!
+//! Driver log formats vary between vendors. The following prefixes are
+//! recognized, where `S` is the source string number and `L` the line:
+//!
+//! | Format             | Drivers                      |
+//! |--------------------|------------------------------|
+//! | `S:L(C): `         | Mesa                         |
+//! | `S(L) : `          | NVIDIA                       |
+//! | `ERROR: S:L: `     | AMD, Intel (Windows), ANGLE  |
+//! | `WARNING: S:L: `   | AMD, Intel (Windows), ANGLE  |
+
+use regex::{Captures, Regex};
+
+use super::shader::ShaderError;
+
+/// Label of lines injected by ShaderBG.
+const INJECTED_LABEL: &str = "ShaderBG";
+
+/// Part of a pass shader a line belongs to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceSection {
+    /// Code injected by ShaderBG (declarations, `main`, etc.).
+    Injected,
+    /// "Common" pass code.
+    Common,
+    /// Code of the pass itself.
+    Pass,
+}
+
+impl SourceSection {
+    /// Returns the source string number of the section in `#line`
+    /// directives.
+    fn source_string(self) -> usize {
+        match self {
+            Self::Injected => 0,
+            Self::Common => 1,
+            Self::Pass => 2,
+        }
+    }
+
+    /// Returns the section of a source string number.
+    fn from_source_string(source_string: usize) -> Self {
+        match source_string {
+            1 => Self::Common,
+            2 => Self::Pass,
+            _ => Self::Injected,
+        }
+    }
+}
+
+/// Location of a line of an assembled pass shader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SourceLocation {
+    pub section: SourceSection,
+
+    /// Line number (1-based) within the section. For injected code, the
+    /// line number within the assembled source.
+    pub line: usize,
+}
+
+impl SourceLocation {
+    /// Returns the location of the next line of the same section.
+    fn next(self) -> Self {
+        Self {
+            line: self.line + 1,
+            ..self
+        }
+    }
+}
+
+/// Maps lines of an assembled pass shader to the preset code.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    /// Line (1-based) of the assembled source at which the converted
+    /// preset code begins.
+    first_user_line: usize,
+
+    /// Location of each line of the converted preset code. Injected
+    /// lines are located at the line they would have without `#line`
+    /// directives.
+    user_lines: Vec<SourceLocation>,
+}
+
+impl SourceMap {
+    /// Creates a map for preset code converted from `common_shader`
+    /// followed by `pass_shader` and placed at `first_user_line`.
+    ///
+    /// `line_origins` are the lines of the unconverted code from which
+    /// each converted line originates, where the code is prefixed by
+    /// `prefix_line_count` injected lines and joined as
+    /// `common_shader + "\n" + pass_shader`.
+    pub fn new(
+        first_user_line: usize,
+        line_origins: &[usize],
+        prefix_line_count: usize,
+        common_shader: &str,
+        pass_shader: &str,
+    ) -> Self {
+        let common_line_count = common_shader.split('\n').count();
+        let pass_line_count = pass_shader.split('\n').count();
+
+        let user_lines = line_origins
+            .iter()
+            .enumerate()
+            .map(|(index, &origin)| {
+                let common_line = origin.saturating_sub(prefix_line_count);
+                let pass_line = common_line.saturating_sub(common_line_count);
+                if common_line == 0 || pass_line > pass_line_count {
+                    SourceLocation {
+                        section: SourceSection::Injected,
+                        line: first_user_line + index,
+                    }
+                } else if pass_line == 0 {
+                    SourceLocation {
+                        section: SourceSection::Common,
+                        line: common_line,
+                    }
+                } else {
+                    SourceLocation {
+                        section: SourceSection::Pass,
+                        line: pass_line,
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            first_user_line,
+            user_lines,
+        }
+    }
+
+    /// Inserts `#line` directives into the converted preset code, which
+    /// must begin at `first_user_line` of the assembled source, so that
+    /// compilers locate each of its lines in the preset code.
+    ///
+    /// Injected lines, including those following the converted code, are
+    /// located at their line in the result.
+    ///
+    /// `__LINE__` and `__FILE__` are replaced by the line and source
+    /// string number of the line they are on.
+    pub fn insert_line_directives(&self, user_source: &str) -> String {
+        let line_macros = Regex::new(r"\b(__LINE__|__FILE__)\b").unwrap();
+        let mut output = Vec::new();
+
+        // Location that the compiler assigns to the next line
+        let mut current = SourceLocation {
+            section: SourceSection::Injected,
+            line: self.first_user_line,
+        };
+        let directive = |output: &mut Vec<String>, location: SourceLocation| {
+            output.push(format!(
+                "#line {} {}",
+                location.line,
+                location.section.source_string()
+            ));
+        };
+
+        for (line, &location) in user_source.split('\n').zip(&self.user_lines) {
+            let location = match location.section {
+                SourceSection::Injected => SourceLocation {
+                    section: SourceSection::Injected,
+                    line: self.first_user_line + output.len(),
+                },
+                _ => location,
+            };
+            if location != current {
+                let location = match location.section {
+                    // The directive itself takes a line
+                    SourceSection::Injected => location.next(),
+                    _ => location,
+                };
+                directive(&mut output, location);
+                current = location;
+            }
+            let line = line_macros.replace_all(line, |caps: &Captures| {
+                if &caps[1] == "__LINE__" {
+                    current.line.to_string()
+                } else {
+                    current.section.source_string().to_string()
+                }
+            });
+            output.push(line.into_owned());
+            current = current.next();
+        }
+
+        // Injected code follows
+        let next_line = self.first_user_line + output.len();
+        if current.section != SourceSection::Injected || current.line != next_line {
+            directive(
+                &mut output,
+                SourceLocation {
+                    section: SourceSection::Injected,
+                    line: next_line + 1,
+                },
+            );
+        }
+
+        output.join("\n")
+    }
+
+    /// Returns the location of `line` of a source string, as reported by
+    /// compilers for code with the directives of
+    /// [`SourceMap::insert_line_directives`].
+    pub fn locate(&self, source_string: usize, line: usize) -> SourceLocation {
+        SourceLocation {
+            section: SourceSection::from_source_string(source_string),
+            line,
+        }
+    }
+
+    /// Rewrites the line references of a driver log to locations in the
+    /// preset, labeled with the section name, e.g. `Image:12(5)` or
+    /// `Common:3(1)` for Mesa logs.
+    pub fn rewrite_log(&self, log: &str, pass_name: &str) -> String {
+        let label = |source_string: &str, line: &str| {
+            let location = self.locate(
+                source_string.parse().unwrap_or_default(),
+                line.parse().unwrap_or_default(),
+            );
+            let section = match location.section {
+                SourceSection::Injected => INJECTED_LABEL,
+                SourceSection::Common => "Common",
+                SourceSection::Pass => pass_name,
+            };
+            (section, location.line)
+        };
+
+        // Mesa: `2:12(5): error: ...`
+        let mesa_location = Regex::new(r"(?m)^(\d+):(\d+)\((\d+)\)").unwrap();
+        // NVIDIA: `2(12) : error C0000: ...`
+        let nvidia_location = Regex::new(r"(?m)^(\d+)\((\d+)\)").unwrap();
+        // AMD, Intel and ANGLE: `ERROR: 2:12: ...`
+        let severity_location = Regex::new(r"(?m)^(ERROR|WARNING): (\d+):(\d+):").unwrap();
+
+        let log = mesa_location.replace_all(log, |caps: &Captures| {
+            let (section, line) = label(&caps[1], &caps[2]);
+            format!("{section}:{line}({})", &caps[3])
+        });
+        let log = nvidia_location.replace_all(&log, |caps: &Captures| {
+            let (section, line) = label(&caps[1], &caps[2]);
+            format!("{section}({line})")
+        });
+        severity_location
+            .replace_all(&log, |caps: &Captures| {
+                let (section, line) = label(&caps[2], &caps[3]);
+                format!("{}: {section}:{line}:", &caps[1])
+            })
+            .into_owned()
+    }
+
+    /// Rewrites the driver log of compilation and link errors with
+    /// [`SourceMap::rewrite_log`].
+    pub fn map_error(&self, err: ShaderError, pass_name: &str) -> ShaderError {
+        match err {
+            ShaderError::ShaderCompile(log) => {
+                ShaderError::ShaderCompile(self.rewrite_log(&log, pass_name))
+            }
+            ShaderError::ProgramLink(log) => {
+                ShaderError::ProgramLink(self.rewrite_log(&log, pass_name))
+            }
+            err => err,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+
+use super::super::animated_image::frame_index;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn test_frame_index_follows_delays() {
+    let end_times = [ms(100), ms(150), ms(400)];
+    assert_eq!(frame_index(&end_times, ms(0)), 0);
+    assert_eq!(frame_index(&end_times, ms(99)), 0);
+    assert_eq!(frame_index(&end_times, ms(100)), 1);
+    assert_eq!(frame_index(&end_times, ms(200)), 2);
+}
+
+#[test]
+fn test_frame_index_loops() {
+    let end_times = [ms(100), ms(150), ms(400)];
+    assert_eq!(frame_index(&end_times, ms(400)), 0);
+    assert_eq!(frame_index(&end_times, ms(525)), 1);
+    assert_eq!(frame_index(&end_times, ms(4399)), 2);
+}
+
+#[test]
+fn test_frame_index_without_frames() {
+    assert_eq!(frame_index(&[], ms(100)), 0);
+    assert_eq!(frame_index(&[Duration::ZERO], ms(100)), 0);
+}
@@ -0,0 +1,39 @@
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+
+use super::super::gpu_timer::TimeSamples;
+
+#[test]
+fn test_empty_samples_have_no_average() {
+    let samples = TimeSamples::new(4);
+
+    assert_eq!(samples.average(), None);
+}
+
+#[test]
+fn test_average_of_samples() {
+    let mut samples = TimeSamples::new(4);
+    samples.push(Duration::from_millis(1));
+    samples.push(Duration::from_millis(2));
+    samples.push(Duration::from_millis(6));
+
+    assert_eq!(samples.average(), Some(Duration::from_millis(3)));
+}
+
+#[test]
+fn test_oldest_samples_are_discarded_when_full() {
+    let mut samples = TimeSamples::new(2);
+    samples.push(Duration::from_millis(10));
+    samples.push(Duration::from_millis(2));
+    samples.push(Duration::from_millis(4));
+
+    assert_eq!(samples.average(), Some(Duration::from_millis(3)));
+}
+
+#[test]
+fn test_zero_capacity_keeps_no_samples() {
+    let mut samples = TimeSamples::new(0);
+    samples.push(Duration::from_millis(1));
+
+    assert_eq!(samples.average(), None);
+}
@@ -0,0 +1,25 @@
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+
+use super::super::gpu_watchdog::parse_gpu_watchdog_timeout;
+
+#[test]
+fn test_parse_gpu_watchdog_timeout() {
+    assert_eq!(parse_gpu_watchdog_timeout("2s"), Ok(Duration::from_secs(2)));
+    assert_eq!(
+        parse_gpu_watchdog_timeout("500ms"),
+        Ok(Duration::from_millis(500))
+    );
+}
+
+#[test]
+fn test_parse_gpu_watchdog_timeout_rejects_zero() {
+    assert!(parse_gpu_watchdog_timeout("0s").is_err());
+    assert!(parse_gpu_watchdog_timeout("0ms").is_err());
+}
+
+#[test]
+fn test_parse_gpu_watchdog_timeout_rejects_invalid() {
+    assert!(parse_gpu_watchdog_timeout("").is_err());
+    assert!(parse_gpu_watchdog_timeout("soon").is_err());
+}
@@ -0,0 +1,97 @@
+use pretty_assertions::assert_eq;
+
+use super::super::pass_graph::{
+    input_framebuffer, output_framebuffer, output_name, render_order, PassNode,
+};
+
+fn node<'a>(output: &'a str, inputs: &[&'a str]) -> PassNode<'a> {
+    PassNode {
+        output,
+        inputs: inputs.to_vec(),
+    }
+}
+
+#[test]
+fn test_independent_passes_keep_preset_order() {
+    let nodes = [
+        node("Buffer A", &["Buffer A"]),
+        node("Buffer B", &["Buffer A"]),
+        node("Buffer C", &[]),
+    ];
+
+    assert_eq!(render_order(&nodes), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_forward_reference_renders_dependency_first() {
+    let nodes = [
+        node("Buffer A", &["Buffer C"]),
+        node("Buffer B", &["Buffer A"]),
+        node("Buffer C", &[]),
+    ];
+
+    assert_eq!(render_order(&nodes), vec![2, 0, 1]);
+}
+
+#[test]
+fn test_cycle_is_broken_at_first_pass() {
+    let nodes = [
+        node("Buffer A", &["Buffer B"]),
+        node("Buffer B", &["Buffer A"]),
+        node("Buffer C", &["Buffer B"]),
+    ];
+
+    assert_eq!(render_order(&nodes), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_unknown_inputs_are_ignored() {
+    let nodes = [
+        node("Blur H", &["Abstract 1", "Blur V"]),
+        node("Blur V", &["Keyboard"]),
+    ];
+
+    assert_eq!(render_order(&nodes), vec![1, 0]);
+}
+
+#[test]
+fn test_cube_a_output_name() {
+    assert_eq!(output_name("Cube A"), "Cubemap A");
+    assert_eq!(output_name("Buffer A"), "Buffer A");
+}
+
+#[test]
+fn test_output_framebuffer_alternates() {
+    assert_eq!(output_framebuffer(0), 1);
+    assert_eq!(output_framebuffer(1), 0);
+    assert_eq!(output_framebuffer(u32::MAX), 0);
+}
+
+#[test]
+fn test_self_feedback_reads_previous_frame() {
+    let nodes = [
+        node("Cubemap A", &["Cubemap A", "Buffer A"]),
+        node("Buffer A", &[]),
+    ];
+    let order = render_order(&nodes);
+    let cube_a_order = order.iter().position(|&index| index == 0).unwrap();
+
+    for frame_number in 0..4 {
+        let written = output_framebuffer(frame_number);
+        let read = input_framebuffer(cube_a_order, cube_a_order, frame_number);
+
+        assert_ne!(read, written);
+        // The framebuffer written on the previous frame
+        assert_eq!(read, output_framebuffer(frame_number + 1));
+    }
+}
+
+#[test]
+fn test_inputs_rendered_earlier_read_current_frame() {
+    for frame_number in 0..4 {
+        let written = output_framebuffer(frame_number);
+
+        assert_eq!(input_framebuffer(0, 1, frame_number), written);
+        assert_ne!(input_framebuffer(1, 0, frame_number), written);
+    }
+}
@@ -0,0 +1,70 @@
+use pretty_assertions::assert_eq;
+use wayland_client::{protocol::wl_shm::Format, WEnum};
+
+use super::super::screen_capture::{to_rgba, BufferInfo};
+
+/// Two rows of two pixels with one byte per channel numbered in memory
+/// order, and rows padded to 12 bytes.
+const PIXELS: [u8; 24] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 0xee, 0xee, 0xee, 0xee, //
+    8, 9, 10, 11, 12, 13, 14, 15, 0xee, 0xee, 0xee, 0xee,
+];
+
+fn info(format: Format) -> BufferInfo {
+    BufferInfo {
+        format: WEnum::Value(format),
+        width: 2,
+        height: 2,
+        stride: 12,
+    }
+}
+
+#[test]
+fn test_to_rgba_argb8888() {
+    assert_eq!(
+        to_rgba(&PIXELS, info(Format::Argb8888), false).unwrap(),
+        [2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15]
+    );
+}
+
+#[test]
+fn test_to_rgba_xrgb8888() {
+    assert_eq!(
+        to_rgba(&PIXELS, info(Format::Xrgb8888), false).unwrap(),
+        [2, 1, 0, 255, 6, 5, 4, 255, 10, 9, 8, 255, 14, 13, 12, 255]
+    );
+}
+
+#[test]
+fn test_to_rgba_abgr8888() {
+    assert_eq!(
+        to_rgba(&PIXELS, info(Format::Abgr8888), false).unwrap(),
+        [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]
+    );
+}
+
+#[test]
+fn test_to_rgba_xbgr8888() {
+    assert_eq!(
+        to_rgba(&PIXELS, info(Format::Xbgr8888), false).unwrap(),
+        [0, 1, 2, 255, 4, 5, 6, 255, 8, 9, 10, 255, 12, 13, 14, 255]
+    );
+}
+
+#[test]
+fn test_to_rgba_flip() {
+    assert_eq!(
+        to_rgba(&PIXELS, info(Format::Abgr8888), true).unwrap(),
+        [8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7]
+    );
+}
+
+#[test]
+fn test_to_rgba_unsupported_format() {
+    assert!(to_rgba(&PIXELS, info(Format::Rgb565), false).is_err());
+    let unknown = BufferInfo {
+        format: WEnum::Unknown(0x1234),
+        ..info(Format::Argb8888)
+    };
+    assert!(to_rgba(&PIXELS, unknown, false).is_err());
+}
@@ -0,0 +1,76 @@
+use pretty_assertions::assert_eq;
+use std::{cell::Cell, rc::Rc};
+
+use super::super::shared_source::{FrameSource, SourceHandle};
+
+/// Source producing the given frames, one per poll.
+struct FakeSource {
+    frames: Vec<u32>,
+}
+
+impl FrameSource for FakeSource {
+    type Frame = u32;
+
+    fn poll_frame(&mut self) -> Option<u32> {
+        (!self.frames.is_empty()).then(|| self.frames.remove(0))
+    }
+}
+
+fn open(key: &str, frames: &[u32], opened: &Cell<u32>) -> SourceHandle<FakeSource> {
+    SourceHandle::open::<()>(key, || {
+        opened.set(opened.get() + 1);
+        Ok(FakeSource {
+            frames: frames.to_vec(),
+        })
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_source_is_opened_once_per_key() {
+    let opened = Cell::new(0);
+
+    let first = open("test:once", &[], &opened);
+    let _second = open("test:once", &[], &opened);
+    assert_eq!(opened.get(), 1);
+
+    let _other = open("test:other", &[], &opened);
+    assert_eq!(opened.get(), 2);
+
+    drop(first);
+    let _third = open("test:once", &[], &opened);
+    assert_eq!(opened.get(), 2);
+}
+
+#[test]
+fn test_source_is_reopened_after_last_handle_is_dropped() {
+    let opened = Cell::new(0);
+
+    drop(open("test:reopen", &[], &opened));
+    drop(open("test:reopen", &[], &opened));
+
+    assert_eq!(opened.get(), 2);
+}
+
+#[test]
+fn test_every_handle_sees_the_newest_frame() {
+    let opened = Cell::new(0);
+    let mut first = open("test:frames", &[1, 2], &opened);
+    let mut second = open("test:frames", &[], &opened);
+
+    assert_eq!(first.poll_frame().as_deref(), Some(&1));
+    assert_eq!(second.poll_frame().as_deref(), Some(&2));
+    assert_eq!(first.poll_frame().as_deref(), Some(&2));
+    assert_eq!(first.poll_frame(), None);
+    assert_eq!(second.poll_frame(), None);
+}
+
+#[test]
+fn test_frames_are_shared_without_copies() {
+    let opened = Cell::new(0);
+    let mut first = open("test:rc", &[7], &opened);
+    let mut second = open("test:rc", &[], &opened);
+
+    let (a, b) = (first.poll_frame().unwrap(), second.poll_frame().unwrap());
+    assert!(Rc::ptr_eq(&a, &b));
+}
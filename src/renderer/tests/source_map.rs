@@ -0,0 +1,85 @@
+use pretty_assertions::assert_eq;
+
+use super::super::source_map::{SourceLocation, SourceMap, SourceSection};
+
+/// Map of a source with 10 injected lines followed by the converted code
+/// of a 2-line "Common" and a 3-line pass, prefixed by a 1-line define.
+fn source_map() -> SourceMap {
+    SourceMap::new(11, &[2, 3, 4, 6], 1, "float a;\nfloat b;", "x\ny\nz")
+}
+
+#[test]
+fn test_insert_line_directives() {
+    let user_source = "#define X\nfloat a;\nfloat b;\nx\nz";
+    let map = SourceMap::new(11, &[1, 2, 3, 4, 6], 1, "float a;\nfloat b;", "x\ny\nz");
+
+    assert_eq!(
+        map.insert_line_directives(user_source),
+        "#define X\n#line 1 1\nfloat a;\nfloat b;\n#line 1 2\nx\n#line 3 2\nz\n#line 20 0"
+    );
+}
+
+#[test]
+fn test_insert_line_directives_after_injected_lines() {
+    let user_source = "float a;\nvoid f();\nx";
+    let map = SourceMap::new(5, &[2, 0, 3], 1, "float a;", "x");
+
+    assert_eq!(
+        map.insert_line_directives(user_source),
+        "#line 1 1\nfloat a;\n#line 8 0\nvoid f();\n#line 1 2\nx\n#line 12 0"
+    );
+}
+
+#[test]
+fn test_insert_line_directives_resolves_line_macros() {
+    let user_source = "#define X __LINE__\nint l = __LINE__;\nint f = __FILE__, l = __LINE__;";
+    let map = SourceMap::new(11, &[1, 3, 6], 1, "float a;\nint l;", "x\ny\nz");
+
+    assert_eq!(
+        map.insert_line_directives(user_source),
+        "#define X 11\n#line 2 1\nint l = 2;\n#line 3 2\nint f = 2, l = 3;\n#line 17 0"
+    );
+}
+
+#[test]
+fn test_locate_source_strings() {
+    let map = source_map();
+    let location = |section, line| SourceLocation { section, line };
+
+    assert_eq!(map.locate(0, 10), location(SourceSection::Injected, 10));
+    assert_eq!(map.locate(1, 2), location(SourceSection::Common, 2));
+    assert_eq!(map.locate(2, 3), location(SourceSection::Pass, 3));
+}
+
+#[test]
+fn test_rewrite_mesa_log() {
+    let log = "2:1(5): error: `foo' undeclared\n1:2(1): warning: unused\n";
+    assert_eq!(
+        source_map().rewrite_log(log, "Image"),
+        "Image:1(5): error: `foo' undeclared\nCommon:2(1): warning: unused\n"
+    );
+}
+
+#[test]
+fn test_rewrite_nvidia_log() {
+    let log = "2(3) : error C1008: undefined variable \"foo\"";
+    assert_eq!(
+        source_map().rewrite_log(log, "Buffer A"),
+        "Buffer A(3) : error C1008: undefined variable \"foo\""
+    );
+}
+
+#[test]
+fn test_rewrite_severity_prefixed_log() {
+    let log = "ERROR: 1:1: 'foo' : undeclared identifier\nWARNING: 0:2: extension not supported";
+    assert_eq!(
+        source_map().rewrite_log(log, "Image"),
+        "ERROR: Common:1: 'foo' : undeclared identifier\nWARNING: ShaderBG:2: extension not supported"
+    );
+}
+
+#[test]
+fn test_rewrite_log_without_locations() {
+    let log = "Linking failed: missing main";
+    assert_eq!(source_map().rewrite_log(log, "Image"), log);
+}
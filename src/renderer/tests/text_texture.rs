@@ -0,0 +1,41 @@
+use chrono::NaiveDate;
+use pretty_assertions::assert_eq;
+
+use super::super::text_texture::{argb_to_rgba, expand_time_patterns};
+
+fn time() -> chrono::NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 3, 7)
+        .unwrap()
+        .and_hms_opt(9, 5, 30)
+        .unwrap()
+}
+
+#[test]
+fn test_expand_time_patterns() {
+    assert_eq!(expand_time_patterns("%H:%M", &time()), "09:05");
+    assert_eq!(
+        expand_time_patterns("<b>%Y-%m-%d</b> %S", &time()),
+        "<b>2025-03-07</b> 30"
+    );
+    assert_eq!(expand_time_patterns("100%%", &time()), "100%");
+}
+
+#[test]
+fn test_expand_time_patterns_without_patterns() {
+    assert_eq!(
+        expand_time_patterns("<span size='x-large'>Hello</span>", &time()),
+        "<span size='x-large'>Hello</span>"
+    );
+}
+
+#[test]
+fn test_expand_invalid_time_patterns() {
+    assert_eq!(expand_time_patterns("100%", &time()), "100%");
+}
+
+#[test]
+fn test_argb_to_rgba() {
+    assert_eq!(argb_to_rgba(0xff10_2030), [0x10, 0x20, 0x30, 0xff]);
+    assert_eq!(argb_to_rgba(0x8040_2000), [0x80, 0x40, 0x00, 0x80]);
+    assert_eq!(argb_to_rgba(0x0000_0000), [0, 0, 0, 0]);
+}
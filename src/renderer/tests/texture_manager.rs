@@ -0,0 +1,204 @@
+use image::{Rgb, Rgb32FImage, RgbImage};
+use pretty_assertions::assert_eq;
+use std::path::{Path, PathBuf};
+
+use super::super::texture_manager::{
+    is_hdr_image, resolve_input_path, split_cubemap_faces, CubemapLayout,
+};
+use crate::preset::InputType;
+
+/// Builds an image whose cells of `face_size` pixels are filled with
+/// `(column, row, 0)`, so that each extracted face can be identified.
+fn grid_image(columns: u32, rows: u32, face_size: u32) -> RgbImage {
+    RgbImage::from_fn(columns * face_size, rows * face_size, |x, y| {
+        Rgb([(x / face_size) as u8, (y / face_size) as u8, 0])
+    })
+}
+
+#[test]
+fn test_resolve_builtin_assets() {
+    let assets_dir = Path::new("/assets");
+    let base_dir = Some(Path::new("/presets"));
+
+    assert_eq!(
+        resolve_input_path(InputType::Texture, "London", base_dir, assets_dir),
+        PathBuf::from("/assets/textures/london.jpg")
+    );
+    assert_eq!(
+        resolve_input_path(InputType::Cubemap, "Forest", base_dir, assets_dir),
+        PathBuf::from("/assets/cubemaps/forest.png")
+    );
+    assert_eq!(
+        resolve_input_path(InputType::Volume, "RGBA Noise3D", base_dir, assets_dir),
+        PathBuf::from("/assets/volumes/rgba_noise_3d.png")
+    );
+}
+
+#[test]
+fn test_resolve_builtin_name_of_other_type() {
+    let path = resolve_input_path(
+        InputType::Cubemap,
+        "London",
+        Some(Path::new("/presets")),
+        Path::new("/assets"),
+    );
+    assert_eq!(path, PathBuf::from("/presets/London"));
+}
+
+#[test]
+fn test_resolve_relative_path() {
+    let assets_dir = Path::new("/assets");
+
+    assert_eq!(
+        resolve_input_path(
+            InputType::Texture,
+            "textures/rock.png",
+            Some(Path::new("/home/user/presets")),
+            assets_dir
+        ),
+        PathBuf::from("/home/user/presets/textures/rock.png")
+    );
+    assert_eq!(
+        resolve_input_path(InputType::Texture, "rock.png", None, assets_dir),
+        PathBuf::from("rock.png")
+    );
+}
+
+#[test]
+fn test_resolve_absolute_path() {
+    let path = resolve_input_path(
+        InputType::Volume,
+        "/data/noise.png",
+        Some(Path::new("/presets")),
+        Path::new("/assets"),
+    );
+    assert_eq!(path, PathBuf::from("/data/noise.png"));
+}
+
+#[test]
+fn test_resolve_uri() {
+    let path = resolve_input_path(
+        InputType::Video,
+        "https://example.com/clip.webm",
+        Some(Path::new("/presets")),
+        Path::new("/assets"),
+    );
+    assert_eq!(path, PathBuf::from("https://example.com/clip.webm"));
+}
+
+#[test]
+fn test_detect_cubemap_layout() {
+    assert_eq!(
+        CubemapLayout::detect(1536, 256),
+        Some((CubemapLayout::Strip, 256))
+    );
+    assert_eq!(
+        CubemapLayout::detect(1024, 768),
+        Some((CubemapLayout::HorizontalCross, 256))
+    );
+    assert_eq!(
+        CubemapLayout::detect(768, 1024),
+        Some((CubemapLayout::VerticalCross, 256))
+    );
+    assert_eq!(CubemapLayout::detect(1000, 700), None);
+    assert_eq!(CubemapLayout::detect(512, 512), None);
+    assert_eq!(CubemapLayout::detect(0, 0), None);
+}
+
+#[test]
+fn test_split_strip() {
+    let faces = split_cubemap_faces(&grid_image(6, 1, 2)).unwrap();
+    let cells: Vec<_> = faces.iter().map(|face| face.get_pixel(0, 0).0).collect();
+    assert_eq!(
+        cells,
+        [
+            [0, 0, 0],
+            [1, 0, 0],
+            [2, 0, 0],
+            [3, 0, 0],
+            [4, 0, 0],
+            [5, 0, 0]
+        ]
+    );
+}
+
+#[test]
+fn test_split_horizontal_cross() {
+    let faces = split_cubemap_faces(&grid_image(4, 3, 2)).unwrap();
+    let cells: Vec<_> = faces.iter().map(|face| face.get_pixel(0, 0).0).collect();
+    assert_eq!(
+        cells,
+        [
+            [2, 1, 0],
+            [0, 1, 0],
+            [1, 0, 0],
+            [1, 2, 0],
+            [1, 1, 0],
+            [3, 1, 0]
+        ]
+    );
+}
+
+#[test]
+fn test_split_vertical_cross_rotates_negative_z() {
+    let mut img = grid_image(3, 4, 2);
+    img.put_pixel(2, 6, Rgb([255, 255, 255]));
+
+    let faces = split_cubemap_faces(&img).unwrap();
+    assert_eq!(faces[4].get_pixel(0, 0).0, [1, 1, 0]);
+    assert_eq!(faces[5].get_pixel(1, 1).0, [255, 255, 255]);
+    assert_eq!(faces[5].get_pixel(0, 0).0, [1, 3, 0]);
+}
+
+#[test]
+fn test_split_unsupported_layout() {
+    assert!(split_cubemap_faces(&grid_image(2, 2, 2)).is_none());
+}
+
+#[test]
+fn test_split_float_strip() {
+    let img = Rgb32FImage::from_fn(12, 2, |x, _| Rgb([(x / 2) as f32 + 0.5, 0.0, 0.0]));
+
+    let faces = split_cubemap_faces(&img).unwrap();
+    assert_eq!(faces[0].get_pixel(0, 0).0, [0.5, 0.0, 0.0]);
+    assert_eq!(faces[5].get_pixel(1, 1).0, [5.5, 0.0, 0.0]);
+}
+
+#[test]
+fn test_is_hdr_image() {
+    assert!(is_hdr_image(Path::new("sky.hdr")));
+    assert!(is_hdr_image(Path::new("/assets/sky_{face}.EXR")));
+    assert!(!is_hdr_image(Path::new("sky.png")));
+    assert!(!is_hdr_image(Path::new("hdr")));
+}
+
+#[test]
+fn test_split_equirectangular_panorama() {
+    // Upper hemisphere is bright, lower hemisphere is dark
+    let img = Rgb32FImage::from_fn(32, 16, |_, y| {
+        Rgb([if y < 8 { 1.0 } else { 0.0 }, 0.0, 0.0])
+    });
+
+    let faces = split_cubemap_faces(&img).unwrap();
+    assert!(faces.iter().all(|face| face.dimensions() == (8, 8)));
+    assert!(faces[2].pixels().all(|pixel| pixel.0[0] > 0.999));
+    assert!(faces[3].pixels().all(|pixel| pixel.0[0] == 0.0));
+    assert!(faces[4].get_pixel(4, 0).0[0] > 0.999);
+    assert_eq!(faces[4].get_pixel(4, 7).0[0], 0.0);
+}
+
+#[test]
+fn test_project_equirectangular_centers_positive_z() {
+    // Red increases from left to right across the panorama
+    let img = Rgb32FImage::from_fn(64, 32, |x, _| Rgb([x as f32, 0.0, 0.0]));
+
+    let faces = split_cubemap_faces(&img).unwrap();
+    let center = |face: usize| {
+        let [r, ..] = faces[face].get_pixel(7, 8).0;
+        let [r2, ..] = faces[face].get_pixel(8, 8).0;
+        (r + r2) / 2.0
+    };
+    assert!((center(4) - 31.5).abs() < 1.0);
+    assert!((center(0) - 47.5).abs() < 1.0);
+    assert!((center(1) - 15.5).abs() < 1.0);
+}
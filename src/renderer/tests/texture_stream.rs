@@ -0,0 +1,68 @@
+use pretty_assertions::assert_eq;
+
+use super::super::texture_stream::{read_frame, StreamError, FRAME_HEADER_SIZE, FRAME_MAGIC};
+use crate::geometry::Size;
+
+fn encode_frame(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FRAME_HEADER_SIZE + pixels.len());
+    bytes.extend_from_slice(FRAME_MAGIC);
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(pixels);
+    bytes
+}
+
+#[test]
+fn test_read_frame() {
+    let pixels: Vec<u8> = (0..2 * 3 * 4).collect();
+    let bytes = encode_frame(2, 3, &pixels);
+
+    let frame = read_frame(&mut bytes.as_slice()).unwrap();
+    assert_eq!(frame.size, Size::new(2, 3));
+    assert_eq!(frame.data, pixels);
+}
+
+#[test]
+fn test_read_consecutive_frames() {
+    let mut bytes = encode_frame(1, 1, &[1, 2, 3, 4]);
+    bytes.extend(encode_frame(1, 1, &[5, 6, 7, 8]));
+    let mut reader = bytes.as_slice();
+
+    assert_eq!(read_frame(&mut reader).unwrap().data, vec![1, 2, 3, 4]);
+    assert_eq!(read_frame(&mut reader).unwrap().data, vec![5, 6, 7, 8]);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn test_read_frame_invalid_magic() {
+    let mut bytes = encode_frame(1, 1, &[0; 4]);
+    bytes[0] = b'X';
+
+    assert!(matches!(
+        read_frame(&mut bytes.as_slice()),
+        Err(StreamError::InvalidMagic)
+    ));
+}
+
+#[test]
+fn test_read_frame_invalid_size() {
+    let bytes = encode_frame(0, 4, &[]);
+
+    assert!(matches!(
+        read_frame(&mut bytes.as_slice()),
+        Err(StreamError::InvalidSize(0, 4))
+    ));
+}
+
+#[test]
+fn test_read_frame_truncated() {
+    let bytes = encode_frame(2, 2, &[0; 10]);
+
+    assert!(matches!(
+        read_frame(&mut bytes.as_slice()),
+        Err(StreamError::Truncated {
+            expected: 16,
+            actual: 10
+        })
+    ));
+}
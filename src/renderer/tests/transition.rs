@@ -0,0 +1,39 @@
+use pretty_assertions::assert_eq;
+
+use super::super::transition::{builtin_transition_source, resolve_transition_parameters};
+use crate::preset::Transition;
+
+#[test]
+fn test_resolve_parameters_with_defaults() {
+    let source = "uniform float smoothness; // = 0.3\n\
+                  uniform vec2 center; // = vec2(0.5, 0.5);\n\
+                  vec4 transition(vec2 uv) { return vec4(0.0); }";
+
+    assert_eq!(
+        resolve_transition_parameters(source),
+        "const float smoothness = 0.3;\n\
+         const vec2 center = vec2(0.5, 0.5);\n\
+         vec4 transition(vec2 uv) { return vec4(0.0); }"
+    );
+}
+
+#[test]
+fn test_resolve_parameters_keeps_other_lines() {
+    let source = "uniform float amplitude;\n\
+                  float x = 1.0; // = not a parameter\n\
+                  // = comment";
+
+    assert_eq!(resolve_transition_parameters(source), source);
+}
+
+#[test]
+fn test_builtin_sources() {
+    assert!(builtin_transition_source(&Transition::Crossfade).is_some());
+    assert!(builtin_transition_source(&Transition::CircularReveal)
+        .unwrap()
+        .contains("vec4 transition(vec2 uv)"));
+    assert_eq!(
+        builtin_transition_source(&Transition::Custom("fade.glsl".into())),
+        None
+    );
+}
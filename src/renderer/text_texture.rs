@@ -0,0 +1,118 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Text rasterization for `InputType::Text` channels.
+//!
+//! The input name is a Pango markup string whose strftime patterns (e.g.,
+//! `%H:%M`) are expanded with the local time, so that shaders can style
+//! or distort text laid out by the system, such as a clock. The texture
+//! is rasterized again only when the expanded text changes.
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    NaiveDateTime,
+};
+use pangocairo::{cairo, glib, pango};
+use std::fmt::Write;
+use thiserror::Error;
+
+use crate::geometry::Size;
+
+#[derive(Debug, Error)]
+pub enum TextError {
+    #[error("Invalid markup: {0}")]
+    Markup(#[from] glib::Error),
+    #[error("Cairo error: {0}")]
+    Cairo(#[from] cairo::Error),
+    #[error("Failed to access surface data: {0}")]
+    SurfaceData(#[from] cairo::BorrowError),
+}
+
+/// Text rasterized into tightly packed RGBA8 pixels, top row first.
+pub struct RasterizedText {
+    pub size: Size,
+    pub data: Vec<u8>,
+}
+
+/// Expands the strftime patterns of `text` with `time`.
+///
+/// Text with invalid patterns is returned unchanged; a literal `%` is
+/// written as `%%`.
+pub fn expand_time_patterns(text: &str, time: &NaiveDateTime) -> String {
+    if StrftimeItems::new(text).any(|item| item == Item::Error) {
+        return text.to_string();
+    }
+
+    let mut expanded = String::with_capacity(text.len());
+    match write!(expanded, "{}", time.format(text)) {
+        Ok(()) => expanded,
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Lays out Pango markup and rasterizes it into a texture of the size of
+/// its logical extents.
+///
+/// Text is black unless colored by the markup, over a transparent
+/// background.
+pub fn rasterize_markup(markup: &str, vflip: bool) -> Result<RasterizedText, TextError> {
+    pango::parse_markup(markup, '\0')?;
+
+    let measure_surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 1, 1)?;
+    let layout = pangocairo::functions::create_layout(&cairo::Context::new(&measure_surface)?);
+    layout.set_markup(markup);
+    let (_, extents) = layout.pixel_extents();
+    let (width, height) = (extents.width().max(1), extents.height().max(1));
+
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    {
+        let cr = cairo::Context::new(&surface)?;
+        cr.move_to(-extents.x() as f64, -extents.y() as f64);
+        pangocairo::functions::update_layout(&cr, &layout);
+        pangocairo::functions::show_layout(&cr, &layout);
+    }
+    surface.flush();
+
+    let stride = surface.stride() as usize;
+    let row_size = width as usize * 4;
+    let surface_data = surface.data()?;
+    let mut data = Vec::with_capacity(row_size * height as usize);
+    let mut append_row = |row: &[u8]| {
+        for pixel in row[..row_size].chunks_exact(4) {
+            let argb = u32::from_ne_bytes(pixel.try_into().expect("Pixel must have 4 bytes"));
+            data.extend(argb_to_rgba(argb));
+        }
+    };
+
+    let rows = surface_data.chunks(stride).take(height as usize);
+    if vflip {
+        rows.rev().for_each(&mut append_row);
+    } else {
+        rows.for_each(&mut append_row);
+    }
+
+    Ok(RasterizedText {
+        size: Size::new(width as u32, height as u32),
+        data,
+    })
+}
+
+/// Converts a premultiplied Cairo ARGB32 pixel to straight RGBA8.
+pub fn argb_to_rgba(argb: u32) -> [u8; 4] {
+    let alpha = argb >> 24;
+    let unpremultiply = |shift: u32| {
+        let channel = (argb >> shift) & 0xff;
+        (channel * 255 + alpha / 2)
+            .checked_div(alpha)
+            .map_or(0, |channel| channel.min(255) as u8)
+    };
+
+    [
+        unpremultiply(16),
+        unpremultiply(8),
+        unpremultiply(0),
+        alpha as u8,
+    ]
+}
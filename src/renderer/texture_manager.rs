@@ -7,15 +7,36 @@
 //!
 //! Responsible for loading external textures (2D, cubemap, 3D),
 //! registering render-pass outputs as textures, and managing
-//! texture lifetime, including the ShaderToy keyboard input texture.
+//! texture lifetime, including the ShaderToy keyboard input texture
+//! and video textures streamed from GStreamer decoders.
+//!
+//! External inputs are either names of bundled ShaderToy assets or paths
+//! to image files. Relative paths are resolved against the directory of
+//! the preset file.
 
+use chrono::Local;
 use gl::types::*;
 use image::*;
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+use thiserror::Error;
 
 use crate::{geometry::Size, keyboard_controller::KeyboardData, preset::*, APP_NAME};
 
+use super::animated_image::AnimatedImage;
+use super::pass_graph::output_name;
 use super::render_pass::RenderPass;
+use super::screen_capture::ScreenCapture;
+use super::shared_source::SourceHandle;
+use super::text_texture::{expand_time_patterns, rasterize_markup};
+use super::texture_stream::TextureStream;
+#[cfg(feature = "video")]
+use super::video_decoder::VideoDecoder;
 
 /// GPU texture wrapper with ownership semantics.
 ///
@@ -25,11 +46,36 @@ use super::render_pass::RenderPass;
 struct Texture {
     id: GLuint,
     input_type: InputType,
+
+    /// Size of level 0, kept up to date when the storage is reallocated
+    /// so that it is never queried while rendering.
+    size: Cell<Size>,
+
+    /// Number of slices of volume textures, 1 otherwise.
+    depth: u32,
 }
 
 impl Texture {
+    /// Wraps a texture created from an input of type `input_type`,
+    /// querying its dimensions.
     fn new(id: u32, input_type: InputType) -> Self {
-        Self { id, input_type }
+        let (size, depth) = query_dimensions(id, input_type);
+        Self::with_size(id, input_type, size, depth)
+    }
+
+    fn with_size(id: u32, input_type: InputType, size: Size, depth: u32) -> Self {
+        Self {
+            id,
+            input_type,
+            size: Cell::new(size),
+            depth,
+        }
+    }
+
+    /// Width, height and depth, as reported by `iChannelResolution`.
+    fn resolution(&self) -> [f32; 3] {
+        let size = self.size.get();
+        [size.width() as f32, size.height() as f32, self.depth as f32]
     }
 }
 
@@ -44,6 +90,102 @@ impl Drop for Texture {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum TextureError {
+    #[error("File not found: {}", .0.display())]
+    NotFound(PathBuf),
+
+    #[error("Failed to decode {}: {source}", .path.display())]
+    Decode { path: PathBuf, source: ImageError },
+
+    #[error(
+        "Unsupported cubemap layout in {} ({width}x{height}); expected a 6:1 strip, a 4:3/3:4 cross or a 2:1 panorama",
+        .path.display()
+    )]
+    CubemapLayout {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+
+    #[error("Cubemap faces of {} must be square and of equal size", .0.display())]
+    CubemapFaces(PathBuf),
+
+    #[error(
+        "Unsupported volume layout in {} ({width}x{height}); expected a strip of square slices",
+        .path.display()
+    )]
+    VolumeLayout {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Number of faces of a cubemap.
+const CUBEMAP_NUM_FACES: usize = 6;
+
+/// Suffixes substituted for [`CUBEMAP_FACE_PLACEHOLDER`], in OpenGL
+/// face order (+X, -X, +Y, -Y, +Z, -Z).
+const CUBEMAP_FACE_SUFFIXES: [&str; CUBEMAP_NUM_FACES] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Marks a cubemap input made of six separate image files,
+/// e.g. `sky_{face}.png` for `sky_px.png`, `sky_nx.png`, etc.
+pub const CUBEMAP_FACE_PLACEHOLDER: &str = "{face}";
+
+/// Six images of a cubemap, in OpenGL face order.
+pub type CubemapFaces<P> = [ImageBuffer<P, Vec<<P as Pixel>::Subpixel>>; CUBEMAP_NUM_FACES];
+
+/// Arrangement of the six faces within a single cubemap image.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CubemapLayout {
+    /// Horizontal strip: +X | -X | +Y | -Y | +Z | -Z
+    Strip,
+    /// Horizontal cross (4x3 faces):
+    ///
+    /// ```text
+    ///      +Y
+    /// -X | +Z | +X | -Z
+    ///      -Y
+    /// ```
+    HorizontalCross,
+    /// Vertical cross (3x4 faces), with -Z rotated by 180 degrees:
+    ///
+    /// ```text
+    ///      +Y
+    /// -X | +Z | +X
+    ///      -Y
+    ///      -Z
+    /// ```
+    VerticalCross,
+}
+
+impl CubemapLayout {
+    /// Detects the layout from the image dimensions.
+    ///
+    /// Returns the layout and the size of each (square) face.
+    pub fn detect(width: u32, height: u32) -> Option<(Self, u32)> {
+        if height > 0 && width == 6 * height {
+            Some((Self::Strip, height))
+        } else if width > 0 && width.is_multiple_of(4) && 3 * width == 4 * height {
+            Some((Self::HorizontalCross, width / 4))
+        } else if width > 0 && width.is_multiple_of(3) && 4 * width == 3 * height {
+            Some((Self::VerticalCross, width / 3))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the (column, row) cell of each face, in OpenGL face order.
+    pub fn cells(self) -> [(u32, u32); CUBEMAP_NUM_FACES] {
+        match self {
+            Self::Strip => [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)],
+            Self::HorizontalCross => [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)],
+            Self::VerticalCross => [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)],
+        }
+    }
+}
+
 /// Width matches JavaScript keycode range (0–255).
 const KEYBOARD_TEXTURE_WIDTH: usize = 256;
 
@@ -53,6 +195,104 @@ const KEYBOARD_TEXTURE_WIDTH: usize = 256;
 /// Row #2: Toggled
 const KEYBOARD_TEXTURE_HEIGHT: usize = 3;
 
+/// Video input streaming decoded frames into a texture.
+#[cfg(feature = "video")]
+struct VideoStream {
+    /// Decoder producing RGBA frames.
+    decoder: VideoDecoder,
+
+    /// Key of the texture receiving the frames.
+    key: String,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
+/// Webcam input receiving captured frames into a texture.
+#[cfg(feature = "video")]
+struct WebcamInput {
+    /// Capture shared with the texture managers of other monitors.
+    capture: SourceHandle<VideoDecoder>,
+
+    /// Key of the texture receiving the frames.
+    key: String,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
+/// Stream input receiving raw RGBA frames from an external program.
+struct StreamInput {
+    /// Stream shared with the texture managers of other monitors.
+    stream: SourceHandle<TextureStream>,
+
+    /// Key of the texture receiving the frames.
+    key: String,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
+/// Screen input receiving captures of an output.
+struct ScreenInput {
+    /// Capture shared with the texture managers of other monitors.
+    capture: SourceHandle<ScreenCapture>,
+
+    /// Key of the texture receiving the frames.
+    key: String,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
+/// Animated image input cycling through its decoded frames.
+struct AnimationInput {
+    animation: AnimatedImage,
+
+    /// Key of the texture receiving the frames.
+    key: String,
+
+    /// Index of the uploaded frame, if any.
+    frame: Option<usize>,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
+/// Name of the texture of album art inputs, whose input name is ignored.
+pub const ALBUM_ART_TEXTURE_NAME: &str = "Album Art";
+
+/// Album art input replaced whenever the track changes.
+struct AlbumArtInput {
+    /// Key of the texture receiving the album art.
+    key: String,
+
+    /// Uploaded album art, if any.
+    album_art: Option<Rc<RgbaImage>>,
+
+    vflip: bool,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
+/// Text input rasterized whenever its expanded text changes.
+struct TextInput {
+    /// Pango markup with strftime patterns.
+    markup: String,
+
+    /// Key of the texture receiving the text.
+    key: String,
+
+    /// Expanded markup of the uploaded text, if any.
+    text: Option<String>,
+
+    vflip: bool,
+
+    /// Whether mipmaps are regenerated after each upload.
+    build_mipmaps: bool,
+}
+
 /// Central registry for all textures used by the renderer.
 ///
 /// Maintains the external input textures, the framebuffer output
@@ -73,17 +313,41 @@ const KEYBOARD_TEXTURE_HEIGHT: usize = 3;
 /// This allows passes to safely read previous-frame results.
 pub struct TextureManager {
     map: HashMap<String, Texture>,
+    /// Rendering order of each pass, by the name of its output.
+    pass_orders: HashMap<String, usize>,
+    /// Directory against which relative input paths are resolved.
+    base_dir: Option<PathBuf>,
     keyboard_texture: Option<Texture>,
     // index = row * 256 + keycode
     keyboard_state: [u8; KEYBOARD_TEXTURE_WIDTH * KEYBOARD_TEXTURE_HEIGHT],
+    #[cfg(feature = "video")]
+    videos: Vec<VideoStream>,
+    #[cfg(feature = "video")]
+    webcams: Vec<WebcamInput>,
+    streams: Vec<StreamInput>,
+    screens: Vec<ScreenInput>,
+    animations: Vec<AnimationInput>,
+    texts: Vec<TextInput>,
+    album_arts: Vec<AlbumArtInput>,
 }
 
 impl TextureManager {
-    pub fn new() -> Self {
+    pub fn new(base_dir: Option<PathBuf>) -> Self {
         Self {
             map: HashMap::new(),
+            pass_orders: HashMap::new(),
+            base_dir,
             keyboard_texture: None,
             keyboard_state: [0; KEYBOARD_TEXTURE_WIDTH * KEYBOARD_TEXTURE_HEIGHT],
+            #[cfg(feature = "video")]
+            videos: Vec::new(),
+            #[cfg(feature = "video")]
+            webcams: Vec::new(),
+            streams: Vec::new(),
+            screens: Vec::new(),
+            animations: Vec::new(),
+            texts: Vec::new(),
+            album_arts: Vec::new(),
         }
     }
 
@@ -92,10 +356,26 @@ impl TextureManager {
         self.map.get(name).map(|t| t.id)
     }
 
+    /// Returns the rendering order of the pass whose output has the
+    /// given input name, or `None` if the name is not a pass output.
+    pub fn pass_order(&self, name: &str) -> Option<usize> {
+        self.pass_orders.get(name).copied()
+    }
+
     pub fn keyboard_id(&self) -> Option<GLuint> {
         self.keyboard_texture.as_ref().map(|t| t.id)
     }
 
+    /// Returns the width, height and depth of the texture associated
+    /// with an input name.
+    pub fn resolution(&self, name: &str) -> Option<[f32; 3]> {
+        self.map.get(name).map(Texture::resolution)
+    }
+
+    pub fn keyboard_resolution(&self) -> Option<[f32; 3]> {
+        self.keyboard_texture.as_ref().map(Texture::resolution)
+    }
+
     /// Loads textures required by the render pipeline.
     ///
     /// Performs three passes:
@@ -118,22 +398,45 @@ impl TextureManager {
 
         let assets_dir = assets_dir();
 
+        // Inputs naming a pass output sample its framebuffers, whatever
+        // their type, e.g., "Cubemap A" inputs of type `cubemap`
+        let outputs: HashSet<&str> = passes.iter().map(|pass| output_name(pass.name())).collect();
+
         // Load external textures and register pass outputs
         for pass in passes {
             for input in pass.inputs().iter().filter_map(|opt| opt.as_ref()) {
                 // Texture key uniquely identifies texture + vertical flip state.
                 // Prevents duplicate GPU uploads.
-                let key = input.name.clone()
+                let name = if input._type == InputType::AlbumArt {
+                    ALBUM_ART_TEXTURE_NAME
+                } else {
+                    &input.name
+                };
+                let key = name.to_string()
                     + if input.vflip
-                        && matches!(input._type, InputType::Texture | InputType::Cubemap)
+                        && matches!(
+                            input._type,
+                            InputType::Texture
+                                | InputType::Cubemap
+                                | InputType::Video
+                                | InputType::Webcam
+                                | InputType::Stream
+                                | InputType::Text
+                                | InputType::AlbumArt
+                                | InputType::Screen
+                        )
                     {
                         "vflip"
                     } else {
                         ""
                     };
-                if !input.name.is_empty()
+                // Webcams without a name capture the default device, and
+                // screens without a name the first output.
+                if (!name.is_empty()
+                    || matches!(input._type, InputType::Webcam | InputType::Screen))
                     && input._type != InputType::Misc
                     && input._type != InputType::Keyboard
+                    && !outputs.contains(name)
                     && !self.map.contains_key(&key)
                 {
                     // Determine whether any pass requests mipmapped sampling
@@ -150,71 +453,51 @@ impl TextureManager {
                     });
 
                     let external_input_id = match input._type {
-                        InputType::Texture => {
-                            let dir = assets_dir.join("textures");
-                            let file = match input.name.as_str() {
-                                "Abstract 1" => dir.join("abstract_1.jpg"),
-                                "Abstract 2" => dir.join("abstract_2.jpg"),
-                                "Abstract 3" => dir.join("abstract_3.jpg"),
-                                "Bayer" => dir.join("bayer.png"),
-                                "Blue Noise" => dir.join("blue_noise.png"),
-                                "Font 1" => dir.join("font_1.png"),
-                                "Gray Noise Medium" => dir.join("gray_noise_medium.png"),
-                                "Gray Noise Small" => dir.join("gray_noise_small.png"),
-                                "Lichen" => dir.join("lichen.jpg"),
-                                "London" => dir.join("london.jpg"),
-                                "Nyancat" => dir.join("nyancat.png"),
-                                "Organic 1" => dir.join("organic_1.jpg"),
-                                "Organic 2" => dir.join("organic_2.jpg"),
-                                "Organic 3" => dir.join("organic_3.jpg"),
-                                "Organic 4" => dir.join("organic_4.jpg"),
-                                "Pebbles" => dir.join("pebbles.png"),
-                                "RGBA Noise Medium" => dir.join("rgba_noise_medium.png"),
-                                "RGBA Noise Small" => dir.join("rgba_noise_small.png"),
-                                "Rock Tiles" => dir.join("rock_tiles.jpg"),
-                                "Rusty Metal" => dir.join("rusty_metal.jpg"),
-                                "Stars" => dir.join("stars.jpg"),
-                                "Wood" => dir.join("wood.jpg"),
-                                _ => PathBuf::from(input.name.clone()),
-                            };
-                            load_2d_texture(file, input.vflip, build_mipmaps)
-                        }
-                        InputType::Cubemap => {
-                            let dir = assets_dir.join("cubemaps");
-                            let file = match input.name.as_str() {
-                                "Forest" => dir.join("forest.png"),
-                                "Forest Blurred" => dir.join("forest_blurred.png"),
-                                "St. Peter's Basilica" => dir.join("st_peters_basilica.png"),
-                                "St. Peter's Basilica Blurred" => {
-                                    dir.join("st_peters_basilica_blurred.png")
+                        InputType::Texture | InputType::Cubemap | InputType::Volume => {
+                            let path = resolve_input_path(
+                                input._type,
+                                &input.name,
+                                self.base_dir.as_deref(),
+                                &assets_dir,
+                            );
+                            match input._type {
+                                InputType::Texture => {
+                                    self.load_texture(&key, &path, input.vflip, build_mipmaps)
                                 }
-                                "Uffizi Gallery" => dir.join("uffizi_gallery.png"),
-                                "Uffizi Gallery Blurred" => dir.join("uffizi_gallery_blurred.png"),
-                                _ => PathBuf::from(input.name.clone()),
-                            };
-                            load_cubemap_texture(file, build_mipmaps)
+                                InputType::Cubemap => load_cubemap_texture(&path, build_mipmaps),
+                                _ => load_3d_texture(&path, build_mipmaps),
+                            }
+                        }
+                        InputType::Video => {
+                            let path = resolve_input_path(
+                                input._type,
+                                &input.name,
+                                self.base_dir.as_deref(),
+                                &assets_dir,
+                            );
+                            self.load_video(&key, &path, input.vflip, build_mipmaps)
                         }
-                        InputType::Volume => {
-                            let dir = assets_dir.join("volumes");
-                            let file = match input.name.as_str() {
-                                "Grey Noise3D" => dir.join("grey_noise_3d.png"),
-                                "RGBA Noise3D" => dir.join("rgba_noise_3d.png"),
-                                _ => PathBuf::from(input.name.clone()),
-                            };
-                            load_3d_texture(file, build_mipmaps)
+                        InputType::Webcam => self.load_webcam(&key, input, build_mipmaps),
+                        InputType::Stream => self.load_stream(&key, input, build_mipmaps),
+                        InputType::Text => self.load_text(&key, input, build_mipmaps),
+                        InputType::Screen => self.load_screen(&key, input, build_mipmaps),
+                        InputType::AlbumArt => {
+                            self.album_arts.push(AlbumArtInput {
+                                key: key.clone(),
+                                album_art: None,
+                                vflip: input.vflip,
+                                build_mipmaps,
+                            });
+                            create_video_texture()
                         }
-                        _ => load_2d_texture(PathBuf::default(), false, false),
+                        _ => create_fallback_2d_texture(),
                     };
 
                     self.map
                         .insert(key, Texture::new(external_input_id, input._type));
                 }
             }
-            let name = if pass.name() == "Cube A" {
-                "Cubemap A"
-            } else {
-                pass.name()
-            };
+            let name = output_name(pass.name());
 
             // Register framebuffer outputs as textures using a ping-pong scheme.
             //
@@ -227,14 +510,373 @@ impl TextureManager {
             // reading from a texture that is simultaneously being written.
             // This enables feedback effects where a pass samples its own
             // result from the previous frame.
-            self.map.insert(
-                name.to_string() + "0",
-                Texture::new(pass.framebuffers()[0].texture(), InputType::Misc),
-            );
-            self.map.insert(
-                name.to_string() + "1",
-                Texture::new(pass.framebuffers()[1].texture(), InputType::Misc),
+            for (index, framebuffer) in pass.framebuffers().iter().enumerate() {
+                self.map.insert(
+                    name.to_string() + &index.to_string(),
+                    Texture::with_size(
+                        framebuffer.texture(),
+                        InputType::Misc,
+                        framebuffer.size(),
+                        1,
+                    ),
+                );
+            }
+            self.pass_orders.insert(name.to_string(), pass.order());
+        }
+    }
+
+    /// Loads a texture input.
+    ///
+    /// Animated images are played back by replacing the texture contents
+    /// (see [`TextureManager::update_animated_textures`]).
+    fn load_texture(&mut self, key: &str, path: &Path, vflip: bool, build_mipmaps: bool) -> GLuint {
+        match AnimatedImage::open(path, vflip) {
+            Ok(Some(animation)) => {
+                self.animations.push(AnimationInput {
+                    animation,
+                    key: key.to_string(),
+                    frame: None,
+                    build_mipmaps,
+                });
+                create_video_texture()
+            }
+            Ok(None) => load_2d_texture(path, vflip, build_mipmaps),
+            Err(err) => {
+                // Missing files are reported when loading the still image
+                if path.is_file() {
+                    log::warn!("Failed to decode animation {}: {err}", path.display());
+                }
+                load_2d_texture(path, vflip, build_mipmaps)
+            }
+        }
+    }
+
+    /// Starts decoding a video input.
+    ///
+    /// Returns a placeholder texture that is filled as frames arrive
+    /// (see [`TextureManager::update_video_textures`]).
+    #[cfg(feature = "video")]
+    fn load_video(&mut self, key: &str, path: &Path, vflip: bool, build_mipmaps: bool) -> GLuint {
+        match VideoDecoder::new(&path.to_string_lossy(), vflip) {
+            Ok(decoder) => self.videos.push(VideoStream {
+                decoder,
+                key: key.to_string(),
+                build_mipmaps,
+            }),
+            Err(err) => log::error!("Failed to open video '{}': {err}", path.display()),
+        }
+
+        create_video_texture()
+    }
+
+    #[cfg(not(feature = "video"))]
+    fn load_video(
+        &mut self,
+        _key: &str,
+        path: &Path,
+        _vflip: bool,
+        _build_mipmaps: bool,
+    ) -> GLuint {
+        log::warn!(
+            "Video input '{}' ignored (built without the 'video' feature)",
+            path.display()
+        );
+        create_video_texture()
+    }
+
+    /// Starts capturing a webcam input, or shares the capture if another
+    /// texture manager already started it.
+    ///
+    /// Captured frames are uploaded together with video frames (see
+    /// [`TextureManager::update_video_textures`]).
+    #[cfg(feature = "video")]
+    fn load_webcam(&mut self, key: &str, input: &Input, build_mipmaps: bool) -> GLuint {
+        let capture = SourceHandle::open(&format!("webcam:{key}"), || {
+            VideoDecoder::webcam(&input.name, input.vflip)
+        });
+        match capture {
+            Ok(capture) => self.webcams.push(WebcamInput {
+                capture,
+                key: key.to_string(),
+                build_mipmaps,
+            }),
+            Err(err) => log::error!("Failed to open webcam '{}': {err}", input.name),
+        }
+
+        create_video_texture()
+    }
+
+    #[cfg(not(feature = "video"))]
+    fn load_webcam(&mut self, _key: &str, input: &Input, _build_mipmaps: bool) -> GLuint {
+        log::warn!(
+            "Webcam input '{}' ignored (built without the 'video' feature)",
+            input.name
+        );
+        create_video_texture()
+    }
+
+    /// Opens a stream input, or shares the stream if another texture
+    /// manager already opened it.
+    ///
+    /// Relative paths are resolved against the preset directory, except
+    /// for `-`, which reads from standard input.
+    fn load_stream(&mut self, key: &str, input: &Input, build_mipmaps: bool) -> GLuint {
+        let path = match &self.base_dir {
+            Some(base_dir) if input.name != "-" => base_dir.join(&input.name),
+            _ => PathBuf::from(&input.name),
+        };
+
+        let source_key = format!("stream:{}", path.display());
+        match SourceHandle::open(&source_key, || TextureStream::open(&path)) {
+            Ok(stream) => self.streams.push(StreamInput {
+                stream,
+                key: key.to_string(),
+                build_mipmaps,
+            }),
+            Err(err) => log::error!("Failed to open stream '{}': {err}", input.name),
+        }
+
+        create_video_texture()
+    }
+
+    /// Starts capturing the output of a screen input, or shares the
+    /// capture if another texture manager already started it.
+    ///
+    /// The texture stays black if screen capture is not allowed by the
+    /// configuration file or fails to start.
+    fn load_screen(&mut self, key: &str, input: &Input, build_mipmaps: bool) -> GLuint {
+        let source_key = format!("screen:{}:{}", input.name, input.vflip);
+        match SourceHandle::open(&source_key, || {
+            ScreenCapture::start(&input.name, input.vflip)
+        }) {
+            Ok(capture) => self.screens.push(ScreenInput {
+                capture,
+                key: key.to_string(),
+                build_mipmaps,
+            }),
+            Err(err) => log::warn!("Screen input '{}' ignored: {err}", input.name),
+        }
+
+        create_video_texture()
+    }
+
+    /// Registers a text input.
+    ///
+    /// Returns a placeholder texture that receives the text on the next
+    /// update (see [`TextureManager::update_text_textures`]).
+    fn load_text(&mut self, key: &str, input: &Input, build_mipmaps: bool) -> GLuint {
+        self.texts.push(TextInput {
+            markup: input.name.clone(),
+            key: key.to_string(),
+            text: None,
+            vflip: input.vflip,
+            build_mipmaps,
+        });
+
+        create_video_texture()
+    }
+
+    /// Rasterizes and uploads each text input whose expanded text changed
+    /// since the last upload.
+    pub fn update_text_textures(&mut self) {
+        if self.texts.is_empty() {
+            return;
+        }
+
+        let now = Local::now().naive_local();
+        for input in &mut self.texts {
+            let text = expand_time_patterns(&input.markup, &now);
+            if input.text.as_ref() == Some(&text) {
+                continue;
+            }
+            let Some(texture) = self.map.get(&input.key) else {
+                continue;
+            };
+
+            match rasterize_markup(&text, input.vflip) {
+                Ok(rasterized) => upload_rgba_frame(
+                    texture,
+                    rasterized.size,
+                    &rasterized.data,
+                    input.build_mipmaps,
+                ),
+                Err(err) => log::error!("Failed to render text input '{text}': {err}"),
+            }
+            input.text = Some(text);
+        }
+    }
+
+    /// Uploads the album art to each album art input, if it changed since
+    /// the last upload. Inputs turn black when there is no album art.
+    pub fn update_album_art_textures(&mut self, album_art: Option<&Rc<RgbaImage>>) {
+        for input in &mut self.album_arts {
+            let unchanged = match (input.album_art.as_ref(), album_art) {
+                (Some(uploaded), Some(album_art)) => Rc::ptr_eq(uploaded, album_art),
+                (None, None) => true,
+                _ => false,
+            };
+            if unchanged {
+                continue;
+            }
+            let Some(texture) = self.map.get(&input.key) else {
+                continue;
+            };
+
+            match album_art {
+                Some(album_art) => {
+                    let flipped;
+                    let image = if input.vflip {
+                        flipped = imageops::flip_vertical(album_art.as_ref());
+                        &flipped
+                    } else {
+                        album_art.as_ref()
+                    };
+                    upload_rgba_frame(
+                        texture,
+                        Size::new(image.width(), image.height()),
+                        image,
+                        input.build_mipmaps,
+                    );
+                }
+                None => upload_rgba_frame(
+                    texture,
+                    Size::new(1, 1),
+                    &[0, 0, 0, 255],
+                    input.build_mipmaps,
+                ),
+            }
+            input.album_art = album_art.cloned();
+        }
+    }
+
+    /// Uploads the newest frame received by each stream input.
+    ///
+    /// Textures keep their previous contents when no new frame is ready.
+    pub fn update_stream_textures(&mut self) {
+        for input in &mut self.streams {
+            let Some(frame) = input.stream.poll_frame() else {
+                continue;
+            };
+            let Some(texture) = self.map.get(&input.key) else {
+                continue;
+            };
+
+            upload_rgba_frame(texture, frame.size, &frame.data, input.build_mipmaps);
+        }
+    }
+
+    /// Uploads the newest capture of each screen input.
+    ///
+    /// Textures keep their previous contents when no new capture is ready.
+    pub fn update_screen_textures(&mut self) {
+        for input in &mut self.screens {
+            let Some(frame) = input.capture.poll_frame() else {
+                continue;
+            };
+            let Some(texture) = self.map.get(&input.key) else {
+                continue;
+            };
+
+            upload_rgba_frame(texture, frame.size, &frame.data, input.build_mipmaps);
+        }
+    }
+
+    /// Uploads the current frame of each animated texture input, if it
+    /// changed since the last upload.
+    pub fn update_animated_textures(&mut self) {
+        for input in &mut self.animations {
+            let (index, frame) = input.animation.current_frame();
+            if input.frame == Some(index) {
+                continue;
+            }
+            let Some(texture) = self.map.get(&input.key) else {
+                continue;
+            };
+
+            upload_rgba_frame(
+                texture,
+                Size::new(frame.width(), frame.height()),
+                frame,
+                input.build_mipmaps,
             );
+            input.frame = Some(index);
+        }
+    }
+
+    /// Uploads the newest decoded frame of each video input.
+    ///
+    /// Textures keep their previous contents when no new frame is ready.
+    pub fn update_video_textures(&mut self) {
+        #[cfg(feature = "video")]
+        for stream in &mut self.videos {
+            let Some(frame) = stream.decoder.poll_frame() else {
+                continue;
+            };
+            let Some(texture) = self.map.get(&stream.key) else {
+                continue;
+            };
+
+            upload_rgba_frame(texture, frame.size, frame.data(), stream.build_mipmaps);
+        }
+
+        #[cfg(feature = "video")]
+        for input in &mut self.webcams {
+            let Some(frame) = input.capture.poll_frame() else {
+                continue;
+            };
+            let Some(texture) = self.map.get(&input.key) else {
+                continue;
+            };
+
+            upload_rgba_frame(texture, frame.size, frame.data(), input.build_mipmaps);
+        }
+    }
+
+    /// Returns the playback position of the media input
+    /// registered under `key`, if any.
+    pub fn channel_time(&self, key: &str) -> Option<Duration> {
+        #[cfg(feature = "video")]
+        if let Some(stream) = self.videos.iter().find(|stream| stream.key == key) {
+            return Some(stream.decoder.position());
+        }
+
+        #[cfg(feature = "video")]
+        if let Some(input) = self.webcams.iter().find(|input| input.key == key) {
+            return Some(input.capture.source().position());
+        }
+
+        self.animations
+            .iter()
+            .find(|input| input.key == key)
+            .map(|input| input.animation.position())
+    }
+
+    /// Pauses or resumes playback of all media inputs.
+    pub fn set_media_paused(&mut self, paused: bool) {
+        #[cfg(feature = "video")]
+        for stream in &self.videos {
+            stream.decoder.set_paused(paused);
+        }
+
+        #[cfg(feature = "video")]
+        for input in &self.webcams {
+            input.capture.source().set_paused(paused);
+        }
+
+        for input in &mut self.animations {
+            input.animation.set_paused(paused);
+        }
+    }
+
+    /// Moves all media inputs to the given playback position.
+    pub fn seek_media(&mut self, position: Duration) {
+        #[cfg(feature = "video")]
+        for stream in &self.videos {
+            stream.decoder.seek(position);
+        }
+
+        for input in &mut self.animations {
+            input.animation.seek(position);
         }
     }
 
@@ -304,170 +946,415 @@ fn assets_dir() -> PathBuf {
         })
 }
 
-/// Loads a cubemap texture from a horizontally stacked image.
+/// Returns the file name of a bundled asset, if `name` refers to one.
+fn builtin_asset(input_type: InputType, name: &str) -> Option<&'static str> {
+    let file = match (input_type, name) {
+        (InputType::Texture, "Abstract 1") => "textures/abstract_1.jpg",
+        (InputType::Texture, "Abstract 2") => "textures/abstract_2.jpg",
+        (InputType::Texture, "Abstract 3") => "textures/abstract_3.jpg",
+        (InputType::Texture, "Bayer") => "textures/bayer.png",
+        (InputType::Texture, "Blue Noise") => "textures/blue_noise.png",
+        (InputType::Texture, "Font 1") => "textures/font_1.png",
+        (InputType::Texture, "Gray Noise Medium") => "textures/gray_noise_medium.png",
+        (InputType::Texture, "Gray Noise Small") => "textures/gray_noise_small.png",
+        (InputType::Texture, "Lichen") => "textures/lichen.jpg",
+        (InputType::Texture, "London") => "textures/london.jpg",
+        (InputType::Texture, "Nyancat") => "textures/nyancat.png",
+        (InputType::Texture, "Organic 1") => "textures/organic_1.jpg",
+        (InputType::Texture, "Organic 2") => "textures/organic_2.jpg",
+        (InputType::Texture, "Organic 3") => "textures/organic_3.jpg",
+        (InputType::Texture, "Organic 4") => "textures/organic_4.jpg",
+        (InputType::Texture, "Pebbles") => "textures/pebbles.png",
+        (InputType::Texture, "RGBA Noise Medium") => "textures/rgba_noise_medium.png",
+        (InputType::Texture, "RGBA Noise Small") => "textures/rgba_noise_small.png",
+        (InputType::Texture, "Rock Tiles") => "textures/rock_tiles.jpg",
+        (InputType::Texture, "Rusty Metal") => "textures/rusty_metal.jpg",
+        (InputType::Texture, "Stars") => "textures/stars.jpg",
+        (InputType::Texture, "Wood") => "textures/wood.jpg",
+        (InputType::Cubemap, "Forest") => "cubemaps/forest.png",
+        (InputType::Cubemap, "Forest Blurred") => "cubemaps/forest_blurred.png",
+        (InputType::Cubemap, "St. Peter's Basilica") => "cubemaps/st_peters_basilica.png",
+        (InputType::Cubemap, "St. Peter's Basilica Blurred") => {
+            "cubemaps/st_peters_basilica_blurred.png"
+        }
+        (InputType::Cubemap, "Uffizi Gallery") => "cubemaps/uffizi_gallery.png",
+        (InputType::Cubemap, "Uffizi Gallery Blurred") => "cubemaps/uffizi_gallery_blurred.png",
+        (InputType::Volume, "Grey Noise3D") => "volumes/grey_noise_3d.png",
+        (InputType::Volume, "RGBA Noise3D") => "volumes/rgba_noise_3d.png",
+        _ => return None,
+    };
+    Some(file)
+}
+
+/// Resolves the file backing a texture, cubemap, volume or video input.
 ///
-/// Expected layout:
-/// +X | -X | +Y | -Y | +Z | -Z
-fn load_cubemap_texture(path: PathBuf, build_mipmaps: bool) -> GLuint {
-    const CUBEMAP_NUM_FACES: usize = 6;
+/// Names of bundled ShaderToy assets map into `assets_dir`, and URIs such
+/// as `https://...` are used as is. Any other name is a file path:
+/// absolute paths are used as is and relative paths are resolved against
+/// `base_dir` (the preset directory), or against the current directory
+/// when the preset was not loaded from a file.
+pub fn resolve_input_path(
+    input_type: InputType,
+    name: &str,
+    base_dir: Option<&Path>,
+    assets_dir: &Path,
+) -> PathBuf {
+    if let Some(file) = builtin_asset(input_type, name) {
+        return assets_dir.join(file);
+    }
 
-    let mut texture_id = 0;
+    let path = PathBuf::from(name);
+    if name.contains("://") {
+        return path;
+    }
 
-    unsafe {
-        gl::GenTextures(1, &mut texture_id);
-        gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture_id);
+    match base_dir {
+        Some(base_dir) if path.is_relative() => base_dir.join(path),
+        _ => path,
     }
+}
 
-    let define_texture = |target: GLenum, size: Size, data: *const u8| unsafe {
-        gl::TexSubImage2D(
-            target,
-            0,
-            0,
-            0,
-            size.width() as i32,
-            size.height() as i32,
-            gl::RGB,
-            gl::UNSIGNED_BYTE,
-            data as *const _,
-        )
-    };
+/// Opens an image file, distinguishing missing files from decoding errors.
+fn open_image(path: &Path) -> Result<DynamicImage, TextureError> {
+    if !path.is_file() {
+        return Err(TextureError::NotFound(path.to_path_buf()));
+    }
+    image::open(path).map_err(|source| TextureError::Decode {
+        path: path.to_path_buf(),
+        source,
+    })
+}
 
-    // Fallback ensures shader execution continues even if
-    // asset loading fails
-    let fallback = || {
-        unsafe { gl::TexStorage2D(gl::TEXTURE_CUBE_MAP, 1, gl::RGB8, 1, 1) };
+/// Splits a single cubemap image into its six faces, in OpenGL face order.
+///
+/// Images with a 2:1 aspect ratio are taken as equirectangular panoramas
+/// and projected onto the faces (see [`project_equirectangular`]).
+///
+/// Returns `None` if the image dimensions match no [`CubemapLayout`].
+pub fn split_cubemap_faces<P: Pixel + 'static>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+) -> Option<CubemapFaces<P>> {
+    if img.height() > 1 && img.width() == 2 * img.height() {
+        return Some(project_equirectangular(img, img.height() / 2));
+    }
 
-        let fallback_data: [u8; 3] = [0, 0, 0];
-        for i in 0..CUBEMAP_NUM_FACES {
-            define_texture(
-                gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
-                Size::new(1, 1),
-                fallback_data.as_ptr(),
-            );
-        }
-    };
+    let (layout, face_size) = CubemapLayout::detect(img.width(), img.height())?;
 
-    if let Ok(img) = image::open(path.as_path()) {
-        let img = img.to_rgb8();
-        let (width, height) = img.dimensions();
-        let face_size = Size::new(width / 6, height);
+    let mut faces = layout.cells().map(|(column, row)| {
+        img.view(column * face_size, row * face_size, face_size, face_size)
+            .to_image()
+    });
 
-        if face_size.width() > 0 {
-            let num_mipmap_levels = if build_mipmaps {
-                (face_size.width().max(face_size.height()) as f32)
-                    .log2()
-                    .floor() as i32
-                    + 1
-            } else {
-                1
-            };
-            unsafe {
-                gl::TexStorage2D(
-                    gl::TEXTURE_CUBE_MAP,
-                    num_mipmap_levels,
-                    gl::RGB8,
-                    face_size.width() as i32,
-                    face_size.height() as i32,
-                )
+    // In a vertical cross, -Z lies below -Y and is seen upside down
+    if layout == CubemapLayout::VerticalCross {
+        faces[5] = imageops::rotate180(&faces[5]);
+    }
+
+    Some(faces)
+}
+
+/// Projects an equirectangular panorama onto six faces of `face_size`
+/// pixels, in OpenGL face order.
+///
+/// The center of the panorama faces +Z, with +X to its right and +Y at the
+/// top, matching the center of the cross layouts.
+pub fn project_equirectangular<P: Pixel>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    face_size: u32,
+) -> CubemapFaces<P> {
+    std::array::from_fn(|face| {
+        ImageBuffer::from_fn(face_size, face_size, |x, y| {
+            // Face coordinates in [-1, 1], with t pointing down the image
+            let s = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+            let t = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+            let (dx, dy, dz) = match face {
+                0 => (1.0, -t, -s),
+                1 => (-1.0, -t, s),
+                2 => (s, 1.0, t),
+                3 => (s, -1.0, -t),
+                4 => (s, -t, 1.0),
+                _ => (-s, -t, -1.0),
             };
 
-            for i in 0..CUBEMAP_NUM_FACES {
-                let x_offset = i as u32 * face_size.width();
-                let face = img.view(x_offset, 0, face_size.width(), face_size.height());
+            let u = 0.5 + dx.atan2(dz) / std::f32::consts::TAU;
+            let v = (dy / (dx * dx + dy * dy + dz * dz).sqrt()).acos() / std::f32::consts::PI;
+            imageops::sample_bilinear(img, u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+                .expect("Panorama must not be empty")
+        })
+    })
+}
 
-                define_texture(
-                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
-                    face_size,
-                    face.to_image().as_ptr(),
-                );
-            }
-        } else {
-            fallback();
-        }
-    } else {
-        fallback();
+/// Reads the six faces of a cubemap input, converting each image with
+/// `convert`.
+///
+/// Paths containing [`CUBEMAP_FACE_PLACEHOLDER`] load one file per face;
+/// other paths load a single image in any [`CubemapLayout`].
+fn read_cubemap_faces<P: Pixel + 'static>(
+    path: &Path,
+    convert: fn(&DynamicImage) -> ImageBuffer<P, Vec<P::Subpixel>>,
+) -> Result<CubemapFaces<P>, TextureError> {
+    let pattern = path.to_string_lossy();
+
+    if !pattern.contains(CUBEMAP_FACE_PLACEHOLDER) {
+        let img = convert(&open_image(path)?);
+        return split_cubemap_faces(&img).ok_or_else(|| TextureError::CubemapLayout {
+            path: path.to_path_buf(),
+            width: img.width(),
+            height: img.height(),
+        });
     }
 
-    if build_mipmaps {
-        unsafe { gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP) };
+    let mut faces = Vec::with_capacity(CUBEMAP_NUM_FACES);
+    for suffix in CUBEMAP_FACE_SUFFIXES {
+        let face_path = PathBuf::from(pattern.replace(CUBEMAP_FACE_PLACEHOLDER, suffix));
+        faces.push(convert(&open_image(&face_path)?));
     }
 
-    texture_id
+    let face_dimensions = faces[0].dimensions();
+    if face_dimensions.0 != face_dimensions.1
+        || faces
+            .iter()
+            .any(|face| face.dimensions() != face_dimensions)
+    {
+        return Err(TextureError::CubemapFaces(path.to_path_buf()));
+    }
+
+    Ok(faces
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("Cubemap must have exactly six faces")))
 }
 
-/// Loads a 2D texture with optional vertical flip and mipmaps.
-///
-/// Automatically selects internal format based on image channels.
-fn load_2d_texture(path: PathBuf, vflip: bool, build_mipmaps: bool) -> GLuint {
+/// Returns `true` if the path names a high dynamic range image (Radiance
+/// HDR or OpenEXR), which is uploaded as a half-float texture.
+pub fn is_hdr_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("hdr") || extension.eq_ignore_ascii_case("exr")
+        })
+}
+
+/// Loads a cubemap texture from a single image or from six face images
+/// (see [`read_cubemap_faces`]).
+fn load_cubemap_texture(path: &Path, build_mipmaps: bool) -> GLuint {
     let mut texture_id = 0;
 
     unsafe {
         gl::GenTextures(1, &mut texture_id);
-        gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture_id);
     }
 
-    let define_texture = |internal_format: GLenum, format: GLenum, size: Size, data: *const u8| {
-        let num_mipmap_levels = if build_mipmaps {
-            (size.width().max(size.height()) as f32).log2().floor() as i32 + 1
-        } else {
-            1
-        };
-        unsafe {
-            gl::TexStorage2D(
-                gl::TEXTURE_2D,
-                num_mipmap_levels,
-                internal_format,
-                size.width() as i32,
-                size.height() as i32,
-            );
+    let result = if is_hdr_image(path) {
+        read_cubemap_faces(path, DynamicImage::to_rgb32f).map(|faces| {
+            define_cubemap_faces(&faces, gl::RGB16F, gl::FLOAT, build_mipmaps);
+        })
+    } else {
+        read_cubemap_faces(path, DynamicImage::to_rgb8).map(|faces| {
+            define_cubemap_faces(&faces, gl::RGB8, gl::UNSIGNED_BYTE, build_mipmaps);
+        })
+    };
+
+    if let Err(err) = result {
+        log::error!("Failed to load cubemap input: {err}");
+
+        // Fallback ensures shader execution continues even if
+        // asset loading fails
+        let fallback_faces = std::array::from_fn(|_| RgbImage::new(1, 1));
+        define_cubemap_faces(&fallback_faces, gl::RGB8, gl::UNSIGNED_BYTE, false);
+    }
+
+    texture_id
+}
+
+/// Allocates the storage of the bound cubemap texture and uploads its
+/// faces, given in OpenGL face order.
+fn define_cubemap_faces<P: Pixel>(
+    faces: &CubemapFaces<P>,
+    internal_format: GLenum,
+    data_type: GLenum,
+    build_mipmaps: bool,
+) {
+    let face_size = Size::new(faces[0].width(), faces[0].height());
+    let num_mipmap_levels = if build_mipmaps {
+        (face_size.width().max(face_size.height()) as f32)
+            .log2()
+            .floor() as i32
+            + 1
+    } else {
+        1
+    };
+
+    unsafe {
+        gl::TexStorage2D(
+            gl::TEXTURE_CUBE_MAP,
+            num_mipmap_levels,
+            internal_format,
+            face_size.width() as i32,
+            face_size.height() as i32,
+        );
+
+        for (i, face) in faces.iter().enumerate() {
             gl::TexSubImage2D(
-                gl::TEXTURE_2D,
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
                 0,
                 0,
                 0,
-                size.width() as i32,
-                size.height() as i32,
-                format,
-                gl::UNSIGNED_BYTE,
-                data as *const _,
+                face_size.width() as i32,
+                face_size.height() as i32,
+                gl::RGB,
+                data_type,
+                face.as_ptr() as *const _,
             );
         }
-    };
 
-    match image::open(path.as_path()) {
+        if build_mipmaps {
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+        }
+    }
+}
+
+/// Loads a 2D texture with optional vertical flip and mipmaps.
+///
+/// Automatically selects internal format based on image channels.
+/// Floating-point images, such as HDR and EXR files, are uploaded as
+/// half-float textures.
+fn load_2d_texture(path: &Path, vflip: bool, build_mipmaps: bool) -> GLuint {
+    match open_image(path) {
         Ok(img) => {
             let img = if vflip { img.flipv() } else { img };
             let size = {
                 let (width, height) = img.dimensions();
                 Size::new(width, height)
             };
+            let is_float = matches!(img.color(), ColorType::Rgb32F | ColorType::Rgba32F);
 
-            if img.color() == ColorType::L8 {
-                define_texture(gl::R8, gl::RED, size, img.to_luma8().as_ptr());
+            if is_float && img.color().has_alpha() {
+                let data = img.to_rgba32f();
+                create_2d_texture(gl::RGBA16F, gl::RGBA, gl::FLOAT, size, &data, build_mipmaps)
+            } else if is_float {
+                let data = img.to_rgb32f();
+                create_2d_texture(gl::RGB16F, gl::RGB, gl::FLOAT, size, &data, build_mipmaps)
+            } else if img.color() == ColorType::L8 {
+                let data = img.to_luma8();
+                create_2d_texture(
+                    gl::R8,
+                    gl::RED,
+                    gl::UNSIGNED_BYTE,
+                    size,
+                    &data,
+                    build_mipmaps,
+                )
             } else if img.color().has_alpha() {
-                define_texture(gl::RGBA8, gl::RGBA, size, img.to_rgba8().as_ptr());
+                let data = img.to_rgba8();
+                create_2d_texture(
+                    gl::RGBA8,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    size,
+                    &data,
+                    build_mipmaps,
+                )
             } else {
-                define_texture(gl::RGB8, gl::RGB, size, img.to_rgb8().as_ptr());
+                let data = img.to_rgb8();
+                create_2d_texture(
+                    gl::RGB8,
+                    gl::RGB,
+                    gl::UNSIGNED_BYTE,
+                    size,
+                    &data,
+                    build_mipmaps,
+                )
             }
         }
-        Err(_) => {
-            let fallback_data: [u8; 3] = [0, 0, 0];
-            define_texture(gl::RGB8, gl::RGB, Size::new(1, 1), fallback_data.as_ptr());
+        Err(err) => {
+            log::error!("Failed to load texture input: {err}");
+            create_fallback_2d_texture()
         }
     }
+}
 
-    if build_mipmaps {
-        unsafe { gl::GenerateMipmap(gl::TEXTURE_2D) };
+/// Creates a 1x1 black texture used in place of inputs that failed to load.
+fn create_fallback_2d_texture() -> GLuint {
+    create_2d_texture(
+        gl::RGB8,
+        gl::RGB,
+        gl::UNSIGNED_BYTE,
+        Size::new(1, 1),
+        &[0u8, 0, 0],
+        false,
+    )
+}
+
+/// Creates an immutable 2D texture from tightly packed pixel data of
+/// `data_type` components.
+fn create_2d_texture<T>(
+    internal_format: GLenum,
+    format: GLenum,
+    data_type: GLenum,
+    size: Size,
+    data: &[T],
+    build_mipmaps: bool,
+) -> GLuint {
+    let mut texture_id = 0;
+    let num_mipmap_levels = if build_mipmaps {
+        (size.width().max(size.height()) as f32).log2().floor() as i32 + 1
+    } else {
+        1
+    };
+
+    unsafe {
+        gl::GenTextures(1, &mut texture_id);
+        gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        gl::TexStorage2D(
+            gl::TEXTURE_2D,
+            num_mipmap_levels,
+            internal_format,
+            size.width() as i32,
+            size.height() as i32,
+        );
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            size.width() as i32,
+            size.height() as i32,
+            format,
+            data_type,
+            data.as_ptr() as *const _,
+        );
+
+        if build_mipmaps {
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
     }
 
     texture_id
 }
 
-/// Loads a 3D texture encoded as a horizontal strip of square slices.
+/// Reads a volume encoded as a horizontal strip of square slices.
 ///
 /// Image layout:
 /// [slice0][slice1][slice2]...
-fn load_3d_texture(path: PathBuf, build_mipmaps: bool) -> GLuint {
+///
+/// Returns the flipped RGBA image and the slice size.
+fn read_volume(path: &Path) -> Result<(RgbaImage, u32), TextureError> {
+    let img = open_image(path)?.flipv().to_rgba8();
+    let (width, height) = img.dimensions();
+
+    if height > 0 && width % height == 0 {
+        Ok((img, height))
+    } else {
+        Err(TextureError::VolumeLayout {
+            path: path.to_path_buf(),
+            width,
+            height,
+        })
+    }
+}
+
+/// Loads a 3D texture encoded as a horizontal strip of square slices.
+fn load_3d_texture(path: &Path, build_mipmaps: bool) -> GLuint {
     let mut texture_id = 0;
 
     unsafe {
@@ -475,33 +1362,9 @@ fn load_3d_texture(path: PathBuf, build_mipmaps: bool) -> GLuint {
         gl::BindTexture(gl::TEXTURE_3D, texture_id);
     }
 
-    let fallback = || {
-        let fallback_data: [u8; 4] = [0, 0, 0, 0];
-        unsafe {
-            gl::TexStorage3D(gl::TEXTURE_3D, 1, gl::RGBA8, 1, 1, 1);
-            gl::TexSubImage3D(
-                gl::TEXTURE_3D,
-                0,
-                0,
-                0,
-                0,
-                1,
-                1,
-                1,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                fallback_data.as_ptr() as *const _,
-            );
-        }
-    };
-
-    if let Ok(img) = image::open(path.as_path()) {
-        let img = img.flipv().to_rgba8();
-        let (width, height) = img.dimensions();
-
-        if height > 0 && width % height == 0 {
-            let slice_size = height;
-            let depth = width / slice_size;
+    match read_volume(path) {
+        Ok((img, slice_size)) => {
+            let depth = img.width() / slice_size;
             let num_mipmap_levels = if build_mipmaps {
                 (slice_size as f32).log2().floor() as i32 + 1
             } else {
@@ -539,11 +1402,28 @@ fn load_3d_texture(path: PathBuf, build_mipmaps: bool) -> GLuint {
                     )
                 };
             }
-        } else {
-            fallback();
         }
-    } else {
-        fallback();
+        Err(err) => {
+            log::error!("Failed to load volume input: {err}");
+
+            let fallback_data: [u8; 4] = [0, 0, 0, 0];
+            unsafe {
+                gl::TexStorage3D(gl::TEXTURE_3D, 1, gl::RGBA8, 1, 1, 1);
+                gl::TexSubImage3D(
+                    gl::TEXTURE_3D,
+                    0,
+                    0,
+                    0,
+                    0,
+                    1,
+                    1,
+                    1,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    fallback_data.as_ptr() as *const _,
+                );
+            }
+        }
     }
 
     if build_mipmaps {
@@ -553,6 +1433,94 @@ fn load_3d_texture(path: PathBuf, build_mipmaps: bool) -> GLuint {
     texture_id
 }
 
+/// Uploads an RGBA8 frame into a 2D texture, reallocating its storage
+/// when the frame size differs from the texture size.
+fn upload_rgba_frame(texture: &Texture, frame_size: Size, data: &[u8], build_mipmaps: bool) {
+    let (width, height) = (frame_size.width() as i32, frame_size.height() as i32);
+
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, texture.id);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+        if frame_size != texture.size.get() {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                width,
+                height,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as *const _,
+            );
+            texture.size.set(frame_size);
+        } else {
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width,
+                height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as *const _,
+            );
+        }
+
+        if build_mipmaps {
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+    }
+}
+
+/// Queries the size and depth of level 0 of a texture created from an
+/// input of type `input_type`.
+fn query_dimensions(texture_id: GLuint, input_type: InputType) -> (Size, u32) {
+    let (target, level_target) = match input_type {
+        InputType::Cubemap => (gl::TEXTURE_CUBE_MAP, gl::TEXTURE_CUBE_MAP_POSITIVE_X),
+        InputType::Volume => (gl::TEXTURE_3D, gl::TEXTURE_3D),
+        _ => (gl::TEXTURE_2D, gl::TEXTURE_2D),
+    };
+
+    let (mut width, mut height, mut depth) = (0, 0, 1);
+    unsafe {
+        gl::BindTexture(target, texture_id);
+        gl::GetTexLevelParameteriv(level_target, 0, gl::TEXTURE_WIDTH, &mut width);
+        gl::GetTexLevelParameteriv(level_target, 0, gl::TEXTURE_HEIGHT, &mut height);
+        if target == gl::TEXTURE_3D {
+            gl::GetTexLevelParameteriv(level_target, 0, gl::TEXTURE_DEPTH, &mut depth);
+        }
+    }
+    (Size::new(width as u32, height as u32), depth as u32)
+}
+
+/// Creates a 1x1 black texture with mutable storage,
+/// resized once the first video frame is decoded.
+fn create_video_texture() -> GLuint {
+    let mut texture_id = 0;
+    let black: [u8; 4] = [0, 0, 0, 255];
+
+    unsafe {
+        gl::GenTextures(1, &mut texture_id);
+        gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA8 as i32,
+            1,
+            1,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            black.as_ptr() as *const _,
+        );
+    }
+
+    texture_id
+}
+
 /// Creates the ShaderToy keyboard input texture.
 ///
 /// Uses single-channel R8 format and nearest sampling.
@@ -0,0 +1,262 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Raw RGBA texture streaming for `InputType::Stream` channels.
+//!
+//! External programs push frames through a named pipe, standard input,
+//! or a regular file (typically in `/dev/shm`). Each frame is encoded
+//! as a 12-byte header followed by the pixels:
+//!
+//! | Offset | Size | Content                                  |
+//! |--------|------|------------------------------------------|
+//! | 0      | 4    | Magic bytes `SBGF`                       |
+//! | 4      | 4    | Width in pixels (little-endian `u32`)    |
+//! | 8      | 4    | Height in pixels (little-endian `u32`)   |
+//! | 12     | w×h×4| Tightly packed RGBA8 pixels, top row first |
+//!
+//! Pipes are read continuously on a background thread, and only the
+//! newest complete frame is kept. Regular files are re-read whenever
+//! their modification time or length changes, so writers should
+//! replace them atomically (e.g., write to a temporary file and
+//! rename it).
+
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::SystemTime,
+};
+use thiserror::Error;
+
+use crate::geometry::Size;
+
+use super::shared_source::FrameSource;
+
+/// Magic bytes starting every frame.
+pub const FRAME_MAGIC: &[u8; 4] = b"SBGF";
+
+/// Size of the frame header in bytes.
+pub const FRAME_HEADER_SIZE: usize = 12;
+
+/// Largest accepted frame dimension, matching common GL texture limits.
+const MAX_FRAME_DIMENSION: u32 = 16384;
+
+/// Input name selecting standard input.
+const STDIN_NAME: &str = "-";
+
+#[derive(Debug, Error)]
+pub enum StreamError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid frame header")]
+    InvalidMagic,
+    #[error("Invalid frame size {0}x{1}")]
+    InvalidSize(u32, u32),
+    #[error("Truncated frame: expected {expected} bytes, got {actual}")]
+    Truncated { expected: usize, actual: usize },
+}
+
+/// Frame received from a stream.
+pub struct StreamFrame {
+    /// Frame dimensions in pixels.
+    pub size: Size,
+
+    /// Tightly packed RGBA8 pixels, top row first.
+    pub data: Vec<u8>,
+}
+
+/// Source of streamed frames.
+pub struct TextureStream {
+    /// Pipe, standard input or file name, kept for diagnostics.
+    name: String,
+
+    source: StreamSource,
+}
+
+enum StreamSource {
+    /// Frames read by a background thread.
+    Pipe {
+        latest: Arc<Mutex<Option<StreamFrame>>>,
+        stop: Arc<AtomicBool>,
+    },
+    /// Regular file polled for changes.
+    File {
+        path: PathBuf,
+        stamp: Option<(SystemTime, u64)>,
+    },
+}
+
+impl TextureStream {
+    /// Opens a stream from a named pipe, a regular file, or standard
+    /// input when `path` is `-`.
+    ///
+    /// Opening never blocks: pipes are opened by the reader thread,
+    /// which waits for a writer to connect.
+    pub fn open(path: &Path) -> Result<Self, StreamError> {
+        let name = path.display().to_string();
+
+        if name == STDIN_NAME {
+            return Ok(Self::spawn_reader(name, None));
+        }
+
+        let file_type = fs::metadata(path)?.file_type();
+        if file_type.is_fifo() {
+            Ok(Self::spawn_reader(name, Some(path.to_path_buf())))
+        } else {
+            Ok(Self {
+                name,
+                source: StreamSource::File {
+                    path: path.to_path_buf(),
+                    stamp: None,
+                },
+            })
+        }
+    }
+
+    /// Returns the newest frame received since the last call, if any.
+    pub fn poll_frame(&mut self) -> Option<StreamFrame> {
+        match &mut self.source {
+            StreamSource::Pipe { latest, .. } => latest.lock().ok()?.take(),
+            StreamSource::File { path, stamp } => {
+                let metadata = fs::metadata(&*path).ok()?;
+                let current = (metadata.modified().ok()?, metadata.len());
+                if *stamp == Some(current) {
+                    return None;
+                }
+                *stamp = Some(current);
+
+                match fs::read(&*path)
+                    .map_err(StreamError::from)
+                    .and_then(|bytes| read_frame(&mut bytes.as_slice()))
+                {
+                    Ok(frame) => Some(frame),
+                    Err(err) => {
+                        log::warn!("Stream '{}': {err}", self.name);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts a thread reading frames from a pipe, or from standard
+    /// input when `path` is `None`.
+    ///
+    /// Named pipes are reopened when the writer disconnects, so
+    /// producers can be restarted freely.
+    fn spawn_reader(name: String, path: Option<PathBuf>) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_name = name.clone();
+        let thread_latest = Arc::clone(&latest);
+        let thread_stop = Arc::clone(&stop);
+        let spawn_result = thread::Builder::new()
+            .name("texture-stream".to_string())
+            .spawn(move || loop {
+                let reader: Box<dyn Read> = match &path {
+                    Some(path) => match File::open(path) {
+                        Ok(file) => Box::new(io::BufReader::new(file)),
+                        Err(err) => {
+                            log::error!("Stream '{thread_name}': {err}");
+                            return;
+                        }
+                    },
+                    None => Box::new(io::stdin().lock()),
+                };
+
+                if let Err(err) = read_frames(reader, &thread_latest, &thread_stop) {
+                    log::warn!("Stream '{thread_name}': {err}");
+                }
+
+                if path.is_none() || thread_stop.load(Ordering::Relaxed) {
+                    return;
+                }
+            });
+
+        if let Err(err) = spawn_result {
+            log::error!("Stream '{name}': failed to start reader: {err}");
+        }
+
+        Self {
+            name,
+            source: StreamSource::Pipe { latest, stop },
+        }
+    }
+}
+
+impl FrameSource for TextureStream {
+    type Frame = StreamFrame;
+
+    fn poll_frame(&mut self) -> Option<StreamFrame> {
+        TextureStream::poll_frame(self)
+    }
+}
+
+impl Drop for TextureStream {
+    fn drop(&mut self) {
+        // A reader blocked on an idle pipe exits the next time it wakes up.
+        if let StreamSource::Pipe { stop, .. } = &self.source {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Reads frames until end of stream, keeping only the newest one.
+fn read_frames(
+    mut reader: impl Read,
+    latest: &Mutex<Option<StreamFrame>>,
+    stop: &AtomicBool,
+) -> Result<(), StreamError> {
+    while !stop.load(Ordering::Relaxed) {
+        let frame = match read_frame(&mut reader) {
+            Ok(frame) => frame,
+            Err(StreamError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        if let Ok(mut latest) = latest.lock() {
+            *latest = Some(frame);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a single frame (header and pixels).
+pub fn read_frame(reader: &mut impl Read) -> Result<StreamFrame, StreamError> {
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+
+    if &header[0..4] != FRAME_MAGIC {
+        return Err(StreamError::InvalidMagic);
+    }
+    let width = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    let height = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    if width == 0 || height == 0 || width > MAX_FRAME_DIMENSION || height > MAX_FRAME_DIMENSION {
+        return Err(StreamError::InvalidSize(width, height));
+    }
+
+    let expected = width as usize * height as usize * 4;
+    let mut data = Vec::with_capacity(expected);
+    reader.take(expected as u64).read_to_end(&mut data)?;
+    if data.len() != expected {
+        return Err(StreamError::Truncated {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    Ok(StreamFrame {
+        size: Size::new(width, height),
+        data,
+    })
+}
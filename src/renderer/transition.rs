@@ -0,0 +1,287 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Effects revealing a preset over the previous one when switching
+//! presets.
+//!
+//! Both renderers present their frames into offscreen framebuffers, which
+//! a transition program composes onto the drawing surface. Programs use
+//! the format of [glsl-transitions](https://gl-transitions.com): they
+//! define `vec4 transition(vec2 uv)` in terms of `getFromColor`,
+//! `getToColor`, `progress` and `ratio`, and declare their parameters as
+//! uniforms with a default value in a trailing comment.
+
+use gl::types::*;
+
+use super::{
+    framebuffer::*, gl_profile, program::Program, shader::*, vertex_array::VertexArray, Renderer,
+};
+use crate::{geometry::Size, preset::Transition};
+
+/// Fullscreen triangle strip generated from the vertex index.
+const TRANSITION_VERTEX_SHADER: &str = r#"
+out vec2 fragTexCoord;
+
+void main() {
+    vec2 position = vec2(gl_VertexID & 1, gl_VertexID >> 1);
+    fragTexCoord = position;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+/// Declarations preceding the transition source.
+const TRANSITION_FRAGMENT_HEADER: &str = r#"
+in vec2 fragTexCoord;
+out vec4 fragColor;
+
+uniform sampler2D fromTexture;
+uniform sampler2D toTexture;
+uniform float progress;
+uniform float ratio;
+
+vec4 getFromColor(vec2 uv) { return texture(fromTexture, uv); }
+vec4 getToColor(vec2 uv) { return texture(toTexture, uv); }
+"#;
+
+const TRANSITION_FRAGMENT_FOOTER: &str = r#"
+void main() {
+    fragColor = vec4(transition(fragTexCoord).rgb, 1.0);
+}
+"#;
+
+const CROSSFADE: &str = r#"
+vec4 transition(vec2 uv) {
+    return mix(getFromColor(uv), getToColor(uv), progress);
+}
+"#;
+
+const WIPE_LEFT: &str = r#"
+vec4 transition(vec2 uv) {
+    return mix(getFromColor(uv), getToColor(uv), step(1.0 - uv.x, progress));
+}
+"#;
+
+const WIPE_RIGHT: &str = r#"
+vec4 transition(vec2 uv) {
+    return mix(getFromColor(uv), getToColor(uv), step(uv.x, progress));
+}
+"#;
+
+const WIPE_UP: &str = r#"
+vec4 transition(vec2 uv) {
+    return mix(getFromColor(uv), getToColor(uv), step(uv.y, progress));
+}
+"#;
+
+const WIPE_DOWN: &str = r#"
+vec4 transition(vec2 uv) {
+    return mix(getFromColor(uv), getToColor(uv), step(1.0 - uv.y, progress));
+}
+"#;
+
+const DISSOLVE: &str = r#"
+uniform float cellCount; // = 200.0
+uniform float smoothness; // = 0.1
+
+float random(vec2 co) {
+    return fract(sin(dot(co, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+vec4 transition(vec2 uv) {
+    float r = random(floor(uv * vec2(ratio, 1.0) * cellCount));
+    float m = 1.0 - smoothstep(-smoothness, 0.0, r - progress * (1.0 + smoothness));
+    return mix(getFromColor(uv), getToColor(uv), m);
+}
+"#;
+
+const CIRCULAR_REVEAL: &str = r#"
+uniform vec2 center; // = vec2(0.5, 0.5)
+uniform float smoothness; // = 0.3
+
+vec4 transition(vec2 uv) {
+    vec2 aspect = vec2(ratio, 1.0);
+    // Distance to the center, 1 at the farthest corner
+    vec2 corner = max(center, 1.0 - center) * aspect;
+    float d = length((uv - center) * aspect) / length(corner);
+    float m = 1.0 - smoothstep(-smoothness, 0.0, d - progress * (1.0 + smoothness));
+    return mix(getFromColor(uv), getToColor(uv), m);
+}
+"#;
+
+/// Returns the glsl-transitions source of a built-in transition, or
+/// `None` for a custom transition.
+pub fn builtin_transition_source(transition: &Transition) -> Option<&'static str> {
+    Some(match transition {
+        Transition::Crossfade => CROSSFADE,
+        Transition::WipeLeft => WIPE_LEFT,
+        Transition::WipeRight => WIPE_RIGHT,
+        Transition::WipeUp => WIPE_UP,
+        Transition::WipeDown => WIPE_DOWN,
+        Transition::Dissolve => DISSOLVE,
+        Transition::CircularReveal => CIRCULAR_REVEAL,
+        Transition::Custom(_) => return None,
+    })
+}
+
+/// Replaces the parameters of a glsl-transitions source, declared as
+/// `uniform float name; // = value`, by constants with their default
+/// value.
+///
+/// Uniforms without a default value are kept, and read as zero.
+pub fn resolve_transition_parameters(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| {
+            let Some((declaration, comment)) = line.split_once("//") else {
+                return line.to_string();
+            };
+            let declaration = declaration.trim();
+            let Some(value) = comment.trim().strip_prefix('=') else {
+                return line.to_string();
+            };
+            let Some(declaration) = declaration
+                .strip_prefix("uniform ")
+                .and_then(|declaration| declaration.strip_suffix(';'))
+            else {
+                return line.to_string();
+            };
+            let value = value.trim().trim_end_matches(';').trim();
+            format!("const {} = {value};", declaration.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// OpenGL resources of a compiled transition.
+struct TransitionResources {
+    program: Program,
+    vao: VertexArray,
+    progress_location: GLint,
+    ratio_location: GLint,
+
+    /// Frames of the previous and the new preset, in viewport size.
+    framebuffers: Option<[Framebuffer; 2]>,
+}
+
+/// Transition program composing the frames of two renderers.
+///
+/// The program is compiled on the first [`TransitionEffect::draw`], in
+/// the OpenGL context of the window that presents it.
+pub struct TransitionEffect {
+    source: String,
+    resources: Option<TransitionResources>,
+    failed: bool,
+}
+
+impl TransitionEffect {
+    /// Creates a transition from its glsl-transitions source.
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: resolve_transition_parameters(source),
+            resources: None,
+            failed: false,
+        }
+    }
+
+    /// Presents the frame of `to` revealed over the frame of `from` by
+    /// `progress`, from 0 to 1, on the current framebuffer.
+    ///
+    /// If the program fails to compile, the frames are crossfaded
+    /// instead.
+    pub fn draw(
+        &mut self,
+        viewport_size: Size,
+        progress: f32,
+        crossfade_t: f32,
+        from: &Renderer,
+        to: &Renderer,
+    ) {
+        if self.resources.is_none() && !self.failed {
+            match self.compile() {
+                Ok(resources) => self.resources = Some(resources),
+                Err(err) => {
+                    log::warn!("Failed to create transition: {err}");
+                    self.failed = true;
+                }
+            }
+        }
+        let Some(resources) = self.resources.as_mut() else {
+            from.blit(crossfade_t);
+            to.blit_over(crossfade_t, progress);
+            return;
+        };
+
+        if resources
+            .framebuffers
+            .as_ref()
+            .is_none_or(|framebuffers| framebuffers[0].size() != viewport_size)
+        {
+            let framebuffer =
+                || Framebuffer::new(viewport_size, 0, FramebufferFormat::Tex2D, false);
+            resources.framebuffers = Some([framebuffer(), framebuffer()]);
+        }
+        let Some(framebuffers) = &resources.framebuffers else {
+            return;
+        };
+
+        let target_fbo = current_framebuffer();
+        from.blit_into(crossfade_t, framebuffers[0].id());
+        to.blit_into(crossfade_t, framebuffers[1].id());
+
+        resources.program.bind();
+        resources.vao.bind();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target_fbo);
+            gl::Viewport(
+                0,
+                0,
+                viewport_size.width() as i32,
+                viewport_size.height() as i32,
+            );
+            for (unit, framebuffer) in framebuffers.iter().enumerate() {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLenum);
+                gl::BindTexture(gl::TEXTURE_2D, framebuffer.texture());
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            }
+            gl::Uniform1f(resources.progress_location, progress.clamp(0.0, 1.0));
+            gl::Uniform1f(
+                resources.ratio_location,
+                viewport_size.width().max(1) as f32 / viewport_size.height().max(1) as f32,
+            );
+            gl::DrawArrays(gl::TRIANGLE_STRIP, 0, 4);
+        }
+    }
+
+    fn compile(&self) -> Result<TransitionResources, ShaderError> {
+        let prelude = gl_profile().shader_prelude();
+        let program = Program::new(&[
+            Shader::new(
+                &(prelude.clone() + TRANSITION_VERTEX_SHADER),
+                gl::VERTEX_SHADER,
+            )?,
+            Shader::new(
+                &(prelude + TRANSITION_FRAGMENT_HEADER + &self.source + TRANSITION_FRAGMENT_FOOTER),
+                gl::FRAGMENT_SHADER,
+            )?,
+        ])?;
+
+        program.bind();
+        unsafe {
+            gl::Uniform1i(program.uniform_location("fromTexture")?, 0);
+            gl::Uniform1i(program.uniform_location("toTexture")?, 1);
+        }
+
+        Ok(TransitionResources {
+            progress_location: program.uniform_location("progress")?,
+            ratio_location: program.uniform_location("ratio")?,
+            program,
+            vao: VertexArray::new(),
+            framebuffers: None,
+        })
+    }
+}
@@ -29,6 +29,11 @@ impl VertexArray {
         unsafe { gl::BindVertexArray(self.id) };
     }
 
+    #[inline]
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
     /// Configures a floating-point vertex attribute.
     ///
     /// Parameters:
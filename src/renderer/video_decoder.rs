@@ -0,0 +1,307 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! GStreamer-backed video decoding for `InputType::Video` and
+//! `InputType::Webcam` channels.
+//!
+//! Each [`VideoDecoder`] owns a pipeline that decodes a local file or
+//! URI, or captures a camera through V4L2 or PipeWire, into RGBA frames
+//! delivered through an `appsink`. Frames are
+//! pulled without blocking from the render loop, so a slow decoder
+//! never stalls the wallpaper: the last uploaded frame is reused
+//! until a new one is available.
+
+use gst::prelude::*;
+use gstreamer as gst;
+use gstreamer_app as gst_app;
+use std::{path::Path, time::Duration};
+use thiserror::Error;
+
+use crate::geometry::Size;
+
+use super::shared_source::FrameSource;
+
+#[derive(Debug, Error)]
+pub enum VideoError {
+    #[error("GStreamer initialization failed: {0}")]
+    Init(#[from] gst::glib::Error),
+    #[error("Failed to build video pipeline: {0}")]
+    Pipeline(#[from] gst::glib::BoolError),
+    #[error("Failed to start video playback: {0}")]
+    StateChange(#[from] gst::StateChangeError),
+}
+
+/// Decoded RGBA frame mapped from a GStreamer buffer.
+pub struct VideoFrame {
+    /// Frame dimensions in pixels.
+    pub size: Size,
+
+    /// Tightly packed RGBA8 pixels, top row first.
+    buffer: gst::MappedBuffer<gst::buffer::Readable>,
+}
+
+impl VideoFrame {
+    pub fn data(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+}
+
+/// Default V4L2 device captured by webcam inputs without a name.
+pub const DEFAULT_WEBCAM_DEVICE: &str = "/dev/video0";
+
+/// Prefix of webcam names that select a PipeWire camera node.
+const PIPEWIRE_PREFIX: &str = "pipewire";
+
+/// Looping video decoder.
+///
+/// Playback starts as soon as the decoder is created and restarts
+/// from the beginning when the end of the stream is reached. Live
+/// sources (webcams) are never looped or seeked.
+pub struct VideoDecoder {
+    /// Source URI or device, kept for diagnostics.
+    uri: String,
+
+    /// Whether frames come from a live capture device.
+    live: bool,
+
+    pipeline: gst::Pipeline,
+
+    appsink: gst_app::AppSink,
+}
+
+impl VideoDecoder {
+    /// Creates a decoder for a file path or URI.
+    ///
+    /// When `vflip` is set, frames are flipped vertically by the
+    /// pipeline so they can be uploaded without further processing.
+    pub fn new(location: &str, vflip: bool) -> Result<Self, VideoError> {
+        gst::init()?;
+
+        let uri = location_to_uri(location)?;
+
+        let source = gst::ElementFactory::make("uridecodebin")
+            .property("uri", &uri)
+            .build()?;
+        let (pipeline, convert, appsink) = build_pipeline(&source, vflip, true)?;
+
+        // uridecodebin exposes its pads only once the stream type is known.
+        // Link the first video pad and ignore the rest (e.g. audio).
+        let convert_weak = convert.downgrade();
+        source.connect_pad_added(move |_, src_pad| {
+            let Some(convert) = convert_weak.upgrade() else {
+                return;
+            };
+            let Some(sink_pad) = convert.static_pad("sink") else {
+                return;
+            };
+            if sink_pad.is_linked() {
+                return;
+            }
+
+            let is_video = src_pad
+                .current_caps()
+                .and_then(|caps| {
+                    caps.structure(0)
+                        .map(|structure| structure.name().starts_with("video/"))
+                })
+                .unwrap_or(false);
+
+            if is_video {
+                if let Err(err) = src_pad.link(&sink_pad) {
+                    log::error!("Failed to link video decoder: {err}");
+                }
+            }
+        });
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        log::debug!("Video decoder started for {uri}");
+
+        Ok(Self {
+            uri,
+            live: false,
+            pipeline,
+            appsink,
+        })
+    }
+
+    /// Creates a decoder capturing frames from a camera.
+    ///
+    /// `device` is a V4L2 device path such as `/dev/video0`, or
+    /// `pipewire` (optionally followed by `:<target>`, a PipeWire node
+    /// name or serial) to capture through PipeWire. An empty string
+    /// selects [`DEFAULT_WEBCAM_DEVICE`].
+    pub fn webcam(device: &str, vflip: bool) -> Result<Self, VideoError> {
+        gst::init()?;
+
+        let device = if device.is_empty() {
+            DEFAULT_WEBCAM_DEVICE
+        } else {
+            device
+        };
+
+        let source = match device.strip_prefix(PIPEWIRE_PREFIX) {
+            Some(target) if target.is_empty() || target.starts_with(':') => {
+                let mut builder = gst::ElementFactory::make("pipewiresrc");
+                if let Some(target) = target.strip_prefix(':').filter(|t| !t.is_empty()) {
+                    builder = builder.property("target-object", target);
+                }
+                builder.build()?
+            }
+            _ => gst::ElementFactory::make("v4l2src")
+                .property("device", device)
+                .build()?,
+        };
+        // Live sources are already paced by the device; syncing against
+        // the pipeline clock would only add latency.
+        let (pipeline, convert, appsink) = build_pipeline(&source, vflip, false)?;
+        source.link(&convert)?;
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        log::debug!("Webcam capture started for {device}");
+
+        Ok(Self {
+            uri: device.to_string(),
+            live: true,
+            pipeline,
+            appsink,
+        })
+    }
+
+    /// Processes pending pipeline messages and returns the newest
+    /// decoded frame, if any.
+    ///
+    /// Returns `None` when no new frame is ready yet.
+    pub fn poll_frame(&self) -> Option<VideoFrame> {
+        self.handle_bus_messages();
+
+        let sample = self.appsink.try_pull_sample(gst::ClockTime::ZERO)?;
+        let structure = sample.caps()?.structure(0)?;
+        let width = structure.get::<i32>("width").ok()?;
+        let height = structure.get::<i32>("height").ok()?;
+        let buffer = sample.buffer_owned()?.into_mapped_buffer_readable().ok()?;
+
+        let size = Size::new(width as u32, height as u32);
+        if buffer.size() < (size.width() * size.height() * 4) as usize {
+            log::warn!("Video '{}': truncated frame", self.uri);
+            return None;
+        }
+
+        Some(VideoFrame { size, buffer })
+    }
+
+    /// Returns the current playback position.
+    pub fn position(&self) -> Duration {
+        self.pipeline
+            .query_position::<gst::ClockTime>()
+            .map(|position| Duration::from_nanos(position.nseconds()))
+            .unwrap_or_default()
+    }
+
+    /// Pauses or resumes playback.
+    ///
+    /// While paused, the playback position (and thus `iChannelTime`)
+    /// stays constant.
+    pub fn set_paused(&self, paused: bool) {
+        let state = if paused {
+            gst::State::Paused
+        } else {
+            gst::State::Playing
+        };
+        if let Err(err) = self.pipeline.set_state(state) {
+            log::warn!("Video '{}': failed to change state: {err}", self.uri);
+        }
+    }
+
+    /// Seeks to the given playback position.
+    ///
+    /// Has no effect on live sources.
+    pub fn seek(&self, position: Duration) {
+        if self.live {
+            return;
+        }
+        let position = gst::ClockTime::from_nseconds(position.as_nanos() as u64);
+        if let Err(err) = self
+            .pipeline
+            .seek_simple(gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT, position)
+        {
+            log::warn!("Video '{}': seek failed: {err}", self.uri);
+        }
+    }
+
+    /// Loops on end-of-stream and reports pipeline errors.
+    fn handle_bus_messages(&self) {
+        let Some(bus) = self.pipeline.bus() else {
+            return;
+        };
+
+        while let Some(message) = bus.pop() {
+            match message.view() {
+                gst::MessageView::Eos(_) => self.seek(Duration::ZERO),
+                gst::MessageView::Error(err) => {
+                    log::error!("Video '{}': {}", self.uri, err.error());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl FrameSource for VideoDecoder {
+    type Frame = VideoFrame;
+
+    fn poll_frame(&mut self) -> Option<VideoFrame> {
+        VideoDecoder::poll_frame(self)
+    }
+}
+
+impl Drop for VideoDecoder {
+    fn drop(&mut self) {
+        let _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
+
+/// Builds a pipeline that converts the output of `source` into RGBA
+/// frames, returning the pipeline, the converter that `source` must be
+/// linked to, and the sink frames are pulled from.
+fn build_pipeline(
+    source: &gst::Element,
+    vflip: bool,
+    sync: bool,
+) -> Result<(gst::Pipeline, gst::Element, gst_app::AppSink), VideoError> {
+    let convert = gst::ElementFactory::make("videoconvert").build()?;
+    let flip = gst::ElementFactory::make("videoflip")
+        .property_from_str("method", if vflip { "vertical-flip" } else { "none" })
+        .build()?;
+    let appsink = gst_app::AppSink::builder()
+        .caps(
+            &gst::Caps::builder("video/x-raw")
+                .field("format", "RGBA")
+                .build(),
+        )
+        .max_buffers(1)
+        .drop(true)
+        .sync(sync)
+        .build();
+
+    let pipeline = gst::Pipeline::new();
+    pipeline.add_many([source, &convert, &flip, appsink.upcast_ref()])?;
+    gst::Element::link_many([&convert, &flip, appsink.upcast_ref()])?;
+
+    Ok((pipeline, convert, appsink))
+}
+
+/// Converts a file path into a `file://` URI.
+///
+/// Strings that already contain a URI scheme are returned unchanged.
+fn location_to_uri(location: &str) -> Result<String, VideoError> {
+    if location.contains("://") {
+        return Ok(location.to_string());
+    }
+
+    let path = std::path::absolute(Path::new(location)).unwrap_or_else(|_| location.into());
+    Ok(gst::glib::filename_to_uri(path, None)?.to_string())
+}
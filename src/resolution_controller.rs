@@ -0,0 +1,180 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Dynamic resolution scaling.
+//!
+//! Lowers the resolution scale of presets that cannot hold their
+//! `target_fps`, and raises it back when there is headroom.
+
+use std::time::{Duration, Instant};
+
+use crate::preset::Preset;
+
+/// Minimum time between adjustments, leaving time for the smoothed frame
+/// rate and GPU times to reflect the previous one.
+const ADJUST_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Fraction of the frame budget aimed at when adjusting the scale.
+const TARGET_LOAD: f64 = 0.8;
+
+/// Load above which the scale is lowered.
+const HIGH_LOAD: f64 = 0.95;
+
+/// Load below which the scale is raised, when GPU times are known.
+const LOW_LOAD: f64 = 0.6;
+
+/// Fraction of the target frame rate that may be missed before the
+/// scale is lowered, when only the frame rate is known. Frames paced by
+/// the display refresh run at the target rate when it is held, so the
+/// frame rate alone never shows headroom.
+const FRAME_RATE_TOLERANCE: f64 = 0.1;
+
+/// Largest factor by which the scale is raised in one adjustment.
+const MAX_RAISE: f64 = 1.25;
+
+/// Factor by which the scale is raised when only the frame rate is
+/// known, since it does not tell how much headroom there is.
+const PROBE_RAISE: f32 = 1.1;
+
+/// Time holding the target frame rate before probing a higher scale,
+/// doubled whenever a probe turns out too slow.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Upper bound of the probe interval.
+const MAX_PROBE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Relative changes of the scale smaller than this are ignored, as
+/// every change recreates the renderers, unless they reach the lowest
+/// or highest scale.
+const MIN_CHANGE: f32 = 0.05;
+
+/// Adjusts the resolution scale to hold a target frame rate.
+pub struct ResolutionController {
+    /// Frame rate to hold.
+    target_fps: f64,
+
+    /// Lowest scale used.
+    min_scale: f32,
+
+    /// Highest scale used, i.e., the `resolution_scale` of the preset.
+    max_scale: f32,
+
+    /// Current scale.
+    scale: f32,
+
+    /// Time of the last adjustment, or of the creation of the controller.
+    last_change: Instant,
+
+    /// Time holding the target before raising the scale without GPU times.
+    probe_interval: Duration,
+
+    /// Indicates whether the last adjustment was a probe.
+    probing: bool,
+}
+
+impl ResolutionController {
+    /// Creates a controller for the preset, or `None` if it has no
+    /// `target_fps`.
+    ///
+    /// The target is lowered to `max_fps` if that is smaller.
+    pub fn new(preset: &Preset) -> Option<Self> {
+        if preset.target_fps <= 0.0 {
+            return None;
+        }
+
+        let target_fps = if preset.max_fps > 0.0 {
+            preset.target_fps.min(preset.max_fps)
+        } else {
+            preset.target_fps
+        };
+        let max_scale = preset.resolution_scale;
+        let min_scale = preset.min_resolution_scale.min(max_scale);
+
+        Some(Self {
+            target_fps,
+            min_scale,
+            max_scale,
+            scale: max_scale,
+            last_change: Instant::now(),
+            probe_interval: PROBE_INTERVAL,
+            probing: false,
+        })
+    }
+
+    /// Returns `true` if the controller was created for the same
+    /// settings as `other`, so that it can be kept.
+    pub fn has_same_settings(&self, other: &Self) -> bool {
+        self.target_fps == other.target_fps
+            && self.min_scale == other.min_scale
+            && self.max_scale == other.max_scale
+    }
+
+    /// Returns the current resolution scale.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Restarts the wait before the next adjustment, e.g., after the
+    /// renderers were recreated for other reasons.
+    pub fn restart(&mut self) {
+        self.last_change = Instant::now();
+    }
+
+    /// Feeds the smoothed frame rate and, if measured, the GPU time of a
+    /// frame, at time `now`.
+    ///
+    /// Returns the new scale if it should be changed.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        frame_rate: f64,
+        gpu_time: Option<Duration>,
+    ) -> Option<f32> {
+        let elapsed = now.saturating_duration_since(self.last_change);
+        if elapsed < ADJUST_INTERVAL {
+            return None;
+        }
+
+        // Pixel count, and so GPU time, grows with the square of the scale
+        let scale = match gpu_time {
+            Some(gpu_time) => {
+                let load = gpu_time.as_secs_f64() * self.target_fps;
+                if load > HIGH_LOAD {
+                    self.scale * (TARGET_LOAD / load).sqrt() as f32
+                } else if load < LOW_LOAD {
+                    self.scale * (TARGET_LOAD / load).sqrt().min(MAX_RAISE) as f32
+                } else {
+                    return None;
+                }
+            }
+            None if frame_rate <= 0.0 => return None,
+            None if frame_rate < self.target_fps * (1.0 - FRAME_RATE_TOLERANCE) => {
+                if self.probing {
+                    self.probe_interval = (self.probe_interval * 2).min(MAX_PROBE_INTERVAL);
+                }
+                let load = self.target_fps / frame_rate;
+                self.scale * (TARGET_LOAD / load).sqrt() as f32
+            }
+            None if elapsed >= self.probe_interval => self.scale * PROBE_RAISE,
+            None => return None,
+        };
+
+        let scale = scale.clamp(self.min_scale, self.max_scale);
+        self.probing = false;
+
+        // Small changes are still made to reach a bound of the scale
+        let at_bound = scale == self.min_scale || scale == self.max_scale;
+        if scale == self.scale
+            || (!at_bound && (scale - self.scale).abs() < self.scale * MIN_CHANGE)
+        {
+            return None;
+        }
+
+        self.probing = gpu_time.is_none() && scale > self.scale;
+        self.scale = scale;
+        self.last_change = now;
+        Some(scale)
+    }
+}
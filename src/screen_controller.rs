@@ -16,7 +16,7 @@ use function_name::named;
 use gtk::{gdk, glib, prelude::*};
 use owo_colors::OwoColorize;
 
-use crate::{app::*, geometry::*, preset::*, *};
+use crate::{app::*, drm::*, geometry::*, preset::*, *};
 
 /// Manages monitor selection and virtual screen layout.
 ///
@@ -40,6 +40,10 @@ pub struct ScreenController {
 
     /// Policy used to compute `screen_bounds`.
     screen_bounds_policy: ScreenBoundsPolicy,
+
+    /// DRM connectors, which identify monitors beyond what GDK reports.
+    /// Empty when no DRM card is accessible.
+    connectors: Vec<Connector>,
 }
 
 impl ScreenController {
@@ -53,27 +57,33 @@ impl ScreenController {
         let all_monitors = ScreenController::all_monitors();
         ScreenController::connect_geometry_notify(app, &all_monitors);
 
-        // Determine whether preset selects all monitors
-        let select_all = app_data
-            .cli_config
-            .preset
-            .monitor_selection
-            .iter()
-            .any(|s| s == "*");
+        // Determine whether preset selects all monitors. Lock surfaces
+        // must cover every output, whatever the selection.
+        let monitor_selection = app_data.cli_config.preset.selected_monitors();
+        let select_all =
+            app_data.session_lock.is_some() || monitor_selection.iter().any(|s| s == "*");
+
+        let connectors = app_data
+            .drm_monitor
+            .as_ref()
+            .map(DrmMonitor::connectors)
+            .unwrap_or_default();
 
-        // Select monitors based on connector names
+        // Select monitors based on connector names or EDID identifiers
         let selected_monitors = all_monitors
             .iter()
             .filter(|monitor| {
                 monitor
                     .connector()
                     .map(|connector| {
+                        let drm_connector = connectors
+                            .iter()
+                            .find(|drm_connector| drm_connector.name == connector.as_str());
                         select_all
-                            || app_data
-                                .cli_config
-                                .preset
-                                .monitor_selection
-                                .contains(&connector.to_string())
+                            || monitor_selection.iter().any(|entry| match drm_connector {
+                                Some(drm_connector) => drm_connector.is_selected_by(entry),
+                                None => entry == connector.as_str(),
+                            })
                     })
                     .unwrap_or(false)
             })
@@ -89,11 +99,22 @@ impl ScreenController {
 
         log::debug!("Screen bounds: {:?}", screen_bounds);
 
-        Self {
+        let controller = Self {
             selected_monitors,
             screen_bounds,
             screen_bounds_policy,
+            connectors,
+        };
+        for monitor in &controller.selected_monitors {
+            if let Some(connector) = controller.drm_connector(monitor) {
+                log::debug!(
+                    "Selected monitor {}: {}",
+                    connector.name,
+                    connector.description()
+                );
+            }
         }
+        controller
     }
 
     /// Returns the monitors participating in rendering.
@@ -101,6 +122,14 @@ impl ScreenController {
         &self.selected_monitors
     }
 
+    /// Returns the DRM connector of a monitor, if known.
+    pub fn drm_connector(&self, monitor: &gdk::Monitor) -> Option<&Connector> {
+        let name = monitor.connector()?;
+        self.connectors
+            .iter()
+            .find(|connector| connector.name == name.as_str())
+    }
+
     /// Returns the virtual screen bounds.
     ///
     /// Returns `None` in cloned mode.
@@ -108,6 +137,18 @@ impl ScreenController {
         self.screen_bounds
     }
 
+    /// Returns the size of the frame shared by all monitors in cloned
+    /// mode, i.e., the size of the first selected monitor.
+    ///
+    /// Returns `None` if not in cloned mode or if no monitor is selected.
+    pub fn cloned_frame_size(&self) -> Option<Size> {
+        if self.screen_bounds_policy != ScreenBoundsPolicy::Cloned {
+            return None;
+        }
+        let bounds = Rectangle::from(self.selected_monitors.first()?.geometry());
+        Some(Size::new(bounds.width() as u32, bounds.height() as u32))
+    }
+
     /// Computes monitor-local bounds and OpenGL offset.
     ///
     /// Returns a tuple containing the monitor rectangle relative to the virtual screen and
@@ -0,0 +1,196 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Lock screen through the `ext_session_lock_v1` protocol.
+//!
+//! Uses the session lock API of GTK4 Layer Shell 1.1 or later, which the
+//! `gtk4-layer-shell` crate does not bind yet, so its functions are
+//! looked up at runtime in the already loaded library. While locked, the
+//! render windows are the lock surfaces of the monitors. ShaderBG does
+//! not check passwords itself: the `auth_command` of the configuration
+//! file receives the typed password on its standard input, and unlocks
+//! the session by exiting successfully.
+
+use gtk::{
+    gdk, gio,
+    glib::{self, translate::*},
+    prelude::*,
+};
+use serde::Deserialize;
+use std::{cell::Cell, ffi::c_void, rc::Rc};
+use thiserror::Error;
+
+/// Library providing the session lock API.
+const LAYER_SHELL_LIBRARY: &str = "libgtk4-layer-shell.so.0";
+
+/// Lock screen settings, from the `[session_lock]` table of the
+/// configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionLockConfig {
+    /// Shell command checking the password written to its standard
+    /// input, e.g., with PAM. Exits with status 0 if it is correct.
+    pub auth_command: String,
+}
+
+#[derive(Debug, Error)]
+pub enum SessionLockError {
+    #[error("Failed to load the session lock API of {LAYER_SHELL_LIBRARY} (version 1.1 or later is required): {0}")]
+    Load(#[from] libloading::Error),
+
+    #[error("Compositor does not support ext_session_lock_v1")]
+    Unsupported,
+
+    #[error("Session lock requires an auth_command in the [session_lock] table of the configuration file")]
+    NoAuthCommand,
+
+    #[error("Compositor refused to lock the session")]
+    Refused,
+}
+
+type IsSupportedFn = unsafe extern "C" fn() -> glib::ffi::gboolean;
+type InstanceNewFn = unsafe extern "C" fn() -> *mut c_void;
+type InstanceLockFn = unsafe extern "C" fn(*mut c_void) -> glib::ffi::gboolean;
+type InstanceUnlockFn = unsafe extern "C" fn(*mut c_void);
+type AssignWindowFn =
+    unsafe extern "C" fn(*mut c_void, *mut gtk::ffi::GtkWindow, *mut gdk::ffi::GdkMonitor);
+
+/// Functions of the session lock API.
+struct SessionLockApi {
+    is_supported: IsSupportedFn,
+    instance_new: InstanceNewFn,
+    instance_lock: InstanceLockFn,
+    instance_unlock: InstanceUnlockFn,
+    assign_window_to_monitor: AssignWindowFn,
+
+    /// Keeps the function pointers valid.
+    _library: libloading::os::unix::Library,
+}
+
+/// Lock of the session, showing the render windows on the lock screen.
+///
+/// The lock windows are destroyed by GTK4 Layer Shell when the session
+/// is unlocked.
+pub struct SessionLock {
+    api: SessionLockApi,
+
+    /// `GtkSessionLockInstance`, whose signals report the end of the
+    /// lock.
+    instance: glib::Object,
+
+    locked: Rc<Cell<bool>>,
+}
+
+impl SessionLock {
+    /// Prepares to lock the session, checking that the compositor
+    /// supports it and that passwords can be checked.
+    pub fn new(config: Option<&SessionLockConfig>) -> Result<Self, SessionLockError> {
+        if config.is_none_or(|config| config.auth_command.trim().is_empty()) {
+            return Err(SessionLockError::NoAuthCommand);
+        }
+
+        let library = unsafe { libloading::os::unix::Library::new(LAYER_SHELL_LIBRARY)? };
+        let api = unsafe {
+            SessionLockApi {
+                is_supported: *library.get(b"gtk_session_lock_is_supported")?,
+                instance_new: *library.get(b"gtk_session_lock_instance_new")?,
+                instance_lock: *library.get(b"gtk_session_lock_instance_lock")?,
+                instance_unlock: *library.get(b"gtk_session_lock_instance_unlock")?,
+                assign_window_to_monitor: *library
+                    .get(b"gtk_session_lock_instance_assign_window_to_monitor")?,
+                _library: library,
+            }
+        };
+
+        if unsafe { (api.is_supported)() } == glib::ffi::GFALSE {
+            return Err(SessionLockError::Unsupported);
+        }
+
+        let instance: glib::Object =
+            unsafe { from_glib_full((api.instance_new)() as *mut glib::gobject_ffi::GObject) };
+
+        let locked = Rc::new(Cell::new(false));
+        instance.connect_local(
+            "unlocked",
+            false,
+            glib::clone!(
+                #[strong]
+                locked,
+                move |_| {
+                    locked.set(false);
+                    None
+                }
+            ),
+        );
+
+        Ok(Self {
+            api,
+            instance,
+            locked,
+        })
+    }
+
+    /// Locks the session. Every monitor must then be given a window with
+    /// [`SessionLock::assign_window_to_monitor`] before returning to the
+    /// main loop.
+    pub fn lock(&self) -> Result<(), SessionLockError> {
+        if unsafe { (self.api.instance_lock)(self.instance_ptr()) } == glib::ffi::GFALSE {
+            return Err(SessionLockError::Refused);
+        }
+        self.locked.set(true);
+        Ok(())
+    }
+
+    /// Returns `true` between [`SessionLock::lock`] and the end of the
+    /// lock.
+    pub fn is_locked(&self) -> bool {
+        self.locked.get()
+    }
+
+    /// Shows `window` as the lock surface of `monitor`. The window must
+    /// not be realized yet.
+    pub fn assign_window_to_monitor(&self, window: &impl IsA<gtk::Window>, monitor: &gdk::Monitor) {
+        unsafe {
+            (self.api.assign_window_to_monitor)(
+                self.instance_ptr(),
+                window.as_ref().to_glib_none().0,
+                monitor.to_glib_none().0,
+            );
+        }
+    }
+
+    /// Unlocks the session.
+    pub fn unlock(&self) {
+        if self.locked.replace(false) {
+            unsafe { (self.api.instance_unlock)(self.instance_ptr()) };
+        }
+    }
+
+    /// Invokes `on_failed` if the compositor refuses the lock, e.g.,
+    /// because another program already holds it.
+    pub fn connect_failed<F: Fn() + 'static>(&self, on_failed: F) {
+        self.instance.connect_local("failed", false, move |_| {
+            on_failed();
+            None
+        });
+    }
+
+    fn instance_ptr(&self) -> *mut c_void {
+        self.instance.as_ptr() as *mut c_void
+    }
+}
+
+/// Runs `auth_command` with `password` on its standard input, and
+/// returns `true` if it exits with status 0.
+pub async fn authenticate(auth_command: &str, password: &str) -> Result<bool, glib::Error> {
+    let process = gio::Subprocess::newv(
+        &["sh".as_ref(), "-c".as_ref(), auth_command.as_ref()],
+        gio::SubprocessFlags::STDIN_PIPE,
+    )?;
+    process
+        .communicate_utf8_future(Some(format!("{password}\n")))
+        .await?;
+    Ok(process.is_successful())
+}
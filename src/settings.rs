@@ -0,0 +1,671 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Preset manager window (`--settings`).
+//!
+//! Lists the presets of the presets directory and lets the user apply
+//! them to the running wallpaper, delete them, and edit their metadata
+//! and display settings.
+//!
+//! Edits are written back to the TOML file of the preset, which the
+//! running wallpaper reloads if it is showing that preset. Presets are
+//! applied over the control socket, so the wallpaper must run in daemon
+//! mode.
+
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use toml_edit::{DocumentMut, Item};
+
+use crate::{
+    ipc::{self, IpcCommand},
+    offscreen::OffscreenContext,
+    preset::*,
+    thumbnail::{cached_thumbnail, prune_thumbnails, thumbnail_async},
+    *,
+};
+
+/// Values of `layout_mode` listed in the editor.
+const LAYOUT_MODES: [&str; 5] = ["stretch", "center", "repeat", "mirrored_repeat", "region"];
+
+/// Values of `filter_mode` listed in the editor.
+const FILTER_MODES: [&str; 3] = ["linear", "nearest", "mipmap"];
+
+/// Size of the preset thumbnails in the list, in logical pixels.
+const THUMBNAIL_SIZE: (i32, i32) = (128, 72);
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to parse preset: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+
+    #[error("Bundles cannot be edited: {}", .0.display())]
+    NotEditable(PathBuf),
+}
+
+/// Preset fields that can be edited in the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetFields {
+    pub name: String,
+    pub username: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub weight: f64,
+    pub layout_mode: LayoutMode,
+    pub filter_mode: FilterMode,
+    pub resolution_scale: f32,
+}
+
+impl PresetFields {
+    /// Returns the editable fields of a preset.
+    pub fn from_preset(preset: &Preset) -> Self {
+        Self {
+            name: preset.name.clone(),
+            username: preset.username.clone(),
+            description: preset.description.clone(),
+            tags: preset.tags.clone(),
+            weight: preset.weight,
+            layout_mode: preset.layout_mode,
+            filter_mode: preset.filter_mode,
+            resolution_scale: preset.resolution_scale,
+        }
+    }
+
+    /// Writes the fields into the TOML preset at `path`, editing it in
+    /// place so that its other keys, layout and comments are kept.
+    pub fn write(&self, path: &Path) -> Result<(), SettingsError> {
+        if crate::bundle::is_bundle(path) {
+            return Err(SettingsError::NotEditable(path.to_path_buf()));
+        }
+
+        let mut document: DocumentMut = fs::read_to_string(path)?.parse()?;
+        let tags = toml_edit::Array::from_iter(&self.tags);
+        for (key, value) in [
+            ("name", toml_edit::Value::from(&self.name)),
+            ("username", toml_edit::Value::from(&self.username)),
+            ("description", toml_edit::Value::from(&self.description)),
+            ("tags", toml_edit::Value::Array(tags)),
+            ("weight", toml_edit::Value::from(self.weight)),
+            ("layout_mode", variant_name(&self.layout_mode).into()),
+            ("filter_mode", variant_name(&self.filter_mode).into()),
+            (
+                "resolution_scale",
+                shortest_f64(self.resolution_scale).into(),
+            ),
+        ] {
+            set_value(&mut document, key, value);
+        }
+
+        fs::write(path, document.to_string())?;
+        Ok(())
+    }
+}
+
+/// Sets a top-level key of a TOML document, keeping the comments and
+/// whitespace around its previous value.
+fn set_value(document: &mut DocumentMut, key: &str, mut value: toml_edit::Value) {
+    if let Some(previous) = document.get(key).and_then(Item::as_value) {
+        *value.decor_mut() = previous.decor().clone();
+    }
+    document[key] = Item::Value(value);
+}
+
+/// Returns `value` as the `f64` with the shortest decimal representation
+/// of the `f32`, e.g., `0.1` instead of `0.10000000149011612`.
+fn shortest_f64(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(f64::from(value))
+}
+
+/// Preset listed in the window.
+struct PresetEntry {
+    path: PathBuf,
+    preset: Preset,
+}
+
+/// Widgets editing the [`PresetFields`] of the selected preset.
+struct Editor {
+    container: gtk::Grid,
+    name: gtk::Entry,
+    username: gtk::Entry,
+    description: gtk::TextView,
+    tags: gtk::Entry,
+    weight: gtk::SpinButton,
+    layout_mode: gtk::DropDown,
+    filter_mode: gtk::DropDown,
+    resolution_scale: gtk::SpinButton,
+    save: gtk::Button,
+}
+
+/// State of the settings window.
+struct SettingsData {
+    entries: Vec<PresetEntry>,
+    list: gtk::ListBox,
+    editor: Editor,
+    status: gtk::Label,
+}
+
+/// Opens the preset manager and runs until it is closed.
+pub fn run() -> glib::ExitCode {
+    let app = gtk::Application::builder()
+        .application_id(APP_ID)
+        .flags(gio::ApplicationFlags::NON_UNIQUE)
+        .build();
+    app.connect_activate(create_window);
+    app.run_with_args(&[""])
+}
+
+/// Creates the window listing the presets.
+fn create_window(app: &gtk::Application) {
+    let window = gtk::ApplicationWindow::builder()
+        .application(app)
+        .icon_name(APP_NAME)
+        .title(format!("{APP_NAME} presets"))
+        .default_width(960)
+        .default_height(600)
+        .build();
+
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::Single);
+    let scrolled_list = gtk::ScrolledWindow::builder()
+        .child(&list)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_width(360)
+        .build();
+
+    let editor = create_editor();
+    let status = gtk::Label::builder()
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_bottom(8)
+        .build();
+
+    let paned = gtk::Paned::new(gtk::Orientation::Horizontal);
+    paned.set_start_child(Some(&scrolled_list));
+    paned.set_end_child(Some(&editor.container));
+    paned.set_shrink_start_child(false);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    paned.set_vexpand(true);
+    content.append(&paned);
+    content.append(&status);
+    window.set_child(Some(&content));
+
+    let entries = list_presets(&presets_dir());
+    let mut missing_thumbnails = Vec::new();
+    for entry in &entries {
+        let (row, picture) = create_row(entry);
+        list.append(&row);
+        if cached_thumbnail(&entry.preset).is_none() {
+            missing_thumbnails.push((entry.preset.clone(), picture));
+        }
+    }
+    render_thumbnails(missing_thumbnails);
+
+    list.connect_row_selected(clone!(
+        #[weak]
+        window,
+        move |_, row| on_row_selected(&window, row)
+    ));
+    editor.save.connect_clicked(clone!(
+        #[weak]
+        window,
+        move |_| save_selected(&window)
+    ));
+
+    set_data!(
+        window,
+        SettingsData {
+            entries,
+            list,
+            editor,
+            status,
+        }
+    );
+
+    let settings_data = get_data!(window, SettingsData, as_ref());
+    settings_data.editor.container.set_sensitive(false);
+    if let Some(row) = settings_data.list.row_at_index(0) {
+        settings_data.list.select_row(Some(&row));
+    }
+
+    window.present();
+}
+
+/// Creates the editor of the selected preset, with its action buttons.
+fn create_editor() -> Editor {
+    let container = gtk::Grid::builder()
+        .row_spacing(8)
+        .column_spacing(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .margin_start(12)
+        .margin_end(12)
+        .hexpand(true)
+        .build();
+
+    let name = gtk::Entry::new();
+    let username = gtk::Entry::new();
+    let description = gtk::TextView::builder()
+        .wrap_mode(gtk::WrapMode::WordChar)
+        .vexpand(true)
+        .build();
+    let tags = gtk::Entry::builder()
+        .placeholder_text("Comma-separated, e.g., calm, dark")
+        .build();
+    let weight = gtk::SpinButton::with_range(0.0, 100.0, 0.5);
+    weight.set_digits(1);
+    let layout_mode = gtk::DropDown::from_strings(&LAYOUT_MODES);
+    let filter_mode = gtk::DropDown::from_strings(&FILTER_MODES);
+    let resolution_scale = gtk::SpinButton::with_range(0.05, 4.0, 0.05);
+    resolution_scale.set_digits(2);
+
+    let description_frame = gtk::ScrolledWindow::builder()
+        .child(&description)
+        .min_content_height(96)
+        .has_frame(true)
+        .build();
+
+    let fields: [(&str, &gtk::Widget); 8] = [
+        ("Name", name.upcast_ref()),
+        ("Author", username.upcast_ref()),
+        ("Description", description_frame.upcast_ref()),
+        ("Tags", tags.upcast_ref()),
+        ("Weight", weight.upcast_ref()),
+        ("Layout mode", layout_mode.upcast_ref()),
+        ("Filter mode", filter_mode.upcast_ref()),
+        ("Resolution scale", resolution_scale.upcast_ref()),
+    ];
+    let field_count = fields.len() as i32;
+    for (row, (label, widget)) in fields.into_iter().enumerate() {
+        let label = gtk::Label::builder()
+            .label(label)
+            .xalign(1.0)
+            .valign(gtk::Align::Start)
+            .margin_top(6)
+            .build();
+        widget.set_hexpand(true);
+        container.attach(&label, 0, row as i32, 1, 1);
+        container.attach(widget, 1, row as i32, 1, 1);
+    }
+
+    let save = gtk::Button::with_label("Save");
+    let apply = gtk::Button::with_label("Apply");
+    let delete = gtk::Button::with_label("Delete");
+    delete.add_css_class("destructive-action");
+    save.add_css_class("suggested-action");
+
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+    buttons.append(&delete);
+    buttons.append(&apply);
+    buttons.append(&save);
+    container.attach(&buttons, 0, field_count, 2, 1);
+
+    apply.connect_clicked(|button| {
+        if let Some(window) = button.root().and_downcast::<gtk::ApplicationWindow>() {
+            apply_selected(&window);
+        }
+    });
+    delete.connect_clicked(|button| {
+        if let Some(window) = button.root().and_downcast::<gtk::ApplicationWindow>() {
+            confirm_delete_selected(&window);
+        }
+    });
+
+    Editor {
+        container,
+        name,
+        username,
+        description,
+        tags,
+        weight,
+        layout_mode,
+        filter_mode,
+        resolution_scale,
+        save,
+    }
+}
+
+/// Loads every preset and bundle of the directory, sorted by name.
+fn list_presets(dir: &Path) -> Vec<PresetEntry> {
+    let presets = match presets_in_directory(dir) {
+        Ok(presets) => presets,
+        Err(PresetError::NoPresets) => Vec::new(),
+        Err(err) => {
+            log::error!("Failed to read {}: {err}", dir.display());
+            Vec::new()
+        }
+    };
+
+    let mut entries: Vec<_> = presets
+        .into_iter()
+        .map(|(preset, path)| PresetEntry { path, preset })
+        .collect();
+    entries.sort_by_cached_key(|entry| entry_title(entry).to_lowercase());
+    entries
+}
+
+/// Returns the name of the preset, or its file name if it has none.
+fn entry_title(entry: &PresetEntry) -> String {
+    if entry.preset.name.trim().is_empty() {
+        entry
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    } else {
+        entry.preset.name.clone()
+    }
+}
+
+/// Creates the list row of a preset and returns it with its thumbnail.
+///
+/// The thumbnail shows the cached rendering of the preset, or else its
+/// fallback image until the rendering is ready.
+fn create_row(entry: &PresetEntry) -> (gtk::ListBoxRow, gtk::Picture) {
+    let thumbnail = gtk::Picture::builder()
+        .content_fit(gtk::ContentFit::Cover)
+        .build();
+    let image_path = cached_thumbnail(&entry.preset).or_else(|| {
+        entry
+            .preset
+            .fallback_image_path()
+            .filter(|path| path.exists())
+    });
+    if let Some(path) = image_path {
+        thumbnail.set_filename(Some(path));
+    }
+    thumbnail.set_size_request(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1);
+
+    let label = gtk::Label::builder()
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build();
+    label.set_markup(&row_markup(entry));
+
+    let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    row_box.set_margin_top(6);
+    row_box.set_margin_bottom(6);
+    row_box.set_margin_start(6);
+    row_box.set_margin_end(6);
+    row_box.append(&thumbnail);
+    row_box.append(&label);
+
+    (
+        gtk::ListBoxRow::builder().child(&row_box).build(),
+        thumbnail,
+    )
+}
+
+/// Prunes unused thumbnails, then renders the thumbnails of the given
+/// presets and shows them once ready.
+///
+/// Thumbnails are rendered one frame per main loop iteration and saved
+/// on a worker thread, so that the window stays responsive.
+fn render_thumbnails(pending: Vec<(Preset, gtk::Picture)>) {
+    glib::spawn_future_local(async move {
+        if let Ok(Err(err)) = gio::spawn_blocking(prune_thumbnails).await {
+            log::warn!("Failed to prune thumbnails: {err}");
+        }
+        if pending.is_empty() {
+            return;
+        }
+
+        let context = match OffscreenContext::new() {
+            Ok(context) => context,
+            Err(err) => {
+                log::warn!("Failed to render thumbnails: {err}");
+                return;
+            }
+        };
+        for (preset, picture) in pending {
+            match thumbnail_async(&context, &preset).await {
+                Ok(path) => picture.set_filename(Some(path)),
+                Err(err) => log::warn!("Failed to render thumbnail of {}: {err}", preset.name),
+            }
+        }
+    });
+}
+
+/// Returns the Pango markup of the label of a preset row.
+fn row_markup(entry: &PresetEntry) -> String {
+    let file_name = entry
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let details = if entry.preset.username.is_empty() {
+        file_name
+    } else {
+        format!("{} · {file_name}", entry.preset.username)
+    };
+    format!(
+        "<b>{}</b>\n<small>{}</small>",
+        glib::markup_escape_text(&entry_title(entry)),
+        glib::markup_escape_text(&details)
+    )
+}
+
+/// Fills the editor with the fields of the selected preset.
+fn on_row_selected(window: &gtk::ApplicationWindow, row: Option<&gtk::ListBoxRow>) {
+    let settings_data = get_data!(window, SettingsData, as_ref());
+    let editor = &settings_data.editor;
+    let Some(entry) = row.and_then(|row| settings_data.entries.get(row.index() as usize)) else {
+        editor.container.set_sensitive(false);
+        return;
+    };
+
+    let fields = PresetFields::from_preset(&entry.preset);
+    editor.name.set_text(&fields.name);
+    editor.username.set_text(&fields.username);
+    editor.description.buffer().set_text(&fields.description);
+    editor.tags.set_text(&fields.tags.join(", "));
+    editor.weight.set_value(fields.weight);
+    editor
+        .layout_mode
+        .set_selected(variant_index(&fields.layout_mode, &LAYOUT_MODES));
+    editor
+        .filter_mode
+        .set_selected(variant_index(&fields.filter_mode, &FILTER_MODES));
+    editor
+        .resolution_scale
+        .set_value(shortest_f64(fields.resolution_scale));
+
+    editor.container.set_sensitive(true);
+    editor
+        .save
+        .set_sensitive(!crate::bundle::is_bundle(&entry.path));
+    settings_data
+        .status
+        .set_text(&entry.path.display().to_string());
+}
+
+/// Returns the fields entered in the editor.
+fn editor_fields(editor: &Editor) -> PresetFields {
+    let buffer = editor.description.buffer();
+    PresetFields {
+        name: editor.name.text().to_string(),
+        username: editor.username.text().to_string(),
+        description: buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), false)
+            .to_string(),
+        tags: editor
+            .tags
+            .text()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        weight: editor.weight.value(),
+        layout_mode: variant_from_index(editor.layout_mode.selected(), &LAYOUT_MODES),
+        filter_mode: variant_from_index(editor.filter_mode.selected(), &FILTER_MODES),
+        resolution_scale: editor.resolution_scale.value() as f32,
+    }
+}
+
+/// Returns the index of the serialized name of an enum variant in
+/// `names`, or `0` if it is not listed.
+fn variant_index<T: Serialize>(value: &T, names: &[&str]) -> u32 {
+    let name = variant_name(value);
+    names
+        .iter()
+        .position(|&listed| listed == name)
+        .unwrap_or_default() as u32
+}
+
+/// Returns the serialized name of an enum variant.
+fn variant_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Returns the enum variant serialized as `names[index]`, or the default
+/// variant.
+fn variant_from_index<T: DeserializeOwned + Default>(index: u32, names: &[&str]) -> T {
+    names
+        .get(index as usize)
+        .and_then(|name| serde_json::from_value(serde_json::Value::from(*name)).ok())
+        .unwrap_or_default()
+}
+
+/// Writes the editor fields into the selected preset.
+fn save_selected(window: &gtk::ApplicationWindow) {
+    let settings_data = get_data!(window, SettingsData, as_mut());
+    let Some(index) = selected_index(settings_data) else {
+        return;
+    };
+
+    let entry = &mut settings_data.entries[index];
+    let result = editor_fields(&settings_data.editor)
+        .write(&entry.path)
+        .map_err(|err| err.to_string())
+        .and_then(|()| Preset::from_file(&entry.path).map_err(|err| err.to_string()));
+
+    match result {
+        Ok(preset) => {
+            entry.preset = preset;
+            if let Some(row) = settings_data.list.row_at_index(index as i32) {
+                if let Some(label) = row
+                    .child()
+                    .and_then(|child| child.last_child())
+                    .and_downcast::<gtk::Label>()
+                {
+                    label.set_markup(&row_markup(entry));
+                }
+            }
+            settings_data
+                .status
+                .set_text(&format!("Saved {}", entry.path.display()));
+        }
+        Err(err) => {
+            log::error!("Failed to save {}: {err}", entry.path.display());
+            settings_data.status.set_text(&err);
+        }
+    }
+}
+
+/// Asks the running wallpaper to switch to the selected preset.
+fn apply_selected(window: &gtk::ApplicationWindow) {
+    let settings_data = get_data!(window, SettingsData, as_ref());
+    let Some(index) = selected_index(settings_data) else {
+        return;
+    };
+
+    let path = settings_data.entries[index].path.clone();
+    settings_data
+        .status
+        .set_text(&format!("Applying {}", path.display()));
+
+    let status_label = settings_data.status.clone();
+    glib::spawn_future_local(async move {
+        let status = match ipc::send_in_background(IpcCommand::Switch(path.clone())).await {
+            Ok(_) => format!("Applied {}", path.display()),
+            Err(err) => err.to_string(),
+        };
+        status_label.set_text(&status);
+    });
+}
+
+/// Deletes the selected preset file once the user confirms.
+fn confirm_delete_selected(window: &gtk::ApplicationWindow) {
+    let settings_data = get_data!(window, SettingsData, as_ref());
+    let Some(index) = selected_index(settings_data) else {
+        return;
+    };
+
+    let entry = &settings_data.entries[index];
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message(format!("Delete \"{}\"?", entry_title(entry)))
+        .detail(format!(
+            "{} will be permanently deleted.",
+            entry.path.display()
+        ))
+        .buttons(["Cancel", "Delete"])
+        .cancel_button(0)
+        .default_button(0)
+        .build();
+
+    dialog.choose(
+        Some(window),
+        None::<&gio::Cancellable>,
+        clone!(
+            #[weak]
+            window,
+            move |response| {
+                if response == Ok(1) {
+                    delete_preset(&window, index);
+                }
+            }
+        ),
+    );
+}
+
+/// Deletes the preset at `index` and removes its row.
+fn delete_preset(window: &gtk::ApplicationWindow, index: usize) {
+    let settings_data = get_data!(window, SettingsData, as_mut());
+    let Some(entry) = settings_data.entries.get(index) else {
+        return;
+    };
+
+    if let Err(err) = fs::remove_file(&entry.path) {
+        log::error!("Failed to delete {}: {err}", entry.path.display());
+        settings_data.status.set_text(&err.to_string());
+        return;
+    }
+
+    let entry = settings_data.entries.remove(index);
+    if let Some(row) = settings_data.list.row_at_index(index as i32) {
+        settings_data.list.remove(&row);
+    }
+    settings_data
+        .status
+        .set_text(&format!("Deleted {}", entry.path.display()));
+}
+
+/// Returns the index of the selected preset.
+fn selected_index(settings_data: &SettingsData) -> Option<usize> {
+    let index = settings_data.list.selected_row()?.index();
+    usize::try_from(index)
+        .ok()
+        .filter(|&index| index < settings_data.entries.len())
+}
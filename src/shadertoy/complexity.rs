@@ -0,0 +1,816 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Static estimation of the cost of ShaderToy shaders.
+//!
+//! Estimates, per pixel, the number of loop iterations, texture fetches
+//! and derivative evaluations of a pass, so that shaders too heavy to run
+//! as a wallpaper can be flagged before they are applied.
+//!
+//! Loop bounds are estimated from `for` headers comparing the loop
+//! variable to literals, `#define`s or `const` variables, and function
+//! costs are multiplied by the iterations of the loops calling them.
+//! Other loops are assumed to run once, so estimates are lower bounds.
+
+use std::collections::{HashMap, HashSet};
+
+use super::glsl_utils::strip_comments;
+use crate::preset::{Preset, PresetError};
+
+/// Loop iterations per pixel above which a pass is reported as heavy.
+pub const HEAVY_LOOP_ITERATIONS: f64 = 10_000.0;
+
+/// Texture fetches per pixel above which a pass is reported as heavy.
+pub const HEAVY_TEXTURE_FETCHES: f64 = 1_000.0;
+
+/// Derivative evaluations per pixel above which a pass is reported as
+/// heavy.
+pub const HEAVY_DERIVATIVES: f64 = 100.0;
+
+/// Functions invoked for each pixel or cubemap texel.
+const ENTRY_POINTS: [&str; 2] = ["mainImage", "mainCubemap"];
+
+const TEXTURE_FUNCTIONS: [&str; 10] = [
+    "texture",
+    "textureLod",
+    "textureGrad",
+    "textureOffset",
+    "textureLodOffset",
+    "textureGradOffset",
+    "textureProj",
+    "textureProjLod",
+    "texelFetch",
+    "texelFetchOffset",
+];
+
+const DERIVATIVE_FUNCTIONS: [&str; 3] = ["dFdx", "dFdy", "fwidth"];
+
+/// Multi-character operators, longest first.
+const OPERATORS: [&str; 12] = [
+    "<=", ">=", "==", "!=", "&&", "||", "++", "--", "+=", "-=", "*=", "/=",
+];
+
+/// Estimated cost of a pass, per pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShaderComplexity {
+    /// Loop body executions.
+    pub loop_iterations: f64,
+
+    /// Texture fetches, including those of `texelFetch`.
+    pub texture_fetches: f64,
+
+    /// Evaluations of `dFdx`, `dFdy` and `fwidth`.
+    pub derivatives: f64,
+
+    /// Loops without an exit condition, e.g., `for (;;)` or
+    /// `while (true)`, which only end with a `break` or `return`.
+    pub unbounded_loops: usize,
+}
+
+impl ShaderComplexity {
+    /// Returns a message for each estimate above its threshold.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.unbounded_loops > 0 {
+            warnings.push(format!(
+                "{} loop(s) without an exit condition may never terminate",
+                self.unbounded_loops
+            ));
+        }
+        if self.loop_iterations > HEAVY_LOOP_ITERATIONS {
+            warnings.push(format!(
+                "About {:.0} loop iterations per pixel",
+                self.loop_iterations
+            ));
+        }
+        if self.texture_fetches > HEAVY_TEXTURE_FETCHES {
+            warnings.push(format!(
+                "About {:.0} texture fetches per pixel",
+                self.texture_fetches
+            ));
+        }
+        if self.derivatives > HEAVY_DERIVATIVES {
+            warnings.push(format!(
+                "About {:.0} derivative evaluations per pixel",
+                self.derivatives
+            ));
+        }
+        warnings
+    }
+
+    fn add(&mut self, other: &Self, times: f64) {
+        self.loop_iterations += other.loop_iterations * times;
+        self.texture_fetches += other.texture_fetches * times;
+        self.derivatives += other.derivatives * times;
+        self.unbounded_loops += other.unbounded_loops;
+    }
+
+    fn max(&self, other: &Self) -> Self {
+        Self {
+            loop_iterations: self.loop_iterations.max(other.loop_iterations),
+            texture_fetches: self.texture_fetches.max(other.texture_fetches),
+            derivatives: self.derivatives.max(other.derivatives),
+            unbounded_loops: self.unbounded_loops.max(other.unbounded_loops),
+        }
+    }
+}
+
+/// Estimates the cost of a pass from its source and the source of the
+/// Common pass.
+pub fn analyze(common: &str, shader: &str) -> ShaderComplexity {
+    let source = format!("{common}\n{shader}");
+    let source = strip_comments(&source);
+
+    let mut constants = HashMap::new();
+    let mut code = String::with_capacity(source.len());
+    for line in source.lines() {
+        match line.trim_start().strip_prefix('#') {
+            Some(directive) => parse_define(directive, &mut constants),
+            None => {
+                code.push_str(line);
+                code.push('\n');
+            }
+        }
+    }
+
+    let tokens = tokenize(&code);
+    collect_constants(&tokens, &mut constants);
+    let analyzer = Analyzer {
+        functions: find_functions(&tokens),
+        tokens: &tokens,
+        constants,
+    };
+
+    let mut memo = HashMap::new();
+    ENTRY_POINTS
+        .iter()
+        .map(|name| analyzer.function_cost(name, &mut memo, &mut HashSet::new()))
+        .fold(ShaderComplexity::default(), |a, b| a.max(&b))
+}
+
+/// Returns the complexity warnings of the render passes of `preset`,
+/// along with the pass names.
+pub fn preset_warnings(preset: &Preset) -> Vec<(String, String)> {
+    let common = preset
+        .common
+        .as_ref()
+        .map_or("", |common_pass| common_pass.shader.as_str());
+
+    preset
+        .render_passes()
+        .flat_map(|(name, pass)| {
+            analyze(common, &pass.shader)
+                .warnings()
+                .into_iter()
+                .map(move |warning| (name.to_string(), warning))
+        })
+        .collect()
+}
+
+/// Logs the complexity warnings of `preset` and, if `strict`, refuses it
+/// when there is any.
+pub fn check_complexity(preset: &Preset, strict: bool) -> Result<(), PresetError> {
+    let warnings = preset_warnings(preset);
+    for (pass, warning) in &warnings {
+        log::warn!("{}: {pass}: {warning}", preset.name);
+    }
+
+    if strict && !warnings.is_empty() {
+        let summary: Vec<_> = warnings
+            .iter()
+            .map(|(pass, warning)| format!("{pass}: {warning}"))
+            .collect();
+        return Err(PresetError::TooHeavy(summary.join("; ")));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Identifier(&'a str),
+    Number(f64),
+    Punctuation(&'a str),
+}
+
+/// Splits GLSL code without comments or directives into tokens.
+fn tokenize(code: &str) -> Vec<Token<'_>> {
+    let bytes = code.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let start = i;
+
+        if byte.is_ascii_whitespace() {
+            i += 1;
+        } else if byte.is_ascii_alphabetic() || byte == b'_' {
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push(Token::Identifier(&code[start..i]));
+        } else if byte.is_ascii_digit()
+            || (byte == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            i = number_end(bytes, i);
+            tokens.push(Token::Number(parse_number(&code[start..i]).unwrap_or(0.0)));
+        } else if let Some(operator) = OPERATORS
+            .iter()
+            .find(|operator| code[i..].starts_with(*operator))
+        {
+            i += operator.len();
+            tokens.push(Token::Punctuation(operator));
+        } else {
+            i += code[i..].chars().next().map_or(1, char::len_utf8);
+            tokens.push(Token::Punctuation(&code[start..i]));
+        }
+    }
+
+    tokens
+}
+
+/// Returns the end of the numeric literal starting at `start`.
+fn number_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    if bytes[i..].starts_with(b"0x") || bytes[i..].starts_with(b"0X") {
+        i += 2;
+        while i < bytes.len() && bytes[i].is_ascii_hexdigit() {
+            i += 1;
+        }
+    } else {
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+            i += 1;
+        }
+        if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+            i += 1;
+            if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+                i += 1;
+            }
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+    }
+    while i < bytes.len() && matches!(bytes[i], b'u' | b'U' | b'f' | b'F') {
+        i += 1;
+    }
+    i
+}
+
+fn parse_number(literal: &str) -> Option<f64> {
+    let literal = literal.trim_end_matches(['u', 'U', 'f', 'F']);
+    match literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok().map(|value| value as f64),
+        None => literal.parse().ok(),
+    }
+}
+
+/// Records the value of an object-like `#define` of a constant
+/// expression.
+fn parse_define(directive: &str, constants: &mut HashMap<String, f64>) {
+    let Some(definition) = directive.trim_start().strip_prefix("define") else {
+        return;
+    };
+    let definition = definition.trim_start();
+    let name_end = definition
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(definition.len());
+    let (name, value) = definition.split_at(name_end);
+    // Function-like macros are not constants
+    if name.is_empty() || value.starts_with('(') {
+        return;
+    }
+
+    let tokens = tokenize(value);
+    if let Some(value) = evaluate(&tokens, constants) {
+        constants.insert(name.to_string(), value);
+    }
+}
+
+/// Records the values of `const` variables initialized with constant
+/// expressions.
+fn collect_constants(tokens: &[Token], constants: &mut HashMap<String, f64>) {
+    for (i, _) in tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| **token == Token::Identifier("const"))
+    {
+        let (Some(Token::Identifier(name)), Some(Token::Punctuation("="))) =
+            (tokens.get(i + 2), tokens.get(i + 3))
+        else {
+            continue;
+        };
+        let Some(end) = tokens[i + 4..]
+            .iter()
+            .position(|token| *token == Token::Punctuation(";"))
+        else {
+            continue;
+        };
+        if let Some(value) = evaluate(&tokens[i + 4..i + 4 + end], constants) {
+            constants.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// Evaluates a constant arithmetic expression.
+fn evaluate(tokens: &[Token], constants: &HashMap<String, f64>) -> Option<f64> {
+    let mut parser = ExpressionParser {
+        tokens,
+        position: 0,
+        constants,
+    };
+    let value = parser.sum()?;
+    (parser.position == tokens.len()).then_some(value)
+}
+
+/// Recursive descent parser of constant expressions.
+struct ExpressionParser<'a, 'b> {
+    tokens: &'b [Token<'a>],
+    position: usize,
+    constants: &'b HashMap<String, f64>,
+}
+
+impl ExpressionParser<'_, '_> {
+    fn next_if(&mut self, punctuation: &str) -> bool {
+        let matched = self.tokens.get(self.position) == Some(&Token::Punctuation(punctuation));
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        loop {
+            if self.next_if("+") {
+                value += self.product()?;
+            } else if self.next_if("-") {
+                value -= self.product()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        loop {
+            if self.next_if("*") {
+                value *= self.factor()?;
+            } else if self.next_if("/") {
+                value /= self.factor()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        if self.next_if("-") {
+            return self.factor().map(|value| -value);
+        }
+        if self.next_if("(") {
+            let value = self.sum()?;
+            return self.next_if(")").then_some(value);
+        }
+
+        let token = *self.tokens.get(self.position)?;
+        self.position += 1;
+        match token {
+            Token::Number(value) => Some(value),
+            // Conversions such as `float(N)`
+            Token::Identifier("int" | "uint" | "float") if self.next_if("(") => {
+                let value = self.sum()?;
+                self.next_if(")").then_some(value)
+            }
+            Token::Identifier(name) => self.constants.get(name).copied(),
+            Token::Punctuation(_) => None,
+        }
+    }
+}
+
+/// Bodies of the functions of a shader, by name. Overloads share a name.
+type Functions = HashMap<String, Vec<(usize, usize)>>;
+
+/// Finds the function definitions at global scope, as token ranges of
+/// their bodies, without the braces.
+fn find_functions(tokens: &[Token]) -> Functions {
+    let mut functions = Functions::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match tokens[i] {
+            Token::Punctuation("{") => depth += 1,
+            Token::Punctuation("}") => depth = depth.saturating_sub(1),
+            Token::Identifier(name) if depth == 0 => {
+                if let Some(close) = tokens
+                    .get(i + 1)
+                    .filter(|token| **token == Token::Punctuation("("))
+                    .and_then(|_| matching(tokens, i + 1))
+                {
+                    if tokens.get(close + 1) == Some(&Token::Punctuation("{")) {
+                        if let Some(end) = matching(tokens, close + 1) {
+                            functions
+                                .entry(name.to_string())
+                                .or_default()
+                                .push((close + 2, end));
+                            i = end + 1;
+                            continue;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    functions
+}
+
+/// Returns the index of the bracket closing the one at `open`.
+fn matching(tokens: &[Token], open: usize) -> Option<usize> {
+    let (opening, closing) = match tokens.get(open)? {
+        Token::Punctuation("(") => ("(", ")"),
+        Token::Punctuation("{") => ("{", "}"),
+        Token::Punctuation("[") => ("[", "]"),
+        _ => return None,
+    };
+
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if *token == Token::Punctuation(opening) {
+            depth += 1;
+        } else if *token == Token::Punctuation(closing) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Number of iterations of a loop, as far as can be told.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LoopBound {
+    Known(f64),
+    Unknown,
+    Unbounded,
+}
+
+impl LoopBound {
+    /// Iterations assumed for the estimates.
+    fn iterations(self) -> f64 {
+        match self {
+            Self::Known(iterations) => iterations,
+            Self::Unknown | Self::Unbounded => 1.0,
+        }
+    }
+}
+
+struct Analyzer<'a> {
+    tokens: &'a [Token<'a>],
+    functions: Functions,
+    constants: HashMap<String, f64>,
+}
+
+impl Analyzer<'_> {
+    /// Returns the cost of a call to `name`, the most expensive overload
+    /// if it is overloaded, or zero if it is not a function of the
+    /// shader.
+    fn function_cost(
+        &self,
+        name: &str,
+        memo: &mut HashMap<String, ShaderComplexity>,
+        visiting: &mut HashSet<String>,
+    ) -> ShaderComplexity {
+        if let Some(cost) = memo.get(name) {
+            return *cost;
+        }
+        // GLSL forbids recursion, but the source may not be valid
+        let Some(bodies) = self.functions.get(name) else {
+            return ShaderComplexity::default();
+        };
+        if !visiting.insert(name.to_string()) {
+            return ShaderComplexity::default();
+        }
+
+        let cost = bodies
+            .iter()
+            .map(|&(start, end)| self.range_cost(start, end, memo, visiting))
+            .fold(ShaderComplexity::default(), |a, b| a.max(&b));
+
+        visiting.remove(name);
+        memo.insert(name.to_string(), cost);
+        cost
+    }
+
+    /// Returns the cost of executing the tokens in `start..end` once.
+    fn range_cost(
+        &self,
+        start: usize,
+        end: usize,
+        memo: &mut HashMap<String, ShaderComplexity>,
+        visiting: &mut HashSet<String>,
+    ) -> ShaderComplexity {
+        let tokens = self.tokens;
+        let mut cost = ShaderComplexity::default();
+        let mut i = start;
+
+        while i < end {
+            let Token::Identifier(name) = tokens[i] else {
+                i += 1;
+                continue;
+            };
+            let header_close = (tokens.get(i + 1) == Some(&Token::Punctuation("(")))
+                .then(|| matching(tokens, i + 1))
+                .flatten()
+                .filter(|&close| close < end);
+
+            match (name, header_close) {
+                ("for", Some(close)) => {
+                    let bound = self.for_bound(&tokens[i + 2..close]);
+                    i = self.add_loop(&mut cost, bound, close + 1, end, memo, visiting);
+                }
+                ("while", Some(close)) => {
+                    let bound = self.condition_bound(&tokens[i + 2..close]);
+                    i = self.add_loop(&mut cost, bound, close + 1, end, memo, visiting);
+                }
+                ("do", _) => {
+                    let body_end = self.statement_end(i + 1, end);
+                    let bound = match (tokens.get(body_end + 1), tokens.get(body_end + 2)) {
+                        (Some(Token::Identifier("while")), Some(Token::Punctuation("("))) => {
+                            match matching(tokens, body_end + 2) {
+                                Some(close) => self.condition_bound(&tokens[body_end + 3..close]),
+                                None => LoopBound::Unknown,
+                            }
+                        }
+                        _ => LoopBound::Unknown,
+                    };
+                    self.add_loop(&mut cost, bound, i + 1, end, memo, visiting);
+                    // Skip the condition, which is evaluated with the body
+                    i = match (tokens.get(body_end + 2), matching(tokens, body_end + 2)) {
+                        (Some(Token::Punctuation("(")), Some(close)) => close + 1,
+                        _ => body_end + 1,
+                    };
+                }
+                (name, Some(_)) => {
+                    if TEXTURE_FUNCTIONS.contains(&name) {
+                        cost.texture_fetches += 1.0;
+                    } else if DERIVATIVE_FUNCTIONS.contains(&name) {
+                        cost.derivatives += 1.0;
+                    } else {
+                        cost.add(&self.function_cost(name, memo, visiting), 1.0);
+                    }
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+
+        cost
+    }
+
+    /// Adds to `cost` the cost of the loop whose body starts at
+    /// `body_start`, and returns the index following the body.
+    fn add_loop(
+        &self,
+        cost: &mut ShaderComplexity,
+        bound: LoopBound,
+        body_start: usize,
+        end: usize,
+        memo: &mut HashMap<String, ShaderComplexity>,
+        visiting: &mut HashSet<String>,
+    ) -> usize {
+        let body_end = self.statement_end(body_start, end);
+        let body = self.range_cost(body_start, body_end.min(end), memo, visiting);
+
+        let iterations = bound.iterations();
+        cost.loop_iterations += iterations;
+        cost.add(&body, iterations);
+        if bound == LoopBound::Unbounded {
+            cost.unbounded_loops += 1;
+        }
+        body_end + 1
+    }
+
+    /// Returns the index of the last token of the statement starting at
+    /// `start`: its closing brace, or its semicolon.
+    fn statement_end(&self, start: usize, end: usize) -> usize {
+        let tokens = self.tokens;
+        if tokens.get(start) == Some(&Token::Punctuation("{")) {
+            return matching(tokens, start).unwrap_or(end);
+        }
+        // A nested loop header contains semicolons of its own
+        if let (Some(Token::Identifier("for" | "while")), Some(Token::Punctuation("("))) =
+            (tokens.get(start), tokens.get(start + 1))
+        {
+            if let Some(close) = matching(tokens, start + 1) {
+                return self.statement_end(close + 1, end);
+            }
+        }
+
+        let mut depth = 0usize;
+        for (i, token) in tokens.iter().enumerate().take(end).skip(start) {
+            match token {
+                Token::Punctuation("(" | "[" | "{") => depth += 1,
+                Token::Punctuation(")" | "]" | "}") => depth = depth.saturating_sub(1),
+                Token::Punctuation(";") if depth == 0 => return i,
+                _ => {}
+            }
+        }
+        end
+    }
+
+    /// Estimates the iterations of a `for` loop from its header.
+    ///
+    /// Besides the usual `for (int i = 0; i < N; i++)`, the loop variable
+    /// may be incremented in the condition, e.g., `for (float i; ++i < N;)`,
+    /// in which case a variable declared without a value starts at zero.
+    fn for_bound(&self, header: &[Token]) -> LoopBound {
+        let parts: Vec<_> = header
+            .split(|token| *token == Token::Punctuation(";"))
+            .collect();
+        let [init, condition, step] = parts[..] else {
+            return LoopBound::Unknown;
+        };
+        if condition.is_empty() {
+            return LoopBound::Unbounded;
+        }
+
+        let Some(comparison) = self.comparison(condition) else {
+            return self.condition_bound(condition);
+        };
+        let variable = comparison.variable;
+        let (Some(start), Some(step_increment)) = (
+            self.initial_value(init, variable),
+            self.increment(step, variable),
+        ) else {
+            return LoopBound::Unknown;
+        };
+
+        let increment = step_increment + comparison.pre_increment + comparison.post_increment;
+        if increment == 0.0 {
+            return LoopBound::Unknown;
+        }
+        // The condition compares the value after a pre-increment
+        let start = start + comparison.pre_increment;
+
+        let steps = (comparison.limit - start) / increment;
+        let iterations = match comparison.operator {
+            "<" | ">" | "!=" => steps.ceil(),
+            "<=" | ">=" => steps.floor() + 1.0,
+            _ => return LoopBound::Unknown,
+        };
+        // A loop moving away from its limit only ends on overflow, and is
+        // rather a mistake in the estimate
+        let toward_limit = match comparison.operator {
+            "<" | "<=" => increment > 0.0,
+            ">" | ">=" => increment < 0.0,
+            _ => steps >= 0.0,
+        };
+        if toward_limit {
+            LoopBound::Known(iterations.max(0.0))
+        } else {
+            LoopBound::Unknown
+        }
+    }
+
+    /// Returns [`LoopBound::Unbounded`] if `condition` is constantly
+    /// true, e.g., `while (true)`.
+    fn condition_bound(&self, condition: &[Token]) -> LoopBound {
+        let always_true = match condition {
+            [] | [Token::Identifier("true")] => true,
+            _ => evaluate(condition, &self.constants).is_some_and(|value| value != 0.0),
+        };
+        if always_true {
+            LoopBound::Unbounded
+        } else {
+            LoopBound::Unknown
+        }
+    }
+
+    /// Returns the first comparison of a variable to a constant in
+    /// `condition`, normalized so that the variable is on the left.
+    fn comparison<'t>(&self, condition: &[Token<'t>]) -> Option<Comparison<'t>> {
+        let first = condition
+            .split(|token| matches!(token, Token::Punctuation("&&" | "||")))
+            .next()?;
+        let position = first.iter().position(|token| {
+            matches!(token, Token::Punctuation("<" | "<=" | ">" | ">=" | "!="))
+        })?;
+        let Token::Punctuation(operator) = first[position] else {
+            return None;
+        };
+        let (left, right) = (&first[..position], &first[position + 1..]);
+
+        if let Some(comparison) = self.variable_operand(left, right, operator) {
+            return Some(comparison);
+        }
+        let flipped = match operator {
+            "<" => ">",
+            "<=" => ">=",
+            ">" => "<",
+            ">=" => "<=",
+            other => other,
+        };
+        self.variable_operand(right, left, flipped)
+    }
+
+    /// Returns the comparison of `operand`, a variable that may be
+    /// incremented or decremented, to the constant expression `limit`.
+    fn variable_operand<'t>(
+        &self,
+        operand: &[Token<'t>],
+        limit: &[Token],
+        operator: &'t str,
+    ) -> Option<Comparison<'t>> {
+        let (variable, pre_increment, post_increment) = match *operand {
+            [Token::Identifier(variable)] => (variable, 0.0, 0.0),
+            [Token::Punctuation("++"), Token::Identifier(variable)] => (variable, 1.0, 0.0),
+            [Token::Punctuation("--"), Token::Identifier(variable)] => (variable, -1.0, 0.0),
+            [Token::Identifier(variable), Token::Punctuation("++")] => (variable, 0.0, 1.0),
+            [Token::Identifier(variable), Token::Punctuation("--")] => (variable, 0.0, -1.0),
+            _ => return None,
+        };
+        Some(Comparison {
+            variable,
+            operator,
+            limit: evaluate(limit, &self.constants)?,
+            pre_increment,
+            post_increment,
+        })
+    }
+
+    /// Returns the initial value of `variable` from the initialization
+    /// of a `for` header, zero if it is declared there without a value.
+    fn initial_value(&self, init: &[Token], variable: &str) -> Option<f64> {
+        let var = Token::Identifier(variable);
+        init.split(|token| *token == Token::Punctuation(","))
+            .find_map(|declaration| {
+                let position = declaration.iter().position(|token| *token == var)?;
+                match declaration.get(position + 1) {
+                    Some(Token::Punctuation("=")) => {
+                        Some(evaluate(&declaration[position + 2..], &self.constants))
+                    }
+                    None if position > 0 || declaration.len() == 1 => Some(Some(0.0)),
+                    _ => None,
+                }
+            })
+            .flatten()
+    }
+
+    /// Returns the change of `variable` per iteration from the step
+    /// expressions of a `for` header, zero if none changes it.
+    fn increment(&self, step: &[Token], variable: &str) -> Option<f64> {
+        let var = Token::Identifier(variable);
+        let mut total = 0.0;
+        for expression in step.split(|token| *token == Token::Punctuation(",")) {
+            if !expression.contains(&var) {
+                continue;
+            }
+            total += match expression {
+                [v, Token::Punctuation("++")] | [Token::Punctuation("++"), v] if *v == var => 1.0,
+                [v, Token::Punctuation("--")] | [Token::Punctuation("--"), v] if *v == var => -1.0,
+                [v, Token::Punctuation("+="), rest @ ..] if *v == var => {
+                    evaluate(rest, &self.constants)?
+                }
+                [v, Token::Punctuation("-="), rest @ ..] if *v == var => {
+                    -evaluate(rest, &self.constants)?
+                }
+                [v, Token::Punctuation("="), w, Token::Punctuation("+"), rest @ ..]
+                    if *v == var && *w == var =>
+                {
+                    evaluate(rest, &self.constants)?
+                }
+                [v, Token::Punctuation("="), w, Token::Punctuation("-"), rest @ ..]
+                    if *v == var && *w == var =>
+                {
+                    -evaluate(rest, &self.constants)?
+                }
+                _ => return None,
+            };
+        }
+        Some(total)
+    }
+}
+
+/// Comparison of a loop variable to its limit in a loop condition.
+struct Comparison<'a> {
+    variable: &'a str,
+    operator: &'a str,
+    limit: f64,
+
+    /// Change of the variable before it is compared.
+    pre_increment: f64,
+
+    /// Change of the variable after it is compared.
+    post_increment: f64,
+}
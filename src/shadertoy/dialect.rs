@@ -0,0 +1,124 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Detection of code outside the ShaderToy GLSL dialect.
+//!
+//! ShaderToy shaders are written in GLSL ES 3.00, as supported by
+//! WebGL 2. Shaders written for, or tested only in, ShaderBG may use
+//! words reserved in that dialect as identifiers, or functions of
+//! WebGL 1 that neither ShaderToy nor desktop GLSL provide. Other
+//! incompatibilities, such as implicit conversions, are left to a GLSL
+//! ES 3.00 validator.
+
+use regex::Regex;
+
+use super::glsl_utils::strip_comments_with_line_origins;
+
+/// Words reserved for future use in GLSL ES 3.00, which are errors when
+/// used as identifiers.
+#[rustfmt::skip]
+const RESERVED_WORDS_3_0_ES: [&str; 109] = [
+    "attribute", "varying", "coherent", "volatile", "restrict", "readonly", "writeonly",
+    "resource", "atomic_uint", "noperspective", "patch", "sample", "subroutine", "common",
+    "partition", "active", "asm", "class", "union", "enum", "typedef", "template", "this",
+    "goto", "inline", "noinline", "public", "static", "extern", "external", "interface",
+    "long", "short", "double", "half", "fixed", "unsigned", "superp", "input", "output",
+    "hvec2", "hvec3", "hvec4", "dvec2", "dvec3", "dvec4", "fvec2", "fvec3", "fvec4",
+    "sampler3DRect", "filter",
+    "image1D", "image2D", "image3D", "imageCube",
+    "iimage1D", "iimage2D", "iimage3D", "iimageCube",
+    "uimage1D", "uimage2D", "uimage3D", "uimageCube",
+    "image1DArray", "image2DArray", "iimage1DArray", "iimage2DArray",
+    "uimage1DArray", "uimage2DArray", "imageBuffer", "iimageBuffer", "uimageBuffer",
+    "sampler1D", "sampler1DShadow", "sampler1DArray", "sampler1DArrayShadow",
+    "isampler1D", "isampler1DArray", "usampler1D", "usampler1DArray",
+    "sampler2DRect", "sampler2DRectShadow", "isampler2DRect", "usampler2DRect",
+    "samplerBuffer", "isamplerBuffer", "usamplerBuffer",
+    "sampler2DMS", "isampler2DMS", "usampler2DMS",
+    "sampler2DMSArray", "isampler2DMSArray", "usampler2DMSArray",
+    "sizeof", "cast", "namespace", "using",
+    "dmat2", "dmat3", "dmat4", "dmat2x2", "dmat2x3", "dmat2x4",
+    "dmat3x2", "dmat3x3", "dmat3x4", "dmat4x2", "dmat4x3", "dmat4x4",
+];
+
+/// Functions and variables of WebGL 1 (GLSL ES 1.00 and its extensions)
+/// along with their GLSL ES 3.00 replacements.
+#[rustfmt::skip]
+const WEBGL1_BUILTINS: [(&str, &str); 14] = [
+    ("texture2D", "'texture'"),
+    ("texture2DProj", "'textureProj'"),
+    ("texture2DLod", "'textureLod'"),
+    ("texture2DProjLod", "'textureProjLod'"),
+    ("textureCube", "'texture'"),
+    ("textureCubeLod", "'textureLod'"),
+    ("texture2DLodEXT", "'textureLod'"),
+    ("texture2DProjLodEXT", "'textureProjLod'"),
+    ("textureCubeLodEXT", "'textureLod'"),
+    ("texture2DGradEXT", "'textureGrad'"),
+    ("texture2DProjGradEXT", "'textureProjGrad'"),
+    ("textureCubeGradEXT", "'textureGrad'"),
+    ("gl_FragColor", "the 'fragColor' output of mainImage"),
+    ("gl_FragData", "the 'fragColor' output of mainImage"),
+];
+
+/// Preprocessor directives whose arguments are not GLSL code.
+const NON_CODE_DIRECTIVES: [&str; 4] = ["#extension", "#pragma", "#version", "#error"];
+
+/// Code of a pass that ShaderToy would not compile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialectIssue {
+    /// Line (1-based) of the pass code.
+    pub line: usize,
+
+    pub message: String,
+}
+
+/// Returns the reserved words and WebGL 1 built-ins used in `source`,
+/// in order of appearance.
+///
+/// WebGL 1 built-ins defined as macros, e.g., `#define texture2D
+/// texture`, are not reported.
+pub fn dialect_issues(source: &str) -> Vec<DialectIssue> {
+    let (code, line_origins) = strip_comments_with_line_origins(source);
+    let identifier = Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]*\b").unwrap();
+    let macro_name = Regex::new(r"(?m)^\s*#\s*define\s+([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+
+    let macros: Vec<_> = macro_name
+        .captures_iter(&code)
+        .map(|caps| caps.get(1).unwrap().as_str())
+        .collect();
+
+    let mut issues = Vec::new();
+    for (index, line) in code.split('\n').enumerate() {
+        let code_line = line.trim_start();
+        if NON_CODE_DIRECTIVES
+            .iter()
+            .any(|name| code_line.starts_with(name))
+        {
+            continue;
+        }
+        let line = line_origins.get(index).copied().unwrap_or(index + 1);
+
+        for word in identifier.find_iter(code_line).map(|word| word.as_str()) {
+            if RESERVED_WORDS_3_0_ES.contains(&word) {
+                issues.push(DialectIssue {
+                    line,
+                    message: format!("'{word}' is a reserved word in GLSL ES 3.00"),
+                });
+            } else if let Some((_, replacement)) = WEBGL1_BUILTINS
+                .iter()
+                .find(|(name, _)| *name == word && !macros.contains(name))
+            {
+                issues.push(DialectIssue {
+                    line,
+                    message: format!(
+                        "'{word}' is only available in WebGL 1, use {replacement} instead"
+                    ),
+                });
+            }
+        }
+    }
+    issues
+}
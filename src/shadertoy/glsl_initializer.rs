@@ -12,11 +12,13 @@
 //! This pass runs after preprocessing and before shader compilation.
 
 use num_traits::Saturating;
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use crate::renderer::shader::ShaderError;
 
-use super::{glsl_depth_tracker::GlslDepthTracker, glsl_preprocessor};
+use super::{
+    glsl_depth_tracker::GlslDepthTracker, glsl_preprocessor, glsl_utils::fit_line_origins,
+};
 
 struct StructMember {
     type_name: String,
@@ -37,21 +39,32 @@ struct GlslInitializer<'a> {
     struct_defs: HashMap<String, StructDefinition>,
 }
 
-/// Returns the GLSL ES code with all uninitialized variables initialized.
-pub fn initialize_uninitialized_variables(source: &str) -> Result<String, ShaderError> {
-    let mut source = glsl_preprocessor::preprocess(source)?;
+/// Returns the GLSL ES code with all uninitialized variables initialized,
+/// and the line of `source` (1-based) from which each of its lines
+/// originates.
+///
+/// Files of `#include` directives are resolved relative to
+/// `include_dir`.
+pub fn initialize_uninitialized_variables(
+    source: &str,
+    include_dir: Option<&Path>,
+) -> Result<(String, Vec<usize>), ShaderError> {
+    let (mut source, mut line_origins) =
+        glsl_preprocessor::preprocess_with_line_origins(source, include_dir)?;
 
     let modifications = GlslInitializer::new(&source).modifications();
     for (start, end, replacement) in modifications.into_iter().rev() {
         source.replace_range(start..end, &replacement);
     }
+    fit_line_origins(&mut line_origins, &source);
 
     // Remove empty lines
-    Ok(source
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .collect::<Vec<_>>()
-        .join("\n"))
+    let (lines, line_origins): (Vec<_>, Vec<_>) = source
+        .split('\n')
+        .zip(line_origins)
+        .filter(|(line, _)| !line.trim().is_empty())
+        .unzip();
+    Ok((lines.join("\n"), line_origins))
 }
 
 impl<'a> GlslInitializer<'a> {
@@ -7,13 +7,40 @@
 //!
 //! Performs the main source transformation required to convert
 //! ShaderToy GLSL ES shaders into desktop OpenGL–compatible GLSL.
+//!
+//! Besides the standard directives, `#include "file.glsl"` inserts the
+//! preprocessed contents of a file, resolved relative to the directory
+//! of the preset, or of the including file for nested includes.
+//!
+//! The predefined `__LINE__` macro expands to the line of the code
+//! being preprocessed (of the included file, within an `#include`), and
+//! `__FILE__` to source string number 0.
 
 use regex::Regex;
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::renderer::shader::ShaderError;
 
-use super::{glsl_preprocessor::ShaderError::ShaderPreprocess, glsl_utils::strip_comments};
+use super::{
+    glsl_preprocessor::ShaderError::ShaderPreprocess,
+    glsl_utils::{fit_line_origins, strip_comments_with_line_origins},
+};
+
+/// Name of the variable arguments of a variadic macro in its body.
+const VA_ARGS: &str = "__VA_ARGS__";
+
+/// Predefined macro expanding to the current source line.
+const LINE_MACRO: &str = "__LINE__";
+
+/// Predefined macro expanding to the current source string number.
+const FILE_MACRO: &str = "__FILE__";
+
+/// Maximum nesting of `#include` directives.
+const MAX_INCLUDE_DEPTH: usize = 16;
 
 /// Represents the state of a conditional compilation block (#if...#endif).
 enum BranchState {
@@ -43,6 +70,11 @@ struct MacroDef {
     /// `None` if it's a simple object-like macro.
     params: Option<Vec<String>>,
 
+    /// Whether the function-like macro takes a variable number of
+    /// arguments after its named parameters (`...`), which replace
+    /// `__VA_ARGS__` in the body.
+    variadic: bool,
+
     /// The string that will replace the macro invocation.
     body: String,
 }
@@ -57,14 +89,37 @@ struct GlslPreprocessor {
 
     /// Current line number for error reporting.
     line_number: usize,
+
+    /// Line of the source from which the current line originates, to
+    /// which `__LINE__` expands.
+    source_line: usize,
+
+    /// Files being included, outermost first, for detecting cycles.
+    include_stack: Vec<PathBuf>,
 }
 
 /// Returns the GLSL code with preprocessor directives evaluated and macros expanded.
 /// This is NOT a full-fledged preprocessor - directives such as `pragma`, `extension`,
-/// `version`, and `line` are stripped. Predefined macros are not expanded.
+/// `version`, and `line` are stripped. Of the predefined macros, only `__LINE__` and
+/// `__FILE__` are expanded, in conditionals and included files. Elsewhere, they are
+/// left for the renderer, which resolves them to the line in the preset code.
+#[cfg(test)]
 pub fn preprocess(source: &str) -> Result<String, ShaderError> {
+    preprocess_with_line_origins(source, None).map(|(output, _)| output)
+}
+
+/// Preprocesses GLSL code like [`preprocess`], also returning the line of
+/// `source` (1-based) from which each line of the result originates.
+///
+/// Files of `#include` directives are resolved relative to
+/// `include_dir`, without which including files is an error. Lines of
+/// included files originate from the line of their directive.
+pub fn preprocess_with_line_origins(
+    source: &str,
+    include_dir: Option<&Path>,
+) -> Result<(String, Vec<usize>), ShaderError> {
     let mut preprocessor = GlslPreprocessor::new();
-    preprocessor.run(source)
+    preprocessor.run(source, include_dir)
 }
 
 impl GlslPreprocessor {
@@ -73,9 +128,16 @@ impl GlslPreprocessor {
             defines: HashMap::new(),
             if_stack: Vec::new(),
             line_number: 0,
+            source_line: 0,
+            include_stack: Vec::new(),
         }
     }
 
+    /// Checks if a macro is defined, either predefined or by `#define`.
+    fn is_defined(&self, name: &str) -> bool {
+        name == LINE_MACRO || name == FILE_MACRO || self.defines.contains_key(name)
+    }
+
     /// Checks if the current line of code is inside an active conditional block.
     fn is_active(&self) -> bool {
         self.if_stack
@@ -83,10 +145,26 @@ impl GlslPreprocessor {
             .is_none_or(|state| matches!(state, BranchState::Active))
     }
 
-    /// Preprocesses a GLSL source string.
-    fn run(&mut self, source: &str) -> Result<String, ShaderError> {
+    /// Preprocesses a GLSL source string, returning the result and the
+    /// source line of each of its lines.
+    fn run(
+        &mut self,
+        source: &str,
+        include_dir: Option<&Path>,
+    ) -> Result<(String, Vec<usize>), ShaderError> {
         self.defines.clear();
         self.if_stack.clear();
+        self.include_stack.clear();
+        self.process(source, include_dir)
+    }
+
+    /// Preprocesses a source string with the current macros and
+    /// conditional blocks, resolving includes relative to `include_dir`.
+    fn process(
+        &mut self,
+        source: &str,
+        include_dir: Option<&Path>,
+    ) -> Result<(String, Vec<usize>), ShaderError> {
         self.line_number = 0;
 
         let source = source
@@ -95,25 +173,31 @@ impl GlslPreprocessor {
             .replace("\r", "\n"); // Remaining CR to LF
 
         // Splice lines
-        let source = source.replace("\\\n", "");
+        let (source, spliced_line_origins) = splice_lines(&source);
 
         // Strip all comments
-        let source_no_comments = strip_comments(&source);
+        let (source_no_comments, stripped_line_origins) = strip_comments_with_line_origins(&source);
 
         let mut output = String::new();
+        let mut line_origins = Vec::new();
         let mut active_buffer = String::new();
+        let mut active_buffer_line_origins = Vec::new();
 
-        for line in source_no_comments.lines() {
+        for (line, stripped_line) in source_no_comments.lines().zip(stripped_line_origins) {
             self.line_number += 1;
+            self.source_line = spliced_line_origins[stripped_line - 1];
             let trimmed_line = line.trim();
 
             // Handle preprocessor directives
             if trimmed_line.starts_with('#') {
                 // Expand buffer before processing any directive
                 if self.is_active() && !active_buffer.is_empty() {
-                    let expanded = self.expand_macros(&active_buffer);
-                    output.push_str(&expanded);
-                    active_buffer.clear();
+                    self.flush_active_buffer(
+                        &mut active_buffer,
+                        &mut active_buffer_line_origins,
+                        &mut output,
+                        &mut line_origins,
+                    );
                 }
                 if let Some(directive) = get_directive_name(trimmed_line) {
                     match directive {
@@ -138,7 +222,17 @@ impl GlslPreprocessor {
                                 return Err(self.handle_error(trimmed_line));
                             }
                         }
-                        // Ignore these directives
+                        "include" => {
+                            if self.is_active() {
+                                let included = self.handle_include(trimmed_line, include_dir)?;
+                                self.source_line = spliced_line_origins[stripped_line - 1];
+                                line_origins.extend(included.lines().map(|_| self.source_line));
+                                output.push_str(&included);
+                            }
+                        }
+                        // Ignore these directives. `#version` and `#line` are
+                        // emitted by the renderer, the latter mapping lines
+                        // back to the preset code
                         "pragma" | "extension" | "version" | "line" => {}
                         _ => {
                             return Err(ShaderPreprocess(
@@ -154,16 +248,47 @@ impl GlslPreprocessor {
                 // Accumulate the line (with a newline) for later expansion
                 active_buffer.push_str(line);
                 active_buffer.push('\n');
+                active_buffer_line_origins.push(spliced_line_origins[stripped_line - 1]);
             }
         }
 
         // Expand any remaining active buffer
         if !active_buffer.is_empty() {
-            let expanded = self.expand_macros(&active_buffer);
-            output.push_str(&expanded);
+            self.flush_active_buffer(
+                &mut active_buffer,
+                &mut active_buffer_line_origins,
+                &mut output,
+                &mut line_origins,
+            );
         }
 
-        Ok(output)
+        Ok((output, line_origins))
+    }
+
+    /// Expands the macros of the accumulated code lines and appends the
+    /// result to `output`.
+    fn flush_active_buffer(
+        &self,
+        active_buffer: &mut String,
+        active_buffer_line_origins: &mut Vec<usize>,
+        output: &mut String,
+        line_origins: &mut Vec<usize>,
+    ) {
+        let expanded = self.expand_macros(active_buffer);
+
+        // Macro invocations spanning lines are expanded into a single line
+        let expanded_lines = expanded.strip_suffix('\n').unwrap_or(&expanded);
+        fit_line_origins(active_buffer_line_origins, expanded_lines);
+        // Lines of included files have no location in the preset code
+        let expanded = if self.include_stack.is_empty() {
+            expanded
+        } else {
+            expand_line_macros(&expanded, active_buffer_line_origins)
+        };
+
+        output.push_str(&expanded);
+        line_origins.append(active_buffer_line_origins);
+        active_buffer.clear();
     }
 
     /// Parses and stores a macro definition.
@@ -178,7 +303,7 @@ impl GlslPreprocessor {
         if let Some(caps) = func_re.captures(line) {
             let name = caps.get(1).unwrap().as_str().to_string();
             let params_str = caps.get(2).unwrap().as_str();
-            let params: Vec<String> = if params_str.is_empty() {
+            let mut params: Vec<String> = if params_str.trim().is_empty() {
                 vec![]
             } else {
                 params_str
@@ -186,18 +311,30 @@ impl GlslPreprocessor {
                     .map(|p| p.trim().to_string())
                     .collect()
             };
+            let variadic = params.last().is_some_and(|p| p == "...");
+            if variadic {
+                params.pop();
+            }
             let body = caps.get(3).unwrap().as_str().trim().to_string();
             self.defines.insert(
                 name,
                 MacroDef {
                     params: Some(params),
+                    variadic,
                     body,
                 },
             );
         } else if let Some(caps) = obj_re.captures(line) {
             let name = caps.get(1).unwrap().as_str().to_string();
             let body = caps.get(2).unwrap().as_str().trim().to_string();
-            self.defines.insert(name, MacroDef { params: None, body });
+            self.defines.insert(
+                name,
+                MacroDef {
+                    params: None,
+                    variadic: false,
+                    body,
+                },
+            );
         }
     }
 
@@ -216,7 +353,7 @@ impl GlslPreprocessor {
         let parts: Vec<&str> = after_hash.split_whitespace().collect();
         if parts.len() >= 2 && parts[0] == "ifdef" {
             if self.is_active() {
-                if self.defines.contains_key(parts[1]) {
+                if self.is_defined(parts[1]) {
                     self.if_stack.push(BranchState::Active);
                 } else {
                     self.if_stack.push(BranchState::Searching);
@@ -234,7 +371,7 @@ impl GlslPreprocessor {
         let parts: Vec<&str> = after_hash.split_whitespace().collect();
         if parts.len() >= 2 && parts[0] == "ifndef" {
             if self.is_active() {
-                if !self.defines.contains_key(parts[1]) {
+                if !self.is_defined(parts[1]) {
                     self.if_stack.push(BranchState::Active);
                 } else {
                     self.if_stack.push(BranchState::Searching);
@@ -320,6 +457,63 @@ impl GlslPreprocessor {
         ShaderPreprocess(message, self.line_number)
     }
 
+    /// Handles #include directive, returning the preprocessed contents
+    /// of the included file.
+    ///
+    /// Macros defined by the file remain defined after the directive.
+    fn handle_include(
+        &mut self,
+        line: &str,
+        include_dir: Option<&Path>,
+    ) -> Result<String, ShaderError> {
+        let line_number = self.line_number;
+        let error = |message: String| ShaderPreprocess(message, line_number);
+
+        let file = included_file(line).ok_or_else(|| {
+            let after_hash = line[1..].trim_start();
+            let argument = after_hash.strip_prefix("include").unwrap_or("").trim();
+            error(format!("Invalid #include argument ({argument})"))
+        })?;
+
+        let Some(include_dir) = include_dir else {
+            return Err(error(format!(
+                "Cannot include {file} in a shader without a preset file"
+            )));
+        };
+        let path = include_dir.join(file);
+        let path = fs::canonicalize(&path).unwrap_or(path);
+
+        if self.include_stack.contains(&path) {
+            return Err(error(format!("Recursive #include of {file}")));
+        }
+        if self.include_stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(error(format!(
+                "Too many nested #include directives (more than {MAX_INCLUDE_DEPTH})"
+            )));
+        }
+        let source = fs::read_to_string(&path)
+            .map_err(|err| error(format!("Cannot include {}: {err}", path.display())))?;
+
+        // Errors in the included file are reported at the directive
+        self.include_stack.push(path.clone());
+        let result = self
+            .process(&source, path.parent())
+            .map_err(|err| match err {
+                ShaderPreprocess(message, line) => {
+                    error(format!("{message} (in {file}, line {line})"))
+                }
+                err => err,
+            });
+        self.include_stack.pop();
+        self.line_number = line_number;
+
+        let (mut output, _) = result?;
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
     /// Evaluates a preprocessor conditional expression.
     fn evaluate_if_expr(&self, expr: &str) -> bool {
         // First, replace all `defined(MACRO)` calls with "1" or "0"
@@ -334,7 +528,7 @@ impl GlslPreprocessor {
                 } else {
                     caps.get(2).unwrap().as_str()
                 };
-                if self.defines.contains_key(name) {
+                if self.is_defined(name) {
                     "1"
                 } else {
                     "0"
@@ -344,6 +538,7 @@ impl GlslPreprocessor {
 
         // Expand any macros in the condition
         let expanded_expr = self.expand_macros(&replaced_expr);
+        let expanded_expr = expand_line_macros(&expanded_expr, &[self.source_line]);
         let expr_no_ws = expanded_expr.replace(char::is_whitespace, "");
 
         // Tokenize the expression
@@ -809,16 +1004,24 @@ impl GlslPreprocessor {
                     if is_start_boundary && is_end_boundary {
                         if let Some(params) = &def.params {
                             // It's a function-like macro, try to parse its arguments
-                            if let Some((args_end, args)) =
-                                self.parse_macro_args(&current_line, end_index, params.len())
-                            {
-                                let expanded = self.replace_params(&def.body, params, &args);
+                            if let Some((args_end, args)) = self.parse_macro_args(
+                                &current_line,
+                                end_index,
+                                params.len(),
+                                def.variadic,
+                            ) {
+                                let expanded = if def.variadic {
+                                    self.replace_variadic_params(&def.body, params, args)
+                                } else {
+                                    self.replace_params(&def.body, params, &args)
+                                };
                                 // This is a valid, earlier expansion. Store it
                                 earliest_expansion = Some((start_index, args_end, expanded));
                             }
                         } else {
                             // It's an object-like macro. This is a valid, earlier expansion. Store it
-                            earliest_expansion = Some((start_index, end_index, def.body.clone()));
+                            earliest_expansion =
+                                Some((start_index, end_index, paste_tokens(&def.body)));
                         }
                     }
                 }
@@ -838,11 +1041,15 @@ impl GlslPreprocessor {
     }
 
     /// Parses the arguments of a function-like macro invocation.
+    ///
+    /// A variadic macro accepts any number of arguments after its
+    /// `arg_count` named ones.
     fn parse_macro_args(
         &self,
         line: &str,
         start_offset: usize,
         arg_count: usize,
+        variadic: bool,
     ) -> Option<(usize, Vec<String>)> {
         let mut chars = line[start_offset..].char_indices().peekable();
 
@@ -865,7 +1072,7 @@ impl GlslPreprocessor {
         let mut end_offset = 0;
 
         // Handle case of function with no arguments e.g. `foo()`
-        if arg_count == 0 {
+        if arg_count == 0 && !variadic {
             let mut final_char_i = 0;
             for (i, c) in chars {
                 final_char_i = i;
@@ -895,56 +1102,169 @@ impl GlslPreprocessor {
                     }
                 }
                 '(' => paren_level += 1,
-                ',' => {
-                    if paren_level == 1 {
-                        args.push(current_arg.trim().to_string());
-                        current_arg.clear();
-                        continue;
-                    }
+                ',' if paren_level == 1 => {
+                    args.push(current_arg.trim().to_string());
+                    current_arg.clear();
+                    continue;
                 }
                 _ => {}
             }
             current_arg.push(c);
         }
 
-        if paren_level == 0 && args.len() == arg_count {
+        if paren_level == 0 && (args.len() == arg_count || (variadic && args.len() > arg_count)) {
             Some((end_offset, args))
         } else {
             None
         }
     }
 
+    /// Replaces the parameters of a variadic macro body with the
+    /// arguments of an invocation, `__VA_ARGS__` standing for the
+    /// arguments after the named ones, separated by commas.
+    fn replace_variadic_params(
+        &self,
+        body: &str,
+        params: &[String],
+        mut args: Vec<String>,
+    ) -> String {
+        let va_args = args.split_off(params.len().min(args.len())).join(", ");
+
+        // `, ## __VA_ARGS__` drops the comma if there are no variable
+        // arguments, as in GCC
+        let body = if va_args.is_empty() {
+            let re = Regex::new(&format!(r",\s*##\s*\b{VA_ARGS}\b")).unwrap();
+            re.replace_all(body, "").into_owned()
+        } else {
+            body.to_string()
+        };
+
+        let mut params = params.to_vec();
+        params.push(VA_ARGS.to_string());
+        args.push(va_args);
+        self.replace_params(&body, &params, &args)
+    }
+
+    /// Replaces the parameters of a function-like macro body with the
+    /// arguments of an invocation, applying the stringizing (`#param`)
+    /// and token-pasting (`a ## b`) operators.
     fn replace_params(&self, body: &str, params: &[String], args: &[String]) -> String {
         if params.is_empty() {
-            return body.to_string();
+            return paste_tokens(body);
         }
 
         // Prioritize longer matches
         let mut sorted_params = params.to_vec();
         sorted_params.sort_by_key(|b| std::cmp::Reverse(b.len()));
 
-        // Match any parameter as whole word
-        let pattern_parts: Vec<String> = sorted_params
-            .iter()
-            .map(|p| format!(r"\b{}\b", regex::escape(p)))
-            .collect();
-        let pattern = pattern_parts.join("|");
+        // Match `##` (kept for pasting), a stringized parameter, or any
+        // parameter as whole word
+        let alternatives: Vec<String> = sorted_params.iter().map(|p| regex::escape(p)).collect();
+        let alternatives = alternatives.join("|");
+        let pattern = format!(r"##|#\s*\b({alternatives})\b|\b({alternatives})\b");
 
         // Replace all parameters
-        if let Ok(re) = Regex::new(&pattern) {
-            re.replace_all(body, |caps: &regex::Captures| {
-                let matched = caps.get(0).unwrap().as_str();
-                params
-                    .iter()
-                    .position(|p| p == matched)
-                    .map(|idx| args[idx].clone())
-                    .unwrap_or_else(|| matched.to_string())
+        let Ok(re) = Regex::new(&pattern) else {
+            return body.to_string(); // Fallback if regex fails
+        };
+        let arg = |name: &str| {
+            params
+                .iter()
+                .position(|p| p == name)
+                .map(|idx| args[idx].clone())
+        };
+        let replaced = re.replace_all(body, |caps: &regex::Captures| {
+            let matched = caps.get(0).unwrap().as_str();
+            if let Some(param) = caps.get(1) {
+                arg(param.as_str()).map_or_else(|| matched.to_string(), |arg| stringize(&arg))
+            } else if let Some(param) = caps.get(2) {
+                arg(param.as_str()).unwrap_or_else(|| matched.to_string())
+            } else {
+                matched.to_string()
+            }
+        });
+        paste_tokens(&replaced)
+    }
+}
+
+/// Applies the token-pasting operator, joining the tokens around each
+/// `##` into a single token.
+fn paste_tokens(body: &str) -> String {
+    if !body.contains("##") {
+        return body.to_string();
+    }
+    let re = Regex::new(r"\s*##\s*").unwrap();
+    re.replace_all(body, "").to_string()
+}
+
+/// Returns a macro argument as a string literal, with whitespace
+/// between its tokens collapsed into single spaces.
+fn stringize(arg: &str) -> String {
+    let text = arg.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Expands `__LINE__` in each line of `code` to the line from which it
+/// originates, and `__FILE__` to source string number 0.
+fn expand_line_macros(code: &str, line_origins: &[usize]) -> String {
+    if !code.contains(LINE_MACRO) && !code.contains(FILE_MACRO) {
+        return code.to_string();
+    }
+
+    let line_macros = Regex::new(r"\b(__LINE__|__FILE__)\b").unwrap();
+    code.split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            let line_number = line_origins.get(index).copied().unwrap_or_default();
+            line_macros.replace_all(line, |caps: &regex::Captures| {
+                if &caps[1] == LINE_MACRO {
+                    line_number.to_string()
+                } else {
+                    "0".to_string()
+                }
             })
-            .to_string()
-        } else {
-            body.to_string() // Fallback if regex fails
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Joins lines ending with a backslash with the next line, returning
+/// the result and the line (1-based) at which each of its lines begins.
+fn splice_lines(source: &str) -> (String, Vec<usize>) {
+    let mut spliced = String::with_capacity(source.len());
+    let mut line_origins = vec![1];
+    let mut previous_line: Option<&str> = None;
+
+    for (index, line) in source.split('\n').enumerate() {
+        if let Some(previous_line) = previous_line {
+            if previous_line.ends_with('\\') {
+                spliced.pop();
+            } else {
+                spliced.push('\n');
+                line_origins.push(index + 1);
+            }
         }
+        spliced.push_str(line);
+        previous_line = Some(line);
     }
+
+    (spliced, line_origins)
+}
+
+/// Returns the file of an `#include "file"` directive, or `None` if
+/// `line` is not a valid `#include` directive.
+pub fn included_file(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if !line.starts_with('#') || get_directive_name(line) != Some("include") {
+        return None;
+    }
+    let after_hash = line[1..].trim_start();
+    after_hash
+        .strip_prefix("include")?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+        .filter(|file| !file.is_empty())
 }
 
 /// Extracts the directive name, assuming the line starts with #
@@ -31,12 +31,25 @@ pub fn replace_in_preprocessor_conditionals(code: &str, find: &str, replace: &st
 /// Strips all GLSL comments (`//` and `/* ... */`) from a source string.
 /// Per the GLSL spec, each comment is replaced by a single space.
 pub fn strip_comments(source: &str) -> Cow<'_, str> {
+    strip_comments_with_line_origins(source).0
+}
+
+/// Strips all GLSL comments like [`strip_comments`], also returning the
+/// line of `source` (1-based) at which each line of the result begins.
+///
+/// Lines spanned by a block comment are joined into a single line,
+/// which begins at the line of its first token.
+pub fn strip_comments_with_line_origins(source: &str) -> (Cow<'_, str>, Vec<usize>) {
     if !source.contains("//") && !source.contains("/*") {
-        return Cow::Borrowed(source);
+        let line_origins = (1..=source.split('\n').count()).collect();
+        return (Cow::Borrowed(source), line_origins);
     }
 
     let mut output = String::with_capacity(source.len());
     let mut chars = source.chars().peekable();
+    let mut line = 1;
+    let mut line_origins = vec![1];
+    let mut line_has_token = false;
 
     #[derive(Clone, Copy)]
     enum State {
@@ -49,6 +62,9 @@ pub fn strip_comments(source: &str) -> Cow<'_, str> {
     let mut state = State::Outside;
 
     while let Some(c) = chars.next() {
+        let output_len = output.len();
+        line += usize::from(c == '\n');
+
         match (state, c, chars.peek()) {
             // Start of a string literal
             (State::Outside, '"', _) => {
@@ -75,6 +91,7 @@ pub fn strip_comments(source: &str) -> Cow<'_, str> {
             (State::InString, '\\', _) => {
                 output.push(c);
                 if let Some(escaped_char) = chars.next() {
+                    line += usize::from(escaped_char == '\n');
                     output.push(escaped_char);
                 }
             }
@@ -100,7 +117,35 @@ pub fn strip_comments(source: &str) -> Cow<'_, str> {
             // Skip all other characters when inside comments
             (State::InLineComment | State::InBlockComment, _, _) => {}
         }
+
+        let pushed = &output[output_len..];
+        if pushed.contains('\n') {
+            line_origins.push(line);
+            line_has_token = false;
+        }
+        // A line starting with a block comment begins where the comment ends
+        if !line_has_token
+            && !pushed
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .is_empty()
+        {
+            *line_origins.last_mut().unwrap() = line;
+            line_has_token = true;
+        }
     }
 
-    Cow::Owned(output)
+    (Cow::Owned(output), line_origins)
+}
+
+/// Adjusts `line_origins` to the number of lines of `text`, for
+/// transformations that may join or split lines.
+///
+/// Missing entries repeat the last origin and extra entries are dropped.
+pub fn fit_line_origins(line_origins: &mut Vec<usize>, text: &str) {
+    let line_count = text.split('\n').count();
+    let last = line_origins.last().copied().unwrap_or(1);
+    line_origins.resize(line_count, last);
 }
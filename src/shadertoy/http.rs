@@ -0,0 +1,289 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! HTTP requests of the ShaderToy importer.
+//!
+//! Requests are made with `curl`. URLs are passed in a config file read
+//! from standard input rather than on the command line, so that API keys
+//! do not show in the process list. Transient failures, such as timeouts
+//! or server errors, are retried with exponential backoff.
+//!
+//! API responses are cached in `$XDG_CACHE_HOME/shaderbg/shadertoy` and
+//! served when the request fails, so shaders imported before can be
+//! imported again offline. Downloaded media is cached by the importer.
+//!
+//! Downloads are reported to the callback set with [`with_progress`], so
+//! that imports running in the background can show their progress.
+
+use std::{
+    cell::RefCell,
+    ffi::OsStr,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use super::importer::remote_asset_stem;
+use crate::{config::cache_dir, preset::*};
+
+/// Seconds `curl` waits for a connection before giving up.
+const CONNECT_TIMEOUT_SECS: u32 = 15;
+
+/// Transfers slower than [`STALL_BYTES_PER_SEC`] for this many seconds
+/// are aborted. Unlike a total timeout, large videos still download on
+/// slow connections.
+const STALL_TIMEOUT_SECS: u32 = 30;
+
+/// Transfer rate below which a transfer is considered stalled.
+const STALL_BYTES_PER_SEC: u32 = 1024;
+
+/// Number of attempts of a request failing transiently.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry, doubled for every later retry.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Callback receiving a description of each download.
+type ProgressCallback = Box<dyn Fn(&str)>;
+
+thread_local! {
+    /// Callback receiving the progress of the requests made on this
+    /// thread, set by [`with_progress`].
+    static ON_PROGRESS: RefCell<Option<ProgressCallback>> = RefCell::default();
+}
+
+/// Failed `curl` run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurlFailure {
+    /// Exit code of `curl`, or `None` if it could not be run.
+    pub exit_code: Option<i32>,
+
+    /// Error message printed by `curl`.
+    pub message: String,
+}
+
+impl CurlFailure {
+    /// Returns `true` if the request may succeed when retried: on
+    /// network errors, timeouts, HTTP 429 (Too Many Requests) and HTTP
+    /// 5xx server errors.
+    pub fn is_transient(&self) -> bool {
+        // See "EXIT CODES" in curl(1)
+        const COULDNT_RESOLVE_HOST: i32 = 6;
+        const COULDNT_CONNECT: i32 = 7;
+        const HTTP_RETURNED_ERROR: i32 = 22;
+        const OPERATION_TIMEDOUT: i32 = 28;
+        const SSL_CONNECT_ERROR: i32 = 35;
+        const GOT_NOTHING: i32 = 52;
+        const SEND_ERROR: i32 = 55;
+        const RECV_ERROR: i32 = 56;
+
+        match self.exit_code {
+            Some(HTTP_RETURNED_ERROR) => {
+                http_status(&self.message).is_some_and(|status| status == 429 || status >= 500)
+            }
+            Some(
+                COULDNT_RESOLVE_HOST | COULDNT_CONNECT | OPERATION_TIMEDOUT | SSL_CONNECT_ERROR
+                | GOT_NOTHING | SEND_ERROR | RECV_ERROR,
+            ) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the HTTP status reported by `curl --fail`, e.g., 503 for
+/// `"curl: (22) The requested URL returned error: 503"`.
+pub fn http_status(message: &str) -> Option<u16> {
+    let (_, status) = message.rsplit_once("returned error:")?;
+    status.split_whitespace().next()?.parse().ok()
+}
+
+/// Returns the delay before retrying after the given number of failed
+/// attempts.
+pub fn backoff_delay(failed_attempts: u32) -> Duration {
+    INITIAL_BACKOFF * 2u32.saturating_pow(failed_attempts.saturating_sub(1))
+}
+
+/// Returns the key a URL is cached under: the URL without its `key`
+/// query parameter, so that responses are found after the API key
+/// changes and keys are not written to disk. Also used to show URLs in
+/// messages.
+pub fn cache_key(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+
+    let query: Vec<_> = query
+        .split('&')
+        .filter(|parameter| !parameter.starts_with("key="))
+        .collect();
+    if query.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base}?{}", query.join("&"))
+    }
+}
+
+/// Runs `f`, passing a description of each download it makes on this
+/// thread, such as `Downloading <url>`, to `on_progress`.
+pub fn with_progress<T>(on_progress: impl Fn(&str) + 'static, f: impl FnOnce() -> T) -> T {
+    ON_PROGRESS.set(Some(Box::new(on_progress)));
+    let result = f();
+    ON_PROGRESS.set(None);
+    result
+}
+
+/// Logs `message` and passes it to the progress callback of this thread.
+fn report_progress(message: &str) {
+    log::info!("{message}");
+    ON_PROGRESS.with_borrow(|on_progress| {
+        if let Some(on_progress) = on_progress {
+            on_progress(message);
+        }
+    });
+}
+
+/// Fetches `url` and returns the response body.
+///
+/// Successful responses are cached. If the request fails, the cached
+/// response is returned instead, if any.
+pub fn fetch(url: &str) -> Result<String, PresetError> {
+    let cache_path = response_cache_dir()
+        .map(|dir| dir.join(format!("{}.json", remote_asset_stem(&cache_key(url)))));
+
+    let body = match curl_with_retries(url, &[]) {
+        Ok(body) => body,
+        Err(err) => match cache_path
+            .as_ref()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+        {
+            Some(body) => {
+                log::warn!("{err}. Using the cached response.");
+                return Ok(body);
+            }
+            None => return Err(err),
+        },
+    };
+
+    let body = String::from_utf8(body).map_err(|err| PresetError::Download {
+        url: cache_key(url),
+        reason: err.to_string(),
+    })?;
+
+    if let Err(err) = cache_path.and_then(|path| {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, &body)
+    }) {
+        log::warn!("Failed to cache the response of {}: {err}", cache_key(url));
+    }
+
+    Ok(body)
+}
+
+/// Downloads `url` to `path`, unless `path` already exists.
+///
+/// The file is written under a temporary name first so that interrupted
+/// downloads are not mistaken for complete ones.
+pub fn download(url: &str, path: &Path) -> Result<(), PresetError> {
+    if path.is_file() {
+        log::debug!("Using cached {}", path.display());
+        return Ok(());
+    }
+
+    report_progress(&format!("Downloading {}", cache_key(url)));
+    let partial_path = path.with_extension("part");
+    let args = [OsStr::new("--output"), partial_path.as_os_str()];
+
+    if let Err(err) = curl_with_retries(url, &args) {
+        let _ = fs::remove_file(&partial_path);
+        return Err(err);
+    }
+
+    fs::rename(&partial_path, path)?;
+    Ok(())
+}
+
+/// Runs `curl` on `url`, retrying transient failures with exponential
+/// backoff.
+fn curl_with_retries(url: &str, args: &[&OsStr]) -> Result<Vec<u8>, PresetError> {
+    let shown_url = cache_key(url);
+    let mut failed_attempts = 0;
+    loop {
+        let failure = match curl(url, args) {
+            Ok(output) => return Ok(output),
+            Err(failure) => failure,
+        };
+
+        failed_attempts += 1;
+        if failed_attempts >= MAX_ATTEMPTS || !failure.is_transient() {
+            return Err(PresetError::Download {
+                url: shown_url,
+                reason: failure.message,
+            });
+        }
+
+        let delay = backoff_delay(failed_attempts);
+        log::warn!(
+            "Request to {shown_url} failed ({}). Retrying in {}s.",
+            failure.message,
+            delay.as_secs()
+        );
+        thread::sleep(delay);
+    }
+}
+
+/// Returns the `curl` config file line requesting `url`.
+pub fn curl_config(url: &str) -> String {
+    let url = url.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("url = \"{url}\"\n")
+}
+
+/// Runs `curl` on `url` with the given arguments, failing on HTTP
+/// errors, and returns its standard output.
+fn curl(url: &str, args: &[&OsStr]) -> Result<Vec<u8>, CurlFailure> {
+    let failed_to_run = |err: std::io::Error| CurlFailure {
+        exit_code: None,
+        message: format!("failed to run curl: {err}"),
+    };
+
+    let mut child = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--connect-timeout", &CONNECT_TIMEOUT_SECS.to_string()])
+        .args(["--speed-time", &STALL_TIMEOUT_SECS.to_string()])
+        .args(["--speed-limit", &STALL_BYTES_PER_SEC.to_string()])
+        .args(args)
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(failed_to_run)?;
+
+    // The config is read before the transfer starts. If curl exits early,
+    // its error is reported below.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(curl_config(url).as_bytes());
+    }
+    let output = child.wait_with_output().map_err(failed_to_run)?;
+
+    if !output.status.success() {
+        return Err(CurlFailure {
+            exit_code: output.status.code(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(output.stdout)
+}
+
+/// Returns the directory storing API responses.
+fn response_cache_dir() -> io::Result<PathBuf> {
+    Ok(cache_dir()?.join("shadertoy"))
+}
@@ -8,15 +8,79 @@
 //! Converts ShaderToy JSON export files into internal [`Preset`]
 //! representations used by the renderer.
 //!
+//! Two JSON layouts are accepted:
+//! - API responses, where the shader is wrapped in a `Shader` object and
+//!   channel inputs use `ctype`/`src`.
+//! - Exports saved from the ShaderToy website, which are an array of
+//!   shaders (or a single shader object) whose channel inputs use
+//!   `type`/`filepath`.
+//!
+//! Media not bundled with ShaderBG is downloaded into
+//! `$XDG_DATA_HOME/shaderbg/assets/remote`, named after a hash of its
+//! URL so that later imports reuse the downloaded files.
+//!
 //! This importer is intentionally permissive: unsupported ShaderToy
 //! features degrade gracefully instead of aborting import.
 
-use crate::preset::*;
-use std::{fs, path::Path};
+use super::{complexity::check_complexity, http::download};
+use crate::{preset::*, util::fnv1a, APP_NAME};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Base URL used to resolve ShaderToy media paths (e.g. `/media/a/...`).
+pub const SHADERTOY_URL: &str = "https://www.shadertoy.com";
+
+/// Number of faces of a ShaderToy cubemap. The first face is stored at
+/// `src` and the others at `src` with `_1` to `_5` appended to the stem.
+const CUBEMAP_FACE_COUNT: usize = 6;
+
+/// Face suffixes of the texture manager's `{face}` placeholder, in the
+/// order of ShaderToy cubemap faces (+X, -X, +Y, -Y, +Z, -Z).
+const CUBEMAP_FACE_SUFFIXES: [&str; CUBEMAP_FACE_COUNT] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Settings of the `import` subcommand.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// ShaderToy JSON export to convert.
+    pub file: PathBuf,
+
+    /// TOML file to write, or `None` to add the preset to the presets
+    /// directory as `<shader_id>.toml`.
+    pub output: Option<PathBuf>,
+
+    /// Refuses shaders that look too heavy to run as a wallpaper,
+    /// instead of only warning about them.
+    pub strict: bool,
+}
+
+/// Converts a ShaderToy JSON export into a TOML preset file and returns
+/// the path of the written file.
+pub fn import(options: &ImportOptions) -> Result<PathBuf, PresetError> {
+    let preset = import_from_json_file(&options.file)?;
+    check_complexity(&preset, options.strict)?;
+    match &options.output {
+        Some(output) => {
+            save_preset_to_file(&preset, output)?;
+            Ok(output.clone())
+        }
+        None => save_to_presets_directory(&preset),
+    }
+}
+
+/// Imports a ShaderToy JSON export file into a [`Preset`].
+///
+/// See [`import_from_json_str`].
+pub fn import_from_json_file(path: &Path) -> Result<Preset, PresetError> {
+    import_from_json_str(&fs::read_to_string(path)?)
+}
 
 /// Imports a ShaderToy JSON export into a [`Preset`].
 ///
-/// The JSON must follow the structure produced by the ShaderToy API.
+/// The JSON must follow the structure produced by the ShaderToy API or
+/// by the export of the ShaderToy website. Only the first shader of a
+/// website export containing several shaders is imported.
 ///
 /// The importer initializes a preset using serde defaults, fills
 /// metadata fields from `Shader.info`, and then reconstructs the
@@ -25,14 +89,23 @@ use std::{fs, path::Path};
 /// Unsupported passes or channel types are ignored with warnings.
 ///
 /// Returns an error when mandatory JSON fields are missing.
-pub fn import_from_json_file(path: &Path) -> Result<Preset, PresetError> {
-    let json_str = fs::read_to_string(path)?;
-
-    let json_value: serde_json::Value = serde_json::from_str(&json_str)?;
-
-    let shader_obj = json_value
-        .get("Shader")
-        .ok_or_else(|| PresetError::Import("Missing 'Shader' key".into()))?;
+pub fn import_from_json_str(json_str: &str) -> Result<Preset, PresetError> {
+    let json_value: serde_json::Value = serde_json::from_str(json_str)?;
+
+    let shader_obj = match &json_value {
+        serde_json::Value::Array(shaders) => {
+            if shaders.len() > 1 {
+                log::warn!(
+                    "Export contains {} shaders; importing the first",
+                    shaders.len()
+                );
+            }
+            shaders
+                .first()
+                .ok_or_else(|| PresetError::Import("Empty shader array".into()))?
+        }
+        json_value => json_value.get("Shader").unwrap_or(json_value),
+    };
     let info = shader_obj
         .get("info")
         .ok_or_else(|| PresetError::Import("Missing 'info' key".into()))?;
@@ -81,9 +154,18 @@ pub fn import_from_json_file(path: &Path) -> Result<Preset, PresetError> {
 ///
 /// Unknown or unsupported pass types are ignored.
 fn process_single_pass(preset: &mut Preset, pass: &serde_json::Value) -> Result<(), PresetError> {
+    // Website exports may leave the name of single-instance passes empty
     let name = pass
         .get("name")
         .and_then(|n| n.as_str())
+        .filter(|n| !n.is_empty())
+        .or_else(|| match pass.get("type").and_then(|t| t.as_str())? {
+            "common" => Some("Common"),
+            "cubemap" => Some("Cube A"),
+            "image" => Some("Image"),
+            "sound" => Some("Sound"),
+            _ => None,
+        })
         .ok_or_else(|| PresetError::Import("Missing pass 'name'".into()))?;
 
     let code = pass
@@ -104,6 +186,9 @@ fn process_single_pass(preset: &mut Preset, pass: &serde_json::Value) -> Result<
 
     let pass_config = Pass {
         shader: code,
+        shader_file: None,
+        buffer_precision: None,
+        scale: None,
         input_0,
         input_1,
         input_2,
@@ -119,7 +204,8 @@ fn process_single_pass(preset: &mut Preset, pass: &serde_json::Value) -> Result<
         "Cube A" => preset.cube_a = Some(pass_config),
         "Image" => preset.image = pass_config,
         "Sound" => {
-            log::warn!("'Sound' pass type ignored (not supported)");
+            log::info!("'Sound' pass imported; set `enable_sound = true` to play it");
+            preset.sound = Some(pass_config);
         }
         _ => {
             log::warn!("Unknown pass type '{name}'");
@@ -160,7 +246,12 @@ fn process_single_input(
     input: &serde_json::Value,
     pass_name: &str,
 ) -> Result<Option<Input>, PresetError> {
-    let ctype = input.get("ctype").and_then(|t| t.as_str()).unwrap_or("");
+    // API responses use `ctype` and `src`, website exports `type` and `filepath`
+    let ctype = input
+        .get("ctype")
+        .or_else(|| input.get("type"))
+        .and_then(|t| t.as_str())
+        .unwrap_or("");
 
     if !is_supported_channel_type(ctype) {
         let channel = input.get("channel").and_then(|c| c.as_i64()).unwrap_or(-1);
@@ -168,7 +259,11 @@ fn process_single_input(
     }
 
     let sampler = input.get("sampler").unwrap_or(&serde_json::Value::Null);
-    let src = input.get("src").and_then(|s| s.as_str()).unwrap_or("");
+    let src = input
+        .get("src")
+        .or_else(|| input.get("filepath"))
+        .and_then(|s| s.as_str())
+        .unwrap_or("");
     let input_config = create_input_config(ctype, src, sampler)?;
 
     Ok(Some(input_config))
@@ -177,13 +272,15 @@ fn process_single_input(
 /// Returns whether a ShaderToy channel type is supported by ShaderBG.
 ///
 /// Unsupported types are accepted during import but replaced with
-/// fallback inputs.
+/// fallback inputs. Video and webcam channels require the `video`
+/// feature.
 fn is_supported_channel_type(ctype: &str) -> bool {
-    !matches!(
-        ctype,
+    match ctype {
+        "video" | "webcam" => cfg!(feature = "video"),
         // Unsupported types
-        "video" | "music" | "musicstream" | "webcam" | "mic"
-    )
+        "music" | "musicstream" | "mic" => false,
+        _ => true,
+    }
 }
 
 /// Translates ShaderToy channel metadata into an [`Input`] configuration.
@@ -210,21 +307,32 @@ fn create_input_config(
 
     let name = if !is_supported_channel_type(ctype) {
         "fallback".to_string()
-    } else if _type == InputType::Keyboard {
+    } else if matches!(_type, InputType::Keyboard | InputType::Webcam) {
         "".to_string()
+    } else if _type == InputType::Video {
+        // Videos are not bundled; stream them from ShaderToy instead.
+        if src.starts_with('/') {
+            format!("{SHADERTOY_URL}{src}")
+        } else {
+            src.to_string()
+        }
     } else if let Some(filename) = std::path::Path::new(src)
         .file_name()
         .and_then(|s| s.to_str())
     {
         match filename {
-            "buffer00.png" => "Buffer A",
-            "buffer01.png" => "Buffer B",
-            "buffer02.png" => "Buffer C",
-            "buffer03.png" => "Buffer D",
-            "cubemap00.png" => "Cubemap A",
-            _ => asset_name_from_src(src)?,
+            "buffer00.png" => "Buffer A".to_string(),
+            "buffer01.png" => "Buffer B".to_string(),
+            "buffer02.png" => "Buffer C".to_string(),
+            "buffer03.png" => "Buffer D".to_string(),
+            "cubemap00.png" => "Cubemap A".to_string(),
+            _ => match asset_name_from_src(src) {
+                Ok(name) => name.to_string(),
+                Err(_) => download_remote_asset(_type, src)?
+                    .to_string_lossy()
+                    .into_owned(),
+            },
         }
-        .to_string()
     } else {
         src.to_string()
     };
@@ -329,3 +437,62 @@ fn asset_name_from_src(src: &str) -> Result<&'static str, PresetError> {
         _ => Err(PresetError::Import(format!("Unknown asset name: {stem}"))),
     }
 }
+
+/// Downloads an asset that is not bundled with ShaderBG and returns the
+/// path of the local copy, which is reused if already downloaded.
+///
+/// Cubemaps are downloaded as six face files and referenced through the
+/// `{face}` placeholder of the texture manager.
+fn download_remote_asset(input_type: InputType, src: &str) -> Result<PathBuf, PresetError> {
+    let url = if src.starts_with('/') {
+        format!("{SHADERTOY_URL}{src}")
+    } else {
+        src.to_string()
+    };
+    let extension = Path::new(src)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("png");
+    let dir = remote_assets_dir();
+    fs::create_dir_all(&dir)?;
+
+    let stem = remote_asset_stem(&url);
+    match input_type {
+        InputType::Texture => {
+            let path = dir.join(format!("{stem}.{extension}"));
+            download(&url, &path)?;
+            Ok(path)
+        }
+        InputType::Cubemap => {
+            let face_url_prefix = url.strip_suffix(&format!(".{extension}")).unwrap_or(&url);
+            for (face, suffix) in CUBEMAP_FACE_SUFFIXES.iter().enumerate() {
+                let face_url = if face == 0 {
+                    url.clone()
+                } else {
+                    format!("{face_url_prefix}_{face}.{extension}")
+                };
+                download(&face_url, &dir.join(format!("{stem}_{suffix}.{extension}")))?;
+            }
+            Ok(dir.join(format!("{stem}_{{face}}.{extension}")))
+        }
+        _ => Err(PresetError::Import(format!(
+            "Unknown asset {src} cannot be downloaded as {input_type:?}"
+        ))),
+    }
+}
+
+/// Returns the directory storing downloaded assets.
+fn remote_assets_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_NAME)
+        .join("assets")
+        .join("remote")
+}
+
+/// Returns the file stem of a downloaded asset: the 64-bit FNV-1a hash
+/// of its URL, in hexadecimal.
+pub fn remote_asset_stem(url: &str) -> String {
+    format!("{:016x}", fnv1a(url.as_bytes()))
+}
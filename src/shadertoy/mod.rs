@@ -13,26 +13,47 @@
 //! - Adapt GLSL ES semantics to desktop GLSL versions
 //! - Initialize undefined variables for stricter desktop compilers
 //! - Rename identifiers conflicting with desktop GLSL reserved words
+//! - Polyfill functions whose results differ from WebGL 2
+//! - Translate precision qualifiers and flag reduced precision code
 //! - Import ShaderToy JSON exports into application presets
+//! - Estimate the cost of shaders to flag those too heavy to run
+//! - Find code outside the ShaderToy GLSL ES 3.00 dialect
 //!
 //! The main entry point is [`to_glsl_version`], which transforms shader
 //! source code according to the requested OpenGL version.
 
 #[cfg(test)]
 mod tests {
+    mod complexity;
     mod convert_to_desktop_glsl;
+    mod dialect;
     mod glsl_initializer;
     mod glsl_preprocessor;
     mod glsl_utils;
+    mod http;
+    mod importer;
+    mod polyfills;
+    mod precision;
+    mod search;
 }
+pub mod complexity;
+pub mod dialect;
 mod glsl_depth_tracker;
 mod glsl_initializer;
 mod glsl_preprocessor;
 mod glsl_utils;
+pub mod http;
 pub mod importer;
+mod polyfills;
+pub mod precision;
+pub mod search;
+
+use std::path::Path;
 
 use crate::renderer::shader::ShaderError;
 
+pub use glsl_preprocessor::included_file;
+
 /// Reserved words or built-in function names in GLSL 4.20 that are not in GLSL ES 3.00.
 #[rustfmt::skip]
 pub const DIFF_RESERVED_WORDS_4_2: [&str; 63] = [
@@ -87,11 +108,21 @@ pub const DIFF_RESERVED_WORDS_3_0_ES_REV_2: [&str; 1] = [
 
 /// Makes a ShaderToy shader compatible with the given GLSL version.
 /// Currently works only with 3.0 es and 4.2.
+///
+/// Also returns the line of `source` (1-based) from which each line of
+/// the result originates, to map compiler errors back to `source`.
+///
+/// `#include` directives are resolved relative to `include_dir`,
+/// usually the directory of the preset. Texture sampling of the
+/// `unbound_channels` (channel indices without an input) returns opaque
+/// black, as in WebGL.
 pub fn to_glsl_version(
     source: &str,
     version: (i32, i32),
     glsl_es: bool,
-) -> Result<String, ShaderError> {
+    include_dir: Option<&Path>,
+    unbound_channels: &[usize],
+) -> Result<(String, Vec<usize>), ShaderError> {
     let mut source = source.to_string();
     let glsl_version = format!("{}{}0", version.0, version.1);
 
@@ -99,7 +130,10 @@ pub fn to_glsl_version(
     source =
         glsl_utils::replace_in_preprocessor_conditionals(&source, "__VERSION__", &glsl_version);
 
-    source = glsl_initializer::initialize_uninitialized_variables(&source)?;
+    let mut line_origins;
+    (source, line_origins) =
+        glsl_initializer::initialize_uninitialized_variables(&source, include_dir)?;
+    source = precision::translate_precision(&source, glsl_es);
 
     fn rename_with_trailing_underscore(text: &str, word: &str) -> String {
         let pattern = format!(r"\b{}\b", regex::escape(word));
@@ -118,5 +152,11 @@ pub fn to_glsl_version(
         }
     }
 
-    Ok(source)
+    // Polyfills precede the code, without an origin
+    let polyfill_code;
+    (source, polyfill_code) = polyfills::apply_polyfills(&source, unbound_channels);
+    line_origins.splice(0..0, polyfill_code.lines().map(|_| 0));
+    source = polyfill_code + &source;
+
+    Ok((source, line_origins))
 }
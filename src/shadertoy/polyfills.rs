@@ -0,0 +1,135 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Polyfills for functions behaving differently than in WebGL 2.
+//!
+//! ShaderToy shaders are tuned against WebGL 2 implementations, which
+//! differ from desktop drivers in a few corner cases:
+//!
+//! - `tanh` saturates to ±1 for large arguments in WebGL, whereas some
+//!   desktop drivers compute it as a ratio of exponentials that
+//!   overflows to NaN.
+//! - `round` of halfway values is implementation-defined; WebGL
+//!   implementations round them away from zero.
+//! - Sampling a channel without an input returns opaque black in
+//!   WebGL, whereas desktop drivers sample whatever texture is left
+//!   bound to the texture unit.
+//!
+//! Calls to these functions are redirected to `sbg_` replacements,
+//! whose code is only injected when the shader references them.
+
+use regex::Regex;
+
+/// Types of the overloads of polyfilled functions.
+const GEN_TYPES: [&str; 4] = ["float", "vec2", "vec3", "vec4"];
+
+/// Built-in functions replaced by a polyfill, and the code of each
+/// overload of the replacement, where `genType` stands for the argument
+/// type.
+const POLYFILLS: [(&str, &str); 2] = [
+    (
+        "tanh",
+        "genType sbg_tanh(genType x) { return tanh(clamp(x, -15.0, 15.0)); }",
+    ),
+    (
+        "round",
+        "genType sbg_round(genType x) { return sign(x) * floor(abs(x) + 0.5); }",
+    ),
+];
+
+/// Texture functions returning a texel of a sampler.
+#[rustfmt::skip]
+const SAMPLING_FUNCTIONS: [&str; 14] = [
+    "texture", "textureOffset", "textureProj", "textureProjOffset",
+    "textureLod", "textureLodOffset", "textureProjLod", "textureProjLodOffset",
+    "textureGrad", "textureGradOffset", "textureProjGrad", "textureProjGradOffset",
+    "texelFetch", "texelFetchOffset",
+];
+
+/// Function wrapping texels sampled from channels without an input.
+const UNBOUND_TEXEL: &str = "vec4 sbg_unbound(vec4 texel) { return vec4(0.0, 0.0, 0.0, 1.0); }";
+
+/// Redirects the calls of `source` to polyfilled functions, including
+/// texture sampling of the `unbound_channels` (channel indices without
+/// an input).
+///
+/// Returns the modified source and the code of the polyfills it calls,
+/// which must precede it.
+pub fn apply_polyfills(source: &str, unbound_channels: &[usize]) -> (String, String) {
+    let mut source = source.to_string();
+    let mut code = String::new();
+
+    for (function, overload) in POLYFILLS {
+        let call = Regex::new(&format!(r"\b{function}(\s*\()")).unwrap();
+        if !call.is_match(&source) {
+            continue;
+        }
+        source = call
+            .replace_all(&source, format!("sbg_{function}$1"))
+            .into_owned();
+        for gen_type in GEN_TYPES {
+            code += &overload.replace("genType", gen_type);
+            code.push('\n');
+        }
+    }
+
+    let sampling = wrap_unbound_sampling(&source, unbound_channels);
+    if sampling != source {
+        source = sampling;
+        code += UNBOUND_TEXEL;
+        code.push('\n');
+    }
+
+    (source, code)
+}
+
+/// Wraps with `sbg_unbound` the calls of `source` sampling the
+/// `unbound_channels`.
+fn wrap_unbound_sampling(source: &str, unbound_channels: &[usize]) -> String {
+    let call = Regex::new(r"\b([A-Za-z]+)\s*\(\s*iChannel(\d)\b").unwrap();
+
+    let mut output = String::new();
+    let mut copied = 0;
+    for caps in call.captures_iter(source) {
+        let start = caps.get(0).unwrap().start();
+        let channel: usize = caps[2].parse().unwrap_or_default();
+        if start < copied
+            || !SAMPLING_FUNCTIONS.contains(&&caps[1])
+            || !unbound_channels.contains(&channel)
+        {
+            continue;
+        }
+        let open = start + caps[0].find('(').unwrap_or_default();
+        let Some(close) = matching_paren(source, open) else {
+            continue;
+        };
+
+        output += &source[copied..start];
+        output += "sbg_unbound(";
+        output += &source[start..=close];
+        output.push(')');
+        copied = close + 1;
+    }
+    output += &source[copied..];
+    output
+}
+
+/// Returns the index of the parenthesis closing the one at `open`.
+fn matching_paren(source: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in source[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
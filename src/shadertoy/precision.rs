@@ -0,0 +1,78 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Translation of GLSL ES precision qualifiers.
+//!
+//! Desktop GLSL accepts precision qualifiers only for portability, with
+//! no effect and with varying driver support, so they are stripped for
+//! desktop targets. For GLSL ES targets, `lowp` and `mediump` are
+//! promoted to `highp`, matching desktop WebGL, where ShaderToy shaders
+//! are usually written and every precision is high.
+//!
+//! Either way, code declared with a low precision runs at high
+//! precision, which changes the results of shaders relying on reduced
+//! precision, e.g., for overflow or quantization effects, so such
+//! declarations are reported.
+
+use regex::Regex;
+
+use super::glsl_utils::strip_comments_with_line_origins;
+
+/// Statement setting the default precision of a type, e.g.,
+/// `precision mediump float;`.
+const PRECISION_STATEMENT: &str = r"\bprecision\s+(lowp|mediump|highp)\s+\w+\s*;";
+
+/// Precision qualifier, along with the whitespace following it.
+const PRECISION_QUALIFIER: &str = r"\b(lowp|mediump|highp)\b\s*";
+
+/// Declaration with a precision lower than desktop GLSL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrecisionWarning {
+    /// Line (1-based) of the pass code.
+    pub line: usize,
+
+    pub message: String,
+}
+
+/// Strips the precision statements and qualifiers of `source` if not
+/// `glsl_es`, or promotes them to `highp` otherwise.
+///
+/// Lines are neither added nor removed.
+pub fn translate_precision(source: &str, glsl_es: bool) -> String {
+    if glsl_es {
+        let low_precision = Regex::new(r"\b(lowp|mediump)\b").unwrap();
+        return low_precision.replace_all(source, "highp").into_owned();
+    }
+
+    let statement = Regex::new(PRECISION_STATEMENT).unwrap();
+    let qualifier = Regex::new(PRECISION_QUALIFIER).unwrap();
+    let source = statement.replace_all(source, "");
+    qualifier.replace_all(&source, "").into_owned()
+}
+
+/// Returns the `lowp` and `mediump` qualifiers of `source`, in order of
+/// appearance.
+pub fn precision_warnings(source: &str) -> Vec<PrecisionWarning> {
+    let (code, line_origins) = strip_comments_with_line_origins(source);
+    let low_precision = Regex::new(r"\b(lowp|mediump)\b").unwrap();
+
+    let mut warnings = Vec::new();
+    for (index, line) in code.split('\n').enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let line_number = line_origins.get(index).copied().unwrap_or(index + 1);
+        for qualifier in low_precision.find_iter(line).map(|word| word.as_str()) {
+            warnings.push(PrecisionWarning {
+                line: line_number,
+                message: format!(
+                    "'{qualifier}' precision runs as 'highp', which may change results \
+                     relying on reduced precision"
+                ),
+            });
+        }
+    }
+    warnings
+}
@@ -0,0 +1,234 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! ShaderToy search.
+//!
+//! Queries the ShaderToy API for shaders matching a search string and
+//! lists them, optionally letting the user pick one to import and apply.
+//! Only the picked shader is imported, so that the media of the others
+//! is not downloaded.
+//!
+//! The API only returns shaders with "public + api" visibility and
+//! requires an API key (see <https://www.shadertoy.com/howto>).
+
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use super::{
+    http::fetch,
+    importer::{import_from_json_str, SHADERTOY_URL},
+};
+use crate::preset::*;
+
+/// Environment variable holding the ShaderToy API key.
+pub const API_KEY_VAR: &str = "SHADERTOY_API_KEY";
+
+/// Order of search results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchSort {
+    Name,
+    Love,
+    Popular,
+    Newest,
+    Hot,
+}
+
+impl SearchSort {
+    /// Returns the value of the `sort` query parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Love => "love",
+            Self::Popular => "popular",
+            Self::Newest => "newest",
+            Self::Hot => "hot",
+        }
+    }
+}
+
+/// Settings of the `search` subcommand.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub query: String,
+    pub sort: SearchSort,
+
+    /// Maximum number of results.
+    pub limit: usize,
+
+    /// ShaderToy API key.
+    pub key: String,
+
+    /// Prompts for a result to import and apply.
+    pub pick: bool,
+}
+
+/// Shader found by a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub id: String,
+    pub name: String,
+    pub username: String,
+
+    /// API response of the shader, imported if picked.
+    pub response: String,
+}
+
+/// Searches ShaderToy and prints the results.
+///
+/// With `options.pick`, the chosen shader is imported into the presets
+/// directory and returned along with the path of the saved preset.
+pub fn search(options: &SearchOptions) -> Result<Option<(Preset, PathBuf)>, PresetError> {
+    let ids = parse_query_response(&fetch(&query_url(options))?)?;
+    if ids.is_empty() {
+        println!("No shaders found");
+        return Ok(None);
+    }
+
+    let results: Vec<SearchResult> = ids
+        .iter()
+        .take(options.limit)
+        .filter_map(|id| {
+            match fetch(&shader_url(id, &options.key)).and_then(parse_shader_response) {
+                Ok(result) => Some(result),
+                Err(err) => {
+                    log::warn!("Skipping shader {id}: {err}");
+                    None
+                }
+            }
+        })
+        .collect();
+    if results.is_empty() {
+        println!("No shaders could be retrieved");
+        return Ok(None);
+    }
+
+    for (index, result) in results.iter().enumerate() {
+        println!(
+            "{:>3}. {}  {} by {}",
+            index + 1,
+            result.id,
+            result.name,
+            result.username
+        );
+    }
+
+    if !options.pick {
+        return Ok(None);
+    }
+
+    let Some(result) = prompt_choice(&results)? else {
+        return Ok(None);
+    };
+    let preset = import_from_json_str(&result.response)?;
+    let path = save_to_presets_directory(&preset)?;
+    log::info!("Saved {}", path.display());
+
+    Ok(Some((preset, path)))
+}
+
+/// Asks for the number of a result on standard input.
+///
+/// Returns `None` if the input is empty.
+fn prompt_choice(results: &[SearchResult]) -> Result<Option<&SearchResult>, PresetError> {
+    let stdin = io::stdin();
+    loop {
+        print!("Select a shader [1-{}] (empty to cancel): ", results.len());
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 || line.trim().is_empty() {
+            return Ok(None);
+        }
+        match line.trim().parse::<usize>() {
+            Ok(choice) if (1..=results.len()).contains(&choice) => {
+                return Ok(Some(&results[choice - 1]))
+            }
+            _ => println!("Invalid selection '{}'", line.trim()),
+        }
+    }
+}
+
+/// Returns the URL of a ShaderToy API query.
+pub fn query_url(options: &SearchOptions) -> String {
+    format!(
+        "{SHADERTOY_URL}/api/v1/shaders/query/{}?sort={}&from=0&num={}&key={}",
+        encode_url_component(&options.query),
+        options.sort.as_str(),
+        options.limit,
+        encode_url_component(&options.key)
+    )
+}
+
+/// Returns the URL of the ShaderToy API response of a shader.
+fn shader_url(id: &str, key: &str) -> String {
+    format!(
+        "{SHADERTOY_URL}/api/v1/shaders/{}?key={}",
+        encode_url_component(id),
+        encode_url_component(key)
+    )
+}
+
+/// Returns the shader IDs of a ShaderToy API query response.
+pub fn parse_query_response(json: &str) -> Result<Vec<String>, PresetError> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+
+    if let Some(error) = value.get("Error").and_then(|e| e.as_str()) {
+        return Err(PresetError::Import(format!("ShaderToy API error: {error}")));
+    }
+
+    Ok(value
+        .get("Results")
+        .and_then(|r| r.as_array())
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|id| id.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Returns the search result of a ShaderToy API shader response, which
+/// is kept to be imported if picked.
+pub fn parse_shader_response(response: String) -> Result<SearchResult, PresetError> {
+    let value: serde_json::Value = serde_json::from_str(&response)?;
+
+    if let Some(error) = value.get("Error").and_then(|e| e.as_str()) {
+        return Err(PresetError::Import(format!("ShaderToy API error: {error}")));
+    }
+
+    let info = value
+        .get("Shader")
+        .and_then(|shader| shader.get("info"))
+        .ok_or_else(|| PresetError::Import("Missing 'info' key".into()))?;
+    let field = |key: &str| {
+        info.get(key)
+            .and_then(|value| value.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+
+    Ok(SearchResult {
+        id: field("id"),
+        name: field("name"),
+        username: field("username"),
+        response,
+    })
+}
+
+/// Percent-encodes every byte of `value` except unreserved characters.
+pub fn encode_url_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
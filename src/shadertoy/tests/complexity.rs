@@ -0,0 +1,176 @@
+use pretty_assertions::assert_eq;
+
+use super::super::complexity::{analyze, ShaderComplexity};
+
+#[test]
+fn test_shader_without_loops() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        fragColor = texture(iChannel0, fragCoord / iResolution.xy);
+    }";
+
+    assert_eq!(
+        analyze("", source),
+        ShaderComplexity {
+            texture_fetches: 1.0,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_loop_with_literal_bounds() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int i = 0; i < 64; i++) {
+            fragColor += texture(iChannel0, fragCoord);
+        }
+    }";
+
+    let complexity = analyze("", source);
+
+    assert_eq!(complexity.loop_iterations, 64.0);
+    assert_eq!(complexity.texture_fetches, 64.0);
+}
+
+#[test]
+fn test_loop_bounds_from_define_and_const() {
+    let source = "#define STEPS 10
+    const float STEP = 0.5;
+    void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (float t = 0.0; t <= float(STEPS) * STEP; t += STEP) {
+            fragColor += dFdx(fragColor);
+        }
+    }";
+
+    let complexity = analyze("", source);
+
+    assert_eq!(complexity.loop_iterations, 11.0);
+    assert_eq!(complexity.derivatives, 11.0);
+}
+
+#[test]
+fn test_decreasing_loop() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int i = 20; i > 0; i -= 2) fragColor += 1.0;
+    }";
+
+    assert_eq!(analyze("", source).loop_iterations, 10.0);
+}
+
+#[test]
+fn test_nested_loops_multiply() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int y = -4; y <= 4; y++)
+            for (int x = -4; x <= 4; x++)
+                fragColor += texelFetch(iChannel0, ivec2(fragCoord) + ivec2(x, y), 0);
+    }";
+
+    let complexity = analyze("", source);
+
+    assert_eq!(complexity.texture_fetches, 81.0);
+    assert_eq!(complexity.loop_iterations, 9.0 + 81.0);
+}
+
+#[test]
+fn test_function_costs_are_multiplied_by_calling_loops() {
+    let common = "float noise(vec2 p) {
+        float n = 0.0;
+        for (int i = 0; i < 8; ++i) n += texture(iChannel0, p).x;
+        return n;
+    }";
+    let source = "float map(vec3 p) { return noise(p.xy) + noise(p.yz); }
+    void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int i = 0; i < 100; i++) {
+            if (map(vec3(fragCoord, 0.0)) < 0.01) break;
+        }
+    }";
+
+    let complexity = analyze(common, source);
+
+    assert_eq!(complexity.texture_fetches, 100.0 * 2.0 * 8.0);
+    assert_eq!(complexity.loop_iterations, 100.0 + 100.0 * 2.0 * 8.0);
+}
+
+#[test]
+fn test_unbounded_loops() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (;;) { break; }
+        while (true) { break; }
+        do { fragColor += 1.0; } while (true);
+    }";
+
+    assert_eq!(analyze("", source).unbounded_loops, 3);
+}
+
+#[test]
+fn test_loop_with_unknown_bound_runs_once() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int i = 0; i < int(iTime); i++) fragColor += texture(iChannel0, fragCoord);
+        while (fragColor.x < 1.0) fragColor += 0.1;
+    }";
+
+    let complexity = analyze("", source);
+
+    assert_eq!(complexity.loop_iterations, 2.0);
+    assert_eq!(complexity.texture_fetches, 1.0);
+    assert_eq!(complexity.unbounded_loops, 0);
+}
+
+#[test]
+fn test_comments_are_ignored() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        // for (int i = 0; i < 1000000; i++) texture(iChannel0, fragCoord);
+        /* while (true) {} */
+    }";
+
+    assert_eq!(analyze("", source), ShaderComplexity::default());
+}
+
+#[test]
+fn test_heavy_shader_warnings() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int i = 0; i < 200; i++)
+            for (int j = 0; j < 200; j++)
+                fragColor += texture(iChannel0, fragCoord + vec2(i, j));
+    }";
+
+    let warnings = analyze("", source).warnings();
+
+    assert_eq!(
+        warnings,
+        vec![
+            "About 40200 loop iterations per pixel".to_string(),
+            "About 40000 texture fetches per pixel".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_light_shader_has_no_warnings() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+        for (int i = 0; i < 128; i++) fragColor += texture(iChannel0, fragCoord);
+    }";
+
+    assert!(analyze("", source).warnings().is_empty());
+}
+
+#[test]
+fn test_loop_incrementing_in_condition() {
+    let source = "void mainImage(out vec4 O, vec2 C) {
+        for (float i, z, d; ++i < 66.; z += .5 * d) O += texture(iChannel0, C);
+        for (int j = 10; j-- > 0;) O += texture(iChannel0, C);
+    }";
+
+    let complexity = analyze("", source);
+
+    assert_eq!(complexity.loop_iterations, 65.0 + 10.0);
+    assert_eq!(complexity.texture_fetches, 65.0 + 10.0);
+}
+
+#[test]
+fn test_loop_with_several_variables() {
+    let source = "void mainImage(out vec4 O, vec2 C) {
+        for (int i = 0, j = 100; i < 20; i++, j--) O += 1.0;
+    }";
+
+    assert_eq!(analyze("", source).loop_iterations, 20.0);
+}
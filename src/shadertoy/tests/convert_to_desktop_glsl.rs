@@ -6,7 +6,7 @@ use super::super::{to_glsl_version, DIFF_RESERVED_WORDS_3_0_ES_REV_2, DIFF_RESER
 fn test_rename_reserved_4_2() {
     for &word in &DIFF_RESERVED_WORDS_4_2 {
         let expected = format!("{}_", word);
-        let source = to_glsl_version(word, (4, 2), false).unwrap();
+        let (source, _) = to_glsl_version(word, (4, 2), false, None, &[]).unwrap();
         assert_eq!(source, expected);
     }
 }
@@ -15,7 +15,15 @@ fn test_rename_reserved_4_2() {
 fn test_rename_reserved_3_0_es() {
     for &word in &DIFF_RESERVED_WORDS_3_0_ES_REV_2 {
         let expected = format!("{}_", word);
-        let source = to_glsl_version(word, (3, 0), true).unwrap();
+        let (source, _) = to_glsl_version(word, (3, 0), true, None, &[]).unwrap();
         assert_eq!(source, expected);
     }
 }
+
+#[test]
+fn test_line_origins_skip_empty_lines() {
+    let source = "\n// Comment\nfloat a;\n\n\nfloat b;\n";
+    let (output, line_origins) = to_glsl_version(source, (4, 2), false, None, &[]).unwrap();
+    assert_eq!(output, "float a = 0.0;\nfloat b = 0.0;");
+    assert_eq!(line_origins, vec![3, 6]);
+}
@@ -0,0 +1,94 @@
+use pretty_assertions::assert_eq;
+
+use super::super::dialect::{dialect_issues, DialectIssue};
+
+#[test]
+fn test_valid_shader_has_no_issues() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    fragColor = texture(iChannel0, fragCoord / iResolution.xy);
+}";
+
+    assert_eq!(dialect_issues(source), vec![]);
+}
+
+#[test]
+fn test_reserved_words() {
+    let source = "float sample(vec2 p) { return p.x; }
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec3 input = vec3(sample(fragCoord));
+    fragColor = vec4(input, 1.0);
+}";
+
+    assert_eq!(
+        dialect_issues(source),
+        vec![
+            DialectIssue {
+                line: 1,
+                message: "'sample' is a reserved word in GLSL ES 3.00".to_string(),
+            },
+            DialectIssue {
+                line: 3,
+                message: "'input' is a reserved word in GLSL ES 3.00".to_string(),
+            },
+            DialectIssue {
+                line: 3,
+                message: "'sample' is a reserved word in GLSL ES 3.00".to_string(),
+            },
+            DialectIssue {
+                line: 4,
+                message: "'input' is a reserved word in GLSL ES 3.00".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_webgl1_builtins() {
+    let source = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    gl_FragColor = texture2D(iChannel0, fragCoord);
+}";
+
+    assert_eq!(
+        dialect_issues(source),
+        vec![
+            DialectIssue {
+                line: 2,
+                message: "'gl_FragColor' is only available in WebGL 1, use the 'fragColor' \
+                          output of mainImage instead"
+                    .to_string(),
+            },
+            DialectIssue {
+                line: 2,
+                message: "'texture2D' is only available in WebGL 1, use 'texture' instead"
+                    .to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_webgl1_builtins_defined_as_macros() {
+    let source = "#define texture2D texture
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    fragColor = texture2D(iChannel0, fragCoord);
+}";
+
+    assert_eq!(dialect_issues(source), vec![]);
+}
+
+#[test]
+fn test_comments_and_directives_are_ignored() {
+    let source = "#extension GL_EXT_shader_texture_lod : enable
+/* float sample;
+   vec3 input; */
+// texture2D(iChannel0, uv);
+float common = 1.0;";
+
+    assert_eq!(
+        dialect_issues(source),
+        vec![DialectIssue {
+            line: 5,
+            message: "'common' is a reserved word in GLSL ES 3.00".to_string(),
+        }]
+    );
+}
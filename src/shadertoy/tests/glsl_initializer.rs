@@ -3,7 +3,9 @@ use pretty_assertions::assert_eq;
 use super::super::glsl_initializer;
 
 fn initialize(source: &str) -> String {
-    glsl_initializer::initialize_uninitialized_variables(source).unwrap()
+    glsl_initializer::initialize_uninitialized_variables(source, None)
+        .unwrap()
+        .0
 }
 
 #[test]
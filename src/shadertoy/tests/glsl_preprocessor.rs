@@ -1382,3 +1382,444 @@ void main() {
         );
     }
 }
+
+mod line_origins {
+    use pretty_assertions::assert_eq;
+
+    use crate::shadertoy::glsl_preprocessor::preprocess_with_line_origins;
+
+    #[test]
+    fn test_line_origins_skip_directives() {
+        let source =
+            "#define VALUE 5.0\nfloat a = VALUE;\n#ifdef MISSING\nfloat b;\n#endif\nfloat c;";
+        let (output, line_origins) = preprocess_with_line_origins(source, None).unwrap();
+        assert_eq!(output, "float a = 5.0;\nfloat c;\n");
+        assert_eq!(line_origins, vec![2, 6]);
+    }
+
+    #[test]
+    fn test_line_origins_across_block_comments() {
+        let source = "float a;\n/* one\n   two */ float b;\nfloat c;";
+        let (output, line_origins) = preprocess_with_line_origins(source, None).unwrap();
+        assert_eq!(output, "float a;\n  float b;\nfloat c;\n");
+        assert_eq!(line_origins, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_line_origins_across_spliced_lines() {
+        let source =
+            "#define ADD(a, b) \\\n    (a + b)\nfloat a = \\\n    ADD(1.0, 2.0);\nfloat b;";
+        let (output, line_origins) = preprocess_with_line_origins(source, None).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert_eq!(line_origins, vec![3, 5]);
+    }
+}
+
+mod token_pasting_and_stringizing {
+    use pretty_assertions::assert_eq;
+
+    use crate::shadertoy::glsl_preprocessor::preprocess;
+
+    #[test]
+    fn test_token_pasting() {
+        let source = r#"
+#define CAT(a, b) a ## b
+float CAT(value, 1) = 1.0;
+"#;
+        let expected = "float value1 = 1.0;";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_token_pasting_without_spaces() {
+        let source = r#"
+#define SWIZZLE(v, c) v.c##c
+vec2 a = SWIZZLE(p, x);
+"#;
+        let expected = "vec2 a = p.xx;";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_token_pasting_with_literal_tokens() {
+        let source = r#"
+#define VEC(n) vec ## n
+#define DECLARE(type, name) type ## 3 name ## _3
+VEC(4) a; DECLARE(vec, b);
+"#;
+        let expected = "vec4 a; vec3 b_3;";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_pasted_token_is_expanded() {
+        let source = r#"
+#define SIZE_SMALL 4
+#define SIZE_LARGE 16
+#define SIZE(name) SIZE_ ## name
+int a = SIZE(SMALL) + SIZE(LARGE);
+"#;
+        let expected = "int a = 4 + 16;";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_token_pasting_in_object_like_macro() {
+        let source = r#"
+#define NAME my ## Function
+float NAME();
+"#;
+        let expected = "float myFunction();";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_token_pasting_generates_functions() {
+        let source = r#"
+#define DEFINE_ADD(T) T add_ ## T(T a, T b) { return a + b; }
+DEFINE_ADD(vec2)
+DEFINE_ADD(vec3)
+"#;
+        let expected = "vec2 add_vec2(vec2 a, vec2 b) { return a + b; }\n\
+                        vec3 add_vec3(vec3 a, vec3 b) { return a + b; }";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_stringizing() {
+        let source = r#"
+#define STR(x) #x
+#define STR2(x) # x
+a = STR(hello); b = STR2(  p.x   +  1.0 );
+"#;
+        let expected = r#"a = "hello"; b = "p.x + 1.0";"#;
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_stringizing_escapes_quotes() {
+        let source = r#"
+#define STR(x) #x
+a = STR("quoted");
+"#;
+        let expected = r#"a = "\"quoted\"";"#;
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_stringizing_and_pasting_together() {
+        let source = r#"
+#define DEBUG(name) name ## _label = #name
+DEBUG(color);
+"#;
+        let expected = r#"color_label = "color";"#;
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_hash_not_followed_by_parameter_is_kept() {
+        let source = r#"
+#define F(x) x #y
+F(a);
+"#;
+        let expected = "a #y;";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+}
+
+mod variadic_macros {
+    use pretty_assertions::assert_eq;
+
+    use crate::shadertoy::glsl_preprocessor::preprocess;
+
+    #[test]
+    fn test_variadic_macro() {
+        let source = r#"
+#define CALL(f, ...) f(__VA_ARGS__)
+float a = CALL(max, 1.0, 2.0);
+"#;
+        let expected = "float a = max(1.0, 2.0);";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_variadic_macro_without_named_parameters() {
+        let source = r#"
+#define V3(...) vec3(__VA_ARGS__)
+vec3 a = V3(1.0, 2.0, 3.0); vec3 b = V3(0.5);
+"#;
+        let expected = "vec3 a = vec3(1.0, 2.0, 3.0); vec3 b = vec3(0.5);";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_variadic_macro_with_parentheses_in_arguments() {
+        let source = r#"
+#define CALL(f, ...) f(__VA_ARGS__)
+float a = CALL(mix, vec2(0.0, 1.0), b, smoothstep(0.0, 1.0, t));
+"#;
+        let expected = "float a = mix(vec2(0.0, 1.0), b, smoothstep(0.0, 1.0, t));";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_nested_variadic_invocations() {
+        let source = r#"
+#define MAX(...) max(__VA_ARGS__)
+#define CALL(f, ...) f(__VA_ARGS__)
+float a = CALL(min, MAX(x, CALL(abs, y)), 1.0);
+"#;
+        let expected = "float a = min(max(x, abs(y)), 1.0);";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_variadic_macro_with_empty_arguments() {
+        let source = r#"
+#define F(...) f(__VA_ARGS__)
+#define G(a, ...) g(a __VA_ARGS__)
+float x = F(); float y = G(1.0); float z = G(1.0,);
+"#;
+        let expected = "float x = f(); float y = g(1.0 ); float z = g(1.0 );";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_variadic_comma_elision() {
+        let source = r#"
+#define CALL(f, a, ...) f(a, ## __VA_ARGS__)
+float x = CALL(abs, t); float y = CALL(pow, t, 2.0);
+"#;
+        let expected = "float x = abs(t); float y = pow(t,2.0);";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_variadic_macro_requires_named_arguments() {
+        let source = r#"
+#define CALL(f, a, ...) f(a, __VA_ARGS__)
+CALL(abs);
+"#;
+        let expected = "CALL(abs);";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_stringizing_variadic_arguments() {
+        let source = r#"
+#define STR(...) #__VA_ARGS__
+a = STR(x, y);
+"#;
+        let expected = r#"a = "x, y";"#;
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+}
+
+mod include_directive {
+    use pretty_assertions::assert_eq;
+    use std::{env, fs, path::PathBuf};
+
+    use crate::{
+        renderer::shader::ShaderError::ShaderPreprocess,
+        shadertoy::glsl_preprocessor::{included_file, preprocess_with_line_origins},
+    };
+
+    /// Creates an empty directory for the files of a test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("shaderbg-include-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_include_file() {
+        let dir = test_dir("file");
+        fs::write(
+            dir.join("sdf.glsl"),
+            "#define RADIUS 0.5\nfloat sdCircle(vec2 p) { return length(p) - RADIUS; }\n",
+        )
+        .unwrap();
+        let source = "float a;\n#include \"sdf.glsl\"\nfloat b = RADIUS;";
+
+        let (output, line_origins) = preprocess_with_line_origins(source, Some(&dir)).unwrap();
+
+        assert_eq!(
+            output,
+            "float a;\nfloat sdCircle(vec2 p) { return length(p) - 0.5; }\nfloat b = 0.5;\n"
+        );
+        assert_eq!(line_origins, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_included_file() {
+        assert_eq!(included_file("#include \"sdf.glsl\""), Some("sdf.glsl"));
+        assert_eq!(
+            included_file("  #  include   \"lib/noise.glsl\"  "),
+            Some("lib/noise.glsl")
+        );
+        assert_eq!(included_file("#include \"\""), None);
+        assert_eq!(included_file("#include <sdf.glsl>"), None);
+        assert_eq!(included_file("#includes \"sdf.glsl\""), None);
+        assert_eq!(included_file("#define INCLUDE \"sdf.glsl\""), None);
+        assert_eq!(included_file("float include;"), None);
+    }
+
+    #[test]
+    fn test_nested_include_relative_to_including_file() {
+        let dir = test_dir("nested");
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(
+            dir.join("lib/noise.glsl"),
+            "#include \"hash.glsl\"\nfloat noise;\n",
+        )
+        .unwrap();
+        fs::write(dir.join("lib/hash.glsl"), "float hash;\n").unwrap();
+        let source = "#include \"lib/noise.glsl\"";
+
+        let (output, _) = preprocess_with_line_origins(source, Some(&dir)).unwrap();
+
+        assert_eq!(output, "float hash;\nfloat noise;\n");
+    }
+
+    #[test]
+    fn test_include_in_inactive_branch() {
+        let dir = test_dir("inactive");
+        let source = "#ifdef MISSING\n#include \"missing.glsl\"\n#endif\nfloat a;";
+
+        let (output, _) = preprocess_with_line_origins(source, Some(&dir)).unwrap();
+
+        assert_eq!(output, "float a;\n");
+    }
+
+    #[test]
+    fn test_recursive_include() {
+        let dir = test_dir("recursive");
+        fs::write(dir.join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+        fs::write(dir.join("b.glsl"), "float b;\n#include \"a.glsl\"\n").unwrap();
+        let source = "float c;\n#include \"a.glsl\"";
+
+        let error = preprocess_with_line_origins(source, Some(&dir)).unwrap_err();
+
+        assert_eq!(
+            error,
+            ShaderPreprocess(
+                "Recursive #include of a.glsl (in b.glsl, line 2) (in a.glsl, line 1)".to_string(),
+                2
+            )
+        );
+    }
+
+    #[test]
+    fn test_include_depth_limit() {
+        let dir = test_dir("depth");
+        for i in 0..20 {
+            fs::write(
+                dir.join(format!("{i}.glsl")),
+                format!("#include \"{}.glsl\"\n", i + 1),
+            )
+            .unwrap();
+        }
+        let source = "#include \"0.glsl\"";
+
+        let error = preprocess_with_line_origins(source, Some(&dir)).unwrap_err();
+
+        assert!(matches!(
+            error,
+            ShaderPreprocess(message, 1) if message.starts_with("Too many nested #include")
+        ));
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let dir = test_dir("errors");
+
+        let error = preprocess_with_line_origins("#include \"lib.glsl\"", None).unwrap_err();
+        assert_eq!(
+            error,
+            ShaderPreprocess(
+                "Cannot include lib.glsl in a shader without a preset file".to_string(),
+                1
+            )
+        );
+
+        let error = preprocess_with_line_origins("\n#include <lib.glsl>", Some(&dir)).unwrap_err();
+        assert_eq!(
+            error,
+            ShaderPreprocess("Invalid #include argument (<lib.glsl>)".to_string(), 2)
+        );
+
+        let error = preprocess_with_line_origins("#include \"lib.glsl\"", Some(&dir)).unwrap_err();
+        assert!(matches!(
+            error,
+            ShaderPreprocess(message, 1) if message.starts_with("Cannot include")
+        ));
+    }
+}
+
+mod line_macros {
+    use pretty_assertions::assert_eq;
+    use std::{env, fs};
+
+    use crate::shadertoy::glsl_preprocessor::{preprocess, preprocess_with_line_origins};
+
+    #[test]
+    fn test_line_macros_are_left_for_the_renderer() {
+        let source = "float a = 0.0;\n\nint line = __LINE__, file = __FILE__;";
+        assert_eq!(preprocess(source).unwrap().trim(), source);
+    }
+
+    #[test]
+    fn test_line_macro_in_macro_body() {
+        let source = r#"
+#define HERE __LINE__
+#define CHECK(x) if (!(x)) line = __LINE__
+int a = HERE;
+CHECK(a > 0);
+"#;
+        let (output, line_origins) = preprocess_with_line_origins(source, None).unwrap();
+        assert_eq!(
+            output.trim(),
+            "int a = __LINE__;\nif (!(a > 0)) line = __LINE__;"
+        );
+        assert_eq!(line_origins, vec![1, 4, 5]);
+    }
+
+    #[test]
+    fn test_line_macro_in_conditional() {
+        let source = r#"
+#if __LINE__ == 2 && defined(__FILE__)
+float a;
+#endif
+#ifdef __LINE__
+float b;
+#endif
+"#;
+        let expected = "float a;\nfloat b;";
+        assert_eq!(preprocess(source).unwrap().trim(), expected);
+    }
+
+    #[test]
+    fn test_line_macro_after_line_splice_in_conditional() {
+        let source = "#define A \\\n  1\n#if __LINE__ == 3\nfloat a;\n#endif";
+        assert_eq!(preprocess(source).unwrap().trim(), "float a;");
+    }
+
+    #[test]
+    fn test_line_macro_is_not_part_of_identifiers() {
+        let source = "int my__LINE__ = __LINE__x;";
+        assert_eq!(preprocess(source).unwrap().trim(), source);
+    }
+
+    #[test]
+    fn test_line_macro_in_included_file() {
+        let dir = env::temp_dir().join(format!("shaderbg-line-macro-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.glsl"), "\nint libLine = __LINE__;\n").unwrap();
+        let source = "#include \"lib.glsl\"\nint line = __LINE__;";
+
+        let (output, _) = preprocess_with_line_origins(source, Some(&dir)).unwrap();
+
+        assert_eq!(output, "\nint libLine = 2;\nint line = __LINE__;\n");
+    }
+}
@@ -0,0 +1,76 @@
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+
+use super::super::http::*;
+
+fn failure(exit_code: i32, message: &str) -> CurlFailure {
+    CurlFailure {
+        exit_code: Some(exit_code),
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn test_http_status() {
+    assert_eq!(
+        http_status("curl: (22) The requested URL returned error: 503"),
+        Some(503)
+    );
+    assert_eq!(
+        http_status("curl: (22) The requested URL returned error: 404 Not Found"),
+        Some(404)
+    );
+    assert_eq!(http_status("curl: (6) Could not resolve host: x"), None);
+}
+
+#[test]
+fn test_transient_failures() {
+    assert!(failure(6, "curl: (6) Could not resolve host: www.shadertoy.com").is_transient());
+    assert!(failure(28, "curl: (28) Operation timed out").is_transient());
+    assert!(failure(22, "curl: (22) The requested URL returned error: 502").is_transient());
+    assert!(failure(22, "curl: (22) The requested URL returned error: 429").is_transient());
+}
+
+#[test]
+fn test_permanent_failures() {
+    assert!(!failure(22, "curl: (22) The requested URL returned error: 404").is_transient());
+    assert!(!failure(22, "curl: (22) The requested URL returned error: 403").is_transient());
+    assert!(!failure(3, "curl: (3) URL using bad/illegal format").is_transient());
+    assert!(!CurlFailure {
+        exit_code: None,
+        message: "failed to run curl".to_string(),
+    }
+    .is_transient());
+}
+
+#[test]
+fn test_backoff_delay_doubles() {
+    assert_eq!(backoff_delay(1), Duration::from_secs(1));
+    assert_eq!(backoff_delay(2), Duration::from_secs(2));
+    assert_eq!(backoff_delay(3), Duration::from_secs(4));
+}
+
+#[test]
+fn test_cache_key_strips_api_key() {
+    assert_eq!(
+        cache_key("https://www.shadertoy.com/api/v1/shaders/wfjcR3?key=abc"),
+        "https://www.shadertoy.com/api/v1/shaders/wfjcR3"
+    );
+    assert_eq!(
+        cache_key("https://www.shadertoy.com/api/v1/shaders/query/sea?sort=hot&key=abc&num=5"),
+        "https://www.shadertoy.com/api/v1/shaders/query/sea?sort=hot&num=5"
+    );
+    assert_eq!(
+        cache_key("https://www.shadertoy.com/media/a/file.png"),
+        "https://www.shadertoy.com/media/a/file.png"
+    );
+}
+
+#[test]
+fn test_curl_config() {
+    assert_eq!(
+        curl_config("https://www.shadertoy.com/api/v1/shaders/XsX3RB?key=abc"),
+        "url = \"https://www.shadertoy.com/api/v1/shaders/XsX3RB?key=abc\"\n"
+    );
+    assert_eq!(curl_config(r#"a"b\c"#), "url = \"a\\\"b\\\\c\"\n");
+}
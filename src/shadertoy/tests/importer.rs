@@ -0,0 +1,138 @@
+use pretty_assertions::assert_eq;
+
+use super::super::importer::{import_from_json_str, remote_asset_stem};
+use crate::preset::*;
+
+const API_RESPONSE: &str = r#"{
+    "Shader": {
+        "ver": "0.1",
+        "info": { "id": "abcd12", "name": "Test", "username": "user", "description": "" },
+        "renderpass": [
+            {
+                "inputs": [
+                    {
+                        "id": 257,
+                        "src": "/media/previz/buffer00.png",
+                        "ctype": "buffer",
+                        "channel": 0,
+                        "sampler": { "filter": "linear", "wrap": "clamp", "vflip": "true" }
+                    }
+                ],
+                "outputs": [{ "id": 37, "channel": 0 }],
+                "code": "void mainImage(out vec4 c, in vec2 p) { c = texture(iChannel0, p); }",
+                "name": "Image",
+                "type": "image"
+            },
+            {
+                "inputs": [],
+                "outputs": [{ "id": 257, "channel": 0 }],
+                "code": "void mainImage(out vec4 c, in vec2 p) { c = vec4(1.0); }",
+                "name": "Buffer A",
+                "type": "buffer"
+            }
+        ]
+    }
+}"#;
+
+const WEBSITE_EXPORT: &str = r#"[
+    {
+        "ver": "0.1",
+        "info": { "id": "abcd12", "name": "Test", "username": "user", "description": "" },
+        "renderpass": [
+            {
+                "inputs": [
+                    {
+                        "id": "4dXGR8",
+                        "filepath": "/media/previz/buffer00.png",
+                        "previewfilepath": "/media/previz/buffer00.png",
+                        "type": "buffer",
+                        "channel": 0,
+                        "sampler": { "filter": "linear", "wrap": "clamp", "vflip": "true" },
+                        "published": 1
+                    }
+                ],
+                "outputs": [{ "id": "4dfGRr", "channel": 0 }],
+                "code": "void mainImage(out vec4 c, in vec2 p) { c = texture(iChannel0, p); }",
+                "name": "Image",
+                "description": "",
+                "type": "image"
+            },
+            {
+                "inputs": [],
+                "outputs": [{ "id": "4dXGR8", "channel": 0 }],
+                "code": "void mainImage(out vec4 c, in vec2 p) { c = vec4(1.0); }",
+                "name": "Buffer A",
+                "description": "",
+                "type": "buffer"
+            }
+        ]
+    }
+]"#;
+
+#[test]
+fn test_import_api_response() {
+    let preset = import_from_json_str(API_RESPONSE).unwrap();
+
+    assert_eq!(preset.id, "abcd12");
+    assert_eq!(preset.name, "Test");
+    assert!(preset.buffer_a.is_some());
+    let input = preset.image.input_0.unwrap();
+    assert_eq!(input.name, "Buffer A");
+    assert!(input.vflip);
+}
+
+#[test]
+fn test_import_website_export_matches_api_response() {
+    assert_eq!(
+        import_from_json_str(WEBSITE_EXPORT).unwrap(),
+        import_from_json_str(API_RESPONSE).unwrap()
+    );
+}
+
+#[test]
+fn test_import_unwrapped_shader_object() {
+    let shader = WEBSITE_EXPORT
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let preset = import_from_json_str(shader).unwrap();
+
+    assert_eq!(preset.id, "abcd12");
+    assert!(preset.buffer_a.is_some());
+}
+
+#[test]
+fn test_import_unnamed_pass_uses_type() {
+    let json = r#"[{
+        "info": { "id": "abcd12" },
+        "renderpass": [
+            { "inputs": [], "code": "// common", "name": "", "type": "common" },
+            { "inputs": [], "code": "// image", "name": "", "type": "image" }
+        ]
+    }]"#;
+    let preset = import_from_json_str(json).unwrap();
+
+    assert_eq!(preset.common.unwrap().shader, "// common");
+    assert_eq!(preset.image.shader, "// image");
+}
+
+#[test]
+fn test_import_empty_export() {
+    assert!(matches!(
+        import_from_json_str("[]"),
+        Err(PresetError::Import(_))
+    ));
+}
+
+#[test]
+fn test_remote_asset_stem() {
+    let url = "https://www.shadertoy.com/media/a/0123456789abcdef.png";
+
+    assert_eq!(remote_asset_stem(""), "cbf29ce484222325");
+    assert_eq!(remote_asset_stem("a"), "af63dc4c8601ec8c");
+    assert_eq!(remote_asset_stem(url), remote_asset_stem(url));
+    assert_ne!(
+        remote_asset_stem(url),
+        remote_asset_stem(&url.replace(".png", ".jpg"))
+    );
+}
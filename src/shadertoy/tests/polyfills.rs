@@ -0,0 +1,66 @@
+use pretty_assertions::assert_eq;
+
+use super::super::{polyfills::apply_polyfills, to_glsl_version};
+
+#[test]
+fn test_source_without_polyfilled_functions() {
+    let source = "float a = atanh(0.5) + floor(1.5);";
+    assert_eq!(
+        apply_polyfills(source, &[0, 1]),
+        (source.to_string(), String::new())
+    );
+}
+
+#[test]
+fn test_tanh_polyfill() {
+    let (source, code) = apply_polyfills("vec3 a = tanh (vec3(100.0));", &[]);
+
+    assert_eq!(source, "vec3 a = sbg_tanh (vec3(100.0));");
+    assert_eq!(code.lines().count(), 4);
+    assert!(code.contains("vec3 sbg_tanh(vec3 x) { return tanh(clamp(x, -15.0, 15.0)); }"));
+}
+
+#[test]
+fn test_round_polyfill() {
+    let (source, code) = apply_polyfills("float a = round(-2.5);", &[]);
+
+    assert_eq!(source, "float a = sbg_round(-2.5);");
+    assert!(code.contains("float sbg_round(float x) { return sign(x) * floor(abs(x) + 0.5); }"));
+    assert!(!code.contains("tanh"));
+}
+
+#[test]
+fn test_sampling_unbound_channels() {
+    let source = "vec4 a = texture(iChannel0, uv) + texelFetch(iChannel1, ivec2(f(x), 0), 0);";
+
+    let (output, code) = apply_polyfills(source, &[1]);
+
+    assert_eq!(
+        output,
+        "vec4 a = texture(iChannel0, uv) + sbg_unbound(texelFetch(iChannel1, ivec2(f(x), 0), 0));"
+    );
+    assert_eq!(
+        code,
+        "vec4 sbg_unbound(vec4 texel) { return vec4(0.0, 0.0, 0.0, 1.0); }\n"
+    );
+}
+
+#[test]
+fn test_size_queries_of_unbound_channels_are_kept() {
+    let source = "vec2 size = vec2(textureSize(iChannel2, 0));";
+    assert_eq!(
+        apply_polyfills(source, &[2]),
+        (source.to_string(), String::new())
+    );
+}
+
+#[test]
+fn test_polyfills_precede_converted_code() {
+    let source = "float a = tanh(x);\nfloat b = round(y);";
+
+    let (output, line_origins) = to_glsl_version(source, (4, 2), false, None, &[]).unwrap();
+
+    assert!(output.ends_with("\nfloat a = sbg_tanh(x);\nfloat b = sbg_round(y);"));
+    assert_eq!(line_origins.len(), output.lines().count());
+    assert_eq!(line_origins[line_origins.len() - 3..], [0, 1, 2]);
+}
@@ -0,0 +1,54 @@
+use pretty_assertions::assert_eq;
+
+use super::super::{
+    precision::{precision_warnings, translate_precision, PrecisionWarning},
+    to_glsl_version,
+};
+
+#[test]
+fn test_strip_precision_for_desktop() {
+    let source = "precision mediump float;\nhighp vec3 a;\nfloat f(lowp float x) { return x; }";
+    assert_eq!(
+        translate_precision(source, false),
+        "\nvec3 a;\nfloat f(float x) { return x; }"
+    );
+}
+
+#[test]
+fn test_promote_precision_for_es() {
+    let source = "precision mediump float;\nlowp vec3 a;\nhighp float b;";
+    assert_eq!(
+        translate_precision(source, true),
+        "precision highp float;\nhighp vec3 a;\nhighp float b;"
+    );
+}
+
+#[test]
+fn test_identifiers_containing_qualifiers_are_kept() {
+    let source = "float mediump_value; float highpass;";
+    assert_eq!(translate_precision(source, false), source);
+    assert_eq!(translate_precision(source, true), source);
+}
+
+#[test]
+fn test_conversion_keeps_line_origins() {
+    let source = "precision mediump float;\nmediump float a;";
+
+    let (output, line_origins) = to_glsl_version(source, (4, 2), false, None, &[]).unwrap();
+
+    assert_eq!(output, "\nfloat a = 0.0;");
+    assert_eq!(line_origins, vec![1, 2]);
+}
+
+#[test]
+fn test_low_precision_warnings() {
+    let source =
+        "// mediump in a comment\nhighp float a;\n#define P lowp\nmediump vec2 b; lowp int c;";
+
+    let lines: Vec<_> = precision_warnings(source)
+        .into_iter()
+        .map(|PrecisionWarning { line, .. }| line)
+        .collect();
+
+    assert_eq!(lines, vec![4, 4]);
+}
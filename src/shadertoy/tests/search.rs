@@ -0,0 +1,77 @@
+use pretty_assertions::assert_eq;
+
+use super::super::search::*;
+use crate::preset::PresetError;
+
+#[test]
+fn test_encode_url_component() {
+    assert_eq!(encode_url_component("fractal"), "fractal");
+    assert_eq!(encode_url_component("ray marching"), "ray%20marching");
+    assert_eq!(encode_url_component("a/b?c=d&e"), "a%2Fb%3Fc%3Dd%26e");
+    assert_eq!(encode_url_component("ção"), "%C3%A7%C3%A3o");
+}
+
+#[test]
+fn test_query_url() {
+    let options = SearchOptions {
+        query: "sea waves".to_string(),
+        sort: SearchSort::Newest,
+        limit: 5,
+        key: "abc".to_string(),
+        pick: false,
+    };
+
+    assert_eq!(
+        query_url(&options),
+        "https://www.shadertoy.com/api/v1/shaders/query/sea%20waves?sort=newest&from=0&num=5&key=abc"
+    );
+}
+
+#[test]
+fn test_parse_query_response() {
+    let json = r#"{ "Shaders": 2, "Results": ["XsX3RB", "4dfGzs"] }"#;
+
+    assert_eq!(
+        parse_query_response(json).unwrap(),
+        vec!["XsX3RB".to_string(), "4dfGzs".to_string()]
+    );
+}
+
+#[test]
+fn test_parse_query_response_without_results() {
+    assert_eq!(
+        parse_query_response(r#"{ "Shaders": 0 }"#).unwrap(),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_parse_query_response_error() {
+    assert!(matches!(
+        parse_query_response(r#"{ "Error": "Invalid key" }"#),
+        Err(PresetError::Import(msg)) if msg.contains("Invalid key")
+    ));
+}
+
+#[test]
+fn test_parse_shader_response() {
+    let json = r#"{ "Shader": { "info": { "id": "XsX3RB", "name": "Sea", "username": "iq" }, "renderpass": [] } }"#;
+    let result = parse_shader_response(json.to_string()).unwrap();
+
+    assert_eq!(result.id, "XsX3RB");
+    assert_eq!(result.name, "Sea");
+    assert_eq!(result.username, "iq");
+    assert_eq!(result.response, json);
+}
+
+#[test]
+fn test_parse_shader_response_error() {
+    assert!(matches!(
+        parse_shader_response(r#"{ "Error": "Shader not found" }"#.to_string()),
+        Err(PresetError::Import(msg)) if msg.contains("Shader not found")
+    ));
+    assert!(matches!(
+        parse_shader_response(r#"{ "Shader": {} }"#.to_string()),
+        Err(PresetError::Import(_))
+    ));
+}
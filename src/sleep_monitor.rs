@@ -0,0 +1,125 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! System suspend detection.
+//!
+//! Listens to the `PrepareForSleep` signal of systemd-logind over the
+//! system D-Bus so that rendering can be paused before the machine
+//! suspends and resumed after it wakes up.
+//!
+//! A delay inhibitor lock (as taken by `systemd-inhibit --mode=delay`)
+//! is held while awake, giving the wallpaper a chance to pause before
+//! the system goes to sleep. The lock is released once the pause has
+//! been handled and taken again after resuming.
+
+use gtk::{
+    gio::{self, prelude::*},
+    glib,
+};
+use std::{cell::RefCell, os::fd::OwnedFd, rc::Rc};
+use thiserror::Error;
+
+use crate::APP_NAME;
+
+const LOGIND_NAME: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_INTERFACE: &str = "org.freedesktop.login1.Manager";
+const PREPARE_FOR_SLEEP_SIGNAL: &str = "PrepareForSleep";
+
+#[derive(Debug, Error)]
+pub enum SleepError {
+    #[error("Failed to connect to logind: {0}")]
+    DBus(#[from] glib::Error),
+
+    #[error("logind is not running")]
+    Unavailable,
+}
+
+/// Watches logind for suspend and resume.
+pub struct SleepMonitor {
+    _proxy: gio::DBusProxy,
+}
+
+impl SleepMonitor {
+    /// Connects to logind.
+    ///
+    /// `on_change` is invoked from the main loop with `true` right before
+    /// the system suspends, and with `false` after it resumes.
+    pub fn new<F>(on_change: F) -> Result<Self, SleepError>
+    where
+        F: Fn(bool) + 'static,
+    {
+        let proxy = gio::DBusProxy::for_bus_sync(
+            gio::BusType::System,
+            gio::DBusProxyFlags::DO_NOT_LOAD_PROPERTIES | gio::DBusProxyFlags::DO_NOT_AUTO_START,
+            None,
+            LOGIND_NAME,
+            LOGIND_PATH,
+            LOGIND_INTERFACE,
+            None::<&gio::Cancellable>,
+        )?;
+
+        if proxy.name_owner().is_none() {
+            return Err(SleepError::Unavailable);
+        }
+
+        let inhibitor = Rc::new(RefCell::new(take_inhibitor_lock(&proxy)));
+
+        proxy.connect_local("g-signal", false, move |values| {
+            let proxy = values.first()?.get::<gio::DBusProxy>().ok()?;
+            let signal_name = values.get(2)?.get::<String>().ok()?;
+            if signal_name != PREPARE_FOR_SLEEP_SIGNAL {
+                return None;
+            }
+
+            let parameters = values.get(3)?.get::<glib::Variant>().ok()?;
+            let (start,) = parameters.get::<(bool,)>()?;
+            on_change(start);
+
+            // Dropping the lock lets the suspend proceed
+            *inhibitor.borrow_mut() = if start {
+                None
+            } else {
+                take_inhibitor_lock(&proxy)
+            };
+            None
+        });
+
+        Ok(Self { _proxy: proxy })
+    }
+}
+
+/// Takes a delay inhibitor lock on sleep, which is held until the
+/// returned file descriptor is closed.
+fn take_inhibitor_lock(proxy: &gio::DBusProxy) -> Option<OwnedFd> {
+    let parameters = ("sleep", APP_NAME, "Pause wallpaper animation", "delay").to_variant();
+    let result = proxy
+        .call_with_unix_fd_list_sync(
+            "Inhibit",
+            Some(&parameters),
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::UnixFDList>,
+            None::<&gio::Cancellable>,
+        )
+        .and_then(|(value, fd_list)| {
+            let index = value
+                .get::<(glib::variant::Handle,)>()
+                .map_or(0, |(h,)| h.0);
+            fd_list
+                .ok_or_else(|| {
+                    glib::Error::new(gio::IOErrorEnum::Failed, "No file descriptor returned")
+                })?
+                .get(index)
+        });
+
+    match result {
+        Ok(fd) => Some(fd),
+        Err(err) => {
+            log::warn!("Failed to take sleep inhibitor lock: {err}");
+            None
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sun position exposed to shaders through `iDayPhase` and `iSunPosition`.
+//!
+//! Implements the NOAA solar position equations, accurate to a fraction
+//! of a degree, for the location of the configuration file. Without a
+//! location, the longitude is estimated from the time zone and the
+//! latitude is the equator's.
+
+use chrono::{DateTime, Local, NaiveDateTime, Offset, TimeZone, Utc};
+use serde::Deserialize;
+
+/// Julian day of the Unix epoch.
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+
+/// Julian day of the J2000.0 epoch.
+const J2000_JULIAN_DAY: f64 = 2451545.0;
+
+const MINUTES_PER_DAY: f64 = 1440.0;
+
+/// Geographic location, in degrees.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Location {
+    /// Latitude, positive north of the equator.
+    pub latitude: f64,
+
+    /// Longitude, positive east of Greenwich.
+    pub longitude: f64,
+}
+
+/// Position of the sun at a given time and location.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SunPosition {
+    /// Fraction of the solar day elapsed: 0 at solar midnight, 0.5 at
+    /// solar noon.
+    pub day_phase: f32,
+
+    /// Angle above the horizon, in radians. Negative at night.
+    pub elevation: f32,
+
+    /// Angle clockwise from north, in radians, in `[0, 2π)`.
+    pub azimuth: f32,
+}
+
+impl SunPosition {
+    /// Computes the sun position at a local date and time, for the
+    /// location of the configuration file.
+    pub fn at_local(date: NaiveDateTime) -> Self {
+        let time = Local
+            .from_local_datetime(&date)
+            .earliest()
+            .unwrap_or_else(|| Local.from_utc_datetime(&date));
+        let location = crate::config::config()
+            .location
+            .unwrap_or_else(|| Location {
+                latitude: 0.0,
+                longitude: time.offset().fix().local_minus_utc() as f64 / 240.0,
+            });
+        Self::at(time.with_timezone(&Utc), location)
+    }
+
+    /// Computes the sun position at a time and location.
+    pub fn at(time: DateTime<Utc>, location: Location) -> Self {
+        let julian_day = time.timestamp_millis() as f64 / 86_400_000.0 + UNIX_EPOCH_JULIAN_DAY;
+        let t = (julian_day - J2000_JULIAN_DAY) / 36525.0;
+
+        // Geometric mean longitude and anomaly of the sun, and
+        // eccentricity of the orbit of the Earth
+        let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+        let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+        let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+
+        let m = mean_anomaly.to_radians();
+        let center = m.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+            + (2.0 * m).sin() * (0.019993 - 0.000101 * t)
+            + (3.0 * m).sin() * 0.000289;
+        let omega = (125.04 - 1934.136 * t).to_radians();
+        let apparent_longitude =
+            (mean_longitude + center - 0.00569 - 0.00478 * omega.sin()).to_radians();
+
+        let mean_obliquity =
+            23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+        let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+        let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+        // Equation of time, in minutes
+        let y = (obliquity / 2.0).tan().powi(2);
+        let l = mean_longitude.to_radians();
+        let equation_of_time = 4.0
+            * (y * (2.0 * l).sin() - 2.0 * eccentricity * m.sin()
+                + 4.0 * eccentricity * y * m.sin() * (2.0 * l).cos()
+                - 0.5 * y * y * (4.0 * l).sin()
+                - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+            .to_degrees();
+
+        let utc_minutes = time.timestamp_millis().rem_euclid(86_400_000) as f64 / 60_000.0;
+        let solar_minutes =
+            (utc_minutes + equation_of_time + 4.0 * location.longitude).rem_euclid(MINUTES_PER_DAY);
+        let hour_angle = (solar_minutes / 4.0 - 180.0).to_radians();
+
+        let latitude = location.latitude.clamp(-90.0, 90.0).to_radians();
+        let elevation = (latitude.sin() * declination.sin()
+            + latitude.cos() * declination.cos() * hour_angle.cos())
+        .clamp(-1.0, 1.0)
+        .asin();
+        let azimuth = (hour_angle.sin())
+            .atan2(hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos())
+            + std::f64::consts::PI;
+
+        Self {
+            day_phase: (solar_minutes / MINUTES_PER_DAY) as f32,
+            elevation: elevation as f32,
+            azimuth: azimuth.rem_euclid(std::f64::consts::TAU) as f32,
+        }
+    }
+}
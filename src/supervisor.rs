@@ -0,0 +1,166 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Recovery from crashes of the wallpaper.
+//!
+//! A panic in the renderer, e.g., on an incomplete framebuffer, aborts
+//! the process when it unwinds into a GTK callback, leaving the desktop
+//! without a wallpaper. The panic hook writes a crash report to
+//! `$XDG_CACHE_HOME/shaderbg/crash.log`, and `--supervise` runs ShaderBG
+//! in a child process that is restarted with the default preset
+//! (`--safe-mode`) when it crashes.
+
+use std::{
+    backtrace::Backtrace,
+    env,
+    ffi::OsString,
+    fs, io,
+    os::unix::process::{CommandExt, ExitStatusExt},
+    panic,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    time::{Duration, Instant},
+};
+
+use gtk::glib;
+
+use crate::APP_NAME;
+
+/// Option running the wallpaper under a supervisor.
+const SUPERVISE_ARG: &str = "--supervise";
+
+/// Option running the default preset instead of the given one.
+const SAFE_MODE_ARG: &str = "--safe-mode";
+
+/// Exit code of a process whose main thread panicked while unwinding.
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Signals taken as a crash rather than as a request to quit.
+const CRASH_SIGNALS: [i32; 5] = [
+    libc::SIGABRT,
+    libc::SIGBUS,
+    libc::SIGFPE,
+    libc::SIGILL,
+    libc::SIGSEGV,
+];
+
+/// A safe mode run that crashes sooner than this is not restarted, so
+/// that a wallpaper that cannot run at all does not restart forever.
+const MIN_SAFE_MODE_UPTIME: Duration = Duration::from_secs(30);
+
+/// Returns the path of the crash report.
+pub fn crash_report_path() -> io::Result<PathBuf> {
+    Ok(crate::config::cache_dir()?.join("crash.log"))
+}
+
+/// Installs a panic hook that logs the panic with a backtrace and writes
+/// it to the crash report before the default hook runs.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let report = format!(
+            "{APP_NAME} {} crashed at {}\n{info}\n\nBacktrace:\n{}\n",
+            crate::APP_SEMVER,
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            Backtrace::force_capture()
+        );
+        log::error!("{info}");
+
+        match crash_report_path().and_then(|path| write_crash_report(&path, &report).map(|()| path))
+        {
+            Ok(path) => log::error!("Crash report saved to {}", path.display()),
+            Err(err) => log::error!("Failed to save crash report: {err}"),
+        }
+        log::logger().flush();
+
+        default_hook(info);
+    }));
+}
+
+fn write_crash_report(path: &Path, report: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, report)
+}
+
+/// Runs ShaderBG with the same arguments in a child process, restarting
+/// it with the default preset whenever it crashes.
+///
+/// Returns the exit code of the last child.
+pub fn run() -> glib::ExitCode {
+    let args: Vec<OsString> = env::args_os()
+        .skip(1)
+        .filter(|arg| arg != SUPERVISE_ARG)
+        .collect();
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => {
+            log::error!("Failed to locate the {APP_NAME} executable: {err}");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let mut safe_mode = args.iter().any(|arg| arg == SAFE_MODE_ARG);
+    loop {
+        let mut command = Command::new(&exe);
+        command.args(&args);
+        if safe_mode && !args.iter().any(|arg| arg == SAFE_MODE_ARG) {
+            command.arg(SAFE_MODE_ARG);
+        }
+        // The wallpaper must not outlive the supervisor
+        unsafe {
+            command.pre_exec(|| {
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let started = Instant::now();
+        let status = match command.status() {
+            Ok(status) => status,
+            Err(err) => {
+                log::error!("Failed to start {APP_NAME}: {err}");
+                return glib::ExitCode::FAILURE;
+            }
+        };
+
+        if !is_crash(status) {
+            return exit_code(status);
+        }
+        if safe_mode && started.elapsed() < MIN_SAFE_MODE_UPTIME {
+            log::error!("{APP_NAME} crashed ({status}) with the default preset, giving up");
+            return glib::ExitCode::FAILURE;
+        }
+
+        match crash_report_path() {
+            Ok(path) => log::error!(
+                "{APP_NAME} crashed ({status}), restarting with the default preset. See {}",
+                path.display()
+            ),
+            Err(_) => {
+                log::error!("{APP_NAME} crashed ({status}), restarting with the default preset")
+            }
+        }
+        safe_mode = true;
+    }
+}
+
+/// Returns `true` if the child panicked or was killed by a fault.
+fn is_crash(status: ExitStatus) -> bool {
+    status.code() == Some(PANIC_EXIT_CODE)
+        || status
+            .signal()
+            .is_some_and(|signal| CRASH_SIGNALS.contains(&signal))
+}
+
+fn exit_code(status: ExitStatus) -> glib::ExitCode {
+    match status.code() {
+        Some(code) => glib::ExitCode::from(code.clamp(0, u8::MAX as i32) as u8),
+        None => glib::ExitCode::FAILURE,
+    }
+}
@@ -0,0 +1,298 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! System statistics exposed to shaders through `iSystemStats`.
+//!
+//! Samples CPU, memory, network and temperature information from
+//! `/proc` and `/sys` so that presets can build system-monitor
+//! wallpapers purely in GLSL. The uniform is laid out as:
+//!
+//! | Index | x               | y                | z                    | w                        |
+//! |-------|-----------------|------------------|----------------------|--------------------------|
+//! | 0     | CPU usage       | User time        | System time          | I/O wait                 |
+//! | 1     | Memory used     | Swap used        | Memory used (GiB)    | Memory total (GiB)       |
+//! | 2     | Upload (KiB/s)  | Download (KiB/s) | Upload / peak upload | Download / peak download |
+//! | 3     | CPU temp. (°C)  | Max. temp. (°C)  | 1-minute load        | Number of CPUs           |
+//!
+//! Fractions are in the range [0, 1]. Unavailable values are zero.
+
+use std::{fs, path::Path, time::Instant};
+
+/// Bytes per kibibyte.
+const KIB: f32 = 1024.0;
+
+/// Kibibytes per gibibyte (`/proc/meminfo` reports kibibytes).
+const KIB_PER_GIB: f32 = 1024.0 * 1024.0;
+
+/// Hardware monitor drivers reporting the CPU package temperature.
+const CPU_HWMON_NAMES: &[&str] = &["coretemp", "k10temp", "zenpower", "cpu_thermal"];
+
+/// Thermal zone types reporting the CPU package temperature.
+const CPU_THERMAL_ZONE_TYPES: &[&str] = &["x86_pkg_temp", "cpu-thermal", "soc_thermal"];
+
+/// Values of the `iSystemStats` uniform.
+pub type SystemStats = [[f32; 4]; 4];
+
+/// Cumulative CPU times read from the first line of `/proc/stat`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub total: u64,
+}
+
+/// Cumulative network traffic over all non-loopback interfaces.
+#[derive(Clone, Copy)]
+struct NetBytes {
+    sent: u64,
+    received: u64,
+    at: Instant,
+}
+
+/// Samples system statistics.
+///
+/// CPU usage and network rates are computed from the difference between
+/// consecutive samples, so the first sample reports CPU usage averaged
+/// since boot and no network traffic.
+#[derive(Default)]
+pub struct SystemStatsSampler {
+    previous_cpu: Option<CpuTimes>,
+    previous_net: Option<NetBytes>,
+    peak_upload: f32,
+    peak_download: f32,
+    stats: SystemStats,
+}
+
+impl SystemStatsSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the most recent sample.
+    pub fn stats(&self) -> SystemStats {
+        self.stats
+    }
+
+    /// Reads the current statistics.
+    pub fn sample(&mut self) {
+        self.stats = [
+            self.sample_cpu(),
+            sample_memory(),
+            self.sample_network(),
+            sample_temperature_and_load(),
+        ];
+    }
+
+    fn sample_cpu(&mut self) -> [f32; 4] {
+        let Some(current) = fs::read_to_string("/proc/stat")
+            .ok()
+            .and_then(|stat| parse_cpu_times(&stat))
+        else {
+            return [0.0; 4];
+        };
+        let previous = self.previous_cpu.replace(current).unwrap_or_default();
+
+        let total = current.total.saturating_sub(previous.total);
+        if total == 0 {
+            return self.stats[0];
+        }
+        let fraction =
+            |current: u64, previous: u64| current.saturating_sub(previous) as f32 / total as f32;
+        let idle = fraction(current.idle, previous.idle);
+        let iowait = fraction(current.iowait, previous.iowait);
+
+        [
+            (1.0 - idle - iowait).clamp(0.0, 1.0),
+            fraction(current.user, previous.user),
+            fraction(current.system, previous.system),
+            iowait,
+        ]
+    }
+
+    fn sample_network(&mut self) -> [f32; 4] {
+        let Some((sent, received)) = fs::read_to_string("/proc/net/dev")
+            .ok()
+            .and_then(|dev| parse_net_bytes(&dev))
+        else {
+            return [0.0; 4];
+        };
+        let current = NetBytes {
+            sent,
+            received,
+            at: Instant::now(),
+        };
+        let Some(previous) = self.previous_net.replace(current) else {
+            return [0.0; 4];
+        };
+
+        let elapsed = current.at.duration_since(previous.at).as_secs_f32();
+        if elapsed <= 0.0 {
+            return self.stats[2];
+        }
+        let upload = current.sent.saturating_sub(previous.sent) as f32 / KIB / elapsed;
+        let download = current.received.saturating_sub(previous.received) as f32 / KIB / elapsed;
+        self.peak_upload = self.peak_upload.max(upload);
+        self.peak_download = self.peak_download.max(download);
+
+        let normalize = |rate: f32, peak: f32| if peak > 0.0 { rate / peak } else { 0.0 };
+        [
+            upload,
+            download,
+            normalize(upload, self.peak_upload),
+            normalize(download, self.peak_download),
+        ]
+    }
+}
+
+fn sample_memory() -> [f32; 4] {
+    let Ok(meminfo) = fs::read_to_string("/proc/meminfo") else {
+        return [0.0; 4];
+    };
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.split_whitespace().next()?.parse::<u64>().ok()
+        })
+    };
+
+    let total = field("MemTotal").unwrap_or(0);
+    let available = field("MemAvailable").unwrap_or(total);
+    let swap_total = field("SwapTotal").unwrap_or(0);
+    let swap_free = field("SwapFree").unwrap_or(swap_total);
+
+    let used = total.saturating_sub(available);
+    let fraction = |used: u64, total: u64| {
+        if total > 0 {
+            used as f32 / total as f32
+        } else {
+            0.0
+        }
+    };
+
+    [
+        fraction(used, total),
+        fraction(swap_total.saturating_sub(swap_free), swap_total),
+        used as f32 / KIB_PER_GIB,
+        total as f32 / KIB_PER_GIB,
+    ]
+}
+
+fn sample_temperature_and_load() -> [f32; 4] {
+    let load = fs::read_to_string("/proc/loadavg")
+        .ok()
+        .and_then(|loadavg| loadavg.split_whitespace().next()?.parse::<f32>().ok())
+        .unwrap_or(0.0);
+    let cpus = std::thread::available_parallelism()
+        .map(|n| n.get() as f32)
+        .unwrap_or(0.0);
+    let (cpu_temperature, max_temperature) = read_temperatures();
+
+    [cpu_temperature, max_temperature, load, cpus]
+}
+
+/// Returns the CPU temperature and the highest temperature reported by
+/// any sensor, in degrees Celsius.
+///
+/// The CPU temperature falls back to the highest temperature when no
+/// known CPU sensor is found.
+fn read_temperatures() -> (f32, f32) {
+    let mut cpu_temperature = None;
+    let mut max_temperature = 0.0f32;
+
+    for entry in read_dir_paths(Path::new("/sys/class/hwmon")) {
+        let name = read_trimmed(&entry.join("name")).unwrap_or_default();
+        let Some(temperature) = read_millidegrees(&entry.join("temp1_input")) else {
+            continue;
+        };
+        max_temperature = max_temperature.max(temperature);
+        if cpu_temperature.is_none() && CPU_HWMON_NAMES.contains(&name.as_str()) {
+            cpu_temperature = Some(temperature);
+        }
+    }
+
+    for entry in read_dir_paths(Path::new("/sys/class/thermal")) {
+        let Some(temperature) = read_millidegrees(&entry.join("temp")) else {
+            continue;
+        };
+        max_temperature = max_temperature.max(temperature);
+        let zone_type = read_trimmed(&entry.join("type")).unwrap_or_default();
+        if cpu_temperature.is_none() && CPU_THERMAL_ZONE_TYPES.contains(&zone_type.as_str()) {
+            cpu_temperature = Some(temperature);
+        }
+    }
+
+    (cpu_temperature.unwrap_or(max_temperature), max_temperature)
+}
+
+fn read_dir_paths(dir: &Path) -> Vec<std::path::PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    read_trimmed(path)?
+        .parse::<i64>()
+        .ok()
+        .map(|millidegrees| millidegrees as f32 / 1000.0)
+}
+
+/// Parses the aggregate `cpu` line of `/proc/stat`.
+pub fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    if values.len() < 5 {
+        return None;
+    }
+
+    // user nice system idle iowait irq softirq steal [guest guest_nice]
+    // Guest time is already accounted for in user and nice.
+    Some(CpuTimes {
+        user: values[0] + values[1],
+        system: values[2],
+        idle: values[3],
+        iowait: values[4],
+        total: values.iter().take(8).sum(),
+    })
+}
+
+/// Parses `/proc/net/dev`, returning the bytes sent and received by all
+/// interfaces except loopback.
+pub fn parse_net_bytes(dev: &str) -> Option<(u64, u64)> {
+    let mut sent = 0;
+    let mut received = 0;
+    let mut found = false;
+
+    for line in dev.lines().skip(2) {
+        let Some((interface, counters)) = line.split_once(':') else {
+            continue;
+        };
+        if interface.trim() == "lo" {
+            continue;
+        }
+        let counters: Vec<u64> = counters
+            .split_whitespace()
+            .filter_map(|value| value.parse().ok())
+            .collect();
+        if counters.len() < 9 {
+            continue;
+        }
+        received += counters[0];
+        sent += counters[8];
+        found = true;
+    }
+
+    found.then_some((sent, received))
+}
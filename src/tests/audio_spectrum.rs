@@ -0,0 +1,71 @@
+use pretty_assertions::assert_eq;
+use std::f32::consts::PI;
+
+use super::super::audio_spectrum::{band_edges, fft};
+
+fn magnitudes(real: &[f32], imag: &[f32]) -> Vec<f32> {
+    real.iter().zip(imag).map(|(r, i)| r.hypot(*i)).collect()
+}
+
+#[test]
+fn test_fft_of_impulse_is_flat() {
+    let mut real = vec![0.0; 16];
+    let mut imag = vec![0.0; 16];
+    real[0] = 1.0;
+
+    fft(&mut real, &mut imag);
+
+    assert_eq!(real, vec![1.0; 16]);
+    assert_eq!(imag, vec![0.0; 16]);
+}
+
+#[test]
+fn test_fft_of_sine_peaks_at_its_bin() {
+    const N: usize = 64;
+    const BIN: usize = 5;
+    let mut real: Vec<f32> = (0..N)
+        .map(|i| (2.0 * PI * (BIN * i) as f32 / N as f32).sin())
+        .collect();
+    let mut imag = vec![0.0; N];
+
+    fft(&mut real, &mut imag);
+
+    for (bin, magnitude) in magnitudes(&real, &imag).into_iter().enumerate() {
+        let expected = if bin == BIN || bin == N - BIN {
+            N as f32 / 2.0
+        } else {
+            0.0
+        };
+        assert!(
+            (magnitude - expected).abs() < 1e-3,
+            "bin {bin}: {magnitude} != {expected}"
+        );
+    }
+    // A sine has a negative imaginary component at its positive frequency
+    assert!(imag[BIN] < 0.0);
+}
+
+#[test]
+fn test_band_edges_cover_frequency_range() {
+    let edges = band_edges(32);
+
+    assert_eq!(edges.len(), 32);
+    // 50 Hz and 16 kHz with bins of 44100 / 2048 Hz
+    assert_eq!(edges.first().unwrap().0, 2);
+    assert_eq!(edges.last().unwrap().1, 743);
+    for window in edges.windows(2) {
+        assert!(window[0].0 <= window[1].0);
+        assert!(window[0].1 <= window[1].1);
+    }
+}
+
+#[test]
+fn test_band_edges_have_at_least_one_bin() {
+    for band_count in [1, 16, 64] {
+        for (first, last) in band_edges(band_count) {
+            assert!(first >= 1);
+            assert!(first < last);
+            assert!(last <= 1024);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use pretty_assertions::assert_eq;
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use super::super::bundle::*;
+use crate::preset::PresetError;
+
+/// Creates an empty directory for the files of a test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("shaderbg-bundle-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn entries(names: &[&str]) -> Vec<(String, Vec<u8>)> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.to_string(), vec![index as u8; index * 300]))
+        .collect()
+}
+
+/// Returns a tar header for a regular file, as written by [`write_tar`].
+fn header(name: &str, size: usize) -> Vec<u8> {
+    let mut archive = Vec::new();
+    write_tar(&mut archive, &[(name.to_string(), vec![0; size])]).unwrap();
+    archive.truncate(512);
+    archive
+}
+
+fn assert_rejected(archive: &[u8], reason: &str) {
+    assert!(
+        matches!(read_tar(archive), Err(PresetError::Bundle(msg)) if msg.contains(reason)),
+        "expected '{reason}'"
+    );
+}
+
+#[test]
+fn test_tar_round_trip() {
+    let entries = entries(&[
+        "preset.toml",
+        "shaders/image.glsl",
+        "files/1_empty.png",
+        "files/2_large.png",
+    ]);
+    let mut archive = Vec::new();
+    write_tar(&mut archive, &entries).unwrap();
+
+    assert_eq!(archive.len() % 512, 0);
+    assert_eq!(read_tar(&archive).unwrap(), entries);
+}
+
+#[test]
+fn test_tar_empty_archive() {
+    let mut archive = Vec::new();
+    write_tar(&mut archive, &[]).unwrap();
+
+    assert_eq!(read_tar(&archive).unwrap(), Vec::new());
+    assert_eq!(read_tar(&[]).unwrap(), Vec::new());
+}
+
+#[test]
+fn test_tar_long_name_is_rejected() {
+    let name = "a".repeat(100);
+
+    assert!(matches!(
+        write_tar(&mut Vec::new(), &[(name, Vec::new())]),
+        Err(PresetError::Bundle(_))
+    ));
+}
+
+#[test]
+fn test_tar_unsafe_names_are_rejected() {
+    for name in ["../evil", "files/../../evil", "/etc/passwd", "", "."] {
+        let mut archive = header(name, 0);
+        archive.extend_from_slice(&[0; 1024]);
+        assert_rejected(&archive, "unsafe file name");
+    }
+}
+
+#[test]
+fn test_tar_unsafe_prefix_is_rejected() {
+    let mut archive = header("evil", 0);
+    archive[345..347].copy_from_slice(b"..");
+    assert_rejected(&archive, "unsafe file name");
+}
+
+#[test]
+fn test_tar_prefix_is_joined() {
+    let mut archive = header("image.glsl", 0);
+    archive[345..352].copy_from_slice(b"shaders");
+
+    assert_eq!(
+        read_tar(&archive).unwrap(),
+        vec![("shaders/image.glsl".to_string(), Vec::new())]
+    );
+}
+
+#[test]
+fn test_tar_truncated_archive_is_rejected() {
+    let mut archive = Vec::new();
+    write_tar(&mut archive, &entries(&["a", "b"])).unwrap();
+
+    assert_rejected(&archive[..2 * 512 + 100], "truncated archive");
+}
+
+#[test]
+fn test_tar_huge_size_is_rejected() {
+    let mut archive = header("huge", 0);
+    archive[124..136].copy_from_slice(b"77777777777\0");
+    assert_rejected(&archive, "truncated archive");
+
+    archive[124..136].copy_from_slice(b"not a size\0\0");
+    assert_rejected(&archive, "invalid file size");
+}
+
+#[test]
+fn test_tar_links_and_directories_are_skipped() {
+    let mut archive = header("link", 0);
+    archive[156] = b'2';
+    let mut directory = header("dir", 0);
+    directory[156] = b'5';
+    archive.extend(directory);
+    archive.extend(header("file", 0));
+
+    assert_eq!(
+        read_tar(&archive).unwrap(),
+        vec![("file".to_string(), Vec::new())]
+    );
+}
+
+#[test]
+fn test_truncate_file_name() {
+    assert_eq!(truncate_file_name("texture.png", 20), "texture.png");
+    assert_eq!(truncate_file_name("a_long_texture.png", 10), "a_long.png");
+    assert_eq!(truncate_file_name("no_extension", 5), "no_ex");
+    assert_eq!(truncate_file_name("ção.png", 6), "ç.png");
+}
+
+#[test]
+fn test_unique_entry_name() {
+    let entries = entries(&["shaders/buffer_a.glsl", "shaders/buffer_a_2.glsl"]);
+
+    assert_eq!(
+        unique_entry_name(&entries, "shaders/image", "glsl"),
+        "shaders/image.glsl"
+    );
+    assert_eq!(
+        unique_entry_name(&entries, "shaders/buffer_a", "glsl"),
+        "shaders/buffer_a_3.glsl"
+    );
+}
+
+#[test]
+fn test_include_entries() {
+    let dir = test_dir("includes");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(
+        dir.join("lib/noise.glsl"),
+        "#include \"hash.glsl\"\nfloat noise;\n",
+    )
+    .unwrap();
+    fs::write(dir.join("lib/hash.glsl"), "float hash;\n").unwrap();
+    let source = "#include \"lib/noise.glsl\" // noise\n#include \"lib/hash.glsl\"\n#include \"missing.glsl\"\nvoid main() {}";
+
+    let mut entries = Vec::new();
+    let mut includes = HashMap::new();
+    let output = add_include_entries(&mut entries, &mut includes, source, &dir, false).unwrap();
+
+    assert_eq!(
+        output,
+        "#include \"includes/0_noise.glsl\" // noise\n#include \"includes/1_hash.glsl\"\n#include \"missing.glsl\"\nvoid main() {}"
+    );
+    assert_eq!(
+        entries,
+        vec![
+            (
+                "includes/1_hash.glsl".to_string(),
+                b"float hash;\n".to_vec()
+            ),
+            (
+                "includes/0_noise.glsl".to_string(),
+                b"#include \"1_hash.glsl\"\nfloat noise;\n".to_vec()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_recursive_include_entries() {
+    let dir = test_dir("recursive");
+    fs::write(dir.join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+    fs::write(dir.join("b.glsl"), "#include \"a.glsl\"\n").unwrap();
+
+    let mut entries = Vec::new();
+    let mut includes = HashMap::new();
+    let output = add_include_entries(
+        &mut entries,
+        &mut includes,
+        "#include \"a.glsl\"",
+        &dir,
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(output, "#include \"includes/0_a.glsl\"");
+    assert_eq!(
+        entries,
+        vec![
+            (
+                "includes/1_b.glsl".to_string(),
+                b"#include \"0_a.glsl\"\n".to_vec()
+            ),
+            (
+                "includes/0_a.glsl".to_string(),
+                b"#include \"1_b.glsl\"\n".to_vec()
+            ),
+        ]
+    );
+}
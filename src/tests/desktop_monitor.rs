@@ -0,0 +1,127 @@
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+
+use super::super::desktop_monitor::*;
+
+fn sway_window(pid: u64, fullscreen_mode: u64) -> Value {
+    json!({ "type": "con", "pid": pid, "fullscreen_mode": fullscreen_mode, "nodes": [] })
+}
+
+fn sway_tree() -> Value {
+    json!({
+        "type": "root",
+        "nodes": [
+            {
+                "type": "output",
+                "name": "__i3",
+                "nodes": [{ "type": "workspace", "name": "__i3_scratch", "floating_nodes": [sway_window(9, 0)] }]
+            },
+            {
+                "type": "output",
+                "name": "DP-1",
+                "nodes": [
+                    {
+                        "type": "workspace",
+                        "name": "1",
+                        "nodes": [
+                            sway_window(1, 0),
+                            { "type": "con", "layout": "splitv", "nodes": [sway_window(2, 0), sway_window(3, 1)] }
+                        ],
+                        "floating_nodes": [sway_window(4, 0)]
+                    },
+                    { "type": "workspace", "name": "2: web", "nodes": [sway_window(5, 0)] }
+                ]
+            }
+        ]
+    })
+}
+
+#[test]
+fn test_hyprland_state() {
+    let active = json!({ "id": 3, "windows": 2, "hasfullscreen": true });
+    let workspaces = json!([
+        { "id": 1, "windows": 4 },
+        { "id": 3, "windows": 2 },
+        { "id": -98, "windows": 1 }
+    ]);
+
+    assert_eq!(
+        hyprland_state(&active, &workspaces),
+        Some([3.0, 2.0, 1.0, 7.0])
+    );
+}
+
+#[test]
+fn test_hyprland_state_missing_fields() {
+    let workspaces = json!([{ "id": 1 }]);
+
+    assert_eq!(
+        hyprland_state(&json!({ "id": 1 }), &workspaces),
+        Some([1.0, 0.0, 0.0, 0.0])
+    );
+    assert_eq!(hyprland_state(&json!({}), &workspaces), None);
+    assert_eq!(hyprland_state(&json!({ "id": 1 }), &json!({})), None);
+}
+
+#[test]
+fn test_sway_state() {
+    let workspaces = json!([
+        { "num": 1, "name": "1", "focused": true },
+        { "num": 2, "name": "2: web", "focused": false }
+    ]);
+
+    // The scratchpad is not counted
+    assert_eq!(
+        sway_state(&workspaces, &sway_tree()),
+        Some([1.0, 4.0, 1.0, 5.0])
+    );
+}
+
+#[test]
+fn test_sway_state_named_workspace() {
+    let workspaces = json!([
+        { "num": 1, "name": "1", "focused": false },
+        { "num": 2, "name": "2: web", "focused": true }
+    ]);
+
+    assert_eq!(
+        sway_state(&workspaces, &sway_tree()),
+        Some([2.0, 1.0, 0.0, 5.0])
+    );
+}
+
+#[test]
+fn test_sway_state_without_focused_workspace() {
+    let workspaces = json!([{ "num": 1, "name": "1", "focused": false }]);
+
+    assert_eq!(sway_state(&workspaces, &sway_tree()), None);
+    assert_eq!(sway_state(&json!({}), &sway_tree()), None);
+}
+
+#[test]
+fn test_focused_output_name() {
+    let outputs = json!([
+        { "name": "HDMI-A-1", "focused": false },
+        { "name": "DP-1", "focused": true }
+    ]);
+
+    assert_eq!(focused_output_name(&outputs), Some("DP-1".to_string()));
+    assert_eq!(focused_output_name(&json!([{ "name": "DP-1" }])), None);
+    assert_eq!(focused_output_name(&json!("DP-1")), None);
+}
+
+#[test]
+fn test_hyprland_events() {
+    assert!(is_hyprland_change("openwindow>>80a6f50,2,kitty,Kitty\n"));
+    assert!(is_hyprland_change("workspace>>2"));
+    assert!(!is_hyprland_change("activewindow>>kitty,~\n"));
+    assert!(!is_hyprland_change("windowtitle>>80a6f50\n"));
+    assert!(!is_hyprland_change("openwindow"));
+}
+
+#[test]
+fn test_sway_events() {
+    assert!(is_sway_change(&json!({ "change": "focus" })));
+    assert!(is_sway_change(&json!({ "change": "new" })));
+    assert!(!is_sway_change(&json!({ "change": "title" })));
+}
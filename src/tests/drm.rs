@@ -0,0 +1,157 @@
+use pretty_assertions::assert_eq;
+
+use super::super::drm::{descriptor_text, Connector, Edid};
+
+/// EDID base block of a Dell U2415, with detailed timing, serial,
+/// product name and range limits descriptors.
+const DELL_U2415: [u8; 128] = [
+    0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x10, 0xac, 0xb1, 0xa0, 0x41, 0x34, 0x33, 0x4c,
+    0x1d, 0x19, 0x01, 0x04, 0xa5, 0x34, 0x20, 0x78, 0x3a, 0xee, 0x95, 0xa3, 0x54, 0x4c, 0x99, 0x26,
+    0x0f, 0x50, 0x54, 0xa5, 0x4b, 0x00, 0x71, 0x4f, 0x81, 0x80, 0xa9, 0x40, 0xd1, 0xc0, 0xd1, 0x00,
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x28, 0x3c, 0x80, 0xa0, 0x70, 0xb0, 0x23, 0x40, 0x30, 0x20,
+    0x36, 0x00, 0x06, 0x44, 0x21, 0x00, 0x00, 0x1a, 0x00, 0x00, 0x00, 0xff, 0x00, 0x37, 0x4d, 0x54,
+    0x30, 0x31, 0x38, 0x35, 0x53, 0x30, 0x41, 0x42, 0x4c, 0x0a, 0x00, 0x00, 0x00, 0xfc, 0x00, 0x44,
+    0x45, 0x4c, 0x4c, 0x20, 0x55, 0x32, 0x34, 0x31, 0x35, 0x0a, 0x20, 0x20, 0x00, 0x00, 0x00, 0xfd,
+    0x00, 0x38, 0x4c, 0x1e, 0x51, 0x11, 0x00, 0x0a, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x01, 0x5e,
+];
+
+fn dell_u2415() -> Edid {
+    Edid {
+        manufacturer: "DEL".to_string(),
+        product_code: 0xa0b1,
+        serial_number: 0x4c333441,
+        model: Some("DELL U2415".to_string()),
+        serial: Some("7MT0185S0ABL".to_string()),
+    }
+}
+
+#[test]
+fn test_edid_parse() {
+    assert_eq!(Edid::parse(&DELL_U2415), Some(dell_u2415()));
+}
+
+#[test]
+fn test_edid_parse_ignores_extension_blocks() {
+    let mut data = DELL_U2415.to_vec();
+    data.extend([0x02; 128]);
+
+    assert_eq!(Edid::parse(&data), Some(dell_u2415()));
+}
+
+#[test]
+fn test_edid_parse_without_text_descriptors() {
+    let mut data = DELL_U2415;
+    // Turn the serial and name descriptors into dummy descriptors
+    data[75] = 0x10;
+    data[93] = 0x10;
+
+    let edid = Edid::parse(&data).unwrap();
+
+    assert_eq!(edid.model, None);
+    assert_eq!(edid.serial, None);
+}
+
+#[test]
+fn test_edid_parse_rejects_short_data() {
+    assert_eq!(Edid::parse(&[]), None);
+    assert_eq!(Edid::parse(&DELL_U2415[..8]), None);
+    assert_eq!(Edid::parse(&DELL_U2415[..127]), None);
+}
+
+#[test]
+fn test_edid_parse_rejects_bad_header() {
+    let mut data = DELL_U2415;
+    data[7] = 0xff;
+
+    assert_eq!(Edid::parse(&data), None);
+    assert_eq!(Edid::parse(&[0; 128]), None);
+}
+
+#[test]
+fn test_descriptor_text() {
+    assert_eq!(
+        descriptor_text(b"DELL U2415\n  "),
+        Some("DELL U2415".to_string())
+    );
+    assert_eq!(
+        descriptor_text(b"ABCDEFGHIJKLM"),
+        Some("ABCDEFGHIJKLM".to_string())
+    );
+    assert_eq!(
+        descriptor_text(b" LG HDR 4K\n  "),
+        Some("LG HDR 4K".to_string())
+    );
+}
+
+#[test]
+fn test_descriptor_text_empty() {
+    assert_eq!(descriptor_text(b"\n            "), None);
+    assert_eq!(descriptor_text(b"             "), None);
+    assert_eq!(descriptor_text(b""), None);
+}
+
+#[test]
+fn test_descriptor_text_invalid_utf8() {
+    assert_eq!(
+        descriptor_text(b"AB\xffCD\n"),
+        Some("AB\u{fffd}CD".to_string())
+    );
+}
+
+fn connector(edid: Option<Edid>) -> Connector {
+    Connector {
+        name: "DP-1".to_string(),
+        connected: true,
+        enabled: true,
+        modes: Vec::new(),
+        edid,
+    }
+}
+
+#[test]
+fn test_edid_identifier() {
+    assert_eq!(dell_u2415().identifier(), "DELL U2415#7MT0185S0ABL");
+}
+
+#[test]
+fn test_edid_identifier_falls_back_to_numeric_serial() {
+    let edid = Edid {
+        serial: None,
+        ..dell_u2415()
+    };
+
+    assert_eq!(edid.identifier(), "DELL U2415#1278424129");
+}
+
+#[test]
+fn test_edid_identifier_without_model_or_serial() {
+    let edid = Edid {
+        model: None,
+        serial: None,
+        serial_number: 0,
+        ..dell_u2415()
+    };
+
+    assert_eq!(edid.identifier(), "DEL A0B1");
+}
+
+#[test]
+fn test_connector_is_selected_by() {
+    let connector = connector(Some(dell_u2415()));
+
+    assert!(connector.is_selected_by("DP-1"));
+    assert!(connector.is_selected_by("DELL U2415#7MT0185S0ABL"));
+    assert!(connector.is_selected_by("DELL U2415"));
+    assert!(!connector.is_selected_by("DP-2"));
+    assert!(!connector.is_selected_by("DELL U2415#OTHER"));
+    assert!(!connector.is_selected_by("DELL"));
+    assert!(!connector.is_selected_by("#7MT0185S0ABL"));
+}
+
+#[test]
+fn test_connector_is_selected_by_without_edid() {
+    let connector = connector(None);
+
+    assert!(connector.is_selected_by("DP-1"));
+    assert!(!connector.is_selected_by("DEL A0B1"));
+}
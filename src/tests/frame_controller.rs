@@ -0,0 +1,147 @@
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+
+use super::super::frame_controller::FrameController;
+use super::super::preset::Preset;
+
+fn controller(max_fps: f64, interval_between_frames: Duration) -> FrameController {
+    let preset = Preset {
+        max_fps,
+        interval_between_frames,
+        crossfade_overlap_ratio: 0.0,
+        ..Preset::with_serde_defaults()
+    };
+    FrameController::new(&preset, 1)
+}
+
+/// Returns whether a frame is due at each of the given frame clock
+/// times, in microseconds.
+fn due_frames(controller: &mut FrameController, frame_times: &[u64]) -> Vec<bool> {
+    frame_times
+        .iter()
+        .map(|&time| controller.is_frame_due(Duration::from_micros(time)))
+        .collect()
+}
+
+#[test]
+fn test_is_frame_due_without_pacing() {
+    let mut controller = controller(0.0, Duration::ZERO);
+
+    assert!(!controller.is_frame_paced());
+    assert_eq!(
+        due_frames(&mut controller, &[0, 1_000, 2_000]),
+        [true, true, true]
+    );
+}
+
+#[test]
+fn test_is_frame_due_halves_refresh_rate() {
+    let mut controller = controller(30.0, Duration::ZERO);
+
+    // 60 Hz refresh
+    let frame_times = [0, 16_667, 33_333, 50_000, 66_667, 83_333, 100_000];
+    assert_eq!(
+        due_frames(&mut controller, &frame_times),
+        [true, false, true, false, true, false, true]
+    );
+}
+
+#[test]
+fn test_is_frame_due_tolerates_jitter() {
+    // Less than 1 ms before the deadline
+    let mut early = controller(30.0, Duration::ZERO);
+    assert_eq!(
+        due_frames(&mut early, &[0, 32_500, 65_900]),
+        [true, true, true]
+    );
+
+    // More than 1 ms before the deadline
+    let mut too_early = controller(30.0, Duration::ZERO);
+    assert_eq!(due_frames(&mut too_early, &[0, 32_000]), [true, false]);
+}
+
+#[test]
+fn test_is_frame_due_rebases_after_stall() {
+    let mut controller = controller(30.0, Duration::ZERO);
+
+    // After a stall, the next deadline is one interval after the late
+    // frame rather than a burst of frames catching up
+    assert_eq!(
+        due_frames(&mut controller, &[0, 200_000, 216_667, 233_333]),
+        [true, true, false, true]
+    );
+}
+
+#[test]
+fn test_is_frame_due_short_interval_between_frames() {
+    let mut controller = controller(0.0, Duration::from_millis(50));
+
+    assert!(controller.is_frame_paced());
+    assert_eq!(
+        due_frames(&mut controller, &[0, 16_667, 33_333, 50_000, 66_667]),
+        [true, false, false, true, false]
+    );
+}
+
+#[test]
+fn test_is_frame_due_long_interval_between_frames_is_not_paced() {
+    let controller = controller(0.0, Duration::from_secs(1));
+
+    assert!(!controller.is_frame_paced());
+}
+
+#[test]
+fn test_record_presentation_smooths_latency() {
+    let mut controller = controller(0.0, Duration::ZERO);
+    assert_eq!(controller.presentation_latency(), None);
+
+    controller.record_presentation(1, 1_000_000, 1_010_000);
+    assert_eq!(
+        controller.presentation_latency(),
+        Some(Duration::from_millis(10))
+    );
+
+    controller.record_presentation(2, 2_000_000, 2_020_000);
+    assert_eq!(
+        controller.presentation_latency(),
+        Some(Duration::from_millis(11))
+    );
+}
+
+#[test]
+fn test_record_presentation_ignores_recorded_frames() {
+    let mut controller = controller(0.0, Duration::ZERO);
+
+    controller.record_presentation(2, 1_000_000, 1_010_000);
+    controller.record_presentation(2, 1_000_000, 1_050_000);
+    controller.record_presentation(1, 1_000_000, 1_050_000);
+
+    assert_eq!(
+        controller.presentation_latency(),
+        Some(Duration::from_millis(10))
+    );
+}
+
+#[test]
+fn test_record_presentation_ignores_unknown_presentation_time() {
+    let mut controller = controller(0.0, Duration::ZERO);
+
+    controller.record_presentation(1, 1_000_000, 0);
+    assert_eq!(controller.presentation_latency(), None);
+
+    // The frame is recorded once its presentation time is known
+    controller.record_presentation(1, 1_000_000, 1_005_000);
+    assert_eq!(
+        controller.presentation_latency(),
+        Some(Duration::from_millis(5))
+    );
+}
+
+#[test]
+fn test_record_presentation_clamps_negative_latency() {
+    let mut controller = controller(0.0, Duration::ZERO);
+
+    controller.record_presentation(1, 1_000_000, 999_000);
+
+    assert_eq!(controller.presentation_latency(), Some(Duration::ZERO));
+}
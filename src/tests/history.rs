@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use pretty_assertions::assert_eq;
+
+use super::super::history::PresetHistory;
+
+fn preset(name: &str) -> PathBuf {
+    PathBuf::from(format!("/nonexistent/{name}.toml"))
+}
+
+fn history_of(names: &[&str]) -> PresetHistory {
+    let mut history = PresetHistory::default();
+    for name in names {
+        history.push(&preset(name));
+    }
+    history
+}
+
+#[test]
+fn test_empty_history() {
+    let history = PresetHistory::default();
+
+    assert_eq!(history.previous(), None);
+    assert_eq!(history.next(), None);
+}
+
+#[test]
+fn test_previous_and_next_do_not_move() {
+    let history = history_of(&["a", "b", "c"]);
+
+    assert_eq!(history.previous(), Some(preset("b").as_path()));
+    assert_eq!(history.previous(), Some(preset("b").as_path()));
+    assert_eq!(history.next(), None);
+}
+
+#[test]
+fn test_push_current_does_nothing() {
+    let mut history = history_of(&["a", "b"]);
+    history.push(&preset("b"));
+
+    assert_eq!(history.previous(), Some(preset("a").as_path()));
+    assert_eq!(history.next(), None);
+}
+
+#[test]
+fn test_push_previous_and_next_moves() {
+    let mut history = history_of(&["a", "b", "c"]);
+
+    history.push(&preset("b"));
+    assert_eq!(history.previous(), Some(preset("a").as_path()));
+    assert_eq!(history.next(), Some(preset("c").as_path()));
+
+    history.push(&preset("a"));
+    assert_eq!(history.previous(), None);
+    assert_eq!(history.next(), Some(preset("b").as_path()));
+
+    history.push(&preset("b"));
+    history.push(&preset("c"));
+    assert_eq!(history.previous(), Some(preset("b").as_path()));
+    assert_eq!(history.next(), None);
+}
+
+#[test]
+fn test_push_new_preset_discards_presets_ahead() {
+    let mut history = history_of(&["a", "b", "c"]);
+    history.push(&preset("b"));
+    history.push(&preset("d"));
+
+    assert_eq!(history.previous(), Some(preset("b").as_path()));
+    assert_eq!(history.next(), None);
+
+    history.push(&preset("b"));
+    assert_eq!(history.previous(), Some(preset("a").as_path()));
+    assert_eq!(history.next(), Some(preset("d").as_path()));
+}
+
+#[test]
+fn test_history_is_capped() {
+    let names: Vec<String> = (0..150).map(|i| i.to_string()).collect();
+    let mut history = PresetHistory::default();
+    for name in &names {
+        history.push(&preset(name));
+    }
+
+    let mut count = 1;
+    let mut oldest = preset("149");
+    while let Some(path) = history.previous().map(Path::to_path_buf) {
+        history.push(&path);
+        oldest = path;
+        count += 1;
+    }
+
+    assert_eq!(count, 100);
+    assert_eq!(oldest, preset("50"));
+}
@@ -0,0 +1,61 @@
+use pretty_assertions::assert_eq;
+use std::path::PathBuf;
+
+use super::super::ipc::{IpcCommand, IpcError};
+
+#[test]
+fn test_ipc_command_parse() {
+    for (line, command) in [
+        ("current", IpcCommand::Current),
+        ("next", IpcCommand::Next),
+        ("previous", IpcCommand::Previous),
+        ("random", IpcCommand::Random),
+        ("hud", IpcCommand::ToggleHud),
+        ("overlay", IpcCommand::ShowOverlay),
+        ("profile", IpcCommand::Profile),
+        ("watchdog", IpcCommand::Watchdog),
+        ("capture", IpcCommand::Capture),
+    ] {
+        assert_eq!(IpcCommand::parse(line).unwrap(), command);
+    }
+}
+
+#[test]
+fn test_ipc_command_parse_switch() {
+    assert_eq!(
+        IpcCommand::parse("  switch   /presets/My Preset.toml \n").unwrap(),
+        IpcCommand::Switch(PathBuf::from("/presets/My Preset.toml"))
+    );
+    assert_eq!(
+        IpcCommand::parse("switch\tpreset.sbgz").unwrap(),
+        IpcCommand::Switch(PathBuf::from("preset.sbgz"))
+    );
+}
+
+#[test]
+fn test_ipc_command_parse_invalid() {
+    for line in ["", "switch", "switch   ", "current now", "Next", "reload"] {
+        assert!(
+            matches!(IpcCommand::parse(line), Err(IpcError::InvalidCommand(_))),
+            "{line:?}"
+        );
+    }
+}
+
+#[test]
+fn test_ipc_command_to_line_roundtrip() {
+    for command in [
+        IpcCommand::Switch(PathBuf::from("/presets/a b.toml")),
+        IpcCommand::Current,
+        IpcCommand::Next,
+        IpcCommand::Previous,
+        IpcCommand::Random,
+        IpcCommand::ToggleHud,
+        IpcCommand::ShowOverlay,
+        IpcCommand::Profile,
+        IpcCommand::Watchdog,
+        IpcCommand::Capture,
+    ] {
+        assert_eq!(IpcCommand::parse(&command.to_line()).unwrap(), command);
+    }
+}
@@ -0,0 +1,78 @@
+use log::LevelFilter;
+use pretty_assertions::assert_eq;
+
+use super::super::logging::{parse_log_filter, LogFilter};
+
+#[test]
+fn test_parse_log_filter_default_level() {
+    assert_eq!(
+        parse_log_filter("info"),
+        Ok(LogFilter::new(LevelFilter::Info))
+    );
+    assert_eq!(parse_log_filter(""), Ok(LogFilter::new(LevelFilter::Warn)));
+    assert_eq!(
+        parse_log_filter(" TRACE "),
+        Ok(LogFilter::new(LevelFilter::Trace))
+    );
+}
+
+#[test]
+fn test_parse_log_filter_modules() {
+    let filter = parse_log_filter("error, frame_controller=trace,::renderer = debug,").unwrap();
+
+    assert_eq!(filter.level("shaderbg::app"), LevelFilter::Error);
+    assert_eq!(
+        filter.level("shaderbg::frame_controller"),
+        LevelFilter::Trace
+    );
+    assert_eq!(filter.level("shaderbg::renderer"), LevelFilter::Debug);
+    assert_eq!(filter.max_level(), LevelFilter::Trace);
+}
+
+#[test]
+fn test_parse_log_filter_invalid_level() {
+    assert!(parse_log_filter("verbose").is_err());
+    assert!(parse_log_filter("warn,renderer=loud").is_err());
+}
+
+#[test]
+fn test_parse_log_filter_missing_module() {
+    assert!(parse_log_filter("=debug").is_err());
+    assert!(parse_log_filter("::=debug").is_err());
+}
+
+#[test]
+fn test_log_filter_level_most_specific_module() {
+    let filter = parse_log_filter("warn,renderer=info,renderer::render_pass=trace").unwrap();
+
+    assert_eq!(filter.level("shaderbg::renderer"), LevelFilter::Info);
+    assert_eq!(
+        filter.level("shaderbg::renderer::program"),
+        LevelFilter::Info
+    );
+    assert_eq!(
+        filter.level("shaderbg::renderer::render_pass"),
+        LevelFilter::Trace
+    );
+    assert_eq!(
+        filter.level("shaderbg::renderer::render_pass::blit"),
+        LevelFilter::Trace
+    );
+}
+
+#[test]
+fn test_log_filter_level_matches_whole_path_segments() {
+    let filter = parse_log_filter("warn,render=trace").unwrap();
+
+    assert_eq!(filter.level("shaderbg::renderer"), LevelFilter::Warn);
+    assert_eq!(filter.level("shaderbg::render"), LevelFilter::Trace);
+}
+
+#[test]
+fn test_log_filter_level_other_crates() {
+    let filter = parse_log_filter("warn,gtk=error,renderer=debug").unwrap();
+
+    assert_eq!(filter.level("gtk::widget"), LevelFilter::Error);
+    assert_eq!(filter.level("renderer"), LevelFilter::Debug);
+    assert_eq!(filter.level("glib"), LevelFilter::Warn);
+}
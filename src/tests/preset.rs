@@ -0,0 +1,122 @@
+use pretty_assertions::assert_eq;
+
+use super::super::geometry::{Point, Size};
+use super::super::preset::{
+    weighted_index, Preset, PresetFilter, PresetSelection, Region, Tile, MIN_FPS,
+};
+
+#[test]
+fn test_region_from_rect() {
+    let region = Region::from_rect([0.25, 0.5, 0.5, 0.25], Size::new(800, 600));
+
+    assert_eq!(region.margins, [300, 200, 150, 200]);
+    assert_eq!(region.origin(1.0), Point::new(200, 150));
+    assert_eq!(
+        region.size_within(Size::new(800, 600), 1.0),
+        Size::new(400, 150)
+    );
+}
+
+#[test]
+fn test_region_from_rect_full_monitor() {
+    let region = Region::from_rect([0.0, 0.0, 1.0, 1.0], Size::new(1920, 1080));
+
+    assert_eq!(region.margins, [0; 4]);
+    assert_eq!(
+        region.size_within(Size::new(1920, 1080), 1.0),
+        Size::new(1920, 1080)
+    );
+}
+
+#[test]
+fn test_region_from_rect_rounds_margins() {
+    let region = Region::from_rect([1.0 / 3.0, 0.0, 1.0 / 3.0, 1.0], Size::new(1000, 100));
+
+    assert_eq!(region.margins, [0, 333, 0, 333]);
+}
+
+fn tile_rect(rect: &str) -> [f32; 4] {
+    toml::from_str::<Tile>(&format!("rect = {rect}"))
+        .unwrap()
+        .rect
+}
+
+#[test]
+fn test_clamp_rect() {
+    assert_eq!(tile_rect("[0.5, 0.0, 0.5, 1.0]"), [0.5, 0.0, 0.5, 1.0]);
+    assert_eq!(tile_rect("[-0.5, 0.5, 2.0, 0.8]"), [0.0, 0.5, 1.0, 0.5]);
+    assert_eq!(tile_rect("[1.5, 0.75, 0.5, -1.0]"), [1.0, 0.75, 0.0, 0.0]);
+}
+
+#[test]
+fn test_tile_pass_name() {
+    assert_eq!(Tile::pass_name(0, "Buffer A"), "Tile 1/Buffer A");
+    assert_eq!(
+        Tile::split_pass_name("Tile 1/Buffer A"),
+        Some((0, "Buffer A"))
+    );
+    assert_eq!(Tile::split_pass_name("Tile 12/a/b"), Some((11, "a/b")));
+    assert_eq!(Tile::split_pass_name("Tile 0/Image"), None);
+    assert_eq!(Tile::split_pass_name("Image"), None);
+}
+
+#[test]
+fn test_max_fps_is_clamped() {
+    let preset: Preset = toml::from_str("max_fps = 1e-300\ntarget_fps = 0.001").unwrap();
+
+    assert_eq!(preset.max_fps, MIN_FPS);
+    assert_eq!(preset.target_fps, MIN_FPS);
+}
+
+#[test]
+fn test_max_fps_disabled() {
+    for value in ["0.0", "-30.0", "inf", "nan"] {
+        let preset: Preset = toml::from_str(&format!("max_fps = {value}")).unwrap();
+
+        assert_eq!(preset.max_fps, 0.0);
+    }
+}
+
+#[test]
+fn test_weighted_index() {
+    let weights = [1.0, 0.0, 3.0];
+
+    assert_eq!(weighted_index(&weights, 0.0), Some(0));
+    assert_eq!(weighted_index(&weights, 0.249), Some(0));
+    assert_eq!(weighted_index(&weights, 0.25), Some(2));
+    assert_eq!(weighted_index(&weights, 0.999), Some(2));
+}
+
+#[test]
+fn test_weighted_index_rounding_picks_last_positive_weight() {
+    assert_eq!(weighted_index(&[0.1, 0.2, 0.0], 1.0), Some(1));
+}
+
+#[test]
+fn test_weighted_index_without_weights() {
+    assert_eq!(weighted_index(&[], 0.5), None);
+    assert_eq!(weighted_index(&[0.0, 0.0], 0.5), None);
+}
+
+#[test]
+fn test_preset_filter_matches() {
+    let tags = |tags: &[&str]| tags.iter().map(ToString::to_string).collect::<Vec<_>>();
+    let filter = PresetFilter {
+        tags: tags(&["Calm", "fractal"]),
+        exclude: tags(&["bright"]),
+        ..Default::default()
+    };
+
+    assert!(filter.matches(&tags(&["fractal", "calm", "blue"])));
+    assert!(!filter.matches(&tags(&["fractal"])));
+    assert!(!filter.matches(&tags(&["calm", "fractal", "BRIGHT"])));
+    assert!(PresetFilter::default().matches(&[]));
+}
+
+#[test]
+fn test_preset_selection_defaults() {
+    let selection: PresetSelection = toml::from_str("name = \"Test\"").unwrap();
+
+    assert_eq!(selection.tags, Vec::<String>::new());
+    assert_eq!(selection.weight, 1.0);
+}
@@ -0,0 +1,152 @@
+use pretty_assertions::assert_eq;
+use std::time::{Duration, Instant};
+
+use super::super::preset::Preset;
+use super::super::resolution_controller::ResolutionController;
+
+/// Time after which the controller may adjust the scale.
+const LATER: Duration = Duration::from_secs(4);
+
+fn controller(target_fps: f64) -> ResolutionController {
+    let preset = Preset {
+        target_fps,
+        resolution_scale: 1.0,
+        min_resolution_scale: 0.25,
+        ..Preset::with_serde_defaults()
+    };
+    ResolutionController::new(&preset).unwrap()
+}
+
+#[test]
+fn test_disabled_without_target_fps() {
+    assert!(ResolutionController::new(&Preset::with_serde_defaults()).is_none());
+}
+
+#[test]
+fn test_waits_between_adjustments() {
+    let mut controller = controller(60.0);
+    let gpu_time = Some(Duration::from_millis(30));
+
+    assert_eq!(controller.update(Instant::now(), 30.0, gpu_time), None);
+    assert!(controller
+        .update(Instant::now() + LATER, 30.0, gpu_time)
+        .is_some());
+}
+
+#[test]
+fn test_lowers_scale_when_gpu_time_exceeds_budget() {
+    let mut controller = controller(50.0);
+
+    // Twice the 20 ms budget: the pixel count must drop to 40%
+    let scale = controller
+        .update(
+            Instant::now() + LATER,
+            25.0,
+            Some(Duration::from_millis(40)),
+        )
+        .unwrap();
+
+    assert!((scale - 0.4f32.sqrt()).abs() < 1e-3, "{scale}");
+    assert_eq!(controller.scale(), scale);
+}
+
+#[test]
+fn test_keeps_scale_when_gpu_time_fits_budget() {
+    let mut controller = controller(50.0);
+
+    assert_eq!(
+        controller.update(
+            Instant::now() + LATER,
+            50.0,
+            Some(Duration::from_millis(15))
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_raises_scale_up_to_resolution_scale() {
+    let mut controller = controller(50.0);
+    let now = Instant::now() + LATER;
+    controller.update(now, 10.0, Some(Duration::from_millis(100)));
+    let lowered = controller.scale();
+
+    let raised = controller
+        .update(now + LATER, 50.0, Some(Duration::from_millis(1)))
+        .unwrap();
+    assert!(raised > lowered);
+    assert!(raised <= lowered * 1.25 + 1e-6);
+
+    let mut now = now + LATER;
+    for _ in 0..20 {
+        now += LATER;
+        controller.update(now, 50.0, Some(Duration::from_millis(1)));
+    }
+    assert_eq!(controller.scale(), 1.0);
+}
+
+#[test]
+fn test_scale_is_clamped_to_min_resolution_scale() {
+    let mut controller = controller(60.0);
+
+    let scale = controller
+        .update(Instant::now() + LATER, 1.0, Some(Duration::from_secs(1)))
+        .unwrap();
+
+    assert_eq!(scale, 0.25);
+}
+
+#[test]
+fn test_frame_rate_at_target_keeps_scale() {
+    let mut controller = controller(60.0);
+
+    // Frames paced by the display refresh, slightly off the target
+    assert_eq!(controller.update(Instant::now() + LATER, 59.5, None), None);
+    assert_eq!(controller.update(Instant::now() + LATER, 55.0, None), None);
+    assert_eq!(controller.scale(), 1.0);
+}
+
+#[test]
+fn test_frame_rate_below_tolerance_lowers_scale() {
+    let mut controller = controller(60.0);
+
+    let scale = controller
+        .update(Instant::now() + LATER, 30.0, None)
+        .unwrap();
+
+    assert!(scale < 1.0);
+}
+
+#[test]
+fn test_unknown_frame_rate_keeps_scale() {
+    let mut controller = controller(60.0);
+
+    assert_eq!(controller.update(Instant::now() + LATER, 0.0, None), None);
+}
+
+#[test]
+fn test_probes_higher_scale_while_holding_target() {
+    let mut controller = controller(60.0);
+    let now = Instant::now() + LATER;
+    let lowered = controller.update(now, 30.0, None).unwrap();
+
+    // Not before the probe interval
+    assert_eq!(controller.update(now + LATER, 60.0, None), None);
+
+    let probed = controller
+        .update(now + Duration::from_secs(10), 60.0, None)
+        .unwrap();
+    assert!((probed - lowered * 1.1).abs() < 1e-6);
+
+    // A failed probe doubles the wait before the next one
+    let now = now + Duration::from_secs(10);
+    let lowered = controller.update(now + LATER, 30.0, None).unwrap();
+    assert!(lowered < probed);
+    assert_eq!(
+        controller.update(now + LATER + Duration::from_secs(10), 60.0, None),
+        None
+    );
+    assert!(controller
+        .update(now + LATER + Duration::from_secs(20), 60.0, None)
+        .is_some());
+}
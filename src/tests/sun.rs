@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+
+use super::super::sun::{Location, SunPosition};
+
+const GREENWICH: Location = Location {
+    latitude: 51.4769,
+    longitude: 0.0,
+};
+
+const LONGYEARBYEN: Location = Location {
+    latitude: 78.2232,
+    longitude: 15.6267,
+};
+
+const NORTH_POLE: Location = Location {
+    latitude: 90.0,
+    longitude: 0.0,
+};
+
+fn position(time: &str, location: Location) -> SunPosition {
+    SunPosition::at(time.parse::<DateTime<Utc>>().unwrap(), location)
+}
+
+fn assert_degrees(radians: f32, expected: f32, tolerance: f32) {
+    let degrees = radians.to_degrees();
+    assert!(
+        (degrees - expected).abs() < tolerance,
+        "{degrees}° != {expected}°"
+    );
+}
+
+#[test]
+fn test_sun_position_declination_at_solstices_and_equinox() {
+    // At the pole the elevation equals the declination of the sun
+    let june_solstice = position("2024-06-20T20:51:00Z", NORTH_POLE);
+    let december_solstice = position("2024-12-21T09:20:00Z", NORTH_POLE);
+    let march_equinox = position("2024-03-20T03:06:00Z", NORTH_POLE);
+
+    assert_degrees(june_solstice.elevation, 23.44, 0.05);
+    assert_degrees(december_solstice.elevation, -23.44, 0.05);
+    assert_degrees(march_equinox.elevation, 0.0, 0.05);
+}
+
+#[test]
+fn test_sun_position_equation_of_time() {
+    // NOAA equation of time: +16.4 minutes on November 3 and -14.2
+    // minutes on February 11
+    let november = position("2024-11-03T12:00:00Z", GREENWICH);
+    let february = position("2024-02-11T12:00:00Z", GREENWICH);
+
+    assert!((november.day_phase * 1440.0 - (720.0 + 16.4)).abs() < 0.2);
+    assert!((february.day_phase * 1440.0 - (720.0 - 14.2)).abs() < 0.2);
+}
+
+#[test]
+fn test_sun_position_at_solar_noon() {
+    // Solar noon at Greenwich on June 21, 2024 is at 12:01:42 UTC
+    let noon = position("2024-06-21T12:01:42Z", GREENWICH);
+
+    assert!((noon.day_phase - 0.5).abs() < 0.0002);
+    assert_degrees(noon.elevation, 90.0 - 51.4769 + 23.43, 0.05);
+    assert_degrees(noon.azimuth, 180.0, 0.5);
+}
+
+#[test]
+fn test_sun_position_polar_day() {
+    for hour in 0..24 {
+        let time = format!("2024-06-21T{hour:02}:00:00Z");
+
+        assert!(position(&time, LONGYEARBYEN).elevation > 0.0, "{time}");
+    }
+
+    // The sun is lowest in the north at solar midnight
+    let midnight = position("2024-06-20T23:00:00Z", LONGYEARBYEN);
+    assert_degrees(midnight.elevation, 78.2232 + 23.43 - 90.0, 0.5);
+    let azimuth = midnight.azimuth.to_degrees();
+    assert!(!(5.0..=355.0).contains(&azimuth), "{azimuth}°");
+}
+
+#[test]
+fn test_sun_position_polar_night() {
+    for hour in 0..24 {
+        let time = format!("2024-12-21T{hour:02}:00:00Z");
+
+        assert!(position(&time, LONGYEARBYEN).elevation < 0.0, "{time}");
+    }
+
+    // The sun is highest in the south at solar noon
+    let noon = position("2024-12-21T10:56:00Z", LONGYEARBYEN);
+    assert_degrees(noon.elevation, 90.0 - 78.2232 - 23.44, 0.5);
+    assert_degrees(noon.azimuth, 180.0, 5.0);
+}
+
+#[test]
+fn test_sun_position_day_and_night_at_mid_latitudes() {
+    let elevations: Vec<_> = (0..24)
+        .map(|hour| position(&format!("2024-06-21T{hour:02}:00:00Z"), GREENWICH).elevation)
+        .collect();
+
+    assert!(elevations.iter().any(|&elevation| elevation > 0.0));
+    assert!(elevations.iter().any(|&elevation| elevation < 0.0));
+}
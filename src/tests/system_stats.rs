@@ -0,0 +1,74 @@
+use pretty_assertions::assert_eq;
+
+use super::super::system_stats::{parse_cpu_times, parse_net_bytes, CpuTimes};
+
+#[test]
+fn test_parse_cpu_times() {
+    let stat = "\
+cpu  100 20 30 400 50 6 7 8 9 10
+cpu0 50 10 15 200 25 3 3 4 4 5
+intr 12345
+";
+
+    assert_eq!(
+        parse_cpu_times(stat),
+        Some(CpuTimes {
+            user: 120,
+            system: 30,
+            idle: 400,
+            iowait: 50,
+            total: 100 + 20 + 30 + 400 + 50 + 6 + 7 + 8,
+        })
+    );
+}
+
+#[test]
+fn test_parse_cpu_times_of_old_kernels() {
+    // Kernels before 2.6 report only four values
+    assert_eq!(parse_cpu_times("cpu  1 2 3 4\n"), None);
+
+    // Kernels before 2.6.11 report no steal time
+    assert_eq!(
+        parse_cpu_times("cpu  1 2 3 4 5 6 7\n"),
+        Some(CpuTimes {
+            user: 3,
+            system: 3,
+            idle: 4,
+            iowait: 5,
+            total: 28,
+        })
+    );
+}
+
+#[test]
+fn test_parse_cpu_times_without_aggregate_line() {
+    assert_eq!(parse_cpu_times(""), None);
+    assert_eq!(parse_cpu_times("cpu0 1 2 3 4 5 6 7 8\n"), None);
+}
+
+#[test]
+fn test_parse_net_bytes() {
+    let dev = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 5000      50    0    0    0     0          0         0     5000      50    0    0    0     0       0          0
+  eth0: 1000      10    0    0    0     0          0         0      200       2    0    0    0     0       0          0
+ wlan0:300 3 0 0 0 0 0 0 40 1 0 0 0 0 0 0
+";
+
+    assert_eq!(parse_net_bytes(dev), Some((240, 1300)));
+}
+
+#[test]
+fn test_parse_net_bytes_without_interfaces() {
+    let header = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+";
+    let loopback = "    lo: 5000 50 0 0 0 0 0 0 5000 50 0 0 0 0 0 0\n";
+    let truncated = "  eth0: 1000 10 0 0\n";
+
+    assert_eq!(parse_net_bytes(header), None);
+    assert_eq!(parse_net_bytes(&format!("{header}{loopback}")), None);
+    assert_eq!(parse_net_bytes(&format!("{header}{truncated}")), None);
+}
@@ -0,0 +1,10 @@
+use pretty_assertions::assert_eq;
+
+use super::super::util::fnv1a;
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+}
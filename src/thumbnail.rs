@@ -0,0 +1,169 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Preset thumbnails.
+//!
+//! Thumbnails are rendered offscreen and cached as PNG files named after
+//! a hash of the preset, so editing a preset produces a new thumbnail
+//! while unchanged presets are rendered only once. Thumbnails unused for
+//! a month, and the least recently used beyond [`MAX_THUMBNAILS`], are
+//! pruned from the cache.
+
+use gtk::gio;
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use thiserror::Error;
+
+use crate::{config::cache_dir, geometry::*, offscreen::*, preset::*, util::fnv1a};
+
+/// Size of the thumbnails, in pixels.
+pub const THUMBNAIL_SIZE: (u32, u32) = (320, 180);
+
+/// Elapsed time of the frame captured as thumbnail.
+const THUMBNAIL_TIME: Duration = Duration::from_secs(2);
+
+/// Time after which an unused thumbnail is pruned.
+const MAX_THUMBNAIL_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Number of thumbnails kept when pruning.
+const MAX_THUMBNAILS: usize = 1000;
+
+/// Time after which a partially written thumbnail is pruned.
+const PARTIAL_THUMBNAIL_AGE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum ThumbnailError {
+    #[error("{0}")]
+    Offscreen(#[from] OffscreenError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to save thumbnail: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+/// Returns the path of the cached thumbnail of the preset, if it has
+/// already been rendered, and marks it as recently used.
+pub fn cached_thumbnail(preset: &Preset) -> Option<PathBuf> {
+    let path = thumbnail_path(preset).ok()?;
+    fs::File::options()
+        .append(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+        .ok()?;
+    Some(path)
+}
+
+/// Returns the path of the thumbnail of the preset, rendering it with
+/// `context` if it is not cached yet.
+pub fn thumbnail(context: &OffscreenContext, preset: &Preset) -> Result<PathBuf, ThumbnailError> {
+    if let Some(path) = cached_thumbnail(preset) {
+        return Ok(path);
+    }
+
+    let size = Size::new(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1);
+    let image = render_still(context, preset, THUMBNAIL_TIME, size, None)?;
+    save_thumbnail(&thumbnail_path(preset)?, &image)
+}
+
+/// Same as [`thumbnail`], but returns to the main loop after every
+/// rendered frame, and saves the thumbnail on a worker thread.
+pub async fn thumbnail_async(
+    context: &OffscreenContext,
+    preset: &Preset,
+) -> Result<PathBuf, ThumbnailError> {
+    if let Some(path) = cached_thumbnail(preset) {
+        return Ok(path);
+    }
+
+    let path = thumbnail_path(preset)?;
+    let size = Size::new(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1);
+    let image = render_still_async(context, preset, THUMBNAIL_TIME, size, None).await?;
+    gio::spawn_blocking(move || save_thumbnail(&path, &image))
+        .await
+        .map_err(|_| io::Error::other("thumbnail writer panicked"))?
+}
+
+/// Removes the thumbnails unused for [`MAX_THUMBNAIL_AGE`], then the
+/// least recently used ones beyond [`MAX_THUMBNAILS`], along with
+/// partially written ones.
+pub fn prune_thumbnails() -> io::Result<()> {
+    let dir = thumbnails_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    let now = SystemTime::now();
+    let mut thumbnails = Vec::new();
+    let mut stale = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) else {
+            continue;
+        };
+        let age = now.duration_since(modified).unwrap_or_default();
+        match path.extension().and_then(OsStr::to_str) {
+            Some("png") => thumbnails.push((age, path)),
+            // Thumbnails being written are younger than a minute
+            Some("part") if age > PARTIAL_THUMBNAIL_AGE => stale.push(path),
+            _ => {}
+        }
+    }
+
+    thumbnails.sort_unstable();
+    stale.extend(
+        thumbnails
+            .into_iter()
+            .enumerate()
+            .filter(|(index, (age, _))| *index >= MAX_THUMBNAILS || *age > MAX_THUMBNAIL_AGE)
+            .map(|(_, (_, path))| path),
+    );
+
+    for path in &stale {
+        fs::remove_file(path)?;
+    }
+    if !stale.is_empty() {
+        log::debug!("Pruned {} thumbnails from {}", stale.len(), dir.display());
+    }
+
+    Ok(())
+}
+
+/// Saves a thumbnail, under a temporary name until it is complete so
+/// that an interrupted write is never mistaken for a cached thumbnail.
+fn save_thumbnail(path: &Path, image: &image::RgbaImage) -> Result<PathBuf, ThumbnailError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let partial_path = path.with_extension("png.part");
+    image.save_with_format(&partial_path, image::ImageFormat::Png)?;
+    fs::rename(&partial_path, path)?;
+
+    log::debug!("Saved thumbnail {}", path.display());
+
+    Ok(path.to_path_buf())
+}
+
+/// Returns the directory thumbnails are cached in.
+fn thumbnails_dir() -> io::Result<PathBuf> {
+    Ok(cache_dir()?.join("thumbnails"))
+}
+
+/// Returns the path of the cache file of the thumbnail of the preset.
+fn thumbnail_path(preset: &Preset) -> io::Result<PathBuf> {
+    Ok(thumbnails_dir()?.join(format!("{:016x}.png", preset_hash(preset))))
+}
+
+/// Returns a hash of every setting of the preset.
+fn preset_hash(preset: &Preset) -> u64 {
+    fnv1a(serde_json::to_string(preset).unwrap_or_default().as_bytes())
+}
@@ -0,0 +1,20 @@
+// ShaderBG
+// Copyright (c) 2025 Harlen Batagelo
+// https://github.com/hbatagelo/shaderbg
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Helpers shared by unrelated modules.
+
+/// Returns the 64-bit FNV-1a hash of `bytes`.
+///
+/// Unlike the hasher of the standard library, it is stable across Rust
+/// releases, so names derived from it, such as those of cached
+/// thumbnails and downloaded assets, remain valid after an upgrade.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}